@@ -30,6 +30,8 @@ A protobuf library extending [`prost`](https://crates.io/crates/prost) with refl
 
 Rust **1.54** or higher.
 
+The `chrono`, `log`, `postgres`, `protobuf` and `tonic` features depend on crates which require a newer version of Rust.
+
 The minimum supported Rust version may be changed in the future, but it will be
 done with a minor version bump.
 
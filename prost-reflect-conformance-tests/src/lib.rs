@@ -22,3 +22,5 @@ pub mod test_messages {
         ));
     }
 }
+
+pub mod runner;
@@ -0,0 +1,217 @@
+//! An implementation of the [protobuf conformance test](https://github.com/protocolbuffers/protobuf/tree/master/conformance)
+//! protocol using [`DynamicMessage`] for both the binary and JSON encodings.
+//!
+//! The `prost-reflect-conformance-tests` binary runs this with options parsed from its command line
+//! arguments. Other crates can call [`run`] directly to measure conformance with their own
+//! serialization options.
+
+use std::io::{self, Read, Write};
+
+use once_cell::sync::Lazy;
+use prost::{
+    bytes::{Buf, BufMut},
+    Message,
+};
+use prost_reflect::{DescriptorPool, DeserializeOptions, DynamicMessage, SerializeOptions};
+
+use crate::conformance::{
+    conformance_request, conformance_response, ConformanceRequest, ConformanceResponse,
+    TestCategory, WireFormat,
+};
+
+const TEST_MESSAGES_DESCRIPTOR_POOL_SET_BYTES: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/test_messages.bin"));
+
+static TEST_MESSAGES_DESCRIPTOR_POOL: Lazy<DescriptorPool> =
+    Lazy::new(|| DescriptorPool::decode(TEST_MESSAGES_DESCRIPTOR_POOL_SET_BYTES).unwrap());
+
+/// Options to control how the conformance test runner encodes and decodes JSON messages.
+#[derive(Debug, Clone, Default)]
+pub struct RunnerOptions {
+    serialize: SerializeOptions,
+    deserialize: DeserializeOptions,
+}
+
+impl RunnerOptions {
+    /// Creates a new instance of [`RunnerOptions`], using the default serialization and deserialization options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the options used when serializing messages to JSON.
+    pub fn serialize_options(mut self, options: SerializeOptions) -> Self {
+        self.serialize = options;
+        self
+    }
+
+    /// Sets the options used when deserializing messages from JSON.
+    ///
+    /// For tests in the `JSON_IGNORE_UNKNOWN_PARSING_TEST` category, unknown fields are always ignored.
+    pub fn deserialize_options(mut self, options: DeserializeOptions) -> Self {
+        self.deserialize = options;
+        self
+    }
+
+    /// Parses options from command line arguments.
+    ///
+    /// The following flags are supported:
+    ///
+    /// - `--json-no-stringify-64-bit-integers`
+    /// - `--json-use-enum-numbers`
+    /// - `--json-use-proto-field-name`
+    /// - `--json-emit-default-fields`
+    /// - `--json-ignore-unknown-fields`
+    pub fn from_args<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut serialize = SerializeOptions::new();
+        let mut deserialize = DeserializeOptions::new();
+        for arg in args {
+            match arg.as_str() {
+                "--json-no-stringify-64-bit-integers" => {
+                    serialize = serialize.stringify_64_bit_integers(false)
+                }
+                "--json-use-enum-numbers" => serialize = serialize.use_enum_numbers(true),
+                "--json-use-proto-field-name" => serialize = serialize.use_proto_field_name(true),
+                "--json-emit-default-fields" => serialize = serialize.skip_default_fields(false),
+                "--json-ignore-unknown-fields" => {
+                    deserialize = deserialize.deny_unknown_fields(false)
+                }
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(RunnerOptions {
+            serialize,
+            deserialize,
+        })
+    }
+}
+
+/// Reads conformance requests from stdin and writes responses to stdout, until stdin is closed.
+pub fn run(options: &RunnerOptions) -> io::Result<()> {
+    let mut bytes = Vec::new();
+
+    loop {
+        bytes.resize(4, 0);
+
+        if io::stdin().read_exact(&mut *bytes).is_err() {
+            // No more test cases.
+            return Ok(());
+        }
+
+        let len = bytes.as_slice().get_u32_le() as usize;
+
+        bytes.resize(len, 0);
+        io::stdin().read_exact(&mut *bytes)?;
+
+        let result = match ConformanceRequest::decode(&*bytes) {
+            Ok(request) => handle_request(request, options),
+            Err(error) => conformance_response::Result::ParseError(format!("{:?}", error)),
+        };
+
+        let response = ConformanceResponse {
+            result: Some(result),
+        };
+
+        let len = response.encoded_len();
+        bytes.clear();
+        bytes.put_u32_le(len as u32);
+        response.encode(&mut bytes)?;
+        assert_eq!(len + 4, bytes.len());
+
+        let mut stdout = io::stdout();
+        stdout.lock().write_all(&bytes)?;
+        stdout.flush()?;
+    }
+}
+
+/// Handles a single conformance request.
+pub fn handle_request(
+    request: ConformanceRequest,
+    options: &RunnerOptions,
+) -> conformance_response::Result {
+    let message_desc =
+        match TEST_MESSAGES_DESCRIPTOR_POOL.get_message_by_name(&request.message_type) {
+            Some(message_desc) => message_desc,
+            None => {
+                return conformance_response::Result::ParseError(format!(
+                    "unknown message type: {}",
+                    request.message_type
+                ));
+            }
+        };
+
+    let mut json_deserialize_options = options.deserialize.clone();
+
+    match request.test_category() {
+        TestCategory::UnspecifiedTest => (),
+        TestCategory::BinaryTest => (),
+        TestCategory::JsonTest => (),
+        TestCategory::JsonIgnoreUnknownParsingTest => {
+            json_deserialize_options = json_deserialize_options.deny_unknown_fields(false);
+        }
+        TestCategory::JspbTest | TestCategory::TextFormatTest => {
+            return conformance_response::Result::Skipped("unsupported test category".to_string())
+        }
+    }
+
+    let output = request.requested_output_format();
+    let dynamic_message = match request.payload {
+        None => return conformance_response::Result::ParseError("no payload".to_string()),
+        Some(conformance_request::Payload::ProtobufPayload(buf)) => {
+            let mut dynamic_message = DynamicMessage::new(message_desc);
+            match dynamic_message.merge(buf.as_ref()) {
+                Ok(()) => (),
+                Err(error) => return conformance_response::Result::ParseError(error.to_string()),
+            }
+            dynamic_message
+        }
+        Some(conformance_request::Payload::JsonPayload(json)) => {
+            let mut deserializer = serde_json::de::Deserializer::from_str(&json);
+            match DynamicMessage::deserialize_with_options(
+                message_desc,
+                &mut deserializer,
+                &json_deserialize_options,
+            ) {
+                Ok(message) => message,
+                Err(error) => return conformance_response::Result::ParseError(error.to_string()),
+            }
+        }
+        Some(conformance_request::Payload::JspbPayload(_)) => {
+            return conformance_response::Result::Skipped(
+                "jspb payload is not supported".to_string(),
+            );
+        }
+        Some(conformance_request::Payload::TextPayload(_)) => {
+            return conformance_response::Result::Skipped(
+                "text payload is not supported".to_string(),
+            );
+        }
+    };
+
+    match output {
+        WireFormat::Unspecified => {
+            conformance_response::Result::ParseError("output format unspecified".to_string())
+        }
+        WireFormat::Jspb => {
+            conformance_response::Result::Skipped("JSPB output is not supported".to_string())
+        }
+        WireFormat::TextFormat => {
+            conformance_response::Result::Skipped("TEXT_FORMAT output is not supported".to_string())
+        }
+        WireFormat::Json => {
+            let mut serializer = serde_json::Serializer::new(Vec::new());
+            match dynamic_message.serialize_with_options(&mut serializer, &options.serialize) {
+                Ok(()) => conformance_response::Result::JsonPayload(
+                    String::from_utf8(serializer.into_inner()).expect("invalid utf-8"),
+                ),
+                Err(err) => conformance_response::Result::SerializeError(err.to_string()),
+            }
+        }
+        WireFormat::Protobuf => {
+            conformance_response::Result::ProtobufPayload(dynamic_message.encode_to_vec())
+        }
+    }
+}
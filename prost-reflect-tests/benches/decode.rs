@@ -1,9 +1,9 @@
 use std::{collections::BTreeMap, iter::FromIterator};
 
 use criterion::{criterion_group, criterion_main, Criterion};
-use prost::Message;
+use prost::{bytes::Bytes, Message};
 use prost_reflect::{DynamicMessage, ReflectMessage};
-use prost_reflect_tests::WellKnownTypes;
+use prost_reflect_tests::{Scalars, WellKnownTypes};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 fn sample_wkt() -> WellKnownTypes {
@@ -106,9 +106,26 @@ fn encode_wkt_multithread(c: &mut Criterion) {
     });
 }
 
+fn decode_string(c: &mut Criterion) {
+    let message = Scalars {
+        string: "hello ☃".repeat(1_000),
+        ..Default::default()
+    };
+    let desc = message.descriptor();
+    let value = message.encode_to_vec();
+    let bytes = Bytes::from(value.clone());
+
+    c.bench_function("decode_string", |b| {
+        b.iter(|| DynamicMessage::decode(desc.clone(), value.as_slice()))
+    });
+    c.bench_function("decode_string_bytes", |b| {
+        b.iter(|| DynamicMessage::decode(desc.clone(), bytes.clone()))
+    });
+}
+
 criterion_group! {
     name = benches;
     config = Criterion::default().sample_size(500);
-    targets = decode_wkt, encode_wkt, decode_wkt_multithread, encode_wkt_multithread
+    targets = decode_wkt, encode_wkt, decode_wkt_multithread, encode_wkt_multithread, decode_string
 }
 criterion_main!(benches);
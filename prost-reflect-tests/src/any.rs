@@ -0,0 +1,96 @@
+use prost::Message;
+use prost_reflect::{DynamicMessage, ReflectMessage, Value};
+use prost_types::Any;
+
+use crate::{test_file_descriptor, Point, Scalars};
+
+#[test]
+fn pack_dynamic() {
+    let mut message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("test.Point")
+            .unwrap(),
+    );
+    message.set_field_by_name("latitude", Value::I32(1));
+
+    let any = message.pack_any();
+    assert_eq!(any.type_url, "type.googleapis.com/test.Point");
+    assert_eq!(
+        any.value,
+        Point {
+            latitude: 1,
+            longitude: 0
+        }
+        .encode_to_vec()
+    );
+
+    let unpacked = DynamicMessage::unpack_any(&any, &test_file_descriptor()).unwrap();
+    assert_eq!(unpacked, message);
+}
+
+#[test]
+fn pack_typed() {
+    let point = Point {
+        latitude: 1,
+        longitude: 2,
+    };
+    let any = point.pack_any();
+    assert_eq!(any.type_url, "type.googleapis.com/test.Point");
+    assert_eq!(Point::unpack_any(&any).unwrap(), point);
+
+    let unpacked = DynamicMessage::unpack_any(&any, &test_file_descriptor()).unwrap();
+    assert_eq!(unpacked.transcode_to::<Point>().unwrap(), point);
+}
+
+#[test]
+fn unpack_custom_prefix() {
+    let any = Any {
+        type_url: "example.com/types/test.Point".to_owned(),
+        value: vec![0x08, 0x05],
+    };
+    assert_eq!(Point::unpack_any(&any).unwrap().latitude, 5);
+    assert_eq!(
+        DynamicMessage::unpack_any(&any, &test_file_descriptor())
+            .unwrap()
+            .get_field_by_name("latitude")
+            .unwrap()
+            .as_i32(),
+        Some(5)
+    );
+}
+
+#[test]
+fn unpack_errors() {
+    let any = |type_url: &str, value: &[u8]| Any {
+        type_url: type_url.to_owned(),
+        value: value.to_vec(),
+    };
+
+    assert_eq!(
+        Point::unpack_any(&any("test.Point", &[]))
+            .unwrap_err()
+            .to_string(),
+        "invalid type url 'test.Point'"
+    );
+    assert_eq!(
+        Scalars::unpack_any(&any("type.googleapis.com/test.Point", &[]))
+            .unwrap_err()
+            .to_string(),
+        "type url 'type.googleapis.com/test.Point' does not match the expected message type 'test.Scalars'"
+    );
+    assert_eq!(
+        DynamicMessage::unpack_any(
+            &any("type.googleapis.com/test.Missing", &[]),
+            &test_file_descriptor()
+        )
+        .unwrap_err()
+        .to_string(),
+        "message type for 'type.googleapis.com/test.Missing' not found"
+    );
+    assert_eq!(
+        Point::unpack_any(&any("type.googleapis.com/test.Point", &[0x08]))
+            .unwrap_err()
+            .to_string(),
+        "failed to decode payload of type 'type.googleapis.com/test.Point'"
+    );
+}
@@ -0,0 +1,193 @@
+use std::fs;
+
+use prost_reflect::{DynamicMessage, ReflectMessage};
+
+use crate::{ComplexType, ScalarArrays, Scalars};
+
+fn assign<T>(assignments: &[&str]) -> T
+where
+    T: ReflectMessage + Default,
+{
+    let mut message = DynamicMessage::new(T::default().descriptor());
+    message.assign_all(assignments).unwrap();
+    message.transcode_to().unwrap()
+}
+
+fn assign_err<T>(assignment: &str) -> String
+where
+    T: ReflectMessage + Default,
+{
+    let mut message = DynamicMessage::new(T::default().descriptor());
+    message.assign(assignment).unwrap_err().to_string()
+}
+
+#[test]
+fn assign_scalars() {
+    assert_eq!(
+        assign::<Scalars>(&[
+            "double=1.5",
+            "int32=-3",
+            "uint64=18446744073709551615",
+            "bool=true",
+            "string=a=b",
+            "bytes=@@abc",
+        ]),
+        Scalars {
+            double: 1.5,
+            int32: -3,
+            uint64: u64::MAX,
+            bool: true,
+            string: "a=b".to_owned(),
+            bytes: b"@abc".to_vec(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn assign_lists() {
+    assert_eq!(
+        assign::<ScalarArrays>(&[
+            "int32+=1",
+            "int32+=2",
+            "int32[0]=5",
+            "string=replaced",
+            "string=x",
+            "string+=y",
+        ]),
+        ScalarArrays {
+            int32: vec![5, 2],
+            string: vec!["x".to_owned(), "y".to_owned()],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn assign_nested() {
+    assert_eq!(
+        assign::<ComplexType>(&[
+            "nested.int32=3",
+            "stringMap[a.b].string=c",
+            "int_map[7]={}",
+            "my_enum+=FOO",
+            "my_enum+=3",
+            "optional_enum=NEG",
+        ]),
+        ComplexType {
+            nested: Some(Scalars {
+                int32: 3,
+                ..Default::default()
+            }),
+            string_map: vec![(
+                "a.b".to_owned(),
+                Scalars {
+                    string: "c".to_owned(),
+                    ..Default::default()
+                }
+            )]
+            .into_iter()
+            .collect(),
+            int_map: vec![(7, Scalars::default())].into_iter().collect(),
+            my_enum: vec![1, 3],
+            optional_enum: -4,
+        }
+    );
+}
+
+#[test]
+fn assign_empty_message() {
+    let mut message = DynamicMessage::new(ComplexType::default().descriptor());
+    message.assign("nested={}").unwrap();
+    assert!(message.has_field_by_name("nested"));
+}
+
+#[test]
+fn assign_file() {
+    let path = std::env::temp_dir().join("prost-reflect-assign-test.bin");
+    fs::write(&path, b"\xff\x01file contents").unwrap();
+
+    let message: Scalars = assign(&[&format!("bytes=@{}", path.display())]);
+    assert_eq!(message.bytes, b"\xff\x01file contents");
+
+    // Strings are never read from files.
+    let message: Scalars = assign(&[&format!("string=@{}", path.display())]);
+    assert_eq!(message.string, format!("@{}", path.display()));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn assign_string_starting_with_at() {
+    let message: Scalars = assign(&["string=@user"]);
+    assert_eq!(message.string, "@user");
+
+    let message: Scalars = assign(&["string=@@user"]);
+    assert_eq!(message.string, "@@user");
+}
+
+#[test]
+fn assign_syntax_errors() {
+    assert_eq!(
+        assign_err::<Scalars>("int32"),
+        "invalid assignment 'int32': expected '=' or '+='"
+    );
+    assert_eq!(
+        assign_err::<ComplexType>("nested..int32=1"),
+        "invalid assignment 'nested..int32=1': expected a field name"
+    );
+    assert_eq!(
+        assign_err::<ComplexType>("int_map[1=2"),
+        "invalid assignment 'int_map[1=2': expected '=' or '+='"
+    );
+    assert_eq!(
+        assign_err::<ComplexType>("int_map[1]x=2"),
+        "invalid assignment 'int_map[1]x=2': expected '.' or '=' after ']'"
+    );
+}
+
+#[test]
+fn assign_field_errors() {
+    assert_eq!(
+        assign_err::<ComplexType>("nested.missing=1"),
+        "invalid assignment 'nested.missing=1': message 'test.Scalars' has no field named 'missing'"
+    );
+    assert_eq!(
+        assign_err::<ComplexType>("optional_enum.x=1"),
+        "invalid assignment 'optional_enum.x=1': field 'test.ComplexType.optional_enum' is not a message"
+    );
+    assert_eq!(
+        assign_err::<ComplexType>("nested[0].int32=1"),
+        "invalid assignment 'nested[0].int32=1': field 'test.ComplexType.nested' is not a repeated or map field, and cannot be indexed"
+    );
+    assert_eq!(
+        assign_err::<Scalars>("int32+=1"),
+        "invalid assignment 'int32+=1': field 'test.Scalars.int32' is not a repeated field, so '+=' cannot be used"
+    );
+    assert_eq!(
+        assign_err::<ComplexType>("string_map=1"),
+        "invalid assignment 'string_map=1': field 'test.ComplexType.string_map' must be indexed, for example 'string_map[key]'"
+    );
+    assert_eq!(
+        assign_err::<ScalarArrays>("int32[2]=1"),
+        "invalid assignment 'int32[2]=1': index '2' is out of range for field 'test.ScalarArrays.int32' with 0 elements"
+    );
+}
+
+#[test]
+fn assign_value_errors() {
+    assert_eq!(
+        assign_err::<ComplexType>("nested=1"),
+        "invalid assignment 'nested=1': invalid value '1' for message field 'test.ComplexType.nested': set its fields individually, or use '{}' for an empty message"
+    );
+    assert_eq!(
+        assign_err::<ComplexType>("int_map[x]={}"),
+        "invalid assignment 'int_map[x]={}': invalid value 'x' for field 'test.ComplexType.int_map': expected a 32-bit integer"
+    );
+    assert_eq!(
+        assign_err::<ComplexType>("optional_enum=BAZ"),
+        "invalid assignment 'optional_enum=BAZ': invalid value 'BAZ' for field 'test.ComplexType.optional_enum': expected a value of enum 'test.ComplexType.MyEnum'"
+    );
+    assert!(assign_err::<Scalars>("bytes=@/nonexistent/file")
+        .starts_with("invalid assignment 'bytes=@/nonexistent/file': failed to read file '/nonexistent/file': "));
+}
@@ -0,0 +1,241 @@
+use prost_reflect::{BindOptions, DynamicMessage, MapKey, ReflectMessage, Value};
+
+use crate::{ComplexType, Scalars};
+
+fn complex_type() -> DynamicMessage {
+    DynamicMessage::new(ComplexType::default().descriptor())
+}
+
+#[test]
+fn bind_scalars() {
+    let mut message = DynamicMessage::new(Scalars::default().descriptor());
+    message
+        .bind(
+            vec![
+                ("double", "1.5"),
+                ("int32", "-3"),
+                ("uint64", "18446744073709551615"),
+                ("bool", "TRUE"),
+                ("string", "hello world"),
+                ("bytes", "abc"),
+            ],
+            &BindOptions::new(),
+        )
+        .unwrap();
+
+    assert_eq!(
+        message.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            double: 1.5,
+            int32: -3,
+            uint64: u64::MAX,
+            bool: true,
+            string: "hello world".to_owned(),
+            bytes: b"abc".to_vec(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn bind_nested() {
+    let mut message = complex_type();
+    message
+        .bind(
+            vec![
+                ("nested.int32", "5"),
+                ("string_map.foo.string", "bar"),
+                ("intMap.3.bool", "1"),
+                ("my_enum", "FOO,3,7"),
+                ("optional_enum", "NEG"),
+            ],
+            &BindOptions::new(),
+        )
+        .unwrap();
+
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            nested: Some(Scalars {
+                int32: 5,
+                ..Default::default()
+            }),
+            string_map: vec![(
+                "foo".to_owned(),
+                Scalars {
+                    string: "bar".to_owned(),
+                    ..Default::default()
+                }
+            )]
+            .into_iter()
+            .collect(),
+            int_map: vec![(
+                3,
+                Scalars {
+                    bool: true,
+                    ..Default::default()
+                }
+            )]
+            .into_iter()
+            .collect(),
+            my_enum: vec![1, 3, 7],
+            optional_enum: -4,
+        }
+    );
+}
+
+#[test]
+fn bind_env_style() {
+    let mut message = complex_type();
+    message
+        .bind(
+            vec![
+                ("APP_NESTED__INT32", "8080"),
+                ("APP_STRING_MAP__Key__UINT32", "2"),
+                ("HOME", "/root"),
+            ],
+            &BindOptions::env("APP"),
+        )
+        .unwrap();
+
+    let nested = message.get_field_by_name("nested").unwrap();
+    assert_eq!(
+        nested
+            .as_message()
+            .unwrap()
+            .get_field_by_name("int32")
+            .unwrap()
+            .as_ref(),
+        &Value::I32(8080)
+    );
+    let map = message.get_field_by_name("string_map").unwrap();
+    let entry = map.as_map().unwrap()[&MapKey::String("Key".to_owned())]
+        .as_message()
+        .unwrap()
+        .get_field_by_name("uint32")
+        .unwrap();
+    assert_eq!(entry.as_ref(), &Value::U32(2));
+}
+
+#[test]
+fn bind_env() {
+    std::env::set_var("PROST_REFLECT_BIND_TEST_NESTED__STRING", "from env");
+
+    let mut message = complex_type();
+    message
+        .bind_env(&BindOptions::env("PROST_REFLECT_BIND_TEST"))
+        .unwrap();
+
+    let nested = message.get_field_by_name("nested").unwrap();
+    assert_eq!(
+        nested
+            .as_message()
+            .unwrap()
+            .get_field_by_name("string")
+            .unwrap()
+            .as_ref(),
+        &Value::String("from env".to_owned())
+    );
+}
+
+#[test]
+fn bind_custom_separators() {
+    let mut message = complex_type();
+    message
+        .bind(
+            vec![("--nested-int64", "12"), ("--my_enum", "BAR;FOO")],
+            &BindOptions::new()
+                .prefix("--")
+                .separator("-")
+                .list_separator(';'),
+        )
+        .unwrap();
+
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            nested: Some(Scalars {
+                int64: 12,
+                ..Default::default()
+            }),
+            my_enum: vec![3, 1],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn bind_unknown_key() {
+    let mut message = complex_type();
+    let err = message
+        .bind(vec![("nested.missing", "1")], &BindOptions::new())
+        .unwrap_err();
+    assert_eq!(err.key(), "nested.missing");
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'nested.missing': message 'test.Scalars' has no field named 'missing'"
+    );
+
+    message
+        .bind(
+            vec![("nested.missing", "1")],
+            &BindOptions::new().ignore_unknown_keys(true),
+        )
+        .unwrap();
+}
+
+#[test]
+fn bind_invalid_value() {
+    let mut message = complex_type();
+    let err = message
+        .bind(vec![("nested.uint32", "-1")], &BindOptions::new())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'nested.uint32': invalid value '-1' for field 'test.Scalars.uint32': expected an unsigned 32-bit integer"
+    );
+
+    let err = message
+        .bind(vec![("optional_enum", "BAZ")], &BindOptions::new())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'optional_enum': invalid value 'BAZ' for field 'test.ComplexType.optional_enum': expected a value of enum 'test.ComplexType.MyEnum'"
+    );
+
+    let err = message
+        .bind(vec![("int_map.x.int32", "1")], &BindOptions::new())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'int_map.x.int32': invalid value 'x' for field 'test.ComplexType.int_map': expected a 32-bit integer"
+    );
+}
+
+#[test]
+fn bind_invalid_path() {
+    let mut message = complex_type();
+    let err = message
+        .bind(vec![("nested", "1")], &BindOptions::new())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'nested': field 'test.ComplexType.nested' is a message, and its fields must be set individually"
+    );
+
+    let err = message
+        .bind(vec![("optional_enum.x", "1")], &BindOptions::new())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'optional_enum.x': field 'test.ComplexType.optional_enum' is not a message"
+    );
+
+    let err = message
+        .bind(vec![("string_map", "1")], &BindOptions::new())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key 'string_map': missing map key for field 'test.ComplexType.string_map'"
+    );
+}
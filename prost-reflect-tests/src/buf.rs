@@ -0,0 +1,167 @@
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+use flate2::{write::GzEncoder, Compression};
+use prost::bytes::BufMut;
+use prost_reflect::buf::{decode_image, read_image, BufClient};
+
+use crate::DESCRIPTOR_POOL_SET_BYTES;
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    env::temp_dir().join(format!("prost-reflect-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn buf_decode_image() {
+    let pool = decode_image(DESCRIPTOR_POOL_SET_BYTES).unwrap();
+    assert!(pool.get_message_by_name("test.Scalars").is_some());
+}
+
+#[test]
+fn buf_read_image() {
+    let path = temp_path("image.bin");
+    fs::write(&path, DESCRIPTOR_POOL_SET_BYTES).unwrap();
+    let pool = read_image(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(pool.get_message_by_name("test.Scalars").is_some());
+}
+
+#[test]
+fn buf_read_compressed_image() {
+    let path = temp_path("image.bin.gz");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(DESCRIPTOR_POOL_SET_BYTES).unwrap();
+    fs::write(&path, encoder.finish().unwrap()).unwrap();
+    let pool = read_image(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(pool.get_message_by_name("test.Scalars").is_some());
+}
+
+#[test]
+fn buf_read_json_image_unsupported() {
+    let err = read_image("image.json").unwrap_err();
+    assert_eq!(err.to_string(), "unsupported image format for 'image.json'");
+}
+
+#[test]
+fn buf_invalid_module() {
+    let err = BufClient::new().get_image("weather").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid module name 'weather': expected 'remote/owner/repository'"
+    );
+}
+
+/// Serves a single HTTP request, returning the request head and body.
+fn serve_once(status: &'static str, body: Vec<u8>) -> (String, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = String::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = len.trim().parse().unwrap();
+            }
+            if line == "\r\n" {
+                break;
+            }
+            head.push_str(&line);
+        }
+        let mut request_body = vec![0; content_length];
+        reader.read_exact(&mut request_body).unwrap();
+
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(&body).unwrap();
+        head
+    });
+    (url, handle)
+}
+
+#[test]
+fn buf_get_image() {
+    let mut body = Vec::new();
+    prost::encoding::encode_key(1, prost::encoding::WireType::LengthDelimited, &mut body);
+    prost::encoding::encode_varint(DESCRIPTOR_POOL_SET_BYTES.len() as u64, &mut body);
+    body.put_slice(DESCRIPTOR_POOL_SET_BYTES);
+
+    let (url, handle) = serve_once("200 OK", body);
+    let pool = BufClient::new()
+        .base_url(url)
+        .token("secret")
+        .get_image("buf.build/acme/weather:main")
+        .unwrap();
+    assert!(pool.get_message_by_name("test.Scalars").is_some());
+
+    let head = handle.join().unwrap();
+    assert!(head.starts_with("POST /buf.alpha.registry.v1alpha1.ImageService/GetImage "));
+    assert!(head.contains("Authorization: Bearer secret\r\n"));
+    assert!(head.contains("Content-Type: application/proto\r\n"));
+}
+
+#[test]
+fn buf_get_image_error_status() {
+    let (url, handle) = serve_once(
+        "404 Not Found",
+        br#"{"code":"not_found","message":"repository not found"}"#.to_vec(),
+    );
+    let err = BufClient::new()
+        .base_url(url)
+        .get_image("acme/missing")
+        .unwrap_err();
+    handle.join().unwrap();
+    assert_eq!(
+        err.to_string(),
+        r#"registry returned status 404: {"code":"not_found","message":"repository not found"}"#
+    );
+}
+
+#[test]
+fn buf_get_image_token_for_other_remote() {
+    let (url, handle) = serve_once("404 Not Found", Vec::new());
+    BufClient::new()
+        .base_url(url)
+        .token("secret")
+        .remote_token("buf.example.com", "other-secret")
+        .get_image("attacker.example/acme/weather")
+        .unwrap_err();
+    let head = handle.join().unwrap();
+    assert!(!head.contains("Authorization"));
+
+    let (url, handle) = serve_once("404 Not Found", Vec::new());
+    BufClient::new()
+        .base_url(url)
+        .token("secret")
+        .remote_token("buf.example.com", "other-secret")
+        .get_image("buf.example.com/acme/weather")
+        .unwrap_err();
+    let head = handle.join().unwrap();
+    assert!(head.contains("Authorization: Bearer other-secret\r\n"));
+}
+
+#[test]
+fn buf_get_image_response_too_large() {
+    let (url, handle) = serve_once("200 OK", vec![0; 100]);
+    let err = BufClient::new()
+        .base_url(url)
+        .max_response_size(10)
+        .get_image("acme/weather")
+        .unwrap_err();
+    handle.join().unwrap();
+    assert_eq!(err.to_string(), "registry response is larger than 10 bytes");
+}
@@ -0,0 +1,244 @@
+use prost_reflect::{
+    Cardinality, DescriptorPool, DynamicMessage, EnumBuilder, FieldBuilder, FileDescriptorBuilder,
+    Kind, MessageBuilder, MethodBuilder, ServiceBuilder, Syntax, Value,
+};
+
+use crate::test_file_descriptor;
+
+#[test]
+fn build_file() {
+    let pool = FileDescriptorBuilder::new("inferred.proto")
+        .package("inferred")
+        .add_dependency("google/protobuf/timestamp.proto")
+        .add_message(
+            MessageBuilder::new("Record")
+                .add_field(FieldBuilder::new("id", 1, "int64"))
+                .add_field(FieldBuilder::new(
+                    "created_at",
+                    2,
+                    "google.protobuf.Timestamp",
+                ))
+                .add_field(FieldBuilder::new("tags", 3, "string").repeated())
+                .add_field(FieldBuilder::map("counts", 4, "string", "Nested"))
+                .add_field(FieldBuilder::new("status", 5, "Status"))
+                .add_field(FieldBuilder::new("label", 6, "string").optional())
+                .add_field(FieldBuilder::new("text", 7, "string").oneof("value"))
+                .add_field(FieldBuilder::new("number", 8, "double").oneof("value"))
+                .add_message(MessageBuilder::new("Nested"))
+                .add_reserved_range(10..20)
+                .add_reserved_name("old"),
+        )
+        .add_enum(
+            EnumBuilder::new("Status")
+                .add_value("STATUS_UNSPECIFIED", 0)
+                .add_value("STATUS_OK", 1),
+        )
+        .add_service(ServiceBuilder::new("RecordService").add_method(
+            MethodBuilder::new("Watch", "Record", ".inferred.Record").server_streaming(true),
+        ))
+        .build()
+        .unwrap();
+
+    let file = pool.get_file_by_name("inferred.proto").unwrap();
+    assert_eq!(file.syntax(), Syntax::Proto3);
+    assert!(pool
+        .get_file_by_name("google/protobuf/timestamp.proto")
+        .is_some());
+
+    let record = pool.get_message_by_name("inferred.Record").unwrap();
+    assert_eq!(
+        record
+            .get_field_by_name("created_at")
+            .unwrap()
+            .kind()
+            .as_message()
+            .unwrap()
+            .full_name(),
+        "google.protobuf.Timestamp"
+    );
+    assert_eq!(
+        record.get_field_by_name("created_at").unwrap().json_name(),
+        "createdAt"
+    );
+    assert!(record.get_field_by_name("tags").unwrap().is_list());
+
+    let counts = record.get_field_by_name("counts").unwrap();
+    assert!(counts.is_map());
+    assert_eq!(
+        counts
+            .kind()
+            .as_message()
+            .unwrap()
+            .map_entry_value_field()
+            .kind()
+            .as_message()
+            .unwrap()
+            .full_name(),
+        "inferred.Record.Nested"
+    );
+
+    assert_eq!(
+        record
+            .get_field_by_name("status")
+            .unwrap()
+            .kind()
+            .as_enum()
+            .unwrap()
+            .full_name(),
+        "inferred.Status"
+    );
+
+    let label = record.get_field_by_name("label").unwrap();
+    assert!(label.supports_presence());
+    assert!(label.field_descriptor_proto().proto3_optional());
+
+    let oneofs: Vec<_> = record
+        .oneofs()
+        .map(|oneof| {
+            (
+                oneof.name().to_owned(),
+                oneof
+                    .fields()
+                    .map(|field| field.name().to_owned())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        oneofs,
+        vec![
+            (
+                "value".to_owned(),
+                vec!["text".to_owned(), "number".to_owned()]
+            ),
+            ("_label".to_owned(), vec!["label".to_owned()]),
+        ]
+    );
+    assert_eq!(record.reserved_ranges().collect::<Vec<_>>(), vec![10..20]);
+    assert_eq!(record.reserved_names().collect::<Vec<_>>(), vec!["old"]);
+
+    let method = file.services().next().unwrap().methods().next().unwrap();
+    assert_eq!(method.input(), record);
+    assert_eq!(method.output(), record);
+    assert!(!method.is_client_streaming());
+    assert!(method.is_server_streaming());
+
+    let mut message = DynamicMessage::new(record);
+    message.set_field_by_name("tags", Value::List(vec![Value::String("a".to_owned())]));
+    assert!(message.has_field_by_name("tags"));
+}
+
+#[test]
+fn build_proto2_file() {
+    let pool = FileDescriptorBuilder::new("proto2.proto")
+        .syntax(Syntax::Proto2)
+        .add_message(
+            MessageBuilder::new("Message")
+                .add_field(FieldBuilder::new("id", 1, "int32").required())
+                .add_field(FieldBuilder::new("name", 2, "string").default_value("unnamed"))
+                .add_field(
+                    FieldBuilder::new("values", 3, "int32")
+                        .repeated()
+                        .packed(true),
+                )
+                .add_extension_range(100..200),
+        )
+        .add_extension("Message", FieldBuilder::new("extra", 100, "bool"))
+        .build()
+        .unwrap();
+
+    let message = pool.get_message_by_name("Message").unwrap();
+    assert_eq!(message.parent_file().syntax(), Syntax::Proto2);
+    assert_eq!(
+        message.get_field_by_name("id").unwrap().cardinality(),
+        Cardinality::Required
+    );
+    assert_eq!(
+        DynamicMessage::new(message.clone()).get_field_by_name("name"),
+        Some(std::borrow::Cow::Owned(Value::String("unnamed".to_owned())))
+    );
+    assert!(message.get_field_by_name("values").unwrap().is_packed());
+
+    let extension = message.get_extension(100).unwrap();
+    assert_eq!(extension.name(), "extra");
+    assert_eq!(extension.kind(), Kind::Bool);
+}
+
+#[test]
+fn add_to_existing_pool() {
+    let mut pool = test_file_descriptor();
+    let file = FileDescriptorBuilder::new("uses_test.proto")
+        .add_dependency("test.proto")
+        .add_message(MessageBuilder::new("UsesTest").add_field(FieldBuilder::new(
+            "scalars",
+            1,
+            "test.Scalars",
+        )))
+        .add_to_pool(&mut pool)
+        .unwrap();
+
+    assert_eq!(file.name(), "uses_test.proto");
+    let message = pool.get_message_by_name("UsesTest").unwrap();
+    assert_eq!(
+        message
+            .get_field_by_name("scalars")
+            .unwrap()
+            .kind()
+            .as_message()
+            .unwrap(),
+        &pool.get_message_by_name("test.Scalars").unwrap()
+    );
+}
+
+#[test]
+fn build_errors() {
+    let build = |message: MessageBuilder| {
+        FileDescriptorBuilder::new("invalid.proto")
+            .package("invalid")
+            .add_message(message)
+            .build()
+            .unwrap_err()
+            .to_string()
+    };
+
+    assert_eq!(
+        build(
+            MessageBuilder::new("Message")
+                .add_field(FieldBuilder::new("a", 1, "int32"))
+                .add_field(FieldBuilder::new("b", 1, "int32"))
+        ),
+        "the field number 1 is used multiple times in message 'invalid.Message'"
+    );
+    assert_eq!(
+        build(MessageBuilder::new("Message").add_field(FieldBuilder::new("a", 19_500, "int32"))),
+        "the number 19500 of field 'a' in message 'invalid.Message' is invalid"
+    );
+    assert_eq!(
+        build(MessageBuilder::new("Message").add_field(FieldBuilder::new("a", 1, "Missing"))),
+        "the message or enum type 'Missing' was not found"
+    );
+    assert_eq!(
+        build(
+            MessageBuilder::new("Message").add_field(FieldBuilder::new("a", 1, "int32").required())
+        ),
+        "the field 'invalid.Message.a' is invalid: required fields are not allowed in proto3"
+    );
+    assert_eq!(
+        build(
+            MessageBuilder::new("Message")
+                .add_field(FieldBuilder::map("a", 1, "string", "string").repeated())
+        ),
+        "the field 'invalid.Message.a' is invalid: map fields cannot have a label or be part of a oneof"
+    );
+
+    let mut pool = DescriptorPool::new();
+    let err = FileDescriptorBuilder::new("invalid.proto")
+        .add_dependency("missing.proto")
+        .add_to_pool(&mut pool)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the file 'missing.proto' was not found while resolving dependencies for 'invalid.proto'"
+    );
+    assert_eq!(pool.files().len(), 0);
+}
@@ -0,0 +1,100 @@
+use prost::Message;
+use prost_reflect::{DynamicMessage, ReflectMessage, Value};
+
+use crate::{ComplexType, Scalars};
+
+fn message() -> DynamicMessage {
+    ComplexType {
+        nested: Some(Scalars {
+            int32: 5,
+            string: "nested".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+fn nested_string(message: &DynamicMessage) -> *const str {
+    let nested = message.get_field_by_name("nested").unwrap();
+    let nested = nested.as_message().unwrap();
+    let string = nested.get_field_by_name("string").unwrap();
+    string.as_str().unwrap() as *const str
+}
+
+#[test]
+fn clones_share_values() {
+    let original = message();
+    let clone = original.clone();
+    assert_eq!(original, clone);
+    assert!(std::ptr::eq(
+        nested_string(&original),
+        nested_string(&clone)
+    ));
+}
+
+#[test]
+fn modifying_clone_leaves_original_unchanged() {
+    let original = message();
+
+    let mut clone = original.clone();
+    clone.set_field_by_name("optional_enum", Value::EnumNumber(1));
+    assert_ne!(original, clone);
+    assert_eq!(
+        original
+            .get_field_by_name("optional_enum")
+            .unwrap()
+            .as_enum_number(),
+        Some(0)
+    );
+    // Unmodified nested messages are still shared.
+    assert!(std::ptr::eq(
+        nested_string(&original),
+        nested_string(&clone)
+    ));
+
+    let mut clone = original.clone();
+    clone
+        .get_field_by_name_mut("nested")
+        .unwrap()
+        .as_message_mut()
+        .unwrap()
+        .set_field_by_name("int32", Value::I32(6));
+    assert_eq!(
+        original
+            .transcode_to::<ComplexType>()
+            .unwrap()
+            .nested
+            .unwrap()
+            .int32,
+        5
+    );
+    assert_eq!(
+        clone
+            .transcode_to::<ComplexType>()
+            .unwrap()
+            .nested
+            .unwrap()
+            .int32,
+        6
+    );
+
+    let mut clone = original.clone();
+    clone.clear_field_by_name("my_enum");
+    clone.clear_unknown_fields();
+    assert_eq!(
+        original.transcode_to::<ComplexType>().unwrap().my_enum,
+        [1, 3]
+    );
+    assert!(clone
+        .transcode_to::<ComplexType>()
+        .unwrap()
+        .my_enum
+        .is_empty());
+
+    let mut clone = original.clone();
+    clone.clear();
+    assert_eq!(clone, ComplexType::default().transcode_to_dynamic());
+    assert_eq!(original, message());
+}
@@ -0,0 +1,182 @@
+use std::cmp::Ordering;
+
+use proptest::prelude::*;
+use prost::Message;
+use prost_reflect::{DynamicMessage, MapKey, ReflectMessage, Value};
+
+use crate::{arb_message, Scalars};
+
+#[test]
+fn partial_cmp_scalars() {
+    assert!(Value::I32(1) < Value::I32(2));
+    assert!(Value::String("a".to_owned()) < Value::String("b".to_owned()));
+    assert!(Value::Bytes(b"a".as_ref().into()) < Value::Bytes(b"ab".as_ref().into()));
+    assert_eq!(
+        Value::F64(0.0).partial_cmp(&Value::F64(-0.0)),
+        Some(Ordering::Equal)
+    );
+    assert_eq!(Value::F64(f64::NAN).partial_cmp(&Value::F64(1.0)), None);
+    assert_eq!(
+        Value::F64(f64::NAN).partial_cmp(&Value::F64(f64::NAN)),
+        Some(Ordering::Equal)
+    );
+    assert_eq!(Value::I32(1).partial_cmp(&Value::I64(1)), None);
+    assert!(
+        Value::List(vec![Value::U32(1), Value::U32(2)])
+            < Value::List(vec![Value::U32(1), Value::U32(3)])
+    );
+}
+
+#[test]
+fn partial_cmp_messages() {
+    let message1 = Value::Message(
+        Scalars {
+            int32: 1,
+            ..Default::default()
+        }
+        .transcode_to_dynamic(),
+    );
+    let message2 = Value::Message(
+        Scalars {
+            int32: 2,
+            ..Default::default()
+        }
+        .transcode_to_dynamic(),
+    );
+    assert_eq!(message1.partial_cmp(&message1), Some(Ordering::Equal));
+    assert_eq!(message1.partial_cmp(&message2), None);
+    assert_eq!(message1.total_cmp(&message2), Ordering::Less);
+}
+
+#[test]
+fn total_cmp_floats() {
+    let mut values = [
+        Value::F32(f32::INFINITY),
+        Value::F32(f32::NAN),
+        Value::F32(0.0),
+        Value::F32(-f32::NAN),
+        Value::F32(-1.5),
+        Value::F32(-0.0),
+        Value::F32(f32::NEG_INFINITY),
+    ];
+    values.sort_by(Value::total_cmp);
+
+    let bits: Vec<u32> = values
+        .iter()
+        .map(|value| value.as_f32().unwrap().to_bits())
+        .collect();
+    assert_eq!(
+        bits,
+        vec![
+            (-f32::NAN).to_bits(),
+            f32::NEG_INFINITY.to_bits(),
+            (-1.5f32).to_bits(),
+            (-0.0f32).to_bits(),
+            0.0f32.to_bits(),
+            f32::INFINITY.to_bits(),
+            f32::NAN.to_bits(),
+        ]
+    );
+}
+
+#[test]
+fn total_cmp_variants() {
+    let mut values = vec![
+        Value::String("a".to_owned()),
+        Value::I64(-1),
+        Value::Bool(true),
+        Value::I32(5),
+    ];
+    values.sort_by(Value::total_cmp);
+    assert_eq!(
+        values,
+        vec![
+            Value::Bool(true),
+            Value::I32(5),
+            Value::I64(-1),
+            Value::String("a".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn total_cmp_maps() {
+    let map = |entries: &[(i32, i32)]| {
+        Value::Map(
+            entries
+                .iter()
+                .map(|&(key, value)| (MapKey::I32(key), Value::I32(value)))
+                .collect(),
+        )
+    };
+
+    assert_eq!(
+        map(&[(1, 1), (2, 2)]).total_cmp(&map(&[(2, 2), (1, 1)])),
+        Ordering::Equal
+    );
+    assert_eq!(
+        map(&[(1, 1), (2, 2)]).total_cmp(&map(&[(1, 1), (2, 3)])),
+        Ordering::Less
+    );
+    assert_eq!(
+        map(&[(1, 1), (3, 0)]).total_cmp(&map(&[(1, 1), (2, 9)])),
+        Ordering::Greater
+    );
+    assert_eq!(map(&[]).total_cmp(&map(&[(1, 1)])), Ordering::Less);
+}
+
+#[test]
+fn total_cmp_messages() {
+    let message = |int32: i32, string: &str| {
+        Value::Message(
+            Scalars {
+                int32,
+                string: string.to_owned(),
+                ..Default::default()
+            }
+            .transcode_to_dynamic(),
+        )
+    };
+
+    let mut values = vec![
+        message(3, "a"),
+        message(1, "b"),
+        message(2, "c"),
+        message(1, "a"),
+    ];
+    values.sort_by(Value::total_cmp);
+    assert_eq!(
+        values,
+        vec![
+            message(1, "a"),
+            message(1, "b"),
+            message(2, "c"),
+            message(3, "a"),
+        ]
+    );
+
+    // A message with fewer fields set is ordered first when the shared fields are equal.
+    assert_eq!(message(1, "").total_cmp(&message(1, "a")), Ordering::Less);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn total_cmp_consistent(
+        message1 in arb_message("test.ComplexType"),
+        message2 in arb_message("test.ComplexType"),
+    ) {
+        let decoded = DynamicMessage::decode(message1.descriptor(), message1.encode_to_vec().as_slice()).unwrap();
+        let value1 = Value::Message(message1);
+        let value2 = Value::Message(message2);
+        let decoded = Value::Message(decoded);
+        if decoded == value1 {
+            prop_assert_eq!(value1.total_cmp(&decoded), Ordering::Equal);
+        }
+        prop_assert_eq!(value1.total_cmp(&value2), value2.total_cmp(&value1).reverse());
+    }
+}
@@ -0,0 +1,92 @@
+use std::io;
+
+use prost::{bytes::BytesMut, Message};
+use prost_reflect::{DynamicMessage, DynamicMessageCodec, ReflectMessage};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{Point, Scalars};
+
+fn point(i: i32) -> Point {
+    Point {
+        latitude: i,
+        longitude: -i,
+    }
+}
+
+#[test]
+fn encode() {
+    let mut codec = DynamicMessageCodec::new(Point::default().descriptor());
+
+    let mut buf = BytesMut::new();
+    codec
+        .encode(point(1).transcode_to_dynamic(), &mut buf)
+        .unwrap();
+    codec
+        .encode(&point(2).transcode_to_dynamic(), &mut buf)
+        .unwrap();
+
+    let mut expected = point(1).encode_length_delimited_to_vec();
+    expected.extend(point(2).encode_length_delimited_to_vec());
+    assert_eq!(buf.as_ref(), expected.as_slice());
+}
+
+#[test]
+fn decode_partial_frames() {
+    let mut codec = DynamicMessageCodec::new(Point::default().descriptor());
+    let mut bytes = point(1).encode_length_delimited_to_vec();
+    bytes.extend(point(2).encode_length_delimited_to_vec());
+
+    // Feed the codec one byte at a time, as if reading from a slow socket.
+    let mut buf = BytesMut::new();
+    let mut decoded = Vec::new();
+    for &byte in &bytes {
+        buf.extend_from_slice(&[byte]);
+        if let Some(message) = codec.decode(&mut buf).unwrap() {
+            decoded.push(message.transcode_to::<Point>().unwrap());
+        }
+    }
+
+    assert_eq!(decoded, vec![point(1), point(2)]);
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn decode_empty_message() {
+    let mut codec = DynamicMessageCodec::new(Point::default().descriptor());
+    let mut buf = BytesMut::from(&[0u8][..]);
+
+    let message = codec.decode(&mut buf).unwrap().unwrap();
+    assert_eq!(message, DynamicMessage::new(Point::default().descriptor()));
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn decode_invalid_message() {
+    let mut codec = DynamicMessageCodec::new(Scalars::default().descriptor());
+    // A length-delimited string field whose length overruns the message.
+    let mut buf = BytesMut::from(&[3u8, 0x72, 0x05, b'h'][..]);
+
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn decode_exceeds_max_length() {
+    let mut codec = DynamicMessageCodec::new(Point::default().descriptor()).max_length(2);
+    let mut buf = BytesMut::from(point(100).encode_length_delimited_to_vec().as_slice());
+
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn encode_exceeds_max_length() {
+    let mut codec = DynamicMessageCodec::new(Point::default().descriptor()).max_length(2);
+
+    let mut buf = BytesMut::new();
+    let err = codec
+        .encode(point(100).transcode_to_dynamic(), &mut buf)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(buf.is_empty());
+}
@@ -0,0 +1,559 @@
+use std::{env, fs};
+
+use prost_reflect::{
+    compat::{self, BreakingChange, ChangeKind, ChangeType, ElementKind},
+    DescriptorPool, EnumBuilder, FieldBuilder, FileDescriptorBuilder, MessageBuilder,
+    MethodBuilder, ServiceBuilder,
+};
+use serde_json::json;
+
+fn user_file(fields: Vec<FieldBuilder>) -> DescriptorPool {
+    let message = fields
+        .into_iter()
+        .fold(MessageBuilder::new("User"), |message, field| {
+            message.add_field(field)
+        });
+    FileDescriptorBuilder::new("user.proto")
+        .package("test.compat")
+        .add_message(message)
+        .build()
+        .unwrap()
+}
+
+fn changes(changes: &[BreakingChange]) -> Vec<(ChangeKind, &str, bool)> {
+    changes
+        .iter()
+        .map(|change| (change.kind(), change.element(), change.is_wire_compatible()))
+        .collect()
+}
+
+#[test]
+fn no_changes() {
+    let pool = user_file(vec![
+        FieldBuilder::new("id", 1, "int64"),
+        FieldBuilder::new("name", 2, "string").optional(),
+    ]);
+    assert_eq!(compat::check_pools(&pool, &pool), []);
+}
+
+#[test]
+fn additions_are_compatible() {
+    let old = user_file(vec![FieldBuilder::new("id", 1, "int64")]);
+    let new = FileDescriptorBuilder::new("user.proto")
+        .package("test.compat")
+        .add_message(
+            MessageBuilder::new("User")
+                .add_field(FieldBuilder::new("id", 1, "int64"))
+                .add_field(FieldBuilder::new("name", 2, "string")),
+        )
+        .add_message(MessageBuilder::new("Group"))
+        .add_enum(EnumBuilder::new("Role").add_value("ROLE_UNSPECIFIED", 0))
+        .build()
+        .unwrap();
+    assert_eq!(compat::check_pools(&old, &new), []);
+}
+
+#[test]
+fn field_changes() {
+    let old = user_file(vec![
+        FieldBuilder::new("id", 1, "int64"),
+        FieldBuilder::new("name", 2, "string"),
+        FieldBuilder::new("age", 3, "int32"),
+        FieldBuilder::new("email", 4, "string"),
+        FieldBuilder::new("tags", 5, "string").repeated(),
+        FieldBuilder::new("phone", 6, "string"),
+        FieldBuilder::new("nickname", 7, "string"),
+        FieldBuilder::new("score", 8, "fixed32"),
+        FieldBuilder::new("labels", 9, "string").repeated(),
+    ]);
+    let new = user_file(vec![
+        FieldBuilder::new("id", 1, "int64"),
+        FieldBuilder::new("full_name", 2, "string"),
+        FieldBuilder::new("age", 3, "int64"),
+        FieldBuilder::new("email", 10, "string"),
+        FieldBuilder::new("tags", 5, "string"),
+        FieldBuilder::new("phone", 6, "string").oneof("contact"),
+        FieldBuilder::new("nickname", 7, "string").optional(),
+        FieldBuilder::new("score", 8, "float"),
+        FieldBuilder::new("labels", 9, "bytes").repeated(),
+    ]);
+
+    let result = compat::check_pools(&old, &new);
+    assert_eq!(
+        changes(&result),
+        [
+            (ChangeKind::FieldNameChanged, "test.compat.User.name", true),
+            (ChangeKind::FieldTypeChanged, "test.compat.User.age", true),
+            (
+                ChangeKind::FieldNumberChanged,
+                "test.compat.User.email",
+                false
+            ),
+            (
+                ChangeKind::FieldCardinalityChanged,
+                "test.compat.User.tags",
+                true
+            ),
+            (
+                ChangeKind::FieldOneofChanged,
+                "test.compat.User.phone",
+                false
+            ),
+            (
+                ChangeKind::FieldTypeChanged,
+                "test.compat.User.score",
+                false
+            ),
+            (
+                ChangeKind::FieldTypeChanged,
+                "test.compat.User.labels",
+                true
+            ),
+        ]
+    );
+    assert_eq!(
+        result[0].to_string(),
+        "test.compat.User.name: name changed from 'name' to 'full_name'"
+    );
+    assert_eq!(result[2].message(), "number changed from 4 to 10");
+    assert_eq!(
+        result[3].message(),
+        "cardinality changed from repeated to optional"
+    );
+    assert_eq!(result[4].message(), "moved into oneof 'contact'");
+    assert_eq!(result[5].message(), "type changed from fixed32 to float");
+}
+
+#[test]
+fn removed_field() {
+    let old = user_file(vec![
+        FieldBuilder::new("id", 1, "int64"),
+        FieldBuilder::new("name", 2, "string"),
+    ]);
+    let new = user_file(vec![FieldBuilder::new("id", 1, "int64")]);
+
+    let result = compat::check_pools(&old, &new);
+    assert_eq!(
+        changes(&result),
+        [(ChangeKind::FieldRemoved, "test.compat.User.name", true)]
+    );
+    assert_eq!(result[0].message(), "field 2 was removed");
+}
+
+#[test]
+fn message_type_changed() {
+    let file = |address_type: &str| {
+        FileDescriptorBuilder::new("user.proto")
+            .add_message(MessageBuilder::new("Address"))
+            .add_message(MessageBuilder::new("Location"))
+            .add_message(
+                MessageBuilder::new("User")
+                    .add_field(FieldBuilder::new("address", 1, address_type))
+                    .add_field(FieldBuilder::map("labels", 2, "string", address_type)),
+            )
+            .build()
+            .unwrap()
+    };
+
+    let result = compat::check_pools(&file("Address"), &file("Location"));
+    assert_eq!(
+        changes(&result),
+        [
+            (
+                ChangeKind::FieldTypeChanged,
+                "User.LabelsEntry.value",
+                false
+            ),
+            (ChangeKind::FieldTypeChanged, "User.address", false),
+        ]
+    );
+    assert_eq!(result[1].message(), "type changed from Address to Location");
+}
+
+#[test]
+fn enum_changes() {
+    let file = |values: &[(&str, i32)]| {
+        let enum_ = values
+            .iter()
+            .fold(EnumBuilder::new("Status"), |enum_, &(name, number)| {
+                enum_.add_value(name, number)
+            });
+        FileDescriptorBuilder::new("status.proto")
+            .add_enum(enum_)
+            .build()
+            .unwrap()
+    };
+
+    let old = file(&[("UNKNOWN", 0), ("ACTIVE", 1), ("DELETED", 2)]);
+    let new = file(&[("STATUS_UNSPECIFIED", 0), ("ACTIVE", 1)]);
+    let result = compat::check_pools(&old, &new);
+    assert_eq!(
+        changes(&result),
+        [
+            (ChangeKind::EnumValueNameChanged, "UNKNOWN", true),
+            (ChangeKind::EnumValueRemoved, "DELETED", true),
+        ]
+    );
+
+    let empty = FileDescriptorBuilder::new("status.proto").build().unwrap();
+    assert_eq!(
+        changes(&compat::check_pools(&old, &empty)),
+        [(ChangeKind::EnumRemoved, "Status", true)]
+    );
+}
+
+#[test]
+fn service_changes() {
+    let file = |service: Option<ServiceBuilder>| {
+        let file = FileDescriptorBuilder::new("service.proto")
+            .add_message(MessageBuilder::new("Request"))
+            .add_message(MessageBuilder::new("Response"));
+        match service {
+            Some(service) => file.add_service(service),
+            None => file,
+        }
+        .build()
+        .unwrap()
+    };
+
+    let old = file(Some(
+        ServiceBuilder::new("Api")
+            .add_method(MethodBuilder::new("Get", "Request", "Response"))
+            .add_method(MethodBuilder::new("List", "Request", "Response"))
+            .add_method(MethodBuilder::new("Watch", "Request", "Response"))
+            .add_method(MethodBuilder::new("Delete", "Request", "Response")),
+    ));
+    let new = file(Some(
+        ServiceBuilder::new("Api")
+            .add_method(MethodBuilder::new("Get", "Request", "Response"))
+            .add_method(MethodBuilder::new("List", "Request", "Request"))
+            .add_method(MethodBuilder::new("Watch", "Request", "Response").server_streaming(true))
+            .add_method(MethodBuilder::new("Create", "Request", "Response")),
+    ));
+
+    let result = compat::check_pools(&old, &new);
+    assert_eq!(
+        changes(&result),
+        [
+            (ChangeKind::MethodTypeChanged, "Api.List", false),
+            (ChangeKind::MethodStreamingChanged, "Api.Watch", false),
+            (ChangeKind::MethodRemoved, "Api.Delete", false),
+        ]
+    );
+    assert_eq!(
+        result[0].message(),
+        "response type changed from Response to Request"
+    );
+    assert_eq!(
+        result[1].message(),
+        "changed from unary to server streaming"
+    );
+
+    assert_eq!(
+        changes(&compat::check_pools(&old, &file(None))),
+        [(ChangeKind::ServiceRemoved, "Api", false)]
+    );
+}
+
+#[test]
+fn check_messages_recursive() {
+    let old = FileDescriptorBuilder::new("v1.proto")
+        .package("v1")
+        .add_message(
+            MessageBuilder::new("Outer")
+                .add_field(FieldBuilder::new("id", 1, "int32"))
+                .add_message(
+                    MessageBuilder::new("Inner").add_field(FieldBuilder::new("value", 1, "string")),
+                )
+                .add_message(MessageBuilder::new("Removed"))
+                .add_enum(EnumBuilder::new("Kind").add_value("KIND_UNSPECIFIED", 0)),
+        )
+        .build()
+        .unwrap();
+    let new = FileDescriptorBuilder::new("v2.proto")
+        .package("v2")
+        .add_message(
+            MessageBuilder::new("Outer")
+                .add_field(FieldBuilder::new("id", 1, "int32"))
+                .add_message(
+                    MessageBuilder::new("Inner").add_field(FieldBuilder::new("value", 1, "int32")),
+                )
+                .add_enum(EnumBuilder::new("Kind").add_value("KIND_DEFAULT", 0)),
+        )
+        .build()
+        .unwrap();
+
+    let result = compat::check_messages(
+        &old.get_message_by_name("v1.Outer").unwrap(),
+        &new.get_message_by_name("v2.Outer").unwrap(),
+    );
+    assert_eq!(
+        changes(&result),
+        [
+            (ChangeKind::FieldTypeChanged, "v1.Outer.Inner.value", false),
+            (ChangeKind::MessageRemoved, "v1.Outer.Removed", true),
+            (
+                ChangeKind::EnumValueNameChanged,
+                "v1.Outer.KIND_UNSPECIFIED",
+                true
+            ),
+        ]
+    );
+}
+
+#[test]
+fn check_files_moved_message() {
+    let mut old = DescriptorPool::new();
+    let old_file = FileDescriptorBuilder::new("a.proto")
+        .add_message(MessageBuilder::new("A").add_field(FieldBuilder::new("x", 1, "int32")))
+        .add_message(MessageBuilder::new("B"))
+        .add_to_pool(&mut old)
+        .unwrap();
+
+    let mut new = DescriptorPool::new();
+    let new_file = FileDescriptorBuilder::new("a.proto")
+        .add_message(MessageBuilder::new("A").add_field(FieldBuilder::new("x", 1, "uint32")))
+        .add_to_pool(&mut new)
+        .unwrap();
+    FileDescriptorBuilder::new("b.proto")
+        .add_message(MessageBuilder::new("B"))
+        .add_to_pool(&mut new)
+        .unwrap();
+
+    assert_eq!(
+        changes(&compat::check_files(&old_file, &new_file)),
+        [
+            (ChangeKind::FieldTypeChanged, "A.x", true),
+            (ChangeKind::MessageRemoved, "B", true),
+        ]
+    );
+    assert_eq!(
+        changes(&compat::check_pools(&old, &new)),
+        [(ChangeKind::FieldTypeChanged, "A.x", true)]
+    );
+}
+
+fn compile(name: &str, source: &str) -> DescriptorPool {
+    let dir = env::temp_dir().join(format!(
+        "prost-reflect-compat-{}-{}",
+        name,
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("schema.proto"), source).unwrap();
+
+    let mut pool = DescriptorPool::new();
+    pool.add_proto_files(["schema.proto"], [&dir]).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    pool
+}
+
+fn diff_schema() -> (DescriptorPool, DescriptorPool) {
+    let old = FileDescriptorBuilder::new("user.proto")
+        .package("test")
+        .add_message(
+            MessageBuilder::new("User")
+                .add_field(FieldBuilder::new("id", 1, "int32"))
+                .add_field(FieldBuilder::new("name", 2, "string"))
+                .add_field(FieldBuilder::new("email", 3, "string").oneof("contact"))
+                .add_field(FieldBuilder::new("phone", 4, "string").oneof("contact")),
+        )
+        .add_message(MessageBuilder::new("Removed"))
+        .add_enum(
+            EnumBuilder::new("Status")
+                .add_value("STATUS_UNSPECIFIED", 0)
+                .add_value("STATUS_ACTIVE", 1),
+        )
+        .add_service(
+            ServiceBuilder::new("Api")
+                .add_method(MethodBuilder::new("Get", "User", "User"))
+                .add_method(MethodBuilder::new("Delete", "User", "User")),
+        )
+        .build()
+        .unwrap();
+
+    let mut new = DescriptorPool::new();
+    FileDescriptorBuilder::new("user.proto")
+        .package("test")
+        .add_message(
+            MessageBuilder::new("User")
+                .add_field(FieldBuilder::new("id", 1, "int64"))
+                .add_field(FieldBuilder::new("email", 3, "string").repeated())
+                .add_field(FieldBuilder::new("phone", 5, "string").optional())
+                .add_field(FieldBuilder::map("labels", 6, "string", "int32")),
+        )
+        .add_enum(
+            EnumBuilder::new("Status")
+                .add_value("STATUS_UNSPECIFIED", 0)
+                .add_value("STATUS_ACTIVE", 2)
+                .add_value("STATUS_DELETED", 3),
+        )
+        .add_service(
+            ServiceBuilder::new("Api")
+                .add_method(MethodBuilder::new("Get", "User", "User").server_streaming(true))
+                .add_method(MethodBuilder::new("List", "User", "User")),
+        )
+        .add_to_pool(&mut new)
+        .unwrap();
+    FileDescriptorBuilder::new("group.proto")
+        .package("test")
+        .add_message(MessageBuilder::new("Group"))
+        .add_to_pool(&mut new)
+        .unwrap();
+
+    (old, new)
+}
+
+#[test]
+fn diff_identical() {
+    let (old, _) = diff_schema();
+    assert!(compat::diff(&old, &old).is_empty());
+}
+
+#[test]
+fn diff_elements() {
+    let (old, new) = diff_schema();
+    let diff = compat::diff(&old, &new);
+    assert_eq!(
+        diff.to_string(),
+        "\
++ file group.proto
+~ field test.User.id: type: int32 -> int64
+- field test.User.name
+~ field test.User.email: cardinality: optional -> repeated, presence: true -> false, oneof: contact -> <unset>
+~ field test.User.phone: number: 4 -> 5, oneof: contact -> <unset>
++ field test.User.labels
+- oneof test.User.contact
+- message test.Removed
++ message test.Group
+~ enum value test.Status.STATUS_ACTIVE: number: 1 -> 2
++ enum value test.Status.STATUS_DELETED
+~ method test.Api.Get: server_streaming: false -> true
+- method test.Api.Delete
++ method test.Api.List"
+    );
+
+    let change = diff.changes().nth(3).unwrap();
+    assert_eq!(change.change_type(), ChangeType::Modified);
+    assert_eq!(change.element_kind(), ElementKind::Field);
+    assert_eq!(change.name(), "test.User.email");
+    assert_eq!(change.properties()[0].name(), "cardinality");
+    assert_eq!(change.properties()[0].old_value(), "optional");
+    assert_eq!(change.properties()[0].new_value(), "repeated");
+}
+
+#[test]
+fn diff_reversed() {
+    let (old, new) = diff_schema();
+    let diff = compat::diff(&new, &old);
+    let removed: Vec<_> = diff
+        .changes()
+        .filter(|change| change.change_type() == ChangeType::Removed)
+        .map(|change| (change.element_kind(), change.name()))
+        .collect();
+    assert_eq!(
+        removed,
+        [
+            (ElementKind::File, "group.proto"),
+            (ElementKind::Field, "test.User.labels"),
+            (ElementKind::Message, "test.Group"),
+            (ElementKind::EnumValue, "test.Status.STATUS_DELETED"),
+            (ElementKind::Method, "test.Api.List"),
+        ]
+    );
+}
+
+#[test]
+fn diff_options_and_properties() {
+    let old = compile(
+        "old",
+        r#"
+        syntax = "proto2";
+        package test;
+
+        option java_package = "com.example";
+
+        message Config {
+          optional int32 retries = 1 [default = 3];
+          optional string name = 2;
+          map<string, int32> limits = 3;
+        }
+
+        enum Mode {
+          MODE_FAST = 0;
+        }
+
+        extend Config {
+          optional string tag = 100;
+        }
+        "#,
+    );
+    let new = compile(
+        "new",
+        r#"
+        syntax = "proto2";
+        package test;
+
+        option java_package = "com.example.v2";
+        option deprecated = true;
+
+        message Config {
+          option deprecated = true;
+
+          optional int32 retries = 1 [default = 5];
+          optional string name = 2 [deprecated = true, json_name = "displayName"];
+          map<string, int64> limits = 3;
+        }
+
+        enum Mode {
+          MODE_FAST = 0 [deprecated = true];
+        }
+
+        extend Config {
+          repeated string tag = 100;
+        }
+        "#,
+    );
+
+    let diff = compat::diff(&old, &new);
+    assert_eq!(
+        diff.to_string(),
+        "\
+~ file schema.proto: options.java_package: \"com.example\" -> \"com.example.v2\", options.deprecated: <unset> -> true
+~ message test.Config: options.deprecated: <unset> -> true
+~ field test.Config.retries: default: 3 -> 5
+~ field test.Config.name: json_name: name -> displayName, options.deprecated: <unset> -> true
+~ field test.Config.limits: type: map<string, int32> -> map<string, int64>
+~ enum value test.Mode.MODE_FAST: options.deprecated: <unset> -> true
+~ extension test.tag: cardinality: optional -> repeated"
+    );
+}
+
+#[test]
+fn diff_serialize() {
+    let (old, new) = diff_schema();
+    let diff = compat::diff(&old, &new);
+    let value = serde_json::to_value(&diff).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), diff.changes().len());
+    assert_eq!(
+        value[0],
+        json!({
+            "type": "added",
+            "kind": "file",
+            "name": "group.proto",
+            "properties": [],
+        })
+    );
+    assert_eq!(
+        value[4],
+        json!({
+            "type": "modified",
+            "kind": "field",
+            "name": "test.User.phone",
+            "properties": [
+                { "name": "number", "old": "4", "new": "5" },
+                { "name": "oneof", "old": "contact", "new": "<unset>" },
+            ],
+        })
+    );
+}
@@ -0,0 +1,299 @@
+use std::{env, fs, path::PathBuf};
+
+use prost_reflect::{Cardinality, DescriptorPool, Kind};
+use prost_types::FileDescriptorProto;
+
+use crate::test_file_descriptor;
+
+const TEST_FILES: &[&str] = &[
+    "test.proto",
+    "test2.proto",
+    "desc.proto",
+    "desc2.proto",
+    "desc_no_package.proto",
+    "imports.proto",
+    "lint.proto",
+];
+
+/// Creates a fresh directory containing the given files.
+fn temp_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!(
+        "prost-reflect-compile-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    for (name, contents) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+fn compile(name: &str, files: &[(&str, &str)]) -> Result<DescriptorPool, String> {
+    let dir = temp_dir(name, files);
+    let mut pool = DescriptorPool::new();
+    let result = pool.add_proto_files(files.iter().take(1).map(|(name, _)| name), [&dir]);
+    fs::remove_dir_all(&dir).unwrap();
+    result.map(|()| pool).map_err(|err| err.to_string())
+}
+
+fn without_source_info(file: &FileDescriptorProto) -> FileDescriptorProto {
+    let mut file = file.clone();
+    file.source_code_info = None;
+    file
+}
+
+#[test]
+fn compile_matches_protoc() {
+    let include_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src");
+    let mut pool = DescriptorPool::new();
+    pool.add_proto_files(
+        TEST_FILES.iter().map(|name| include_dir.join(name)),
+        [&include_dir],
+    )
+    .unwrap();
+
+    let expected = test_file_descriptor();
+    for name in TEST_FILES {
+        assert_eq!(
+            without_source_info(pool.get_file_by_name(name).unwrap().file_descriptor_proto()),
+            without_source_info(
+                expected
+                    .get_file_by_name(name)
+                    .unwrap()
+                    .file_descriptor_proto()
+            ),
+            "file '{}' does not match",
+            name
+        );
+    }
+    assert!(pool
+        .get_file_by_name("google/protobuf/timestamp.proto")
+        .is_some());
+}
+
+#[test]
+fn compile_relative_paths() {
+    let dir = temp_dir(
+        "relative",
+        &[
+            (
+                "pkg/a.proto",
+                "syntax = 'proto3'; package pkg; import 'pkg/b.proto'; message A { B b = 1; }",
+            ),
+            (
+                "pkg/b.proto",
+                "syntax = 'proto3'; package pkg; message B {}",
+            ),
+        ],
+    );
+    let mut pool = DescriptorPool::new();
+    pool.add_proto_files(["pkg/a.proto"], [&dir]).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let a = pool.get_message_by_name("pkg.A").unwrap();
+    assert_eq!(a.parent_file().name(), "pkg/a.proto");
+    assert_eq!(
+        a.get_field_by_name("b")
+            .unwrap()
+            .kind()
+            .as_message()
+            .unwrap()
+            .full_name(),
+        "pkg.B"
+    );
+    assert!(pool.get_file_by_name("pkg/b.proto").is_some());
+}
+
+#[test]
+fn compile_skips_files_in_pool() {
+    let mut pool = test_file_descriptor();
+    let dir = temp_dir(
+        "existing",
+        &[(
+            "uses_test.proto",
+            "syntax = 'proto3'; import 'test.proto'; message UsesTest { test.Scalars scalars = 1; }",
+        )],
+    );
+    pool.add_proto_files(["uses_test.proto"], [&dir]).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let message = pool.get_message_by_name("UsesTest").unwrap();
+    assert_eq!(
+        message
+            .get_field_by_name("scalars")
+            .unwrap()
+            .kind()
+            .as_message()
+            .unwrap(),
+        &pool.get_message_by_name("test.Scalars").unwrap()
+    );
+}
+
+#[test]
+fn compile_proto3_optional_and_options() {
+    let pool = compile(
+        "options",
+        &[(
+            "options.proto",
+            r#"
+            syntax = "proto3";
+            package options;
+
+            option java_package = "com.example";
+            option optimize_for = CODE_SIZE;
+            option (my_option) = { foo: 1 };
+
+            message Message {
+                optional int32 maybe = 1 [json_name = "perhaps"];
+                repeated int32 packed = 2 [packed = false, deprecated = true];
+                oneof choice {
+                    string name = 3;
+                    Nested.Inner inner = 4;
+                }
+                map<string, Nested.Kind> kinds = 5;
+
+                message Nested {
+                    message Inner {}
+                    enum Kind { KIND_UNSPECIFIED = 0; }
+                }
+            }
+
+            service Service {
+                rpc Stream(stream Message) returns (stream .options.Message) {
+                    option deprecated = true;
+                }
+            }
+            "#,
+        )],
+    )
+    .unwrap();
+
+    let file = pool.get_file_by_name("options.proto").unwrap();
+    let options = file.file_descriptor_proto().options.as_ref().unwrap();
+    assert_eq!(options.java_package(), "com.example");
+    assert_eq!(options.optimize_for, Some(2));
+    assert_eq!(
+        options.uninterpreted_option[0].name[0].name_part,
+        "my_option"
+    );
+    assert_eq!(options.uninterpreted_option[0].aggregate_value(), "foo: 1");
+
+    let message = pool.get_message_by_name("options.Message").unwrap();
+    let maybe = message.get_field_by_name("maybe").unwrap();
+    assert_eq!(maybe.json_name(), "perhaps");
+    assert!(maybe.supports_presence());
+    assert_eq!(maybe.containing_oneof().unwrap().name(), "_maybe");
+    assert_eq!(
+        message
+            .oneofs()
+            .map(|o| o.name().to_owned())
+            .collect::<Vec<_>>(),
+        vec!["choice", "_maybe"]
+    );
+
+    let packed = message.get_field_by_name("packed").unwrap();
+    assert_eq!(packed.cardinality(), Cardinality::Repeated);
+    assert!(!packed.is_packed());
+
+    let inner = message.get_field_by_name("inner").unwrap();
+    assert_eq!(inner.containing_oneof().unwrap().name(), "choice");
+    assert_eq!(
+        inner.kind().as_message().unwrap().full_name(),
+        "options.Message.Nested.Inner"
+    );
+
+    let kinds = message.get_field_by_name("kinds").unwrap();
+    assert!(kinds.is_map());
+    let entry = kinds.kind();
+    let entry = entry.as_message().unwrap();
+    assert_eq!(entry.name(), "KindsEntry");
+    assert!(matches!(
+        entry.map_entry_value_field().kind(),
+        Kind::Enum(_)
+    ));
+
+    let method = pool.services().next().unwrap().methods().next().unwrap();
+    assert!(method.is_client_streaming());
+    assert!(method.is_server_streaming());
+    assert_eq!(method.input(), message);
+}
+
+#[test]
+fn compile_parse_error() {
+    let err = compile(
+        "parse_error",
+        &[(
+            "invalid.proto",
+            "syntax = \"proto3\";\n\nmessage Foo {\n  int32 foo = ;\n}\n",
+        )],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        "invalid.proto:4:15: expected a field number, but found ';'"
+    );
+
+    let err = compile(
+        "proto3_required",
+        &[(
+            "invalid.proto",
+            "syntax = \"proto3\";\nmessage Foo { required int32 foo = 1; }",
+        )],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        "invalid.proto:2:15: required fields are not allowed in proto3"
+    );
+}
+
+#[test]
+fn compile_unknown_type() {
+    let err = compile(
+        "unknown_type",
+        &[(
+            "invalid.proto",
+            "syntax = \"proto3\"; message Foo { Bar bar = 1; }",
+        )],
+    )
+    .unwrap_err();
+    assert_eq!(err, "the message or enum type 'Bar' was not found");
+}
+
+#[test]
+fn compile_import_errors() {
+    let err = compile(
+        "missing_import",
+        &[("a.proto", "syntax = \"proto3\"; import \"missing.proto\";")],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        "the file 'missing.proto' was not found while resolving dependencies for 'a.proto'"
+    );
+
+    let err = compile(
+        "circular_import",
+        &[
+            ("a.proto", "syntax = \"proto3\"; import \"b.proto\";"),
+            ("b.proto", "syntax = \"proto3\"; import \"a.proto\";"),
+        ],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        "circular import detected: a.proto -> b.proto -> a.proto"
+    );
+
+    let mut pool = DescriptorPool::new();
+    let err = pool
+        .add_proto_files(["does_not_exist.proto"], &[env::temp_dir()])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the file 'does_not_exist.proto' was not found in any include directory"
+    );
+}
@@ -0,0 +1,134 @@
+use prost_reflect::{Compression, DescriptorPool};
+
+use crate::test_file_descriptor;
+
+fn file_names(pool: &DescriptorPool) -> Vec<String> {
+    let mut names: Vec<_> = pool.files().map(|file| file.name().to_owned()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn compressed_roundtrip() {
+    let pool = test_file_descriptor();
+    for &compression in &[Compression::None, Compression::Gzip, Compression::Zstd] {
+        let bytes = pool.encode_compressed(compression);
+        let decoded = DescriptorPool::decode_compressed(&bytes).unwrap();
+        assert_eq!(file_names(&decoded), file_names(&pool));
+        assert!(decoded.get_message_by_name("test.Point").is_some());
+    }
+}
+
+#[test]
+fn compression_reduces_size() {
+    let pool = test_file_descriptor();
+    let uncompressed = pool.encode_compressed(Compression::None).len();
+    assert!(pool.encode_compressed(Compression::Gzip).len() < uncompressed);
+    assert!(pool.encode_compressed(Compression::Zstd).len() < uncompressed);
+}
+
+#[test]
+fn decode_compressed_invalid() {
+    let err = DescriptorPool::decode_compressed(b"\x1f\x8bnot gzip").unwrap_err();
+    assert_eq!(err.to_string(), "failed to decompress file descriptor set");
+
+    let err = DescriptorPool::decode_compressed(b"\x28\xb5\x2f\xfdnot zstd").unwrap_err();
+    assert_eq!(err.to_string(), "failed to decompress file descriptor set");
+}
+
+#[test]
+fn prune() {
+    let pool = test_file_descriptor();
+    let message = pool.get_message_by_name("test.WellKnownTypes").unwrap();
+
+    let pruned = pool.prune(vec![message.parent_file()]);
+    assert!(pruned.get_message_by_name("test.WellKnownTypes").is_some());
+    assert!(pruned
+        .get_message_by_name("google.protobuf.Timestamp")
+        .is_some());
+    assert!(pruned.get_file_by_name("test2.proto").is_none());
+    assert!(pruned.files().len() < pool.files().len());
+
+    let decoded =
+        DescriptorPool::decode_compressed(&pruned.encode_compressed(Compression::Gzip)).unwrap();
+    assert_eq!(file_names(&decoded), file_names(&pruned));
+}
+
+#[test]
+#[should_panic(expected = "is not from this pool")]
+fn prune_other_pool() {
+    let pool = test_file_descriptor();
+    let other =
+        DescriptorPool::decode(pool.encode_compressed(Compression::None).as_slice()).unwrap();
+    pool.prune(other.files());
+}
+
+#[test]
+fn message_file_descriptor_set() {
+    let pool = test_file_descriptor();
+    let message = pool.get_message_by_name("test.WellKnownTypes").unwrap();
+
+    let file_descriptor_set = message.file_descriptor_set();
+    let names: Vec<_> = file_descriptor_set
+        .file
+        .iter()
+        .map(|file| file.name())
+        .collect();
+    assert_eq!(names.last(), Some(&"test.proto"));
+    assert!(names.contains(&"google/protobuf/timestamp.proto"));
+    assert!(!names.contains(&"test2.proto"));
+
+    // Dependencies are listed before the files which import them, so the set can be loaded in order.
+    let mut loaded = DescriptorPool::new();
+    for file in file_descriptor_set.file {
+        loaded.add_file_descriptor_proto(file).unwrap();
+    }
+    assert!(loaded.get_message_by_name("test.WellKnownTypes").is_some());
+}
+
+#[test]
+fn file_descriptor_set_for_symbols() {
+    let pool = test_file_descriptor();
+
+    let file_descriptor_set = pool
+        .file_descriptor_set_for([
+            "my.package.MyService",
+            "my.package2.in_file",
+            "test.EnumWithAlias",
+            "my.package.MyService",
+        ])
+        .unwrap();
+    let mut names: Vec<_> = file_descriptor_set
+        .file
+        .iter()
+        .map(|file| file.name().to_owned())
+        .collect();
+    names.sort();
+    let mut expected = pool
+        .get_file_by_name("test.proto")
+        .unwrap()
+        .file_descriptor_proto()
+        .dependency
+        .clone();
+    expected.extend(
+        ["desc.proto", "desc2.proto", "test.proto"]
+            .iter()
+            .map(|name| name.to_string()),
+    );
+    expected.sort_unstable();
+    assert_eq!(names, expected);
+
+    assert!(pool
+        .file_descriptor_set_for(Vec::<String>::new())
+        .unwrap()
+        .file
+        .is_empty());
+
+    let err = pool
+        .file_descriptor_set_for(["my.package.Missing"])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the symbol 'my.package.Missing' was not found"
+    );
+}
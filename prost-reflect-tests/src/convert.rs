@@ -0,0 +1,245 @@
+use std::{collections::HashMap, convert::TryFrom};
+
+use proptest::prelude::*;
+use prost::bytes::Bytes;
+use prost_reflect::{DynamicMessage, MapKey, ReflectMessage, Value};
+
+use crate::{arb_message, ComplexType, Scalars};
+
+fn complex_type() -> DynamicMessage {
+    ComplexType {
+        int_map: vec![(
+            1,
+            Scalars {
+                string: "a".to_owned(),
+                ..Default::default()
+            },
+        )]
+        .into_iter()
+        .collect(),
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn to_map() {
+    let map = complex_type().to_map();
+
+    let expected: HashMap<String, Value> = vec![
+        (
+            "int_map".to_owned(),
+            Value::Map(
+                vec![(
+                    MapKey::I32(1),
+                    Value::Map(
+                        vec![(
+                            MapKey::String("string".to_owned()),
+                            Value::String("a".to_owned()),
+                        )]
+                        .into_iter()
+                        .collect(),
+                    ),
+                )]
+                .into_iter()
+                .collect(),
+            ),
+        ),
+        (
+            "nested".to_owned(),
+            Value::Map(
+                vec![(MapKey::String("int32".to_owned()), Value::I32(5))]
+                    .into_iter()
+                    .collect(),
+            ),
+        ),
+        (
+            "my_enum".to_owned(),
+            Value::List(vec![Value::EnumNumber(1), Value::EnumNumber(3)]),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(map, expected);
+}
+
+#[test]
+fn from_map() {
+    let message = complex_type();
+    let decoded = DynamicMessage::from_map(message.descriptor(), message.to_map()).unwrap();
+    assert_eq!(decoded, message);
+
+    // Nested messages may also be given as messages.
+    let mut map = message.to_map();
+    map.insert(
+        "nested".to_owned(),
+        Value::Message(
+            message
+                .get_field_by_name("nested")
+                .unwrap()
+                .as_message()
+                .unwrap()
+                .clone(),
+        ),
+    );
+    let decoded = DynamicMessage::from_map(message.descriptor(), map).unwrap();
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn from_map_errors() {
+    let desc = ComplexType::default().descriptor();
+    let from_map = |entries: Vec<(&str, Value)>| {
+        DynamicMessage::from_map(
+            desc.clone(),
+            entries
+                .into_iter()
+                .map(|(name, value)| (name.to_owned(), value))
+                .collect(),
+        )
+        .unwrap_err()
+        .to_string()
+    };
+
+    assert_eq!(
+        from_map(vec![("foo", Value::I32(1))]),
+        "message 'test.ComplexType' has no field named 'foo'"
+    );
+    assert_eq!(
+        from_map(vec![(
+            "nested",
+            Value::Map(
+                vec![(MapKey::String("int32".to_owned()), Value::Bool(true))]
+                    .into_iter()
+                    .collect()
+            )
+        )]),
+        "invalid value for field 'test.Scalars.int32': expected a 32-bit integer, but got a bool"
+    );
+    assert_eq!(
+        from_map(vec![(
+            "nested",
+            Value::Map(
+                vec![(MapKey::I32(1), Value::Bool(true))]
+                    .into_iter()
+                    .collect()
+            )
+        )]),
+        "invalid value for field 'test.ComplexType.nested': expected a message of type 'test.Scalars', but got a map"
+    );
+}
+
+#[test]
+fn value_from_scalars() {
+    assert_eq!(Value::from(true), Value::Bool(true));
+    assert_eq!(Value::from(-1i32), Value::I32(-1));
+    assert_eq!(Value::from(-1i64), Value::I64(-1));
+    assert_eq!(Value::from(1u32), Value::U32(1));
+    assert_eq!(Value::from(1u64), Value::U64(1));
+    assert_eq!(Value::from(1.5f32), Value::F32(1.5));
+    assert_eq!(Value::from(1.5f64), Value::F64(1.5));
+    assert_eq!(Value::from("abc"), Value::String("abc".to_owned()));
+    assert_eq!(
+        Value::from("abc".to_owned()),
+        Value::String("abc".to_owned())
+    );
+    assert_eq!(
+        Value::from(b"abc".to_vec()),
+        Value::Bytes(Bytes::from_static(b"abc"))
+    );
+    assert_eq!(
+        Value::from(vec![Value::from(1i32)]),
+        Value::List(vec![Value::I32(1)])
+    );
+}
+
+#[test]
+fn value_try_into_scalars() {
+    assert_eq!(bool::try_from(Value::Bool(true)), Ok(true));
+    assert_eq!(i32::try_from(Value::I32(-1)), Ok(-1));
+    assert_eq!(i64::try_from(Value::I64(-1)), Ok(-1));
+    assert_eq!(u32::try_from(Value::U32(1)), Ok(1));
+    assert_eq!(u64::try_from(Value::U64(1)), Ok(1));
+    assert_eq!(f32::try_from(Value::F32(1.5)), Ok(1.5));
+    assert_eq!(f64::try_from(Value::F64(1.5)), Ok(1.5));
+    assert_eq!(
+        String::try_from(Value::String("abc".to_owned())),
+        Ok("abc".to_owned())
+    );
+    assert_eq!(
+        Vec::<u8>::try_from(Value::Bytes(Bytes::from_static(b"abc"))),
+        Ok(b"abc".to_vec())
+    );
+    assert_eq!(
+        Bytes::try_from(Value::Bytes(Bytes::from_static(b"abc"))),
+        Ok(Bytes::from_static(b"abc"))
+    );
+
+    let message = complex_type();
+    assert_eq!(
+        DynamicMessage::try_from(Value::Message(message.clone())),
+        Ok(message)
+    );
+}
+
+#[test]
+fn value_try_into_mismatch() {
+    let err = i64::try_from(Value::I32(5)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a 64-bit integer, but got a 32-bit integer"
+    );
+    assert_eq!(err.value(), &Value::I32(5));
+    assert_eq!(err.into_value(), Value::I32(5));
+
+    let err = String::try_from(Value::Bytes(Bytes::from_static(b"abc"))).unwrap_err();
+    assert_eq!(err.to_string(), "expected a string, but got bytes");
+
+    let err = f64::try_from(Value::EnumNumber(1)).unwrap_err();
+    assert_eq!(err.to_string(), "expected a double, but got an enum number");
+}
+
+#[test]
+fn value_try_into_list_and_map() {
+    assert_eq!(
+        Value::List(vec![Value::I32(1)]).try_into_list(),
+        Ok(vec![Value::I32(1)])
+    );
+    assert_eq!(
+        Value::I32(1).try_into_list().unwrap_err().to_string(),
+        "expected a list, but got a 32-bit integer"
+    );
+
+    let map: HashMap<MapKey, Value> = vec![(MapKey::I32(1), Value::from("a"))]
+        .into_iter()
+        .collect();
+    assert_eq!(Value::from(map.clone()).try_into_map(), Ok(map));
+    assert_eq!(
+        Value::List(vec![]).try_into_map().unwrap_err().to_string(),
+        "expected a map, but got a list"
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn roundtrip_map(message in arb_message("test.ComplexType")) {
+        let decoded = DynamicMessage::from_map(message.descriptor(), message.to_map()).unwrap();
+        prop_assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn roundtrip_map_well_known_types(message in arb_message("test.WellKnownTypes")) {
+        let decoded = DynamicMessage::from_map(message.descriptor(), message.to_map()).unwrap();
+        prop_assert_eq!(decoded, message);
+    }
+}
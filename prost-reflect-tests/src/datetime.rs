@@ -0,0 +1,177 @@
+use std::{
+    convert::TryFrom,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use prost_reflect::{DynamicMessage, MessageDescriptor, Value};
+use time::OffsetDateTime;
+
+use crate::test_file_descriptor;
+
+fn timestamp_desc() -> MessageDescriptor {
+    test_file_descriptor()
+        .get_message_by_name("google.protobuf.Timestamp")
+        .unwrap()
+}
+
+fn duration_desc() -> MessageDescriptor {
+    test_file_descriptor()
+        .get_message_by_name("google.protobuf.Duration")
+        .unwrap()
+}
+
+fn message(desc: MessageDescriptor, seconds: i64, nanos: i32) -> DynamicMessage {
+    let mut message = DynamicMessage::new(desc);
+    message.set_field_by_name("seconds", Value::I64(seconds));
+    message.set_field_by_name("nanos", Value::I32(nanos));
+    message
+}
+
+#[test]
+fn system_time_roundtrip() {
+    let time = UNIX_EPOCH + Duration::new(1_000_000_000, 5);
+    let timestamp = DynamicMessage::from_system_time(timestamp_desc(), time).unwrap();
+    assert_eq!(timestamp, message(timestamp_desc(), 1_000_000_000, 5));
+    assert_eq!(timestamp.to_system_time().unwrap(), time);
+    assert_eq!(
+        SystemTime::try_from(&Value::Message(timestamp)).unwrap(),
+        time
+    );
+}
+
+#[test]
+fn system_time_before_epoch() {
+    let time = UNIX_EPOCH - Duration::new(1, 250_000_000);
+    let timestamp = DynamicMessage::from_system_time(timestamp_desc(), time).unwrap();
+    assert_eq!(timestamp, message(timestamp_desc(), -2, 750_000_000));
+    assert_eq!(timestamp.to_system_time().unwrap(), time);
+}
+
+#[test]
+fn system_time_out_of_range() {
+    let time = UNIX_EPOCH + Duration::from_secs(253_402_300_800);
+    assert_eq!(
+        DynamicMessage::from_system_time(timestamp_desc(), time)
+            .unwrap_err()
+            .to_string(),
+        "timestamp out of range"
+    );
+
+    for (seconds, nanos) in [(-62_135_596_801, 0), (253_402_300_800, 0), (0, -1)] {
+        let timestamp = message(timestamp_desc(), seconds, nanos);
+        assert_eq!(
+            timestamp.to_system_time().unwrap_err().to_string(),
+            "timestamp out of range"
+        );
+    }
+
+    let max = message(timestamp_desc(), 253_402_300_799, 999_999_999);
+    assert!(max.to_system_time().is_ok());
+}
+
+#[test]
+fn std_duration_roundtrip() {
+    let duration = Duration::new(3, 500);
+    let message_value = DynamicMessage::from_std_duration(duration_desc(), duration).unwrap();
+    assert_eq!(message_value, message(duration_desc(), 3, 500));
+    assert_eq!(message_value.to_std_duration().unwrap(), duration);
+    assert_eq!(
+        Duration::try_from(&Value::Message(message_value)).unwrap(),
+        duration
+    );
+}
+
+#[test]
+fn std_duration_errors() {
+    assert_eq!(
+        DynamicMessage::from_std_duration(duration_desc(), Duration::from_secs(315_576_000_001))
+            .unwrap_err()
+            .to_string(),
+        "duration out of range"
+    );
+    assert_eq!(
+        message(duration_desc(), -1, 0)
+            .to_std_duration()
+            .unwrap_err()
+            .to_string(),
+        "duration is negative"
+    );
+    assert_eq!(
+        message(duration_desc(), 1, -1)
+            .to_std_duration()
+            .unwrap_err()
+            .to_string(),
+        "inconsistent signs for duration"
+    );
+    assert_eq!(
+        message(duration_desc(), 0, 1_000_000_000)
+            .to_std_duration()
+            .unwrap_err()
+            .to_string(),
+        "duration out of range"
+    );
+}
+
+#[test]
+fn wrong_message_type() {
+    assert_eq!(
+        DynamicMessage::from_system_time(duration_desc(), UNIX_EPOCH)
+            .unwrap_err()
+            .to_string(),
+        "expected a message of type 'google.protobuf.Timestamp', but got 'google.protobuf.Duration'"
+    );
+    assert_eq!(
+        message(timestamp_desc(), 1, 0)
+            .to_std_duration()
+            .unwrap_err()
+            .to_string(),
+        "expected a message of type 'google.protobuf.Duration', but got 'google.protobuf.Timestamp'"
+    );
+    assert_eq!(
+        SystemTime::try_from(&Value::I64(1))
+            .unwrap_err()
+            .to_string(),
+        "expected a message, but got a 64-bit integer"
+    );
+}
+
+#[test]
+#[cfg(feature = "integrations")]
+fn chrono_roundtrip() {
+    use chrono::{DateTime, Utc};
+
+    let date_time = DateTime::<Utc>::from_timestamp(-1_000, 123_456_789).unwrap();
+    let timestamp = DynamicMessage::from_chrono_date_time(timestamp_desc(), date_time).unwrap();
+    assert_eq!(timestamp, message(timestamp_desc(), -1_000, 123_456_789));
+    assert_eq!(timestamp.to_chrono_date_time().unwrap(), date_time);
+    assert_eq!(
+        DateTime::<Utc>::try_from(&Value::Message(timestamp)).unwrap(),
+        date_time
+    );
+}
+
+#[test]
+#[cfg(feature = "integrations")]
+fn chrono_out_of_range() {
+    use chrono::{DateTime, Utc};
+
+    let date_time = DateTime::<Utc>::from_timestamp(253_402_300_800, 0).unwrap();
+    assert_eq!(
+        DynamicMessage::from_chrono_date_time(timestamp_desc(), date_time)
+            .unwrap_err()
+            .to_string(),
+        "timestamp out of range"
+    );
+}
+
+#[test]
+fn offset_date_time_roundtrip() {
+    let date_time = OffsetDateTime::from_unix_timestamp_nanos(1_500_000_000_000_000_001).unwrap();
+    let timestamp = DynamicMessage::from_offset_date_time(timestamp_desc(), date_time).unwrap();
+    assert_eq!(timestamp, message(timestamp_desc(), 1_500_000_000, 1));
+    assert_eq!(timestamp.to_offset_date_time().unwrap(), date_time);
+    assert_eq!(
+        OffsetDateTime::try_from(&Value::Message(timestamp)).unwrap(),
+        date_time
+    );
+}
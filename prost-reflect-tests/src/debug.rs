@@ -0,0 +1,74 @@
+use prost_reflect::{DynamicMessage, ReflectMessage};
+
+use crate::{ComplexType, Scalars};
+
+#[test]
+fn debug_alternate_uses_names() {
+    let message = ComplexType {
+        string_map: vec![
+            (
+                "b".to_owned(),
+                Scalars {
+                    int32: 2,
+                    ..Default::default()
+                },
+            ),
+            ("a".to_owned(), Scalars::default()),
+        ]
+        .into_iter()
+        .collect(),
+        nested: Some(Scalars {
+            string: "s".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 5],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        format!("{:#?}", message),
+        r#"test.ComplexType {
+    string_map: {
+        "a": test.Scalars,
+        "b": test.Scalars {
+            int32: 2,
+        },
+    },
+    nested: test.Scalars {
+        string: "s",
+    },
+    my_enum: [
+        FOO,
+        5,
+    ],
+}"#
+    );
+}
+
+#[test]
+fn debug_alternate_unknown_fields() {
+    let message = DynamicMessage::decode(
+        Scalars::default().descriptor(),
+        b"\x18\x05\x80\x01\x07".as_ref(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        format!("{:#?}", message),
+        "test.Scalars {\n    int32: 5,\n    16: [\n        Varint(\n            7,\n        ),\n    ],\n}"
+    );
+}
+
+#[test]
+fn debug_default_format_unchanged() {
+    let message = Scalars {
+        int32: 5,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let debug = format!("{:?}", message);
+    assert!(debug.starts_with("DynamicMessage { desc: "), "{}", debug);
+    assert!(debug.contains("fields: "), "{}", debug);
+}
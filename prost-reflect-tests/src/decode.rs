@@ -7,13 +7,19 @@ use std::{
 };
 
 use proptest::{prelude::*, test_runner::TestCaseError};
-use prost::{bytes::Bytes, Message};
-use prost_reflect::{DynamicMessage, MapKey, ReflectMessage, Value};
+use prost::{
+    bytes::{Buf, Bytes},
+    Message,
+};
+use prost_reflect::{
+    ArbitraryOptions, DecodeOptions, DynamicMessage, MapKey, ReflectMessage, Utf8Validation, Value,
+};
 use prost_types::FileDescriptorSet;
 
 use crate::{
-    contains_group, message_with_oneof, test_file_descriptor, ComplexType, ContainsGroup,
-    MessageWithOneof, ScalarArrays, Scalars, WellKnownTypes,
+    arb_message, arb_message_with_options, contains_group, message_with_oneof,
+    test_file_descriptor, ComplexType, ContainsGroup, MessageWithOneof, ScalarArrays, Scalars,
+    WellKnownTypes,
 };
 
 #[test]
@@ -517,6 +523,37 @@ fn set_oneof_to_default() {
     assert!(!dynamic.has_field_by_name("oneof_field_1"));
 }
 
+#[test]
+fn get_field_opt() {
+    let mut dynamic = Scalars {
+        int32: 5,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let desc = dynamic.descriptor();
+    let int32 = desc.get_field_by_name("int32").unwrap();
+    let int64 = desc.get_field_by_name("int64").unwrap();
+
+    assert_eq!(dynamic.get_field_opt(&int32), Some(&Value::I32(5)));
+    assert_eq!(dynamic.get_field_opt(&int64), None);
+
+    dynamic.set_field(&int32, Value::I32(0));
+    assert_eq!(dynamic.get_field_opt(&int32), None);
+
+    let mut dynamic = ComplexType::default().transcode_to_dynamic();
+    let nested = dynamic.descriptor().get_field_by_name("nested").unwrap();
+    assert_eq!(dynamic.get_field_opt(&nested), None);
+
+    dynamic.set_field(
+        &nested,
+        Value::Message(Scalars::default().transcode_to_dynamic()),
+    );
+    assert_eq!(
+        dynamic.get_field_opt(&nested),
+        Some(&Value::Message(Scalars::default().transcode_to_dynamic()))
+    );
+}
+
 #[test]
 fn roundtrip_scalars() {
     roundtrip(&Scalars {
@@ -700,6 +737,89 @@ proptest! {
     }
 }
 
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn roundtrip_generated_complex_type(message in arb_message("test.ComplexType")) {
+        roundtrip_dynamic(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_generated_well_known_types(message in arb_message("test.WellKnownTypes")) {
+        roundtrip_dynamic(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_generated_oneof(message in arb_message("test.MessageWithOneof")) {
+        let oneof_fields = message.descriptor().fields().filter(|f| message.has_field(f)).count();
+        prop_assert!(oneof_fields <= 1);
+        roundtrip_dynamic(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_generated_proto2(message in arb_message("test2.DefaultValues")) {
+        roundtrip_dynamic(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_generated_group(message in arb_message("test2.ContainsGroup")) {
+        roundtrip_dynamic(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_generated_unknown_enum_values(
+        message in arb_message_with_options("test.ComplexType", &ArbitraryOptions::new().unknown_enum_values(true))
+    ) {
+        roundtrip_dynamic(&message)?;
+    }
+
+    #[test]
+    fn decode_into_generated_complex_type(
+        first in arb_message("test.ComplexType"),
+        second in arb_message("test.ComplexType"),
+    ) {
+        decode_into_dynamic(&first, &second)?;
+    }
+
+    #[test]
+    fn decode_into_generated_proto2(
+        first in arb_message("test2.DefaultValues"),
+        second in arb_message("test2.DefaultValues"),
+    ) {
+        decode_into_dynamic(&first, &second)?;
+    }
+
+    #[test]
+    fn generated_strings_match_pattern(
+        message in arb_message_with_options("test.Scalars", &ArbitraryOptions::new().string_pattern("[a-c]{0,3}"))
+    ) {
+        let value = message.get_field_by_name("string").unwrap();
+        let string = value.as_str().unwrap();
+        prop_assert!(string.len() <= 3);
+        prop_assert!(string.chars().all(|c| ('a'..='c').contains(&c)));
+    }
+
+    #[test]
+    fn generated_messages_respect_max_depth(
+        message in arb_message_with_options("test.ComplexType", &ArbitraryOptions::new().max_depth(0))
+    ) {
+        prop_assert!(!message.has_field_by_name("nested"));
+        prop_assert!(!message.has_field_by_name("string_map"));
+        prop_assert!(!message.has_field_by_name("int_map"));
+    }
+
+    #[test]
+    fn generated_messages_set_required_fields_beyond_max_depth(
+        message in arb_message_with_options("test2.RequiredNested", &ArbitraryOptions::new().max_depth(1))
+    ) {
+        prop_assert!(message.is_initialized(), "{:?}", message);
+    }
+}
+
 #[test]
 fn unpacked_fields_accept_packed_bytes() {
     let desc = test_file_descriptor()
@@ -878,3 +998,191 @@ where
 
     Ok(())
 }
+
+fn decode_into_dynamic(
+    first: &DynamicMessage,
+    second: &DynamicMessage,
+) -> Result<(), TestCaseError> {
+    let mut message = DynamicMessage::new(first.descriptor());
+    message
+        .decode_into(first.encode_to_vec().as_slice())
+        .unwrap();
+    prop_assert_eq!(&message, first);
+    message
+        .decode_into(second.encode_to_vec().as_slice())
+        .unwrap();
+    prop_assert_eq!(&message, second);
+    prop_assert_eq!(message.encoded_len(), second.encoded_len());
+    for field in message.descriptor().fields() {
+        prop_assert_eq!(message.has_field(&field), second.has_field(&field));
+        prop_assert_eq!(message.get_field(&field), second.get_field(&field));
+    }
+    Ok(())
+}
+
+fn roundtrip_dynamic(message: &DynamicMessage) -> Result<(), TestCaseError> {
+    let bytes = message.encode_to_vec();
+    let decoded = DynamicMessage::decode(message.descriptor(), bytes.as_slice()).unwrap();
+    prop_assert_eq!(message, &decoded);
+    Ok(())
+}
+
+#[test]
+fn decode_into_reuses_message() {
+    let first = ComplexType {
+        nested: Some(Scalars {
+            string: "first string".to_owned(),
+            int32: 1,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    };
+    let second = ComplexType {
+        nested: Some(Scalars {
+            string: "second".to_owned(),
+            ..Default::default()
+        }),
+        optional_enum: 3,
+        ..Default::default()
+    };
+
+    let mut message = ComplexType::default().transcode_to_dynamic();
+    message
+        .decode_into(first.encode_to_vec().as_slice())
+        .unwrap();
+    let shared = message.clone();
+    let string_ptr = |message: &DynamicMessage| {
+        message
+            .get_field_by_name("nested")
+            .unwrap()
+            .as_message()
+            .unwrap()
+            .get_field_by_name("string")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .as_ptr()
+    };
+
+    // The message is shared with a clone, so its values are not reused.
+    message
+        .decode_into(second.encode_to_vec().as_slice())
+        .unwrap();
+    assert_eq!(message.transcode_to::<ComplexType>().unwrap(), second);
+    assert_eq!(shared.transcode_to::<ComplexType>().unwrap(), first);
+    drop(shared);
+
+    message
+        .decode_into(first.encode_to_vec().as_slice())
+        .unwrap();
+    let reused_ptr = string_ptr(&message);
+    message
+        .decode_into(second.encode_to_vec().as_slice())
+        .unwrap();
+    assert_eq!(message.transcode_to::<ComplexType>().unwrap(), second);
+    assert_eq!(message, second.transcode_to_dynamic());
+    assert!(!message.has_field_by_name("my_enum"));
+    assert_eq!(string_ptr(&message), reused_ptr);
+
+    // Unknown fields are discarded.
+    let mut empty = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+    );
+    empty.decode_into(first.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(empty.unknown_fields().count(), 2);
+    empty.decode_into(&[][..]).unwrap();
+    assert_eq!(empty.unknown_fields().count(), 0);
+    assert_eq!(empty.encoded_len(), 0);
+}
+
+#[test]
+fn decode_bytes_without_copying() {
+    let message = ComplexType {
+        nested: Some(Scalars {
+            bytes: vec![7; 1000],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let buf = Bytes::from(message.encode_to_vec());
+    let buf_range = buf.as_ptr_range();
+
+    let decoded = DynamicMessage::decode(message.descriptor(), buf.clone()).unwrap();
+    let nested = decoded.get_field_by_name("nested").unwrap();
+    let bytes = nested
+        .as_message()
+        .unwrap()
+        .get_field_by_name("bytes")
+        .unwrap();
+    let bytes = bytes.as_bytes().unwrap();
+    assert_eq!(bytes.as_ref(), [7; 1000].as_ref());
+    assert!(buf_range.contains(&bytes.as_ptr()));
+
+    let empty = test_file_descriptor()
+        .get_message_by_name("google.protobuf.Empty")
+        .unwrap();
+    let decoded = DynamicMessage::decode(empty, buf.clone()).unwrap();
+    let unknown = decoded.get_unknown_fields(3).next().unwrap();
+    assert!(buf_range.contains(&unknown.value().as_ptr()));
+    assert_eq!(decoded.encode_to_vec(), buf);
+}
+
+#[test]
+fn decode_string_from_bytes() {
+    let message = Scalars {
+        string: "hello ☃".to_owned(),
+        ..Default::default()
+    };
+    let buf = Bytes::from(message.encode_to_vec());
+    let decoded = DynamicMessage::decode(message.descriptor(), buf).unwrap();
+    assert_eq!(
+        decoded.get_field_by_name("string").unwrap().as_str(),
+        Some("hello ☃")
+    );
+
+    let invalid = Bytes::from_static(b"\x72\x02\xc0\x80");
+    let err = DynamicMessage::decode(message.descriptor(), invalid.clone()).unwrap_err();
+    assert!(err.to_string().contains("data is not UTF-8 encoded"));
+
+    let decoded = DynamicMessage::decode_with_options(
+        message.descriptor(),
+        invalid,
+        &DecodeOptions::new().utf8_validation(Utf8Validation::Replace),
+    )
+    .unwrap();
+    assert_eq!(
+        decoded.get_field_by_name("string").unwrap().as_str(),
+        Some("\u{FFFD}\u{FFFD}")
+    );
+}
+
+#[test]
+fn decode_string_from_non_contiguous_buffer() {
+    let desc = Scalars::default().descriptor();
+
+    let buf = b"\x72\x09hel".as_ref().chain(b"lo \xe2\x98\x83".as_ref());
+    let decoded = DynamicMessage::decode(desc.clone(), buf).unwrap();
+    assert_eq!(
+        decoded.get_field_by_name("string").unwrap().as_str(),
+        Some("hello ☃")
+    );
+
+    let invalid = b"\x72\x02\xc0".as_ref().chain(b"\x80".as_ref());
+    let err = DynamicMessage::decode(desc.clone(), invalid).unwrap_err();
+    assert!(err.to_string().contains("data is not UTF-8 encoded"));
+
+    let invalid = b"\x72\x02\xc0".as_ref().chain(b"\x80".as_ref());
+    let decoded = DynamicMessage::decode_with_options(
+        desc,
+        invalid,
+        &DecodeOptions::new().utf8_validation(Utf8Validation::Replace),
+    )
+    .unwrap();
+    assert_eq!(
+        decoded.get_field_by_name("string").unwrap().as_str(),
+        Some("\u{FFFD}\u{FFFD}")
+    );
+}
@@ -0,0 +1,118 @@
+use std::io;
+
+use prost::Message;
+use prost_reflect::{DynamicMessage, ReflectMessage};
+
+use crate::{contains_group, ComplexType, ContainsGroup, ScalarArrays, Scalars};
+
+fn decode_async<T>(bytes: &[u8]) -> io::Result<DynamicMessage>
+where
+    T: ReflectMessage + Default,
+{
+    futures_executor::block_on(DynamicMessage::decode_async(
+        T::default().descriptor(),
+        bytes,
+    ))
+}
+
+#[test]
+fn decode_async_scalars() {
+    let message = Scalars {
+        double: 1.1,
+        float: 2.2,
+        int32: 3,
+        int64: -4,
+        fixed32: 9,
+        fixed64: 10,
+        bool: true,
+        string: "hello".to_owned(),
+        bytes: b"world".to_vec(),
+        ..Default::default()
+    };
+
+    let dynamic = decode_async::<Scalars>(&message.encode_to_vec()).unwrap();
+    assert_eq!(dynamic.transcode_to::<Scalars>().unwrap(), message);
+}
+
+#[test]
+fn decode_async_merges_repeated_fields() {
+    let message = ScalarArrays {
+        int32: vec![1, 2, 3],
+        string: vec!["a".to_owned(), "b".to_owned()],
+        ..Default::default()
+    };
+    let mut bytes = message.encode_to_vec();
+    bytes.extend(message.encode_to_vec());
+
+    let dynamic = decode_async::<ScalarArrays>(&bytes).unwrap();
+    assert_eq!(
+        dynamic.transcode_to::<ScalarArrays>().unwrap(),
+        ScalarArrays::decode(bytes.as_slice()).unwrap()
+    );
+}
+
+#[test]
+fn decode_async_nested_message() {
+    let message = ComplexType {
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    };
+
+    let dynamic = decode_async::<ComplexType>(&message.encode_to_vec()).unwrap();
+    assert_eq!(dynamic.transcode_to::<ComplexType>().unwrap(), message);
+}
+
+#[test]
+fn decode_async_group() {
+    let message = ContainsGroup {
+        groupmessage: vec![
+            contains_group::GroupMessage::default(),
+            contains_group::GroupMessage {
+                url: "hello".to_string(),
+                id: Some(10),
+            },
+        ],
+    };
+
+    let dynamic = decode_async::<ContainsGroup>(&message.encode_to_vec()).unwrap();
+    assert_eq!(dynamic.transcode_to::<ContainsGroup>().unwrap(), message);
+}
+
+#[test]
+fn decode_async_empty() {
+    let dynamic = decode_async::<Scalars>(&[]).unwrap();
+    assert_eq!(
+        dynamic,
+        DynamicMessage::new(Scalars::default().descriptor())
+    );
+}
+
+#[test]
+fn decode_async_truncated() {
+    let message = Scalars {
+        string: "hello".to_owned(),
+        ..Default::default()
+    };
+    let mut bytes = message.encode_to_vec();
+    bytes.pop();
+
+    let err = decode_async::<Scalars>(&bytes).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn decode_async_unterminated_group() {
+    // A start group tag for field 1 with no matching end group tag.
+    let err = decode_async::<ContainsGroup>(&[0x0b]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn decode_async_invalid_wire_type() {
+    let err = decode_async::<Scalars>(&[0x0f]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,184 @@
+use prost::Message;
+use prost_reflect::{DecodeOptions, DynamicMessage, ReflectMessage, Utf8Validation, Value};
+
+use crate::{test_file_descriptor, ComplexType, ScalarArrays, Scalars};
+
+const INVALID_STRING: &[u8] = b"\x72\x03a\xffb";
+
+fn decode_with_utf8_validation<T>(
+    bytes: &[u8],
+    utf8_validation: Utf8Validation,
+) -> Result<DynamicMessage, prost::DecodeError>
+where
+    T: ReflectMessage + Default,
+{
+    DynamicMessage::decode_with_options(
+        T::default().descriptor(),
+        bytes,
+        &DecodeOptions::new().utf8_validation(utf8_validation),
+    )
+}
+
+#[test]
+fn proto3_invalid_utf8_is_error_by_default() {
+    let err = DynamicMessage::decode(Scalars::default().descriptor(), INVALID_STRING).unwrap_err();
+    assert!(err.to_string().contains("UTF-8"));
+
+    let err =
+        decode_with_utf8_validation::<Scalars>(INVALID_STRING, Utf8Validation::Strict).unwrap_err();
+    assert!(err.to_string().contains("UTF-8"));
+}
+
+#[test]
+fn invalid_utf8_replace() {
+    let message =
+        decode_with_utf8_validation::<Scalars>(INVALID_STRING, Utf8Validation::Replace).unwrap();
+    assert_eq!(
+        message.get_field_by_name("string").unwrap().as_str(),
+        Some("a\u{fffd}b")
+    );
+    assert_eq!(message.unknown_fields().count(), 0);
+}
+
+#[test]
+fn invalid_utf8_replace_nested() {
+    let mut bytes = vec![0x1a, INVALID_STRING.len() as u8];
+    bytes.extend_from_slice(INVALID_STRING);
+
+    let message =
+        decode_with_utf8_validation::<ComplexType>(&bytes, Utf8Validation::Replace).unwrap();
+    let nested = message.get_field_by_name("nested").unwrap();
+    assert_eq!(
+        nested
+            .as_message()
+            .unwrap()
+            .get_field_by_name("string")
+            .unwrap()
+            .as_str(),
+        Some("a\u{fffd}b")
+    );
+}
+
+#[test]
+fn invalid_utf8_preserve_unknown() {
+    let message =
+        decode_with_utf8_validation::<Scalars>(INVALID_STRING, Utf8Validation::PreserveUnknown)
+            .unwrap();
+    assert!(!message.has_field_by_name("string"));
+    assert_eq!(message.unknown_fields().count(), 1);
+    assert_eq!(message.encode_to_vec(), INVALID_STRING);
+}
+
+#[test]
+fn valid_utf8_preserve_unknown() {
+    let message = Scalars {
+        string: "hello".to_owned(),
+        ..Default::default()
+    };
+    let dynamic = decode_with_utf8_validation::<Scalars>(
+        &message.encode_to_vec(),
+        Utf8Validation::PreserveUnknown,
+    )
+    .unwrap();
+    assert_eq!(dynamic.transcode_to::<Scalars>().unwrap(), message);
+    assert_eq!(dynamic.unknown_fields().count(), 0);
+}
+
+#[test]
+fn invalid_utf8_preserve_unknown_repeated() {
+    let mut bytes = ScalarArrays {
+        string: vec!["a".to_owned()],
+        ..Default::default()
+    }
+    .encode_to_vec();
+    bytes.extend_from_slice(INVALID_STRING);
+    bytes.extend(
+        ScalarArrays {
+            string: vec!["b".to_owned()],
+            ..Default::default()
+        }
+        .encode_to_vec(),
+    );
+
+    let message =
+        decode_with_utf8_validation::<ScalarArrays>(&bytes, Utf8Validation::PreserveUnknown)
+            .unwrap();
+    assert!(!message.has_field_by_name("string"));
+    assert_eq!(message.unknown_fields().count(), 3);
+    assert_eq!(message.encode_to_vec(), bytes);
+}
+
+#[test]
+fn invalid_utf8_preserve_unknown_map_key() {
+    // An entry of `string_map` with an invalid key and an empty value.
+    let bytes = b"\x0a\x07\x0a\x03a\xffb\x12\x00";
+
+    let message =
+        decode_with_utf8_validation::<ComplexType>(bytes, Utf8Validation::PreserveUnknown).unwrap();
+    assert!(!message.has_field_by_name("string_map"));
+    assert_eq!(message.encode_to_vec(), bytes);
+
+    let message =
+        decode_with_utf8_validation::<ComplexType>(bytes, Utf8Validation::Replace).unwrap();
+    let map = message.get_field_by_name("string_map").unwrap();
+    assert!(map
+        .as_map()
+        .unwrap()
+        .contains_key(&prost_reflect::MapKey::String("a\u{fffd}b".to_owned())));
+}
+
+#[test]
+fn proto2_invalid_utf8_is_preserved_by_default() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.DefaultValues")
+        .unwrap();
+    let mut bytes = b"\x72\x02ok".to_vec();
+    bytes.extend_from_slice(INVALID_STRING);
+
+    let message = DynamicMessage::decode(desc.clone(), bytes.as_slice()).unwrap();
+    assert!(!message.has_field_by_name("string"));
+    assert_eq!(
+        message.get_field_by_name("string").unwrap().as_ref(),
+        &Value::String("hello".to_owned())
+    );
+    assert_eq!(message.encode_to_vec(), bytes);
+
+    let err = DynamicMessage::decode_with_options(
+        desc,
+        bytes.as_slice(),
+        &DecodeOptions::new().utf8_validation(Utf8Validation::Strict),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("UTF-8"));
+}
+
+#[test]
+fn proto2_merge_preserved_string() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.DefaultValues")
+        .unwrap();
+    let mut message = DynamicMessage::new(desc.clone());
+    message.set_field_by_name("string", Value::String("ok".to_owned()));
+    let invalid = DynamicMessage::decode(desc.clone(), INVALID_STRING).unwrap();
+
+    let mut merged = message.clone();
+    merged.merge_from(&invalid);
+    assert!(!merged.has_field_by_name("string"));
+    assert_eq!(merged.get_unknown_fields(14).count(), 2);
+
+    let mut expected = message.encode_to_vec();
+    expected.extend_from_slice(INVALID_STRING);
+    assert_eq!(merged.encode_to_vec(), expected);
+
+    let mut merged = invalid.clone();
+    merged.merge_from(&message);
+    assert!(!merged.has_field_by_name("string"));
+
+    let mut expected = INVALID_STRING.to_vec();
+    expected.extend_from_slice(&message.encode_to_vec());
+    assert_eq!(merged.encode_to_vec(), expected);
+    assert_eq!(
+        merged,
+        DynamicMessage::decode(desc, expected.as_slice()).unwrap()
+    );
+}
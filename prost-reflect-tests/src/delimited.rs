@@ -0,0 +1,91 @@
+use std::io;
+
+use prost::Message;
+use prost_reflect::{DynamicMessage, ReflectMessage};
+
+use crate::{Point, Scalars};
+
+fn points() -> Vec<Point> {
+    (0..3)
+        .map(|i| Point {
+            latitude: i,
+            longitude: -i,
+        })
+        .collect()
+}
+
+#[test]
+fn encode_length_delimited_to_writer() {
+    let mut buf = Vec::new();
+    for point in points() {
+        point
+            .transcode_to_dynamic()
+            .encode_length_delimited_to_writer(&mut buf)
+            .unwrap();
+    }
+
+    let expected: Vec<u8> = points()
+        .iter()
+        .flat_map(|point| point.encode_length_delimited_to_vec())
+        .collect();
+    assert_eq!(buf, expected);
+}
+
+#[test]
+fn read_length_delimited() {
+    let buf: Vec<u8> = points()
+        .iter()
+        .flat_map(|point| point.encode_length_delimited_to_vec())
+        .collect();
+
+    let messages =
+        DynamicMessage::read_length_delimited(Point::default().descriptor(), buf.as_slice())
+            .map(|message| message.unwrap().transcode_to::<Point>().unwrap())
+            .collect::<Vec<_>>();
+    assert_eq!(messages, points());
+}
+
+#[test]
+fn read_length_delimited_empty() {
+    let mut messages =
+        DynamicMessage::read_length_delimited(Point::default().descriptor(), &[][..]);
+    assert!(messages.next().is_none());
+}
+
+#[test]
+fn read_length_delimited_truncated_message() {
+    let mut buf = Point::default().encode_length_delimited_to_vec();
+    buf.extend(
+        Point {
+            latitude: 1,
+            longitude: 2,
+        }
+        .encode_length_delimited_to_vec(),
+    );
+    buf.pop();
+
+    let mut messages =
+        DynamicMessage::read_length_delimited(Point::default().descriptor(), buf.as_slice());
+    assert!(messages.next().unwrap().is_ok());
+    let err = messages.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    assert!(messages.next().is_none());
+}
+
+#[test]
+fn read_length_delimited_invalid_message() {
+    let scalars = Scalars {
+        string: "hello".to_owned(),
+        ..Default::default()
+    };
+    // Shorten the message by one byte, so the string field overruns the end of the message.
+    let mut buf = scalars.encode_length_delimited_to_vec();
+    buf[0] -= 1;
+    buf.pop();
+
+    let mut messages =
+        DynamicMessage::read_length_delimited(Scalars::default().descriptor(), buf.as_slice());
+    let err = messages.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(messages.next().is_none());
+}
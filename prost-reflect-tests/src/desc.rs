@@ -1,4 +1,4 @@
-use prost_reflect::{DescriptorPool, Syntax};
+use prost_reflect::{DescriptorPool, FieldBuilder, FileDescriptorBuilder, MessageBuilder, Syntax};
 
 use crate::test_file_descriptor;
 
@@ -359,6 +359,165 @@ fn descriptor_pool_add_individual_files() {
     assert_eq!(message_desc.package_name(), "my.package");
 }
 
+#[test]
+fn descriptor_pool_get_by_name() {
+    let pool = test_file_descriptor();
+
+    let service = pool.get_service_by_name("my.package.MyService").unwrap();
+    assert_eq!(service.full_name(), "my.package.MyService");
+    assert_eq!(
+        pool.get_service_by_name(".my.package.MyService"),
+        Some(service)
+    );
+    assert!(pool.get_service_by_name("my.package.MyMessage").is_none());
+    assert!(pool.get_service_by_name("my.MyService").is_none());
+
+    for name in [
+        "my.package2.MyMessage.in_extendee",
+        "my.package2.in_file",
+        "my.package2.OtherMessage.in_other",
+    ] {
+        let extension = pool.get_extension_by_name(name).unwrap();
+        assert_eq!(extension.full_name(), name);
+        assert_eq!(
+            extension.containing_message().full_name(),
+            "my.package2.MyMessage"
+        );
+    }
+    assert!(pool.get_extension_by_name(".my.package2.in_file").is_some());
+    assert!(pool
+        .get_extension_by_name("my.package2.MyMessage")
+        .is_none());
+    assert!(pool.get_extension_by_name("in_file").is_none());
+
+    // Every definition can be found by its name.
+    for message in pool.all_messages() {
+        assert_eq!(pool.get_message_by_name(message.full_name()), Some(message));
+    }
+    for enum_desc in pool.all_enums() {
+        assert_eq!(
+            pool.get_enum_by_name(enum_desc.full_name()),
+            Some(enum_desc)
+        );
+    }
+    for extension in pool.all_extensions() {
+        assert_eq!(
+            pool.get_extension_by_name(extension.full_name()),
+            Some(extension)
+        );
+    }
+    for service in pool.services() {
+        assert_eq!(pool.get_service_by_name(service.full_name()), Some(service));
+    }
+}
+
+#[test]
+fn descriptor_pool_get_by_name_after_remove_file() {
+    let mut pool = test_file_descriptor();
+    pool.remove_file("desc.proto").unwrap();
+    assert!(pool.get_service_by_name("my.package.MyService").is_none());
+    assert!(pool.get_extension_by_name("my.package2.in_file").is_some());
+
+    pool.remove_file("desc2.proto").unwrap();
+    assert!(pool.get_extension_by_name("my.package2.in_file").is_none());
+}
+
+fn editable_pool() -> DescriptorPool {
+    let mut pool = DescriptorPool::new();
+    FileDescriptorBuilder::new("common.proto")
+        .add_message(MessageBuilder::new("Id").add_field(FieldBuilder::new("value", 1, "string")))
+        .add_to_pool(&mut pool)
+        .unwrap();
+    FileDescriptorBuilder::new("user.proto")
+        .add_dependency("common.proto")
+        .add_message(MessageBuilder::new("User").add_field(FieldBuilder::new("id", 1, "Id")))
+        .add_to_pool(&mut pool)
+        .unwrap();
+    pool
+}
+
+#[test]
+fn descriptor_pool_remove_file() {
+    let mut pool = editable_pool();
+    let user = pool.get_message_by_name("User").unwrap();
+
+    let err = pool.remove_file("common.proto").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the file 'common.proto' cannot be removed because it is imported by 'user.proto'"
+    );
+    let err = pool.remove_file("missing.proto").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the file 'missing.proto' is not in the pool"
+    );
+    assert_eq!(pool.files().len(), 2);
+
+    pool.remove_file("user.proto").unwrap();
+    assert_eq!(pool.files().len(), 1);
+    assert!(pool.get_file_by_name("user.proto").is_none());
+    assert!(pool.get_message_by_name("User").is_none());
+    assert!(pool.get_message_by_name("Id").is_some());
+
+    // Existing descriptors still refer to the old pool.
+    assert_eq!(user.full_name(), "User");
+    assert!(user.parent_pool().get_message_by_name("User").is_some());
+
+    pool.remove_file("common.proto").unwrap();
+    assert_eq!(pool.files().len(), 0);
+}
+
+#[test]
+fn descriptor_pool_replace_file() {
+    let mut pool = editable_pool();
+    let old_id = pool.get_message_by_name("Id").unwrap();
+
+    let mut common = pool
+        .get_file_by_name("common.proto")
+        .unwrap()
+        .file_descriptor_proto()
+        .clone();
+    common.message_type[0].field[0].name = Some("key".to_owned());
+    pool.replace_file(common.clone()).unwrap();
+
+    let id = pool.get_message_by_name("Id").unwrap();
+    assert!(id.get_field_by_name("key").is_some());
+    assert!(old_id.get_field_by_name("value").is_some());
+    assert_eq!(
+        pool.get_message_by_name("User")
+            .unwrap()
+            .get_field_by_name("id")
+            .unwrap()
+            .kind()
+            .as_message(),
+        Some(&id)
+    );
+    assert_eq!(
+        pool.files()
+            .map(|file| file.name().to_owned())
+            .collect::<Vec<_>>(),
+        ["common.proto", "user.proto"]
+    );
+
+    // Replacing with an identical file is a no-op.
+    let unchanged = pool.clone();
+    pool.replace_file(common.clone()).unwrap();
+    assert_eq!(pool, unchanged);
+
+    // Removing a type used by another file fails and leaves the pool unchanged.
+    common.message_type[0].name = Some("Identifier".to_owned());
+    let err = pool.replace_file(common.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the message or enum type 'Id' was not found"
+    );
+    assert_eq!(pool, unchanged);
+
+    common.name = Some("other.proto".to_owned());
+    let err = pool.replace_file(common).unwrap_err();
+    assert_eq!(err.to_string(), "the file 'other.proto' is not in the pool");
+}
+
 #[test]
 fn test_enum_alias() {
     let enum_desc = test_file_descriptor()
@@ -390,3 +549,64 @@ fn test_enum_alias() {
     assert_eq!(enum_desc.get_value(2).unwrap().name(), "TWO");
     assert_eq!(enum_desc.get_value(3), None);
 }
+
+#[test]
+fn source_code_info() {
+    let pool = test_file_descriptor();
+    let message = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    assert_eq!(
+        message.leading_comments(),
+        Some(" A message with a oneof.\n")
+    );
+    assert_eq!(message.trailing_comments(), None);
+    assert_eq!(message.location().unwrap().span, vec![5, 0, 18, 1]);
+
+    let field = message.get_field_by_name("my_field").unwrap();
+    assert_eq!(field.leading_comments(), Some(" The only field.\n"));
+    assert_eq!(field.trailing_comments(), Some(" Trailing comment.\n"));
+    assert_eq!(field.location().unwrap().span, vec![8, 4, 23]);
+
+    let oneof = message.oneofs().next().unwrap();
+    assert_eq!(oneof.location().unwrap().span, vec![6, 2, 9, 3]);
+    assert_eq!(oneof.leading_comments(), None);
+
+    let nested_enum = pool
+        .get_enum_by_name("my.package.MyMessage.MyNestedEnum")
+        .unwrap();
+    assert_eq!(nested_enum.location().unwrap().span, vec![15, 2, 17, 3]);
+    assert_eq!(
+        nested_enum
+            .values()
+            .next()
+            .unwrap()
+            .location()
+            .unwrap()
+            .span,
+        vec![16, 4, 17]
+    );
+
+    let method = pool.services().next().unwrap().methods().next().unwrap();
+    assert_eq!(method.parent_service().name(), "MyService");
+    assert_eq!(method.leading_comments(), Some(" Block comment. "));
+
+    let extension = pool
+        .all_extensions()
+        .find(|ext| ext.full_name() == "my.package2.in_file")
+        .unwrap();
+    assert!(extension.location().is_some());
+
+    // Files without source code info have no locations.
+    let mut file = pool
+        .get_file_by_name("desc.proto")
+        .unwrap()
+        .file_descriptor_proto()
+        .clone();
+    file.source_code_info = None;
+    let pool = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+        file: vec![file],
+    })
+    .unwrap();
+    let message = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    assert_eq!(message.location(), None);
+    assert_eq!(message.leading_comments(), None);
+}
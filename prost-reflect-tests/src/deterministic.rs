@@ -0,0 +1,108 @@
+use proptest::prelude::*;
+use prost::Message;
+use prost_reflect::{DynamicMessage, MapKey, ReflectMessage, Value};
+
+use crate::{test_file_descriptor, ComplexType, Scalars};
+
+fn map_message(keys: &[i32]) -> DynamicMessage {
+    let mut message = ComplexType::default().transcode_to_dynamic();
+    let field = message.descriptor().get_field_by_name("int_map").unwrap();
+    for &key in keys {
+        let mut nested = Scalars {
+            int32: key,
+            ..Default::default()
+        }
+        .transcode_to_dynamic();
+        nested
+            .try_set_field_by_name("string", Value::String(format!("value {}", key)))
+            .unwrap();
+        message
+            .insert_map_value(&field, MapKey::I32(key), Value::Message(nested))
+            .unwrap();
+    }
+    message
+}
+
+#[test]
+fn encode_deterministic_sorts_map_entries() {
+    let message = map_message(&[5, -1, 3, 100, 0]);
+    let bytes = message.encode_deterministic();
+
+    assert_eq!(bytes.len(), message.encoded_len());
+    assert_eq!(
+        DynamicMessage::decode(message.descriptor(), bytes.as_slice()).unwrap(),
+        message
+    );
+
+    // Decode the map entries as unknown fields to check their order.
+    let empty = DynamicMessage::decode(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+        bytes.as_slice(),
+    )
+    .unwrap();
+    let keys: Vec<i32> = empty
+        .get_unknown_fields(2)
+        .map(|field| {
+            let mut value = field.value().as_ref();
+            // The key field is omitted when it has the default value.
+            if value[0] == 0x08 {
+                value = &value[1..];
+                prost::encoding::decode_varint(&mut value).unwrap() as i32
+            } else {
+                0
+            }
+        })
+        .collect();
+    assert_eq!(keys, vec![-1, 0, 3, 5, 100]);
+}
+
+#[test]
+fn encode_deterministic_string_keys() {
+    let mut outer = ComplexType::default().transcode_to_dynamic();
+    let field = outer.descriptor().get_field_by_name("string_map").unwrap();
+    for key in ["c", "a", "b"] {
+        outer
+            .insert_map_value(
+                &field,
+                MapKey::String(key.to_owned()),
+                Value::Message(Scalars::default().transcode_to_dynamic()),
+            )
+            .unwrap();
+    }
+
+    let mut reordered = ComplexType::default().transcode_to_dynamic();
+    for key in ["b", "c", "a"] {
+        reordered
+            .insert_map_value(
+                &field,
+                MapKey::String(key.to_owned()),
+                Value::Message(Scalars::default().transcode_to_dynamic()),
+            )
+            .unwrap();
+    }
+
+    assert_eq!(
+        outer.encode_deterministic(),
+        reordered.encode_deterministic()
+    );
+}
+
+proptest! {
+    #[test]
+    fn encode_deterministic_is_stable(keys in prop::collection::vec(any::<i32>(), 0..20)) {
+        let message = map_message(&keys);
+
+        let mut reversed_keys = keys.clone();
+        reversed_keys.reverse();
+        let reversed = map_message(&reversed_keys);
+
+        let bytes = message.encode_deterministic();
+        prop_assert_eq!(&bytes, &reversed.encode_deterministic());
+        prop_assert_eq!(
+            DynamicMessage::decode(message.descriptor(), bytes.as_slice()).unwrap(),
+            message
+        );
+    }
+}
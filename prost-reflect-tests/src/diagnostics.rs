@@ -0,0 +1,194 @@
+use prost_reflect::DescriptorPool;
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    source_code_info::Location,
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    MethodDescriptorProto, ServiceDescriptorProto, SourceCodeInfo,
+};
+
+fn field(name: &str, number: i32, type_name: &str) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(Type::Message as i32),
+        type_name: Some(type_name.to_owned()),
+        ..Default::default()
+    }
+}
+
+fn invalid_file() -> FileDescriptorProto {
+    FileDescriptorProto {
+        name: Some("invalid.proto".to_owned()),
+        package: Some("my.package".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        message_type: vec![
+            DescriptorProto {
+                name: Some("Message".to_owned()),
+                field: vec![
+                    field("a", 1, ".my.package.MissingA"),
+                    field("b", 2, ".my.package.Message"),
+                    field("c", 3, ".my.package.MissingC"),
+                ],
+                ..Default::default()
+            },
+            DescriptorProto {
+                name: Some("Message".to_owned()),
+                ..Default::default()
+            },
+        ],
+        enum_type: vec![EnumDescriptorProto {
+            name: Some("Empty".to_owned()),
+            ..Default::default()
+        }],
+        service: vec![ServiceDescriptorProto {
+            name: Some("Service".to_owned()),
+            method: vec![MethodDescriptorProto {
+                name: Some("Method".to_owned()),
+                input_type: Some(".my.package.Empty".to_owned()),
+                output_type: Some(".my.package.Message".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        source_code_info: Some(SourceCodeInfo {
+            location: vec![Location {
+                path: vec![4, 0, 2, 2],
+                span: vec![5, 2, 30],
+                ..Default::default()
+            }],
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn all_errors_reported() {
+    let err = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+        file: vec![invalid_file()],
+    })
+    .unwrap_err();
+
+    let diagnostics: Vec<_> = err
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| (diagnostic.file(), diagnostic.symbol(), diagnostic.message()))
+        .collect();
+    assert_eq!(
+        diagnostics,
+        [
+            (
+                Some("invalid.proto"),
+                Some("my.package.Message"),
+                "the message or enum type 'my.package.Message' is defined multiple times"
+                    .to_owned()
+            ),
+            (
+                Some("invalid.proto"),
+                Some("my.package.Empty"),
+                "enums must have at least one value".to_owned()
+            ),
+            (
+                Some("invalid.proto"),
+                Some("my.package.Message.a"),
+                "the message or enum type 'my.package.MissingA' was not found".to_owned()
+            ),
+            (
+                Some("invalid.proto"),
+                Some("my.package.Message.c"),
+                "the message or enum type 'my.package.MissingC' was not found".to_owned()
+            ),
+            (
+                Some("invalid.proto"),
+                Some("my.package.Service.Method"),
+                "invalid type '.my.package.Empty' for method 'my.package.Service.Method'"
+                    .to_owned()
+            ),
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "the message or enum type 'my.package.Message' is defined multiple times (and 4 other errors)"
+    );
+}
+
+#[test]
+fn diagnostic_locations() {
+    let err = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+        file: vec![invalid_file()],
+    })
+    .unwrap_err();
+
+    let missing_a = &err.diagnostics()[2];
+    assert_eq!(missing_a.location(), None);
+    assert_eq!(
+        missing_a.to_string(),
+        "invalid.proto: the message or enum type 'my.package.MissingA' was not found"
+    );
+
+    let missing_c = &err.diagnostics()[3];
+    let location = missing_c.location().unwrap();
+    assert_eq!(
+        (
+            location.line(),
+            location.column(),
+            location.end_line(),
+            location.end_column()
+        ),
+        (6, 3, 6, 31)
+    );
+    assert_eq!(
+        missing_c.to_string(),
+        "invalid.proto:6:3: the message or enum type 'my.package.MissingC' was not found"
+    );
+}
+
+#[test]
+fn missing_dependencies_reported() {
+    let file = FileDescriptorProto {
+        name: Some("a.proto".to_owned()),
+        dependency: vec!["b.proto".to_owned(), "c.proto".to_owned()],
+        ..Default::default()
+    };
+    let err = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+        file: vec![file],
+    })
+    .unwrap_err();
+
+    let messages: Vec<_> = err
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| {
+            assert_eq!(diagnostic.symbol(), None);
+            diagnostic.to_string()
+        })
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "a.proto: the file 'b.proto' was not found while resolving dependencies for 'a.proto'",
+            "a.proto: the file 'c.proto' was not found while resolving dependencies for 'a.proto'",
+        ]
+    );
+}
+
+#[test]
+fn single_error() {
+    let mut file = invalid_file();
+    file.message_type.pop();
+    file.message_type[0].field.remove(2);
+    file.message_type[0].field.remove(0);
+    file.enum_type.clear();
+    file.service.clear();
+    file.dependency.push("missing.proto".to_owned());
+
+    let err = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+        file: vec![file],
+    })
+    .unwrap_err();
+    assert_eq!(err.diagnostics().len(), 1);
+    assert_eq!(
+        err.to_string(),
+        "the file 'missing.proto' was not found while resolving dependencies for 'invalid.proto'"
+    );
+}
@@ -0,0 +1,301 @@
+use prost_reflect::{
+    testing::{assert_messages_eq, assert_messages_eq_ignoring},
+    CompareOptions, DynamicMessage, ReflectMessage, Value,
+};
+use prost_types::FieldMask;
+
+use crate::{test_file_descriptor, ComplexType, ScalarArrays, Scalars};
+
+fn mask(paths: &[&str]) -> FieldMask {
+    FieldMask {
+        paths: paths.iter().map(|&path| path.to_owned()).collect(),
+    }
+}
+
+fn complex_type(int32: i32, string: &str) -> DynamicMessage {
+    ComplexType {
+        string_map: vec![(
+            "foo".to_owned(),
+            Scalars {
+                string: string.to_owned(),
+                ..Default::default()
+            },
+        )]
+        .into_iter()
+        .collect(),
+        nested: Some(Scalars {
+            int32,
+            ..Default::default()
+        }),
+        my_enum: vec![1],
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn diff_equal_messages() {
+    let diff = complex_type(1, "a").diff(&complex_type(1, "a"), &FieldMask::default());
+    assert!(diff.is_empty());
+    assert_messages_eq(&complex_type(1, "a"), &complex_type(1, "a"));
+}
+
+#[test]
+fn diff_reports_paths() {
+    let left = complex_type(1, "a");
+    let mut right = complex_type(2, "b");
+    right.set_field_by_name(
+        "my_enum",
+        Value::List(vec![Value::EnumNumber(1), Value::EnumNumber(3)]),
+    );
+
+    let diff = left.diff(&right, &FieldMask::default());
+    assert_eq!(
+        diff.to_string(),
+        r#"string_map["foo"].string: "a" != "b"
+nested.int32: 1 != 2
+my_enum[1]: <unset> != BAR"#
+    );
+
+    let difference = diff.differences().next().unwrap();
+    assert_eq!(difference.path(), "string_map[\"foo\"].string");
+    assert_eq!(difference.left(), "\"a\"");
+    assert_eq!(difference.right(), "\"b\"");
+}
+
+#[test]
+fn diff_ignores_masked_fields() {
+    let left = complex_type(1, "a");
+    let right = complex_type(2, "b");
+
+    let diff = left.diff(&right, &mask(&["nested"]));
+    assert_eq!(diff.to_string(), r#"string_map["foo"].string: "a" != "b""#);
+
+    assert!(left
+        .diff(&right, &mask(&["nested.int32", "string_map.string"]))
+        .is_empty());
+    assert!(!left
+        .diff(&right, &mask(&["nest", "string_map.str"]))
+        .is_empty());
+    assert_messages_eq_ignoring(&left, &right, &mask(&["nested", "string_map"]));
+}
+
+#[test]
+fn diff_treats_default_values_as_unset() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.Scalars")
+        .unwrap();
+    let mut left = DynamicMessage::new(desc.clone());
+    left.set_field_by_name("int32", Value::I32(0));
+    left.set_field_by_name("double", Value::F64(f64::NAN));
+    let mut right = DynamicMessage::new(desc);
+    right.set_field_by_name("double", Value::F64(f64::NAN));
+
+    assert_ne!(left, right);
+    assert_messages_eq(&left, &right);
+
+    right.set_field_by_name("int32", Value::I32(3));
+    assert_eq!(
+        left.diff(&right, &FieldMask::default()).to_string(),
+        "int32: 0 != 3"
+    );
+}
+
+#[test]
+fn diff_presence_fields() {
+    let left = complex_type(1, "a");
+    let mut right = left.clone();
+    right.clear_field_by_name("nested");
+
+    assert_eq!(
+        left.diff(&right, &FieldMask::default()).to_string(),
+        "nested:\n  left: {\n  int32: 1\n}\n  right: <unset>"
+    );
+}
+
+#[test]
+fn diff_different_types() {
+    let left = complex_type(1, "a");
+    let right = Scalars::default().transcode_to_dynamic();
+    assert_eq!(
+        left.diff(&right, &FieldMask::default()).to_string(),
+        "<root>: test.ComplexType != test.Scalars"
+    );
+}
+
+#[test]
+#[should_panic(expected = "messages are not equal (ignoring string_map):\nnested.int32: 1 != 2")]
+fn assert_messages_eq_ignoring_panics() {
+    assert_messages_eq_ignoring(
+        &complex_type(1, "a"),
+        &complex_type(2, "b"),
+        &mask(&["string_map"]),
+    );
+}
+
+#[test]
+fn equals_with_ignored_paths() {
+    let left = complex_type(1, "a");
+    let right = complex_type(2, "a");
+    assert!(!left.equals_with(&right, &CompareOptions::new()));
+    assert!(left.equals_with(&right, &CompareOptions::new().ignore("nested.int32")));
+    assert!(left.equals_with(&right, &CompareOptions::new().ignore("nested")));
+    assert!(!left.equals_with(&right, &CompareOptions::new().ignore("nest")));
+}
+
+#[test]
+fn equals_with_repeated_as_set() {
+    let left = ScalarArrays {
+        int32: vec![1, 2, 2, 3],
+        string: vec!["a".to_owned(), "b".to_owned()],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let right = ScalarArrays {
+        int32: vec![2, 3, 1, 2],
+        string: vec!["b".to_owned(), "a".to_owned()],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert!(!left.equals_with(&right, &CompareOptions::new()));
+    assert!(left.equals_with(&right, &CompareOptions::new().repeated_as_set(true)));
+    assert!(!left.equals_with(&right, &CompareOptions::new().treat_as_set("int32")));
+    assert!(left.equals_with(
+        &right,
+        &CompareOptions::new()
+            .treat_as_set("int32")
+            .treat_as_set("string")
+    ));
+
+    let right = ScalarArrays {
+        int32: vec![3, 1, 1, 2],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    assert_eq!(
+        left.diff_with(
+            &right,
+            &CompareOptions::new().repeated_as_set(true).ignore("string")
+        )
+        .to_string(),
+        "int32[2]: 2 != <unset>\nint32[2]: <unset> != 1"
+    );
+}
+
+#[test]
+fn equals_with_ignore_unknown_fields() {
+    let desc = Scalars::default().descriptor();
+    let left = DynamicMessage::decode(desc.clone(), b"\x18\x01\x80\x01\x05".as_ref()).unwrap();
+    let right = DynamicMessage::decode(desc, b"\x18\x01".as_ref()).unwrap();
+
+    assert_eq!(
+        left.diff_with(&right, &CompareOptions::new()).to_string(),
+        "16: 5 != <unset>"
+    );
+    assert!(left.equals_with(&right, &CompareOptions::new().ignore_unknown_fields(true)));
+}
+
+#[test]
+fn equals_with_float_tolerance() {
+    let scalars = |double: f64, float: f32| {
+        Scalars {
+            double,
+            float,
+            ..Default::default()
+        }
+        .transcode_to_dynamic()
+    };
+
+    let left = scalars(1.0, 100.0);
+    let right = scalars(1.0 + 1e-9, 100.001);
+    assert!(!left.equals_with(&right, &CompareOptions::new()));
+    assert!(left.equals_with(&right, &CompareOptions::new().float_tolerance(1e-4, 0.0)));
+    assert!(!left.equals_with(&right, &CompareOptions::new().float_tolerance(1e-6, 0.0)));
+    assert!(!left.equals_with(&right, &CompareOptions::new().float_tolerance(0.0, 1e-6)));
+    assert!(left.equals_with(&right, &CompareOptions::new().float_tolerance(0.0, 0.01)));
+
+    let infinite = scalars(f64::INFINITY, f32::NEG_INFINITY);
+    let options = CompareOptions::new().float_tolerance(1.0, 1.0);
+    assert!(infinite.equals_with(&infinite, &options));
+    assert!(!infinite.equals_with(&scalars(f64::MAX, f32::MIN), &options));
+}
+
+#[test]
+fn equals_with_float_ulps() {
+    let scalars = |double: f64, float: f32| {
+        Scalars {
+            double,
+            float,
+            ..Default::default()
+        }
+        .transcode_to_dynamic()
+    };
+    let next_f64 = |value: f64, ulps: u64| f64::from_bits(value.to_bits() + ulps);
+    let next_f32 = |value: f32, ulps: u32| f32::from_bits(value.to_bits() + ulps);
+
+    let left = scalars(0.1, 0.1);
+    let right = scalars(next_f64(0.1, 4), next_f32(0.1, 2));
+    assert!(!left.equals_with(&right, &CompareOptions::new()));
+    assert!(left.equals_with(&right, &CompareOptions::new().float_ulps(4)));
+    assert!(!left.equals_with(&right, &CompareOptions::new().float_ulps(3)));
+
+    // Values either side of zero.
+    let left = scalars(f64::from_bits(1), -0.0);
+    let right = scalars(-f64::from_bits(2), f32::from_bits(1));
+    assert!(left.equals_with(&right, &CompareOptions::new().float_ulps(3)));
+    assert!(!left.equals_with(&right, &CompareOptions::new().float_ulps(2)));
+
+    // Either tolerance is sufficient.
+    let left = scalars(1.0, 1.0);
+    let right = scalars(1.5, next_f32(1.0, 1));
+    assert!(left.equals_with(
+        &right,
+        &CompareOptions::new()
+            .float_ulps(1)
+            .float_tolerance(0.0, 0.5)
+    ));
+}
+
+#[test]
+fn equals_with_field_float_tolerance() {
+    let mut left = complex_type(1, "a");
+    let mut right = complex_type(1, "a");
+    let nested = |double: f64, float: f32| {
+        Value::Message(
+            Scalars {
+                int32: 1,
+                double,
+                float,
+                ..Default::default()
+            }
+            .transcode_to_dynamic(),
+        )
+    };
+    left.set_field_by_name("nested", nested(1.0, 1.0));
+    right.set_field_by_name("nested", nested(1.1, 1.1));
+
+    let options = CompareOptions::new().field_float_tolerance("nested.double", 0.0, 0.2);
+    assert_eq!(
+        left.diff_with(&right, &options).to_string(),
+        "nested.float: 1 != 1.1"
+    );
+
+    let options = CompareOptions::new()
+        .field_float_tolerance("nested", 0.0, 0.2)
+        .field_float_ulps("nested.float", 1);
+    assert_eq!(
+        left.diff_with(&right, &options).to_string(),
+        "nested.float: 1 != 1.1"
+    );
+
+    // A field tolerance replaces the global tolerance.
+    let options = CompareOptions::new()
+        .float_tolerance(0.0, 0.2)
+        .field_float_ulps("nested.double", 1);
+    assert_eq!(
+        left.diff_with(&right, &options).to_string(),
+        "nested.double: 1 != 1.1"
+    );
+    assert!(left.equals_with(&right, &CompareOptions::new().float_tolerance(0.0, 0.2)));
+}
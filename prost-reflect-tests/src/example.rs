@@ -0,0 +1,178 @@
+use prost_reflect::{
+    DynamicMessage, FieldBuilder, FileDescriptorBuilder, FillOptions, MapKey, MessageBuilder,
+    MessageDescriptor, Value,
+};
+
+use crate::test_file_descriptor;
+
+fn filled(name: &str, options: &FillOptions) -> DynamicMessage {
+    let mut message =
+        DynamicMessage::new(test_file_descriptor().get_message_by_name(name).unwrap());
+    message.fill_example(options);
+    message
+}
+
+#[test]
+fn fill_example_string_hints() {
+    let message = filled("test.Contact", &FillOptions::new());
+
+    assert_eq!(
+        message.get_field_by_name("id").unwrap().as_str(),
+        Some("123e4567-e89b-12d3-a456-426614174000")
+    );
+    assert_eq!(
+        message.get_field_by_name("display_name").unwrap().as_str(),
+        Some("example display name")
+    );
+    assert_eq!(
+        message.get_field_by_name("email").unwrap().as_str(),
+        Some("user@example.com")
+    );
+    assert_eq!(
+        message.get_field_by_name("website_url").unwrap().as_str(),
+        Some("https://example.com")
+    );
+    assert_eq!(
+        message.get_field_by_name("phone_number").unwrap().as_str(),
+        Some("+1-202-555-0100")
+    );
+    assert_eq!(
+        message.get_field_by_name("created_at").unwrap().as_str(),
+        Some("2022-01-01T00:00:00Z")
+    );
+    assert_eq!(
+        message
+            .get_field_by_name("tags")
+            .unwrap()
+            .as_list()
+            .unwrap(),
+        &[
+            Value::String("example tags".to_owned()),
+            Value::String("example tags".to_owned())
+        ]
+    );
+}
+
+#[test]
+fn fill_example_complex_type() {
+    let message = filled("test.ComplexType", &FillOptions::new().repeated_len(3));
+
+    assert_eq!(
+        message
+            .get_field_by_name("optional_enum")
+            .unwrap()
+            .as_enum_number(),
+        Some(1)
+    );
+    assert_eq!(
+        message
+            .get_field_by_name("my_enum")
+            .unwrap()
+            .as_list()
+            .unwrap(),
+        &[
+            Value::EnumNumber(1),
+            Value::EnumNumber(1),
+            Value::EnumNumber(1)
+        ]
+    );
+
+    let string_map = message.get_field_by_name("string_map").unwrap();
+    let string_map = string_map.as_map().unwrap();
+    assert_eq!(string_map.len(), 3);
+    assert!(string_map.contains_key(&MapKey::String("key1".to_owned())));
+
+    let nested = message.get_field_by_name("nested").unwrap();
+    let nested = nested.as_message().unwrap();
+    assert_eq!(nested.get_field_by_name("int32").unwrap().as_i32(), Some(1));
+    assert_eq!(
+        nested.get_field_by_name("bool").unwrap().as_bool(),
+        Some(true)
+    );
+}
+
+#[test]
+fn fill_example_oneof() {
+    let message = filled("test.MessageWithOneof", &FillOptions::new());
+
+    assert!(message.has_field_by_name("oneof_field_1"));
+    assert!(!message.has_field_by_name("oneof_field_2"));
+}
+
+fn built_message() -> MessageDescriptor {
+    FileDescriptorBuilder::new("example.proto")
+        .package("example")
+        .add_message(
+            MessageBuilder::new("Message")
+                .add_field(FieldBuilder::map("flags", 1, "bool", "int32"))
+                .add_field(FieldBuilder::new("nested", 2, "Message").oneof("choice"))
+                .add_field(FieldBuilder::new("text", 3, "string").oneof("choice")),
+        )
+        .build()
+        .unwrap()
+        .get_message_by_name("example.Message")
+        .unwrap()
+}
+
+#[test]
+fn fill_example_bool_map_keys() {
+    let mut message = DynamicMessage::new(built_message());
+    message.fill_example(&FillOptions::new().max_depth(0).repeated_len(3));
+
+    let flags = message.get_field_by_name("flags").unwrap();
+    let flags = flags.as_map().unwrap();
+    assert_eq!(flags.len(), 2);
+    assert!(flags.contains_key(&MapKey::Bool(false)));
+    assert!(flags.contains_key(&MapKey::Bool(true)));
+}
+
+#[test]
+fn fill_example_oneof_skips_unpopulated_fields() {
+    let mut message = DynamicMessage::new(built_message());
+    message.fill_example(&FillOptions::new().max_depth(0));
+
+    assert!(!message.has_field_by_name("nested"));
+    assert!(message.has_field_by_name("text"));
+
+    message.fill_example(&FillOptions::new().max_depth(1));
+    assert!(message.has_field_by_name("nested"));
+    assert!(!message.has_field_by_name("text"));
+}
+
+#[test]
+fn fill_example_clears_message() {
+    let mut message = DynamicMessage::new(built_message());
+    message.fill_example(&FillOptions::new().max_depth(1));
+    assert!(message.has_field_by_name("nested"));
+
+    message.fill_example(&FillOptions::new().max_depth(0));
+    assert!(!message.has_field_by_name("nested"));
+    assert!(message.has_field_by_name("text"));
+}
+
+#[test]
+fn fill_example_max_depth() {
+    let message = filled("test.ComplexType", &FillOptions::new().max_depth(0));
+
+    assert!(!message.has_field_by_name("nested"));
+    assert!(message.has_field_by_name("optional_enum"));
+}
+
+#[test]
+fn fill_example_is_deterministic() {
+    assert_eq!(
+        filled("test.WellKnownTypes", &FillOptions::new()),
+        filled("test.WellKnownTypes", &FillOptions::new())
+    );
+}
+
+#[test]
+fn fill_example_well_known_types_serialize_to_json() {
+    let message = filled("test.WellKnownTypes", &FillOptions::new());
+    let json = serde_json::to_value(&message).unwrap();
+
+    assert_eq!(json["timestamp"], "2022-01-01T00:00:00Z");
+    assert_eq!(json["duration"], "1s");
+    assert_eq!(json["mask"], "example");
+    assert_eq!(json["string"], "example value");
+}
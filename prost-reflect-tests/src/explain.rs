@@ -0,0 +1,161 @@
+use proptest::prelude::*;
+use prost::Message;
+use prost_reflect::{DynamicMessage, ReflectMessage};
+
+use crate::{arb_message, test_file_descriptor, ComplexType, Scalars};
+
+#[test]
+fn explain_nested_message() {
+    let message = ComplexType {
+        nested: Some(Scalars {
+            int32: 150,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let explanation = message.explain();
+    assert_eq!(explanation.encoded_len(), 9);
+    assert_eq!(
+        explanation.to_string(),
+        "0..5  nested (3, length-delimited)\n\
+         2..5    int32 (3, varint) = 150\n\
+         5..9  my_enum (4, length-delimited) = [FOO, BAR]\n"
+    );
+
+    let nested = &explanation.entries()[0];
+    assert_eq!(nested.offset(), 0);
+    assert_eq!(nested.encoded_len(), 5);
+    assert_eq!(nested.header_len(), 2);
+    assert_eq!(nested.depth(), 0);
+    assert_eq!(nested.value(), None);
+
+    let int32 = &explanation.entries()[1];
+    assert_eq!(int32.name(), "int32");
+    assert_eq!(int32.number(), 3);
+    assert_eq!(int32.wire_type(), "varint");
+    assert_eq!(int32.header_len(), 1);
+    assert_eq!(int32.depth(), 1);
+    assert_eq!(int32.value(), Some("150"));
+}
+
+#[test]
+fn explain_map() {
+    let message = ComplexType {
+        string_map: vec![(
+            "a".to_owned(),
+            Scalars {
+                bool: true,
+                ..Default::default()
+            },
+        )]
+        .into_iter()
+        .collect(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let explanation = message.explain();
+    let entries: Vec<_> = explanation
+        .entries()
+        .iter()
+        .map(|entry| {
+            (
+                entry.offset(),
+                entry.encoded_len(),
+                entry.depth(),
+                entry.name(),
+                entry.value(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        entries,
+        vec![
+            (0, 9, 0, "string_map", None),
+            (2, 3, 1, "key", Some("\"a\"")),
+            (5, 4, 1, "value", None),
+            (7, 2, 2, "bool", Some("true")),
+        ]
+    );
+}
+
+#[test]
+fn explain_group() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.ContainsGroup")
+        .unwrap();
+    let message = DynamicMessage::decode(desc, b"\x0b\x12\x01a\x18\x01\x0c".as_ref()).unwrap();
+
+    let explanation = message.explain();
+    assert_eq!(
+        explanation.to_string(),
+        "0..7  groupmessage (1, group)\n\
+         1..4    url (2, length-delimited) = \"a\"\n\
+         4..6    id (3, varint) = 1\n"
+    );
+    assert_eq!(explanation.entries()[0].header_len(), 2);
+}
+
+#[test]
+fn explain_unknown_fields() {
+    let desc = Scalars::default().descriptor();
+    let message = DynamicMessage::decode(
+        desc,
+        b"\x80\x01\x05\x8d\x01\x01\x00\x00\x00\x82\x01\x01\xff".as_ref(),
+    )
+    .unwrap();
+
+    let explanation = message.explain();
+    assert_eq!(
+        explanation.to_string(),
+        "0..3   16 (16, varint) = 5\n\
+         3..7   16 (16, length-delimited) = b\"\\xff\"\n\
+         7..13  17 (17, fixed32) = 0x00000001\n"
+    );
+}
+
+#[test]
+fn explain_empty() {
+    let explanation = Scalars::default().transcode_to_dynamic().explain();
+    assert_eq!(explanation.encoded_len(), 0);
+    assert!(explanation.entries().is_empty());
+    assert_eq!(explanation.to_string(), "");
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn explain_covers_encoding(message in arb_message("test.ComplexType")) {
+        check_covers_encoding(&message)?;
+    }
+
+    #[test]
+    fn explain_covers_encoding_group(message in arb_message("test2.ContainsGroup")) {
+        check_covers_encoding(&message)?;
+    }
+}
+
+fn check_covers_encoding(message: &DynamicMessage) -> Result<(), TestCaseError> {
+    let explanation = message.explain();
+    prop_assert_eq!(explanation.encoded_len(), message.encoded_len());
+
+    // The fields of the top-level message are contiguous and cover the whole encoding.
+    let mut offset = 0;
+    for entry in explanation
+        .entries()
+        .iter()
+        .filter(|entry| entry.depth() == 0)
+    {
+        prop_assert_eq!(entry.offset(), offset);
+        offset += entry.encoded_len();
+    }
+    prop_assert_eq!(offset, explanation.encoded_len());
+    Ok(())
+}
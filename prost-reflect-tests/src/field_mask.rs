@@ -0,0 +1,130 @@
+use prost_reflect::{field_mask, DynamicMessage, Value};
+use prost_types::FieldMask;
+
+use crate::test_file_descriptor;
+
+fn mask(paths: &[&str]) -> FieldMask {
+    FieldMask {
+        paths: paths.iter().map(|&path| path.to_owned()).collect(),
+    }
+}
+
+fn complex_type() -> DynamicMessage {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+    let mut nested = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("test.Scalars")
+            .unwrap(),
+    );
+    nested.set_field_by_name("int32", Value::I32(1));
+    nested.set_field_by_name("string", Value::String("s".to_owned()));
+
+    let mut message = DynamicMessage::new(desc);
+    message.set_field_by_name("nested", Value::Message(nested));
+    message.set_field_by_name("optional_enum", Value::EnumNumber(1));
+    message.set_field_by_name("my_enum", Value::List(vec![Value::EnumNumber(3)]));
+    message
+}
+
+fn nested_field(message: &DynamicMessage, name: &str) -> bool {
+    message
+        .get_field_by_name("nested")
+        .unwrap()
+        .as_message()
+        .unwrap()
+        .has_field_by_name(name)
+}
+
+#[test]
+fn validate() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+
+    field_mask::validate(&desc, &mask(&[])).unwrap();
+    field_mask::validate(&desc, &mask(&["nested", "nested.int32", "string_map"])).unwrap();
+
+    assert_eq!(
+        field_mask::validate(&desc, &mask(&["nested.missing"]))
+            .unwrap_err()
+            .to_string(),
+        "invalid field mask path 'nested.missing': field 'missing' not found in message 'test.Scalars'"
+    );
+    assert_eq!(
+        field_mask::validate(&desc, &mask(&["string_map.key"]))
+            .unwrap_err()
+            .to_string(),
+        "invalid field mask path 'string_map.key': field 'test.ComplexType.string_map' is not a singular message, so it cannot contain subfields"
+    );
+    assert_eq!(
+        field_mask::validate(&desc, &mask(&["optional_enum.value"]))
+            .unwrap_err()
+            .to_string(),
+        "invalid field mask path 'optional_enum.value': field 'test.ComplexType.optional_enum' is not a singular message, so it cannot contain subfields"
+    );
+    assert_eq!(
+        field_mask::validate(&desc, &mask(&[""]))
+            .unwrap_err()
+            .to_string(),
+        "invalid field mask path '': the path is empty"
+    );
+}
+
+#[test]
+fn project() {
+    let mut message = complex_type();
+    field_mask::project(&mut message, &mask(&["nested.int32", "optional_enum"]));
+
+    assert!(message.has_field_by_name("optional_enum"));
+    assert!(!message.has_field_by_name("my_enum"));
+    assert!(nested_field(&message, "int32"));
+    assert!(!nested_field(&message, "string"));
+
+    let mut message = complex_type();
+    field_mask::project(&mut message, &mask(&["nested"]));
+    assert!(!message.has_field_by_name("optional_enum"));
+    assert!(nested_field(&message, "int32"));
+    assert!(nested_field(&message, "string"));
+}
+
+#[test]
+fn prune() {
+    let mut message = complex_type();
+    field_mask::prune(&mut message, &mask(&["nested.int32", "my_enum", "unknown"]));
+
+    assert!(message.has_field_by_name("optional_enum"));
+    assert!(!message.has_field_by_name("my_enum"));
+    assert!(!nested_field(&message, "int32"));
+    assert!(nested_field(&message, "string"));
+
+    field_mask::prune(&mut message, &mask(&["nested"]));
+    assert!(!message.has_field_by_name("nested"));
+}
+
+#[test]
+fn merge_and_intersect() {
+    assert_eq!(
+        field_mask::merge(&mask(&["b", "a.x"]), &mask(&["a.y", "c.z"])),
+        mask(&["a.x", "a.y", "b", "c.z"])
+    );
+    assert_eq!(
+        field_mask::merge(&mask(&["a.x", "b"]), &mask(&["a"])),
+        mask(&["a", "b"])
+    );
+
+    assert_eq!(
+        field_mask::intersect(&mask(&["a", "b.x", "c"]), &mask(&["a.y", "b", "d"])),
+        mask(&["a.y", "b.x"])
+    );
+    assert_eq!(
+        field_mask::intersect(&mask(&["a.x"]), &mask(&["a.y"])),
+        mask(&[])
+    );
+
+    assert_eq!(
+        field_mask::normalize(&mask(&["b", "a.x", "a", "b.c"])),
+        mask(&["a", "b"])
+    );
+}
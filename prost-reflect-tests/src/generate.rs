@@ -0,0 +1,171 @@
+use prost::Message;
+use prost_reflect::{DynamicMessage, GenerateOptions, ReflectMessage, SnapshotOptions, Value};
+
+use crate::test_file_descriptor;
+
+fn generate(name: &str, seed: u64) -> DynamicMessage {
+    DynamicMessage::generate(
+        test_file_descriptor().get_message_by_name(name).unwrap(),
+        seed,
+    )
+}
+
+#[test]
+fn generate_is_deterministic() {
+    for &name in &["test.Scalars", "test.ComplexType", "test.WellKnownTypes"] {
+        for seed in 0..20 {
+            assert_eq!(generate(name, seed), generate(name, seed));
+        }
+    }
+
+    assert_ne!(
+        generate("test.Scalars", 1).encode_to_vec(),
+        generate("test.Scalars", 2).encode_to_vec()
+    );
+}
+
+#[test]
+fn generate_golden() {
+    assert_eq!(
+        generate("test.Scalars", 42).to_snapshot(&SnapshotOptions::new()),
+        "test.Scalars {\n  double: -13.40625\n  float: -14.453125\n  int32: 207\n  int64: 956\n  uint32: 47\n  sint64: -929\n  fixed32: 991\n  fixed64: 501\n  sfixed32: 158\n  sfixed64: -793\n  string: \"sdz0MnC3n\"\n}\n"
+    );
+}
+
+#[test]
+fn generate_sets_required_fields() {
+    for seed in 0..20 {
+        let message = generate("test2.ContainsGroup", seed);
+        for group in message
+            .get_field_by_name("groupmessage")
+            .unwrap()
+            .as_list()
+            .unwrap()
+        {
+            assert!(group.as_message().unwrap().has_field_by_name("url"));
+        }
+    }
+}
+
+#[test]
+fn generate_sets_required_fields_beyond_max_depth() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.RequiredNested")
+        .unwrap();
+    for &max_depth in &[0, 1, 3] {
+        let options = GenerateOptions::new().max_depth(max_depth);
+        for seed in 0..20 {
+            let message = DynamicMessage::generate_with_options(desc.clone(), seed, &options);
+            assert!(message.is_initialized(), "{:?}", message);
+        }
+    }
+}
+
+#[test]
+fn generate_respects_options() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+    let options = GenerateOptions::new().max_depth(0).max_collection_size(1);
+    for seed in 0..20 {
+        let message = DynamicMessage::generate_with_options(desc.clone(), seed, &options);
+        assert!(!message.has_field_by_name("nested"));
+        assert!(
+            message
+                .get_field_by_name("my_enum")
+                .unwrap()
+                .as_list()
+                .unwrap()
+                .len()
+                <= 1
+        );
+        assert!(
+            message
+                .get_field_by_name("int_map")
+                .unwrap()
+                .as_map()
+                .unwrap()
+                .len()
+                <= 1
+        );
+    }
+}
+
+#[test]
+fn generate_respects_collection_and_string_options() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+    let options = GenerateOptions::new()
+        .min_collection_size(2)
+        .max_collection_size(3)
+        .max_string_length(4)
+        .string_alphabet("xyz");
+    for seed in 0..20 {
+        let message = DynamicMessage::generate_with_options(desc.clone(), seed, &options);
+        let my_enum = message.get_field_by_name("my_enum").unwrap();
+        let len = my_enum.as_list().unwrap().len();
+        assert!(len == 0 || (2..=3).contains(&len));
+
+        let string_map = message.get_field_by_name("string_map").unwrap();
+        for (key, value) in string_map.as_map().unwrap() {
+            let key = key.as_str().unwrap();
+            assert!(key.chars().count() <= 4);
+            assert!(key.chars().all(|c| "xyz".contains(c)));
+
+            let string = value
+                .as_message()
+                .unwrap()
+                .get_field_by_name("string")
+                .unwrap();
+            assert!(string.as_str().unwrap().chars().all(|c| "xyz".contains(c)));
+        }
+    }
+
+    let options = GenerateOptions::new().string_alphabet(String::new());
+    let message = DynamicMessage::generate_with_options(
+        test_file_descriptor()
+            .get_message_by_name("test.Scalars")
+            .unwrap(),
+        1,
+        &options,
+    );
+    assert_eq!(
+        message.get_field_by_name("string").unwrap().as_str(),
+        Some("")
+    );
+}
+
+#[test]
+fn generate_declared_enum_values() {
+    for seed in 0..20 {
+        let message = generate("test.ComplexType", seed);
+        for value in message
+            .get_field_by_name("my_enum")
+            .unwrap()
+            .as_list()
+            .unwrap()
+        {
+            assert!([0, 1, 3, -4].contains(&value.as_enum_number().unwrap()));
+        }
+    }
+}
+
+#[test]
+fn generate_valid_well_known_types() {
+    for seed in 0..20 {
+        let message = generate("test.WellKnownTypes", seed);
+        if let Some(Value::Message(timestamp)) = message.get_field_by_name("timestamp").as_deref() {
+            let seconds = timestamp
+                .get_field_by_name("seconds")
+                .unwrap()
+                .as_i64()
+                .unwrap();
+            assert!((0..=253_402_300_799).contains(&seconds));
+        }
+
+        let json = serde_json::to_string(&message).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        DynamicMessage::deserialize(message.descriptor(), &mut deserializer).unwrap();
+    }
+}
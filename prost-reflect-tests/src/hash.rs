@@ -0,0 +1,144 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use proptest::prelude::*;
+use prost::Message;
+use prost_reflect::{DynamicMessage, MapKey, ReflectMessage, Value};
+
+use crate::{arb_message, ComplexType, Scalars};
+
+fn hash<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn hash_equal_messages() {
+    let message = ComplexType {
+        string_map: (0..20)
+            .map(|i| {
+                (
+                    i.to_string(),
+                    Scalars {
+                        int32: i,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect(),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    // Decoding inserts the map entries in a different order.
+    let decoded =
+        DynamicMessage::decode(message.descriptor(), message.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded, message);
+    assert_eq!(hash(&decoded), hash(&message));
+}
+
+#[test]
+fn hash_cleared_field() {
+    let mut message = Scalars {
+        int32: 5,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    message.clear_field_by_name("int32");
+
+    let empty = Scalars::default().transcode_to_dynamic();
+    assert_eq!(message, empty);
+    assert_eq!(hash(&message), hash(&empty));
+}
+
+#[test]
+fn hash_floats() {
+    assert_eq!(Value::F64(0.0), Value::F64(-0.0));
+    assert_eq!(hash(&Value::F64(0.0)), hash(&Value::F64(-0.0)));
+    assert_eq!(hash(&Value::F32(0.0)), hash(&Value::F32(-0.0)));
+    assert_ne!(hash(&Value::F64(1.0)), hash(&Value::F64(2.0)));
+    assert_ne!(hash(&Value::F32(1.0)), hash(&Value::F64(1.0)));
+
+    assert_eq!(Value::F64(f64::NAN), Value::F64(f64::NAN));
+    assert_eq!(Value::F64(f64::NAN), Value::F64(-f64::NAN));
+    assert_eq!(Value::F32(f32::NAN), Value::F32(f32::NAN));
+    assert_ne!(Value::F64(f64::NAN), Value::F64(1.0));
+    assert_eq!(hash(&Value::F64(f64::NAN)), hash(&Value::F64(-f64::NAN)));
+}
+
+#[test]
+fn hash_set_finds_nan() {
+    let mut message = Scalars::default().transcode_to_dynamic();
+    message.set_field_by_name("double", Value::F64(f64::NAN));
+
+    #[allow(clippy::mutable_key_type)]
+    let mut set = HashSet::new();
+    assert!(set.insert(message.clone()));
+    assert!(!set.insert(message.clone()));
+    assert!(set.contains(&message));
+}
+
+#[test]
+fn hash_maps() {
+    let map1 = Value::Map((0..20).map(|i| (MapKey::I32(i), Value::I32(i))).collect());
+    let map2 = Value::Map(
+        (0..20)
+            .rev()
+            .map(|i| (MapKey::I32(i), Value::I32(i)))
+            .collect(),
+    );
+    assert_eq!(hash(&map1), hash(&map2));
+}
+
+#[test]
+fn hash_set_dedupes_messages() {
+    let message1 = Scalars {
+        int32: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let message2 = Scalars {
+        string: "a".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    // The encoded length cached by a message does not affect its hash.
+    #[allow(clippy::mutable_key_type)]
+    let mut set = HashSet::new();
+    assert!(set.insert(message1.clone()));
+    assert!(set.insert(message2.clone()));
+    assert!(!set.insert(message1.clone()));
+    assert!(!set.insert(message2));
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&message1));
+}
+
+#[test]
+fn hash_unknown_fields() {
+    let desc = Scalars::default().descriptor();
+    let message1 = DynamicMessage::decode(desc.clone(), b"\x80\x01\x05".as_ref()).unwrap();
+    let message2 = DynamicMessage::decode(desc.clone(), b"\x80\x01\x06".as_ref()).unwrap();
+    let message3 = DynamicMessage::decode(desc, b"\x80\x01\x05".as_ref()).unwrap();
+    assert_ne!(hash(&message1), hash(&message2));
+    assert_eq!(hash(&message1), hash(&message3));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn hash_roundtrip(message in arb_message("test.ComplexType")) {
+        let decoded = DynamicMessage::decode(message.descriptor(), message.encode_to_vec().as_slice()).unwrap();
+        if decoded == message {
+            prop_assert_eq!(hash(&decoded), hash(&message));
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, FieldBuilder, FileDescriptorBuilder, IncrementalPool, MessageBuilder,
+};
+use prost_types::FileDescriptorProto;
+
+use crate::test_file_descriptor;
+
+/// Builds `a.proto`, `b.proto` importing `a.proto`, and `c.proto` importing `b.proto`.
+fn chain() -> Vec<FileDescriptorProto> {
+    let mut pool = DescriptorPool::new();
+    FileDescriptorBuilder::new("a.proto")
+        .add_message(MessageBuilder::new("A"))
+        .add_to_pool(&mut pool)
+        .unwrap();
+    FileDescriptorBuilder::new("b.proto")
+        .add_dependency("a.proto")
+        .add_message(MessageBuilder::new("B").add_field(FieldBuilder::new("a", 1, "A")))
+        .add_to_pool(&mut pool)
+        .unwrap();
+    FileDescriptorBuilder::new("c.proto")
+        .add_dependency("b.proto")
+        .add_message(MessageBuilder::new("C").add_field(FieldBuilder::new("b", 1, "B")))
+        .add_to_pool(&mut pool)
+        .unwrap();
+    pool.file_descriptor_protos().cloned().collect()
+}
+
+fn pending_names(pool: &IncrementalPool) -> Vec<&str> {
+    pool.pending_files().map(|file| file.name()).collect()
+}
+
+#[test]
+fn add_out_of_order() {
+    let files = chain();
+    let mut pool = IncrementalPool::new();
+
+    assert_eq!(pool.add_file_descriptor_proto(files[2].clone()).unwrap(), 0);
+    assert_eq!(pool.add_file_descriptor_proto(files[1].clone()).unwrap(), 0);
+    assert_eq!(pending_names(&pool), ["c.proto", "b.proto"]);
+    assert_eq!(pool.missing_dependencies(), ["a.proto"]);
+    assert_eq!(pool.pool().files().len(), 0);
+    assert!(!pool.is_complete());
+
+    assert_eq!(pool.add_file_descriptor_proto(files[0].clone()).unwrap(), 3);
+    assert!(pool.is_complete());
+    assert!(pool.missing_dependencies().is_empty());
+    assert!(pool.pool().get_message_by_name("C").is_some());
+
+    let pool = pool.finalize().unwrap();
+    assert_eq!(pool.files().len(), 3);
+}
+
+#[test]
+fn add_in_order() {
+    let mut pool = IncrementalPool::new();
+    for file in chain() {
+        assert_eq!(pool.add_file_descriptor_proto(file).unwrap(), 1);
+    }
+    assert_eq!(pool.pool().files().len(), 3);
+}
+
+#[test]
+fn add_duplicates() {
+    let files = chain();
+    let mut pool = IncrementalPool::new();
+    pool.add_file_descriptor_proto(files[0].clone()).unwrap();
+    pool.add_file_descriptor_proto(files[2].clone()).unwrap();
+
+    // Identical files are ignored, whether they are in the pool or pending.
+    assert_eq!(pool.add_file_descriptor_proto(files[0].clone()).unwrap(), 0);
+    assert_eq!(pool.add_file_descriptor_proto(files[2].clone()).unwrap(), 0);
+    assert_eq!(pending_names(&pool), ["c.proto"]);
+
+    let mut conflicting = files[0].clone();
+    conflicting.package = Some("other".to_owned());
+    assert!(pool.add_file_descriptor_proto(conflicting).is_err());
+    let mut conflicting = files[2].clone();
+    conflicting.package = Some("other".to_owned());
+    assert!(pool.add_file_descriptor_proto(conflicting).is_err());
+    assert_eq!(pending_names(&pool), ["c.proto"]);
+}
+
+#[test]
+fn add_invalid_file() {
+    let files = chain();
+    let mut pool = IncrementalPool::new();
+    pool.add_file_descriptor_proto(files[2].clone()).unwrap();
+
+    // `b.proto` is pending until `a.proto` is added, at which point it fails to resolve.
+    let mut invalid = files[1].clone();
+    invalid.message_type[0].field[0].type_name = Some("Missing".to_owned());
+    assert_eq!(pool.add_file_descriptor_proto(invalid).unwrap(), 0);
+    let err = pool
+        .add_file_descriptor_proto(files[0].clone())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the message or enum type 'Missing' was not found"
+    );
+    assert_eq!(pending_names(&pool), ["c.proto", "b.proto"]);
+    assert_eq!(pool.pool().files().len(), 0);
+}
+
+#[test]
+fn finalize_missing_dependency() {
+    let files = chain();
+    let mut pool = IncrementalPool::new();
+    pool.add_file_descriptor_proto(files[0].clone()).unwrap();
+    pool.add_file_descriptor_proto(files[2].clone()).unwrap();
+
+    let err = pool.finalize().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the file 'b.proto' was not found while resolving dependencies for 'c.proto' (and 1 other error)"
+    );
+}
+
+#[test]
+fn decode_with_existing_pool() {
+    let existing = test_file_descriptor();
+    let file = existing.get_file_by_name("desc2.proto").unwrap();
+    let mut base = existing.prune(file.dependencies());
+    for dependency in &file.file_descriptor_proto().dependency {
+        let dependency = existing.get_file_by_name(dependency).unwrap();
+        base.add_file_descriptor_proto(dependency.file_descriptor_proto().clone())
+            .unwrap();
+    }
+
+    let mut pool = IncrementalPool::with_pool(base);
+    let encoded = file.file_descriptor_proto().encode_to_vec();
+    assert_eq!(
+        pool.decode_file_descriptor_proto(encoded.as_slice())
+            .unwrap(),
+        1
+    );
+    assert!(pool
+        .pool()
+        .get_message_by_name("my.package2.MyMessage")
+        .is_some());
+}
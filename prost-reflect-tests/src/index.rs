@@ -0,0 +1,120 @@
+use prost_reflect::{DynamicMessage, ReflectMessage, Value};
+
+use crate::{test_file_descriptor, ComplexType, Scalars};
+
+fn complex_message() -> DynamicMessage {
+    ComplexType {
+        string_map: IntoIterator::into_iter([(
+            "key".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )])
+        .collect(),
+        nested: Some(Scalars {
+            string: "nested".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn index_message() {
+    let message = complex_message();
+
+    assert_eq!(
+        message["nested"]["string"],
+        Value::String("nested".to_owned())
+    );
+    assert_eq!(message[3][14], Value::String("nested".to_owned()));
+    assert_eq!(message["string_map"]["key"]["int32"], Value::I32(5));
+    assert_eq!(message["my_enum"][1], Value::EnumNumber(3));
+}
+
+#[test]
+fn index_unset_fields() {
+    let message = complex_message();
+
+    assert_eq!(message["nested"]["int64"], Value::I64(0));
+    assert_eq!(message["nested"]["bytes"], Value::Bytes(Default::default()));
+    assert_eq!(message["optional_enum"], Value::EnumNumber(0));
+
+    let message = DynamicMessage::new(complex_message().descriptor());
+    assert_eq!(message["my_enum"], Value::List(vec![]));
+}
+
+#[test]
+fn index_unset_fields_explicit_default() {
+    let message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("test2.DefaultValues")
+            .unwrap(),
+    );
+
+    assert_eq!(message["int32"], Value::I32(-3));
+    assert_eq!(message["string"], Value::String("hello".to_owned()));
+}
+
+#[test]
+fn index_mut() {
+    let mut message = DynamicMessage::new(complex_message().descriptor());
+
+    message["nested"]["int32"] = Value::I32(3);
+    message[5] = Value::EnumNumber(1);
+    message["my_enum"] = Value::List(vec![Value::EnumNumber(1)]);
+    message["my_enum"][0] = Value::EnumNumber(3);
+
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            nested: Some(Scalars {
+                int32: 3,
+                ..Default::default()
+            }),
+            optional_enum: 1,
+            my_enum: vec![3],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "message 'test.ComplexType' has no field named 'missing'")]
+fn index_missing_field() {
+    let _ = &complex_message()["missing"];
+}
+
+#[test]
+#[should_panic(expected = "message 'test.ComplexType' has no field with number 42")]
+fn index_missing_field_number() {
+    let _ = &complex_message()[42];
+}
+
+#[test]
+#[should_panic(expected = "field 'nested' of message 'test.ComplexType' is not set")]
+fn index_unset_message() {
+    let message = DynamicMessage::new(complex_message().descriptor());
+    let _ = &message["nested"];
+}
+
+#[test]
+#[should_panic(expected = "index 2 is out of bounds for list of length 2")]
+fn index_list_out_of_bounds() {
+    let _ = &complex_message()["my_enum"][2];
+}
+
+#[test]
+#[should_panic(expected = "map has no entry with key 'missing'")]
+fn index_map_missing_key() {
+    let _ = &complex_message()["string_map"]["missing"];
+}
+
+#[test]
+#[should_panic(expected = "cannot index a string with a string")]
+fn index_scalar() {
+    let _ = &complex_message()["nested"]["string"]["field"];
+}
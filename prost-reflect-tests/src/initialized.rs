@@ -0,0 +1,125 @@
+use prost::Message;
+use prost_reflect::{DecodeOptions, DynamicMessage, EncodeOptions, ReflectMessage, Value};
+
+use crate::{test_file_descriptor, ComplexType, ContainsGroup};
+
+fn group_message(url: Option<&str>) -> DynamicMessage {
+    let mut group = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("test2.ContainsGroup.GroupMessage")
+            .unwrap(),
+    );
+    group.set_field_by_name("id", Value::I32(1));
+    if let Some(url) = url {
+        group.set_field_by_name("url", Value::String(url.to_owned()));
+    }
+    group
+}
+
+fn contains_group(urls: &[Option<&str>]) -> DynamicMessage {
+    let mut message = DynamicMessage::new(ContainsGroup::default().descriptor());
+    message.set_field_by_name(
+        "groupmessage",
+        Value::List(
+            urls.iter()
+                .map(|&url| Value::Message(group_message(url)))
+                .collect(),
+        ),
+    );
+    message
+}
+
+#[test]
+fn is_initialized() {
+    assert!(DynamicMessage::new(ContainsGroup::default().descriptor()).is_initialized());
+    assert!(contains_group(&[Some("a"), Some("b")]).is_initialized());
+    assert!(!group_message(None).is_initialized());
+    assert!(!contains_group(&[Some("a"), None]).is_initialized());
+}
+
+#[test]
+fn proto3_is_initialized() {
+    assert!(ComplexType::default()
+        .transcode_to_dynamic()
+        .is_initialized());
+}
+
+#[test]
+fn check_initialized_reports_paths() {
+    let err = contains_group(&[None, Some("b"), None])
+        .check_initialized()
+        .unwrap_err();
+    assert_eq!(
+        err.missing_fields().collect::<Vec<_>>(),
+        ["groupmessage[0].url", "groupmessage[2].url"]
+    );
+    assert_eq!(
+        err.to_string(),
+        "message is missing required fields: groupmessage[0].url, groupmessage[2].url"
+    );
+}
+
+#[test]
+fn check_initialized_nested_paths() {
+    let pool = test_file_descriptor();
+    let message = |name: &str| DynamicMessage::new(pool.get_message_by_name(name).unwrap());
+
+    let mut inner = message("test2.RequiredInner");
+    inner.set_field_by_name("leaf", Value::Message(message("test2.RequiredLeaf")));
+    let mut next = message("test2.RequiredNested");
+    next.set_field_by_name("inner", Value::Message(inner));
+    let mut root = message("test2.RequiredNested");
+    root.set_field_by_name("next", Value::Message(next));
+
+    assert_eq!(
+        root.check_initialized()
+            .unwrap_err()
+            .missing_fields()
+            .collect::<Vec<_>>(),
+        ["inner", "next.inner.leaf.value"]
+    );
+}
+
+#[test]
+fn decode_check_initialized() {
+    let bytes = contains_group(&[Some("a"), None]).encode_to_vec();
+    let desc = ContainsGroup::default().descriptor();
+
+    let message = DynamicMessage::decode(desc.clone(), bytes.as_slice()).unwrap();
+    assert!(!message.is_initialized());
+
+    let err = DynamicMessage::decode_with_options(
+        desc.clone(),
+        bytes.as_slice(),
+        &DecodeOptions::new().check_initialized(true),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("groupmessage[1].url"));
+
+    let bytes = contains_group(&[Some("a")]).encode_to_vec();
+    DynamicMessage::decode_with_options(
+        desc,
+        bytes.as_slice(),
+        &DecodeOptions::new().check_initialized(true),
+    )
+    .unwrap();
+}
+
+#[test]
+fn encode_check_initialized() {
+    let message = contains_group(&[None]);
+    assert_eq!(
+        message
+            .encode_to_vec_with_options(&EncodeOptions::new())
+            .unwrap(),
+        message.encode_to_vec()
+    );
+
+    let err = message
+        .encode_to_vec_with_options(&EncodeOptions::new().check_initialized(true))
+        .unwrap_err();
+    assert_eq!(
+        err.missing_fields().collect::<Vec<_>>(),
+        ["groupmessage[0].url"]
+    );
+}
@@ -6,8 +6,12 @@ use std::{
 
 use proptest::{prelude::*, test_runner::TestCaseError};
 use prost::Message;
-use prost_reflect::{DeserializeOptions, DynamicMessage, ReflectMessage, SerializeOptions};
-use prost_types::FileDescriptorSet;
+use prost_reflect::{
+    BytesEncoding, DeserializeOptions, DurationEncoding, DynamicMessage, JsonDeserializeHandler,
+    JsonSerializeHandler, MessageDescriptor, NonFiniteFloatEncoding, ReflectMessage,
+    SerializeOptions, TimestampEncoding,
+};
+use prost_types::{FieldMask, FileDescriptorSet};
 use serde_json::json;
 
 use crate::{
@@ -384,49 +388,851 @@ fn serialize_skip_default_fields() {
     );
 
     assert_eq!(
-        value,
+        value,
+        json!({
+            "stringMap": {
+                "1": {
+                    "double": 0.0,
+                    "float": 0.0,
+                    "int32": 0,
+                    "int64": "0",
+                    "uint32": 0,
+                    "uint64": "0",
+                    "sint32": 0,
+                    "sint64": "0",
+                    "fixed32": 0,
+                    "fixed64": "0",
+                    "sfixed32": 0,
+                    "sfixed64": "0",
+                    "bool": false,
+                    "string": "",
+                    "bytes": "",
+                },
+            },
+            "intMap": {},
+            "myEnum": [],
+            "optionalEnum": "DEFAULT"
+        })
+    );
+}
+
+#[test]
+fn serialize_string_skip_default_fields() {
+    let value = Point::default();
+    let mut dynamic = DynamicMessage::new(value.descriptor());
+    dynamic.transcode_from(&value).unwrap();
+    let mut s = serde_json::Serializer::new(vec![]);
+
+    dynamic
+        .serialize_with_options(&mut s, &SerializeOptions::new().skip_default_fields(false))
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(s.into_inner()).unwrap(),
+        "{\"latitude\":0,\"longitude\":0}"
+    );
+}
+
+#[test]
+fn serialize_sort_keys() {
+    let value = ComplexType {
+        string_map: HashMap::from_iter([
+            (
+                "b".to_owned(),
+                Scalars {
+                    string: "x".to_owned(),
+                    bool: true,
+                    double: 1.5,
+                    ..Default::default()
+                },
+            ),
+            ("a".to_owned(), Scalars::default()),
+            ("c".to_owned(), Scalars::default()),
+        ]),
+        nested: Some(Scalars {
+            uint32: 1,
+            int64: -2,
+            ..Default::default()
+        }),
+        optional_enum: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let to_string = |options: &SerializeOptions| {
+        let mut s = serde_json::Serializer::new(vec![]);
+        value.serialize_with_options(&mut s, options).unwrap();
+        String::from_utf8(s.into_inner()).unwrap()
+    };
+
+    assert_eq!(
+        to_string(&SerializeOptions::new().sort_keys(true)),
+        "{\"nested\":{\"int64\":\"-2\",\"uint32\":1},\"optionalEnum\":\"FOO\",\"stringMap\":{\"a\":{},\"b\":{\"bool\":true,\"double\":1.5,\"string\":\"x\"},\"c\":{}}}"
+    );
+
+    let options = SerializeOptions::new().skip_default_fields(false);
+    let sorted = to_string(&options.clone().sort_keys(true));
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&sorted).unwrap(),
+        serde_json::from_str::<serde_json::Value>(&to_string(&options)).unwrap(),
+    );
+    assert!(sorted
+        .starts_with("{\"intMap\":{},\"myEnum\":[],\"nested\":{\"bool\":false,\"bytes\":\"\","));
+}
+
+#[test]
+fn serialize_field_mask() {
+    let value = ComplexType {
+        string_map: HashMap::from_iter([
+            (
+                "a".to_owned(),
+                Scalars {
+                    int32: 1,
+                    string: "x".to_owned(),
+                    ..Default::default()
+                },
+            ),
+            (
+                "b".to_owned(),
+                Scalars {
+                    int32: 2,
+                    ..Default::default()
+                },
+            ),
+        ]),
+        int_map: HashMap::from_iter([(3, Scalars::default())]),
+        nested: Some(Scalars {
+            double: 1.5,
+            int32: 4,
+            string: "y".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1],
+        optional_enum: 3,
+    };
+    let mask = |paths: &[&str]| FieldMask {
+        paths: paths.iter().map(|&path| path.to_owned()).collect(),
+    };
+
+    assert_eq!(
+        to_json_with_options(
+            &value,
+            &SerializeOptions::new().field_mask(mask(&["optional_enum", "nested.int32"]))
+        ),
+        json!({
+            "nested": { "int32": 4 },
+            "optionalEnum": "BAR",
+        })
+    );
+    assert_eq!(
+        to_json_with_options(
+            &value,
+            &SerializeOptions::new().field_mask(mask(&["nested", "nested.int32"]))
+        ),
+        json!({
+            "nested": { "double": 1.5, "int32": 4, "string": "y" },
+        })
+    );
+    assert_eq!(
+        to_json_with_options(
+            &value,
+            &SerializeOptions::new().field_mask(mask(&["string_map.string", "*.int32"]))
+        ),
+        json!({
+            "stringMap": {
+                "a": { "string": "x" },
+                "b": {},
+            },
+            "intMap": { "3": {} },
+            "nested": { "int32": 4 },
+            "myEnum": ["FOO"],
+            "optionalEnum": "BAR",
+        })
+    );
+    assert_eq!(
+        to_json_with_options(
+            &value,
+            &SerializeOptions::new()
+                .skip_default_fields(false)
+                .field_mask(mask(&["nested.bool", "my_enum"]))
+        ),
+        json!({
+            "nested": { "bool": false },
+            "myEnum": ["FOO"],
+        })
+    );
+    assert_eq!(
+        to_json_with_options(&value, &SerializeOptions::new().field_mask(mask(&[]))),
+        to_json(&value)
+    );
+}
+
+#[test]
+fn serialize_bytes_encoding() {
+    let value = WellKnownTypes {
+        bytes: Some(vec![0xfb, 0xff, 0x01]),
+        ..Default::default()
+    };
+    let scalars = Scalars {
+        bytes: vec![0xfb, 0xff, 0x01],
+        ..Default::default()
+    };
+
+    let options = SerializeOptions::new().bytes_encoding(BytesEncoding::Base64);
+    assert_eq!(
+        to_json_with_options(&scalars, &options),
+        json!({ "bytes": "+/8B" })
+    );
+    assert_eq!(
+        to_json_with_options(&value, &options),
+        json!({ "bytes": "+/8B" })
+    );
+
+    let options = SerializeOptions::new().bytes_encoding(BytesEncoding::Base64UrlSafe);
+    assert_eq!(
+        to_json_with_options(&scalars, &options),
+        json!({ "bytes": "-_8B" })
+    );
+    assert_eq!(
+        to_json_with_options(&value, &options),
+        json!({ "bytes": "-_8B" })
+    );
+
+    let options = SerializeOptions::new().bytes_encoding(BytesEncoding::Hex);
+    assert_eq!(
+        to_json_with_options(&scalars, &options),
+        json!({ "bytes": "fbff01" })
+    );
+    assert_eq!(
+        to_json_with_options(&value, &options),
+        json!({ "bytes": "fbff01" })
+    );
+}
+
+#[test]
+fn deserialize_bytes_encoding() {
+    let expected = Scalars {
+        bytes: vec![0xfb, 0xff, 0x01],
+        ..Default::default()
+    };
+
+    for encoding in [BytesEncoding::Base64, BytesEncoding::Base64UrlSafe] {
+        let options = DeserializeOptions::new().bytes_encoding(encoding);
+        for json in ["+/8B", "-_8B"] {
+            let value: Scalars =
+                from_json_with_options(json!({ "bytes": json }), "test.Scalars", &options);
+            assert_eq!(value, expected);
+        }
+    }
+
+    let options = DeserializeOptions::new().bytes_encoding(BytesEncoding::Hex);
+    for json in ["fbff01", "FBFF01", "+/8B", "-_8B"] {
+        let value: Scalars =
+            from_json_with_options(json!({ "bytes": json }), "test.Scalars", &options);
+        assert_eq!(value, expected);
+    }
+
+    let value: WellKnownTypes = from_json_with_options(
+        json!({ "bytes": "fbff01" }),
+        "test.WellKnownTypes",
+        &options,
+    );
+    assert_eq!(value.bytes, Some(vec![0xfb, 0xff, 0x01]));
+
+    // Hex strings are interpreted as base64 unless the hex encoding is chosen.
+    let value: Scalars = from_json_with_options(
+        json!({ "bytes": "fbff01" }),
+        "test.Scalars",
+        &DeserializeOptions::new(),
+    );
+    assert_eq!(value.bytes, vec![0x7d, 0xb7, 0xdf, 0xd3]);
+}
+
+#[test]
+fn serialize_timestamp_encoding() {
+    let timestamp = |seconds, nanos| WellKnownTypes {
+        timestamp: Some(prost_types::Timestamp { seconds, nanos }),
+        ..Default::default()
+    };
+
+    let options = SerializeOptions::new().timestamp_encoding(TimestampEncoding::Rfc3339);
+    assert_eq!(
+        to_json_with_options(&timestamp(63_108_020, 21_000_000), &options),
+        json!({ "timestamp": "1972-01-01T10:00:20.021Z" })
+    );
+
+    let options = SerializeOptions::new().timestamp_encoding(TimestampEncoding::EpochSeconds);
+    assert_eq!(
+        to_json_with_options(&timestamp(63_108_020, 0), &options),
+        json!({ "timestamp": 63_108_020 })
+    );
+    assert_eq!(
+        to_json_with_options(&timestamp(63_108_020, 500_000_000), &options),
+        json!({ "timestamp": 63_108_020.5 })
+    );
+    assert_eq!(
+        to_json_with_options(&timestamp(-1, 0), &options),
+        json!({ "timestamp": -1 })
+    );
+
+    let options = SerializeOptions::new().timestamp_encoding(TimestampEncoding::EpochMillis);
+    assert_eq!(
+        to_json_with_options(&timestamp(63_108_020, 21_000_000), &options),
+        json!({ "timestamp": 63_108_020_021i64 })
+    );
+    assert_eq!(
+        to_json_with_options(&timestamp(1, 500_000), &options),
+        json!({ "timestamp": 1000.5 })
+    );
+    assert_eq!(
+        to_json_with_options(&timestamp(-2, 999_000_000), &options),
+        json!({ "timestamp": -1001 })
+    );
+}
+
+#[test]
+fn deserialize_timestamp_encoding() {
+    let timestamp = |json: serde_json::Value, encoding| {
+        from_json_with_options::<WellKnownTypes>(
+            json!({ "timestamp": json }),
+            "test.WellKnownTypes",
+            &DeserializeOptions::new().timestamp_encoding(encoding),
+        )
+        .timestamp
+        .unwrap()
+    };
+    let expected = |seconds, nanos| prost_types::Timestamp { seconds, nanos };
+
+    for encoding in [
+        TimestampEncoding::Rfc3339,
+        TimestampEncoding::EpochSeconds,
+        TimestampEncoding::EpochMillis,
+    ] {
+        assert_eq!(
+            timestamp(json!("1972-01-01T10:00:20.021Z"), encoding),
+            expected(63_108_020, 21_000_000)
+        );
+    }
+
+    assert_eq!(
+        timestamp(json!(63_108_020), TimestampEncoding::EpochSeconds),
+        expected(63_108_020, 0)
+    );
+    assert_eq!(
+        timestamp(json!(63_108_020.5), TimestampEncoding::EpochSeconds),
+        expected(63_108_020, 500_000_000)
+    );
+    assert_eq!(
+        timestamp(json!(-1.5), TimestampEncoding::EpochSeconds),
+        expected(-2, 500_000_000)
+    );
+    assert_eq!(
+        timestamp(json!(63_108_020_021i64), TimestampEncoding::EpochMillis),
+        expected(63_108_020, 21_000_000)
+    );
+    assert_eq!(
+        timestamp(json!(-1001), TimestampEncoding::EpochMillis),
+        expected(-2, 999_000_000)
+    );
+    assert_eq!(
+        timestamp(json!(1000.5), TimestampEncoding::EpochMillis),
+        expected(1, 500_000)
+    );
+
+    let err = try_from_json_string_with_options(
+        r#"{ "timestamp": 1000 }"#,
+        "test.WellKnownTypes",
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().starts_with(
+            "timestamp: invalid type: integer `1000`, expected a rfc3339 timestamp string"
+        ),
+        "{}",
+        err
+    );
+
+    let err = try_from_json_string_with_options(
+        r#"{ "timestamp": 1e20 }"#,
+        "test.WellKnownTypes",
+        &DeserializeOptions::new().timestamp_encoding(TimestampEncoding::EpochSeconds),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("timestamp: timestamp out of range"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn serialize_duration_encoding() {
+    let duration = |seconds, nanos| WellKnownTypes {
+        duration: Some(prost_types::Duration { seconds, nanos }),
+        ..Default::default()
+    };
+
+    let options = SerializeOptions::new().duration_encoding(DurationEncoding::String);
+    assert_eq!(
+        to_json_with_options(&duration(1, 500_000_000), &options),
+        json!({ "duration": "1.500s" })
+    );
+
+    let options = SerializeOptions::new().duration_encoding(DurationEncoding::Seconds);
+    assert_eq!(
+        to_json_with_options(&duration(3, 0), &options),
+        json!({ "duration": 3 })
+    );
+    assert_eq!(
+        to_json_with_options(&duration(1, 500_000_000), &options),
+        json!({ "duration": 1.5 })
+    );
+    assert_eq!(
+        to_json_with_options(&duration(-1, -500_000_000), &options),
+        json!({ "duration": -1.5 })
+    );
+
+    let options = SerializeOptions::new().duration_encoding(DurationEncoding::Object);
+    assert_eq!(
+        to_json_with_options(&duration(-1, -500_000_000), &options),
+        json!({ "duration": { "seconds": -1, "nanos": -500_000_000 } })
+    );
+}
+
+#[test]
+fn deserialize_duration_encoding() {
+    let duration = |json: serde_json::Value, encoding| {
+        from_json_with_options::<WellKnownTypes>(
+            json!({ "duration": json }),
+            "test.WellKnownTypes",
+            &DeserializeOptions::new().duration_encoding(encoding),
+        )
+        .duration
+        .unwrap()
+    };
+    let expected = |seconds, nanos| prost_types::Duration { seconds, nanos };
+
+    for encoding in [
+        DurationEncoding::String,
+        DurationEncoding::Seconds,
+        DurationEncoding::Object,
+    ] {
+        assert_eq!(duration(json!("1.5s"), encoding), expected(1, 500_000_000));
+    }
+
+    for encoding in [DurationEncoding::Seconds, DurationEncoding::Object] {
+        assert_eq!(duration(json!(3), encoding), expected(3, 0));
+        assert_eq!(duration(json!(-1.5), encoding), expected(-1, -500_000_000));
+        assert_eq!(
+            duration(json!({ "seconds": 1, "nanos": 500_000_000 }), encoding),
+            expected(1, 500_000_000)
+        );
+        assert_eq!(
+            duration(json!({ "seconds": "-2" }), encoding),
+            expected(-2, 0)
+        );
+    }
+
+    let err = try_from_json_string_with_options(
+        r#"{ "duration": 1.5 }"#,
+        "test.WellKnownTypes",
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().starts_with(
+            "duration: invalid type: floating point `1.5`, expected a duration string"
+        ),
+        "{}",
+        err
+    );
+
+    let options = DeserializeOptions::new().duration_encoding(DurationEncoding::Object);
+    for (json, message) in [
+        (r#"{ "duration": 1e12 }"#, "duration out of range"),
+        (
+            r#"{ "duration": { "seconds": 1, "nanos": -1 } }"#,
+            "inconsistent signs for duration",
+        ),
+        (
+            r#"{ "duration": { "seconds": 1, "nanos": 1000000000 } }"#,
+            "duration out of range",
+        ),
+        (
+            r#"{ "duration": { "secs": 1 } }"#,
+            "unknown field `secs`, expected `seconds` or `nanos`",
+        ),
+    ] {
+        let err =
+            try_from_json_string_with_options(json, "test.WellKnownTypes", &options).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with(&format!("duration: {}", message)),
+            "{}",
+            err
+        );
+    }
+}
+
+#[test]
+fn serialize_non_finite_floats() {
+    let value = Scalars {
+        double: f64::NAN,
+        float: f32::NEG_INFINITY,
+        ..Default::default()
+    };
+    let wkt = WellKnownTypes {
+        double: Some(f64::INFINITY),
+        float: Some(f32::NAN),
+        ..Default::default()
+    };
+
+    let options = SerializeOptions::new().non_finite_floats(NonFiniteFloatEncoding::String);
+    assert_eq!(
+        to_json_with_options(&value, &options),
+        json!({ "double": "NaN", "float": "-Infinity" })
+    );
+    assert_eq!(
+        to_json_with_options(&wkt, &options),
+        json!({ "double": "Infinity", "float": "NaN" })
+    );
+
+    let options = SerializeOptions::new().non_finite_floats(NonFiniteFloatEncoding::Null);
+    assert_eq!(
+        to_json_with_options(&value, &options),
+        json!({ "double": null, "float": null })
+    );
+    assert_eq!(
+        to_json_with_options(&wkt, &options),
+        json!({ "double": null, "float": null })
+    );
+
+    let options = SerializeOptions::new().non_finite_floats(NonFiniteFloatEncoding::Error);
+    let err = value
+        .transcode_to_dynamic()
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "cannot serialize non-finite value 'NaN'");
+}
+
+#[test]
+fn serialize_float_precision() {
+    let value = Scalars {
+        double: 1.2345678901234567,
+        float: 9.876543,
+        ..Default::default()
+    };
+    let wkt = WellKnownTypes {
+        double: Some(123456.789),
+        float: Some(0.000123456),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        to_json_string_with_options(&value, &SerializeOptions::new()),
+        r#"{"double":1.2345678901234567,"float":9.876543}"#
+    );
+    assert_eq!(
+        to_json_string_with_options(&value, &SerializeOptions::new().float_precision(Some(3))),
+        r#"{"double":1.23,"float":9.88}"#
+    );
+    assert_eq!(
+        to_json_string_with_options(&wkt, &SerializeOptions::new().float_precision(Some(2))),
+        r#"{"float":0.00012,"double":120000.0}"#
+    );
+    assert_eq!(
+        to_json_string_with_options(&value, &SerializeOptions::new().float_precision(Some(0))),
+        r#"{"double":1.0,"float":10.0}"#
+    );
+}
+
+#[test]
+fn deserialize_ignore_unknown_enum_values() {
+    let options = DeserializeOptions::new().ignore_unknown_enum_values(true);
+
+    let value: ComplexType = from_json_with_options(
+        json!({
+            "myEnum": ["FOO", "UNKNOWN", 3, 5, 10000000000i64, "NEG"],
+            "optionalEnum": "UNKNOWN",
+        }),
+        "test.ComplexType",
+        &options,
+    );
+    assert_eq!(
+        value,
+        ComplexType {
+            my_enum: vec![1, 3, 5, -4],
+            ..Default::default()
+        }
+    );
+
+    let value: ComplexType = from_json_with_options(
+        json!({ "optionalEnum": -10000000000i64 }),
+        "test.ComplexType",
+        &options,
+    );
+    assert_eq!(value, ComplexType::default());
+
+    let mut message = ComplexType {
+        optional_enum: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    message
+        .apply_merge_patch_with_options(json!({ "optionalEnum": "UNKNOWN" }), &options)
+        .unwrap();
+    assert_eq!(
+        message
+            .get_field_by_name("optional_enum")
+            .unwrap()
+            .as_enum_number(),
+        Some(1)
+    );
+
+    let err = try_from_json_string_with_options(
+        r#"{ "optionalEnum": "UNKNOWN" }"#,
+        "test.ComplexType",
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("optionalEnum: unrecognized enum value 'UNKNOWN'"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn deserialize_field_name_matching() {
+    let json = r#"{ "INT32": 1, "Uint64": "2", "flag": true, "Text": "x" }"#;
+
+    let err = try_from_json_string_with_options(json, "test.Scalars", &DeserializeOptions::new())
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("unrecognized field name 'INT32'"),
+        "{}",
+        err
+    );
+
+    let options = DeserializeOptions::new()
+        .case_insensitive_field_names(true)
+        .field_alias("test.Scalars.bool", "flag")
+        .field_alias("test.Scalars.string", "text");
+    let value: Scalars = from_json_string_with_options(json, "test.Scalars", &options);
+    assert_eq!(
+        value,
+        Scalars {
+            int32: 1,
+            uint64: 2,
+            bool: true,
+            string: "x".to_owned(),
+            ..Default::default()
+        }
+    );
+
+    // Aliases only apply to the message type they are registered for.
+    let options = DeserializeOptions::new().field_alias("test.Point.latitude", "lat");
+    let err =
+        try_from_json_string_with_options(r#"{ "lat": 1 }"#, "test.Scalars", &options).unwrap_err();
+    assert!(
+        err.to_string().starts_with("unrecognized field name 'lat'"),
+        "{}",
+        err
+    );
+    assert_eq!(
+        from_json_string_with_options::<Point>(r#"{ "lat": 1 }"#, "test.Point", &options),
+        Point {
+            latitude: 1,
+            ..Default::default()
+        }
+    );
+
+    let mut message = Scalars::default().transcode_to_dynamic();
+    message
+        .apply_merge_patch_with_options(
+            json!({ "DOUBLE": 1.5 }),
+            &DeserializeOptions::new().case_insensitive_field_names(true),
+        )
+        .unwrap();
+    assert_eq!(
+        message.get_field_by_name("double").unwrap().as_f64(),
+        Some(1.5)
+    );
+}
+
+#[test]
+fn deserialize_capture_unknown_fields() {
+    let json = json!({
+        "nested": { "int32": 1, "extra": [1, "two", { "three": null }] },
+        "other": "x",
+    });
+
+    let options = DeserializeOptions::new().deny_unknown_fields(false);
+    let message = DynamicMessage::deserialize_with_options(
+        test_file_descriptor()
+            .get_message_by_name("test.ComplexType")
+            .unwrap(),
+        json.clone(),
+        &options,
+    )
+    .unwrap();
+    assert!(message.unknown_json_fields().is_empty());
+
+    let options = options.capture_unknown_fields(true);
+    let mut message = DynamicMessage::deserialize_with_options(
+        test_file_descriptor()
+            .get_message_by_name("test.ComplexType")
+            .unwrap(),
+        json.clone(),
+        &options,
+    )
+    .unwrap();
+
+    let fields = message.unknown_json_fields();
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].name(), "other");
+    assert_eq!(serde_json::to_value(fields[0].value()).unwrap(), json!("x"));
+
+    let nested = message.get_field_by_name("nested").unwrap();
+    let fields = nested.as_message().unwrap().unknown_json_fields();
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].name(), "extra");
+
+    assert_eq!(serde_json::to_value(&message).unwrap(), json);
+
+    message
+        .apply_merge_patch_with_options(json!({ "other": 5 }), &options)
+        .unwrap();
+    assert_eq!(message.unknown_json_fields().len(), 1);
+    assert_eq!(
+        serde_json::to_value(message.unknown_json_fields()[0].value()).unwrap(),
+        json!(5)
+    );
+
+    message.clear_unknown_json_fields();
+    assert!(message.unknown_json_fields().is_empty());
+}
+
+#[test]
+fn serialize_not_human_readable() {
+    let value = Scalars {
+        int64: -1,
+        uint64: 2,
+        bytes: vec![1, 2, 3],
+        ..Default::default()
+    };
+    let wkt = WellKnownTypes {
+        timestamp: Some(prost_types::Timestamp {
+            seconds: 63_108_020,
+            nanos: 21_000_000,
+        }),
+        duration: Some(prost_types::Duration {
+            seconds: -1,
+            nanos: -500_000_000,
+        }),
+        int64: Some(3),
+        bytes: Some(vec![4]),
+        ..Default::default()
+    };
+
+    let options = SerializeOptions::new().human_readable(Some(false));
+    assert_eq!(
+        to_json_with_options(&value, &options),
+        json!({ "int64": -1, "uint64": 2, "bytes": [1, 2, 3] })
+    );
+    assert_eq!(
+        to_json_with_options(&wkt, &options),
         json!({
-            "stringMap": {
-                "1": {
-                    "double": 0.0,
-                    "float": 0.0,
-                    "int32": 0,
-                    "int64": "0",
-                    "uint32": 0,
-                    "uint64": "0",
-                    "sint32": 0,
-                    "sint64": "0",
-                    "fixed32": 0,
-                    "fixed64": "0",
-                    "sfixed32": 0,
-                    "sfixed64": "0",
-                    "bool": false,
-                    "string": "",
-                    "bytes": "",
-                },
-            },
-            "intMap": {},
-            "myEnum": [],
-            "optionalEnum": "DEFAULT"
+            "timestamp": { "seconds": 63_108_020, "nanos": 21_000_000 },
+            "duration": { "seconds": -1, "nanos": -500_000_000 },
+            "int64": 3,
+            "bytes": [4],
+        })
+    );
+    assert_eq!(
+        to_json_with_options(&wkt, &options.clone().sort_keys(true)),
+        json!({
+            "bytes": [4],
+            "duration": { "nanos": -500_000_000, "seconds": -1 },
+            "int64": 3,
+            "timestamp": { "nanos": 21_000_000, "seconds": 63_108_020 },
+        })
+    );
+
+    let de_options = DeserializeOptions::new().human_readable(Some(false));
+    roundtrip_json_with_options(&value, &options, &de_options).unwrap();
+    roundtrip_json_with_options(&wkt, &options, &de_options).unwrap();
+
+    let timestamp: WellKnownTypes = from_json_with_options(
+        json!({ "timestamp": [1, 2] }),
+        "test.WellKnownTypes",
+        &de_options,
+    );
+    assert_eq!(
+        timestamp.timestamp,
+        Some(prost_types::Timestamp {
+            seconds: 1,
+            nanos: 2
         })
     );
+
+    let err = try_from_json_string_with_options(
+        r#"{ "timestamp": { "seconds": 1, "nanos": -1 } }"#,
+        "test.WellKnownTypes",
+        &de_options,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("timestamp: timestamp out of range"),
+        "{}",
+        err
+    );
 }
 
 #[test]
-fn serialize_string_skip_default_fields() {
-    let value = Point::default();
-    let mut dynamic = DynamicMessage::new(value.descriptor());
-    dynamic.transcode_from(&value).unwrap();
-    let mut s = serde_json::Serializer::new(vec![]);
+fn json_value_conversions() {
+    let value = ComplexType {
+        nested: Some(Scalars {
+            int64: 5,
+            ..Default::default()
+        }),
+        optional_enum: 3,
+        ..Default::default()
+    };
+    let message = value.transcode_to_dynamic();
 
-    dynamic
-        .serialize_with_options(&mut s, &SerializeOptions::new().skip_default_fields(false))
-        .unwrap();
+    let json = message.to_json_value(&SerializeOptions::new()).unwrap();
+    assert_eq!(json, to_json(&value));
+    assert_eq!(
+        json,
+        json!({ "nested": { "int64": "5" }, "optionalEnum": "BAR" })
+    );
 
+    let json = message
+        .to_json_value(&SerializeOptions::new().stringify_64_bit_integers(false))
+        .unwrap();
     assert_eq!(
-        String::from_utf8(s.into_inner()).unwrap(),
-        "{\"latitude\":0,\"longitude\":0}"
+        json,
+        json!({ "nested": { "int64": 5 }, "optionalEnum": "BAR" })
     );
+
+    let roundtripped =
+        DynamicMessage::from_json_value(message.descriptor(), json, &DeserializeOptions::new())
+            .unwrap();
+    assert_eq!(roundtripped, message);
+
+    let err = DynamicMessage::from_json_value(
+        message.descriptor(),
+        json!({ "unknown": 1 }),
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "unrecognized field name 'unknown'");
 }
 
 #[test]
@@ -1318,6 +2124,162 @@ proptest! {
         let _ = try_from_json_string_with_options(&json, ".test.WellKnownTypes", &DeserializeOptions::default());
         let _ = try_from_json_string_with_options(&json, ".test.WellKnownTypes", &DeserializeOptions::default().deny_unknown_fields(false));
     }
+
+    #[test]
+    fn serialize_encoded_arb_scalars(message: Scalars) {
+        check_serialize_encoded(message.descriptor(), &message.encode_to_vec())?;
+    }
+
+    #[test]
+    fn serialize_encoded_arb_scalar_arrays(message: ScalarArrays) {
+        check_serialize_encoded(message.descriptor(), &message.encode_to_vec())?;
+    }
+
+    #[test]
+    fn serialize_encoded_arb_complex_type(message: ComplexType, other: ComplexType) {
+        check_serialize_encoded(message.descriptor(), &message.encode_to_vec())?;
+        let mut concatenated = message.encode_to_vec();
+        concatenated.extend(other.encode_to_vec());
+        check_serialize_encoded(message.descriptor(), &concatenated)?;
+    }
+
+    #[test]
+    fn serialize_encoded_arb_well_known_types(message: WellKnownTypes) {
+        check_serialize_encoded(message.descriptor(), &message.encode_to_vec())?;
+    }
+}
+
+#[test]
+fn merge_patch() {
+    let mut message = ComplexType {
+        string_map: HashMap::from_iter([
+            (
+                "a".to_owned(),
+                Scalars {
+                    int32: 1,
+                    string: "a".to_owned(),
+                    ..Default::default()
+                },
+            ),
+            (
+                "b".to_owned(),
+                Scalars {
+                    int32: 2,
+                    ..Default::default()
+                },
+            ),
+        ]),
+        int_map: HashMap::from_iter([(1, Scalars::default())]),
+        nested: Some(Scalars {
+            int32: 1,
+            string: "nested".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        optional_enum: 1,
+    }
+    .transcode_to_dynamic();
+
+    message
+        .apply_merge_patch(json!({
+            "stringMap": {
+                "a": { "int32": 5, "string": null },
+                "b": null,
+                "c": { "double": 1.5 },
+            },
+            "int_map": null,
+            "nested": { "int64": "7" },
+            "myEnum": ["NEG"],
+            "optionalEnum": null,
+        }))
+        .unwrap();
+
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            string_map: HashMap::from_iter([
+                (
+                    "a".to_owned(),
+                    Scalars {
+                        int32: 5,
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "c".to_owned(),
+                    Scalars {
+                        double: 1.5,
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            int_map: HashMap::new(),
+            nested: Some(Scalars {
+                int32: 1,
+                int64: 7,
+                string: "nested".to_owned(),
+                ..Default::default()
+            }),
+            my_enum: vec![-4],
+            optional_enum: 0,
+        }
+    );
+}
+
+#[test]
+fn merge_patch_oneof_and_well_known_types() {
+    let mut message =
+        from_json::<MessageWithOneof>(json!({ "oneofField1": "a" }), "test.MessageWithOneof")
+            .transcode_to_dynamic();
+    message
+        .apply_merge_patch(json!({ "oneofField2": 5 }))
+        .unwrap();
+    assert_eq!(
+        message.transcode_to::<MessageWithOneof>().unwrap(),
+        MessageWithOneof {
+            test_oneof: Some(message_with_oneof::TestOneof::OneofField2(5)),
+        }
+    );
+
+    let mut message = from_json::<WellKnownTypes>(
+        json!({ "struct": { "a": 1, "b": 2 } }),
+        "test.WellKnownTypes",
+    )
+    .transcode_to_dynamic();
+    message
+        .apply_merge_patch(json!({ "struct": { "c": 3 } }))
+        .unwrap();
+    assert_eq!(
+        to_json(&message.transcode_to::<WellKnownTypes>().unwrap())["struct"],
+        json!({ "c": 3.0 })
+    );
+}
+
+#[test]
+fn merge_patch_errors() {
+    let mut message = Point::default().transcode_to_dynamic();
+    assert_eq!(
+        message
+            .apply_merge_patch(json!({ "latitude": 1, "unknown": 2 }))
+            .unwrap_err()
+            .to_string(),
+        "unrecognized field name 'unknown'"
+    );
+    assert!(message.apply_merge_patch(json!([1, 2])).is_err());
+
+    message
+        .apply_merge_patch_with_options(
+            json!({ "longitude": 3, "unknown": 2 }),
+            &DeserializeOptions::new().deny_unknown_fields(false),
+        )
+        .unwrap();
+    assert_eq!(
+        message.transcode_to::<Point>().unwrap(),
+        Point {
+            latitude: 1,
+            longitude: 3,
+        }
+    );
 }
 
 #[test]
@@ -1469,3 +2431,337 @@ where
     prop_assert_eq!(message, &roundtripped_message);
     Ok(())
 }
+
+fn check_serialize_encoded(desc: MessageDescriptor, bytes: &[u8]) -> Result<(), TestCaseError> {
+    let message = DynamicMessage::decode(desc.clone(), bytes).unwrap();
+    for options in [
+        SerializeOptions::new(),
+        SerializeOptions::new().skip_default_fields(false),
+        SerializeOptions::new()
+            .use_enum_numbers(true)
+            .use_proto_field_name(true)
+            .stringify_64_bit_integers(false),
+        SerializeOptions::new().field_mask(FieldMask {
+            paths: vec!["nested.int32".to_owned(), "my_enum".to_owned()],
+        }),
+    ] {
+        let expected = message
+            .serialize_with_options(serde_json::value::Serializer, &options)
+            .unwrap();
+        let actual = DynamicMessage::serialize_encoded(
+            desc.clone(),
+            bytes,
+            serde_json::value::Serializer,
+            &options,
+        )
+        .unwrap();
+        prop_assert_eq!(expected, actual);
+    }
+
+    let options = SerializeOptions::new().sort_keys(true);
+    let mut expected = serde_json::Serializer::new(Vec::new());
+    message
+        .serialize_with_options(&mut expected, &options)
+        .unwrap();
+    let mut actual = serde_json::Serializer::new(Vec::new());
+    DynamicMessage::serialize_encoded(desc, bytes, &mut actual, &options).unwrap();
+    prop_assert_eq!(expected.into_inner(), actual.into_inner());
+    Ok(())
+}
+
+#[test]
+fn serialize_encoded_oneof() {
+    let mut bytes = MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField1("foo".to_owned())),
+    }
+    .encode_to_vec();
+    bytes.extend(
+        MessageWithOneof {
+            test_oneof: Some(message_with_oneof::TestOneof::OneofField2(0)),
+        }
+        .encode_to_vec(),
+    );
+
+    let json = DynamicMessage::serialize_encoded(
+        MessageWithOneof::default().descriptor(),
+        &bytes,
+        serde_json::value::Serializer,
+        &SerializeOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(json, json!({ "oneofField2": 0 }));
+    check_serialize_encoded(MessageWithOneof::default().descriptor(), &bytes).unwrap();
+}
+
+#[test]
+fn serialize_encoded_invalid() {
+    let mut bytes = ComplexType {
+        nested: Some(Scalars {
+            string: "foo".to_owned(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    bytes.pop();
+
+    let err = DynamicMessage::serialize_encoded(
+        ComplexType::default().descriptor(),
+        &bytes,
+        serde_json::value::Serializer,
+        &SerializeOptions::new(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("buffer underflow"), "{}", err);
+}
+
+#[test]
+fn struct_json_conversions() {
+    let value = json!({
+        "number": 1.5,
+        "string": "foo",
+        "bool": true,
+        "null": null,
+        "list": [1.0, "bar", [], {}],
+        "nested": { "a": { "b": false } },
+    });
+
+    let message = prost_reflect::json::to_struct(value.as_object().unwrap().clone());
+    assert_eq!(
+        message.fields["number"].kind,
+        Some(prost_types::value::Kind::NumberValue(1.5))
+    );
+    assert_eq!(
+        message.fields["null"].kind,
+        Some(prost_types::value::Kind::NullValue(0))
+    );
+    assert_eq!(
+        serde_json::Value::Object(prost_reflect::json::from_struct(&message).unwrap()),
+        value
+    );
+
+    let list = prost_reflect::json::to_list_value(vec![json!(1.0), json!(null)]);
+    assert_eq!(
+        prost_reflect::json::from_list_value(&list).unwrap(),
+        vec![json!(1.0), json!(null)]
+    );
+    assert_eq!(
+        prost_reflect::json::from_value(&prost_types::Value { kind: None }).unwrap(),
+        json!(null)
+    );
+
+    let err = prost_reflect::json::from_value(&prost_types::Value {
+        kind: Some(prost_types::value::Kind::NumberValue(f64::NAN)),
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot serialize non-finite double in google.protobuf.Value"
+    );
+}
+
+#[test]
+fn struct_message_json_conversions() {
+    let message = WellKnownTypes {
+        r#struct: Some(prost_types::Struct {
+            fields: BTreeMap::from_iter([(
+                "number".to_owned(),
+                prost_types::Value {
+                    kind: Some(prost_types::value::Kind::NumberValue(42.)),
+                },
+            )]),
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let field = message.get_field_by_name("struct").unwrap();
+    assert_eq!(
+        prost_reflect::json::from_message(&field).unwrap(),
+        json!({ "number": 42.0 })
+    );
+
+    let value = prost_reflect::json::to_struct_message(
+        json!({ "number": 42.0 }).as_object().unwrap().clone(),
+    );
+    assert_eq!(
+        value
+            .as_message()
+            .unwrap()
+            .transcode_to::<prost_types::Struct>(),
+        field
+            .as_message()
+            .unwrap()
+            .transcode_to::<prost_types::Struct>()
+    );
+
+    let err =
+        prost_reflect::json::from_message(&prost_reflect::Value::Message(message)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a google.protobuf.Struct, google.protobuf.Value or google.protobuf.ListValue message, found 'test.WellKnownTypes'"
+    );
+}
+
+#[derive(Debug)]
+struct PointHandler;
+
+impl JsonSerializeHandler for PointHandler {
+    fn serialize(
+        &self,
+        message: &DynamicMessage,
+        _: &SerializeOptions,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+        let point: Point = message.transcode_to()?;
+        Ok(json!(format!("{},{}", point.latitude, point.longitude)))
+    }
+}
+
+impl JsonDeserializeHandler for PointHandler {
+    fn deserialize(
+        &self,
+        desc: &MessageDescriptor,
+        value: serde_json::Value,
+        _: &DeserializeOptions,
+    ) -> Result<DynamicMessage, Box<dyn std::error::Error + Send + Sync>> {
+        let (latitude, longitude) = value
+            .as_str()
+            .and_then(|s| s.split_once(','))
+            .ok_or("expected a string 'latitude,longitude'")?;
+        let mut message = DynamicMessage::new(desc.clone());
+        message.transcode_from(&Point {
+            latitude: latitude.parse()?,
+            longitude: longitude.parse()?,
+        })?;
+        Ok(message)
+    }
+}
+
+#[derive(Debug)]
+struct TimestampHandler;
+
+impl JsonSerializeHandler for TimestampHandler {
+    fn serialize(
+        &self,
+        message: &DynamicMessage,
+        _: &SerializeOptions,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+        let timestamp: prost_types::Timestamp = message.transcode_to()?;
+        Ok(json!([timestamp.seconds, timestamp.nanos]))
+    }
+}
+
+#[test]
+fn serialize_json_handler() {
+    let options = SerializeOptions::new().json_handler("test.Point", PointHandler);
+    let value = Point {
+        latitude: 1,
+        longitude: -2,
+    };
+    assert_eq!(to_json_with_options(&value, &options), json!("1,-2"));
+    assert_eq!(to_json(&value), json!({ "latitude": 1, "longitude": -2 }));
+
+    let options =
+        SerializeOptions::new().json_handler("google.protobuf.Timestamp", TimestampHandler);
+    let value = WellKnownTypes {
+        timestamp: Some(prost_types::Timestamp {
+            seconds: 63,
+            nanos: 5,
+        }),
+        ..Default::default()
+    };
+    assert_eq!(
+        to_json_with_options(&value, &options),
+        json!({ "timestamp": [63, 5] })
+    );
+}
+
+#[test]
+fn deserialize_json_handler() {
+    let options = DeserializeOptions::new().json_handler("test.Point", PointHandler);
+    let value: Point = from_json_with_options(json!("1,-2"), "test.Point", &options);
+    assert_eq!(
+        value,
+        Point {
+            latitude: 1,
+            longitude: -2,
+        }
+    );
+
+    let err = try_from_json_string_with_options("\"1\"", "test.Point", &options).unwrap_err();
+    assert_eq!(err.to_string(), "expected a string 'latitude,longitude'");
+
+    let options = SerializeOptions::new().json_handler("test.Point", PointHandler);
+    roundtrip_json_with_options(
+        &Point {
+            latitude: 3,
+            longitude: 4,
+        },
+        &options,
+        &DeserializeOptions::new().json_handler("test.Point", PointHandler),
+    )
+    .unwrap();
+}
+
+#[test]
+fn deserialize_error_path() {
+    let options = DeserializeOptions::new();
+    for (json, message_name, expected) in [
+        (
+            r#"{ "nested": { "int32": "foo" } }"#,
+            "test.ComplexType",
+            "nested.int32: invalid digit found in string",
+        ),
+        (
+            r#"{ "myEnum": ["FOO", "BAR", "TLS_1_4"] }"#,
+            "test.ComplexType",
+            "myEnum[2]: unrecognized enum value 'TLS_1_4'",
+        ),
+        (
+            r#"{ "stringMap": { "a.b": { "bool": 1 } } }"#,
+            "test.ComplexType",
+            "stringMap[\"a.b\"].bool: invalid type: integer `1`, expected a boolean",
+        ),
+        (
+            r#"{ "intMap": { "one": {} } }"#,
+            "test.ComplexType",
+            "intMap[\"one\"]: invalid digit found in string",
+        ),
+        (
+            r#"{ "nested": { "unknown": 1 } }"#,
+            "test.ComplexType",
+            "nested: unrecognized field name 'unknown'",
+        ),
+        (
+            r#"{ "unknown": 1 }"#,
+            "test.ComplexType",
+            "unrecognized field name 'unknown'",
+        ),
+        (
+            r#"{ "@type": "type.googleapis.com/test.Point", "latitude": "north" }"#,
+            "google.protobuf.Any",
+            "latitude: invalid digit found in string",
+        ),
+        (
+            r#"{ "@type": "type.googleapis.com/google.protobuf.Duration", "value": "1m" }"#,
+            "google.protobuf.Any",
+            "value: invalid duration string",
+        ),
+    ] {
+        let err = try_from_json_string_with_options(json, message_name, &options).unwrap_err();
+        assert!(err.to_string().starts_with(expected), "{}", err);
+    }
+
+    let mut message = ComplexType::default().transcode_to_dynamic();
+    let err = message
+        .apply_merge_patch(json!({ "stringMap": { "foo": { "double": "x" } } }))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "stringMap[\"foo\"].double: invalid float literal"
+    );
+    let err = message
+        .apply_merge_patch(json!({ "nested": { "double": "x" } }))
+        .unwrap_err();
+    assert!(err.to_string().starts_with("nested.double: "), "{}", err);
+}
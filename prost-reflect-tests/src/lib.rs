@@ -1,14 +1,130 @@
 use once_cell::sync::Lazy;
 use prost_reflect::DescriptorPool;
 
+#[cfg(test)]
+mod any;
 #[cfg(test)]
 mod arbitrary;
 #[cfg(test)]
+mod assign;
+#[cfg(test)]
+mod bind;
+#[cfg(test)]
+mod buf;
+#[cfg(test)]
+mod builder;
+#[cfg(test)]
+mod clone;
+#[cfg(test)]
+mod cmp;
+#[cfg(test)]
+mod codec;
+#[cfg(test)]
+mod compat;
+#[cfg(test)]
+mod compile;
+#[cfg(test)]
+mod compress;
+#[cfg(test)]
+mod convert;
+#[cfg(test)]
+mod datetime;
+#[cfg(test)]
+mod debug;
+#[cfg(test)]
 mod decode;
 #[cfg(test)]
+mod decode_async;
+#[cfg(test)]
+mod decode_options;
+#[cfg(test)]
+mod delimited;
+#[cfg(test)]
 mod desc;
 #[cfg(test)]
+mod deterministic;
+#[cfg(test)]
+mod diagnostics;
+#[cfg(test)]
+mod diff;
+#[cfg(test)]
+mod example;
+#[cfg(test)]
+mod explain;
+#[cfg(test)]
+mod field_mask;
+#[cfg(test)]
+mod generate;
+#[cfg(test)]
+mod hash;
+#[cfg(test)]
+mod incremental;
+#[cfg(test)]
+mod index;
+#[cfg(test)]
+mod initialized;
+#[cfg(test)]
 mod json;
+#[cfg(test)]
+mod lint;
+#[cfg(test)]
+mod logging;
+#[cfg(test)]
+mod merge;
+#[cfg(test)]
+mod mutate;
+#[cfg(test)]
+mod ndjson;
+#[cfg(test)]
+mod options;
+#[cfg(test)]
+mod placeholder;
+#[cfg(test)]
+mod plugin;
+#[cfg(test)]
+mod pool_merge;
+#[cfg(all(test, feature = "integrations"))]
+mod postgres;
+#[cfg(test)]
+mod profile;
+#[cfg(all(test, feature = "integrations"))]
+mod protobuf;
+#[cfg(test)]
+mod reflection;
+#[cfg(test)]
+mod registry;
+#[cfg(test)]
+mod remote;
+#[cfg(test)]
+mod reparse;
+#[cfg(test)]
+mod resolver;
+#[cfg(test)]
+mod roundtrip;
+#[cfg(test)]
+mod set;
+#[cfg(test)]
+mod snapshot;
+#[cfg(test)]
+mod stream;
+#[cfg(test)]
+mod text_format;
+#[cfg(all(test, feature = "integrations"))]
+mod tonic;
+#[cfg(test)]
+mod typed;
+#[cfg(test)]
+mod unknown;
+#[cfg(test)]
+mod unstructured;
+#[cfg(test)]
+mod validate;
+#[cfg(test)]
+mod verify;
+#[cfg(test)]
+mod visit;
+#[cfg(test)]
+mod wire;
 
 include!(concat!(env!("OUT_DIR"), "/test.rs"));
 include!(concat!(env!("OUT_DIR"), "/test2.rs"));
@@ -22,3 +138,17 @@ static TEST_DESCRIPTOR_POOL: Lazy<DescriptorPool> =
 pub fn test_file_descriptor() -> DescriptorPool {
     TEST_DESCRIPTOR_POOL.clone()
 }
+
+#[cfg(test)]
+fn arb_message(name: &str) -> proptest::strategy::BoxedStrategy<prost_reflect::DynamicMessage> {
+    arb_message_with_options(name, &prost_reflect::ArbitraryOptions::new())
+}
+
+#[cfg(test)]
+fn arb_message_with_options(
+    name: &str,
+    options: &prost_reflect::ArbitraryOptions,
+) -> proptest::strategy::BoxedStrategy<prost_reflect::DynamicMessage> {
+    let desc = test_file_descriptor().get_message_by_name(name).unwrap();
+    prost_reflect::DynamicMessage::arbitrary_with_options(desc, options)
+}
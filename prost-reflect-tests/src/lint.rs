@@ -0,0 +1,119 @@
+use prost_reflect::{
+    lint::{Diagnostic, LintContext, LintRule, Linter},
+    FieldDescriptor,
+};
+
+use crate::test_file_descriptor;
+
+fn lint_file(linter: &Linter) -> Vec<Diagnostic> {
+    let file = test_file_descriptor()
+        .get_file_by_name("lint.proto")
+        .unwrap();
+    linter.lint_file(&file)
+}
+
+fn messages(diagnostics: &[Diagnostic], rule: &str) -> Vec<String> {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.rule() == rule)
+        .map(|diagnostic| format!("{}: {}", diagnostic.element(), diagnostic.message()))
+        .collect()
+}
+
+#[test]
+fn lint_naming_conventions() {
+    let diagnostics = lint_file(&Linter::new());
+    assert_eq!(
+        messages(&diagnostics, "naming_conventions"),
+        vec![
+            "lint.v1.bad_message: message name 'bad_message' should be PascalCase",
+            "lint.v1.bad_message.BadField: field name 'BadField' should be lower_snake_case",
+            "lint.v1.bad_message.lowerValue: enum value name 'lowerValue' should be UPPER_SNAKE_CASE",
+            "lint.v1.LintService.good_method: method name 'good_method' should be PascalCase",
+        ]
+    );
+}
+
+#[test]
+fn lint_field_number_gaps() {
+    let diagnostics = lint_file(&Linter::new());
+    assert_eq!(
+        messages(&diagnostics, "field_number_gaps"),
+        vec!["lint.v1.bad_message: field numbers 2 to 3 are unused and not reserved"]
+    );
+}
+
+#[test]
+fn lint_enum_zero_value() {
+    let diagnostics = lint_file(&Linter::new());
+    assert_eq!(
+        messages(&diagnostics, "enum_zero_value"),
+        vec!["lint.v1.BadEnum: the zero value of enum 'BadEnum' should be named with the suffix '_UNSPECIFIED', found 'BAD_ENUM_DEFAULT'"]
+    );
+
+    // Proto2 enums such as 'test2.Proto2Enum' need not have a zero value.
+    let file = test_file_descriptor()
+        .get_file_by_name("test2.proto")
+        .unwrap();
+    let diagnostics = Linter::new().lint_file(&file);
+    assert!(messages(&diagnostics, "enum_zero_value").is_empty());
+}
+
+#[test]
+fn lint_file_layout() {
+    let diagnostics = lint_file(&Linter::new());
+    assert_eq!(
+        messages(&diagnostics, "file_layout"),
+        vec!["lint.proto: file should be in directory 'lint/v1' to match its package 'lint.v1'"]
+    );
+
+    let file = test_file_descriptor()
+        .get_file_by_name("desc_no_package.proto")
+        .unwrap();
+    let diagnostics = Linter::new().lint_file(&file);
+    assert_eq!(
+        messages(&diagnostics, "file_layout"),
+        vec!["desc_no_package.proto: file does not declare a package"]
+    );
+}
+
+#[test]
+fn lint_source_locations() {
+    let diagnostics = lint_file(&Linter::new());
+    let diagnostic = diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.element() == "lint.v1.bad_message.BadField")
+        .unwrap();
+
+    let location = diagnostic.location().unwrap();
+    assert_eq!(location.line(), 16);
+    assert_eq!(location.column(), 3);
+    assert_eq!(
+        diagnostic.to_string(),
+        "lint.proto:16:3: field name 'BadField' should be lower_snake_case (naming_conventions)"
+    );
+}
+
+struct NoUppercaseFields;
+
+impl LintRule for NoUppercaseFields {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn check_field(&self, field: &FieldDescriptor, cx: &mut LintContext<'_>) {
+        if field.name().chars().any(|ch| ch.is_ascii_uppercase()) {
+            cx.report("uppercase");
+        }
+    }
+}
+
+#[test]
+fn lint_custom_rule() {
+    let diagnostics = lint_file(&Linter::empty().rule(NoUppercaseFields));
+    assert_eq!(
+        messages(&diagnostics, "custom"),
+        vec!["lint.v1.bad_message.BadField: uppercase"]
+    );
+    assert_eq!(diagnostics.len(), 1);
+}
@@ -0,0 +1,133 @@
+use prost::Message;
+use prost_reflect::{DynamicMessage, LogOptions, ReflectMessage};
+
+use crate::{ComplexType, Scalars};
+
+fn complex_type() -> DynamicMessage {
+    ComplexType {
+        string_map: vec![
+            (
+                "foo".to_owned(),
+                Scalars {
+                    int32: 3,
+                    ..Default::default()
+                },
+            ),
+            (
+                "bar".to_owned(),
+                Scalars {
+                    string: "hello world".to_owned(),
+                    bytes: b"\x00\x01abc".to_vec(),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .collect(),
+        nested: Some(Scalars {
+            int32: 5,
+            bool: true,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3, 7],
+        optional_enum: -4,
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn log_fields_complex_type() {
+    let fields = complex_type().log_fields(&LogOptions::new());
+    let pairs: Vec<_> = fields.iter().collect();
+    assert_eq!(
+        pairs,
+        vec![
+            ("string_map.bar.string", "hello world"),
+            ("string_map.bar.bytes", "<5 bytes>"),
+            ("string_map.foo.int32", "3"),
+            ("nested.int32", "5"),
+            ("nested.bool", "true"),
+            ("my_enum.0", "FOO"),
+            ("my_enum.1", "BAR"),
+            ("my_enum.2", "7"),
+            ("optional_enum", "NEG"),
+        ]
+    );
+    assert_eq!(fields.len(), 9);
+    assert_eq!(fields.get("nested.int32"), Some("5"));
+    assert_eq!(fields.get("nested.uint32"), None);
+}
+
+#[test]
+fn log_fields_display() {
+    let fields = complex_type().log_fields(&LogOptions::new());
+    assert_eq!(
+        fields.to_string(),
+        r#"string_map.bar.string="hello world" string_map.bar.bytes="<5 bytes>" string_map.foo.int32=3 nested.int32=5 nested.bool=true my_enum.0=FOO my_enum.1=BAR my_enum.2=7 optional_enum=NEG"#
+    );
+}
+
+#[test]
+fn log_fields_empty() {
+    let fields = Scalars::default()
+        .transcode_to_dynamic()
+        .log_fields(&LogOptions::new());
+    assert!(fields.is_empty());
+    assert_eq!(fields.to_string(), "");
+}
+
+#[test]
+fn log_fields_redacted() {
+    let options = LogOptions::new()
+        .redact("string_map.string")
+        .redact("nested");
+    let fields = complex_type().log_fields(&options);
+    assert_eq!(fields.get("string_map.bar.string"), Some("[redacted]"));
+    assert_eq!(fields.get("string_map.bar.bytes"), Some("<5 bytes>"));
+    assert_eq!(fields.get("nested"), Some("[redacted]"));
+    assert_eq!(fields.get("nested.int32"), None);
+}
+
+#[test]
+fn log_fields_truncated() {
+    let message = Scalars {
+        string: "héllo".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let fields = message.log_fields(&LogOptions::new().max_value_len(2));
+    assert_eq!(fields.get("string"), Some("h..."));
+}
+
+#[test]
+fn log_fields_unknown() {
+    let mut message = Scalars::default().transcode_to_dynamic();
+    message.merge(b"\xa8\x1f\x05".as_ref()).unwrap();
+    let fields = message.log_fields(&LogOptions::new());
+    assert_eq!(fields.iter().collect::<Vec<_>>(), vec![("501", "5")]);
+}
+
+#[test]
+#[cfg(feature = "integrations")]
+fn log_fields_kv_source() {
+    use std::collections::BTreeMap;
+
+    use log::kv::{Error, Key, Source, Value, VisitSource};
+
+    struct Collect(BTreeMap<String, String>);
+
+    impl<'kvs> VisitSource<'kvs> for Collect {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+            self.0.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+    }
+
+    let fields = complex_type().log_fields(&LogOptions::new());
+    let mut visitor = Collect(BTreeMap::new());
+    fields.visit(&mut visitor).unwrap();
+    assert_eq!(visitor.0.len(), 9);
+    assert_eq!(visitor.0["optional_enum"], "NEG");
+    assert_eq!(Source::count(&fields), 9);
+}
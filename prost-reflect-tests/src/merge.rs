@@ -0,0 +1,176 @@
+use prost::Message;
+use prost_reflect::{DynamicMessage, ReflectMessage, Value};
+
+use crate::{test_file_descriptor, ComplexType, Point, Scalars};
+
+fn merge_encoded(a: &DynamicMessage, b: &DynamicMessage) -> DynamicMessage {
+    let mut merged = a.clone();
+    merged.merge(b.encode_to_vec().as_slice()).unwrap();
+    merged
+}
+
+#[test]
+fn merge_scalars() {
+    let a = Scalars {
+        int32: 1,
+        string: "a".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let b = Scalars {
+        int32: 2,
+        double: 1.5,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let mut merged = a.clone();
+    merged.merge_from(&b);
+    assert_eq!(merged, merge_encoded(&a, &b));
+    assert_eq!(
+        merged.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 2,
+            double: 1.5,
+            string: "a".to_owned(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn merge_complex() {
+    let a = ComplexType {
+        string_map: IntoIterator::into_iter([
+            (
+                "a".to_owned(),
+                Scalars {
+                    int32: 1,
+                    ..Default::default()
+                },
+            ),
+            (
+                "b".to_owned(),
+                Scalars {
+                    int32: 2,
+                    ..Default::default()
+                },
+            ),
+        ])
+        .collect(),
+        nested: Some(Scalars {
+            int32: 1,
+            string: "a".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let b = ComplexType {
+        string_map: IntoIterator::into_iter([(
+            "b".to_owned(),
+            Scalars {
+                uint32: 3,
+                ..Default::default()
+            },
+        )])
+        .collect(),
+        nested: Some(Scalars {
+            int64: 2,
+            ..Default::default()
+        }),
+        my_enum: vec![3, 1],
+        optional_enum: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let mut merged = a.clone();
+    merged.merge_from(&b);
+    assert_eq!(merged, merge_encoded(&a, &b));
+
+    let merged: ComplexType = merged.transcode_to().unwrap();
+    assert_eq!(merged.string_map["a"].int32, 1);
+    assert_eq!(
+        merged.string_map["b"],
+        Scalars {
+            uint32: 3,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        merged.nested,
+        Some(Scalars {
+            int32: 1,
+            int64: 2,
+            string: "a".to_owned(),
+            ..Default::default()
+        })
+    );
+    assert_eq!(merged.my_enum, vec![1, 3, 1]);
+    assert_eq!(merged.optional_enum, 1);
+}
+
+#[test]
+fn merge_oneof() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.MessageWithOneof")
+        .unwrap();
+
+    let mut a = DynamicMessage::new(desc.clone());
+    a.set_field_by_name("oneof_field_1", Value::String("a".to_owned()));
+    let mut b = DynamicMessage::new(desc);
+    b.set_field_by_name("oneof_field_2", Value::I32(0));
+
+    let mut merged = a.clone();
+    merged.merge_from(&b);
+    assert_eq!(merged, merge_encoded(&a, &b));
+    assert!(!merged.has_field_by_name("oneof_field_1"));
+    assert!(merged.has_field_by_name("oneof_field_2"));
+}
+
+#[test]
+fn merge_unknown_fields() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.Empty")
+        .unwrap();
+    let a = DynamicMessage::decode(
+        desc.clone(),
+        Point {
+            latitude: 1,
+            longitude: 2,
+        }
+        .encode_to_vec()
+        .as_slice(),
+    )
+    .unwrap();
+    let b = DynamicMessage::decode(
+        desc,
+        Point {
+            latitude: 3,
+            longitude: 0,
+        }
+        .encode_to_vec()
+        .as_slice(),
+    )
+    .unwrap();
+
+    let mut merged = a.clone();
+    merged.merge_from(&b);
+    assert_eq!(merged, merge_encoded(&a, &b));
+    assert_eq!(
+        Point::decode(merged.encode_to_vec().as_slice()).unwrap(),
+        Point {
+            latitude: 3,
+            longitude: 2,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot merge messages of different types")]
+fn merge_different_types() {
+    let mut a = Scalars::default().transcode_to_dynamic();
+    a.merge_from(&Point::default().transcode_to_dynamic());
+}
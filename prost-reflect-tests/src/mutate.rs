@@ -0,0 +1,130 @@
+use prost::Message;
+use prost_reflect::{DynamicMessage, Mutator, ReflectMessage, Value};
+
+use crate::{test_file_descriptor, ComplexType, Scalars};
+
+fn complex_type() -> DynamicMessage {
+    ComplexType {
+        string_map: vec![("foo".to_owned(), Scalars::default())]
+            .into_iter()
+            .collect(),
+        nested: Some(Scalars {
+            int32: 5,
+            string: "hello".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![0, 1, 3],
+        optional_enum: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn mutate_is_deterministic() {
+    let mut first = complex_type();
+    let mut second = first.clone();
+
+    let mut first_mutator = Mutator::new(1234);
+    let mut second_mutator = Mutator::new(1234);
+    for _ in 0..100 {
+        first_mutator.mutate(&mut first);
+        second_mutator.mutate(&mut second);
+    }
+
+    assert_eq!(first, second);
+}
+
+#[test]
+fn mutated_messages_can_be_decoded() {
+    for name in [
+        "test.ComplexType",
+        "test.WellKnownTypes",
+        "test2.ContainsGroup",
+    ] {
+        let desc = test_file_descriptor().get_message_by_name(name).unwrap();
+        let mut message = DynamicMessage::new(desc.clone());
+        let mut mutator = Mutator::new(0);
+        for _ in 0..500 {
+            mutator.mutate(&mut message);
+
+            let bytes = message.encode_to_vec();
+            let decoded = DynamicMessage::decode(desc.clone(), bytes.as_slice()).unwrap();
+            assert_eq!(decoded.encoded_len(), bytes.len());
+        }
+    }
+}
+
+#[test]
+fn mutate_covers_schema_aware_mutations() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+    let enum_desc = desc
+        .get_field_by_name("optional_enum")
+        .unwrap()
+        .kind()
+        .as_enum()
+        .cloned()
+        .unwrap();
+
+    let mut saw_unknown_enum = false;
+    let mut saw_unknown_field = false;
+    let mut saw_boundary_int = false;
+
+    let mut mutator = Mutator::new(42);
+    for _ in 0..200 {
+        let mut message = complex_type();
+        for _ in 0..10 {
+            mutator.mutate(&mut message);
+        }
+
+        if let Value::EnumNumber(number) = *message.get_field_by_name("optional_enum").unwrap() {
+            saw_unknown_enum |= enum_desc.get_value(number).is_none();
+        }
+
+        let mut fields_only = DynamicMessage::new(desc.clone());
+        for field in desc.fields() {
+            if message.has_field(&field) {
+                fields_only.set_field(&field, message.get_field(&field).into_owned());
+            }
+        }
+        saw_unknown_field |= fields_only.encoded_len() < message.encoded_len();
+
+        if let Some(Value::Message(nested)) = message.get_field_by_name("nested").as_deref() {
+            if let Some(Value::I32(value)) = nested.get_field_by_name("int32").as_deref() {
+                saw_boundary_int |= *value == i32::MIN || *value == i32::MAX;
+            }
+        }
+    }
+
+    assert!(saw_unknown_enum);
+    assert!(saw_unknown_field);
+    assert!(saw_boundary_int);
+}
+
+#[test]
+fn mutate_can_clear_required_fields() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.ContainsGroup")
+        .unwrap();
+    let group_desc = desc
+        .get_field_by_name("groupmessage")
+        .unwrap()
+        .kind()
+        .as_message()
+        .cloned()
+        .unwrap();
+    let url = group_desc.get_field_by_name("url").unwrap();
+
+    let mut group = DynamicMessage::new(group_desc);
+    group.set_field(&url, Value::String("https://example.com".to_owned()));
+
+    let mut mutator = Mutator::new(7);
+    let cleared = (0..200).any(|_| {
+        let mut message = group.clone();
+        mutator.mutate(&mut message);
+        !message.has_field(&url)
+    });
+    assert!(cleared);
+}
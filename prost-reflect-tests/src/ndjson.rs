@@ -0,0 +1,179 @@
+use prost::Message;
+use prost_reflect::{ndjson, DeserializeOptions, ReflectMessage, SerializeOptions};
+
+use crate::{ComplexType, Point, Scalars};
+
+fn points() -> Vec<Point> {
+    (0..3)
+        .map(|i| Point {
+            latitude: i,
+            longitude: -i,
+        })
+        .collect()
+}
+
+fn encode_all<T: Message>(messages: &[T]) -> Vec<u8> {
+    messages
+        .iter()
+        .flat_map(|message| message.encode_length_delimited_to_vec())
+        .collect()
+}
+
+#[test]
+fn binary_to_ndjson() {
+    let binary = encode_all(&points());
+
+    let mut json = Vec::new();
+    let count = ndjson::binary_to_ndjson(
+        &Point::default().descriptor(),
+        binary.as_slice(),
+        &mut json,
+        &SerializeOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        "{}\n{\"latitude\":1,\"longitude\":-1}\n{\"latitude\":2,\"longitude\":-2}\n"
+    );
+}
+
+#[test]
+fn ndjson_to_binary() {
+    let json = "{}\n\n{\"latitude\":1,\"longitude\":-1}\r\n  {\"latitude\":2,\"longitude\":-2}";
+
+    let mut binary = Vec::new();
+    let count = ndjson::ndjson_to_binary(
+        &Point::default().descriptor(),
+        json.as_bytes(),
+        &mut binary,
+        &DeserializeOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(binary, encode_all(&points()));
+}
+
+#[test]
+fn roundtrip_large_messages() {
+    let messages: Vec<ComplexType> = (0..100)
+        .map(|i| ComplexType {
+            nested: Some(Scalars {
+                string: "x".repeat(i * 50),
+                int64: i as i64,
+                ..Default::default()
+            }),
+            my_enum: vec![1; i],
+            ..Default::default()
+        })
+        .collect();
+    let binary = encode_all(&messages);
+    let desc = ComplexType::default().descriptor();
+
+    let mut json = Vec::new();
+    ndjson::binary_to_ndjson(
+        &desc,
+        binary.as_slice(),
+        &mut json,
+        &SerializeOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(json.iter().filter(|&&b| b == b'\n').count(), 100);
+
+    let mut roundtripped = Vec::new();
+    ndjson::ndjson_to_binary(
+        &desc,
+        json.as_slice(),
+        &mut roundtripped,
+        &DeserializeOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(roundtripped, binary);
+}
+
+#[test]
+fn binary_to_ndjson_errors() {
+    let desc = Point::default().descriptor();
+    let transcode = |binary: &[u8]| {
+        ndjson::binary_to_ndjson(&desc, binary, Vec::new(), &SerializeOptions::new())
+            .unwrap_err()
+            .to_string()
+    };
+
+    let mut binary = encode_all(&points());
+    binary.pop();
+    assert_eq!(
+        transcode(&binary),
+        "i/o error: stream ended in the middle of a message"
+    );
+    assert_eq!(transcode(&[0x80]), "i/o error: unexpected end of file");
+    assert_eq!(
+        transcode(&[0xff; 10]),
+        "i/o error: invalid length delimiter"
+    );
+
+    let mut binary = encode_all(&points());
+    binary.extend([2, 0x08, 0x80]);
+    assert_eq!(
+        transcode(&binary),
+        "failed to decode message 3: failed to decode Protobuf message: invalid varint"
+    );
+}
+
+#[test]
+fn ndjson_to_binary_errors() {
+    let err = ndjson::ndjson_to_binary(
+        &Point::default().descriptor(),
+        "{}\n{\"latitude\":1} {}\n".as_bytes(),
+        Vec::new(),
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to parse line 2: trailing characters at line 1 column 16"
+    );
+
+    let err = ndjson::ndjson_to_binary(
+        &Point::default().descriptor(),
+        "\n\n{\"latitude\":\"north\"}\n".as_bytes(),
+        Vec::new(),
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to parse line 3: latitude: invalid digit found in string at line 1 column 19"
+    );
+}
+
+#[test]
+fn async_roundtrip() {
+    futures_executor::block_on(async {
+        let binary = encode_all(&points());
+        let desc = Point::default().descriptor();
+
+        let mut json = Vec::new();
+        let count = ndjson::binary_to_ndjson_async(
+            &desc,
+            binary.as_slice(),
+            &mut json,
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(count, 3);
+
+        let mut roundtripped = Vec::new();
+        let count = ndjson::ndjson_to_binary_async(
+            &desc,
+            json.as_slice(),
+            &mut roundtripped,
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(roundtripped, binary);
+    });
+}
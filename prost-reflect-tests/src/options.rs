@@ -0,0 +1,273 @@
+use std::{env, fs};
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage, Value};
+
+use crate::test_file_descriptor;
+
+const CUSTOM_PROTO: &str = r#"
+syntax = "proto3";
+
+package custom;
+
+import "google/protobuf/descriptor.proto";
+
+extend google.protobuf.MessageOptions {
+  string resource = 50000;
+}
+
+extend google.protobuf.FieldOptions {
+  bool sensitive = 50001;
+}
+
+extend google.protobuf.MethodOptions {
+  string route = 50002;
+}
+
+extend google.protobuf.EnumValueOptions {
+  int32 weight = 50003;
+}
+
+message Annotated {
+  message Nested {
+    string secret = 1 [deprecated = true];
+  }
+
+  Nested nested = 1;
+}
+
+enum Level {
+  LEVEL_UNSPECIFIED = 0;
+  LEVEL_HIGH = 1;
+}
+
+service Api {
+  rpc Get(Annotated) returns (Annotated);
+}
+"#;
+
+/// Builds a pool from an encoded file descriptor set in which the elements of `custom.proto` have
+/// custom options set, as they would be by protoc.
+fn custom_options_pool() -> DescriptorPool {
+    let dir = env::temp_dir().join(format!("prost-reflect-options-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("custom.proto"), CUSTOM_PROTO).unwrap();
+
+    let mut pool = test_file_descriptor();
+    pool.add_proto_files(["custom.proto"], [&dir]).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let file_desc = pool
+        .get_message_by_name("google.protobuf.FileDescriptorProto")
+        .unwrap();
+    let to_dynamic = |name: &str| {
+        DynamicMessage::decode(
+            file_desc.clone(),
+            pool.get_file_by_name(name)
+                .unwrap()
+                .file_descriptor_proto()
+                .encode_to_vec()
+                .as_slice(),
+        )
+        .unwrap()
+    };
+    let mut custom = to_dynamic("custom.proto");
+    set_extension(
+        &mut custom,
+        &["message_type[0]", "options"],
+        "custom.resource",
+        Value::String("annotated".to_owned()),
+    );
+    set_extension(
+        &mut custom,
+        &["message_type[0]", "nested_type[0]", "field[0]", "options"],
+        "custom.sensitive",
+        Value::Bool(true),
+    );
+    set_extension(
+        &mut custom,
+        &["service[0]", "method[0]", "options"],
+        "custom.route",
+        Value::String("/get".to_owned()),
+    );
+    set_extension(
+        &mut custom,
+        &["enum_type[0]", "value[1]", "options"],
+        "custom.weight",
+        Value::I32(10),
+    );
+
+    let mut set = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.FileDescriptorSet")
+            .unwrap(),
+    );
+    set.set_field_by_name(
+        "file",
+        Value::List(vec![
+            Value::Message(to_dynamic("google/protobuf/descriptor.proto")),
+            Value::Message(custom),
+        ]),
+    );
+
+    DescriptorPool::decode(set.encode_to_vec().as_slice()).unwrap()
+}
+
+/// Sets an extension field of the message at `path`, where each element of the path is a field name
+/// followed by an index into the repeated field.
+pub(crate) fn set_extension(
+    message: &mut DynamicMessage,
+    path: &[&str],
+    extension: &str,
+    value: Value,
+) {
+    let mut message = message;
+    for part in path {
+        let (name, index) = match part.split_once('[') {
+            Some((name, index)) => (
+                name,
+                Some(index.trim_end_matches(']').parse::<usize>().unwrap()),
+            ),
+            None => (*part, None),
+        };
+        let value = message.get_field_by_name_mut(name).unwrap();
+        let value = match index {
+            Some(index) => &mut value.as_list_mut().unwrap()[index],
+            None => value,
+        };
+        message = value.as_message_mut().unwrap();
+    }
+
+    let extension = message
+        .descriptor()
+        .extensions()
+        .find(|ext| ext.full_name() == extension)
+        .unwrap();
+    message.set_extension(&extension, value);
+}
+
+#[test]
+fn custom_options() {
+    let pool = custom_options_pool();
+
+    let annotated = pool.get_message_by_name("custom.Annotated").unwrap();
+    let options = annotated.options().unwrap();
+    assert_eq!(
+        options.descriptor().full_name(),
+        "google.protobuf.MessageOptions"
+    );
+    let resource = pool
+        .all_extensions()
+        .find(|ext| ext.full_name() == "custom.resource")
+        .unwrap();
+    assert_eq!(options.get_extension(&resource).as_str(), Some("annotated"));
+
+    let secret = pool
+        .get_message_by_name("custom.Annotated.Nested")
+        .unwrap()
+        .get_field_by_name("secret")
+        .unwrap();
+    let options = secret.options().unwrap();
+    assert_eq!(
+        options.get_field_by_name("deprecated").unwrap().as_bool(),
+        Some(true)
+    );
+    let sensitive = pool
+        .all_extensions()
+        .find(|ext| ext.full_name() == "custom.sensitive")
+        .unwrap();
+    assert_eq!(options.get_extension(&sensitive).as_bool(), Some(true));
+
+    let method = pool.services().next().unwrap().methods().next().unwrap();
+    let route = pool
+        .all_extensions()
+        .find(|ext| ext.full_name() == "custom.route")
+        .unwrap();
+    assert_eq!(
+        method.options().unwrap().get_extension(&route).as_str(),
+        Some("/get")
+    );
+
+    let level = pool.get_enum_by_name("custom.Level").unwrap();
+    let weight = pool
+        .all_extensions()
+        .find(|ext| ext.full_name() == "custom.weight")
+        .unwrap();
+    assert_eq!(
+        level
+            .get_value(1)
+            .unwrap()
+            .options()
+            .unwrap()
+            .get_extension(&weight)
+            .as_i32(),
+        Some(10)
+    );
+    assert!(!level
+        .get_value(0)
+        .unwrap()
+        .options()
+        .unwrap()
+        .has_extension(&weight));
+}
+
+#[test]
+fn standard_options() {
+    let pool = custom_options_pool();
+    let file = pool.get_file_by_name("custom.proto").unwrap();
+    let options = file.options().unwrap();
+    assert_eq!(
+        options.descriptor().full_name(),
+        "google.protobuf.FileOptions"
+    );
+    assert!(!options.has_field_by_name("java_package"));
+
+    let level = pool.get_enum_by_name("custom.Level").unwrap();
+    assert_eq!(
+        level.options().unwrap().descriptor().full_name(),
+        "google.protobuf.EnumOptions"
+    );
+    let service = pool.services().next().unwrap();
+    assert_eq!(
+        service.options().unwrap().descriptor().full_name(),
+        "google.protobuf.ServiceOptions"
+    );
+}
+
+#[test]
+fn options_without_encoded_bytes() {
+    // Pools built from `FileDescriptorProto` values only have the options known to prost.
+    let pool = test_file_descriptor();
+    let mut file = pool
+        .get_file_by_name("desc.proto")
+        .unwrap()
+        .file_descriptor_proto()
+        .clone();
+    file.options = Some(prost_types::FileOptions {
+        java_package: Some("com.example".to_owned()),
+        ..Default::default()
+    });
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_proto(file).unwrap();
+
+    let options = pool
+        .get_file_by_name("desc.proto")
+        .unwrap()
+        .options()
+        .unwrap();
+    assert_eq!(
+        options.descriptor().full_name(),
+        "google.protobuf.FileOptions"
+    );
+    assert_eq!(
+        options.get_field_by_name("java_package").unwrap().as_str(),
+        Some("com.example")
+    );
+    assert!(pool
+        .get_message_by_name("my.package.MyMessage")
+        .unwrap()
+        .oneofs()
+        .next()
+        .unwrap()
+        .options()
+        .is_some());
+}
@@ -0,0 +1,216 @@
+use std::iter;
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, Kind, PoolOptions};
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    MethodDescriptorProto, ServiceDescriptorProto,
+};
+
+fn field(name: &str, number: i32, ty: Type, type_name: &str) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(ty as i32),
+        type_name: Some(type_name.to_owned()),
+        ..Default::default()
+    }
+}
+
+/// A file in package `my.package` which imports `missing.proto`, and uses types which are not defined.
+fn partial_file() -> FileDescriptorProto {
+    FileDescriptorProto {
+        name: Some("partial.proto".to_owned()),
+        package: Some("my.package".to_owned()),
+        dependency: vec!["missing.proto".to_owned()],
+        message_type: vec![DescriptorProto {
+            name: Some("Message".to_owned()),
+            field: vec![
+                field("message", 1, Type::Message, ".other.Message"),
+                field("enum", 2, Type::Enum, ".other.Enum"),
+                field("relative", 3, Type::Message, "Relative"),
+                field("local", 4, Type::Message, "Message"),
+            ],
+            extension: vec![FieldDescriptorProto {
+                extendee: Some(".other.Extendee".to_owned()),
+                ..field("ext", 5, Type::Message, ".other.Message")
+            }],
+            ..Default::default()
+        }],
+        service: vec![ServiceDescriptorProto {
+            name: Some("Service".to_owned()),
+            method: vec![MethodDescriptorProto {
+                name: Some("Method".to_owned()),
+                input_type: Some(".other.Request".to_owned()),
+                output_type: Some(".my.package.Message".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn lenient() -> PoolOptions {
+    PoolOptions::new().allow_unknown_dependencies(true)
+}
+
+#[test]
+fn unknown_dependencies_not_allowed_by_default() {
+    let err = DescriptorPool::from_file_descriptor_set_with_options(
+        FileDescriptorSet {
+            file: vec![partial_file()],
+        },
+        &PoolOptions::new(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the file 'missing.proto' was not found while resolving dependencies for 'partial.proto' (and 6 other errors)"
+    );
+}
+
+#[test]
+fn placeholder_files() {
+    let pool = DescriptorPool::from_file_descriptor_set_with_options(
+        FileDescriptorSet {
+            file: vec![partial_file()],
+        },
+        &lenient(),
+    )
+    .unwrap();
+
+    let files: Vec<_> = pool
+        .files()
+        .map(|file| (file.name().to_owned(), file.is_placeholder()))
+        .collect();
+    assert_eq!(
+        files,
+        [
+            ("partial.proto".to_owned(), false),
+            ("missing.proto".to_owned(), true),
+            ("placeholder.proto".to_owned(), true),
+            ("other.placeholder.proto".to_owned(), true),
+        ]
+    );
+    assert_eq!(
+        pool.get_file_by_name("missing.proto")
+            .unwrap()
+            .file_descriptor_proto(),
+        &FileDescriptorProto {
+            name: Some("missing.proto".to_owned()),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn placeholder_types() {
+    let pool = DescriptorPool::from_file_descriptor_set_with_options(
+        FileDescriptorSet {
+            file: vec![partial_file()],
+        },
+        &lenient(),
+    )
+    .unwrap();
+
+    let message = pool.get_message_by_name("my.package.Message").unwrap();
+    assert!(!message.is_placeholder());
+
+    let placeholder = match message.get_field_by_name("message").unwrap().kind() {
+        Kind::Message(placeholder) => placeholder,
+        kind => panic!("unexpected kind {:?}", kind),
+    };
+    assert_eq!(placeholder.full_name(), "other.Message");
+    assert!(placeholder.is_placeholder());
+    assert_eq!(placeholder.fields().len(), 0);
+    assert_eq!(placeholder.parent_file().name(), "other.placeholder.proto");
+
+    let placeholder = match message.get_field_by_name("enum").unwrap().kind() {
+        Kind::Enum(placeholder) => placeholder,
+        kind => panic!("unexpected kind {:?}", kind),
+    };
+    assert_eq!(placeholder.full_name(), "other.Enum");
+    assert!(placeholder.is_placeholder());
+    assert_eq!(placeholder.default_value().name(), "PLACEHOLDER_VALUE");
+    assert_eq!(placeholder.default_value().number(), 0);
+
+    // Relative names which cannot be resolved are treated as fully-qualified.
+    let placeholder = message.get_field_by_name("relative").unwrap().kind();
+    let placeholder = placeholder.as_message().unwrap();
+    assert_eq!(placeholder.full_name(), "Relative");
+    assert_eq!(placeholder.parent_file().name(), "placeholder.proto");
+
+    // Names which can be resolved are not affected.
+    let local = message.get_field_by_name("local").unwrap().kind();
+    assert_eq!(local.as_message(), Some(&message));
+
+    let extendee = pool.get_message_by_name("other.Extendee").unwrap();
+    assert!(extendee.is_placeholder());
+    assert!(extendee.extension_ranges().eq(iter::once(1..536_870_912)));
+    assert_eq!(
+        extendee.get_extension(5).unwrap().full_name(),
+        "my.package.Message.ext"
+    );
+
+    let method = pool.services().next().unwrap().methods().next().unwrap();
+    assert_eq!(method.input().full_name(), "other.Request");
+    assert!(method.input().is_placeholder());
+    assert!(!method.output().is_placeholder());
+}
+
+#[test]
+fn placeholder_file_names_are_unique() {
+    let existing = FileDescriptorProto {
+        name: Some("other.placeholder.proto".to_owned()),
+        ..Default::default()
+    };
+    let pool = DescriptorPool::from_file_descriptor_set_with_options(
+        FileDescriptorSet {
+            file: vec![existing, partial_file()],
+        },
+        &lenient(),
+    )
+    .unwrap();
+
+    assert!(!pool
+        .get_file_by_name("other.placeholder.proto")
+        .unwrap()
+        .is_placeholder());
+    let message = pool.get_message_by_name("other.Message").unwrap();
+    assert_eq!(message.parent_file().name(), "other.placeholder2.proto");
+}
+
+#[test]
+fn decode_with_unknown_dependencies() {
+    let bytes = FileDescriptorSet {
+        file: vec![partial_file()],
+    }
+    .encode_to_vec();
+
+    assert!(DescriptorPool::decode(bytes.as_slice()).is_err());
+    let pool = DescriptorPool::decode_with_options(bytes.as_slice(), &lenient()).unwrap();
+    assert!(pool.get_message_by_name("other.Message").is_some());
+}
+
+#[test]
+fn placeholders_preserved_when_replacing_file() {
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_protos_with_options(vec![partial_file()], &lenient())
+        .unwrap();
+
+    let mut file = partial_file();
+    file.message_type[0].field.pop();
+    pool.replace_file(file).unwrap();
+
+    assert!(pool
+        .get_file_by_name("missing.proto")
+        .unwrap()
+        .is_placeholder());
+    assert!(!pool
+        .get_file_by_name("partial.proto")
+        .unwrap()
+        .is_placeholder());
+}
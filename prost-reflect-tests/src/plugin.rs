@@ -0,0 +1,67 @@
+use prost::Message;
+use prost_reflect::plugin::PluginRequest;
+use prost_types::compiler::{CodeGeneratorRequest, Version};
+
+use crate::test_file_descriptor;
+
+fn request(file_to_generate: &[&str]) -> CodeGeneratorRequest {
+    CodeGeneratorRequest {
+        file_to_generate: file_to_generate
+            .iter()
+            .map(|&name| name.to_owned())
+            .collect(),
+        parameter: Some("foo=bar".to_owned()),
+        proto_file: test_file_descriptor()
+            .file_descriptor_protos()
+            .cloned()
+            .collect(),
+        compiler_version: Some(Version {
+            major: Some(3),
+            minor: Some(20),
+            patch: Some(1),
+            suffix: None,
+        }),
+    }
+}
+
+#[test]
+fn read_request() {
+    let bytes = request(&["test2.proto", "test.proto"]).encode_to_vec();
+    let request = PluginRequest::read_from(bytes.as_slice()).unwrap();
+
+    let names: Vec<_> = request
+        .files_to_generate()
+        .map(|file| file.name().to_owned())
+        .collect();
+    assert_eq!(names, ["test2.proto", "test.proto"]);
+    assert_eq!(request.parameter(), Some("foo=bar"));
+    assert_eq!(request.compiler_version().unwrap().minor, Some(20));
+
+    assert!(request.pool().get_message_by_name("test.Point").is_some());
+    assert!(request
+        .pool()
+        .get_file_by_name("google/protobuf/any.proto")
+        .is_some());
+}
+
+#[test]
+fn file_to_generate_not_found() {
+    let err = PluginRequest::from_request(request(&["missing.proto"])).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "file to generate 'missing.proto' not found in request"
+    );
+}
+
+#[test]
+fn invalid_request() {
+    let err = PluginRequest::decode(b"\x0a\x05ab".as_ref()).unwrap_err();
+    assert!(err.to_string().starts_with("failed to decode request: "));
+
+    let mut request = request(&["test.proto"]);
+    request
+        .proto_file
+        .retain(|file| file.name() == "test.proto");
+    let err = PluginRequest::from_request(request).unwrap_err();
+    assert!(err.to_string().starts_with("invalid request: "));
+}
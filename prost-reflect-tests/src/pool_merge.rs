@@ -0,0 +1,155 @@
+use prost_reflect::{
+    DescriptorPool, EnumBuilder, FieldBuilder, FileDescriptorBuilder, MergeOptions, MessageBuilder,
+    MethodBuilder, ServiceBuilder,
+};
+
+fn common_file() -> FileDescriptorBuilder {
+    FileDescriptorBuilder::new("common.proto")
+        .package("common")
+        .add_message(MessageBuilder::new("Id").add_field(FieldBuilder::new("value", 1, "string")))
+}
+
+fn pool_with(name: &str, message: &str) -> DescriptorPool {
+    let mut pool = DescriptorPool::new();
+    common_file().add_to_pool(&mut pool).unwrap();
+    FileDescriptorBuilder::new(name)
+        .package("test")
+        .add_dependency("common.proto")
+        .add_message(MessageBuilder::new(message).add_field(FieldBuilder::new(
+            "id",
+            1,
+            ".common.Id",
+        )))
+        .add_to_pool(&mut pool)
+        .unwrap();
+    pool
+}
+
+fn file_names(pool: &DescriptorPool) -> Vec<String> {
+    pool.files().map(|file| file.name().to_owned()).collect()
+}
+
+#[test]
+fn merge_disjoint() {
+    let mut pool = FileDescriptorBuilder::new("a.proto")
+        .add_message(MessageBuilder::new("A"))
+        .build()
+        .unwrap();
+    let other = FileDescriptorBuilder::new("b.proto")
+        .add_message(MessageBuilder::new("B"))
+        .add_enum(EnumBuilder::new("E").add_value("E_UNSPECIFIED", 0))
+        .build()
+        .unwrap();
+
+    pool.merge(&other).unwrap();
+    assert_eq!(file_names(&pool), ["a.proto", "b.proto"]);
+    assert!(pool.get_message_by_name("A").is_some());
+    assert!(pool.get_message_by_name("B").is_some());
+}
+
+#[test]
+fn merge_identical_duplicates() {
+    let mut pool = pool_with("a.proto", "A");
+    let other = pool_with("b.proto", "B");
+
+    let err = pool.merge(&other).unwrap_err();
+    assert_eq!(err.merge_conflicts().len(), 1);
+    let conflict = &err.merge_conflicts()[0];
+    assert_eq!(conflict.name(), "common.proto");
+    assert!(conflict.is_identical());
+    assert_eq!(
+        err.to_string(),
+        "failed to merge descriptor pools: the file 'common.proto' is present in both pools"
+    );
+    assert_eq!(file_names(&pool), ["common.proto", "a.proto"]);
+
+    pool.merge_with_options(
+        &other,
+        &MergeOptions::new().allow_identical_duplicates(true),
+    )
+    .unwrap();
+    assert_eq!(file_names(&pool), ["common.proto", "a.proto", "b.proto"]);
+    let b = pool.get_message_by_name("test.B").unwrap();
+    assert_eq!(
+        b.get_field_by_name("id")
+            .unwrap()
+            .kind()
+            .as_message()
+            .unwrap(),
+        &pool.get_message_by_name("common.Id").unwrap()
+    );
+}
+
+#[test]
+fn merge_conflicting_definitions() {
+    let mut pool = FileDescriptorBuilder::new("a.proto")
+        .package("test")
+        .add_message(MessageBuilder::new("Shared").add_message(MessageBuilder::new("Nested")))
+        .add_enum(EnumBuilder::new("Color").add_value("COLOR_UNSPECIFIED", 0))
+        .add_service(
+            ServiceBuilder::new("Api").add_method(MethodBuilder::new("Get", "Shared", "Shared")),
+        )
+        .build()
+        .unwrap();
+    let mut other = FileDescriptorBuilder::new("a.proto")
+        .package("other")
+        .build()
+        .unwrap();
+    FileDescriptorBuilder::new("b.proto")
+        .package("test")
+        .add_message(MessageBuilder::new("Shared").add_message(MessageBuilder::new("Nested")))
+        .add_message(MessageBuilder::new("Unique"))
+        .add_enum(EnumBuilder::new("Color").add_value("COLOR_UNSPECIFIED", 0))
+        .add_service(
+            ServiceBuilder::new("Api").add_method(MethodBuilder::new("Get", "Shared", "Shared")),
+        )
+        .add_to_pool(&mut other)
+        .unwrap();
+
+    let err = pool
+        .merge_with_options(
+            &other,
+            &MergeOptions::new().allow_identical_duplicates(true),
+        )
+        .unwrap_err();
+    let conflicts: Vec<_> = err
+        .merge_conflicts()
+        .iter()
+        .map(|conflict| {
+            (
+                conflict.name(),
+                conflict.existing_file(),
+                conflict.other_file(),
+                conflict.is_identical(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        conflicts,
+        [
+            ("a.proto", "a.proto", "a.proto", false),
+            ("test.Shared", "a.proto", "b.proto", false),
+            ("test.Color", "a.proto", "b.proto", false),
+            ("test.Api", "a.proto", "b.proto", false),
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "failed to merge descriptor pools: the file 'a.proto' differs between the pools; \
+        'test.Shared' is defined in both 'a.proto' and 'b.proto'; \
+        'test.Color' is defined in both 'a.proto' and 'b.proto'; \
+        'test.Api' is defined in both 'a.proto' and 'b.proto'"
+    );
+
+    assert_eq!(file_names(&pool), ["a.proto"]);
+    assert!(pool.get_message_by_name("test.Unique").is_none());
+}
+
+#[test]
+fn merge_other_errors_have_no_conflicts() {
+    let err = FileDescriptorBuilder::new("a.proto")
+        .add_message(MessageBuilder::new("A").add_field(FieldBuilder::new("b", 1, "B")))
+        .build()
+        .unwrap_err();
+    assert!(err.merge_conflicts().is_empty());
+}
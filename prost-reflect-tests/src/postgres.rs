@@ -0,0 +1,97 @@
+use prost_reflect::{DynamicMessage, ReflectMessage, RowOptions, Value};
+use sqlx::Arguments;
+
+use crate::{ComplexType, Scalars, WellKnownTypes};
+
+#[test]
+fn to_pg_arguments_scalars() {
+    let message = Scalars {
+        int32: 5,
+        uint64: 10,
+        string: "hello".to_owned(),
+        bytes: b"abc".to_vec(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let arguments = message
+        .to_pg_arguments(&["int32", "uint64", "string", "bytes", "bool"])
+        .unwrap();
+    assert_eq!(arguments.len(), 5);
+}
+
+#[test]
+fn to_pg_arguments_json_name() {
+    let message = ComplexType::default().transcode_to_dynamic();
+
+    let arguments = message
+        .to_pg_arguments(&["stringMap", "int_map", "nested", "my_enum", "optional_enum"])
+        .unwrap();
+    assert_eq!(arguments.len(), 5);
+}
+
+#[test]
+fn to_pg_arguments_well_known_types() {
+    let message = WellKnownTypes {
+        timestamp: Some(prost_types::Timestamp {
+            seconds: 1_650_000_000,
+            nanos: 5,
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let arguments = message
+        .to_pg_arguments(&["timestamp", "duration", "struct"])
+        .unwrap();
+    assert_eq!(arguments.len(), 3);
+}
+
+#[test]
+fn to_pg_arguments_field_not_found() {
+    let message = Scalars::default().transcode_to_dynamic();
+
+    let err = message.to_pg_arguments(&["int32", "missing"]).unwrap_err();
+    assert_eq!(err.to_string(), "no field found for column 'missing'");
+}
+
+#[test]
+fn to_pg_arguments_out_of_range() {
+    let message = Scalars {
+        uint64: u64::MAX,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let err = message.to_pg_arguments(&["uint64"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "value of column 'uint64' is out of range for field 'test.Scalars.uint64'"
+    );
+}
+
+#[test]
+fn to_pg_arguments_unknown_enum_value() {
+    let mut message = ComplexType::default().transcode_to_dynamic();
+    message.set_field_by_name("optional_enum", Value::EnumNumber(7));
+
+    let err = message.to_pg_arguments(&["optional_enum"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "column 'optional_enum' has value '7', which is not a value of enum 'test.ComplexType.MyEnum'"
+    );
+
+    let options = RowOptions::new().use_enum_numbers(true);
+    let arguments = message
+        .to_pg_arguments_with_options(&["optional_enum"], &options)
+        .unwrap();
+    assert_eq!(arguments.len(), 1);
+}
+
+#[test]
+fn to_pg_arguments_empty() {
+    let message = DynamicMessage::new(Scalars::default().descriptor());
+
+    let arguments = message.to_pg_arguments(&[]).unwrap();
+    assert_eq!(arguments.len(), 0);
+}
@@ -0,0 +1,119 @@
+use prost::Message;
+use prost_reflect::{CorpusProfiler, FieldStatistics, ReflectMessage};
+
+use crate::{ComplexType, Scalars};
+
+fn field<'a>(fields: &'a [FieldStatistics], path: &str) -> &'a FieldStatistics {
+    fields
+        .iter()
+        .find(|field| field.path() == path)
+        .unwrap_or_else(|| panic!("field '{}' not found", path))
+}
+
+#[test]
+fn profile_fill_rates() {
+    let mut profiler = CorpusProfiler::new(Scalars::default().descriptor());
+    for int32 in 0..4 {
+        let message = Scalars {
+            int32,
+            string: "hello".to_owned(),
+            ..Default::default()
+        };
+        profiler.add(message.encode_to_vec().as_slice()).unwrap();
+    }
+
+    assert_eq!(profiler.message_count(), 4);
+    let fields: Vec<_> = profiler.fields().collect();
+    assert_eq!(fields.len(), 15);
+
+    let int32 = field(&fields, "int32");
+    assert_eq!(int32.message_count(), 4);
+    assert_eq!(int32.present_count(), 3);
+    assert_eq!(int32.fill_rate(), 0.75);
+    assert_eq!(int32.distinct_values(), Some(3));
+    assert_eq!(int32.encoded_len(), 6);
+
+    let string = field(&fields, "string");
+    assert_eq!(string.fill_rate(), 1.0);
+    assert_eq!(string.distinct_values(), Some(1));
+    assert_eq!(string.encoded_len(), 28);
+
+    let double = field(&fields, "double");
+    assert_eq!(double.present_count(), 0);
+    assert_eq!(double.fill_rate(), 0.0);
+    assert_eq!(double.encoded_len(), 0);
+
+    assert_eq!(
+        profiler.encoded_len(),
+        fields.iter().map(|field| field.encoded_len()).sum::<u64>()
+    );
+}
+
+#[test]
+fn profile_nested_fields() {
+    let mut profiler = CorpusProfiler::new(ComplexType::default().descriptor());
+    profiler
+        .add(
+            ComplexType {
+                nested: Some(Scalars {
+                    bool: true,
+                    ..Default::default()
+                }),
+                my_enum: vec![1, 1, 3],
+                ..Default::default()
+            }
+            .encode_to_vec()
+            .as_slice(),
+        )
+        .unwrap();
+    profiler
+        .add(ComplexType::default().encode_to_vec().as_slice())
+        .unwrap();
+
+    let fields: Vec<_> = profiler.fields().collect();
+
+    let nested = field(&fields, "nested");
+    assert_eq!(nested.fill_rate(), 0.5);
+
+    // Fill rates of nested fields are relative to the number of times the parent message appeared.
+    let nested_bool = field(&fields, "nested.bool");
+    assert_eq!(nested_bool.message_count(), 1);
+    assert_eq!(nested_bool.fill_rate(), 1.0);
+
+    let my_enum = field(&fields, "my_enum");
+    assert_eq!(my_enum.present_count(), 1);
+    assert_eq!(my_enum.value_count(), 3);
+    assert_eq!(my_enum.distinct_values(), Some(2));
+
+    assert!(fields
+        .iter()
+        .all(|field| !field.path().starts_with("int_map.")));
+}
+
+#[test]
+fn profile_unknown_fields() {
+    let mut profiler = CorpusProfiler::new(Scalars::default().descriptor());
+    profiler.add(b"\xa0\x06\x96\x01".as_ref()).unwrap();
+
+    let fields: Vec<_> = profiler.fields().collect();
+    let unknown = field(&fields, "100");
+    assert_eq!(unknown.fill_rate(), 1.0);
+    assert_eq!(unknown.encoded_len(), 4);
+}
+
+#[test]
+fn profile_max_distinct_values() {
+    let mut profiler = CorpusProfiler::new(Scalars::default().descriptor()).max_distinct_values(2);
+    for int32 in 1..=3 {
+        let message = Scalars {
+            int32,
+            bool: true,
+            ..Default::default()
+        };
+        profiler.add(message.encode_to_vec().as_slice()).unwrap();
+    }
+
+    let fields: Vec<_> = profiler.fields().collect();
+    assert_eq!(field(&fields, "int32").distinct_values(), None);
+    assert_eq!(field(&fields, "bool").distinct_values(), Some(1));
+}
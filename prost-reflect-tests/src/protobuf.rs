@@ -0,0 +1,88 @@
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage, Value};
+use protobuf::{well_known_types::timestamp::Timestamp, MessageFull};
+
+use crate::{test_file_descriptor, Point};
+
+#[test]
+fn pool_to_protobuf() {
+    let pool = test_file_descriptor();
+    let files = pool.to_protobuf().unwrap();
+
+    let names: Vec<_> = files.iter().map(|file| file.name().to_owned()).collect();
+    let expected: Vec<_> = pool.files().map(|file| file.name().to_owned()).collect();
+    assert_eq!(names, expected);
+
+    let point = files
+        .iter()
+        .find_map(|file| file.message_by_full_name(".test.Point"))
+        .unwrap();
+    let fields: Vec<_> = point
+        .fields()
+        .map(|field| field.name().to_owned())
+        .collect();
+    assert_eq!(fields, ["latitude", "longitude"]);
+}
+
+#[test]
+fn message_descriptor_to_protobuf() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.WellKnownTypes")
+        .unwrap();
+    let protobuf_desc = desc.to_protobuf().unwrap();
+    assert_eq!(protobuf_desc.full_name(), "test.WellKnownTypes");
+    assert_eq!(protobuf_desc.fields().count(), desc.fields().count());
+}
+
+#[test]
+fn pool_from_protobuf() {
+    let file = Timestamp::descriptor().file_descriptor().clone();
+    let pool = DescriptorPool::from_protobuf(&[file]).unwrap();
+
+    let desc = pool
+        .get_message_by_name("google.protobuf.Timestamp")
+        .unwrap();
+    assert_eq!(desc.fields().count(), 2);
+}
+
+#[test]
+fn message_roundtrip() {
+    let point = Point {
+        latitude: 1,
+        longitude: -2,
+    };
+    let message = point.transcode_to_dynamic();
+
+    let protobuf_desc = message.descriptor().to_protobuf().unwrap();
+    let protobuf_message = message.to_protobuf(&protobuf_desc).unwrap();
+    assert_eq!(
+        protobuf_message.write_to_bytes_dyn().unwrap(),
+        point.encode_to_vec()
+    );
+
+    let roundtrip =
+        DynamicMessage::from_protobuf(message.descriptor(), &*protobuf_message).unwrap();
+    assert_eq!(roundtrip, message);
+}
+
+#[test]
+fn generated_message_from_protobuf() {
+    let timestamp = Timestamp {
+        seconds: 12,
+        nanos: 34,
+        ..Default::default()
+    };
+    let desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.Timestamp")
+        .unwrap();
+
+    let message = DynamicMessage::from_protobuf(desc, &timestamp).unwrap();
+    assert_eq!(
+        message.get_field_by_name("seconds").unwrap().as_ref(),
+        &Value::I64(12)
+    );
+    assert_eq!(
+        message.get_field_by_name("nanos").unwrap().as_ref(),
+        &Value::I32(34)
+    );
+}
@@ -0,0 +1,327 @@
+use std::{cell::RefCell, io};
+
+use prost::Message;
+use prost_reflect::{
+    reflection::{
+        ErrorResponse, ExtensionNumberResponse, ExtensionRequest, FileDescriptorResponse,
+        ListServiceResponse, MessageRequest, MessageResponse, ReflectionClient, ReflectionService,
+        ServerReflectionRequest, ServerReflectionResponse, ServiceResponse,
+    },
+    DescriptorPool, FileDescriptor,
+};
+
+use crate::test_file_descriptor;
+
+/// Responds to requests using the test pool. Only the requested file is returned, without its
+/// dependencies, so the client must request them separately.
+fn serve(
+    requests: &RefCell<Vec<MessageRequest>>,
+) -> impl FnMut(ServerReflectionRequest) -> Result<ServerReflectionResponse, io::Error> + '_ {
+    move |request| {
+        let pool = test_file_descriptor();
+        let message_request = request.message_request.clone().unwrap();
+        requests.borrow_mut().push(message_request.clone());
+
+        let file = match message_request {
+            MessageRequest::ListServices(_) => {
+                return Ok(response(
+                    request,
+                    MessageResponse::ListServicesResponse(ListServiceResponse {
+                        service: pool
+                            .services()
+                            .map(|service| ServiceResponse {
+                                name: service.full_name().to_owned(),
+                            })
+                            .collect(),
+                    }),
+                ))
+            }
+            MessageRequest::FileByFilename(name) => pool.get_file_by_name(&name),
+            MessageRequest::FileContainingSymbol(symbol) => pool.files().find(|file| {
+                file.messages().any(|message| message.full_name() == symbol)
+                    || file.services().any(|service| service.full_name() == symbol)
+            }),
+            MessageRequest::FileContainingExtension(_)
+            | MessageRequest::AllExtensionNumbersOfType(_) => {
+                return Err(io::Error::other("unsupported"))
+            }
+        };
+
+        Ok(response(request, file_response(file)))
+    }
+}
+
+fn file_response(file: Option<FileDescriptor>) -> MessageResponse {
+    match file {
+        Some(file) => MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+            file_descriptor_proto: vec![file.file_descriptor_proto().encode_to_vec()],
+        }),
+        None => MessageResponse::ErrorResponse(ErrorResponse {
+            error_code: 5,
+            error_message: "not found".to_owned(),
+        }),
+    }
+}
+
+fn response(
+    request: ServerReflectionRequest,
+    message_response: MessageResponse,
+) -> ServerReflectionResponse {
+    ServerReflectionResponse {
+        valid_host: request.host.clone(),
+        original_request: Some(request),
+        message_response: Some(message_response),
+    }
+}
+
+#[test]
+fn list_services() {
+    let requests = RefCell::new(Vec::new());
+    let mut client = ReflectionClient::new(serve(&requests));
+
+    let services = client.list_services().unwrap();
+    assert!(services.contains(&"my.package.MyService".to_owned()));
+    assert!(client.pool().files().next().is_none());
+}
+
+#[test]
+fn load_symbol_resolves_dependencies() {
+    let requests = RefCell::new(Vec::new());
+    let mut client = ReflectionClient::new(serve(&requests));
+
+    client.load_symbol("test.ComplexType").unwrap();
+    let pool = client.into_pool();
+    assert!(pool.get_message_by_name("test.ComplexType").is_some());
+    assert!(pool
+        .get_message_by_name("google.protobuf.Timestamp")
+        .is_some());
+    assert!(pool
+        .get_file_by_name("google/protobuf/field_mask.proto")
+        .is_some());
+
+    let requests = requests.into_inner();
+    assert_eq!(
+        requests[0],
+        MessageRequest::FileContainingSymbol("test.ComplexType".to_owned())
+    );
+    assert_eq!(
+        requests.len(),
+        1 + test_file_descriptor()
+            .get_file_by_name("test.proto")
+            .unwrap()
+            .file_descriptor_proto()
+            .dependency
+            .len()
+    );
+}
+
+#[test]
+fn load_skips_files_in_initial_pool() {
+    let mut initial_pool = DescriptorPool::new();
+    initial_pool
+        .add_file_descriptor_protos(
+            test_file_descriptor()
+                .files()
+                .filter(|file| file.package_name() == "google.protobuf")
+                .map(|file| file.file_descriptor_proto().clone()),
+        )
+        .unwrap();
+
+    let requests = RefCell::new(Vec::new());
+    let mut client = ReflectionClient::new(serve(&requests)).initial_pool(initial_pool);
+
+    client.load_file("test.proto").unwrap();
+    client.load_file("test.proto").unwrap();
+    assert!(client.pool().get_message_by_name("test.Scalars").is_some());
+    drop(client);
+    assert_eq!(
+        requests.into_inner(),
+        vec![MessageRequest::FileByFilename("test.proto".to_owned())]
+    );
+}
+
+#[test]
+fn load_all_services() {
+    let requests = RefCell::new(Vec::new());
+    let mut client = ReflectionClient::new(serve(&requests)).host("example.com");
+
+    client.load_all_services().unwrap();
+    assert!(client
+        .pool()
+        .services()
+        .any(|service| service.full_name() == "my.package.MyService"));
+}
+
+#[test]
+fn errors() {
+    let requests = RefCell::new(Vec::new());
+    let mut client = ReflectionClient::new(serve(&requests));
+
+    let err = client.load_symbol("test.DoesNotExist").unwrap_err();
+    assert_eq!(err.to_string(), "server returned error 5: not found");
+
+    let err = client.load_extension("test.Scalars", 100).unwrap_err();
+    assert_eq!(err.to_string(), "failed to send request: unsupported");
+}
+
+fn handle(service: &ReflectionService, message_request: MessageRequest) -> MessageResponse {
+    service
+        .handle(&ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(message_request),
+        })
+        .message_response
+        .unwrap()
+}
+
+fn file_names(response: MessageResponse) -> Vec<String> {
+    match response {
+        MessageResponse::FileDescriptorResponse(response) => response
+            .file_descriptor_proto
+            .iter()
+            .map(|bytes| {
+                prost_types::FileDescriptorProto::decode(bytes.as_slice())
+                    .unwrap()
+                    .name()
+                    .to_owned()
+            })
+            .collect(),
+        response => panic!("unexpected response {:?}", response),
+    }
+}
+
+#[test]
+fn service_file_by_filename() {
+    let service = ReflectionService::new(test_file_descriptor());
+
+    let mut names = file_names(handle(
+        &service,
+        MessageRequest::FileByFilename("test.proto".to_owned()),
+    ));
+    assert_eq!(names[0], "test.proto");
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "google/protobuf/duration.proto",
+            "google/protobuf/empty.proto",
+            "google/protobuf/field_mask.proto",
+            "google/protobuf/struct.proto",
+            "google/protobuf/timestamp.proto",
+            "google/protobuf/wrappers.proto",
+            "test.proto",
+        ]
+    );
+}
+
+#[test]
+fn service_file_containing_symbol() {
+    let service = ReflectionService::new(test_file_descriptor());
+
+    for symbol in &[
+        "my.package.MyService",
+        "my.package.MyService.MyMethod",
+        "my.package.MyMessage.MyNestedMessage",
+        "my.package.MyEnum",
+    ] {
+        assert_eq!(
+            file_names(handle(
+                &service,
+                MessageRequest::FileContainingSymbol(symbol.to_string())
+            )),
+            vec!["desc.proto"]
+        );
+    }
+    assert_eq!(
+        file_names(handle(
+            &service,
+            MessageRequest::FileContainingSymbol("my.package2.OtherMessage.in_other".to_owned())
+        )),
+        vec!["desc2.proto"]
+    );
+}
+
+#[test]
+fn service_extensions() {
+    let service = ReflectionService::new(test_file_descriptor());
+
+    assert_eq!(
+        file_names(handle(
+            &service,
+            MessageRequest::FileContainingExtension(ExtensionRequest {
+                containing_type: "my.package2.MyMessage".to_owned(),
+                extension_number: 112,
+            })
+        )),
+        vec!["desc2.proto"]
+    );
+
+    match handle(
+        &service,
+        MessageRequest::AllExtensionNumbersOfType("my.package2.MyMessage".to_owned()),
+    ) {
+        MessageResponse::AllExtensionNumbersResponse(ExtensionNumberResponse {
+            base_type_name,
+            mut extension_number,
+        }) => {
+            extension_number.sort_unstable();
+            assert_eq!(base_type_name, "my.package2.MyMessage");
+            assert_eq!(extension_number, vec![111, 112, 113]);
+        }
+        response => panic!("unexpected response {:?}", response),
+    }
+}
+
+#[test]
+fn service_list_services() {
+    let service =
+        ReflectionService::new(test_file_descriptor()).services(vec!["my.package.MyService"]);
+
+    assert_eq!(
+        handle(&service, MessageRequest::ListServices(String::new())),
+        MessageResponse::ListServicesResponse(ListServiceResponse {
+            service: vec![ServiceResponse {
+                name: "my.package.MyService".to_owned()
+            }],
+        })
+    );
+}
+
+#[test]
+fn service_not_found() {
+    let service = ReflectionService::new(test_file_descriptor());
+
+    assert_eq!(
+        handle(
+            &service,
+            MessageRequest::FileContainingSymbol("my.package.Missing".to_owned())
+        ),
+        MessageResponse::ErrorResponse(ErrorResponse {
+            error_code: 5,
+            error_message: "symbol 'my.package.Missing' not found".to_owned(),
+        })
+    );
+
+    let response = service.handle(&ServerReflectionRequest::default());
+    assert_eq!(
+        response.message_response,
+        Some(MessageResponse::ErrorResponse(ErrorResponse {
+            error_code: 3,
+            error_message: "empty request".to_owned(),
+        }))
+    );
+}
+
+#[test]
+fn client_with_service() {
+    let mut client = ReflectionClient::new(ReflectionService::new(test_file_descriptor()));
+    client.load_all_services().unwrap();
+    client.load_symbol("test.WellKnownTypes").unwrap();
+
+    let pool = client.into_pool();
+    assert!(pool.get_message_by_name("my.package.MyMessage").is_some());
+    assert!(pool.get_message_by_name("test.WellKnownTypes").is_some());
+    assert!(pool
+        .get_message_by_name("google.protobuf.FieldMask")
+        .is_some());
+}
@@ -0,0 +1,153 @@
+use std::thread;
+
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DescriptorRegistry, FieldBuilder, FileDescriptorBuilder, MessageBuilder,
+    ReflectMessage,
+};
+use prost_types::FileDescriptorSet;
+
+fn file(name: &str, package: &str, message: &str) -> DescriptorPool {
+    FileDescriptorBuilder::new(name)
+        .package(package)
+        .add_message(MessageBuilder::new(message).add_field(FieldBuilder::new("id", 1, "int32")))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn register_files() {
+    let registry = DescriptorRegistry::new();
+    registry
+        .register_pool(&file("a.proto", "registry", "A"))
+        .unwrap();
+    let before = registry.pool();
+
+    registry
+        .register_file_descriptor_protos(
+            file("b.proto", "registry", "B")
+                .file_descriptor_protos()
+                .cloned(),
+        )
+        .unwrap();
+
+    assert!(registry.get_message_by_name("registry.A").is_some());
+    assert!(registry.get_message_by_name("registry.B").is_some());
+    assert_eq!(registry.pool().files().len(), 2);
+    // Earlier snapshots are unaffected.
+    assert_eq!(before.files().len(), 1);
+}
+
+#[test]
+fn register_file_descriptor_set_bytes() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: file("a.proto", "registry", "A")
+            .file_descriptor_protos()
+            .cloned()
+            .collect(),
+    };
+    let registry = DescriptorRegistry::new();
+    registry
+        .register_file_descriptor_set_bytes(&file_descriptor_set.encode_to_vec())
+        .unwrap();
+    assert!(registry.get_message_by_name("registry.A").is_some());
+}
+
+#[test]
+fn register_identical_files() {
+    let registry = DescriptorRegistry::new();
+    let pool = file("a.proto", "registry", "A");
+    registry.register_pool(&pool).unwrap();
+    registry.register_pool(&pool).unwrap();
+    registry
+        .register_file_descriptor_protos(pool.file_descriptor_protos().cloned())
+        .unwrap();
+    assert_eq!(registry.pool().files().len(), 1);
+}
+
+#[test]
+fn register_conflicting_files() {
+    let registry = DescriptorRegistry::new();
+    registry
+        .register_pool(&file("a.proto", "registry", "A"))
+        .unwrap();
+
+    let err = registry
+        .register_pool(&file("a.proto", "registry", "Other"))
+        .unwrap_err();
+    assert_eq!(err.merge_conflicts().len(), 1);
+
+    let err = registry
+        .register_pool(&file("b.proto", "registry", "A"))
+        .unwrap_err();
+    assert_eq!(err.merge_conflicts()[0].name(), "registry.A");
+
+    assert_eq!(registry.pool().files().len(), 1);
+    assert!(registry.get_message_by_name("registry.Other").is_none());
+}
+
+#[test]
+fn register_concurrently() {
+    let registry = DescriptorRegistry::named("register_concurrently");
+    let threads: Vec<_> = (0..8)
+        .map(|index| {
+            thread::spawn(move || {
+                let name = format!("file{}.proto", index);
+                let message = format!("Message{}", index);
+                registry
+                    .register_pool(&file(&name, "concurrent", &message))
+                    .unwrap();
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let pool = registry.pool();
+    assert_eq!(pool.files().len(), 8);
+    for index in 0..8 {
+        assert!(pool
+            .get_message_by_name(&format!("concurrent.Message{}", index))
+            .is_some());
+    }
+}
+
+#[test]
+fn named_registries() {
+    let a = DescriptorRegistry::named("named_registries_a");
+    let b = DescriptorRegistry::named("named_registries_b");
+    assert!(std::ptr::eq(
+        a,
+        DescriptorRegistry::named("named_registries_a")
+    ));
+    assert!(!std::ptr::eq(a, b));
+    assert!(!std::ptr::eq(a, DescriptorRegistry::global()));
+
+    a.register_pool(&file("a.proto", "named", "A")).unwrap();
+    assert!(DescriptorRegistry::named("named_registries_a")
+        .get_message_by_name("named.A")
+        .is_some());
+    assert!(b.get_message_by_name("named.A").is_none());
+}
+
+#[derive(Clone, PartialEq, Message, ReflectMessage)]
+#[prost_reflect(
+    descriptor_pool = "prost_reflect::DescriptorRegistry::global()",
+    message_name = "registry_derive.Derived"
+)]
+struct Derived {
+    #[prost(int32, tag = "1")]
+    id: i32,
+}
+
+#[test]
+fn derive_with_global_registry() {
+    DescriptorRegistry::global()
+        .register_pool(&file("registry_derive.proto", "registry_derive", "Derived"))
+        .unwrap();
+
+    let message = Derived { id: 5 }.transcode_to_dynamic();
+    assert_eq!(message.descriptor().full_name(), "registry_derive.Derived");
+    assert_eq!(message.get_field_by_name("id").unwrap().as_i32(), Some(5));
+}
@@ -0,0 +1,161 @@
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+    time::Duration,
+};
+
+use prost_reflect::remote::RemoteDescriptorSet;
+
+use crate::DESCRIPTOR_POOL_SET_BYTES;
+
+/// Serves one HTTP request for each response given, returning the head of each request.
+fn serve(
+    responses: Vec<(&'static str, Vec<String>, &'static [u8])>,
+) -> (String, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/descriptors.bin", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut heads = Vec::new();
+        for (status, headers, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            heads.push(head);
+
+            let mut stream = reader.into_inner();
+            write!(stream, "HTTP/1.1 {}\r\n", status).unwrap();
+            for header in headers {
+                write!(stream, "{}\r\n", header).unwrap();
+            }
+            write!(
+                stream,
+                "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+        }
+        heads
+    });
+    (url, handle)
+}
+
+#[test]
+fn remote_conditional_refresh() {
+    let (url, handle) = serve(vec![
+        (
+            "200 OK",
+            vec![
+                "ETag: \"v1\"".to_owned(),
+                "Last-Modified: Wed, 01 Jun 2022 00:00:00 GMT".to_owned(),
+            ],
+            DESCRIPTOR_POOL_SET_BYTES,
+        ),
+        ("304 Not Modified", vec![], b""),
+        (
+            "200 OK",
+            vec!["ETag: \"v2\"".to_owned()],
+            DESCRIPTOR_POOL_SET_BYTES,
+        ),
+    ]);
+
+    let mut remote = RemoteDescriptorSet::new(url).header("Authorization", "Bearer secret");
+    assert!(remote.pool().is_none());
+    let pool = remote.load().unwrap();
+    assert!(pool.get_message_by_name("test.Scalars").is_some());
+
+    assert!(!remote.refresh().unwrap());
+    assert!(remote.refresh().unwrap());
+    assert!(remote.pool().is_some());
+
+    let heads = handle.join().unwrap();
+    assert!(heads[0].contains("Authorization: Bearer secret\r\n"));
+    assert!(!heads[0].contains("If-None-Match"));
+    assert!(heads[1].contains("If-None-Match: \"v1\"\r\n"));
+    assert!(heads[1].contains("If-Modified-Since: Wed, 01 Jun 2022 00:00:00 GMT\r\n"));
+    assert!(heads[2].contains("If-None-Match: \"v1\"\r\n"));
+}
+
+#[test]
+fn remote_min_refresh_interval() {
+    let (url, handle) = serve(vec![("200 OK", vec![], DESCRIPTOR_POOL_SET_BYTES)]);
+
+    let mut remote = RemoteDescriptorSet::new(url).min_refresh_interval(Duration::from_secs(3600));
+    remote.load().unwrap();
+    assert!(!remote.refresh().unwrap());
+    assert_eq!(handle.join().unwrap().len(), 1);
+}
+
+#[test]
+fn remote_error_status() {
+    let (url, handle) = serve(vec![("404 Not Found", vec![], b"")]);
+
+    let err = RemoteDescriptorSet::new(url).load().unwrap_err();
+    handle.join().unwrap();
+    assert_eq!(err.to_string(), "server returned status 404");
+}
+
+#[test]
+fn remote_disk_cache() {
+    let cache_dir = env::temp_dir().join(format!("prost-reflect-remote-{}", std::process::id()));
+    let (url, handle) = serve(vec![
+        (
+            "200 OK",
+            vec!["ETag: \"v1\"".to_owned()],
+            DESCRIPTOR_POOL_SET_BYTES,
+        ),
+        ("304 Not Modified", vec![], b""),
+    ]);
+
+    RemoteDescriptorSet::new(url.clone())
+        .cache_dir(&cache_dir)
+        .load()
+        .unwrap();
+
+    // A new instance revalidates the cached copy instead of downloading it again.
+    let pool = RemoteDescriptorSet::new(url.clone())
+        .cache_dir(&cache_dir)
+        .load()
+        .unwrap();
+    assert!(pool.get_message_by_name("test.Scalars").is_some());
+
+    let heads = handle.join().unwrap();
+    assert!(heads[1].contains("If-None-Match: \"v1\"\r\n"));
+
+    // The server has shut down, so the cached copy is used.
+    let pool = RemoteDescriptorSet::new(url)
+        .cache_dir(&cache_dir)
+        .load()
+        .unwrap();
+    assert!(pool.get_message_by_name("test.Scalars").is_some());
+
+    fs::remove_dir_all(&cache_dir).unwrap();
+}
+
+#[test]
+fn remote_cache_write_failure() {
+    // A file where the cache directory should be, so the cache cannot be written.
+    let cache_dir =
+        env::temp_dir().join(format!("prost-reflect-remote-file-{}", std::process::id()));
+    fs::write(&cache_dir, b"").unwrap();
+    let (url, handle) = serve(vec![("200 OK", vec![], DESCRIPTOR_POOL_SET_BYTES)]);
+
+    let mut remote = RemoteDescriptorSet::new(url).cache_dir(&cache_dir);
+    let pool = remote.load().unwrap();
+    handle.join().unwrap();
+    assert!(pool.get_message_by_name("test.Scalars").is_some());
+    assert!(remote.pool().is_some());
+    assert!(remote.cache_error().is_some());
+
+    fs::remove_file(&cache_dir).unwrap();
+}
@@ -0,0 +1,195 @@
+use std::{env, fs};
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage, Value};
+
+const BASE_PROTO: &str = r#"
+syntax = "proto2";
+
+package reparse;
+
+message Base {
+  optional Inner inner = 1;
+  repeated Inner inners = 2;
+
+  extensions 100 to 200;
+}
+
+message Inner {
+  extensions 100 to 200;
+}
+"#;
+
+const EXT_PROTO: &str = r#"
+syntax = "proto2";
+
+package reparse;
+
+import "base.proto";
+
+extend Base {
+  optional int32 number = 100;
+}
+
+extend Inner {
+  optional string name = 101;
+}
+"#;
+
+/// Builds a pool containing only `base.proto`, and a copy of it which also contains the extensions
+/// defined in `ext.proto`.
+fn pools() -> (DescriptorPool, DescriptorPool) {
+    let dir = env::temp_dir().join(format!("prost-reflect-reparse-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("base.proto"), BASE_PROTO).unwrap();
+    fs::write(dir.join("ext.proto"), EXT_PROTO).unwrap();
+
+    let mut base_pool = DescriptorPool::new();
+    base_pool.add_proto_files(["base.proto"], [&dir]).unwrap();
+    let mut ext_pool = base_pool.clone();
+    ext_pool.add_proto_files(["ext.proto"], [&dir]).unwrap();
+
+    fs::remove_dir_all(&dir).unwrap();
+    (base_pool, ext_pool)
+}
+
+#[test]
+fn reparse_unknown_fields() {
+    let (base_pool, ext_pool) = pools();
+
+    // inner { name: "a" }, inners { name: "b" }, field 3: 7, number: 5
+    const BYTES: &[u8] = b"\x0a\x04\xaa\x06\x01a\x12\x04\xaa\x06\x01b\x18\x07\xa0\x06\x05";
+    let mut message = DynamicMessage::decode(
+        base_pool.get_message_by_name("reparse.Base").unwrap(),
+        BYTES,
+    )
+    .unwrap();
+    assert_eq!(
+        message
+            .unknown_fields()
+            .map(|field| field.number())
+            .collect::<Vec<_>>(),
+        vec![3, 100]
+    );
+
+    message.reparse_unknown_fields(&ext_pool).unwrap();
+
+    let number = ext_pool
+        .get_message_by_name("reparse.Base")
+        .unwrap()
+        .get_extension(100)
+        .unwrap();
+    let name = ext_pool
+        .get_message_by_name("reparse.Inner")
+        .unwrap()
+        .get_extension(101)
+        .unwrap();
+    assert_eq!(
+        message.descriptor(),
+        ext_pool.get_message_by_name("reparse.Base").unwrap()
+    );
+    assert_eq!(message.get_extension(&number).as_ref(), &Value::I32(5));
+    assert_eq!(
+        message
+            .get_field_by_name("inner")
+            .unwrap()
+            .as_message()
+            .unwrap()
+            .get_extension(&name)
+            .as_ref(),
+        &Value::String("a".to_owned())
+    );
+    assert_eq!(
+        message
+            .get_field_by_name("inners")
+            .unwrap()
+            .as_list()
+            .unwrap()[0]
+            .as_message()
+            .unwrap()
+            .get_extension(&name)
+            .as_ref(),
+        &Value::String("b".to_owned())
+    );
+    assert_eq!(
+        message
+            .unknown_fields()
+            .map(|field| field.number())
+            .collect::<Vec<_>>(),
+        vec![3]
+    );
+    assert_eq!(message.encode_to_vec(), BYTES);
+}
+
+#[test]
+fn reparse_unknown_fields_type_not_in_pool() {
+    let (base_pool, _) = pools();
+
+    let mut message = DynamicMessage::decode(
+        base_pool.get_message_by_name("reparse.Base").unwrap(),
+        b"\xa0\x06\x05".as_ref(),
+    )
+    .unwrap();
+    let original = message.clone();
+
+    message
+        .reparse_unknown_fields(&DescriptorPool::new())
+        .unwrap();
+    assert_eq!(message, original);
+}
+
+#[test]
+fn reparse_unknown_fields_wire_type_mismatch() {
+    let (base_pool, ext_pool) = pools();
+
+    // number: "x", which is not a valid encoding of an int32
+    let mut message = DynamicMessage::decode(
+        base_pool.get_message_by_name("reparse.Base").unwrap(),
+        b"\xa2\x06\x01x".as_ref(),
+    )
+    .unwrap();
+    let original = message.clone();
+
+    assert!(message.reparse_unknown_fields(&ext_pool).is_err());
+    assert_eq!(message, original);
+}
+
+#[test]
+fn reparse_unknown_fields_incompatible_type() {
+    const CHANGED_PROTO: &str = r#"
+syntax = "proto2";
+
+package reparse;
+
+message Base {
+  optional int32 inner = 1;
+
+  extensions 100 to 200;
+}
+"#;
+
+    let (base_pool, _) = pools();
+
+    let dir = env::temp_dir().join(format!(
+        "prost-reflect-reparse-changed-{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("base.proto"), CHANGED_PROTO).unwrap();
+    let mut changed_pool = DescriptorPool::new();
+    changed_pool
+        .add_proto_files(["base.proto"], [&dir])
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // inner {}, inners {}
+    let mut message = DynamicMessage::decode(
+        base_pool.get_message_by_name("reparse.Base").unwrap(),
+        b"\x0a\x00\x12\x00".as_ref(),
+    )
+    .unwrap();
+    let original = message.clone();
+
+    assert!(message.reparse_unknown_fields(&changed_pool).is_err());
+    assert_eq!(message, original);
+}
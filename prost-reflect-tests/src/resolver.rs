@@ -0,0 +1,404 @@
+use prost::Message;
+use prost_reflect::{
+    resolver::{self, unpack_any, ResolveError, SchemaResolver, TypeUrlPolicy},
+    DeserializeOptions, DynamicMessage, MessageDescriptor, ReflectMessage, SerializeOptions,
+};
+use serde_json::json;
+
+use crate::{test_file_descriptor, Point};
+
+#[derive(Debug)]
+struct PrefixResolver;
+
+impl SchemaResolver for PrefixResolver {
+    fn resolve_type_url(&self, type_url: &str) -> Result<Option<MessageDescriptor>, ResolveError> {
+        match type_url.strip_prefix("registry.example.com/") {
+            Some("broken") => Err(ResolveError::new("registry unavailable")),
+            Some(name) => Ok(test_file_descriptor().get_message_by_name(name)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn any_desc() -> MessageDescriptor {
+    test_file_descriptor()
+        .get_message_by_name("google.protobuf.Any")
+        .unwrap()
+}
+
+fn point_any(type_url: &str) -> DynamicMessage {
+    let any = prost_types::Any {
+        type_url: type_url.to_owned(),
+        value: Point {
+            latitude: 1,
+            longitude: 2,
+        }
+        .encode_to_vec(),
+    };
+    DynamicMessage::decode(any_desc(), any.encode_to_vec().as_slice()).unwrap()
+}
+
+fn to_json(message: &DynamicMessage, options: &SerializeOptions) -> Result<String, String> {
+    let mut serializer = serde_json::Serializer::new(vec![]);
+    message
+        .serialize_with_options(&mut serializer, options)
+        .map_err(|err| err.to_string())?;
+    Ok(String::from_utf8(serializer.into_inner()).unwrap())
+}
+
+fn from_json(
+    json: serde_json::Value,
+    options: &DeserializeOptions,
+) -> Result<DynamicMessage, String> {
+    DynamicMessage::deserialize_with_options(any_desc(), json, options)
+        .map_err(|err| err.to_string())
+}
+
+#[test]
+fn pool_resolves_any_prefix() {
+    let pool = test_file_descriptor();
+    assert_eq!(
+        pool.resolve_type_url("type.googleapis.com/test.Point")
+            .unwrap()
+            .unwrap()
+            .full_name(),
+        "test.Point"
+    );
+    assert_eq!(
+        pool.resolve_type_url("example.com/schemas/test.Point")
+            .unwrap()
+            .unwrap()
+            .full_name(),
+        "test.Point"
+    );
+    assert!(pool.resolve_type_url("test.Point").unwrap().is_none());
+    assert!(pool
+        .resolve_type_url("type.googleapis.com/test.Missing")
+        .unwrap()
+        .is_none());
+    assert!(pool.resolve_id(1).unwrap().is_none());
+    assert!(pool.resolve_subject("test-value", None).unwrap().is_none());
+}
+
+#[test]
+fn serialize_any_with_resolver() {
+    let message = point_any("registry.example.com/test.Point");
+
+    assert_eq!(
+        to_json(&message, &SerializeOptions::new()).unwrap_err(),
+        "unsupported type url 'registry.example.com/test.Point'"
+    );
+
+    let json = to_json(&message, &SerializeOptions::new().resolver(PrefixResolver)).unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+        json!({
+            "@type": "registry.example.com/test.Point",
+            "latitude": 1,
+            "longitude": 2,
+        })
+    );
+}
+
+#[test]
+fn deserialize_any_with_resolver() {
+    let json = json!({
+        "@type": "registry.example.com/test.Point",
+        "latitude": 1,
+        "longitude": 2,
+    });
+
+    assert_eq!(
+        from_json(json.clone(), &DeserializeOptions::new()).unwrap_err(),
+        "unsupported type url 'registry.example.com/test.Point'"
+    );
+
+    let message = from_json(json, &DeserializeOptions::new().resolver(PrefixResolver)).unwrap();
+    assert_eq!(message, point_any("registry.example.com/test.Point"));
+}
+
+#[test]
+fn resolver_falls_back_to_pool() {
+    let json = json!({
+        "@type": "type.googleapis.com/test.Point",
+        "latitude": 1,
+        "longitude": 2,
+    });
+
+    let message = from_json(json, &DeserializeOptions::new().resolver(PrefixResolver)).unwrap();
+    assert_eq!(message, point_any("type.googleapis.com/test.Point"));
+
+    let json = to_json(&message, &SerializeOptions::new().resolver(PrefixResolver)).unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+        json!({
+            "@type": "type.googleapis.com/test.Point",
+            "latitude": 1,
+            "longitude": 2,
+        })
+    );
+}
+
+#[test]
+fn resolver_error() {
+    let json = json!({ "@type": "registry.example.com/broken" });
+    assert_eq!(
+        from_json(json, &DeserializeOptions::new().resolver(PrefixResolver)).unwrap_err(),
+        "failed to resolve type url 'registry.example.com/broken': registry unavailable"
+    );
+}
+
+#[test]
+fn json_with_fn_resolver() {
+    let other_pool = test_file_descriptor();
+    let resolver = resolver::from_fn(move |type_url| match type_url.strip_prefix("other/") {
+        Some(name) => Ok(other_pool.get_message_by_name(name)),
+        None => Err(ResolveError::new("unknown registry")),
+    });
+    let json = json!({
+        "@type": "other/test.Point",
+        "latitude": 1,
+        "longitude": 2,
+    });
+
+    let message = from_json(
+        json.clone(),
+        &DeserializeOptions::new().resolver(resolver.clone()),
+    )
+    .unwrap();
+    assert_eq!(message, point_any("other/test.Point"));
+
+    let serialized = to_json(
+        &message,
+        &SerializeOptions::new().resolver(resolver.clone()),
+    )
+    .unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&serialized).unwrap(),
+        json
+    );
+
+    assert_eq!(
+        to_json(
+            &point_any("type.googleapis.com/test.Point"),
+            &SerializeOptions::new().resolver(resolver)
+        )
+        .unwrap_err(),
+        "failed to resolve type url 'type.googleapis.com/test.Point': unknown registry"
+    );
+}
+
+#[test]
+fn unpack_any_with_resolver() {
+    let any = prost_types::Any {
+        type_url: "registry.example.com/test.Point".to_owned(),
+        value: Point {
+            latitude: 1,
+            longitude: 2,
+        }
+        .encode_to_vec(),
+    };
+
+    let message = unpack_any(&any, &PrefixResolver).unwrap();
+    assert_eq!(message.descriptor().full_name(), "test.Point");
+    assert_eq!(
+        message.transcode_to::<Point>().unwrap(),
+        Point {
+            latitude: 1,
+            longitude: 2,
+        }
+    );
+
+    assert_eq!(
+        unpack_any(&any, &test_file_descriptor())
+            .unwrap()
+            .descriptor()
+            .full_name(),
+        "test.Point"
+    );
+
+    let missing = prost_types::Any {
+        type_url: "registry.example.com/test.Missing".to_owned(),
+        value: vec![],
+    };
+    assert_eq!(
+        unpack_any(&missing, &PrefixResolver)
+            .unwrap_err()
+            .to_string(),
+        "message type for 'registry.example.com/test.Missing' not found"
+    );
+}
+
+fn policy() -> TypeUrlPolicy {
+    TypeUrlPolicy::new()
+        .resolver("local", test_file_descriptor())
+        .resolver("registry", PrefixResolver)
+        .load(
+            "
+            # Types from the local pool
+            map type.googleapis.com/ local
+            map registry.example.com/ registry
+
+            allow test.*
+            allow google.protobuf.Timestamp
+            deny test.Scalars
+            ",
+        )
+        .unwrap()
+}
+
+fn resolve(policy: &TypeUrlPolicy, type_url: &str) -> Result<Option<String>, String> {
+    policy
+        .resolve_type_url(type_url)
+        .map(|desc| desc.map(|desc| desc.full_name().to_owned()))
+        .map_err(|err| err.to_string())
+}
+
+#[test]
+fn policy_resolve() {
+    let policy = policy();
+    assert_eq!(
+        resolve(&policy, "type.googleapis.com/test.Point"),
+        Ok(Some("test.Point".to_owned()))
+    );
+    assert_eq!(
+        resolve(&policy, "registry.example.com/test.Point"),
+        Ok(Some("test.Point".to_owned()))
+    );
+    assert_eq!(
+        resolve(&policy, "type.googleapis.com/google.protobuf.Timestamp"),
+        Ok(Some("google.protobuf.Timestamp".to_owned()))
+    );
+    assert_eq!(
+        resolve(&policy, "type.googleapis.com/test.Missing"),
+        Ok(None)
+    );
+    assert_eq!(
+        resolve(&policy, "type.googleapis.com/test.Scalars"),
+        Err("message type 'test.Scalars' is not permitted".to_owned())
+    );
+    assert_eq!(
+        resolve(&policy, "type.googleapis.com/google.protobuf.Duration"),
+        Err("message type 'google.protobuf.Duration' is not permitted".to_owned())
+    );
+    assert_eq!(
+        resolve(&policy, "type.googleapis.com/testing.Point"),
+        Err("message type 'testing.Point' is not permitted".to_owned())
+    );
+    assert_eq!(
+        resolve(&policy, "example.com/test.Point"),
+        Err("type url 'example.com/test.Point' is not permitted".to_owned())
+    );
+}
+
+#[test]
+fn policy_longest_prefix() {
+    let policy = TypeUrlPolicy::new()
+        .resolver("local", test_file_descriptor())
+        .resolver("registry", PrefixResolver)
+        .map("registry.example.com/", "local")
+        .map("registry.example.com/bro", "registry");
+    assert_eq!(
+        resolve(&policy, "registry.example.com/broken"),
+        Err("registry unavailable".to_owned())
+    );
+    assert_eq!(
+        resolve(&policy, "registry.example.com/test.Point"),
+        Ok(Some("test.Point".to_owned()))
+    );
+}
+
+#[test]
+fn policy_checks_resolved_message_name() {
+    let allow = TypeUrlPolicy::new()
+        .resolver("local", test_file_descriptor())
+        .map("type.googleapis.com/", "local")
+        .allow("test.*");
+    assert_eq!(
+        resolve(&allow, "type.googleapis.com/google.protobuf.Timestamp"),
+        Err("message type 'google.protobuf.Timestamp' is not permitted".to_owned())
+    );
+    assert_eq!(
+        resolve(
+            &allow,
+            "type.googleapis.com/test.Point/google.protobuf.Timestamp"
+        ),
+        Err(
+            "type url 'type.googleapis.com/test.Point/google.protobuf.Timestamp' is not permitted"
+                .to_owned()
+        )
+    );
+
+    let deny = TypeUrlPolicy::new()
+        .resolver("local", test_file_descriptor())
+        .map("type.googleapis.com/", "local")
+        .deny("google.protobuf.*");
+    assert_eq!(
+        resolve(&deny, "type.googleapis.com/x/google.protobuf.Timestamp"),
+        Err(
+            "type url 'type.googleapis.com/x/google.protobuf.Timestamp' is not permitted"
+                .to_owned()
+        )
+    );
+
+    let renaming = TypeUrlPolicy::new()
+        .resolver(
+            "renaming",
+            resolver::from_fn(|_| {
+                Ok(test_file_descriptor().get_message_by_name("google.protobuf.Timestamp"))
+            }),
+        )
+        .map("type.googleapis.com/", "renaming")
+        .allow("test.*");
+    assert_eq!(
+        resolve(&renaming, "type.googleapis.com/test.Point"),
+        Err("message type 'google.protobuf.Timestamp' is not permitted".to_owned())
+    );
+}
+
+#[test]
+fn policy_prevents_json_fallback() {
+    let json = json!({
+        "@type": "type.googleapis.com/test.Scalars",
+    });
+    assert_eq!(
+        from_json(json, &DeserializeOptions::new().resolver(policy())).unwrap_err(),
+        "failed to resolve type url 'type.googleapis.com/test.Scalars': message type 'test.Scalars' is not permitted"
+    );
+
+    let message = point_any("type.googleapis.com/test.Point");
+    to_json(&message, &SerializeOptions::new().resolver(policy())).unwrap();
+}
+
+#[test]
+fn policy_unpack_any() {
+    let any = prost_types::Any {
+        type_url: "type.googleapis.com/test.Scalars".to_owned(),
+        value: vec![],
+    };
+    assert_eq!(
+        unpack_any(&any, &policy()).unwrap_err().to_string(),
+        "message type 'test.Scalars' is not permitted"
+    );
+}
+
+#[test]
+fn policy_load_errors() {
+    let load = |config: &str| {
+        TypeUrlPolicy::new()
+            .resolver("local", test_file_descriptor())
+            .load(config)
+            .unwrap_err()
+            .to_string()
+    };
+
+    assert_eq!(
+        load("map a/ local\nmap b/ remote"),
+        "line 2: resolver 'remote' is not registered"
+    );
+    assert_eq!(
+        load("allow"),
+        "line 1: wrong number of arguments for 'allow'"
+    );
+    assert_eq!(load("\n\npermit test.*"), "line 3: unknown rule 'permit'");
+}
@@ -0,0 +1,94 @@
+use proptest::prelude::*;
+use prost_reflect::{
+    testing::{
+        assert_roundtrip, check_binary_roundtrip, check_json_roundtrip, check_text_format_roundtrip,
+    },
+    ArbitraryOptions, DeserializeOptions, DynamicMessage, ReflectMessage, SerializeOptions,
+};
+
+use crate::{test_file_descriptor, ComplexType, Scalars};
+
+#[test]
+fn roundtrip_checks_pass() {
+    let message = ComplexType {
+        nested: Some(Scalars {
+            int64: -5,
+            string: "hello".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![0, 1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_roundtrip(&message);
+}
+
+#[test]
+fn json_roundtrip_drops_unknown_fields() {
+    let mut message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+    );
+    message
+        .transcode_from(&Scalars {
+            int32: 150,
+            ..Default::default()
+        })
+        .unwrap();
+
+    check_binary_roundtrip(&message).unwrap();
+    let err = check_json_roundtrip(
+        &message,
+        &SerializeOptions::new(),
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "message changed after JSON round-trip (left: original, right: round-tripped):\n3: 150 != <unset>"
+    );
+}
+
+#[test]
+fn json_roundtrip_serialize_error() {
+    let message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Timestamp")
+            .unwrap(),
+    );
+    let mut message = message;
+    message.set_field_by_name("seconds", prost_reflect::Value::I64(i64::MAX));
+
+    let err = check_json_roundtrip(
+        &message,
+        &SerializeOptions::new(),
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().starts_with("failed to serialize message"));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn roundtrip_checks_arb_complex_type(
+        message in DynamicMessage::arbitrary_with_options(
+            test_file_descriptor().get_message_by_name("test.ComplexType").unwrap(),
+            &ArbitraryOptions::new(),
+        )
+    ) {
+        check_binary_roundtrip(&message).unwrap();
+        check_json_roundtrip(
+            &message,
+            &SerializeOptions::new().use_enum_numbers(true).skip_default_fields(false),
+            &DeserializeOptions::new(),
+        ).unwrap();
+        check_text_format_roundtrip(&message).unwrap();
+    }
+}
@@ -0,0 +1,361 @@
+use prost::Message;
+use prost_reflect::{DynamicMessage, MapKey, ReflectMessage, Value};
+
+use crate::{ComplexType, Point, Scalars};
+
+fn complex_message() -> DynamicMessage {
+    ComplexType::default().transcode_to_dynamic()
+}
+
+#[test]
+fn try_set_field_valid() {
+    let mut message = complex_message();
+
+    message
+        .try_set_field_by_name("optional_enum", Value::EnumNumber(3))
+        .unwrap();
+    message
+        .try_set_field_by_name(
+            "my_enum",
+            Value::List(vec![Value::EnumNumber(1), Value::EnumNumber(-4)]),
+        )
+        .unwrap();
+    message
+        .try_set_field_by_name(
+            "nested",
+            Value::Message(
+                Scalars {
+                    int32: 1,
+                    ..Default::default()
+                }
+                .transcode_to_dynamic(),
+            ),
+        )
+        .unwrap();
+    message
+        .try_set_field_by_name(
+            "int_map",
+            Value::Map(
+                IntoIterator::into_iter([(
+                    MapKey::I32(1),
+                    Value::Message(Scalars::default().transcode_to_dynamic()),
+                )])
+                .collect(),
+            ),
+        )
+        .unwrap();
+
+    let message: ComplexType = message.transcode_to().unwrap();
+    assert_eq!(message.optional_enum, 3);
+    assert_eq!(message.my_enum, vec![1, -4]);
+    assert_eq!(message.nested.unwrap().int32, 1);
+    assert_eq!(message.int_map.len(), 1);
+}
+
+#[test]
+fn try_set_field_type_mismatch() {
+    let mut message = complex_message();
+
+    let err = message
+        .try_set_field_by_name("optional_enum", Value::String("FOO".to_owned()))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.optional_enum': expected a value of enum 'test.ComplexType.MyEnum', but got a string"
+    );
+
+    let err = message
+        .try_set_field_by_name("my_enum", Value::EnumNumber(1))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.my_enum': expected a list, but got an enum"
+    );
+
+    let err = message
+        .try_set_field_by_name(
+            "nested",
+            Value::Message(Point::default().transcode_to_dynamic()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.nested': expected a message of type 'test.Scalars', but got a message of type 'test.Point'"
+    );
+
+    let err = message
+        .try_set_field_by_name(
+            "int_map",
+            Value::Map(
+                IntoIterator::into_iter([(
+                    MapKey::String("1".to_owned()),
+                    Value::Message(Scalars::default().transcode_to_dynamic()),
+                )])
+                .collect(),
+            ),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.int_map': expected map keys of a 32-bit integer, but got a key of a string"
+    );
+
+    assert_eq!(message, complex_message());
+}
+
+#[test]
+fn try_set_field_invalid_enum_value() {
+    let mut message = complex_message();
+
+    let err = message
+        .try_set_field_by_name("optional_enum", Value::EnumNumber(2))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.optional_enum': enum 'test.ComplexType.MyEnum' has no value with number 2"
+    );
+
+    let err = message
+        .try_set_field_by_name(
+            "my_enum",
+            Value::List(vec![Value::EnumNumber(1), Value::EnumNumber(5)]),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.my_enum': enum 'test.ComplexType.MyEnum' has no value with number 5"
+    );
+
+    assert_eq!(message, complex_message());
+}
+
+#[test]
+fn try_set_field_not_found() {
+    let mut message = complex_message();
+
+    let err = message
+        .try_set_field_by_name("missing", Value::I32(1))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "message 'test.ComplexType' has no field named 'missing'"
+    );
+}
+
+#[test]
+fn list_helpers() {
+    let mut message = complex_message();
+    let field = message.descriptor().get_field_by_name("my_enum").unwrap();
+
+    message
+        .push_field_value(&field, Value::EnumNumber(1))
+        .unwrap();
+    message
+        .extend_field(&field, vec![Value::EnumNumber(3), Value::EnumNumber(-4)])
+        .unwrap();
+    message.insert_at(&field, 0, Value::EnumNumber(0)).unwrap();
+    message.insert_at(&field, 4, Value::EnumNumber(1)).unwrap();
+    assert_eq!(message.remove_at(&field, 2).unwrap(), Value::EnumNumber(3));
+
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap().my_enum,
+        vec![0, 1, -4, 1]
+    );
+}
+
+#[test]
+fn list_helpers_errors() {
+    let mut message = complex_message();
+    let field = message.descriptor().get_field_by_name("my_enum").unwrap();
+    let nested = message.descriptor().get_field_by_name("nested").unwrap();
+
+    let err = message.push_field_value(&field, Value::I32(1)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.my_enum': expected a value of enum 'test.ComplexType.MyEnum', but got an integer"
+    );
+
+    let err = message
+        .extend_field(&field, vec![Value::EnumNumber(1), Value::EnumNumber(2)])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.my_enum': enum 'test.ComplexType.MyEnum' has no value with number 2"
+    );
+
+    let err = message
+        .insert_at(&field, 1, Value::EnumNumber(1))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "index 1 is out of range for field 'test.ComplexType.my_enum' with 0 elements"
+    );
+
+    let err = message.remove_at(&field, 0).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "index 0 is out of range for field 'test.ComplexType.my_enum' with 0 elements"
+    );
+
+    let err = message
+        .push_field_value(
+            &nested,
+            Value::Message(Scalars::default().transcode_to_dynamic()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "field 'test.ComplexType.nested' is not a repeated field"
+    );
+
+    assert_eq!(message, complex_message());
+}
+
+#[test]
+fn map_helpers() {
+    let mut message = complex_message();
+    let field = message.descriptor().get_field_by_name("int_map").unwrap();
+    let scalars = |int32| {
+        Value::Message(
+            Scalars {
+                int32,
+                ..Default::default()
+            }
+            .transcode_to_dynamic(),
+        )
+    };
+
+    assert_eq!(message.get_map_value(&field, &MapKey::I32(1)), None);
+    assert_eq!(
+        message
+            .insert_map_value(&field, MapKey::I32(1), scalars(1))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        message
+            .insert_map_value(&field, MapKey::I32(2), scalars(2))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        message
+            .insert_map_value(&field, MapKey::I32(1), scalars(3))
+            .unwrap(),
+        Some(scalars(1))
+    );
+    assert_eq!(
+        message.get_map_value(&field, &MapKey::I32(1)),
+        Some(&scalars(3))
+    );
+    assert_eq!(
+        message.remove_map_value(&field, &MapKey::I32(2)).unwrap(),
+        Some(scalars(2))
+    );
+    assert_eq!(
+        message.remove_map_value(&field, &MapKey::I32(2)).unwrap(),
+        None
+    );
+
+    let message: ComplexType = message.transcode_to().unwrap();
+    assert_eq!(message.int_map.len(), 1);
+    assert_eq!(message.int_map[&1].int32, 3);
+}
+
+#[test]
+fn map_helpers_errors() {
+    let mut message = complex_message();
+    let field = message.descriptor().get_field_by_name("int_map").unwrap();
+    let list = message.descriptor().get_field_by_name("my_enum").unwrap();
+
+    let err = message
+        .insert_map_value(&field, MapKey::String("1".to_owned()), Value::I32(1))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.int_map': expected map keys of a 32-bit integer, but got a key of a string"
+    );
+
+    let err = message
+        .insert_map_value(&field, MapKey::I32(1), Value::I32(1))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field 'test.ComplexType.int_map': expected a message of type 'test.Scalars', but got an integer"
+    );
+
+    let err = message
+        .remove_map_value(&list, &MapKey::I32(1))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "field 'test.ComplexType.my_enum' is not a map field"
+    );
+
+    assert_eq!(message, complex_message());
+}
+
+#[test]
+fn set_fields_out_of_order() {
+    let mut message = Scalars::default().transcode_to_dynamic();
+    message.set_field_by_name("string", Value::String("hello".to_owned()));
+    message.set_field_by_name("int32", Value::I32(3));
+    message.set_field_by_name("bool", Value::Bool(true));
+    message.set_field_by_name("double", Value::F64(1.5));
+    message.set_field_by_name("int32", Value::I32(4));
+    message.clear_field_by_name("bool");
+
+    let expected = Scalars {
+        double: 1.5,
+        int32: 4,
+        string: "hello".to_owned(),
+        ..Default::default()
+    };
+    assert_eq!(message.transcode_to::<Scalars>().unwrap(), expected);
+    // Fields are encoded in order of their numbers.
+    assert_eq!(message.encode_to_vec(), expected.encode_to_vec());
+
+    let mut in_order = Scalars::default().transcode_to_dynamic();
+    in_order.set_field_by_name("double", Value::F64(1.5));
+    in_order.set_field_by_name("int32", Value::I32(4));
+    in_order.set_field_by_name("string", Value::String("hello".to_owned()));
+    assert_eq!(message, in_order);
+}
+
+#[test]
+fn encoded_len_updated_after_modification() {
+    let mut message = ComplexType {
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    assert_eq!(message.encoded_len(), message.encode_to_vec().len());
+
+    message
+        .get_field_by_name_mut("nested")
+        .unwrap()
+        .as_message_mut()
+        .unwrap()
+        .set_field_by_name("string", Value::String("hello".to_owned()));
+    let expected = ComplexType {
+        nested: Some(Scalars {
+            int32: 5,
+            string: "hello".to_owned(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert_eq!(message.encoded_len(), expected.encoded_len());
+    assert_eq!(message.encode_to_vec(), expected.encode_to_vec());
+
+    let clone = message.clone();
+    message.clear_field_by_name("nested");
+    assert_eq!(message.encoded_len(), 0);
+    assert_eq!(clone.encoded_len(), expected.encoded_len());
+
+    message.merge(expected.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(message.encoded_len(), expected.encoded_len());
+}
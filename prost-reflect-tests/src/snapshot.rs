@@ -0,0 +1,144 @@
+use prost_reflect::{DynamicMessage, ReflectMessage, SnapshotOptions, Value};
+
+use crate::{test_file_descriptor, ComplexType, Scalars};
+
+fn complex_type() -> DynamicMessage {
+    ComplexType {
+        string_map: vec![
+            (
+                "foo".to_owned(),
+                Scalars {
+                    double: -0.0,
+                    float: 0.1,
+                    ..Default::default()
+                },
+            ),
+            (
+                "bar".to_owned(),
+                Scalars {
+                    string: "hello\n".to_owned(),
+                    bytes: b"\x00\x01abc".to_vec(),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .collect(),
+        nested: Some(Scalars {
+            int32: 5,
+            double: f64::NAN,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3, 7],
+        optional_enum: -4,
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn snapshot_complex_type() {
+    assert_eq!(
+        complex_type().to_snapshot(&SnapshotOptions::new()),
+        r#"test.ComplexType {
+  string_map { key: "bar", value: {
+    string: "hello\n"
+    bytes: "\x00\x01abc"
+  } }
+  string_map { key: "foo", value: {
+    float: 0.1
+  } }
+  nested: {
+    double: nan
+    int32: 5
+  }
+  my_enum: FOO
+  my_enum: BAR
+  my_enum: 7
+  optional_enum: NEG
+}
+"#
+    );
+}
+
+#[test]
+fn snapshot_is_stable() {
+    let message = complex_type();
+    let snapshot = message.to_snapshot(&SnapshotOptions::new());
+    for _ in 0..10 {
+        assert_eq!(
+            message.clone().to_snapshot(&SnapshotOptions::new()),
+            snapshot
+        );
+    }
+}
+
+#[test]
+fn snapshot_redacted_paths() {
+    let options = SnapshotOptions::new()
+        .redact("nested.int32")
+        .redact("string_map.string")
+        .redact("my_enum");
+
+    assert_eq!(
+        complex_type().to_snapshot(&options),
+        r#"test.ComplexType {
+  string_map { key: "bar", value: {
+    string: [redacted]
+    bytes: "\x00\x01abc"
+  } }
+  string_map { key: "foo", value: {
+    float: 0.1
+  } }
+  nested: {
+    double: nan
+    int32: [redacted]
+  }
+  my_enum: [redacted]
+  my_enum: [redacted]
+  my_enum: [redacted]
+  optional_enum: NEG
+}
+"#
+    );
+}
+
+#[test]
+fn snapshot_empty_and_unknown_fields() {
+    let mut message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+    );
+    assert_eq!(
+        message.to_snapshot(&SnapshotOptions::new()),
+        "google.protobuf.Empty {}\n"
+    );
+
+    message
+        .transcode_from(&Scalars {
+            int32: 150,
+            string: "hi".to_owned(),
+            float: 1.0,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(
+        message.to_snapshot(&SnapshotOptions::new()),
+        "google.protobuf.Empty {\n  2: 0x3f800000\n  3: 150\n  14: \"hi\"\n}\n"
+    );
+}
+
+#[test]
+fn snapshot_omits_default_fields() {
+    let mut message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("test.Scalars")
+            .unwrap(),
+    );
+    message.set_field_by_name("int32", Value::I32(0));
+    assert_eq!(
+        message.to_snapshot(&SnapshotOptions::new()),
+        "test.Scalars {}\n"
+    );
+}
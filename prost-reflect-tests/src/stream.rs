@@ -0,0 +1,231 @@
+use std::io::{self, Write};
+
+use proptest::prelude::*;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, FileDescriptorSetDecoder, ReflectMessage};
+use prost_types::FileDescriptorSet;
+
+use crate::{
+    arb_message, contains_group, test_file_descriptor, ComplexType, ContainsGroup, Scalars,
+    DESCRIPTOR_POOL_SET_BYTES,
+};
+
+fn file_names(pool: &DescriptorPool) -> Vec<String> {
+    pool.files().map(|file| file.name().to_owned()).collect()
+}
+
+#[test]
+fn decode_chunks() {
+    let expected = file_names(&test_file_descriptor());
+    for &chunk_size in &[1, 7, 64, 4096, DESCRIPTOR_POOL_SET_BYTES.len()] {
+        let pool =
+            DescriptorPool::decode_chunks(DESCRIPTOR_POOL_SET_BYTES.chunks(chunk_size)).unwrap();
+        assert_eq!(file_names(&pool), expected);
+    }
+}
+
+#[test]
+fn decoder_progress() {
+    let file_count = test_file_descriptor().files().len();
+
+    let mut decoder = FileDescriptorSetDecoder::new();
+    let mut completed = 0;
+    let mut last_files_decoded = 0;
+    for chunk in DESCRIPTOR_POOL_SET_BYTES.chunks(100) {
+        completed += decoder.push(chunk).unwrap();
+        assert!(decoder.files_decoded() >= last_files_decoded);
+        last_files_decoded = decoder.files_decoded();
+    }
+
+    assert_eq!(completed, file_count);
+    assert_eq!(decoder.files_decoded(), file_count);
+    assert_eq!(decoder.decoded_files().len(), file_count);
+    assert_eq!(decoder.bytes_read(), DESCRIPTOR_POOL_SET_BYTES.len() as u64);
+    decoder.finish().unwrap();
+}
+
+#[test]
+fn decode_chunks_skips_unknown_fields() {
+    let mut bytes = b"\x10\x96\x01\x1d\x01\x02\x03\x04".to_vec();
+    bytes.extend_from_slice(DESCRIPTOR_POOL_SET_BYTES);
+    let pool = DescriptorPool::decode_chunks(bytes.chunks(3)).unwrap();
+    assert_eq!(file_names(&pool), file_names(&test_file_descriptor()));
+}
+
+#[test]
+fn decode_chunks_truncated() {
+    let bytes = &DESCRIPTOR_POOL_SET_BYTES[..DESCRIPTOR_POOL_SET_BYTES.len() - 1];
+    let mut decoder = FileDescriptorSetDecoder::new();
+    decoder.push(bytes).unwrap();
+    let files_decoded = decoder.files_decoded();
+    assert_eq!(files_decoded, test_file_descriptor().files().len() - 1);
+
+    let err = decoder.finish().unwrap_err();
+    assert_eq!(err.to_string(), "failed to decode file descriptor set");
+}
+
+#[test]
+fn decode_chunks_invalid_file() {
+    let mut set = FileDescriptorSet::decode(DESCRIPTOR_POOL_SET_BYTES).unwrap();
+    set.file.truncate(1);
+    let mut bytes = set.encode_to_vec();
+    bytes.extend_from_slice(b"\x0a\x02\x08\x01");
+
+    let mut decoder = FileDescriptorSetDecoder::new();
+    assert!(decoder.push(&bytes).is_err());
+    assert_eq!(decoder.files_decoded(), 1);
+}
+
+/// A writer which records the length of each write.
+#[derive(Default)]
+struct RecordingWriter {
+    bytes: Vec<u8>,
+    writes: Vec<usize>,
+}
+
+impl Write for RecordingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.extend_from_slice(buf);
+        self.writes.push(buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn large_message() -> DynamicMessage {
+    ComplexType {
+        int_map: (0..1000)
+            .map(|i| {
+                let value = Scalars {
+                    int32: i,
+                    string: format!("value {}", i),
+                    ..Default::default()
+                };
+                (i, value)
+            })
+            .collect(),
+        nested: Some(Scalars {
+            bytes: vec![7; 20_000],
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3, -4],
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn encode_to_writer() {
+    let message = large_message();
+
+    let mut writer = RecordingWriter::default();
+    message.encode_to_writer(&mut writer).unwrap();
+    assert_eq!(writer.bytes, message.encode_to_vec());
+    assert!(writer.writes.len() > 1);
+}
+
+#[test]
+fn decode_from_reader() {
+    let message = large_message();
+    let bytes = message.encode_to_vec();
+
+    let decoded =
+        DynamicMessage::decode_from_reader(message.descriptor(), bytes.as_slice()).unwrap();
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn decode_from_reader_group() {
+    let message = ContainsGroup {
+        groupmessage: vec![
+            contains_group::GroupMessage {
+                url: "hello".to_owned(),
+                id: Some(10),
+            },
+            contains_group::GroupMessage::default(),
+        ],
+    };
+    let bytes = message.encode_to_vec();
+
+    let decoded =
+        DynamicMessage::decode_from_reader(message.descriptor(), bytes.as_slice()).unwrap();
+    assert_eq!(decoded.transcode_to::<ContainsGroup>().unwrap(), message);
+}
+
+#[test]
+fn decode_from_reader_unexpected_eof() {
+    let message = large_message();
+    let bytes = message.encode_to_vec();
+
+    for &len in &[1, 2, bytes.len() - 1] {
+        let err =
+            DynamicMessage::decode_from_reader(message.descriptor(), &bytes[..len]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    let err = DynamicMessage::decode_from_reader(
+        ContainsGroup::default().descriptor(),
+        [0x0b, 0x12, 0x00].as_ref(),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn decode_from_reader_invalid_data() {
+    let desc = ComplexType::default().descriptor();
+    for bytes in [
+        // Invalid wire type.
+        [0x0f].as_ref(),
+        // Unexpected end group tag.
+        [0x0c].as_ref(),
+        // Invalid UTF-8 in a string field of a nested message.
+        [0x1a, 0x03, 0x72, 0x01, 0xff].as_ref(),
+        // Field number zero.
+        [0x00].as_ref(),
+    ] {
+        let err = DynamicMessage::decode_from_reader(desc.clone(), bytes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", bytes);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn roundtrip_stream_complex_type(message in arb_message("test.ComplexType")) {
+        roundtrip_stream(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_stream_well_known_types(message in arb_message("test.WellKnownTypes")) {
+        roundtrip_stream(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_stream_proto2(message in arb_message("test2.DefaultValues")) {
+        roundtrip_stream(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_stream_group(message in arb_message("test2.ContainsGroup")) {
+        roundtrip_stream(&message)?;
+    }
+}
+
+fn roundtrip_stream(message: &DynamicMessage) -> Result<(), TestCaseError> {
+    let mut bytes = Vec::new();
+    message.encode_to_writer(&mut bytes).unwrap();
+    prop_assert_eq!(&bytes, &message.encode_to_vec());
+
+    let decoded =
+        DynamicMessage::decode_from_reader(message.descriptor(), bytes.as_slice()).unwrap();
+    prop_assert_eq!(message, &decoded);
+    Ok(())
+}
@@ -0,0 +1,463 @@
+use prost::Message;
+use prost_reflect::{DynamicMessage, FormatOptions, MapKey, ReflectMessage, Value};
+
+use crate::{
+    contains_group::GroupMessage, test_file_descriptor, ComplexType, ContainsGroup, ScalarArrays,
+    Scalars,
+};
+
+fn parse<T>(input: &str) -> T
+where
+    T: ReflectMessage + Default,
+{
+    DynamicMessage::parse_text_format(T::default().descriptor(), input)
+        .unwrap()
+        .transcode_to()
+        .unwrap()
+}
+
+fn parse_err<T>(input: &str) -> String
+where
+    T: ReflectMessage + Default,
+{
+    DynamicMessage::parse_text_format(T::default().descriptor(), input)
+        .unwrap_err()
+        .to_string()
+}
+
+fn format<T>(message: &T, options: &FormatOptions) -> String
+where
+    T: ReflectMessage,
+{
+    message
+        .transcode_to_dynamic()
+        .to_text_format_with_options(options)
+}
+
+#[test]
+fn format_scalars() {
+    let message = Scalars {
+        double: 1.5,
+        float: f32::NEG_INFINITY,
+        int32: -3,
+        uint64: u64::MAX,
+        bool: true,
+        string: "a\"b\n\u{e9}".to_owned(),
+        bytes: b"\x00\xffz".to_vec(),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        format(&message, &FormatOptions::new()),
+        r#"double: 1.5 float: -inf int32: -3 uint64: 18446744073709551615 bool: true string: "a\"b\né" bytes: "\000\377z""#
+    );
+}
+
+#[test]
+fn format_pretty() {
+    let message = ComplexType {
+        string_map: vec![
+            (
+                "b".to_owned(),
+                Scalars {
+                    int32: 2,
+                    ..Default::default()
+                },
+            ),
+            ("a".to_owned(), Scalars::default()),
+        ]
+        .into_iter()
+        .collect(),
+        nested: Some(Scalars {
+            sint64: 4,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 5],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        format(&message, &FormatOptions::new().pretty(true)),
+        "string_map {\n  key: \"a\"\n  value {}\n}\nstring_map {\n  key: \"b\"\n  value {\n    int32: 2\n  }\n}\nnested {\n  sint64: 4\n}\nmy_enum: FOO\nmy_enum: 5"
+    );
+    assert_eq!(
+        message.transcode_to_dynamic().to_string(),
+        "string_map { key: \"a\" value {} } string_map { key: \"b\" value { int32: 2 } } nested { sint64: 4 } my_enum: FOO my_enum: 5"
+    );
+    assert_eq!(
+        format!("{:#}", message.transcode_to_dynamic()),
+        format(&message, &FormatOptions::new().pretty(true))
+    );
+}
+
+#[test]
+fn format_protoc_style() {
+    let message = ComplexType {
+        string_map: vec![("a".to_owned(), Scalars::default())]
+            .into_iter()
+            .collect(),
+        nested: Some(Scalars {
+            sint64: 4,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 5],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        message.transcode_to_dynamic().to_text_format_pretty(),
+        "string_map {\n  key: \"a\"\n  value {\n  }\n}\nnested {\n  sint64: 4\n}\nmy_enum: FOO\nmy_enum: 5\n"
+    );
+    assert_eq!(
+        Scalars::default()
+            .transcode_to_dynamic()
+            .to_text_format_pretty(),
+        ""
+    );
+}
+
+#[test]
+fn format_value() {
+    assert_eq!(Value::I32(-3).to_string(), "-3");
+    assert_eq!(Value::F64(f64::NAN).to_string(), "nan");
+    assert_eq!(Value::String("a\"b".to_owned()).to_string(), r#""a\"b""#);
+    assert_eq!(
+        Value::Bytes(b"\x00z".as_ref().into()).to_string(),
+        r#""\000z""#
+    );
+    assert_eq!(Value::EnumNumber(3).to_string(), "3");
+    assert_eq!(
+        Value::List(vec![Value::U32(1), Value::U32(2)]).to_string(),
+        "[1, 2]"
+    );
+    assert_eq!(Value::List(vec![]).to_string(), "[]");
+
+    let message = Scalars {
+        int32: 5,
+        string: "s".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    assert_eq!(message.to_string(), "int32: 5 string: \"s\"");
+    assert_eq!(
+        Value::Message(message.clone()).to_string(),
+        "{ int32: 5 string: \"s\" }"
+    );
+    assert_eq!(
+        format!("{:#}", Value::Message(message.clone())),
+        "{\n  int32: 5\n  string: \"s\"\n}"
+    );
+    assert_eq!(
+        Value::Message(Scalars::default().transcode_to_dynamic()).to_string(),
+        "{}"
+    );
+
+    let map = Value::Map(
+        vec![
+            (MapKey::String("b".to_owned()), Value::Message(message)),
+            (MapKey::String("a".to_owned()), Value::I32(1)),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    assert_eq!(
+        map.to_string(),
+        "[{ key: \"a\" value: 1 }, { key: \"b\" value { int32: 5 string: \"s\" } }]"
+    );
+}
+
+#[test]
+fn format_group() {
+    let message = ContainsGroup {
+        groupmessage: vec![GroupMessage {
+            url: "x".to_owned(),
+            id: Some(1),
+        }],
+    };
+
+    assert_eq!(
+        format(&message, &FormatOptions::new()),
+        "GroupMessage { url: \"x\" id: 1 }"
+    );
+}
+
+#[test]
+fn format_unknown_fields() {
+    let mut message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+    );
+    message
+        .merge(
+            Scalars {
+                int32: 150,
+                fixed32: 7,
+                string: "s".to_owned(),
+                ..Default::default()
+            }
+            .encode_to_vec()
+            .as_slice(),
+        )
+        .unwrap();
+
+    assert_eq!(message.to_text_format(), "");
+    assert_eq!(
+        message.to_text_format_with_options(&FormatOptions::new().skip_unknown_fields(false)),
+        "3: 150 9: 0x7 14: \"s\""
+    );
+    assert_eq!(
+        message.to_text_format_pretty(),
+        "3: 150\n9: 0x00000007\n14: \"s\"\n"
+    );
+}
+
+#[test]
+fn format_any() {
+    let any_desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.Any")
+        .unwrap();
+    let mut any = DynamicMessage::new(any_desc);
+    any.set_field_by_name(
+        "type_url",
+        Value::String("type.googleapis.com/test.Scalars".to_owned()),
+    );
+    any.set_field_by_name(
+        "value",
+        Value::Bytes(
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            }
+            .encode_to_vec()
+            .into(),
+        ),
+    );
+
+    assert_eq!(
+        any.to_text_format(),
+        "[type.googleapis.com/test.Scalars] { int32: 5 }"
+    );
+    assert_eq!(
+        any.to_text_format_pretty(),
+        "[type.googleapis.com/test.Scalars] {\n  int32: 5\n}\n"
+    );
+    assert_eq!(
+        any.to_text_format_with_options(&FormatOptions::new().expand_any(false)),
+        "type_url: \"type.googleapis.com/test.Scalars\" value: \"\\030\\005\""
+    );
+
+    let parsed = DynamicMessage::parse_text_format(
+        any.descriptor(),
+        "[type.googleapis.com/test.Scalars] { int32: 5 }",
+    )
+    .unwrap();
+    assert_eq!(parsed, any);
+}
+
+#[test]
+fn parse_scalars() {
+    assert_eq!(
+        parse::<Scalars>(
+            r#"
+            # comment
+            double: -1.5e2
+            float: -Infinity
+            int32: 0x1f;
+            int64: -9223372036854775808,
+            uint32: 010
+            sint32: -2
+            fixed64: 18446744073709551615
+            bool: t
+            string: 'a' "\x62\u00e9\n"
+            bytes: "\000\377"
+            "#
+        ),
+        Scalars {
+            double: -150.0,
+            float: f32::NEG_INFINITY,
+            int32: 31,
+            int64: i64::MIN,
+            uint32: 8,
+            sint32: -2,
+            fixed64: u64::MAX,
+            bool: true,
+            string: "ab\u{e9}\n".to_owned(),
+            bytes: b"\x00\xff".to_vec(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn parse_repeated() {
+    assert_eq!(
+        parse::<ScalarArrays>("int32: 1 int32: [2, 3] string: [] bool: [true, false]"),
+        ScalarArrays {
+            int32: vec![1, 2, 3],
+            bool: vec![true, false],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn parse_nested() {
+    assert_eq!(
+        parse::<ComplexType>(
+            r#"
+            string_map { key: "a" value { int32: 1 } }
+            string_map: < key: "b" >
+            int_map [{ key: -1 value: {} }]
+            nested: { string: "n" }
+            my_enum: [FOO, 3, -4]
+            optional_enum: BAR
+            "#
+        ),
+        ComplexType {
+            string_map: vec![
+                (
+                    "a".to_owned(),
+                    Scalars {
+                        int32: 1,
+                        ..Default::default()
+                    }
+                ),
+                ("b".to_owned(), Scalars::default()),
+            ]
+            .into_iter()
+            .collect(),
+            int_map: vec![(-1, Scalars::default())].into_iter().collect(),
+            nested: Some(Scalars {
+                string: "n".to_owned(),
+                ..Default::default()
+            }),
+            my_enum: vec![1, 3, -4],
+            optional_enum: 3,
+        }
+    );
+}
+
+#[test]
+fn parse_group() {
+    assert_eq!(
+        parse::<ContainsGroup>("GroupMessage { url: \"x\" } GroupMessage { url: \"y\" id: 2 }"),
+        ContainsGroup {
+            groupmessage: vec![
+                GroupMessage {
+                    url: "x".to_owned(),
+                    id: None,
+                },
+                GroupMessage {
+                    url: "y".to_owned(),
+                    id: Some(2),
+                }
+            ],
+        }
+    );
+}
+
+#[test]
+fn merge_text_format() {
+    let mut message = Scalars {
+        int32: 1,
+        int64: 2,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    message.merge_text_format("int64: 3 string: \"s\"").unwrap();
+
+    assert_eq!(
+        message.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 1,
+            int64: 3,
+            string: "s".to_owned(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        parse_err::<Scalars>("int32: 1\n  foo: 2"),
+        "line 2, column 3: message 'test.Scalars' has no field named 'foo'"
+    );
+    assert_eq!(
+        parse_err::<Scalars>("int32 1"),
+        "line 1, column 7: expected ':', but found '1'"
+    );
+    assert_eq!(
+        parse_err::<Scalars>("int32: 1 int32: 2"),
+        "line 1, column 10: field 'test.Scalars.int32' is specified multiple times"
+    );
+    assert_eq!(
+        parse_err::<Scalars>("int32: 2147483648"),
+        "line 1, column 8: invalid value for field 'test.Scalars.int32': expected a 32-bit integer"
+    );
+    assert_eq!(
+        parse_err::<Scalars>("string: \"\\377\""),
+        "line 1, column 9: invalid UTF-8 in string field 'test.Scalars.string'"
+    );
+    assert_eq!(
+        parse_err::<Scalars>("string: \"abc"),
+        "line 1, column 9: unterminated string"
+    );
+    assert_eq!(
+        parse_err::<ComplexType>("optional_enum: BAZ"),
+        "line 1, column 16: invalid value for field 'test.ComplexType.optional_enum': expected a value of enum 'test.ComplexType.MyEnum'"
+    );
+    assert_eq!(
+        parse_err::<ComplexType>("nested { int32: 1"),
+        "line 1, column 18: expected a field name, but found end of input"
+    );
+    assert_eq!(
+        parse_err::<Scalars>("[type.googleapis.com/test.Scalars] {}"),
+        "line 1, column 1: a type URL can only be used in 'google.protobuf.Any', not 'test.Scalars'"
+    );
+    assert_eq!(
+        parse_err::<Scalars>("[test.ext] : 1"),
+        "line 1, column 1: message 'test.Scalars' has no extension named 'test.ext'"
+    );
+}
+
+#[test]
+fn parse_recursion_limit() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.Struct")
+        .unwrap();
+    let nested = |depth: usize| {
+        "fields { key: \"a\" value { struct_value { ".repeat(depth) + &"} } }".repeat(depth)
+    };
+
+    DynamicMessage::parse_text_format(desc.clone(), &nested(33)).unwrap();
+    let err = DynamicMessage::parse_text_format(desc, &nested(34)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 1, column 1378: recursion limit reached"
+    );
+}
+
+#[test]
+fn text_format_roundtrip() {
+    let message = ComplexType {
+        string_map: vec![(
+            "\u{1}key".to_owned(),
+            Scalars {
+                double: 1e300,
+                float: f32::MAX,
+                bytes: b"\x01\x02".to_vec(),
+                ..Default::default()
+            },
+        )]
+        .into_iter()
+        .collect(),
+        my_enum: vec![0, 1, 3, 7],
+        optional_enum: -4,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    prost_reflect::testing::check_text_format_roundtrip(&message).unwrap();
+}
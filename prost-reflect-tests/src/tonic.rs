@@ -0,0 +1,109 @@
+use std::{
+    convert::Infallible,
+    future::{self, Future, Ready},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use prost_reflect::{DynamicCodec, DynamicMessage, MethodDescriptor, ReflectMessage, Value};
+use tonic::{body::BoxBody, server::UnaryService, Request, Response, Status};
+
+use crate::test_file_descriptor;
+
+fn my_method() -> MethodDescriptor {
+    test_file_descriptor()
+        .services()
+        .find(|service| service.full_name() == "my.package.MyService")
+        .unwrap()
+        .methods()
+        .next()
+        .unwrap()
+}
+
+/// Responds with the request's field incremented by one, or an error if it is negative.
+struct Increment;
+
+impl UnaryService<DynamicMessage> for Increment {
+    type Response = DynamicMessage;
+    type Future = Ready<Result<Response<DynamicMessage>, Status>>;
+
+    fn call(&mut self, request: Request<DynamicMessage>) -> Self::Future {
+        let mut message = request.into_inner();
+        let value = message
+            .get_field_by_name("my_field")
+            .unwrap()
+            .as_i32()
+            .unwrap();
+        if value < 0 {
+            return future::ready(Err(Status::invalid_argument("negative value")));
+        }
+
+        message.set_field_by_name("my_field", Value::I32(value + 1));
+        future::ready(Ok(Response::new(message)))
+    }
+}
+
+/// Passes client requests directly to a server using the dynamic codec.
+#[derive(Clone)]
+struct Loopback;
+
+impl tower_service::Service<http::Request<BoxBody>> for Loopback {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        assert_eq!(request.uri().path(), "/my.package.MyService/MyMethod");
+        Box::pin(async move {
+            let mut server = tonic::server::Grpc::new(DynamicCodec::server(my_method()));
+            Ok(server.unary(Increment, request).await)
+        })
+    }
+}
+
+fn call(value: i32) -> Result<DynamicMessage, Box<Status>> {
+    let method = my_method();
+    let mut request = DynamicMessage::new(method.input());
+    request.set_field_by_name("my_field", Value::I32(value));
+
+    let mut client = tonic::client::Grpc::new(Loopback);
+    futures_executor::block_on(async move {
+        client.ready().await.unwrap();
+        client
+            .unary(
+                Request::new(request),
+                http::uri::PathAndQuery::from_static("/my.package.MyService/MyMethod"),
+                DynamicCodec::new(method),
+            )
+            .await
+            .map(Response::into_inner)
+            .map_err(Box::new)
+    })
+}
+
+#[test]
+fn dynamic_codec_unary() {
+    let response = call(5).unwrap();
+    assert_eq!(response.descriptor(), my_method().output());
+    assert_eq!(
+        response.get_field_by_name("my_field").unwrap().as_ref(),
+        &Value::I32(6)
+    );
+}
+
+#[test]
+fn dynamic_codec_error() {
+    let status = call(-1).unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert_eq!(status.message(), "negative value");
+}
+
+#[test]
+fn dynamic_codec_method() {
+    let codec = DynamicCodec::server(my_method());
+    assert_eq!(codec.method(), &my_method());
+}
@@ -0,0 +1,107 @@
+use prost::bytes::Bytes;
+use prost_reflect::{MapKey, ReflectMessage, Value};
+
+use crate::{ComplexType, Scalars};
+
+#[test]
+fn get_scalars() {
+    let message = Scalars {
+        double: 1.5,
+        float: 2.5,
+        int32: -3,
+        int64: -4,
+        uint32: 5,
+        uint64: 6,
+        sint32: -7,
+        fixed64: 10,
+        bool: true,
+        string: "hello".to_owned(),
+        bytes: b"world".to_vec(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(message.get_f64_by_name("double"), Some(1.5));
+    assert_eq!(message.get_f32_by_name("float"), Some(2.5));
+    assert_eq!(message.get_i32_by_name("int32"), Some(-3));
+    assert_eq!(message.get_i64_by_name("int64"), Some(-4));
+    assert_eq!(message.get_u32_by_name("uint32"), Some(5));
+    assert_eq!(message.get_u64_by_name("uint64"), Some(6));
+    assert_eq!(message.get_i32_by_name("sint32"), Some(-7));
+    assert_eq!(message.get_u64_by_name("fixed64"), Some(10));
+    assert_eq!(message.get_bool_by_name("bool"), Some(true));
+    assert_eq!(message.get_str_by_name("string"), Some("hello"));
+    assert_eq!(
+        message.get_bytes_by_name("bytes"),
+        Some(&Bytes::from_static(b"world"))
+    );
+
+    assert_eq!(message.get_i32_by_name("sfixed32"), None);
+    assert_eq!(message.get_i32_by_name("missing"), None);
+
+    let field = message.descriptor().get_field_by_name("int32").unwrap();
+    assert_eq!(message.get_i32(&field), Some(-3));
+}
+
+#[test]
+fn get_complex() {
+    let message = ComplexType {
+        string_map: IntoIterator::into_iter([("key".to_owned(), Scalars::default())]).collect(),
+        nested: Some(Scalars {
+            int32: 1,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        optional_enum: 3,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        message
+            .get_message_by_name("nested")
+            .unwrap()
+            .get_i32_by_name("int32"),
+        Some(1)
+    );
+    assert_eq!(
+        message.get_list_by_name("my_enum"),
+        Some([Value::EnumNumber(1), Value::EnumNumber(3)].as_ref())
+    );
+    assert!(message
+        .get_map_by_name("string_map")
+        .unwrap()
+        .contains_key(&MapKey::String("key".to_owned())));
+    assert_eq!(message.get_enum_number_by_name("optional_enum"), Some(3));
+    assert_eq!(message.get_map_by_name("int_map"), None);
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot get field 'test.Scalars.int32' as a string, since it holds a 32-bit integer"
+)]
+fn get_kind_mismatch() {
+    Scalars::default()
+        .transcode_to_dynamic()
+        .get_str_by_name("int32");
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot get field 'test.ComplexType.my_enum' as an enum number, since it holds a list"
+)]
+fn get_list_as_scalar() {
+    ComplexType::default()
+        .transcode_to_dynamic()
+        .get_enum_number_by_name("my_enum");
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot get field 'test.ComplexType.int_map' as a list, since it holds a map"
+)]
+fn get_map_as_list() {
+    ComplexType::default()
+        .transcode_to_dynamic()
+        .get_list_by_name("int_map");
+}
@@ -0,0 +1,145 @@
+use prost::{bytes::Bytes, encoding::WireType, Message};
+use prost_reflect::{DynamicMessage, UnknownField};
+
+use crate::{test_file_descriptor, Point};
+
+fn empty_message(bytes: &[u8]) -> DynamicMessage {
+    let desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.Empty")
+        .unwrap();
+    DynamicMessage::decode(desc, bytes).unwrap()
+}
+
+#[test]
+fn iterate_unknown_fields() {
+    // field 1: varint 150, field 2: fixed64, field 3: "hi", field 4: group { field 1: varint 1 }, field 5: fixed32
+    let message = empty_message(
+        b"\x08\x96\x01\x11\x01\x02\x03\x04\x05\x06\x07\x08\x1a\x02hi\x23\x08\x01\x24\x2d\x01\x02\x03\x04",
+    );
+
+    let fields: Vec<_> = message
+        .unknown_fields()
+        .map(|field| (field.number(), field.wire_type(), field.value().clone()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            (1, WireType::Varint, Bytes::from_static(b"\x96\x01")),
+            (
+                2,
+                WireType::SixtyFourBit,
+                Bytes::from_static(b"\x01\x02\x03\x04\x05\x06\x07\x08")
+            ),
+            (3, WireType::LengthDelimited, Bytes::from_static(b"hi")),
+            (4, WireType::StartGroup, Bytes::from_static(b"\x08\x01")),
+            (
+                5,
+                WireType::ThirtyTwoBit,
+                Bytes::from_static(b"\x01\x02\x03\x04")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn get_unknown_fields_by_number() {
+    let message = empty_message(b"\x08\x01\x10\x02\x08\x03");
+
+    let values: Vec<_> = message
+        .get_unknown_fields(1)
+        .map(|field| field.value().clone())
+        .collect();
+    assert_eq!(
+        values,
+        vec![Bytes::from_static(b"\x01"), Bytes::from_static(b"\x03")]
+    );
+    assert_eq!(message.get_unknown_fields(3).count(), 0);
+}
+
+#[test]
+fn add_unknown_field() {
+    let mut message = empty_message(b"");
+    message.add_unknown_field(
+        UnknownField::new(1, WireType::Varint, Bytes::from_static(b"\x05")).unwrap(),
+    );
+    message.add_unknown_field(
+        UnknownField::new(2, WireType::Varint, Bytes::from_static(b"\x07")).unwrap(),
+    );
+
+    assert_eq!(
+        Point::decode(message.encode_to_vec().as_slice()).unwrap(),
+        Point {
+            latitude: 5,
+            longitude: 7,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "field number 1 is not unknown for message 'test.Point'")]
+fn add_unknown_field_known_number() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.Point")
+        .unwrap();
+    let mut message = DynamicMessage::new(desc);
+    message.add_unknown_field(
+        UnknownField::new(1, WireType::Varint, Bytes::from_static(b"\x05")).unwrap(),
+    );
+}
+
+#[test]
+fn take_unknown_fields_preserved_string() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.DefaultValues")
+        .unwrap();
+    let bytes = b"\x72\x02ok\x72\x03a\xffb\xc0\x3e\x01";
+    let mut message = DynamicMessage::decode(desc, bytes.as_ref()).unwrap();
+
+    let fields = message.take_unknown_fields();
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].number(), 1000);
+    assert_eq!(message.get_unknown_fields(14).count(), 2);
+    for field in fields {
+        message.add_unknown_field(field);
+    }
+    assert_eq!(message.encode_to_vec(), bytes);
+}
+
+#[test]
+fn new_unknown_field_invalid() {
+    assert!(UnknownField::new(1, WireType::Varint, Bytes::from_static(b"\x96")).is_err());
+    assert!(UnknownField::new(1, WireType::Varint, Bytes::from_static(b"\x01\x02")).is_err());
+    assert!(UnknownField::new(1, WireType::SixtyFourBit, Bytes::from_static(b"\x01")).is_err());
+    assert!(UnknownField::new(1, WireType::ThirtyTwoBit, Bytes::from_static(b"")).is_err());
+    assert!(UnknownField::new(1, WireType::StartGroup, Bytes::from_static(b"\x0c")).is_err());
+    assert!(UnknownField::new(1, WireType::EndGroup, Bytes::new()).is_err());
+}
+
+#[test]
+fn take_unknown_fields() {
+    let mut message = empty_message(b"\x10\x02\x08\x01");
+
+    let fields = message.take_unknown_fields();
+    assert_eq!(
+        fields
+            .iter()
+            .map(|field| field.number())
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(message.unknown_fields().count(), 0);
+    assert!(message.encode_to_vec().is_empty());
+
+    for field in fields {
+        message.add_unknown_field(field);
+    }
+    assert_eq!(message.encode_to_vec(), b"\x08\x01\x10\x02");
+}
+
+#[test]
+fn clear_unknown_fields() {
+    let mut message = empty_message(b"\x08\x01\x10\x02");
+    message.clear_unknown_fields();
+    assert_eq!(message.unknown_fields().count(), 0);
+    assert!(message.encode_to_vec().is_empty());
+}
@@ -0,0 +1,80 @@
+use arbitrary::Unstructured;
+use prost::Message;
+use prost_reflect::{DynamicMessage, ReflectMessage};
+
+use crate::test_file_descriptor;
+
+/// Returns some pseudo-random bytes to use as fuzzer input.
+fn input(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+fn arbitrary(name: &str, data: &[u8]) -> DynamicMessage {
+    let desc = test_file_descriptor().get_message_by_name(name).unwrap();
+    DynamicMessage::arbitrary_from_unstructured(desc, &mut Unstructured::new(data)).unwrap()
+}
+
+#[test]
+fn arbitrary_from_empty_input() {
+    let message = arbitrary("test.ComplexType", &[]);
+    assert_eq!(message.encode_to_vec(), Vec::<u8>::new());
+}
+
+#[test]
+fn arbitrary_roundtrip() {
+    for name in ["test.Scalars", "test.ComplexType", "test.WellKnownTypes"] {
+        for seed in 0..20 {
+            let message = arbitrary(name, &input(seed, 1024));
+            let encoded = message.encode_deterministic();
+            let decoded = DynamicMessage::decode(message.descriptor(), encoded.as_slice()).unwrap();
+            assert_eq!(decoded.encode_deterministic(), encoded);
+        }
+    }
+}
+
+#[test]
+fn arbitrary_is_deterministic() {
+    let data = input(7, 512);
+    assert_eq!(
+        arbitrary("test.ComplexType", &data).encode_deterministic(),
+        arbitrary("test.ComplexType", &data).encode_deterministic()
+    );
+}
+
+#[test]
+fn arbitrary_sets_required_fields() {
+    for seed in 0..20 {
+        let message = arbitrary("test2.ContainsGroup", &input(seed, 256));
+        for group in message
+            .get_field_by_name("groupmessage")
+            .unwrap()
+            .as_list()
+            .unwrap()
+        {
+            assert!(group.as_message().unwrap().has_field_by_name("url"));
+        }
+    }
+}
+
+#[test]
+fn arbitrary_declared_enum_values() {
+    for seed in 0..20 {
+        let message = arbitrary("test.ComplexType", &input(seed, 512));
+        for value in message
+            .get_field_by_name("my_enum")
+            .unwrap()
+            .as_list()
+            .unwrap()
+        {
+            assert!([0, 1, 3, -4].contains(&value.as_enum_number().unwrap()));
+        }
+    }
+}
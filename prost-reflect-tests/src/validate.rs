@@ -0,0 +1,592 @@
+use std::{env, fs};
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MapKey, Validator, Value};
+
+use crate::{options::set_extension, test_file_descriptor};
+
+const PROTOVALIDATE_PROTO: &str = r#"
+syntax = "proto2";
+
+package buf.validate;
+
+import "google/protobuf/descriptor.proto";
+
+extend google.protobuf.MessageOptions {
+  optional MessageConstraints message = 1159;
+}
+
+extend google.protobuf.OneofOptions {
+  optional OneofConstraints oneof = 1159;
+}
+
+extend google.protobuf.FieldOptions {
+  optional FieldConstraints field = 1159;
+}
+
+message MessageConstraints {
+  optional bool disabled = 1;
+}
+
+message OneofConstraints {
+  optional bool required = 1;
+}
+
+message FieldConstraints {
+  optional bool required = 25;
+  optional Ignore ignore = 27;
+
+  oneof type {
+    DoubleRules double = 2;
+    Int32Rules int32 = 3;
+    StringRules string = 14;
+    BytesRules bytes = 15;
+    EnumRules enum = 16;
+    RepeatedRules repeated = 18;
+    MapRules map = 19;
+  }
+}
+
+enum Ignore {
+  IGNORE_UNSPECIFIED = 0;
+  IGNORE_IF_UNPOPULATED = 1;
+  IGNORE_IF_DEFAULT_VALUE = 2;
+  IGNORE_ALWAYS = 3;
+}
+
+message DoubleRules {
+  optional double const = 1;
+  optional double lt = 2;
+  optional double lte = 3;
+  optional double gt = 4;
+  optional double gte = 5;
+  optional bool finite = 8;
+}
+
+message Int32Rules {
+  optional int32 const = 1;
+  optional int32 lt = 2;
+  optional int32 lte = 3;
+  optional int32 gt = 4;
+  optional int32 gte = 5;
+  repeated int32 in = 6;
+  repeated int32 not_in = 7;
+}
+
+message StringRules {
+  optional string const = 1;
+  optional uint64 min_len = 2;
+  optional uint64 max_len = 3;
+  optional string pattern = 6;
+  optional string prefix = 7;
+  repeated string in = 10;
+  optional bool email = 12;
+  optional bool uuid = 22;
+}
+
+message BytesRules {
+  optional uint64 min_len = 2;
+  optional bool ipv4 = 11;
+}
+
+message EnumRules {
+  optional int32 const = 1;
+  optional bool defined_only = 2;
+  repeated int32 not_in = 4;
+}
+
+message RepeatedRules {
+  optional uint64 min_items = 1;
+  optional uint64 max_items = 2;
+  optional bool unique = 3;
+  optional FieldConstraints items = 4;
+}
+
+message MapRules {
+  optional uint64 min_pairs = 1;
+  optional uint64 max_pairs = 2;
+  optional FieldConstraints keys = 4;
+  optional FieldConstraints values = 5;
+}
+"#;
+
+const PGV_PROTO: &str = r#"
+syntax = "proto2";
+
+package validate;
+
+import "google/protobuf/descriptor.proto";
+
+extend google.protobuf.MessageOptions {
+  optional bool disabled = 1071;
+}
+
+extend google.protobuf.OneofOptions {
+  optional bool required = 1071;
+}
+
+extend google.protobuf.FieldOptions {
+  optional FieldRules rules = 1071;
+}
+
+message FieldRules {
+  optional MessageRules message = 17;
+
+  oneof type {
+    Int32Rules int32 = 3;
+    StringRules string = 14;
+  }
+}
+
+message MessageRules {
+  optional bool skip = 1;
+  optional bool required = 2;
+}
+
+message Int32Rules {
+  optional int32 gt = 4;
+}
+
+message StringRules {
+  optional uint64 min_len = 2;
+  optional bool ignore_empty = 26;
+}
+"#;
+
+const VALIDATED_PROTO: &str = r#"
+syntax = "proto3";
+
+package validated;
+
+import "buf/validate/validate.proto";
+import "validate/validate.proto";
+
+message User {
+  string name = 1;
+  int32 age = 2;
+  string email = 3;
+  repeated string tags = 4;
+  map<string, int32> scores = 5;
+  Address address = 6;
+  Status status = 7;
+  oneof contact {
+    string phone = 8;
+    string fax = 9;
+  }
+  double ratio = 10;
+  string id = 11;
+  bytes ip = 12;
+  repeated Address previous_addresses = 13;
+}
+
+message Address {
+  string city = 1;
+  int32 number = 2;
+  string postcode = 3;
+}
+
+message Legacy {
+  Address address = 1;
+  Address unchecked = 2;
+  oneof kind {
+    string a = 3;
+    string b = 4;
+  }
+}
+
+message Disabled {
+  string name = 1;
+}
+
+enum Status {
+  STATUS_UNSPECIFIED = 0;
+  STATUS_ACTIVE = 1;
+}
+"#;
+
+/// Builds a pool containing minimal copies of the protovalidate and protoc-gen-validate options, and
+/// a file with validation rules set on its messages, fields and oneofs as they would be by protoc.
+fn validate_pool() -> DescriptorPool {
+    let dir = env::temp_dir().join(format!("prost-reflect-validate-{}", std::process::id()));
+    fs::create_dir_all(dir.join("buf/validate")).unwrap();
+    fs::create_dir_all(dir.join("validate")).unwrap();
+    fs::write(dir.join("buf/validate/validate.proto"), PROTOVALIDATE_PROTO).unwrap();
+    fs::write(dir.join("validate/validate.proto"), PGV_PROTO).unwrap();
+    fs::write(dir.join("validated.proto"), VALIDATED_PROTO).unwrap();
+
+    let mut pool = test_file_descriptor();
+    pool.add_proto_files(["validated.proto"], [&dir]).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let file_desc = pool
+        .get_message_by_name("google.protobuf.FileDescriptorProto")
+        .unwrap();
+    let to_dynamic = |name: &str| {
+        DynamicMessage::decode(
+            file_desc.clone(),
+            pool.get_file_by_name(name)
+                .unwrap()
+                .file_descriptor_proto()
+                .encode_to_vec()
+                .as_slice(),
+        )
+        .unwrap()
+    };
+
+    let mut file = to_dynamic("validated.proto");
+    let mut rules = |path: &[&str], extension: &str, text: &str| {
+        let extension_desc = pool
+            .all_extensions()
+            .find(|ext| ext.full_name() == extension)
+            .unwrap();
+        let value = match extension_desc.kind().as_message() {
+            Some(desc) => {
+                Value::Message(DynamicMessage::parse_text_format(desc.clone(), text).unwrap())
+            }
+            None => Value::Bool(text.parse().unwrap()),
+        };
+        set_extension(&mut file, path, extension, value);
+    };
+
+    let user_field = |index: usize| vec!["message_type[0]".to_owned(), format!("field[{}]", index)];
+    let user_rules = [
+        (0, "string { min_len: 1 max_len: 5 }"),
+        (1, "int32 { gte: 0 lt: 150 }"),
+        (2, "ignore: IGNORE_IF_UNPOPULATED string { email: true }"),
+        (
+            3,
+            "repeated { max_items: 2 unique: true items { string { min_len: 1 } } }",
+        ),
+        (
+            4,
+            "map { max_pairs: 2 keys { string { max_len: 3 } } values { int32 { gte: 0 } } }",
+        ),
+        (5, "required: true"),
+        (6, "enum { defined_only: true not_in: 1 }"),
+        (9, "double { finite: true }"),
+        (
+            10,
+            "ignore: IGNORE_IF_DEFAULT_VALUE string { uuid: true pattern: \"^[0-9a-f-]+$\" }",
+        ),
+        (11, "ignore: IGNORE_IF_UNPOPULATED bytes { ipv4: true }"),
+    ];
+    for (index, text) in user_rules.iter() {
+        let mut path = user_field(*index);
+        path.push("options".to_owned());
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        rules(&path, "buf.validate.field", text);
+    }
+    rules(
+        &["message_type[0]", "oneof_decl[0]", "options"],
+        "buf.validate.oneof",
+        "required: true",
+    );
+
+    rules(
+        &["message_type[1]", "field[0]", "options"],
+        "validate.rules",
+        "string { min_len: 1 }",
+    );
+    rules(
+        &["message_type[1]", "field[1]", "options"],
+        "validate.rules",
+        "int32 { gt: 0 }",
+    );
+    rules(
+        &["message_type[1]", "field[2]", "options"],
+        "validate.rules",
+        "string { min_len: 5 ignore_empty: true }",
+    );
+
+    rules(
+        &["message_type[2]", "field[0]", "options"],
+        "validate.rules",
+        "message { required: true }",
+    );
+    rules(
+        &["message_type[2]", "field[1]", "options"],
+        "validate.rules",
+        "message { skip: true }",
+    );
+    rules(
+        &["message_type[2]", "oneof_decl[0]", "options"],
+        "validate.required",
+        "true",
+    );
+
+    rules(
+        &["message_type[3]", "field[0]", "options"],
+        "buf.validate.field",
+        "string { min_len: 1 }",
+    );
+    rules(
+        &["message_type[3]", "options"],
+        "buf.validate.message",
+        "disabled: true",
+    );
+
+    let mut set = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.FileDescriptorSet")
+            .unwrap(),
+    );
+    set.set_field_by_name(
+        "file",
+        Value::List(vec![
+            Value::Message(to_dynamic("google/protobuf/descriptor.proto")),
+            Value::Message(to_dynamic("buf/validate/validate.proto")),
+            Value::Message(to_dynamic("validate/validate.proto")),
+            Value::Message(file),
+        ]),
+    );
+
+    DescriptorPool::decode(set.encode_to_vec().as_slice()).unwrap()
+}
+
+fn valid_user(pool: &DescriptorPool) -> DynamicMessage {
+    let mut address = DynamicMessage::new(pool.get_message_by_name("validated.Address").unwrap());
+    address.set_field_by_name("city", Value::String("London".to_owned()));
+    address.set_field_by_name("number", Value::I32(10));
+
+    let mut user = DynamicMessage::new(pool.get_message_by_name("validated.User").unwrap());
+    user.set_field_by_name("name", Value::String("Alice".to_owned()));
+    user.set_field_by_name("age", Value::I32(30));
+    user.set_field_by_name("address", Value::Message(address));
+    user.set_field_by_name("phone", Value::String("555".to_owned()));
+    user
+}
+
+fn violations(message: &DynamicMessage) -> Vec<(String, String)> {
+    match message.validate() {
+        Ok(()) => vec![],
+        Err(err) => err
+            .violations()
+            .map(|v| (v.field_path().to_owned(), v.rule_id().to_owned()))
+            .collect(),
+    }
+}
+
+fn violation(path: &str, rule_id: &str) -> (String, String) {
+    (path.to_owned(), rule_id.to_owned())
+}
+
+#[test]
+fn validate_valid_message() {
+    let pool = validate_pool();
+    let user = valid_user(&pool);
+    assert_eq!(user.validate(), Ok(()));
+}
+
+#[test]
+fn validate_with_reused_validator() {
+    let pool = validate_pool();
+    let mut validator = Validator::new(&pool);
+
+    let mut user = valid_user(&pool);
+    assert_eq!(validator.validate(&user), Ok(()));
+
+    user.set_field_by_name("id", Value::String("not-a-uuid".to_owned()));
+    let err = validator.validate(&user).unwrap_err();
+    assert_eq!(err, user.validate().unwrap_err());
+    assert_eq!(err.violations().len(), 2);
+
+    // Violations from earlier calls are not reported again.
+    user.clear_field_by_name("id");
+    assert_eq!(validator.validate(&user), Ok(()));
+
+    // Messages from another pool use that pool's rules.
+    let other = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("test.Scalars")
+            .unwrap(),
+    );
+    assert_eq!(validator.validate(&other), Ok(()));
+    user.set_field_by_name("age", Value::I32(150));
+    assert_eq!(validator.validate(&user).unwrap_err().violations().len(), 1);
+}
+
+#[test]
+fn validate_without_rules() {
+    let pool = test_file_descriptor();
+    let message = DynamicMessage::new(pool.get_message_by_name("test.Scalars").unwrap());
+    assert_eq!(message.validate(), Ok(()));
+}
+
+#[test]
+fn validate_scalar_rules() {
+    let pool = validate_pool();
+    let mut user = valid_user(&pool);
+    user.set_field_by_name("name", Value::String("Alexander".to_owned()));
+    user.set_field_by_name("age", Value::I32(150));
+    user.set_field_by_name("email", Value::String("not an email".to_owned()));
+    user.set_field_by_name("ratio", Value::F64(f64::NAN));
+    user.set_field_by_name("id", Value::String("not-a-uuid".to_owned()));
+    user.set_field_by_name("ip", Value::Bytes(vec![127, 0, 0].into()));
+
+    assert_eq!(
+        violations(&user),
+        [
+            violation("name", "string.max_len"),
+            violation("age", "int32.gte_lt"),
+            violation("email", "string.email"),
+            violation("ratio", "double.finite"),
+            violation("id", "string.pattern"),
+            violation("id", "string.uuid"),
+            violation("ip", "bytes.ipv4"),
+        ]
+    );
+
+    user.set_field_by_name("name", Value::String("".to_owned()));
+    user.set_field_by_name("age", Value::I32(-1));
+    user.set_field_by_name("email", Value::String("alice@example.com".to_owned()));
+    user.set_field_by_name("ratio", Value::F64(0.5));
+    user.set_field_by_name(
+        "id",
+        Value::String("7c9e6679-7425-40de-944b-e07fc1f90ae7".to_owned()),
+    );
+    user.set_field_by_name("ip", Value::Bytes(vec![127, 0, 0, 1].into()));
+    assert_eq!(
+        violations(&user),
+        [
+            violation("name", "string.min_len"),
+            violation("age", "int32.gte_lt"),
+        ]
+    );
+}
+
+#[test]
+fn validate_required() {
+    let pool = validate_pool();
+    let mut user = valid_user(&pool);
+    user.clear_field_by_name("address");
+    user.clear_field_by_name("phone");
+
+    assert_eq!(
+        violations(&user),
+        [
+            violation("contact", "required"),
+            violation("address", "required")
+        ]
+    );
+}
+
+#[test]
+fn validate_enum() {
+    let pool = validate_pool();
+    let mut user = valid_user(&pool);
+    user.set_field_by_name("status", Value::EnumNumber(1));
+    assert_eq!(violations(&user), [violation("status", "enum.not_in")]);
+
+    user.set_field_by_name("status", Value::EnumNumber(5));
+    assert_eq!(
+        violations(&user),
+        [violation("status", "enum.defined_only")]
+    );
+}
+
+#[test]
+fn validate_repeated_and_map() {
+    let pool = validate_pool();
+    let mut user = valid_user(&pool);
+    user.set_field_by_name(
+        "tags",
+        Value::List(vec![
+            Value::String("a".to_owned()),
+            Value::String("".to_owned()),
+            Value::String("a".to_owned()),
+        ]),
+    );
+    user.set_field_by_name(
+        "scores",
+        Value::Map(
+            vec![
+                (MapKey::String("abcd".to_owned()), Value::I32(1)),
+                (MapKey::String("b".to_owned()), Value::I32(-1)),
+            ]
+            .into_iter()
+            .collect(),
+        ),
+    );
+
+    assert_eq!(
+        violations(&user),
+        [
+            violation("tags", "repeated.max_items"),
+            violation("tags", "repeated.unique"),
+            violation("tags[1]", "string.min_len"),
+            violation("scores[\"abcd\"]", "string.max_len"),
+            violation("scores[\"b\"]", "int32.gte"),
+        ]
+    );
+}
+
+#[test]
+fn validate_nested_messages() {
+    let pool = validate_pool();
+    let mut user = valid_user(&pool);
+    user.get_field_by_name_mut("address")
+        .unwrap()
+        .as_message_mut()
+        .unwrap()
+        .set_field_by_name("city", Value::String("".to_owned()));
+    let mut previous = DynamicMessage::new(pool.get_message_by_name("validated.Address").unwrap());
+    previous.set_field_by_name("city", Value::String("Paris".to_owned()));
+    previous.set_field_by_name("postcode", Value::String("75".to_owned()));
+    user.set_field_by_name(
+        "previous_addresses",
+        Value::List(vec![Value::Message(previous)]),
+    );
+
+    let err = user.validate().unwrap_err();
+    assert_eq!(
+        err.violations()
+            .map(|v| (v.field_path(), v.rule_id()))
+            .collect::<Vec<_>>(),
+        [
+            ("address.city", "string.min_len"),
+            ("previous_addresses[0].number", "int32.gt"),
+            ("previous_addresses[0].postcode", "string.min_len"),
+        ]
+    );
+    assert_eq!(
+        err.violations().next().unwrap().message(),
+        "value length must be at least 1 characters"
+    );
+    assert_eq!(
+        err.to_string(),
+        "message failed validation: address.city: value length must be at least 1 characters [string.min_len]; \
+        previous_addresses[0].number: value must be greater than 0 [int32.gt]; \
+        previous_addresses[0].postcode: value length must be at least 5 characters [string.min_len]"
+    );
+}
+
+#[test]
+fn validate_legacy_message_rules() {
+    let pool = validate_pool();
+    let mut legacy = DynamicMessage::new(pool.get_message_by_name("validated.Legacy").unwrap());
+    assert_eq!(
+        violations(&legacy),
+        [
+            violation("kind", "required"),
+            violation("address", "required")
+        ]
+    );
+
+    let address = DynamicMessage::new(pool.get_message_by_name("validated.Address").unwrap());
+    legacy.set_field_by_name("a", Value::String("a".to_owned()));
+    legacy.set_field_by_name("address", Value::Message(address.clone()));
+    legacy.set_field_by_name("unchecked", Value::Message(address));
+    assert_eq!(
+        violations(&legacy),
+        [
+            violation("address.city", "string.min_len"),
+            violation("address.number", "int32.gt"),
+        ]
+    );
+}
+
+#[test]
+fn validate_disabled_message() {
+    let pool = validate_pool();
+    let message = DynamicMessage::new(pool.get_message_by_name("validated.Disabled").unwrap());
+    assert_eq!(message.validate(), Ok(()));
+}
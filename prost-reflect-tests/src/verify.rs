@@ -0,0 +1,213 @@
+use prost::Message;
+use prost_reflect::{DecodeOptions, DynamicMessage, ReflectMessage, Utf8Validation};
+
+use crate::{test_file_descriptor, ComplexType, ScalarArrays, Scalars};
+
+#[test]
+fn verify_valid_message() {
+    let message = ComplexType {
+        string_map: vec![(
+            "foo".to_owned(),
+            Scalars {
+                string: "bar".to_owned(),
+                double: 1.5,
+                ..Default::default()
+            },
+        )]
+        .into_iter()
+        .collect(),
+        nested: Some(Scalars {
+            int32: -5,
+            fixed64: 3,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3, -4],
+        ..Default::default()
+    };
+
+    DynamicMessage::verify(&message.descriptor(), &message.encode_to_vec()).unwrap();
+}
+
+#[test]
+fn verify_packed_and_unpacked() {
+    let message = ScalarArrays {
+        int32: vec![1, -2, 300],
+        fixed32: vec![4, 5],
+        double: vec![1.0, 2.0],
+        ..Default::default()
+    };
+    let desc = message.descriptor();
+    DynamicMessage::verify(&desc, &message.encode_to_vec()).unwrap();
+
+    // Unpacked encoding of repeated int32 field 3
+    DynamicMessage::verify(&desc, b"\x18\x01\x18\x02").unwrap();
+}
+
+#[test]
+fn verify_unknown_fields() {
+    let desc = Scalars::default().descriptor();
+    DynamicMessage::verify(&desc, b"\xa0\x06\x96\x01\xab\x06\x08\x01\xac\x06").unwrap();
+}
+
+#[test]
+fn verify_truncated_varint() {
+    let desc = Scalars::default().descriptor();
+    let err = DynamicMessage::verify(&desc, b"\x18\x01\x18\x96").unwrap_err();
+    assert_eq!(err.offset(), 3);
+    assert_eq!(err.to_string(), "truncated varint at offset 3");
+}
+
+#[test]
+fn verify_varint_overflow() {
+    let desc = Scalars::default().descriptor();
+    let err =
+        DynamicMessage::verify(&desc, b"\x18\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02").unwrap_err();
+    assert_eq!(err.offset(), 1);
+    assert_eq!(err.to_string(), "varint exceeds 64 bits at offset 1");
+}
+
+#[test]
+fn verify_unexpected_wire_type() {
+    let desc = Scalars::default().descriptor();
+    let err = DynamicMessage::verify(&desc, b"\x18\x01\x1d\x00\x00\x00\x00").unwrap_err();
+    assert_eq!(err.offset(), 2);
+    assert_eq!(
+        err.to_string(),
+        "invalid wire type for field 'test.Scalars.int32': expected Varint, found ThirtyTwoBit at offset 2"
+    );
+}
+
+#[test]
+fn verify_invalid_wire_type() {
+    let desc = Scalars::default().descriptor();
+    let err = DynamicMessage::verify(&desc, b"\x1e").unwrap_err();
+    assert_eq!(err.offset(), 0);
+    assert_eq!(err.to_string(), "invalid wire type value 6 at offset 0");
+}
+
+#[test]
+fn verify_invalid_field_number() {
+    let desc = Scalars::default().descriptor();
+    let err = DynamicMessage::verify(&desc, b"\x00").unwrap_err();
+    assert_eq!(err.to_string(), "invalid field number 0 at offset 0");
+}
+
+#[test]
+fn verify_length_overrun() {
+    let desc = Scalars::default().descriptor();
+    let err = DynamicMessage::verify(&desc, b"\x72\x05abc").unwrap_err();
+    assert_eq!(err.offset(), 1);
+    assert_eq!(
+        err.to_string(),
+        "length 5 exceeds the 3 bytes remaining in the enclosing message at offset 1"
+    );
+}
+
+#[test]
+fn verify_nested_length_overrun() {
+    let desc = ComplexType::default().descriptor();
+    // The nested message is 3 bytes long, but its string field claims 4 bytes.
+    let err = DynamicMessage::verify(&desc, b"\x1a\x03\x72\x04ab\x28\x01").unwrap_err();
+    assert_eq!(err.offset(), 3);
+}
+
+#[test]
+fn verify_truncated_fixed() {
+    let desc = Scalars::default().descriptor();
+    let err = DynamicMessage::verify(&desc, b"\x51\x01\x02\x03").unwrap_err();
+    assert_eq!(err.offset(), 1);
+    assert_eq!(
+        err.to_string(),
+        "expected 8 bytes but only 3 remain in the enclosing message at offset 1"
+    );
+}
+
+#[test]
+fn verify_invalid_utf8() {
+    let desc = Scalars::default().descriptor();
+    let err = DynamicMessage::verify(&desc, b"\x72\x03a\xffb").unwrap_err();
+    assert_eq!(err.offset(), 3);
+    assert_eq!(
+        err.to_string(),
+        "invalid UTF-8 in string field 'test.Scalars.string' at offset 3"
+    );
+}
+
+#[test]
+fn verify_invalid_utf8_options() {
+    let desc = Scalars::default().descriptor();
+    let bytes = b"\x72\x03a\xffb";
+    for utf8_validation in [Utf8Validation::Replace, Utf8Validation::PreserveUnknown] {
+        let options = DecodeOptions::new().utf8_validation(utf8_validation);
+        DynamicMessage::verify_with_options(&desc, bytes, &options).unwrap();
+        DynamicMessage::decode_with_options(desc.clone(), bytes.as_ref(), &options).unwrap();
+    }
+}
+
+#[test]
+fn verify_proto2_invalid_utf8() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.DefaultValues")
+        .unwrap();
+    let bytes = b"\x72\x03a\xffb";
+    DynamicMessage::verify(&desc, bytes).unwrap();
+    DynamicMessage::decode(desc.clone(), bytes.as_ref()).unwrap();
+
+    let options = DecodeOptions::new().utf8_validation(Utf8Validation::Strict);
+    let err = DynamicMessage::verify_with_options(&desc, bytes, &options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid UTF-8 in string field 'test2.DefaultValues.string' at offset 3"
+    );
+}
+
+#[test]
+fn verify_invalid_packed_length() {
+    let desc = ScalarArrays::default().descriptor();
+    let err = DynamicMessage::verify(&desc, b"\x4a\x03\x01\x02\x03").unwrap_err();
+    assert_eq!(err.offset(), 2);
+    assert_eq!(
+        err.to_string(),
+        "packed field 'test.ScalarArrays.fixed32' has length 3 which is not a multiple of 4 at offset 2"
+    );
+}
+
+#[test]
+fn verify_groups() {
+    let desc = Scalars::default().descriptor();
+
+    let err = DynamicMessage::verify(&desc, b"\xab\x06\x08\x01").unwrap_err();
+    assert_eq!(err.offset(), 4);
+    assert_eq!(
+        err.to_string(),
+        "missing end group tag for field number 101 at offset 4"
+    );
+
+    let err = DynamicMessage::verify(&desc, b"\xac\x06").unwrap_err();
+    assert_eq!(err.offset(), 0);
+    assert_eq!(
+        err.to_string(),
+        "unexpected end group tag for field number 101 at offset 0"
+    );
+}
+
+#[test]
+fn verify_matches_decode() {
+    let desc = Scalars::default().descriptor();
+    let valid = Scalars {
+        string: "hello".to_owned(),
+        uint64: u64::MAX,
+        ..Default::default()
+    }
+    .encode_to_vec();
+
+    for len in 0..valid.len() {
+        let truncated = &valid[..len];
+        assert_eq!(
+            DynamicMessage::verify(&desc, truncated).is_ok(),
+            DynamicMessage::decode(desc.clone(), truncated).is_ok(),
+            "{:?}",
+            truncated
+        );
+    }
+}
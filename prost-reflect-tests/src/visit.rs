@@ -0,0 +1,122 @@
+use prost::Message;
+use prost_reflect::{
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, Kind, MapKey, MessageDescriptor,
+    ReflectMessage, UnknownField, Value, Visit,
+};
+
+use crate::{test_file_descriptor, ComplexType, Point, Scalars};
+
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl Visit for Recorder {
+    fn visit_message(&mut self, path: &str, message: &DynamicMessage) {
+        self.0.push(format!(
+            "message {:?} {}",
+            path,
+            message.descriptor().name()
+        ));
+    }
+
+    fn visit_field(&mut self, path: &str, field_desc: &FieldDescriptor, _: &Value) {
+        self.0.push(format!("field {} {}", path, field_desc.name()));
+    }
+
+    fn visit_extension(&mut self, path: &str, _: &ExtensionDescriptor, _: &Value) {
+        self.0.push(format!("extension {}", path));
+    }
+
+    fn visit_list_element(&mut self, path: &str, _: &Kind, index: usize, value: &Value) {
+        self.0
+            .push(format!("element {} {} {:?}", path, index, value));
+    }
+
+    fn visit_map_entry(&mut self, path: &str, _: &MessageDescriptor, key: &MapKey, _: &Value) {
+        self.0.push(format!("entry {} {:?}", path, key));
+    }
+
+    fn visit_unknown_field(&mut self, path: &str, field: &UnknownField) {
+        self.0.push(format!("unknown {} {}", path, field.number()));
+    }
+}
+
+#[test]
+fn visit_complex_type() {
+    let message = ComplexType {
+        string_map: IntoIterator::into_iter([
+            ("b".to_owned(), Scalars::default()),
+            (
+                "a".to_owned(),
+                Scalars {
+                    int32: 1,
+                    ..Default::default()
+                },
+            ),
+        ])
+        .collect(),
+        nested: Some(Scalars {
+            string: "x".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let mut recorder = Recorder::default();
+    message.visit(&mut recorder);
+    assert_eq!(
+        recorder.0,
+        vec![
+            "message \"\" ComplexType",
+            "field string_map string_map",
+            "entry string_map[\"a\"] String(\"a\")",
+            "message \"string_map[\\\"a\\\"]\" Scalars",
+            "field string_map[\"a\"].int32 int32",
+            "entry string_map[\"b\"] String(\"b\")",
+            "message \"string_map[\\\"b\\\"]\" Scalars",
+            "field nested nested",
+            "message \"nested\" Scalars",
+            "field nested.string string",
+            "field my_enum my_enum",
+            "element my_enum[0] 0 EnumNumber(1)",
+            "element my_enum[1] 1 EnumNumber(3)",
+        ]
+    );
+}
+
+#[test]
+fn visit_unknown_fields() {
+    let message = DynamicMessage::decode(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+        Point {
+            latitude: 1,
+            longitude: 2,
+        }
+        .encode_to_vec()
+        .as_slice(),
+    )
+    .unwrap();
+
+    let mut recorder = Recorder::default();
+    message.visit(&mut recorder);
+    assert_eq!(
+        recorder.0,
+        vec!["message \"\" Empty", "unknown 1 1", "unknown 2 2"]
+    );
+}
+
+#[test]
+fn visit_default_methods() {
+    struct Noop;
+    impl Visit for Noop {}
+
+    ComplexType {
+        nested: Some(Scalars::default()),
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+    .visit(&mut Noop);
+}
@@ -0,0 +1,277 @@
+use proptest::prelude::*;
+use prost::{bytes::Bytes, encoding::WireType, Message};
+use prost_reflect::{
+    wire::{self, InspectedValue, RawField, RawFields},
+    DynamicMessage,
+};
+
+use crate::{arb_message, contains_group, ComplexType, ContainsGroup, Scalars};
+
+fn raw_fields(buf: impl Into<Bytes>) -> Vec<RawField> {
+    RawFields::new(buf).collect::<Result<_, _>>().unwrap()
+}
+
+#[test]
+fn read_scalars() {
+    let message = Scalars {
+        double: 1.5,
+        float: 2.5,
+        int32: 150,
+        string: "foo".to_owned(),
+        ..Default::default()
+    };
+    let fields = raw_fields(message.encode_to_vec());
+
+    let keys: Vec<_> = fields
+        .iter()
+        .map(|field| (field.number(), field.wire_type()))
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            (1, WireType::SixtyFourBit),
+            (2, WireType::ThirtyTwoBit),
+            (3, WireType::Varint),
+            (14, WireType::LengthDelimited),
+        ]
+    );
+    assert_eq!(fields[0].value().as_ref(), 1.5f64.to_le_bytes());
+    assert_eq!(fields[1].value().as_ref(), 2.5f32.to_le_bytes());
+    assert_eq!(fields[2].value().as_ref(), b"\x96\x01");
+    assert_eq!(fields[3].value().as_ref(), b"foo");
+}
+
+#[test]
+fn read_group() {
+    let message = ContainsGroup {
+        groupmessage: vec![contains_group::GroupMessage {
+            url: "hello".to_owned(),
+            id: Some(10),
+        }],
+    };
+    let fields = raw_fields(message.encode_to_vec());
+
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].number(), 1);
+    assert_eq!(fields[0].wire_type(), WireType::StartGroup);
+    assert_eq!(fields[0].value().as_ref(), b"\x12\x05hello\x18\x0a");
+
+    let inner = raw_fields(fields[0].value().clone());
+    assert_eq!(inner.len(), 2);
+    assert_eq!(inner[0].value().as_ref(), b"hello");
+}
+
+#[test]
+fn values_are_not_copied() {
+    let buf = Bytes::from(
+        Scalars {
+            bytes: vec![1; 100],
+            ..Default::default()
+        }
+        .encode_to_vec(),
+    );
+    let fields = raw_fields(buf.clone());
+    assert!(buf.as_ptr_range().contains(&fields[0].value().as_ptr()));
+}
+
+#[test]
+fn rewrite_fields() {
+    let message = Scalars {
+        int32: 150,
+        string: "foo".to_owned(),
+        bool: true,
+        ..Default::default()
+    };
+
+    let mut rewritten = Vec::new();
+    for field in RawFields::new(message.encode_to_vec()) {
+        let field = field.unwrap();
+        match field.number() {
+            13 => continue,
+            14 => RawField::new(14, WireType::LengthDelimited, Bytes::from_static(b"bar"))
+                .unwrap()
+                .encode(&mut rewritten),
+            _ => field.encode(&mut rewritten),
+        }
+    }
+
+    let expected = Scalars {
+        int32: 150,
+        string: "bar".to_owned(),
+        ..Default::default()
+    };
+    assert_eq!(Scalars::decode(rewritten.as_slice()).unwrap(), expected);
+    assert_eq!(rewritten, expected.encode_to_vec());
+}
+
+#[test]
+fn invalid_fields() {
+    fn error_offset(buf: &'static [u8]) -> usize {
+        let mut fields = RawFields::new(buf);
+        loop {
+            match fields.next() {
+                Some(Ok(_)) => continue,
+                Some(Err(_)) => break,
+                None => panic!("expected error for {:?}", buf),
+            }
+        }
+        assert!(fields.next().is_none());
+        fields.offset()
+    }
+
+    // Truncated varint.
+    assert_eq!(error_offset(b"\x08\x96"), 0);
+    // Length prefix longer than the message.
+    assert_eq!(error_offset(b"\x08\x01\x12\x05foo"), 2);
+    // Unexpected end group tag.
+    assert_eq!(error_offset(b"\x08\x01\x0c"), 2);
+    // Mismatched end group tag.
+    assert_eq!(error_offset(b"\x0b\x14"), 0);
+    // Invalid wire type.
+    assert_eq!(error_offset(b"\x0f"), 0);
+    // Truncated fixed-width value.
+    assert_eq!(error_offset(b"\x0d\x01\x02"), 0);
+}
+
+#[test]
+fn new_field() {
+    assert!(RawField::new(1, WireType::Varint, Bytes::from_static(b"\x96\x01")).is_ok());
+    assert!(RawField::new(1, WireType::Varint, Bytes::from_static(b"\x96\x01\x00")).is_err());
+    assert!(RawField::new(1, WireType::SixtyFourBit, Bytes::from_static(b"\x00")).is_err());
+    assert!(RawField::new(
+        1,
+        WireType::ThirtyTwoBit,
+        Bytes::from_static(b"\x00\x00\x00\x00")
+    )
+    .is_ok());
+    assert!(RawField::new(1, WireType::StartGroup, Bytes::from_static(b"\x08\x01")).is_ok());
+    assert!(RawField::new(1, WireType::StartGroup, Bytes::from_static(b"\x08")).is_err());
+    assert!(RawField::new(1, WireType::EndGroup, Bytes::new()).is_err());
+    assert!(RawField::new(0, WireType::Varint, Bytes::from_static(b"\x00")).is_err());
+
+    let field = RawField::new(1, WireType::StartGroup, Bytes::from_static(b"\x08\x01")).unwrap();
+    let mut buf = Vec::new();
+    field.encode(&mut buf);
+    assert_eq!(buf, b"\x0b\x08\x01\x0c");
+    assert_eq!(field.encoded_len(), buf.len());
+}
+
+#[test]
+fn inspect_nested_message() {
+    let message = ComplexType {
+        nested: Some(Scalars {
+            int32: 150,
+            float: 1.5,
+            string: "hello\nworld".to_owned(),
+            bytes: vec![0, 159, 255],
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    };
+    let inspected = wire::inspect(message.encode_to_vec());
+
+    assert!(inspected.trailing_bytes().is_empty());
+    assert_eq!(inspected.fields().len(), 2);
+    assert_eq!(inspected.fields()[0].number(), 3);
+    let nested = match inspected.fields()[0].value() {
+        InspectedValue::Message(nested) => nested,
+        value => panic!("expected message, got {:?}", value),
+    };
+    let values: Vec<_> = nested.fields().iter().map(|field| field.value()).collect();
+    assert_eq!(
+        values,
+        [
+            &InspectedValue::Fixed32(1.5f32.to_bits()),
+            &InspectedValue::Varint(150),
+            &InspectedValue::String("hello\nworld".to_owned()),
+            &InspectedValue::Bytes(Bytes::from_static(b"\x00\x9f\xff")),
+        ]
+    );
+
+    assert_eq!(
+        inspected.to_string(),
+        r#"3: {
+  2: 1069547520i32
+  3: 150
+  14: "hello\nworld"
+  15: b"\x00\x9f\xff"
+}
+4: b"\x01\x03"
+"#
+    );
+}
+
+#[test]
+fn inspect_group() {
+    let message = ContainsGroup {
+        groupmessage: vec![contains_group::GroupMessage {
+            url: "hello".to_owned(),
+            id: Some(-1),
+        }],
+    };
+    assert_eq!(
+        wire::inspect(message.encode_to_vec()).to_string(),
+        "1: !{\n  2: \"hello\"\n  3: 18446744073709551615\n}\n"
+    );
+}
+
+#[test]
+fn inspect_invalid() {
+    let inspected = wire::inspect(b"\x08\x01\x12\x05foo".as_ref());
+    assert_eq!(inspected.fields().len(), 1);
+    assert_eq!(inspected.trailing_bytes().as_ref(), b"\x12\x05foo");
+    assert_eq!(
+        inspected.to_string(),
+        "1: 1\n# invalid: b\"\\x12\\x05foo\"\n"
+    );
+
+    // A length-delimited value which is not a valid message is shown as bytes.
+    let inspected = wire::inspect(b"\x0a\x02\x08\x80".as_ref());
+    assert_eq!(
+        inspected.fields()[0].value(),
+        &InspectedValue::Bytes(Bytes::from_static(b"\x08\x80"))
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn reencode_complex_type(message in arb_message("test.ComplexType")) {
+        reencode(&message)?;
+    }
+
+    #[test]
+    fn reencode_group(message in arb_message("test2.ContainsGroup")) {
+        reencode(&message)?;
+    }
+
+    #[test]
+    fn inspect_arbitrary_bytes(bytes: Vec<u8>) {
+        let inspected = wire::inspect(bytes.clone());
+        prop_assert!(bytes.ends_with(inspected.trailing_bytes()));
+        prop_assert!(!inspected.to_string().is_empty() || bytes.is_empty());
+    }
+}
+
+fn reencode(message: &DynamicMessage) -> Result<(), TestCaseError> {
+    let bytes = message.encode_to_vec();
+
+    let mut fields = RawFields::new(bytes.clone());
+    let mut reencoded = Vec::new();
+    for field in &mut fields {
+        let field = field.unwrap();
+        let copy = RawField::new(field.number(), field.wire_type(), field.value().clone());
+        prop_assert_eq!(copy.as_ref(), Ok(&field));
+        let len = reencoded.len();
+        field.encode(&mut reencoded);
+        prop_assert_eq!(reencoded.len() - len, field.encoded_len());
+    }
+    prop_assert_eq!(fields.offset(), bytes.len());
+    prop_assert_eq!(reencoded, bytes);
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Loading schemas from the [Buf Schema Registry](https://buf.build/product/bsr) and from
+//! [Buf images](https://docs.buf.build/reference/images).
+//!
+//! A Buf image is a self-contained set of files, including all their dependencies, in a format which is
+//! compatible with `google.protobuf.FileDescriptorSet`. Images can be downloaded from the registry with
+//! [`BufClient::get_image`], or read from the output of `buf build` with [`read_image`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use prost_reflect::buf::BufClient;
+//!
+//! let client = BufClient::new().token(std::env::var("BUF_TOKEN").unwrap());
+//! let pool = client.get_image("buf.build/acme/weather:main").unwrap();
+//! let message = pool.get_message_by_name("acme.weather.v1.Forecast").unwrap();
+//! ```
+
+use std::{collections::HashMap, error::Error, fmt, fs, io, io::Read, path::Path};
+
+use prost::{DecodeError, Message};
+use prost_types::FileDescriptorSet;
+
+use crate::{DescriptorError, DescriptorPool};
+
+const DEFAULT_REMOTE: &str = "buf.build";
+const GET_IMAGE_PATH: &str = "/buf.alpha.registry.v1alpha1.ImageService/GetImage";
+const DEFAULT_MAX_RESPONSE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// A client for downloading images from the Buf Schema Registry.
+#[derive(Debug, Clone)]
+pub struct BufClient {
+    agent: ureq::Agent,
+    base_url: Option<String>,
+    tokens: HashMap<String, String>,
+    max_response_size: u64,
+}
+
+/// An error that may occur while loading a Buf image.
+#[derive(Debug)]
+pub struct BufError {
+    kind: BufErrorKind,
+}
+
+#[derive(Debug)]
+enum BufErrorKind {
+    InvalidModule { module: String },
+    UnsupportedFormat { path: String },
+    Io(io::Error),
+    Transport(Box<ureq::Transport>),
+    Status { status: u16, message: String },
+    ResponseTooLarge { limit: u64 },
+    Decode(DecodeError),
+    Descriptor(DescriptorError),
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct GetImageRequest {
+    #[prost(string, tag = "1")]
+    owner: String,
+    #[prost(string, tag = "2")]
+    repository: String,
+    #[prost(string, tag = "3")]
+    reference: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct GetImageResponse {
+    // buf.alpha.image.v1.Image is wire-compatible with FileDescriptorSet.
+    #[prost(message, optional, tag = "1")]
+    image: Option<FileDescriptorSet>,
+}
+
+impl BufClient {
+    /// Creates a new [`BufClient`] with no authentication token.
+    pub fn new() -> Self {
+        BufClient {
+            agent: ureq::Agent::new(),
+            base_url: None,
+            tokens: HashMap::new(),
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        }
+    }
+
+    /// Sets the token used to authenticate with the `buf.build` registry. This is required to access private
+    /// modules.
+    ///
+    /// Tokens can be created in the settings page of the registry. The token is only sent when downloading
+    /// modules whose remote is `buf.build`. Use [`remote_token`][Self::remote_token] for other registries.
+    pub fn token(self, token: impl Into<String>) -> Self {
+        self.remote_token(DEFAULT_REMOTE, token)
+    }
+
+    /// Sets the token used to authenticate with the registry at `remote`, for example `buf.example.com`.
+    ///
+    /// The token is only sent when downloading modules whose remote is exactly `remote`.
+    pub fn remote_token(mut self, remote: impl Into<String>, token: impl Into<String>) -> Self {
+        self.tokens.insert(remote.into(), token.into());
+        self
+    }
+
+    /// Sets the maximum size, in bytes, of a response from the registry. Larger responses cause
+    /// [`get_image`][Self::get_image] to return an error.
+    ///
+    /// The default value is 256 MiB.
+    pub fn max_response_size(mut self, size: u64) -> Self {
+        self.max_response_size = size;
+        self
+    }
+
+    /// Overrides the URL of the registry API, which is otherwise determined by the remote of the module name.
+    ///
+    /// This is useful for testing, or for self-hosted registries with a non-standard API address.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Downloads the image of a module from the registry, including all of its dependencies.
+    ///
+    /// The module is named in the same format as used in `buf.yaml`: `remote/owner/repository`,
+    /// optionally followed by `:reference` to select a branch, tag or commit. If the reference is
+    /// omitted, the latest commit on the main branch is used. The remote may also be omitted, in which
+    /// case it defaults to `buf.build`.
+    pub fn get_image(&self, module: &str) -> Result<DescriptorPool, BufError> {
+        let (remote, owner, repository, reference) = parse_module(module)?;
+
+        let base_url = match &self.base_url {
+            Some(base_url) => base_url.trim_end_matches('/').to_owned(),
+            None if remote == DEFAULT_REMOTE => format!("https://api.{}", remote),
+            None => format!("https://{}", remote),
+        };
+
+        let request = GetImageRequest {
+            owner: owner.to_owned(),
+            repository: repository.to_owned(),
+            reference: reference.to_owned(),
+        };
+
+        let mut http_request = self
+            .agent
+            .post(&format!("{}{}", base_url, GET_IMAGE_PATH))
+            .set("Content-Type", "application/proto")
+            .set("Connect-Protocol-Version", "1");
+        if let Some(token) = self.tokens.get(remote) {
+            http_request = http_request.set("Authorization", &format!("Bearer {}", token));
+        }
+
+        let response = match http_request.send_bytes(&request.encode_to_vec()) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                let message = response.into_string().unwrap_or_default();
+                return Err(BufErrorKind::Status { status, message }.into());
+            }
+            Err(ureq::Error::Transport(err)) => {
+                return Err(BufErrorKind::Transport(Box::new(err)).into())
+            }
+        };
+
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .take(self.max_response_size.saturating_add(1))
+            .read_to_end(&mut bytes)
+            .map_err(BufErrorKind::Io)?;
+        if bytes.len() as u64 > self.max_response_size {
+            return Err(BufErrorKind::ResponseTooLarge {
+                limit: self.max_response_size,
+            }
+            .into());
+        }
+
+        let response = GetImageResponse::decode(bytes.as_slice()).map_err(BufErrorKind::Decode)?;
+        let image = response.image.unwrap_or_default();
+        DescriptorPool::from_file_descriptor_set(image)
+            .map_err(|err| BufErrorKind::Descriptor(err).into())
+    }
+}
+
+impl Default for BufClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads a Buf image from a file, as produced by `buf build -o`.
+///
+/// Binary images (usually with the extension `.bin` or `.binpb`) are supported, optionally compressed with
+/// gzip if the file name ends in `.gz`. JSON images are not supported.
+pub fn read_image(path: impl AsRef<Path>) -> Result<DescriptorPool, BufError> {
+    let path = path.as_ref();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    let (file_name, compressed) = match file_name.strip_suffix(".gz") {
+        Some(file_name) => (file_name, true),
+        None => (&*file_name, false),
+    };
+    if file_name.ends_with(".json") {
+        return Err(BufErrorKind::UnsupportedFormat {
+            path: path.display().to_string(),
+        }
+        .into());
+    }
+
+    let file = fs::File::open(path).map_err(BufErrorKind::Io)?;
+    let mut bytes = Vec::new();
+    if compressed {
+        flate2::read::GzDecoder::new(file).read_to_end(&mut bytes)
+    } else {
+        io::BufReader::new(file).read_to_end(&mut bytes)
+    }
+    .map_err(BufErrorKind::Io)?;
+
+    decode_image(&bytes)
+}
+
+/// Decodes a binary Buf image.
+pub fn decode_image(bytes: &[u8]) -> Result<DescriptorPool, BufError> {
+    let image = FileDescriptorSet::decode(bytes).map_err(BufErrorKind::Decode)?;
+    DescriptorPool::from_file_descriptor_set(image)
+        .map_err(|err| BufErrorKind::Descriptor(err).into())
+}
+
+/// Splits a module name into its remote, owner, repository and reference.
+fn parse_module(module: &str) -> Result<(&str, &str, &str, &str), BufError> {
+    let (name, reference) = match module.split_once(':') {
+        Some((name, reference)) => (name, reference),
+        None => (module, ""),
+    };
+
+    let parts: Vec<&str> = name.split('/').collect();
+    let (remote, owner, repository) = match parts.as_slice() {
+        [remote, owner, repository] => (*remote, *owner, *repository),
+        [owner, repository] => (DEFAULT_REMOTE, *owner, *repository),
+        _ => ("", "", ""),
+    };
+
+    if remote.is_empty() || owner.is_empty() || repository.is_empty() {
+        return Err(BufErrorKind::InvalidModule {
+            module: module.to_owned(),
+        }
+        .into());
+    }
+
+    Ok((remote, owner, repository, reference))
+}
+
+impl fmt::Display for BufError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            BufErrorKind::InvalidModule { module } => write!(
+                f,
+                "invalid module name '{}': expected 'remote/owner/repository'",
+                module
+            ),
+            BufErrorKind::UnsupportedFormat { path } => {
+                write!(f, "unsupported image format for '{}'", path)
+            }
+            BufErrorKind::Io(err) => write!(f, "failed to read image: {}", err),
+            BufErrorKind::Transport(err) => write!(f, "failed to connect to registry: {}", err),
+            BufErrorKind::Status { status, message } if message.is_empty() => {
+                write!(f, "registry returned status {}", status)
+            }
+            BufErrorKind::Status { status, message } => {
+                write!(f, "registry returned status {}: {}", status, message)
+            }
+            BufErrorKind::ResponseTooLarge { limit } => {
+                write!(f, "registry response is larger than {} bytes", limit)
+            }
+            BufErrorKind::Decode(err) => write!(f, "failed to decode image: {}", err),
+            BufErrorKind::Descriptor(err) => write!(f, "invalid image: {}", err),
+        }
+    }
+}
+
+impl Error for BufError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            BufErrorKind::Io(err) => Some(err),
+            BufErrorKind::Transport(err) => Some(err),
+            BufErrorKind::Decode(err) => Some(err),
+            BufErrorKind::Descriptor(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BufErrorKind> for BufError {
+    fn from(kind: BufErrorKind) -> Self {
+        BufError { kind }
+    }
+}
@@ -0,0 +1,146 @@
+//! A [`tokio_util::codec`] implementation for reading and writing length-delimited dynamic messages.
+
+use std::{convert::TryFrom, io};
+
+use prost::{
+    bytes::{Buf, BytesMut},
+    encoding::encoded_len_varint,
+    Message,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{dynamic::delimited::push_varint_byte, DynamicMessage, MessageDescriptor};
+
+const DEFAULT_MAX_LENGTH: usize = 8 * 1024 * 1024;
+
+/// A [`Decoder`] and [`Encoder`] for streams of length-delimited [`DynamicMessage`]s.
+///
+/// Each message is prefixed with its length encoded as a varint, which is the framing used by
+/// [`Message::encode_length_delimited`], `writeDelimitedTo` in the Java protobuf library and `protodelim`
+/// in Go. Combined with [`Framed`](tokio_util::codec::Framed), this gives a stream and sink of dynamic
+/// messages over any asynchronous transport, such as a TCP or unix socket.
+///
+/// Decoded messages have the type given by the [`MessageDescriptor`] the codec was created with. Messages
+/// are encoded as-is, so it is the caller's responsibility to send messages of the correct type.
+///
+/// Decoding errors are returned as [`io::Error`]s of kind [`InvalidData`][io::ErrorKind::InvalidData].
+/// To avoid unbounded memory usage, frames longer than [`max_length`][DynamicMessageCodec::max_length],
+/// which defaults to 8 MiB, are rejected.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, DynamicMessage, DynamicMessageCodec, Value};
+/// # let pool = DescriptorPool::decode(include_bytes!("file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// use prost_reflect::bytes::BytesMut;
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// let mut codec = DynamicMessageCodec::new(message_descriptor.clone());
+///
+/// let mut message = DynamicMessage::new(message_descriptor);
+/// message.set_field_by_name("foo", Value::I32(150));
+///
+/// let mut buf = BytesMut::new();
+/// codec.encode(message.clone(), &mut buf).unwrap();
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(message));
+/// assert_eq!(codec.decode(&mut buf).unwrap(), None);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+pub struct DynamicMessageCodec {
+    desc: MessageDescriptor,
+    max_length: usize,
+}
+
+impl DynamicMessageCodec {
+    /// Creates a new [`DynamicMessageCodec`] which decodes messages of type `desc`.
+    pub fn new(desc: MessageDescriptor) -> Self {
+        DynamicMessageCodec {
+            desc,
+            max_length: DEFAULT_MAX_LENGTH,
+        }
+    }
+
+    /// Sets the maximum length of a message, not including its length prefix.
+    ///
+    /// Decoding a longer message, or encoding one, returns an error.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Gets a reference to the type of messages decoded by this codec.
+    pub fn descriptor(&self) -> &MessageDescriptor {
+        &self.desc
+    }
+
+    fn check_length(&self, len: u64) -> io::Result<usize> {
+        match usize::try_from(len) {
+            Ok(len) if len <= self.max_length => Ok(len),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "message length {} exceeds maximum of {}",
+                    len, self.max_length
+                ),
+            )),
+        }
+    }
+}
+
+impl Decoder for DynamicMessageCodec {
+    type Item = DynamicMessage;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        let mut len = 0;
+        let mut prefix_len = None;
+        for (i, &byte) in src.iter().take(10).enumerate() {
+            if let Some(value) = push_varint_byte(&mut len, i as u32, byte)? {
+                len = value;
+                prefix_len = Some(i + 1);
+                break;
+            }
+        }
+
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len,
+            None => return Ok(None),
+        };
+        let len = self.check_length(len)?;
+
+        if src.len() < prefix_len + len {
+            src.reserve(prefix_len + len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(prefix_len);
+        let message = src.split_to(len);
+        DynamicMessage::decode(self.desc.clone(), message.freeze())
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl Encoder<DynamicMessage> for DynamicMessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: DynamicMessage, dst: &mut BytesMut) -> io::Result<()> {
+        Encoder::<&DynamicMessage>::encode(self, &item, dst)
+    }
+}
+
+impl<'a> Encoder<&'a DynamicMessage> for DynamicMessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &'a DynamicMessage, dst: &mut BytesMut) -> io::Result<()> {
+        let len = item.encoded_len();
+        self.check_length(len as u64)?;
+
+        dst.reserve(encoded_len_varint(len as u64) + len);
+        item.encode_length_delimited(dst)
+            .expect("buffer has sufficient capacity");
+        Ok(())
+    }
+}
@@ -0,0 +1,688 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    DescriptorPool, DynamicMessage, EnumDescriptor, ExtensionDescriptor, FieldDescriptor,
+    FileDescriptor, MessageDescriptor, MethodDescriptor, OneofDescriptor, ServiceDescriptor,
+};
+
+use super::{cardinality_name, is_proto3_optional, type_name};
+
+const UNSET: &str = "<unset>";
+
+/// The structural differences between two descriptor pools, returned by [`diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    changes: Vec<SchemaChange>,
+}
+
+/// An element which was added to, removed from or modified between two versions of a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    change_type: ChangeType,
+    element_kind: ElementKind,
+    name: String,
+    properties: Vec<PropertyChange>,
+}
+
+/// Whether an element in a [`SchemaChange`] was added, removed or modified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeType {
+    /// The element exists only in the new schema.
+    Added,
+    /// The element exists only in the old schema.
+    Removed,
+    /// The element exists in both schemas, but some of its properties changed.
+    Modified,
+}
+
+/// The kind of element in a [`SchemaChange`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    /// A file, named by its path.
+    File,
+    /// A message type.
+    Message,
+    /// A field of a message.
+    Field,
+    /// A oneof of a message.
+    Oneof,
+    /// An enum type.
+    Enum,
+    /// A value of an enum, named by the full name of its enum followed by its name.
+    EnumValue,
+    /// An extension field.
+    Extension,
+    /// A service.
+    Service,
+    /// A method of a service.
+    Method,
+}
+
+/// A property of an element which differs between two versions of a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyChange {
+    name: String,
+    old_value: String,
+    new_value: String,
+}
+
+/// Lists the files, messages, fields, oneofs, enums, enum values, extensions, services and methods which were
+/// added, removed or modified between two descriptor pools.
+///
+/// Elements are matched by name: files by their path, fields, oneofs, enum values and methods by their name
+/// within their parent, and other elements by their full name. This means a renamed element is reported as
+/// removed and added. The children of an added or removed element are not reported separately.
+///
+/// Changes are listed in the order of the old pool, followed by added elements in the order of the new pool.
+/// Options are compared by value, including custom options, and each differing option is reported as a
+/// property named `options.<name>`, or `options.[<full name>]` for custom options.
+///
+/// Unlike [`check_pools`][super::check_pools], every change is reported, whether or not it could break
+/// existing clients. With the `serde` feature, the result can be serialized, for example to JSON, for
+/// consumption by other tools.
+///
+/// # Examples
+///
+/// ```
+/// use prost_reflect::{compat::{self, ChangeType, ElementKind}, FieldBuilder, FileDescriptorBuilder, MessageBuilder};
+///
+/// let old = FileDescriptorBuilder::new("user.proto")
+///     .add_message(MessageBuilder::new("User").add_field(FieldBuilder::new("id", 1, "int32")))
+///     .build()
+///     .unwrap();
+/// let new = FileDescriptorBuilder::new("user.proto")
+///     .add_message(
+///         MessageBuilder::new("User")
+///             .add_field(FieldBuilder::new("id", 1, "int64"))
+///             .add_field(FieldBuilder::new("name", 2, "string")),
+///     )
+///     .build()
+///     .unwrap();
+///
+/// let diff = compat::diff(&old, &new);
+/// assert_eq!(diff.to_string(), "~ field User.id: type: int32 -> int64\n+ field User.name");
+///
+/// let change = diff.changes().next().unwrap();
+/// assert_eq!(change.change_type(), ChangeType::Modified);
+/// assert_eq!(change.element_kind(), ElementKind::Field);
+/// assert_eq!(change.properties()[0].name(), "type");
+/// ```
+pub fn diff(old: &DescriptorPool, new: &DescriptorPool) -> SchemaDiff {
+    let mut differ = Differ::default();
+
+    for old_file in old.files() {
+        match new.get_file_by_name(old_file.name()) {
+            Some(new_file) => differ.file(&old_file, &new_file),
+            None => differ.push(ChangeType::Removed, ElementKind::File, old_file.name()),
+        }
+    }
+    for new_file in new.files() {
+        if old.get_file_by_name(new_file.name()).is_none() {
+            differ.push(ChangeType::Added, ElementKind::File, new_file.name());
+        }
+    }
+
+    for old_message in old.all_messages().filter(|message| !message.is_map_entry()) {
+        match new.get_message_by_name(old_message.full_name()) {
+            Some(new_message) => differ.message(&old_message, &new_message),
+            None => differ.push(
+                ChangeType::Removed,
+                ElementKind::Message,
+                old_message.full_name(),
+            ),
+        }
+    }
+    for new_message in new.all_messages().filter(|message| !message.is_map_entry()) {
+        if old.get_message_by_name(new_message.full_name()).is_none() {
+            differ.push(
+                ChangeType::Added,
+                ElementKind::Message,
+                new_message.full_name(),
+            );
+        }
+    }
+
+    for old_enum in old.all_enums() {
+        match new.get_enum_by_name(old_enum.full_name()) {
+            Some(new_enum) => differ.enum_(&old_enum, &new_enum),
+            None => differ.push(ChangeType::Removed, ElementKind::Enum, old_enum.full_name()),
+        }
+    }
+    for new_enum in new.all_enums() {
+        if old.get_enum_by_name(new_enum.full_name()).is_none() {
+            differ.push(ChangeType::Added, ElementKind::Enum, new_enum.full_name());
+        }
+    }
+
+    let old_extensions: Vec<_> = old.all_extensions().collect();
+    let new_extensions: Vec<_> = new.all_extensions().collect();
+    differ.children(
+        ElementKind::Extension,
+        &old_extensions,
+        &new_extensions,
+        |extension| extension.full_name().to_owned(),
+        |extension| extension.full_name().to_owned(),
+        extension_properties,
+    );
+
+    let old_services: Vec<_> = old.services().collect();
+    let new_services: Vec<_> = new.services().collect();
+    for old_service in &old_services {
+        match new_services
+            .iter()
+            .find(|service| service.full_name() == old_service.full_name())
+        {
+            Some(new_service) => differ.service(old_service, new_service),
+            None => differ.push(
+                ChangeType::Removed,
+                ElementKind::Service,
+                old_service.full_name(),
+            ),
+        }
+    }
+    for new_service in &new_services {
+        if !old_services
+            .iter()
+            .any(|service| service.full_name() == new_service.full_name())
+        {
+            differ.push(
+                ChangeType::Added,
+                ElementKind::Service,
+                new_service.full_name(),
+            );
+        }
+    }
+
+    differ.diff
+}
+
+impl SchemaDiff {
+    /// Returns `true` if there are no differences between the schemas.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Gets an iterator over the changed elements.
+    pub fn changes(&self) -> impl ExactSizeIterator<Item = &SchemaChange> + '_ {
+        self.changes.iter()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, change) in self.changes.iter().enumerate() {
+            if index != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+impl SchemaChange {
+    /// Whether the element was added, removed or modified.
+    pub fn change_type(&self) -> ChangeType {
+        self.change_type
+    }
+
+    /// The kind of element which changed.
+    pub fn element_kind(&self) -> ElementKind {
+        self.element_kind
+    }
+
+    /// The name of the element which changed. See [`ElementKind`] for how each kind of element is named.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The properties of the element which changed. This is empty unless the element was modified.
+    pub fn properties(&self) -> &[PropertyChange] {
+        &self.properties
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = match self.change_type {
+            ChangeType::Added => '+',
+            ChangeType::Removed => '-',
+            ChangeType::Modified => '~',
+        };
+        write!(f, "{} {} {}", prefix, self.element_kind, self.name)?;
+        for (index, property) in self.properties.iter().enumerate() {
+            let separator = if index == 0 { ": " } else { ", " };
+            write!(f, "{}{}", separator, property)?;
+        }
+        Ok(())
+    }
+}
+
+impl ChangeType {
+    /// Gets a lowercase name for this change type, such as `"added"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeType::Added => "added",
+            ChangeType::Removed => "removed",
+            ChangeType::Modified => "modified",
+        }
+    }
+}
+
+impl fmt::Display for ChangeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ElementKind {
+    /// Gets a lowercase name for this element kind, such as `"enum_value"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ElementKind::File => "file",
+            ElementKind::Message => "message",
+            ElementKind::Field => "field",
+            ElementKind::Oneof => "oneof",
+            ElementKind::Enum => "enum",
+            ElementKind::EnumValue => "enum_value",
+            ElementKind::Extension => "extension",
+            ElementKind::Service => "service",
+            ElementKind::Method => "method",
+        }
+    }
+}
+
+impl fmt::Display for ElementKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElementKind::EnumValue => f.write_str("enum value"),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+impl PropertyChange {
+    /// The name of the property, for example `number` or `options.deprecated`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The value of the property in the old schema, or `<unset>`.
+    pub fn old_value(&self) -> &str {
+        &self.old_value
+    }
+
+    /// The value of the property in the new schema, or `<unset>`.
+    pub fn new_value(&self) -> &str {
+        &self.new_value
+    }
+}
+
+impl fmt::Display for PropertyChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.name, self.old_value, self.new_value)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serialize {
+    use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+    use super::{ChangeType, ElementKind, PropertyChange, SchemaChange, SchemaDiff};
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    impl Serialize for SchemaDiff {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_seq(&self.changes)
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    impl Serialize for SchemaChange {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut s = serializer.serialize_struct("SchemaChange", 4)?;
+            s.serialize_field("type", &self.change_type)?;
+            s.serialize_field("kind", &self.element_kind)?;
+            s.serialize_field("name", &self.name)?;
+            s.serialize_field("properties", &self.properties)?;
+            s.end()
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    impl Serialize for ChangeType {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(self.as_str())
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    impl Serialize for ElementKind {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(self.as_str())
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    impl Serialize for PropertyChange {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut s = serializer.serialize_struct("PropertyChange", 3)?;
+            s.serialize_field("name", &self.name)?;
+            s.serialize_field("old", &self.old_value)?;
+            s.serialize_field("new", &self.new_value)?;
+            s.end()
+        }
+    }
+}
+
+#[derive(Default)]
+struct Differ {
+    diff: SchemaDiff,
+}
+
+#[derive(Default)]
+struct Properties {
+    changes: Vec<PropertyChange>,
+}
+
+impl Differ {
+    fn push(&mut self, change_type: ChangeType, element_kind: ElementKind, name: &str) {
+        self.push_properties(change_type, element_kind, name, Vec::new());
+    }
+
+    fn push_properties(
+        &mut self,
+        change_type: ChangeType,
+        element_kind: ElementKind,
+        name: &str,
+        properties: Vec<PropertyChange>,
+    ) {
+        self.diff.changes.push(SchemaChange {
+            change_type,
+            element_kind,
+            name: name.to_owned(),
+            properties,
+        });
+    }
+
+    fn modified(&mut self, element_kind: ElementKind, name: &str, properties: Properties) {
+        if !properties.changes.is_empty() {
+            self.push_properties(ChangeType::Modified, element_kind, name, properties.changes);
+        }
+    }
+
+    /// Compares lists of child elements by key, reporting removed, added and modified elements.
+    fn children<T>(
+        &mut self,
+        element_kind: ElementKind,
+        old: &[T],
+        new: &[T],
+        key: impl Fn(&T) -> String,
+        name: impl Fn(&T) -> String,
+        properties: impl Fn(&T, &T) -> Properties,
+    ) {
+        for old_child in old {
+            let old_key = key(old_child);
+            match new.iter().find(|new_child| key(new_child) == old_key) {
+                Some(new_child) => self.modified(
+                    element_kind,
+                    &name(old_child),
+                    properties(old_child, new_child),
+                ),
+                None => self.push(ChangeType::Removed, element_kind, &name(old_child)),
+            }
+        }
+        let old_keys: HashSet<String> = old.iter().map(&key).collect();
+        for new_child in new {
+            if !old_keys.contains(&key(new_child)) {
+                self.push(ChangeType::Added, element_kind, &name(new_child));
+            }
+        }
+    }
+
+    fn file(&mut self, old: &FileDescriptor, new: &FileDescriptor) {
+        let mut properties = Properties::default();
+        properties.compare("package", old.package_name(), new.package_name());
+        properties.compare(
+            "syntax",
+            format!("{:?}", old.syntax()),
+            format!("{:?}", new.syntax()),
+        );
+        properties.compare("dependencies", dependencies(old), dependencies(new));
+        properties.options(old.options(), new.options());
+        self.modified(ElementKind::File, old.name(), properties);
+    }
+
+    fn message(&mut self, old: &MessageDescriptor, new: &MessageDescriptor) {
+        let mut properties = Properties::default();
+        properties.options(old.options(), new.options());
+        self.modified(ElementKind::Message, old.full_name(), properties);
+
+        let old_fields: Vec<_> = old.fields().collect();
+        let new_fields: Vec<_> = new.fields().collect();
+        self.children(
+            ElementKind::Field,
+            &old_fields,
+            &new_fields,
+            |field| field.name().to_owned(),
+            |field| field.full_name().to_owned(),
+            field_properties,
+        );
+
+        let old_oneofs: Vec<_> = old.oneofs().filter(|oneof| !is_synthetic(oneof)).collect();
+        let new_oneofs: Vec<_> = new.oneofs().filter(|oneof| !is_synthetic(oneof)).collect();
+        self.children(
+            ElementKind::Oneof,
+            &old_oneofs,
+            &new_oneofs,
+            |oneof| oneof.name().to_owned(),
+            |oneof| oneof.full_name().to_owned(),
+            |old, new| {
+                let mut properties = Properties::default();
+                properties.options(old.options(), new.options());
+                properties
+            },
+        );
+    }
+
+    fn enum_(&mut self, old: &EnumDescriptor, new: &EnumDescriptor) {
+        let mut properties = Properties::default();
+        properties.options(old.options(), new.options());
+        self.modified(ElementKind::Enum, old.full_name(), properties);
+
+        let old_values: Vec<_> = old.values().collect();
+        let new_values: Vec<_> = new.values().collect();
+        self.children(
+            ElementKind::EnumValue,
+            &old_values,
+            &new_values,
+            |value| value.name().to_owned(),
+            |value| format!("{}.{}", old.full_name(), value.name()),
+            |old, new| {
+                let mut properties = Properties::default();
+                properties.compare("number", old.number(), new.number());
+                properties.options(old.options(), new.options());
+                properties
+            },
+        );
+    }
+
+    fn service(&mut self, old: &ServiceDescriptor, new: &ServiceDescriptor) {
+        let mut properties = Properties::default();
+        properties.options(old.options(), new.options());
+        self.modified(ElementKind::Service, old.full_name(), properties);
+
+        let old_methods: Vec<_> = old.methods().collect();
+        let new_methods: Vec<_> = new.methods().collect();
+        self.children(
+            ElementKind::Method,
+            &old_methods,
+            &new_methods,
+            |method| method.name().to_owned(),
+            |method| method.full_name().to_owned(),
+            method_properties,
+        );
+    }
+}
+
+impl Properties {
+    fn compare(&mut self, name: &str, old: impl ToString, new: impl ToString) {
+        let old_value = old.to_string();
+        let new_value = new.to_string();
+        if old_value != new_value {
+            self.changes.push(PropertyChange {
+                name: name.to_owned(),
+                old_value,
+                new_value,
+            });
+        }
+    }
+
+    fn options(&mut self, old: Option<DynamicMessage>, new: Option<DynamicMessage>) {
+        let old_options = old
+            .map(|options| options.field_strings())
+            .unwrap_or_default();
+        let new_options = new
+            .map(|options| options.field_strings())
+            .unwrap_or_default();
+
+        let get = |options: &[(String, String)], name: &str| {
+            options
+                .iter()
+                .find(|(option, _)| option == name)
+                .map(|(_, value)| value.clone())
+                .unwrap_or_else(|| UNSET.to_owned())
+        };
+
+        for (name, old_value) in &old_options {
+            self.compare(
+                &format!("options.{}", name),
+                old_value,
+                get(&new_options, name),
+            );
+        }
+        for (name, new_value) in &new_options {
+            if !old_options.iter().any(|(option, _)| option == name) {
+                self.compare(&format!("options.{}", name), UNSET, new_value);
+            }
+        }
+    }
+}
+
+fn field_properties(old: &FieldDescriptor, new: &FieldDescriptor) -> Properties {
+    let mut properties = Properties::default();
+    properties.compare("number", old.number(), new.number());
+    properties.compare(
+        "cardinality",
+        cardinality_name(old.cardinality()),
+        cardinality_name(new.cardinality()),
+    );
+    properties.compare("type", field_type(old), field_type(new));
+    properties.compare("presence", old.supports_presence(), new.supports_presence());
+    properties.compare("json_name", old.json_name(), new.json_name());
+    properties.compare("oneof", oneof_name(old), oneof_name(new));
+    properties.compare("default", default_value(old), default_value(new));
+    properties.options(old.options(), new.options());
+    properties
+}
+
+fn extension_properties(old: &ExtensionDescriptor, new: &ExtensionDescriptor) -> Properties {
+    let mut properties = Properties::default();
+    properties.compare(
+        "extendee",
+        old.containing_message().full_name(),
+        new.containing_message().full_name(),
+    );
+    properties.compare("number", old.number(), new.number());
+    properties.compare(
+        "cardinality",
+        cardinality_name(old.cardinality()),
+        cardinality_name(new.cardinality()),
+    );
+    properties.compare("type", type_name(&old.kind()), type_name(&new.kind()));
+    properties.options(old.options(), new.options());
+    properties
+}
+
+fn method_properties(old: &MethodDescriptor, new: &MethodDescriptor) -> Properties {
+    let mut properties = Properties::default();
+    properties.compare(
+        "input_type",
+        old.input().full_name(),
+        new.input().full_name(),
+    );
+    properties.compare(
+        "output_type",
+        old.output().full_name(),
+        new.output().full_name(),
+    );
+    properties.compare(
+        "client_streaming",
+        old.is_client_streaming(),
+        new.is_client_streaming(),
+    );
+    properties.compare(
+        "server_streaming",
+        old.is_server_streaming(),
+        new.is_server_streaming(),
+    );
+    properties.options(old.options(), new.options());
+    properties
+}
+
+fn dependencies(file: &FileDescriptor) -> String {
+    file.dependencies()
+        .map(|dependency| dependency.name().to_owned())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn field_type(field: &FieldDescriptor) -> String {
+    if field.is_map() {
+        let entry = field.kind();
+        let entry = entry.as_message().expect("map field must be a message");
+        format!(
+            "map<{}, {}>",
+            type_name(&entry.map_entry_key_field().kind()),
+            type_name(&entry.map_entry_value_field().kind())
+        )
+    } else if field.is_group() {
+        format!("group {}", type_name(&field.kind()))
+    } else {
+        type_name(&field.kind())
+    }
+}
+
+fn oneof_name(field: &FieldDescriptor) -> String {
+    match field.containing_oneof() {
+        Some(oneof) if !is_proto3_optional(field) => oneof.name().to_owned(),
+        _ => UNSET.to_owned(),
+    }
+}
+
+fn default_value(field: &FieldDescriptor) -> &str {
+    field
+        .field_descriptor_proto()
+        .default_value
+        .as_deref()
+        .unwrap_or(UNSET)
+}
+
+fn is_synthetic(oneof: &OneofDescriptor) -> bool {
+    oneof.fields().all(|field| is_proto3_optional(&field))
+}
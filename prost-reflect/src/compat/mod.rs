@@ -0,0 +1,544 @@
+//! Checking for breaking changes between two versions of a protobuf schema.
+//!
+//! [`check_pools`] compares each message, enum and service in an old [`DescriptorPool`] with the element of the
+//! same full name in a new pool, and reports every change which could break existing clients or stored data.
+//! [`check_files`] and [`check_messages`] compare a single file or message type in the same way. Changes which
+//! are always safe, such as adding fields, enum values or methods, are not reported.
+//!
+//! Fields and enum values are matched by number, so renaming a field is reported as a
+//! [`FieldNameChanged`][ChangeKind::FieldNameChanged], and changing its number is reported as a
+//! [`FieldNumberChanged`][ChangeKind::FieldNumberChanged]. Some changes break the JSON mapping or generated
+//! code without changing the binary encoding; [`BreakingChange::is_wire_compatible`] can be used to
+//! ignore these, for example when only the binary encoding is ever persisted.
+//!
+//! To list every difference between two schemas, including safe changes and changed options, use [`diff`].
+//!
+//! # Examples
+//!
+//! ```
+//! use prost_reflect::{compat::{self, ChangeKind}, FieldBuilder, FileDescriptorBuilder, MessageBuilder};
+//!
+//! let old = FileDescriptorBuilder::new("user.proto")
+//!     .add_message(
+//!         MessageBuilder::new("User")
+//!             .add_field(FieldBuilder::new("id", 1, "int64"))
+//!             .add_field(FieldBuilder::new("name", 2, "string")),
+//!     )
+//!     .build()
+//!     .unwrap();
+//! let new = FileDescriptorBuilder::new("user.proto")
+//!     .add_message(MessageBuilder::new("User").add_field(FieldBuilder::new("id", 1, "string")))
+//!     .build()
+//!     .unwrap();
+//!
+//! let changes = compat::check_pools(&old, &new);
+//! assert_eq!(changes.len(), 2);
+//! assert_eq!(changes[0].kind(), ChangeKind::FieldTypeChanged);
+//! assert_eq!(changes[0].to_string(), "User.id: type changed from int64 to string");
+//! assert_eq!(changes[1].kind(), ChangeKind::FieldRemoved);
+//! assert!(changes[1].is_wire_compatible());
+//! ```
+
+mod diff;
+
+pub use self::diff::{diff, ChangeType, ElementKind, PropertyChange, SchemaChange, SchemaDiff};
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    Cardinality, DescriptorPool, EnumDescriptor, FieldDescriptor, FileDescriptor, Kind,
+    MessageDescriptor, ServiceDescriptor,
+};
+
+/// A change between two versions of a schema which may break existing clients or data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakingChange {
+    kind: ChangeKind,
+    element: String,
+    message: String,
+    wire_compatible: bool,
+}
+
+/// The kind of a [`BreakingChange`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /// A message type was removed.
+    MessageRemoved,
+    /// A field was removed. Data encoded with the old schema will be decoded as unknown fields, but clients
+    /// using the old schema may still expect the field to be set.
+    FieldRemoved,
+    /// The number of a field changed.
+    FieldNumberChanged,
+    /// The name of a field changed. This breaks the JSON mapping and generated code.
+    FieldNameChanged,
+    /// The type of a field changed.
+    FieldTypeChanged,
+    /// A field changed between optional, required and repeated.
+    FieldCardinalityChanged,
+    /// A field was moved into or out of a oneof, or into a different oneof.
+    FieldOneofChanged,
+    /// An enum type was removed.
+    EnumRemoved,
+    /// An enum value was removed.
+    EnumValueRemoved,
+    /// The name of an enum value changed. This breaks the JSON mapping and generated code.
+    EnumValueNameChanged,
+    /// A service was removed.
+    ServiceRemoved,
+    /// A method was removed from a service.
+    MethodRemoved,
+    /// The request or response type of a method changed.
+    MethodTypeChanged,
+    /// A method changed between unary and client, server or bidirectional streaming.
+    MethodStreamingChanged,
+}
+
+/// Checks for breaking changes between all the messages, enums and services in two descriptor pools.
+///
+/// Elements are matched by their full name, regardless of which file they are defined in.
+pub fn check_pools(old: &DescriptorPool, new: &DescriptorPool) -> Vec<BreakingChange> {
+    let mut checker = Checker::default();
+
+    for old_message in old.all_messages() {
+        match new.get_message_by_name(old_message.full_name()) {
+            Some(new_message) => checker.message(&old_message, &new_message),
+            None => checker.message_removed(&old_message),
+        }
+    }
+    for old_enum in old.all_enums() {
+        match new.get_enum_by_name(old_enum.full_name()) {
+            Some(new_enum) => checker.enum_(&old_enum, &new_enum),
+            None => checker.enum_removed(&old_enum),
+        }
+    }
+    let new_services: HashMap<_, _> = new
+        .services()
+        .map(|service| (service.full_name().to_owned(), service))
+        .collect();
+    for old_service in old.services() {
+        checker.service(&old_service, new_services.get(old_service.full_name()));
+    }
+
+    checker.changes
+}
+
+/// Checks for breaking changes between two versions of a file.
+///
+/// Messages, enums and services defined in `old` are matched by full name with those defined in `new`. An
+/// element which was moved to a different file is reported as removed.
+pub fn check_files(old: &FileDescriptor, new: &FileDescriptor) -> Vec<BreakingChange> {
+    let mut checker = Checker::default();
+
+    let mut new_messages = HashMap::new();
+    let mut new_enums = HashMap::new();
+    collect_types(
+        new.messages(),
+        new.enums(),
+        &mut new_messages,
+        &mut new_enums,
+    );
+
+    let mut old_messages = HashMap::new();
+    let mut old_enums = HashMap::new();
+    collect_types(
+        old.messages(),
+        old.enums(),
+        &mut old_messages,
+        &mut old_enums,
+    );
+
+    let mut old_messages: Vec<_> = old_messages.into_values().collect();
+    old_messages.sort_by(|l, r| l.full_name().cmp(r.full_name()));
+    for old_message in old_messages {
+        match new_messages.get(old_message.full_name()) {
+            Some(new_message) => checker.message(&old_message, new_message),
+            None => checker.message_removed(&old_message),
+        }
+    }
+
+    let mut old_enums: Vec<_> = old_enums.into_values().collect();
+    old_enums.sort_by(|l, r| l.full_name().cmp(r.full_name()));
+    for old_enum in old_enums {
+        match new_enums.get(old_enum.full_name()) {
+            Some(new_enum) => checker.enum_(&old_enum, new_enum),
+            None => checker.enum_removed(&old_enum),
+        }
+    }
+
+    for old_service in old.services() {
+        let new_service = new
+            .services()
+            .find(|service| service.full_name() == old_service.full_name());
+        checker.service(&old_service, new_service.as_ref());
+    }
+
+    checker.changes
+}
+
+/// Checks for breaking changes between two versions of a message type, including its nested message and
+/// enum types.
+///
+/// The messages do not need to have the same name.
+pub fn check_messages(old: &MessageDescriptor, new: &MessageDescriptor) -> Vec<BreakingChange> {
+    let mut checker = Checker::default();
+    checker.message_recursive(old, new);
+    checker.changes
+}
+
+impl BreakingChange {
+    /// The kind of this change.
+    pub fn kind(&self) -> ChangeKind {
+        self.kind
+    }
+
+    /// The full name of the element which changed, in the old schema.
+    pub fn element(&self) -> &str {
+        &self.element
+    }
+
+    /// A description of the change.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns `true` if data in the binary encoding can still be exchanged between the old and new schemas
+    /// without being misinterpreted, although the JSON mapping or generated code may be broken.
+    ///
+    /// For example, this is `true` for renamed fields and for type changes between `int32` and `int64`,
+    /// but `false` for type changes between `int32` and `string`.
+    pub fn is_wire_compatible(&self) -> bool {
+        self.wire_compatible
+    }
+}
+
+impl fmt::Display for BreakingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.element, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Checker {
+    changes: Vec<BreakingChange>,
+}
+
+impl Checker {
+    fn report(
+        &mut self,
+        kind: ChangeKind,
+        element: &str,
+        message: impl Into<String>,
+        wire_compatible: bool,
+    ) {
+        self.changes.push(BreakingChange {
+            kind,
+            element: element.to_owned(),
+            message: message.into(),
+            wire_compatible,
+        });
+    }
+
+    fn message_removed(&mut self, old: &MessageDescriptor) {
+        // Map entry types are removed along with their field, which is reported instead.
+        if !old.is_map_entry() {
+            self.report(
+                ChangeKind::MessageRemoved,
+                old.full_name(),
+                "message was removed",
+                true,
+            );
+        }
+    }
+
+    fn enum_removed(&mut self, old: &EnumDescriptor) {
+        self.report(
+            ChangeKind::EnumRemoved,
+            old.full_name(),
+            "enum was removed",
+            true,
+        );
+    }
+
+    fn message_recursive(&mut self, old: &MessageDescriptor, new: &MessageDescriptor) {
+        self.message(old, new);
+
+        for old_message in old.child_messages() {
+            match new
+                .child_messages()
+                .find(|message| message.name() == old_message.name())
+            {
+                Some(new_message) => self.message_recursive(&old_message, &new_message),
+                None => self.message_removed(&old_message),
+            }
+        }
+        for old_enum in old.child_enums() {
+            match new
+                .child_enums()
+                .find(|enum_desc| enum_desc.name() == old_enum.name())
+            {
+                Some(new_enum) => self.enum_(&old_enum, &new_enum),
+                None => self.enum_removed(&old_enum),
+            }
+        }
+    }
+
+    fn message(&mut self, old: &MessageDescriptor, new: &MessageDescriptor) {
+        for old_field in old.fields() {
+            match new.get_field(old_field.number()) {
+                Some(new_field) => self.field(&old_field, &new_field),
+                None => match new.get_field_by_name(old_field.name()) {
+                    Some(new_field) => self.report(
+                        ChangeKind::FieldNumberChanged,
+                        old_field.full_name(),
+                        format!(
+                            "number changed from {} to {}",
+                            old_field.number(),
+                            new_field.number()
+                        ),
+                        false,
+                    ),
+                    None => self.report(
+                        ChangeKind::FieldRemoved,
+                        old_field.full_name(),
+                        format!("field {} was removed", old_field.number()),
+                        true,
+                    ),
+                },
+            }
+        }
+    }
+
+    fn field(&mut self, old: &FieldDescriptor, new: &FieldDescriptor) {
+        if old.name() != new.name() {
+            self.report(
+                ChangeKind::FieldNameChanged,
+                old.full_name(),
+                format!("name changed from '{}' to '{}'", old.name(), new.name()),
+                true,
+            );
+        }
+
+        let old_kind = old.kind();
+        let new_kind = new.kind();
+        if type_name(&old_kind) != type_name(&new_kind) || old.is_group() != new.is_group() {
+            let wire_compatible = old.is_group() == new.is_group()
+                && old_kind.as_message().is_none()
+                && wire_group(&old_kind) == wire_group(&new_kind);
+            self.report(
+                ChangeKind::FieldTypeChanged,
+                old.full_name(),
+                format!(
+                    "type changed from {} to {}",
+                    describe_type(old),
+                    describe_type(new)
+                ),
+                wire_compatible,
+            );
+        }
+
+        if old.cardinality() != new.cardinality() {
+            let wire_compatible = old.cardinality() != Cardinality::Required
+                && new.cardinality() != Cardinality::Required
+                && matches!(new_kind, Kind::String | Kind::Bytes | Kind::Message(_));
+            self.report(
+                ChangeKind::FieldCardinalityChanged,
+                old.full_name(),
+                format!(
+                    "cardinality changed from {} to {}",
+                    cardinality_name(old.cardinality()),
+                    cardinality_name(new.cardinality())
+                ),
+                wire_compatible,
+            );
+        }
+
+        let old_oneof = old.containing_oneof().filter(|_| !is_proto3_optional(old));
+        let new_oneof = new.containing_oneof().filter(|_| !is_proto3_optional(new));
+        let old_oneof_name = old_oneof.as_ref().map(|oneof| oneof.name());
+        let new_oneof_name = new_oneof.as_ref().map(|oneof| oneof.name());
+        if old_oneof_name != new_oneof_name {
+            let message = match (old_oneof_name, new_oneof_name) {
+                (Some(old), Some(new)) => format!("moved from oneof '{}' to '{}'", old, new),
+                (Some(old), None) => format!("moved out of oneof '{}'", old),
+                (None, Some(new)) => format!("moved into oneof '{}'", new),
+                (None, None) => unreachable!(),
+            };
+            self.report(
+                ChangeKind::FieldOneofChanged,
+                old.full_name(),
+                message,
+                false,
+            );
+        }
+    }
+
+    fn enum_(&mut self, old: &EnumDescriptor, new: &EnumDescriptor) {
+        for old_value in old.values() {
+            match new.get_value(old_value.number()) {
+                Some(new_value) => {
+                    if old_value.name() != new_value.name() {
+                        self.report(
+                            ChangeKind::EnumValueNameChanged,
+                            old_value.full_name(),
+                            format!(
+                                "name changed from '{}' to '{}'",
+                                old_value.name(),
+                                new_value.name()
+                            ),
+                            true,
+                        );
+                    }
+                }
+                None => self.report(
+                    ChangeKind::EnumValueRemoved,
+                    old_value.full_name(),
+                    format!("enum value {} was removed", old_value.number()),
+                    true,
+                ),
+            }
+        }
+    }
+
+    fn service(&mut self, old: &ServiceDescriptor, new: Option<&ServiceDescriptor>) {
+        let new = match new {
+            Some(new) => new,
+            None => {
+                self.report(
+                    ChangeKind::ServiceRemoved,
+                    old.full_name(),
+                    "service was removed",
+                    false,
+                );
+                return;
+            }
+        };
+
+        for old_method in old.methods() {
+            let new_method = match new
+                .methods()
+                .find(|method| method.name() == old_method.name())
+            {
+                Some(new_method) => new_method,
+                None => {
+                    self.report(
+                        ChangeKind::MethodRemoved,
+                        old_method.full_name(),
+                        "method was removed",
+                        false,
+                    );
+                    continue;
+                }
+            };
+
+            for (direction, old_type, new_type) in [
+                ("request", old_method.input(), new_method.input()),
+                ("response", old_method.output(), new_method.output()),
+            ] {
+                if old_type.full_name() != new_type.full_name() {
+                    self.report(
+                        ChangeKind::MethodTypeChanged,
+                        old_method.full_name(),
+                        format!(
+                            "{} type changed from {} to {}",
+                            direction,
+                            old_type.full_name(),
+                            new_type.full_name()
+                        ),
+                        false,
+                    );
+                }
+            }
+
+            if old_method.is_client_streaming() != new_method.is_client_streaming()
+                || old_method.is_server_streaming() != new_method.is_server_streaming()
+            {
+                self.report(
+                    ChangeKind::MethodStreamingChanged,
+                    old_method.full_name(),
+                    format!(
+                        "changed from {} to {}",
+                        streaming_name(
+                            old_method.is_client_streaming(),
+                            old_method.is_server_streaming()
+                        ),
+                        streaming_name(
+                            new_method.is_client_streaming(),
+                            new_method.is_server_streaming()
+                        )
+                    ),
+                    false,
+                );
+            }
+        }
+    }
+}
+
+fn collect_types(
+    messages: impl Iterator<Item = MessageDescriptor>,
+    enums: impl Iterator<Item = EnumDescriptor>,
+    message_map: &mut HashMap<String, MessageDescriptor>,
+    enum_map: &mut HashMap<String, EnumDescriptor>,
+) {
+    for enum_desc in enums {
+        enum_map.insert(enum_desc.full_name().to_owned(), enum_desc);
+    }
+    for message in messages {
+        let child_messages: Vec<_> = message.child_messages().collect();
+        let child_enums: Vec<_> = message.child_enums().collect();
+        collect_types(
+            child_messages.into_iter(),
+            child_enums.into_iter(),
+            message_map,
+            enum_map,
+        );
+        message_map.insert(message.full_name().to_owned(), message);
+    }
+}
+
+fn is_proto3_optional(field: &FieldDescriptor) -> bool {
+    field.field_descriptor_proto().proto3_optional()
+}
+
+fn type_name(kind: &Kind) -> String {
+    format!("{:?}", kind)
+}
+
+fn describe_type(field: &FieldDescriptor) -> String {
+    if field.is_group() {
+        format!("group {:?}", field.kind())
+    } else {
+        type_name(&field.kind())
+    }
+}
+
+/// Groups types whose values have the same encoding, so can be decoded as each other, although values may
+/// be truncated or reinterpreted.
+fn wire_group(kind: &Kind) -> u8 {
+    match kind {
+        Kind::Int32 | Kind::Int64 | Kind::Uint32 | Kind::Uint64 | Kind::Bool | Kind::Enum(_) => 0,
+        Kind::Sint32 | Kind::Sint64 => 1,
+        Kind::Fixed32 | Kind::Sfixed32 => 2,
+        Kind::Fixed64 | Kind::Sfixed64 => 3,
+        Kind::String | Kind::Bytes => 4,
+        Kind::Float => 5,
+        Kind::Double => 6,
+        Kind::Message(_) => 7,
+    }
+}
+
+fn cardinality_name(cardinality: Cardinality) -> &'static str {
+    match cardinality {
+        Cardinality::Optional => "optional",
+        Cardinality::Required => "required",
+        Cardinality::Repeated => "repeated",
+    }
+}
+
+fn streaming_name(client_streaming: bool, server_streaming: bool) -> &'static str {
+    match (client_streaming, server_streaming) {
+        (false, false) => "unary",
+        (true, false) => "client streaming",
+        (false, true) => "server streaming",
+        (true, true) => "bidirectional streaming",
+    }
+}
@@ -0,0 +1,697 @@
+use std::{collections::HashSet, ops::Range};
+
+use prost_types::{
+    descriptor_proto::{ExtensionRange, ReservedRange},
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto,
+    FieldDescriptorProto, FieldOptions, FileDescriptorProto, MessageOptions, MethodDescriptorProto,
+    OneofDescriptorProto, ServiceDescriptorProto,
+};
+
+use super::compile::{scalar_type, to_camel_case, well_known_type, Names};
+use crate::{DescriptorError, DescriptorPool, FileDescriptor, Syntax};
+
+const MAX_FIELD_NUMBER: i32 = 536_870_911;
+const RESERVED_FIELD_NUMBERS: Range<i32> = 19_000..20_000;
+
+/// A builder for constructing a file descriptor in code, without writing a [`FileDescriptorProto`] by hand.
+///
+/// Types are referred to by name, as they would be in a `.proto` file. Scalar types are named by their
+/// protobuf keyword, such as `int32` or `string`. Other names are resolved relative to the scope they
+/// are used in, and may refer to types defined in the file itself or in any file in the pool it is
+/// added to. A name starting with `.` is fully-qualified.
+///
+/// The file is validated when it is finalized with [`build`](FileDescriptorBuilder::build) or
+/// [`add_to_pool`](FileDescriptorBuilder::add_to_pool).
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{FieldBuilder, FileDescriptorBuilder, MessageBuilder, Kind};
+/// let pool = FileDescriptorBuilder::new("person.proto")
+///     .package("example")
+///     .add_message(
+///         MessageBuilder::new("Person")
+///             .add_field(FieldBuilder::new("name", 1, "string"))
+///             .add_field(FieldBuilder::new("friends", 2, "Person").repeated())
+///             .add_field(FieldBuilder::map("attributes", 3, "string", "string")),
+///     )
+///     .build()
+///     .unwrap();
+///
+/// let person = pool.get_message_by_name("example.Person").unwrap();
+/// let friends = person.get_field_by_name("friends").unwrap();
+/// assert!(friends.is_list());
+/// assert_eq!(friends.kind(), Kind::Message(person.clone()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileDescriptorBuilder {
+    name: String,
+    package: Option<String>,
+    syntax: Syntax,
+    dependencies: Vec<String>,
+    messages: Vec<MessageBuilder>,
+    enums: Vec<EnumBuilder>,
+    services: Vec<ServiceBuilder>,
+    extensions: Vec<(String, FieldBuilder)>,
+}
+
+/// A builder for a message type, used with [`FileDescriptorBuilder`].
+#[derive(Debug, Clone)]
+pub struct MessageBuilder {
+    name: String,
+    fields: Vec<FieldBuilder>,
+    messages: Vec<MessageBuilder>,
+    enums: Vec<EnumBuilder>,
+    reserved_ranges: Vec<Range<i32>>,
+    reserved_names: Vec<String>,
+    extension_ranges: Vec<Range<i32>>,
+}
+
+/// A builder for a field of a message, or an extension field, used with [`MessageBuilder`].
+#[derive(Debug, Clone)]
+pub struct FieldBuilder {
+    name: String,
+    number: i32,
+    ty: FieldType,
+    label: Option<Label>,
+    oneof: Option<String>,
+    json_name: Option<String>,
+    default_value: Option<String>,
+    packed: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+enum FieldType {
+    Named(String),
+    Map { key: String, value: String },
+}
+
+/// A builder for an enum type, used with [`FileDescriptorBuilder`] and [`MessageBuilder`].
+#[derive(Debug, Clone)]
+pub struct EnumBuilder {
+    name: String,
+    values: Vec<(String, i32)>,
+    allow_alias: bool,
+}
+
+/// A builder for a service, used with [`FileDescriptorBuilder`].
+#[derive(Debug, Clone)]
+pub struct ServiceBuilder {
+    name: String,
+    methods: Vec<MethodBuilder>,
+}
+
+/// A builder for a method of a service, used with [`ServiceBuilder`].
+#[derive(Debug, Clone)]
+pub struct MethodBuilder {
+    name: String,
+    input: String,
+    output: String,
+    client_streaming: bool,
+    server_streaming: bool,
+}
+
+impl FileDescriptorBuilder {
+    /// Creates a new builder for a `proto3` file with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        FileDescriptorBuilder {
+            name: name.into(),
+            package: None,
+            syntax: Syntax::Proto3,
+            dependencies: Vec::new(),
+            messages: Vec::new(),
+            enums: Vec::new(),
+            services: Vec::new(),
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Sets the package of the file.
+    pub fn package(mut self, package: impl Into<String>) -> Self {
+        self.package = Some(package.into());
+        self
+    }
+
+    /// Sets the syntax of the file. The default is [`Syntax::Proto3`].
+    pub const fn syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Adds an import of the file with the given name.
+    ///
+    /// When the file is finalized, imported files must already be in the pool, unless they define
+    /// well-known types and the `reflect-well-known-types` feature is enabled, in which case built-in
+    /// copies are added.
+    pub fn add_dependency(mut self, name: impl Into<String>) -> Self {
+        self.dependencies.push(name.into());
+        self
+    }
+
+    /// Adds a message type to the file.
+    pub fn add_message(mut self, message: MessageBuilder) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    /// Adds an enum type to the file.
+    pub fn add_enum(mut self, enum_: EnumBuilder) -> Self {
+        self.enums.push(enum_);
+        self
+    }
+
+    /// Adds a service to the file.
+    pub fn add_service(mut self, service: ServiceBuilder) -> Self {
+        self.services.push(service);
+        self
+    }
+
+    /// Adds an extension of the message type named `extendee` to the file.
+    pub fn add_extension(mut self, extendee: impl Into<String>, field: FieldBuilder) -> Self {
+        self.extensions.push((extendee.into(), field));
+        self
+    }
+
+    /// Validates the file and creates a new [`DescriptorPool`] containing it.
+    pub fn build(self) -> Result<DescriptorPool, DescriptorError> {
+        let mut pool = DescriptorPool::new();
+        self.add_to_pool(&mut pool)?;
+        Ok(pool)
+    }
+
+    /// Validates the file and adds it to `pool`, returning its descriptor.
+    ///
+    /// If the file is invalid, an error is returned and the pool is not modified.
+    pub fn add_to_pool(self, pool: &mut DescriptorPool) -> Result<FileDescriptor, DescriptorError> {
+        let name = self.name.clone();
+        let file = self.into_file_descriptor_proto()?;
+
+        let mut files = Vec::new();
+        let mut seen = HashSet::new();
+        let mut pending = file.dependency.clone();
+        while let Some(dependency) = pending.pop() {
+            if pool.get_file_by_name(&dependency).is_some() || !seen.insert(dependency.clone()) {
+                continue;
+            }
+            match well_known_type(&dependency) {
+                Some(dependency) => {
+                    pending.extend(dependency.dependency.iter().cloned());
+                    files.push(dependency);
+                }
+                None => return Err(DescriptorError::file_not_found(&name, dependency)),
+            }
+        }
+        files.push(file);
+
+        let names = Names::new(pool, &files);
+        for file in &mut files {
+            names.resolve_file(file);
+        }
+        pool.add_file_descriptor_protos(files)?;
+        Ok(pool
+            .get_file_by_name(&name)
+            .expect("file was added to the pool"))
+    }
+
+    fn into_file_descriptor_proto(self) -> Result<FileDescriptorProto, DescriptorError> {
+        let syntax = self.syntax;
+        let package = self.package.clone().unwrap_or_default();
+
+        let mut file = FileDescriptorProto {
+            name: Some(self.name),
+            package: self.package,
+            dependency: self.dependencies,
+            syntax: match syntax {
+                Syntax::Proto2 => None,
+                Syntax::Proto3 => Some("proto3".to_owned()),
+            },
+            ..Default::default()
+        };
+        for message in self.messages {
+            file.message_type
+                .push(message.into_descriptor_proto(&package, syntax)?);
+        }
+        file.enum_type = self
+            .enums
+            .into_iter()
+            .map(EnumBuilder::into_descriptor_proto)
+            .collect();
+        file.service = self
+            .services
+            .into_iter()
+            .map(ServiceBuilder::into_descriptor_proto)
+            .collect();
+        for (extendee, field) in self.extensions {
+            let mut field = field.into_descriptor_proto(&package, syntax, &mut Vec::new())?;
+            field.extendee = Some(extendee);
+            file.extension.push(field);
+        }
+        Ok(file)
+    }
+}
+
+impl MessageBuilder {
+    /// Creates a new builder for a message type with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        MessageBuilder {
+            name: name.into(),
+            fields: Vec::new(),
+            messages: Vec::new(),
+            enums: Vec::new(),
+            reserved_ranges: Vec::new(),
+            reserved_names: Vec::new(),
+            extension_ranges: Vec::new(),
+        }
+    }
+
+    /// Adds a field to the message.
+    pub fn add_field(mut self, field: FieldBuilder) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Adds a nested message type to the message.
+    pub fn add_message(mut self, message: MessageBuilder) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    /// Adds a nested enum type to the message.
+    pub fn add_enum(mut self, enum_: EnumBuilder) -> Self {
+        self.enums.push(enum_);
+        self
+    }
+
+    /// Reserves a range of field numbers, which may not be used by fields. The range is exclusive.
+    pub fn add_reserved_range(mut self, range: Range<i32>) -> Self {
+        self.reserved_ranges.push(range);
+        self
+    }
+
+    /// Reserves a field name, which may not be used by fields.
+    pub fn add_reserved_name(mut self, name: impl Into<String>) -> Self {
+        self.reserved_names.push(name.into());
+        self
+    }
+
+    /// Declares a range of field numbers as available for extensions. The range is exclusive.
+    pub fn add_extension_range(mut self, range: Range<i32>) -> Self {
+        self.extension_ranges.push(range);
+        self
+    }
+
+    fn into_descriptor_proto(
+        self,
+        namespace: &str,
+        syntax: Syntax,
+    ) -> Result<DescriptorProto, DescriptorError> {
+        let full_name = if namespace.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", namespace, self.name)
+        };
+
+        let mut message = DescriptorProto {
+            name: Some(self.name),
+            reserved_range: self
+                .reserved_ranges
+                .iter()
+                .map(|range| ReservedRange {
+                    start: Some(range.start),
+                    end: Some(range.end),
+                })
+                .collect(),
+            reserved_name: self.reserved_names,
+            extension_range: self
+                .extension_ranges
+                .iter()
+                .map(|range| ExtensionRange {
+                    start: Some(range.start),
+                    end: Some(range.end),
+                    options: None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let mut numbers = HashSet::new();
+        let mut synthetic_oneofs = Vec::new();
+        for field in self.fields {
+            if field.number < 1
+                || field.number > MAX_FIELD_NUMBER
+                || RESERVED_FIELD_NUMBERS.contains(&field.number)
+            {
+                return Err(DescriptorError::invalid_field_number(
+                    &full_name,
+                    &field.name,
+                    field.number,
+                ));
+            }
+            if !numbers.insert(field.number) {
+                return Err(DescriptorError::duplicate_field_number(
+                    &full_name,
+                    field.number,
+                ));
+            }
+
+            let oneof = field.oneof.clone();
+            let mut field_proto =
+                field.into_descriptor_proto(&full_name, syntax, &mut message.nested_type)?;
+            if let Some(oneof) = oneof {
+                let index = match message
+                    .oneof_decl
+                    .iter()
+                    .position(|decl| decl.name() == oneof)
+                {
+                    Some(index) => index,
+                    None => {
+                        message.oneof_decl.push(OneofDescriptorProto {
+                            name: Some(oneof),
+                            options: None,
+                        });
+                        message.oneof_decl.len() - 1
+                    }
+                };
+                field_proto.oneof_index = Some(index as i32);
+            } else if field_proto.proto3_optional == Some(true) {
+                synthetic_oneofs.push(message.field.len());
+            }
+            message.field.push(field_proto);
+        }
+
+        // Synthetic oneofs for proto3 optional fields are placed after all real oneofs.
+        for index in synthetic_oneofs {
+            let field = &mut message.field[index];
+            field.oneof_index = Some(message.oneof_decl.len() as i32);
+            message.oneof_decl.push(OneofDescriptorProto {
+                name: Some(format!("_{}", field.name())),
+                options: None,
+            });
+        }
+
+        for nested in self.messages {
+            message
+                .nested_type
+                .push(nested.into_descriptor_proto(&full_name, syntax)?);
+        }
+        message.enum_type = self
+            .enums
+            .into_iter()
+            .map(EnumBuilder::into_descriptor_proto)
+            .collect();
+        Ok(message)
+    }
+}
+
+impl FieldBuilder {
+    /// Creates a new builder for a singular field with the given name, number and type.
+    ///
+    /// The type may be the name of a scalar type such as `int32`, or of a message or enum type.
+    pub fn new(name: impl Into<String>, number: i32, ty: impl Into<String>) -> Self {
+        FieldBuilder::with_type(name.into(), number, FieldType::Named(ty.into()))
+    }
+
+    /// Creates a new builder for a map field with the given name, number, and key and value types.
+    ///
+    /// The key type must be an integral or string type. The map entry message is generated
+    /// automatically.
+    pub fn map(
+        name: impl Into<String>,
+        number: i32,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        FieldBuilder::with_type(
+            name.into(),
+            number,
+            FieldType::Map {
+                key: key.into(),
+                value: value.into(),
+            },
+        )
+    }
+
+    fn with_type(name: String, number: i32, ty: FieldType) -> Self {
+        FieldBuilder {
+            name,
+            number,
+            ty,
+            label: None,
+            oneof: None,
+            json_name: None,
+            default_value: None,
+            packed: None,
+        }
+    }
+
+    /// Marks the field as repeated.
+    pub const fn repeated(mut self) -> Self {
+        self.label = Some(Label::Repeated);
+        self
+    }
+
+    /// Marks the field as optional.
+    ///
+    /// In `proto3` files, this makes the field track presence. In `proto2` files, fields are optional by
+    /// default.
+    pub const fn optional(mut self) -> Self {
+        self.label = Some(Label::Optional);
+        self
+    }
+
+    /// Marks the field as required. Required fields are only allowed in `proto2` files.
+    pub const fn required(mut self) -> Self {
+        self.label = Some(Label::Required);
+        self
+    }
+
+    /// Places the field in the oneof with the given name, which is created if it does not exist.
+    pub fn oneof(mut self, name: impl Into<String>) -> Self {
+        self.oneof = Some(name.into());
+        self
+    }
+
+    /// Sets the JSON name of the field. By default, it is the field name converted to lower camel case.
+    pub fn json_name(mut self, json_name: impl Into<String>) -> Self {
+        self.json_name = Some(json_name.into());
+        self
+    }
+
+    /// Sets the default value of the field, in the same format as
+    /// [`FieldDescriptorProto::default_value`]. Default values are only allowed in `proto2` files.
+    pub fn default_value(mut self, default_value: impl Into<String>) -> Self {
+        self.default_value = Some(default_value.into());
+        self
+    }
+
+    /// Sets whether a repeated scalar field uses the packed encoding.
+    pub const fn packed(mut self, packed: bool) -> Self {
+        self.packed = Some(packed);
+        self
+    }
+
+    fn into_descriptor_proto(
+        self,
+        scope: &str,
+        syntax: Syntax,
+        nested_types: &mut Vec<DescriptorProto>,
+    ) -> Result<FieldDescriptorProto, DescriptorError> {
+        let json_name = match self.json_name {
+            Some(json_name) => json_name,
+            None => to_camel_case(&self.name, false),
+        };
+        let mut field = FieldDescriptorProto {
+            number: Some(self.number),
+            label: Some(self.label.unwrap_or(Label::Optional) as i32),
+            json_name: Some(json_name),
+            default_value: self.default_value,
+            options: self.packed.map(|packed| FieldOptions {
+                packed: Some(packed),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        match self.ty {
+            FieldType::Named(ty) => set_type(&mut field, ty),
+            FieldType::Map { key, value } => {
+                if self.label.is_some() || self.oneof.is_some() {
+                    return Err(DescriptorError::invalid_field(
+                        format!("{}.{}", scope, self.name),
+                        "map fields cannot have a label or be part of a oneof",
+                    ));
+                }
+                let entry_name = format!("{}Entry", to_camel_case(&self.name, true));
+                let mut key_field = FieldDescriptorProto {
+                    name: Some("key".to_owned()),
+                    number: Some(1),
+                    label: Some(Label::Optional as i32),
+                    json_name: Some("key".to_owned()),
+                    ..Default::default()
+                };
+                set_type(&mut key_field, key);
+                let mut value_field = FieldDescriptorProto {
+                    name: Some("value".to_owned()),
+                    number: Some(2),
+                    label: Some(Label::Optional as i32),
+                    json_name: Some("value".to_owned()),
+                    ..Default::default()
+                };
+                set_type(&mut value_field, value);
+
+                nested_types.push(DescriptorProto {
+                    name: Some(entry_name.clone()),
+                    field: vec![key_field, value_field],
+                    options: Some(MessageOptions {
+                        map_entry: Some(true),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                });
+                field.label = Some(Label::Repeated as i32);
+                field.r#type = Some(Type::Message as i32);
+                field.type_name = Some(entry_name);
+            }
+        }
+
+        if syntax == Syntax::Proto3 {
+            if self.label == Some(Label::Required) {
+                return Err(DescriptorError::invalid_field(
+                    format!("{}.{}", scope, self.name),
+                    "required fields are not allowed in proto3",
+                ));
+            }
+            if field.default_value.is_some() {
+                return Err(DescriptorError::invalid_field(
+                    format!("{}.{}", scope, self.name),
+                    "default values are not allowed in proto3",
+                ));
+            }
+            if self.label == Some(Label::Optional) && self.oneof.is_none() {
+                field.proto3_optional = Some(true);
+            }
+        }
+
+        field.name = Some(self.name);
+        Ok(field)
+    }
+}
+
+fn set_type(field: &mut FieldDescriptorProto, ty: String) {
+    match scalar_type(&ty) {
+        Some(ty) => field.r#type = Some(ty as i32),
+        None => field.type_name = Some(ty),
+    }
+}
+
+impl EnumBuilder {
+    /// Creates a new builder for an enum type with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        EnumBuilder {
+            name: name.into(),
+            values: Vec::new(),
+            allow_alias: false,
+        }
+    }
+
+    /// Adds a value to the enum. In `proto3` files, the first value must have the number zero.
+    pub fn add_value(mut self, name: impl Into<String>, number: i32) -> Self {
+        self.values.push((name.into(), number));
+        self
+    }
+
+    /// Sets whether multiple values may have the same number.
+    pub const fn allow_alias(mut self, allow_alias: bool) -> Self {
+        self.allow_alias = allow_alias;
+        self
+    }
+
+    fn into_descriptor_proto(self) -> EnumDescriptorProto {
+        EnumDescriptorProto {
+            name: Some(self.name),
+            value: self
+                .values
+                .into_iter()
+                .map(|(name, number)| EnumValueDescriptorProto {
+                    name: Some(name),
+                    number: Some(number),
+                    options: None,
+                })
+                .collect(),
+            options: if self.allow_alias {
+                Some(EnumOptions {
+                    allow_alias: Some(true),
+                    ..Default::default()
+                })
+            } else {
+                None
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl ServiceBuilder {
+    /// Creates a new builder for a service with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        ServiceBuilder {
+            name: name.into(),
+            methods: Vec::new(),
+        }
+    }
+
+    /// Adds a method to the service.
+    pub fn add_method(mut self, method: MethodBuilder) -> Self {
+        self.methods.push(method);
+        self
+    }
+
+    fn into_descriptor_proto(self) -> ServiceDescriptorProto {
+        ServiceDescriptorProto {
+            name: Some(self.name),
+            method: self
+                .methods
+                .into_iter()
+                .map(|method| MethodDescriptorProto {
+                    name: Some(method.name),
+                    input_type: Some(method.input),
+                    output_type: Some(method.output),
+                    options: None,
+                    client_streaming: Some(method.client_streaming).filter(|&streaming| streaming),
+                    server_streaming: Some(method.server_streaming).filter(|&streaming| streaming),
+                })
+                .collect(),
+            options: None,
+        }
+    }
+}
+
+impl MethodBuilder {
+    /// Creates a new builder for a unary method with the given name, and input and output message types.
+    pub fn new(
+        name: impl Into<String>,
+        input: impl Into<String>,
+        output: impl Into<String>,
+    ) -> Self {
+        MethodBuilder {
+            name: name.into(),
+            input: input.into(),
+            output: output.into(),
+            client_streaming: false,
+            server_streaming: false,
+        }
+    }
+
+    /// Sets whether the client sends a stream of messages.
+    pub const fn client_streaming(mut self, client_streaming: bool) -> Self {
+        self.client_streaming = client_streaming;
+        self
+    }
+
+    /// Sets whether the server sends a stream of messages.
+    pub const fn server_streaming(mut self, server_streaming: bool) -> Self {
+        self.server_streaming = server_streaming;
+        self
+    }
+}
@@ -0,0 +1,127 @@
+use std::{collections::HashSet, iter};
+
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+
+use crate::{DescriptorError, DescriptorPool, FileDescriptor, MessageDescriptor};
+
+impl DescriptorPool {
+    /// Creates a new [`DescriptorPool`] containing only the given files and the files they depend on.
+    ///
+    /// This can be used to reduce the size of an encoded pool when only a few types are needed, for example
+    /// by passing the [parent files][crate::MessageDescriptor::parent_file] of the message types used by a
+    /// service.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the files are not from this pool.
+    pub fn prune<I>(&self, files: I) -> DescriptorPool
+    where
+        I: IntoIterator<Item = FileDescriptor>,
+    {
+        let files = files.into_iter().inspect(|file| {
+            assert!(
+                file.parent_pool() == self,
+                "file '{}' is not from this pool",
+                file.name()
+            );
+        });
+
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_protos(dependency_closure(self, files))
+            .expect("files from an existing pool must be valid");
+        pool
+    }
+
+    /// Creates a [`FileDescriptorSet`] containing only the files which define the given symbols and the files
+    /// they depend on, with each file listed after its dependencies.
+    ///
+    /// Each symbol is the fully-qualified name of a message, enum, extension or service in this pool, for
+    /// example `my.package.MyMessage`. The result is self-contained, so it can be sent to another process to
+    /// describe the symbols, for example alongside `google.protobuf.Any` values or in a gRPC reflection
+    /// response.
+    ///
+    /// Returns an error if any symbol is not found in this pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::DescriptorPool;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let file_descriptor_set = pool.file_descriptor_set_for(["package.MyMessage"]).unwrap();
+    /// assert_eq!(file_descriptor_set.file.len(), 1);
+    ///
+    /// assert!(pool.file_descriptor_set_for(["package.Missing"]).is_err());
+    /// ```
+    pub fn file_descriptor_set_for<I, S>(
+        &self,
+        symbols: I,
+    ) -> Result<FileDescriptorSet, DescriptorError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut files = Vec::new();
+        for symbol in symbols {
+            let symbol = symbol.as_ref();
+            let file = if let Some(message) = self.get_message_by_name(symbol) {
+                message.parent_file()
+            } else if let Some(enum_desc) = self.get_enum_by_name(symbol) {
+                enum_desc.parent_file()
+            } else if let Some(extension) = self.get_extension_by_name(symbol) {
+                extension.parent_file()
+            } else if let Some(service) = self.get_service_by_name(symbol) {
+                service.parent_file()
+            } else {
+                return Err(DescriptorError::symbol_not_found(symbol));
+            };
+            files.push(file);
+        }
+
+        Ok(FileDescriptorSet {
+            file: dependency_closure(self, files),
+        })
+    }
+}
+
+impl MessageDescriptor {
+    /// Creates a [`FileDescriptorSet`] containing only the file which defines this message and the files it
+    /// depends on, with each file listed after its dependencies.
+    ///
+    /// See [`DescriptorPool::file_descriptor_set_for`] for more details.
+    pub fn file_descriptor_set(&self) -> FileDescriptorSet {
+        FileDescriptorSet {
+            file: dependency_closure(self.parent_pool(), iter::once(self.parent_file())),
+        }
+    }
+}
+
+/// Collects the given files and their transitive dependencies, with dependencies before the files which
+/// import them.
+fn dependency_closure(
+    pool: &DescriptorPool,
+    files: impl IntoIterator<Item = FileDescriptor>,
+) -> Vec<FileDescriptorProto> {
+    let mut seen = HashSet::new();
+    let mut protos = Vec::new();
+    for file in files {
+        collect_files(pool, file.file_descriptor_proto(), &mut seen, &mut protos);
+    }
+    protos
+}
+
+/// Collects a file and its transitive dependencies, with dependencies before the files which import them.
+fn collect_files(
+    pool: &DescriptorPool,
+    file: &FileDescriptorProto,
+    seen: &mut HashSet<String>,
+    protos: &mut Vec<FileDescriptorProto>,
+) {
+    if !seen.insert(file.name().to_owned()) {
+        return;
+    }
+    for name in &file.dependency {
+        let dependency = pool.get_file_by_name(name).expect("dependency not found");
+        collect_files(pool, dependency.file_descriptor_proto(), seen, protos);
+    }
+    protos.push(file.clone());
+}
@@ -0,0 +1,190 @@
+mod parse;
+mod resolve;
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use prost_types::FileDescriptorProto;
+
+use crate::{DescriptorError, DescriptorPool};
+
+pub(super) use self::{
+    parse::{scalar_type, to_camel_case},
+    resolve::Names,
+};
+
+impl DescriptorPool {
+    /// Parses the given `.proto` source files, along with any files they import, and adds them to this pool.
+    ///
+    /// This allows a pool to be built directly from source files, without running the protobuf compiler.
+    /// Each file is named by its path relative to the include directory containing it, and imports are
+    /// looked up relative to each include directory in turn. Files which are already in the pool are not
+    /// parsed again. If `include_dirs` is empty, the current directory is used.
+    ///
+    /// When the `reflect-well-known-types` feature is enabled, imports of the well-known types such as
+    /// `google/protobuf/timestamp.proto` which are not found in any include directory are resolved using
+    /// built-in copies.
+    ///
+    /// Custom options are not interpreted, and are instead stored as
+    /// [`uninterpreted_option`](prost_types::FileOptions::uninterpreted_option)s. Source code info is not
+    /// generated.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use prost_reflect::DescriptorPool;
+    /// let mut pool = DescriptorPool::new();
+    /// pool.add_proto_files(&["protos/my_service.proto"], &["protos"]).unwrap();
+    /// ```
+    pub fn add_proto_files<I, P, J, D>(
+        &mut self,
+        files: I,
+        include_dirs: J,
+    ) -> Result<(), DescriptorError>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+        J: IntoIterator<Item = D>,
+        D: AsRef<Path>,
+    {
+        let mut include_dirs: Vec<PathBuf> = include_dirs
+            .into_iter()
+            .map(|dir| dir.as_ref().to_owned())
+            .collect();
+        if include_dirs.is_empty() {
+            include_dirs.push(PathBuf::from("."));
+        }
+
+        let mut loader = Loader {
+            pool: self,
+            include_dirs,
+            files: Vec::new(),
+            loaded: HashSet::new(),
+            stack: Vec::new(),
+        };
+        for path in files {
+            let name = loader.file_name(path.as_ref())?;
+            loader.load(&name, None)?;
+        }
+
+        let mut files = loader.files;
+        let names = Names::new(self, &files);
+        for file in &mut files {
+            names.resolve_file(file);
+        }
+        self.add_file_descriptor_protos(files)
+    }
+}
+
+struct Loader<'a> {
+    pool: &'a DescriptorPool,
+    include_dirs: Vec<PathBuf>,
+    /// Parsed files, ordered so that each file comes after its dependencies.
+    files: Vec<FileDescriptorProto>,
+    loaded: HashSet<String>,
+    /// The chain of imports currently being loaded, used to detect cycles.
+    stack: Vec<String>,
+}
+
+impl<'a> Loader<'a> {
+    /// Gets the name of a file given on the command line, relative to the include directory containing it.
+    fn file_name(&self, path: &Path) -> Result<String, DescriptorError> {
+        for dir in &self.include_dirs {
+            if let Ok(relative) = path.strip_prefix(dir) {
+                if let Some(name) = relative_name(relative) {
+                    return Ok(name);
+                }
+            }
+        }
+        if path.is_relative() {
+            if let Some(name) = relative_name(path) {
+                if self.include_dirs.iter().any(|dir| dir.join(path).is_file()) {
+                    return Ok(name);
+                }
+            }
+        }
+        Err(DescriptorError::file_not_in_include_dirs(path.display()))
+    }
+
+    fn load(&mut self, name: &str, required_by: Option<&str>) -> Result<(), DescriptorError> {
+        if self.loaded.contains(name) || self.pool.get_file_by_name(name).is_some() {
+            return Ok(());
+        }
+        if let Some(index) = self.stack.iter().position(|file| file == name) {
+            let mut cycle = self.stack[index..].to_vec();
+            cycle.push(name.to_owned());
+            return Err(DescriptorError::circular_import(cycle.join(" -> ")));
+        }
+
+        let file = match self.read(name)? {
+            Some(source) => parse::parse_file(name, &source).map_err(|err| {
+                DescriptorError::parse_file(name, err.line, err.column, err.message)
+            })?,
+            None => match well_known_type(name) {
+                Some(file) => file,
+                None => {
+                    return Err(match required_by {
+                        Some(required_by) => DescriptorError::file_not_found(required_by, name),
+                        None => DescriptorError::file_not_in_include_dirs(name),
+                    })
+                }
+            },
+        };
+
+        self.stack.push(name.to_owned());
+        for dependency in &file.dependency {
+            self.load(dependency, Some(name))?;
+        }
+        self.stack.pop();
+
+        self.loaded.insert(name.to_owned());
+        self.files.push(file);
+        Ok(())
+    }
+
+    fn read(&self, name: &str) -> Result<Option<String>, DescriptorError> {
+        for dir in &self.include_dirs {
+            let path = dir.join(name);
+            if path.is_file() {
+                return fs::read_to_string(&path)
+                    .map(Some)
+                    .map_err(|err| DescriptorError::read_file(path.display(), err));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Converts a relative path to a file name using `/` as the separator.
+fn relative_name(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
+}
+
+/// Gets a copy of the file defining some of the well-known types, if the `reflect-well-known-types`
+/// feature is enabled.
+#[cfg(feature = "reflect-well-known-types")]
+pub(super) fn well_known_type(name: &str) -> Option<FileDescriptorProto> {
+    crate::reflect::well_known_types()
+        .get_file_by_name(name)
+        .map(|file| file.file_descriptor_proto().clone())
+}
+
+#[cfg(not(feature = "reflect-well-known-types"))]
+pub(super) fn well_known_type(_: &str) -> Option<FileDescriptorProto> {
+    None
+}
@@ -0,0 +1,1374 @@
+use std::convert::TryFrom;
+
+use prost_types::{
+    descriptor_proto::{ExtensionRange, ReservedRange},
+    enum_descriptor_proto::EnumReservedRange,
+    field_descriptor_proto::{Label, Type},
+    uninterpreted_option::NamePart,
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto, EnumValueOptions,
+    ExtensionRangeOptions, FieldDescriptorProto, FieldOptions, FileDescriptorProto, FileOptions,
+    MessageOptions, MethodDescriptorProto, MethodOptions, OneofDescriptorProto, OneofOptions,
+    ServiceDescriptorProto, ServiceOptions, UninterpretedOption,
+};
+
+const MAX_FIELD_NUMBER: i32 = 536_870_911;
+
+/// An error encountered while parsing a source file, with a 1-based line and column.
+#[derive(Debug)]
+pub(super) struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(u64),
+    Float(f64),
+    String(Vec<u8>),
+    Punct(char),
+    Eof,
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<(Token, usize)>,
+    proto3: bool,
+}
+
+#[derive(Debug, Clone)]
+enum OptionValue {
+    Ident(String),
+    Int { negative: bool, value: u64 },
+    Float(f64),
+    String(Vec<u8>),
+    Aggregate(String),
+}
+
+/// A message type which can hold options, either directly or as uninterpreted options.
+trait Options: Default {
+    /// Sets the option with the given name, returning `false` if the option is not known.
+    fn set(&mut self, name: &str, value: &OptionValue) -> Result<bool, String>;
+
+    fn uninterpreted_option(&mut self) -> &mut Vec<UninterpretedOption>;
+}
+
+/// Parses the contents of a `.proto` file into a [`FileDescriptorProto`].
+///
+/// Type names are left as written in the source, and must be resolved before the file is added to a pool.
+pub(super) fn parse_file(name: &str, source: &str) -> Result<FileDescriptorProto, ParseError> {
+    let mut parser = Parser {
+        lexer: Lexer {
+            input: source,
+            pos: 0,
+        },
+        peeked: None,
+        proto3: false,
+    };
+    parser.file(name)
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(value) => format!("'{}'", value),
+            Token::Int(value) => format!("'{}'", value),
+            Token::Float(value) => format!("'{}'", value),
+            Token::String(_) => "a string".to_owned(),
+            Token::Punct(ch) => format!("'{}'", ch),
+            Token::Eof => "end of input".to_owned(),
+        }
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump_char(&mut self) -> Option<char> {
+        let ch = self.peek_char()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), (String, usize)> {
+        loop {
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("//") {
+                while !matches!(self.bump_char(), Some('\n') | None) {}
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(end) => self.pos += end + 4,
+                    None => return Err(("unterminated comment".to_owned(), self.pos)),
+                }
+            } else if matches!(self.peek_char(), Some(ch) if ch.is_whitespace()) {
+                self.bump_char();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<(Token, usize), (String, usize)> {
+        self.skip_whitespace()?;
+        let start = self.pos;
+        let ch = match self.peek_char() {
+            Some(ch) => ch,
+            None => return Ok((Token::Eof, start)),
+        };
+
+        let token = if ch.is_ascii_alphabetic() || ch == '_' {
+            while matches!(self.peek_char(), Some(ch) if ch.is_ascii_alphanumeric() || ch == '_') {
+                self.bump_char();
+            }
+            Token::Ident(self.input[start..self.pos].to_owned())
+        } else if ch.is_ascii_digit()
+            || (ch == '.'
+                && matches!(self.input[start + 1..].chars().next(), Some(ch) if ch.is_ascii_digit()))
+        {
+            self.number(start).map_err(|message| (message, start))?
+        } else if ch == '"' || ch == '\'' {
+            self.string(ch).map_err(|message| (message, start))?
+        } else if "=;,.{}[]()<>-+:/".contains(ch) {
+            self.bump_char();
+            Token::Punct(ch)
+        } else {
+            return Err((format!("unexpected character '{}'", ch), start));
+        };
+        Ok((token, start))
+    }
+
+    fn number(&mut self, start: usize) -> Result<Token, String> {
+        let is_hex = self.input[start..].starts_with("0x") || self.input[start..].starts_with("0X");
+        while let Some(ch) = self.peek_char() {
+            let prev = self.input[..self.pos].chars().next_back();
+            if ch.is_ascii_alphanumeric()
+                || ch == '.'
+                || (!is_hex && (ch == '+' || ch == '-') && matches!(prev, Some('e' | 'E')))
+            {
+                self.bump_char();
+            } else {
+                break;
+            }
+        }
+
+        let text = &self.input[start..self.pos];
+        let invalid = || format!("invalid number '{}'", text);
+        if let Some(digits) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            u64::from_str_radix(digits, 16)
+                .map(Token::Int)
+                .map_err(|_| invalid())
+        } else if text.contains(|ch| matches!(ch, '.' | 'e' | 'E')) {
+            text.parse().map(Token::Float).map_err(|_| invalid())
+        } else if text.len() > 1 && text.starts_with('0') {
+            u64::from_str_radix(&text[1..], 8)
+                .map(Token::Int)
+                .map_err(|_| invalid())
+        } else {
+            text.parse().map(Token::Int).map_err(|_| invalid())
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<Token, String> {
+        let invalid_escape = || "invalid escape sequence".to_owned();
+
+        self.bump_char();
+        let mut value = Vec::new();
+        loop {
+            match self.bump_char() {
+                None | Some('\n') => return Err("unterminated string".to_owned()),
+                Some(ch) if ch == quote => return Ok(Token::String(value)),
+                Some('\\') => {
+                    let byte = match self.bump_char() {
+                        Some('n') => b'\n',
+                        Some('r') => b'\r',
+                        Some('t') => b'\t',
+                        Some('a') => b'\x07',
+                        Some('b') => b'\x08',
+                        Some('f') => b'\x0c',
+                        Some('v') => b'\x0b',
+                        Some('?') => b'?',
+                        Some('\\') => b'\\',
+                        Some('\'') => b'\'',
+                        Some('"') => b'"',
+                        Some(ch @ '0'..='7') => {
+                            let mut byte = ch.to_digit(8).unwrap();
+                            for _ in 0..2 {
+                                match self.peek_char().and_then(|ch| ch.to_digit(8)) {
+                                    Some(digit) => {
+                                        self.bump_char();
+                                        byte = byte * 8 + digit;
+                                    }
+                                    None => break,
+                                }
+                            }
+                            u8::try_from(byte).map_err(|_| invalid_escape())?
+                        }
+                        Some('x' | 'X') => {
+                            let digits = self.hex_digits(2);
+                            u8::from_str_radix(digits, 16).map_err(|_| invalid_escape())?
+                        }
+                        Some(escape @ ('u' | 'U')) => {
+                            let len = if escape == 'u' { 4 } else { 8 };
+                            let digits = self.hex_digits(len);
+                            if digits.len() != len {
+                                return Err(invalid_escape());
+                            }
+                            let ch = u32::from_str_radix(digits, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(invalid_escape)?;
+                            let mut buf = [0; 4];
+                            value.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                            continue;
+                        }
+                        _ => return Err(invalid_escape()),
+                    };
+                    value.push(byte);
+                }
+                Some(ch) => {
+                    let mut buf = [0; 4];
+                    value.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+    }
+
+    fn hex_digits(&mut self, max_len: usize) -> &'a str {
+        let start = self.pos;
+        while self.pos - start < max_len
+            && matches!(self.peek_char(), Some(ch) if ch.is_ascii_hexdigit())
+        {
+            self.bump_char();
+        }
+        &self.input[start..self.pos]
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, pos: usize, message: impl Into<String>) -> ParseError {
+        let before = &self.lexer.input[..pos];
+        let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    fn unexpected(&self, token: &Token, pos: usize, expected: &str) -> ParseError {
+        self.error(
+            pos,
+            format!("expected {}, but found {}", expected, token.describe()),
+        )
+    }
+
+    fn peek(&mut self) -> Result<&Token, ParseError> {
+        if self.peeked.is_none() {
+            let next = self
+                .lexer
+                .next()
+                .map_err(|(message, pos)| self.error(pos, message))?;
+            self.peeked = Some(next);
+        }
+        Ok(&self.peeked.as_ref().unwrap().0)
+    }
+
+    fn peek_pos(&mut self) -> Result<usize, ParseError> {
+        self.peek()?;
+        Ok(self.peeked.as_ref().unwrap().1)
+    }
+
+    fn bump(&mut self) -> Result<(Token, usize), ParseError> {
+        self.peek()?;
+        Ok(self.peeked.take().unwrap())
+    }
+
+    fn peek_ident(&mut self, ident: &str) -> Result<bool, ParseError> {
+        Ok(matches!(self.peek()?, Token::Ident(value) if value == ident))
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> Result<bool, ParseError> {
+        if self.peek_ident(ident)? {
+            self.bump()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn eat_punct(&mut self, ch: char) -> Result<bool, ParseError> {
+        if self.peek()? == &Token::Punct(ch) {
+            self.bump()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect_punct(&mut self, ch: char) -> Result<(), ParseError> {
+        let (token, pos) = self.bump()?;
+        if token == Token::Punct(ch) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&token, pos, &format!("'{}'", ch)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        let (token, pos) = self.bump()?;
+        match token {
+            Token::Ident(value) if value == keyword => Ok(()),
+            token => Err(self.unexpected(&token, pos, &format!("'{}'", keyword))),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, ParseError> {
+        match self.bump()? {
+            (Token::Ident(value), _) => Ok(value),
+            (token, pos) => Err(self.unexpected(&token, pos, "an identifier")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let pos = self.peek_pos()?;
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes).map_err(|_| self.error(pos, "string is not valid UTF-8"))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, ParseError> {
+        match self.bump()? {
+            (Token::String(mut value), _) => {
+                // Adjacent string literals are concatenated.
+                while let Token::String(next) = self.peek()? {
+                    value.extend_from_slice(next);
+                    self.bump()?;
+                }
+                Ok(value)
+            }
+            (token, pos) => Err(self.unexpected(&token, pos, "a string")),
+        }
+    }
+
+    /// Parses a dot-separated name, such as a package name or a type name with an optional leading dot.
+    fn full_ident(&mut self, allow_leading_dot: bool) -> Result<String, ParseError> {
+        let mut name = String::new();
+        if allow_leading_dot && self.eat_punct('.')? {
+            name.push('.');
+        }
+        name.push_str(&self.ident()?);
+        while self.eat_punct('.')? {
+            name.push('.');
+            name.push_str(&self.ident()?);
+        }
+        Ok(name)
+    }
+
+    fn field_number(&mut self) -> Result<i32, ParseError> {
+        match self.bump()? {
+            (Token::Int(value), pos) => i32::try_from(value)
+                .ok()
+                .filter(|&number| number <= MAX_FIELD_NUMBER)
+                .ok_or_else(|| self.error(pos, "field number out of range")),
+            (token, pos) => Err(self.unexpected(&token, pos, "a field number")),
+        }
+    }
+
+    fn int32(&mut self) -> Result<i32, ParseError> {
+        let pos = self.peek_pos()?;
+        let negative = self.eat_punct('-')?;
+        match self.bump()? {
+            (Token::Int(value), _) => {
+                let value = if negative {
+                    -i64::try_from(value).unwrap_or(i64::MAX)
+                } else {
+                    i64::try_from(value).unwrap_or(i64::MAX)
+                };
+                i32::try_from(value).map_err(|_| self.error(pos, "integer out of range"))
+            }
+            (token, pos) => Err(self.unexpected(&token, pos, "an integer")),
+        }
+    }
+
+    fn file(&mut self, name: &str) -> Result<FileDescriptorProto, ParseError> {
+        let mut file = FileDescriptorProto {
+            name: Some(name.to_owned()),
+            ..Default::default()
+        };
+
+        if self.eat_ident("syntax")? {
+            self.expect_punct('=')?;
+            let pos = self.peek_pos()?;
+            match self.string()?.as_str() {
+                "proto2" => (),
+                "proto3" => {
+                    self.proto3 = true;
+                    file.syntax = Some("proto3".to_owned());
+                }
+                syntax => {
+                    return Err(self.error(pos, format!("unrecognized syntax '{}'", syntax)));
+                }
+            }
+            self.expect_punct(';')?;
+        }
+
+        loop {
+            let (token, pos) = self.bump()?;
+            match token {
+                Token::Eof => break,
+                Token::Punct(';') => (),
+                Token::Ident(keyword) => match keyword.as_str() {
+                    "package" => {
+                        if file.package.is_some() {
+                            return Err(self.error(pos, "multiple package definitions"));
+                        }
+                        file.package = Some(self.full_ident(false)?);
+                        self.expect_punct(';')?;
+                    }
+                    "import" => {
+                        if self.eat_ident("public")? {
+                            file.public_dependency.push(file.dependency.len() as i32);
+                        } else if self.eat_ident("weak")? {
+                            file.weak_dependency.push(file.dependency.len() as i32);
+                        }
+                        file.dependency.push(self.string()?);
+                        self.expect_punct(';')?;
+                    }
+                    "option" => self.option_statement(&mut file.options)?,
+                    "message" => file.message_type.push(self.message()?),
+                    "enum" => file.enum_type.push(self.enum_()?),
+                    "service" => file.service.push(self.service()?),
+                    "extend" => self.extend(&mut file.extension, &mut file.message_type)?,
+                    _ => {
+                        return Err(self.unexpected(
+                            &Token::Ident(keyword),
+                            pos,
+                            "a top-level definition",
+                        ))
+                    }
+                },
+                token => return Err(self.unexpected(&token, pos, "a top-level definition")),
+            }
+        }
+
+        Ok(file)
+    }
+
+    fn message(&mut self) -> Result<DescriptorProto, ParseError> {
+        let name = self.ident()?;
+        self.message_body(name)
+    }
+
+    fn message_body(&mut self, name: String) -> Result<DescriptorProto, ParseError> {
+        let mut message = DescriptorProto {
+            name: Some(name),
+            ..Default::default()
+        };
+        let mut proto3_optional_fields = Vec::new();
+
+        self.expect_punct('{')?;
+        loop {
+            if self.eat_punct('}')? {
+                break;
+            }
+            if self.eat_punct(';')? {
+                continue;
+            }
+
+            let pos = self.peek_pos()?;
+            let keyword = match self.peek()? {
+                Token::Ident(keyword) => keyword.clone(),
+                token => {
+                    let token = token.clone();
+                    return Err(self.unexpected(&token, pos, "a message element"));
+                }
+            };
+            match keyword.as_str() {
+                "message" => {
+                    self.bump()?;
+                    message.nested_type.push(self.message()?);
+                }
+                "enum" => {
+                    self.bump()?;
+                    message.enum_type.push(self.enum_()?);
+                }
+                "extend" => {
+                    self.bump()?;
+                    self.extend(&mut message.extension, &mut message.nested_type)?;
+                }
+                "option" => {
+                    self.bump()?;
+                    self.option_statement(&mut message.options)?;
+                }
+                "oneof" => {
+                    self.bump()?;
+                    self.oneof(&mut message)?;
+                }
+                "reserved" => {
+                    self.bump()?;
+                    self.reserved(&mut message)?;
+                }
+                "extensions" => {
+                    self.bump()?;
+                    self.extension_ranges(&mut message)?;
+                }
+                _ => {
+                    let label = self.label()?;
+                    let mut field = self.field(label, &mut message.nested_type)?;
+                    if self.proto3 && label == Some(Label::Optional) {
+                        field.proto3_optional = Some(true);
+                        proto3_optional_fields.push(message.field.len());
+                    }
+                    message.field.push(field);
+                }
+            }
+        }
+
+        // Synthetic oneofs for proto3 optional fields are placed after all real oneofs.
+        for index in proto3_optional_fields {
+            let field = &mut message.field[index];
+            field.oneof_index = Some(message.oneof_decl.len() as i32);
+            message.oneof_decl.push(OneofDescriptorProto {
+                name: Some(format!("_{}", field.name())),
+                options: None,
+            });
+        }
+
+        Ok(message)
+    }
+
+    fn label(&mut self) -> Result<Option<Label>, ParseError> {
+        let pos = self.peek_pos()?;
+        let label = if self.eat_ident("optional")? {
+            Some(Label::Optional)
+        } else if self.eat_ident("required")? {
+            Some(Label::Required)
+        } else if self.eat_ident("repeated")? {
+            Some(Label::Repeated)
+        } else {
+            None
+        };
+
+        if self.proto3 && label == Some(Label::Required) {
+            return Err(self.error(pos, "required fields are not allowed in proto3"));
+        }
+        if !self.proto3 && label.is_none() && !self.peek_ident("map")? {
+            return Err(self.error(pos, "expected 'required', 'optional', or 'repeated'"));
+        }
+        Ok(label)
+    }
+
+    /// Parses a field, group or map field. Nested types created for groups and map entries are added to
+    /// `nested_types`.
+    fn field(
+        &mut self,
+        label: Option<Label>,
+        nested_types: &mut Vec<DescriptorProto>,
+    ) -> Result<FieldDescriptorProto, ParseError> {
+        let explicit_label = label;
+        let label = label.unwrap_or(Label::Optional);
+        let pos = self.peek_pos()?;
+
+        if self.eat_ident("group")? {
+            if self.proto3 {
+                return Err(self.error(pos, "groups are not supported in proto3"));
+            }
+            let name_pos = self.peek_pos()?;
+            let name = self.ident()?;
+            if !name.starts_with(|ch: char| ch.is_ascii_uppercase()) {
+                return Err(self.error(name_pos, "group names must start with a capital letter"));
+            }
+            self.expect_punct('=')?;
+            let mut field = FieldDescriptorProto {
+                name: Some(name.to_ascii_lowercase()),
+                number: Some(self.field_number()?),
+                label: Some(label as i32),
+                r#type: Some(Type::Group as i32),
+                type_name: Some(name.clone()),
+                ..Default::default()
+            };
+            self.field_options(&mut field)?;
+            nested_types.push(self.message_body(name)?);
+            return Ok(field);
+        }
+
+        if self.peek_ident("map")? {
+            self.bump()?;
+            if self.eat_punct('<')? {
+                if explicit_label.is_some() {
+                    return Err(self.error(pos, "map fields cannot have a label"));
+                }
+                return self.map_field(nested_types);
+            }
+            return self.field_rest(label, "map".to_owned());
+        }
+
+        let type_name = self.full_ident(true)?;
+        self.field_rest(label, type_name)
+    }
+
+    fn field_rest(
+        &mut self,
+        label: Label,
+        type_name: String,
+    ) -> Result<FieldDescriptorProto, ParseError> {
+        let name = self.ident()?;
+        self.expect_punct('=')?;
+        let number = self.field_number()?;
+
+        let mut field = FieldDescriptorProto {
+            name: Some(name),
+            number: Some(number),
+            label: Some(label as i32),
+            ..Default::default()
+        };
+        match scalar_type(&type_name) {
+            Some(ty) => field.r#type = Some(ty as i32),
+            None => field.type_name = Some(type_name),
+        }
+        self.field_options(&mut field)?;
+        self.expect_punct(';')?;
+        Ok(field)
+    }
+
+    fn map_field(
+        &mut self,
+        nested_types: &mut Vec<DescriptorProto>,
+    ) -> Result<FieldDescriptorProto, ParseError> {
+        let key_pos = self.peek_pos()?;
+        let key_type = self.ident()?;
+        let key_type = match scalar_type(&key_type) {
+            Some(Type::Double | Type::Float | Type::Bytes) | None => {
+                return Err(self.error(key_pos, format!("invalid map key type '{}'", key_type)))
+            }
+            Some(ty) => ty,
+        };
+        self.expect_punct(',')?;
+        let value_type = self.full_ident(true)?;
+        self.expect_punct('>')?;
+
+        let entry_name = format!("{}Entry", to_camel_case(&self.peek_field_name()?, true));
+        let mut field = self.field_rest(Label::Repeated, entry_name.clone())?;
+        field.r#type = Some(Type::Message as i32);
+
+        let mut value = FieldDescriptorProto {
+            name: Some("value".to_owned()),
+            number: Some(2),
+            label: Some(Label::Optional as i32),
+            json_name: Some("value".to_owned()),
+            ..Default::default()
+        };
+        match scalar_type(&value_type) {
+            Some(ty) => value.r#type = Some(ty as i32),
+            None => value.type_name = Some(value_type),
+        }
+
+        nested_types.push(DescriptorProto {
+            name: Some(entry_name),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("key".to_owned()),
+                    number: Some(1),
+                    label: Some(Label::Optional as i32),
+                    r#type: Some(key_type as i32),
+                    json_name: Some("key".to_owned()),
+                    ..Default::default()
+                },
+                value,
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        Ok(field)
+    }
+
+    fn peek_field_name(&mut self) -> Result<String, ParseError> {
+        match self.peek()? {
+            Token::Ident(name) => Ok(name.clone()),
+            token => {
+                let token = token.clone();
+                let pos = self.peek_pos()?;
+                Err(self.unexpected(&token, pos, "an identifier"))
+            }
+        }
+    }
+
+    fn field_options(&mut self, field: &mut FieldDescriptorProto) -> Result<(), ParseError> {
+        if self.eat_punct('[')? {
+            loop {
+                let pos = self.peek_pos()?;
+                let (name, value) = self.option_assignment()?;
+                match name.as_slice() {
+                    [NamePart {
+                        name_part,
+                        is_extension: false,
+                    }] if name_part == "default" => {
+                        if self.proto3 {
+                            return Err(self
+                                .error(pos, "explicit default values are not allowed in proto3"));
+                        }
+                        let default_value = default_value(field, value)
+                            .map_err(|message| self.error(pos, message))?;
+                        field.default_value = Some(default_value);
+                    }
+                    [NamePart {
+                        name_part,
+                        is_extension: false,
+                    }] if name_part == "json_name" => match value {
+                        OptionValue::String(value) => {
+                            field.json_name = Some(
+                                String::from_utf8(value)
+                                    .map_err(|_| self.error(pos, "string is not valid UTF-8"))?,
+                            )
+                        }
+                        _ => return Err(self.error(pos, "json_name must be a string")),
+                    },
+                    _ => set_option(&mut field.options, name, value)
+                        .map_err(|message| self.error(pos, message))?,
+                }
+
+                if !self.eat_punct(',')? {
+                    break;
+                }
+            }
+            self.expect_punct(']')?;
+        }
+
+        if field.json_name.is_none() {
+            field.json_name = Some(to_camel_case(field.name(), false));
+        }
+        Ok(())
+    }
+
+    fn oneof(&mut self, message: &mut DescriptorProto) -> Result<(), ParseError> {
+        let index = message.oneof_decl.len() as i32;
+        let mut oneof = OneofDescriptorProto {
+            name: Some(self.ident()?),
+            options: None,
+        };
+
+        self.expect_punct('{')?;
+        loop {
+            if self.eat_punct('}')? {
+                break;
+            }
+            if self.eat_punct(';')? {
+                continue;
+            }
+            if self.eat_ident("option")? {
+                self.option_statement(&mut oneof.options)?;
+                continue;
+            }
+
+            let pos = self.peek_pos()?;
+            if self.peek_ident("optional")?
+                || self.peek_ident("required")?
+                || self.peek_ident("repeated")?
+            {
+                return Err(self.error(pos, "fields in oneofs must not have labels"));
+            }
+            if self.peek_ident("map")? {
+                return Err(self.error(pos, "map fields are not allowed in oneofs"));
+            }
+            let mut field = self.field(Some(Label::Optional), &mut message.nested_type)?;
+            field.oneof_index = Some(index);
+            message.field.push(field);
+        }
+
+        message.oneof_decl.push(oneof);
+        Ok(())
+    }
+
+    fn extend(
+        &mut self,
+        extensions: &mut Vec<FieldDescriptorProto>,
+        nested_types: &mut Vec<DescriptorProto>,
+    ) -> Result<(), ParseError> {
+        let extendee = self.full_ident(true)?;
+
+        self.expect_punct('{')?;
+        loop {
+            if self.eat_punct('}')? {
+                break;
+            }
+            if self.eat_punct(';')? {
+                continue;
+            }
+
+            let pos = self.peek_pos()?;
+            let label = self.label()?;
+            if self.peek_ident("map")? {
+                return Err(self.error(pos, "map fields are not allowed in extensions"));
+            }
+            let mut field = self.field(label, nested_types)?;
+            field.extendee = Some(extendee.clone());
+            extensions.push(field);
+        }
+        Ok(())
+    }
+
+    fn reserved(&mut self, message: &mut DescriptorProto) -> Result<(), ParseError> {
+        if let Token::String(_) = self.peek()? {
+            loop {
+                message.reserved_name.push(self.string()?);
+                if !self.eat_punct(',')? {
+                    break;
+                }
+            }
+        } else {
+            loop {
+                let (start, end) = self.range(MAX_FIELD_NUMBER)?;
+                message.reserved_range.push(ReservedRange {
+                    start: Some(start),
+                    end: Some(end + 1),
+                });
+                if !self.eat_punct(',')? {
+                    break;
+                }
+            }
+        }
+        self.expect_punct(';')
+    }
+
+    fn extension_ranges(&mut self, message: &mut DescriptorProto) -> Result<(), ParseError> {
+        let start_index = message.extension_range.len();
+        loop {
+            let (start, end) = self.range(MAX_FIELD_NUMBER)?;
+            message.extension_range.push(ExtensionRange {
+                start: Some(start),
+                end: Some(end + 1),
+                options: None,
+            });
+            if !self.eat_punct(',')? {
+                break;
+            }
+        }
+
+        if self.eat_punct('[')? {
+            let mut options: Option<ExtensionRangeOptions> = None;
+            loop {
+                let pos = self.peek_pos()?;
+                let (name, value) = self.option_assignment()?;
+                set_option(&mut options, name, value)
+                    .map_err(|message| self.error(pos, message))?;
+                if !self.eat_punct(',')? {
+                    break;
+                }
+            }
+            self.expect_punct(']')?;
+            for range in &mut message.extension_range[start_index..] {
+                range.options = options.clone();
+            }
+        }
+        self.expect_punct(';')
+    }
+
+    /// Parses a range of the form `start`, `start to end` or `start to max`. Both ends are inclusive.
+    fn range(&mut self, max: i32) -> Result<(i32, i32), ParseError> {
+        let start = self.int32()?;
+        let end = if self.eat_ident("to")? {
+            if self.eat_ident("max")? {
+                max
+            } else {
+                self.int32()?
+            }
+        } else {
+            start
+        };
+        Ok((start, end))
+    }
+
+    fn enum_(&mut self) -> Result<EnumDescriptorProto, ParseError> {
+        let mut enum_ = EnumDescriptorProto {
+            name: Some(self.ident()?),
+            ..Default::default()
+        };
+
+        self.expect_punct('{')?;
+        loop {
+            if self.eat_punct('}')? {
+                break;
+            }
+            if self.eat_punct(';')? {
+                continue;
+            }
+            if self.eat_ident("option")? {
+                self.option_statement(&mut enum_.options)?;
+                continue;
+            }
+            if self.eat_ident("reserved")? {
+                if let Token::String(_) = self.peek()? {
+                    loop {
+                        enum_.reserved_name.push(self.string()?);
+                        if !self.eat_punct(',')? {
+                            break;
+                        }
+                    }
+                } else {
+                    loop {
+                        let (start, end) = self.range(i32::MAX)?;
+                        enum_.reserved_range.push(EnumReservedRange {
+                            start: Some(start),
+                            end: Some(end),
+                        });
+                        if !self.eat_punct(',')? {
+                            break;
+                        }
+                    }
+                }
+                self.expect_punct(';')?;
+                continue;
+            }
+
+            let name = self.ident()?;
+            self.expect_punct('=')?;
+            let mut value = EnumValueDescriptorProto {
+                name: Some(name),
+                number: Some(self.int32()?),
+                options: None,
+            };
+            if self.eat_punct('[')? {
+                loop {
+                    let pos = self.peek_pos()?;
+                    let (name, option) = self.option_assignment()?;
+                    set_option(&mut value.options, name, option)
+                        .map_err(|message| self.error(pos, message))?;
+                    if !self.eat_punct(',')? {
+                        break;
+                    }
+                }
+                self.expect_punct(']')?;
+            }
+            self.expect_punct(';')?;
+            enum_.value.push(value);
+        }
+
+        Ok(enum_)
+    }
+
+    fn service(&mut self) -> Result<ServiceDescriptorProto, ParseError> {
+        let mut service = ServiceDescriptorProto {
+            name: Some(self.ident()?),
+            ..Default::default()
+        };
+
+        self.expect_punct('{')?;
+        loop {
+            if self.eat_punct('}')? {
+                break;
+            }
+            if self.eat_punct(';')? {
+                continue;
+            }
+            if self.eat_ident("option")? {
+                self.option_statement(&mut service.options)?;
+                continue;
+            }
+
+            self.expect_keyword("rpc")?;
+            let mut method = MethodDescriptorProto {
+                name: Some(self.ident()?),
+                ..Default::default()
+            };
+            self.expect_punct('(')?;
+            if self.peek_ident("stream")? {
+                self.bump()?;
+                if self.peek()? == &Token::Punct(')') {
+                    // A message type named `stream`.
+                    method.input_type = Some("stream".to_owned());
+                } else {
+                    method.client_streaming = Some(true);
+                }
+            }
+            if method.input_type.is_none() {
+                method.input_type = Some(self.full_ident(true)?);
+            }
+            self.expect_punct(')')?;
+            self.expect_keyword("returns")?;
+            self.expect_punct('(')?;
+            if self.peek_ident("stream")? {
+                self.bump()?;
+                if self.peek()? == &Token::Punct(')') {
+                    method.output_type = Some("stream".to_owned());
+                } else {
+                    method.server_streaming = Some(true);
+                }
+            }
+            if method.output_type.is_none() {
+                method.output_type = Some(self.full_ident(true)?);
+            }
+            self.expect_punct(')')?;
+
+            if self.eat_punct('{')? {
+                loop {
+                    if self.eat_punct('}')? {
+                        break;
+                    }
+                    if self.eat_punct(';')? {
+                        continue;
+                    }
+                    self.expect_keyword("option")?;
+                    self.option_statement(&mut method.options)?;
+                }
+            } else {
+                self.expect_punct(';')?;
+            }
+            service.method.push(method);
+        }
+
+        Ok(service)
+    }
+
+    /// Parses the remainder of an `option name = value;` statement.
+    fn option_statement<T: Options>(&mut self, options: &mut Option<T>) -> Result<(), ParseError> {
+        let pos = self.peek_pos()?;
+        let (name, value) = self.option_assignment()?;
+        set_option(options, name, value).map_err(|message| self.error(pos, message))?;
+        self.expect_punct(';')
+    }
+
+    fn option_assignment(&mut self) -> Result<(Vec<NamePart>, OptionValue), ParseError> {
+        let mut name = Vec::new();
+        loop {
+            if self.eat_punct('(')? {
+                let name_part = self.full_ident(true)?;
+                self.expect_punct(')')?;
+                name.push(NamePart {
+                    name_part,
+                    is_extension: true,
+                });
+            } else {
+                name.push(NamePart {
+                    name_part: self.ident()?,
+                    is_extension: false,
+                });
+            }
+            if !self.eat_punct('.')? {
+                break;
+            }
+        }
+        self.expect_punct('=')?;
+        let value = self.option_value()?;
+        Ok((name, value))
+    }
+
+    fn option_value(&mut self) -> Result<OptionValue, ParseError> {
+        let negative = self.eat_punct('-')?;
+        if !negative {
+            self.eat_punct('+')?;
+        }
+        let (token, pos) = self.bump()?;
+        match token {
+            Token::Int(value) => Ok(OptionValue::Int { negative, value }),
+            Token::Float(value) if negative => Ok(OptionValue::Float(-value)),
+            Token::Float(value) => Ok(OptionValue::Float(value)),
+            Token::Ident(ident) if negative => match ident.as_str() {
+                "inf" => Ok(OptionValue::Float(f64::NEG_INFINITY)),
+                "nan" => Ok(OptionValue::Float(f64::NAN)),
+                _ => Err(self.unexpected(&Token::Ident(ident), pos, "a number")),
+            },
+            Token::Ident(ident) => Ok(OptionValue::Ident(ident)),
+            Token::String(mut value) if !negative => {
+                while let Token::String(next) = self.peek()? {
+                    value.extend_from_slice(next);
+                    self.bump()?;
+                }
+                Ok(OptionValue::String(value))
+            }
+            Token::Punct('{') if !negative => {
+                let start = self.lexer.pos;
+                let mut depth = 1;
+                let end = loop {
+                    match self.bump()? {
+                        (Token::Punct('{'), _) => depth += 1,
+                        (Token::Punct('}'), end) => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break end;
+                            }
+                        }
+                        (Token::Eof, pos) => {
+                            return Err(self.error(pos, "unterminated aggregate value"))
+                        }
+                        _ => (),
+                    }
+                };
+                Ok(OptionValue::Aggregate(
+                    self.lexer.input[start..end].trim().to_owned(),
+                ))
+            }
+            token => Err(self.unexpected(&token, pos, "an option value")),
+        }
+    }
+}
+
+pub(in crate::descriptor) fn scalar_type(name: &str) -> Option<Type> {
+    match name {
+        "double" => Some(Type::Double),
+        "float" => Some(Type::Float),
+        "int32" => Some(Type::Int32),
+        "int64" => Some(Type::Int64),
+        "uint32" => Some(Type::Uint32),
+        "uint64" => Some(Type::Uint64),
+        "sint32" => Some(Type::Sint32),
+        "sint64" => Some(Type::Sint64),
+        "fixed32" => Some(Type::Fixed32),
+        "fixed64" => Some(Type::Fixed64),
+        "sfixed32" => Some(Type::Sfixed32),
+        "sfixed64" => Some(Type::Sfixed64),
+        "bool" => Some(Type::Bool),
+        "string" => Some(Type::String),
+        "bytes" => Some(Type::Bytes),
+        _ => None,
+    }
+}
+
+/// Converts a field name to camel case, as done by `protoc` for JSON names and map entry names.
+pub(in crate::descriptor) fn to_camel_case(name: &str, upper_first: bool) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut capitalize_next = upper_first;
+    for ch in name.chars() {
+        if ch == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            result.push(ch.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+/// Formats the value of a `default` option in the form stored in [`FieldDescriptorProto::default_value`].
+fn default_value(field: &FieldDescriptorProto, value: OptionValue) -> Result<String, String> {
+    let ty = field.r#type.and_then(Type::from_i32);
+    match (ty, value) {
+        (Some(Type::Message | Type::Group), _) => {
+            Err("messages can't have default values".to_owned())
+        }
+        (_, _) if field.label == Some(Label::Repeated as i32) => {
+            Err("repeated fields can't have default values".to_owned())
+        }
+        (Some(Type::String), OptionValue::String(value)) => {
+            String::from_utf8(value).map_err(|_| "string is not valid UTF-8".to_owned())
+        }
+        (Some(Type::Bytes), OptionValue::String(value)) => Ok(c_escape(&value)),
+        (Some(Type::String | Type::Bytes), _) => Err("expected a string".to_owned()),
+        (_, OptionValue::String(_) | OptionValue::Aggregate(_)) => {
+            Err("expected a number or identifier".to_owned())
+        }
+        (Some(Type::Float), OptionValue::Float(value)) => Ok(format_float(value as f32)),
+        (_, OptionValue::Float(value)) => Ok(format_float(value)),
+        (_, OptionValue::Int { negative, value }) => Ok(if negative {
+            format!("-{}", value)
+        } else {
+            value.to_string()
+        }),
+        (_, OptionValue::Ident(ident)) => Ok(ident),
+    }
+}
+
+fn format_float<T>(value: T) -> String
+where
+    T: Into<f64> + ToString + Copy,
+{
+    let double = value.into();
+    if double.is_nan() {
+        "nan".to_owned()
+    } else if double.is_infinite() {
+        if double > 0.0 { "inf" } else { "-inf" }.to_owned()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes bytes in the same way as the `CEscape` function used by `protoc`.
+fn c_escape(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        match byte {
+            b'\n' => result.push_str("\\n"),
+            b'\r' => result.push_str("\\r"),
+            b'\t' => result.push_str("\\t"),
+            b'"' => result.push_str("\\\""),
+            b'\'' => result.push_str("\\'"),
+            b'\\' => result.push_str("\\\\"),
+            0x20..=0x7e => result.push(byte as char),
+            _ => result.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    result
+}
+
+fn set_option<T: Options>(
+    options: &mut Option<T>,
+    name: Vec<NamePart>,
+    value: OptionValue,
+) -> Result<(), String> {
+    let options = options.get_or_insert_with(T::default);
+    if let [NamePart {
+        name_part,
+        is_extension: false,
+    }] = name.as_slice()
+    {
+        return if options.set(name_part, &value)? {
+            Ok(())
+        } else {
+            Err(format!("option '{}' unknown", name_part))
+        };
+    }
+
+    let mut option = UninterpretedOption {
+        name,
+        ..Default::default()
+    };
+    match value {
+        OptionValue::Ident(value) => option.identifier_value = Some(value),
+        OptionValue::Int {
+            negative: false,
+            value,
+        } => option.positive_int_value = Some(value),
+        OptionValue::Int {
+            negative: true,
+            value,
+        } => {
+            option.negative_int_value = Some(
+                i64::try_from(-i128::from(value)).map_err(|_| "integer out of range".to_owned())?,
+            )
+        }
+        OptionValue::Float(value) => option.double_value = Some(value),
+        OptionValue::String(value) => option.string_value = Some(value),
+        OptionValue::Aggregate(value) => option.aggregate_value = Some(value),
+    }
+    options.uninterpreted_option().push(option);
+    Ok(())
+}
+
+impl OptionValue {
+    fn bool(&self) -> Result<bool, String> {
+        match self {
+            OptionValue::Ident(value) if value == "true" => Ok(true),
+            OptionValue::Ident(value) if value == "false" => Ok(false),
+            _ => Err("expected 'true' or 'false'".to_owned()),
+        }
+    }
+
+    fn string(&self) -> Result<String, String> {
+        match self {
+            OptionValue::String(value) => {
+                String::from_utf8(value.clone()).map_err(|_| "string is not valid UTF-8".to_owned())
+            }
+            _ => Err("expected a string".to_owned()),
+        }
+    }
+
+    fn enum_value(&self, values: &[(&str, i32)]) -> Result<i32, String> {
+        if let OptionValue::Ident(ident) = self {
+            if let Some(&(_, number)) = values.iter().find(|(name, _)| name == ident) {
+                return Ok(number);
+            }
+        }
+        Err(format!(
+            "expected one of {}",
+            values
+                .iter()
+                .map(|(name, _)| format!("'{}'", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+}
+
+macro_rules! impl_options {
+    ($ty:ty { $($name:ident: $kind:ident $(($($variant:literal = $number:literal),*))?),* $(,)? }) => {
+        impl Options for $ty {
+            #[allow(deprecated, unused_variables)]
+            fn set(&mut self, name: &str, value: &OptionValue) -> Result<bool, String> {
+                match name {
+                    $(stringify!($name) => {
+                        self.$name = Some(impl_options!(@value value, $kind $(($($variant = $number),*))?));
+                        Ok(true)
+                    })*
+                    _ => Ok(false),
+                }
+            }
+
+            fn uninterpreted_option(&mut self) -> &mut Vec<UninterpretedOption> {
+                &mut self.uninterpreted_option
+            }
+        }
+    };
+    (@value $value:ident, bool) => { $value.bool()? };
+    (@value $value:ident, string) => { $value.string()? };
+    (@value $value:ident, enum($($variant:literal = $number:literal),*)) => {
+        $value.enum_value(&[$(($variant, $number)),*])?
+    };
+}
+
+impl_options!(FileOptions {
+    java_package: string,
+    java_outer_classname: string,
+    java_multiple_files: bool,
+    java_generate_equals_and_hash: bool,
+    java_string_check_utf8: bool,
+    optimize_for: enum("SPEED" = 1, "CODE_SIZE" = 2, "LITE_RUNTIME" = 3),
+    go_package: string,
+    cc_generic_services: bool,
+    java_generic_services: bool,
+    py_generic_services: bool,
+    php_generic_services: bool,
+    deprecated: bool,
+    cc_enable_arenas: bool,
+    objc_class_prefix: string,
+    csharp_namespace: string,
+    swift_prefix: string,
+    php_class_prefix: string,
+    php_namespace: string,
+    php_metadata_namespace: string,
+    ruby_package: string,
+});
+
+impl_options!(MessageOptions {
+    message_set_wire_format: bool,
+    no_standard_descriptor_accessor: bool,
+    deprecated: bool,
+    map_entry: bool,
+});
+
+impl_options!(FieldOptions {
+    ctype: enum("STRING" = 0, "CORD" = 1, "STRING_PIECE" = 2),
+    packed: bool,
+    jstype: enum("JS_NORMAL" = 0, "JS_STRING" = 1, "JS_NUMBER" = 2),
+    lazy: bool,
+    deprecated: bool,
+    weak: bool,
+});
+
+impl_options!(OneofOptions {});
+
+impl_options!(EnumOptions {
+    allow_alias: bool,
+    deprecated: bool,
+});
+
+impl_options!(EnumValueOptions { deprecated: bool });
+
+impl_options!(ServiceOptions { deprecated: bool });
+
+impl_options!(MethodOptions {
+    deprecated: bool,
+    idempotency_level: enum("IDEMPOTENCY_UNKNOWN" = 0, "NO_SIDE_EFFECTS" = 1, "IDEMPOTENT" = 2),
+});
+
+impl_options!(ExtensionRangeOptions {});
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use prost_types::{
+    field_descriptor_proto::Type, DescriptorProto, EnumDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto,
+};
+
+use crate::DescriptorPool;
+
+/// The full names of all message and enum types visible to the files being compiled, mapped to whether
+/// they are messages.
+pub(in crate::descriptor) struct Names {
+    types: HashMap<String, bool>,
+}
+
+impl Names {
+    pub(in crate::descriptor) fn new(pool: &DescriptorPool, files: &[FileDescriptorProto]) -> Self {
+        let mut types = HashMap::new();
+        for message in pool.all_messages() {
+            types.insert(message.full_name().to_owned(), true);
+        }
+        for enum_ in pool.all_enums() {
+            types.insert(enum_.full_name().to_owned(), false);
+        }
+        for file in files {
+            let package = file.package();
+            add_types(&mut types, package, &file.message_type, &file.enum_type);
+        }
+        Names { types }
+    }
+
+    /// Resolves the types referenced by `file` to fully-qualified names.
+    ///
+    /// Names which cannot be resolved are left as written, so that adding the file to a pool reports an
+    /// error.
+    pub(in crate::descriptor) fn resolve_file(&self, file: &mut FileDescriptorProto) {
+        let package = file.package().to_owned();
+        for message in &mut file.message_type {
+            self.resolve_message(&make_full_name(&package, message.name()), message);
+        }
+        for extension in &mut file.extension {
+            self.resolve_field(&package, extension);
+        }
+        for service in &mut file.service {
+            for method in &mut service.method {
+                if let Some(input_type) = &mut method.input_type {
+                    if let Some((full_name, _)) = self.resolve(&package, input_type) {
+                        *input_type = full_name;
+                    }
+                }
+                if let Some(output_type) = &mut method.output_type {
+                    if let Some((full_name, _)) = self.resolve(&package, output_type) {
+                        *output_type = full_name;
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve_message(&self, namespace: &str, message: &mut DescriptorProto) {
+        for field in &mut message.field {
+            self.resolve_field(namespace, field);
+        }
+        for extension in &mut message.extension {
+            self.resolve_field(namespace, extension);
+        }
+        for nested in &mut message.nested_type {
+            self.resolve_message(&make_full_name(namespace, nested.name()), nested);
+        }
+    }
+
+    fn resolve_field(&self, namespace: &str, field: &mut FieldDescriptorProto) {
+        if let Some(type_name) = &mut field.type_name {
+            if let Some((full_name, is_message)) = self.resolve(namespace, type_name) {
+                *type_name = full_name;
+                if field.r#type.is_none() {
+                    field.r#type = Some(if is_message {
+                        Type::Message as i32
+                    } else {
+                        Type::Enum as i32
+                    });
+                }
+            }
+        }
+        if let Some(extendee) = &mut field.extendee {
+            if let Some((full_name, _)) = self.resolve(namespace, extendee) {
+                *extendee = full_name;
+            }
+        }
+    }
+
+    /// Resolves a type name relative to `namespace`, searching each enclosing scope in turn. Returns the
+    /// fully-qualified name with a leading dot.
+    fn resolve(&self, mut namespace: &str, type_name: &str) -> Option<(String, bool)> {
+        if let Some(full_name) = type_name.strip_prefix('.') {
+            return self
+                .types
+                .get(full_name)
+                .map(|&is_message| (type_name.to_owned(), is_message));
+        }
+
+        loop {
+            let full_name = make_full_name(namespace, type_name);
+            if let Some(&is_message) = self.types.get(&full_name) {
+                return Some((format!(".{}", full_name), is_message));
+            }
+            if namespace.is_empty() {
+                return None;
+            }
+            namespace = match namespace.rfind('.') {
+                Some(index) => &namespace[..index],
+                None => "",
+            };
+        }
+    }
+}
+
+fn add_types(
+    types: &mut HashMap<String, bool>,
+    namespace: &str,
+    messages: &[DescriptorProto],
+    enums: &[EnumDescriptorProto],
+) {
+    for message in messages {
+        let full_name = make_full_name(namespace, message.name());
+        add_types(types, &full_name, &message.nested_type, &message.enum_type);
+        types.insert(full_name, true);
+    }
+    for enum_ in enums {
+        types.insert(make_full_name(namespace, enum_.name()), false);
+    }
+}
+
+fn make_full_name(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", namespace, name)
+    }
+}
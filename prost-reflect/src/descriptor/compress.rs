@@ -0,0 +1,94 @@
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use std::io::Read;
+#[cfg(feature = "gzip")]
+use std::io::Write;
+
+use prost::Message;
+use prost_types::FileDescriptorSet;
+
+use crate::{DescriptorError, DescriptorPool};
+
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+const ZSTD_MAGIC: &[u8] = b"\x28\xb5\x2f\xfd";
+
+/// A compression format for encoded file descriptor sets, used by [`DescriptorPool::encode_compressed`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// The file descriptor set is not compressed.
+    None,
+    /// The file descriptor set is compressed with gzip.
+    #[cfg(feature = "gzip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+    Gzip,
+    /// The file descriptor set is compressed with zstd.
+    #[cfg(feature = "zstd")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+    Zstd,
+}
+
+impl DescriptorPool {
+    /// Decodes a [`FileDescriptorSet`] which may be compressed, and creates a new [`DescriptorPool`]
+    /// wrapping it.
+    ///
+    /// The compression format is detected from the first bytes of the input. Data compressed with gzip
+    /// requires the `gzip` feature, and data compressed with zstd requires the `zstd` feature. Input
+    /// which is not compressed is decoded as by [`DescriptorPool::decode`].
+    pub fn decode_compressed(bytes: &[u8]) -> Result<Self, DescriptorError> {
+        if bytes.starts_with(GZIP_MAGIC) {
+            #[cfg(feature = "gzip")]
+            {
+                let mut decompressed = Vec::new();
+                flate2::read::GzDecoder::new(bytes)
+                    .read_to_end(&mut decompressed)
+                    .map_err(DescriptorError::decompress_file_descriptor_set)?;
+                return DescriptorPool::decode(decompressed.as_slice());
+            }
+            #[cfg(not(feature = "gzip"))]
+            return Err(DescriptorError::unsupported_compression("gzip"));
+        }
+
+        if bytes.starts_with(ZSTD_MAGIC) {
+            #[cfg(feature = "zstd")]
+            {
+                let mut decompressed = Vec::new();
+                zstd::stream::read::Decoder::new(bytes)
+                    .and_then(|mut decoder| decoder.read_to_end(&mut decompressed))
+                    .map_err(DescriptorError::decompress_file_descriptor_set)?;
+                return DescriptorPool::decode(decompressed.as_slice());
+            }
+            #[cfg(not(feature = "zstd"))]
+            return Err(DescriptorError::unsupported_compression("zstd"));
+        }
+
+        DescriptorPool::decode(bytes)
+    }
+
+    /// Encodes the files in this pool as a [`FileDescriptorSet`], compressed with the given format.
+    ///
+    /// The output can be read back with [`DescriptorPool::decode_compressed`]. To encode only the files
+    /// needed for some types, use [`prune`][DescriptorPool::prune] first.
+    pub fn encode_compressed(&self, compression: Compression) -> Vec<u8> {
+        let bytes = FileDescriptorSet {
+            file: self.file_descriptor_protos().cloned().collect(),
+        }
+        .encode_to_vec();
+
+        match compression {
+            Compression::None => bytes,
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder
+                    .write_all(&bytes)
+                    .expect("writing to a vec cannot fail");
+                encoder.finish().expect("writing to a vec cannot fail")
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                zstd::stream::encode_all(bytes.as_slice(), 0).expect("writing to a vec cannot fail")
+            }
+        }
+    }
+}
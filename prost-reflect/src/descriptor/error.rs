@@ -1,9 +1,39 @@
 use std::fmt;
 
+use super::{FileDescriptorInner, MergeConflict};
+
 /// An error that may occur while creating a [`DescriptorPool`][crate::DescriptorPool].
+///
+/// When adding files to a pool, all problems with the files are reported rather than just the first, and
+/// are available from [`diagnostics`][Self::diagnostics]. The [`Display`][fmt::Display] implementation
+/// describes the first problem, along with the number of other problems.
 #[derive(Debug)]
 pub struct DescriptorError {
+    diagnostics: Vec<DescriptorDiagnostic>,
+}
+
+/// A single problem reported by a [`DescriptorError`].
+///
+/// The [`Display`][fmt::Display] implementation formats the problem like the protobuf compiler, prefixed by
+/// the file name and location when they are known, for example
+/// `foo.proto:3:5: the message or enum type 'Bar' was not found`.
+#[derive(Debug)]
+pub struct DescriptorDiagnostic {
     kind: DescriptorErrorKind,
+    file: Option<String>,
+    symbol: Option<String>,
+    location: Option<Location>,
+}
+
+/// The location of an element in a source file.
+///
+/// Lines and columns start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
 }
 
 #[derive(Debug)]
@@ -11,12 +41,23 @@ enum DescriptorErrorKind {
     DecodeFileDescriptorSet {
         err: prost::DecodeError,
     },
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    DecompressFileDescriptorSet {
+        err: std::io::Error,
+    },
+    #[cfg(not(all(feature = "gzip", feature = "zstd")))]
+    UnsupportedCompression {
+        format: &'static str,
+    },
     TypeNotFound {
         name: String,
     },
     TypeAlreadyExists {
         name: String,
     },
+    SymbolNotFound {
+        name: String,
+    },
     UnknownSyntax {
         syntax: String,
     },
@@ -40,6 +81,13 @@ enum DescriptorErrorKind {
     FileAlreadyExists {
         name: String,
     },
+    FileNotInPool {
+        name: String,
+    },
+    FileInUse {
+        name: String,
+        required_by: String,
+    },
     InvalidMethodType {
         name: String,
         type_name: String,
@@ -48,45 +96,128 @@ enum DescriptorErrorKind {
         name: String,
         type_name: String,
     },
+    ReadFile {
+        path: String,
+        err: std::io::Error,
+    },
+    ParseFile {
+        name: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    FileNotInIncludeDirs {
+        path: String,
+    },
+    CircularImport {
+        cycle: String,
+    },
+    InvalidFieldNumber {
+        message: String,
+        field: String,
+        number: i32,
+    },
+    DuplicateFieldNumber {
+        message: String,
+        number: i32,
+    },
+    InvalidField {
+        name: String,
+        reason: &'static str,
+    },
+    MergeConflicts {
+        conflicts: Vec<MergeConflict>,
+    },
 }
 
 impl DescriptorError {
-    pub(super) fn decode_file_descriptor_set(err: prost::DecodeError) -> Self {
+    fn new(kind: DescriptorErrorKind) -> Self {
         DescriptorError {
-            kind: DescriptorErrorKind::DecodeFileDescriptorSet { err },
+            diagnostics: vec![DescriptorDiagnostic {
+                kind,
+                file: None,
+                symbol: None,
+                location: None,
+            }],
         }
     }
 
-    pub(super) fn type_not_found(name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::TypeNotFound {
-                name: name.to_string(),
-            },
+    /// Combines several errors into one, returning `Ok` if there are none.
+    pub(super) fn combine(errors: Vec<DescriptorError>) -> Result<(), DescriptorError> {
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(DescriptorError {
+                diagnostics: errors.into_iter().flat_map(|err| err.diagnostics).collect(),
+            })
         }
     }
 
-    pub(super) fn type_already_exists(name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::TypeAlreadyExists {
-                name: name.to_string(),
-            },
+    /// Sets the file, symbol and location of any problems in this error which do not already have them.
+    /// The location is looked up in the file's source code info using `path`.
+    pub(super) fn with_context(
+        mut self,
+        file: &FileDescriptorInner,
+        symbol: Option<&str>,
+        path: &[i32],
+    ) -> Self {
+        let location = file
+            .locations
+            .get(path)
+            .and_then(|&index| file.raw.source_code_info.as_ref()?.location.get(index))
+            .and_then(|location| Location::from_span(&location.span));
+        for diagnostic in &mut self.diagnostics {
+            if diagnostic.file.is_none() {
+                diagnostic.file = Some(file.raw.name().to_owned());
+                diagnostic.symbol = symbol.map(ToOwned::to_owned);
+                diagnostic.location = location;
+            }
         }
+        self
+    }
+
+    pub(crate) fn decode_file_descriptor_set(err: prost::DecodeError) -> Self {
+        DescriptorError::new(DescriptorErrorKind::DecodeFileDescriptorSet { err })
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub(super) fn decompress_file_descriptor_set(err: std::io::Error) -> Self {
+        DescriptorError::new(DescriptorErrorKind::DecompressFileDescriptorSet { err })
+    }
+
+    #[cfg(not(all(feature = "gzip", feature = "zstd")))]
+    pub(super) fn unsupported_compression(format: &'static str) -> Self {
+        DescriptorError::new(DescriptorErrorKind::UnsupportedCompression { format })
+    }
+
+    pub(super) fn type_not_found(name: impl ToString) -> Self {
+        DescriptorError::new(DescriptorErrorKind::TypeNotFound {
+            name: name.to_string(),
+        })
+    }
+
+    pub(super) fn type_already_exists(name: impl ToString) -> Self {
+        DescriptorError::new(DescriptorErrorKind::TypeAlreadyExists {
+            name: name.to_string(),
+        })
+    }
+
+    pub(super) fn symbol_not_found(name: impl ToString) -> Self {
+        DescriptorError::new(DescriptorErrorKind::SymbolNotFound {
+            name: name.to_string(),
+        })
     }
 
     pub(super) fn unknown_syntax(syntax: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::UnknownSyntax {
-                syntax: syntax.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::UnknownSyntax {
+            syntax: syntax.to_string(),
+        })
     }
 
     pub(super) fn invalid_map_entry(name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidMapEntry {
-                name: name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::InvalidMapEntry {
+            name: name.to_string(),
+        })
     }
 
     pub(super) fn invalid_default_value(
@@ -94,76 +225,246 @@ impl DescriptorError {
         field: impl ToString,
         value: impl ToString,
     ) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidDefaultValue {
-                name: name.to_string(),
-                field: field.to_string(),
-                value: value.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::InvalidDefaultValue {
+            name: name.to_string(),
+            field: field.to_string(),
+            value: value.to_string(),
+        })
     }
 
     pub(super) fn empty_enum() -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::EmptyEnum,
-        }
+        DescriptorError::new(DescriptorErrorKind::EmptyEnum)
     }
 
     pub(crate) fn invalid_oneof_index(name: impl ToString, field: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidOneofIndex {
-                name: name.to_string(),
-                field: field.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::InvalidOneofIndex {
+            name: name.to_string(),
+            field: field.to_string(),
+        })
     }
 
     pub(crate) fn file_not_found(required_by: impl ToString, name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::FileNotFound {
-                required_by: required_by.to_string(),
-                name: name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::FileNotFound {
+            required_by: required_by.to_string(),
+            name: name.to_string(),
+        })
     }
 
     pub(crate) fn file_already_exists(name: impl ToString) -> Self {
-        DescriptorError {
-            kind: DescriptorErrorKind::FileAlreadyExists {
-                name: name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::FileAlreadyExists {
+            name: name.to_string(),
+        })
+    }
+
+    pub(super) fn file_not_in_pool(name: impl ToString) -> Self {
+        DescriptorError::new(DescriptorErrorKind::FileNotInPool {
+            name: name.to_string(),
+        })
+    }
+
+    pub(super) fn file_in_use(name: impl ToString, required_by: impl ToString) -> Self {
+        DescriptorError::new(DescriptorErrorKind::FileInUse {
+            name: name.to_string(),
+            required_by: required_by.to_string(),
+        })
     }
 
     pub(crate) fn invalid_method_type(
         name: impl ToString,
         type_name: impl ToString,
     ) -> DescriptorError {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidMethodType {
-                name: name.to_string(),
-                type_name: type_name.to_string(),
-            },
-        }
+        DescriptorError::new(DescriptorErrorKind::InvalidMethodType {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+        })
     }
 
     pub(crate) fn invalid_extendee_type(
         name: impl ToString,
         type_name: impl ToString,
     ) -> DescriptorError {
-        DescriptorError {
-            kind: DescriptorErrorKind::InvalidExtendeeType {
-                name: name.to_string(),
-                type_name: type_name.to_string(),
-            },
+        DescriptorError::new(DescriptorErrorKind::InvalidExtendeeType {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+        })
+    }
+
+    pub(super) fn read_file(path: impl ToString, err: std::io::Error) -> Self {
+        DescriptorError::new(DescriptorErrorKind::ReadFile {
+            path: path.to_string(),
+            err,
+        })
+    }
+
+    pub(super) fn parse_file(
+        name: impl ToString,
+        line: usize,
+        column: usize,
+        message: impl ToString,
+    ) -> Self {
+        let mut err = DescriptorError::new(DescriptorErrorKind::ParseFile {
+            name: name.to_string(),
+            line,
+            column,
+            message: message.to_string(),
+        });
+        let diagnostic = &mut err.diagnostics[0];
+        diagnostic.file = Some(name.to_string());
+        diagnostic.location = Some(Location::new(line as u32, column as u32));
+        err
+    }
+
+    pub(super) fn file_not_in_include_dirs(path: impl ToString) -> Self {
+        DescriptorError::new(DescriptorErrorKind::FileNotInIncludeDirs {
+            path: path.to_string(),
+        })
+    }
+
+    pub(super) fn circular_import(cycle: impl ToString) -> Self {
+        DescriptorError::new(DescriptorErrorKind::CircularImport {
+            cycle: cycle.to_string(),
+        })
+    }
+
+    pub(super) fn invalid_field_number(
+        message: impl ToString,
+        field: impl ToString,
+        number: i32,
+    ) -> Self {
+        DescriptorError::new(DescriptorErrorKind::InvalidFieldNumber {
+            message: message.to_string(),
+            field: field.to_string(),
+            number,
+        })
+    }
+
+    pub(super) fn duplicate_field_number(message: impl ToString, number: i32) -> Self {
+        DescriptorError::new(DescriptorErrorKind::DuplicateFieldNumber {
+            message: message.to_string(),
+            number,
+        })
+    }
+
+    pub(super) fn invalid_field(name: impl ToString, reason: &'static str) -> Self {
+        DescriptorError::new(DescriptorErrorKind::InvalidField {
+            name: name.to_string(),
+            reason,
+        })
+    }
+
+    pub(super) fn merge_conflict(conflicts: Vec<MergeConflict>) -> Self {
+        DescriptorError::new(DescriptorErrorKind::MergeConflicts { conflicts })
+    }
+
+    /// Gets the conflicting definitions which caused [`DescriptorPool::merge`][crate::DescriptorPool::merge]
+    /// to fail, or an empty slice if this error was not caused by a merge conflict.
+    pub fn merge_conflicts(&self) -> &[MergeConflict] {
+        match &self.diagnostics[0].kind {
+            DescriptorErrorKind::MergeConflicts { conflicts } => conflicts,
+            _ => &[],
+        }
+    }
+
+    /// Gets all the problems which caused this error, in the order they were found. There is always at
+    /// least one.
+    pub fn diagnostics(&self) -> &[DescriptorDiagnostic] {
+        &self.diagnostics
+    }
+}
+
+impl DescriptorDiagnostic {
+    /// A description of the problem, without the file name or location.
+    pub fn message(&self) -> String {
+        match &self.kind {
+            DescriptorErrorKind::ParseFile { message, .. } => message.clone(),
+            kind => kind.to_string(),
+        }
+    }
+
+    /// The name of the file containing the problem, if it is known.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// The fully-qualified name of the definition with the problem, such as a message, field or method, if
+    /// the problem is not with the file as a whole.
+    pub fn symbol(&self) -> Option<&str> {
+        self.symbol.as_deref()
+    }
+
+    /// The location of the problem in the source file, if the file includes source code info.
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+}
+
+impl Location {
+    /// The line on which the element starts.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column at which the element starts.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// The line on which the element ends.
+    pub fn end_line(&self) -> u32 {
+        self.end_line
+    }
+
+    /// The column at which the element ends, exclusive.
+    pub fn end_column(&self) -> u32 {
+        self.end_column
+    }
+
+    /// Creates a location covering the single character at the given line and column.
+    pub(crate) fn new(line: u32, column: u32) -> Self {
+        Location {
+            line,
+            column,
+            end_line: line,
+            end_column: column + 1,
         }
     }
+
+    /// Creates a location from the zero-based span of a `SourceCodeInfo` location.
+    pub(crate) fn from_span(span: &[i32]) -> Option<Self> {
+        let (line, column, end_line, end_column) = match *span {
+            [line, column, end_column] => (line, column, line, end_column),
+            [line, column, end_line, end_column] => (line, column, end_line, end_column),
+            _ => return None,
+        };
+        Some(Location {
+            line: line as u32 + 1,
+            column: column as u32 + 1,
+            end_line: end_line as u32 + 1,
+            end_column: end_column as u32 + 1,
+        })
+    }
+}
+
+impl fmt::Display for DescriptorDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}", file)?;
+            if let Some(location) = &self.location {
+                write!(f, ":{}:{}", location.line(), location.column())?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.message())
+    }
 }
 
 impl std::error::Error for DescriptorError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match &self.kind {
+        match &self.diagnostics[0].kind {
             DescriptorErrorKind::DecodeFileDescriptorSet { err } => Some(err),
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            DescriptorErrorKind::DecompressFileDescriptorSet { err } => Some(err),
+            DescriptorErrorKind::ReadFile { err, .. } => Some(err),
             _ => None,
         }
     }
@@ -171,10 +472,31 @@ impl std::error::Error for DescriptorError {
 
 impl fmt::Display for DescriptorError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.kind {
+        write!(f, "{}", self.diagnostics[0].kind)?;
+        match self.diagnostics.len() {
+            1 => Ok(()),
+            2 => write!(f, " (and 1 other error)"),
+            len => write!(f, " (and {} other errors)", len - 1),
+        }
+    }
+}
+
+impl fmt::Display for DescriptorErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
             DescriptorErrorKind::DecodeFileDescriptorSet { .. } => {
                 write!(f, "failed to decode file descriptor set")
             }
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            DescriptorErrorKind::DecompressFileDescriptorSet { .. } => {
+                write!(f, "failed to decompress file descriptor set")
+            }
+            #[cfg(not(all(feature = "gzip", feature = "zstd")))]
+            DescriptorErrorKind::UnsupportedCompression { format } => write!(
+                f,
+                "the file descriptor set is compressed with {}, but the '{}' feature is not enabled",
+                format, format
+            ),
             DescriptorErrorKind::TypeNotFound { name } => {
                 write!(f, "the message or enum type '{}' was not found", name)
             }
@@ -185,6 +507,9 @@ impl fmt::Display for DescriptorError {
                     name
                 )
             }
+            DescriptorErrorKind::SymbolNotFound { name } => {
+                write!(f, "the symbol '{}' was not found", name)
+            }
             DescriptorErrorKind::UnknownSyntax { syntax } => {
                 write!(f, "the syntax '{}' is not recognized", syntax)
             }
@@ -208,8 +533,27 @@ impl fmt::Display for DescriptorError {
             }
             DescriptorErrorKind::FileNotFound { required_by, name } => write!(f, "the file '{}' was not found while resolving dependencies for '{}'", name, required_by),
             DescriptorErrorKind::FileAlreadyExists { name } => write!(f, "a conflicting file named '{}' is already added. Duplicate files must match exactly", name),
+            DescriptorErrorKind::FileNotInPool { name } => write!(f, "the file '{}' is not in the pool", name),
+            DescriptorErrorKind::FileInUse { name, required_by } => write!(f, "the file '{}' cannot be removed because it is imported by '{}'", name, required_by),
             DescriptorErrorKind::InvalidMethodType { name, type_name } => write!(f, "invalid type '{}' for method '{}'", type_name, name),
             DescriptorErrorKind::InvalidExtendeeType { name, type_name } => write!(f, "invalid type '{}' for extension '{}'", type_name, name),
+            DescriptorErrorKind::ReadFile { path, .. } => write!(f, "failed to read file '{}'", path),
+            DescriptorErrorKind::ParseFile { name, line, column, message } => write!(f, "{}:{}:{}: {}", name, line, column, message),
+            DescriptorErrorKind::FileNotInIncludeDirs { path } => write!(f, "the file '{}' was not found in any include directory", path),
+            DescriptorErrorKind::CircularImport { cycle } => write!(f, "circular import detected: {}", cycle),
+            DescriptorErrorKind::InvalidFieldNumber { message, field, number } => write!(f, "the number {} of field '{}' in message '{}' is invalid", number, field, message),
+            DescriptorErrorKind::DuplicateFieldNumber { message, number } => write!(f, "the field number {} is used multiple times in message '{}'", number, message),
+            DescriptorErrorKind::InvalidField { name, reason } => write!(f, "the field '{}' is invalid: {}", name, reason),
+            DescriptorErrorKind::MergeConflicts { conflicts } => {
+                write!(f, "failed to merge descriptor pools: ")?;
+                for (index, conflict) in conflicts.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", conflict)?;
+                }
+                Ok(())
+            }
         }
     }
 }
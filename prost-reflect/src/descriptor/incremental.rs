@@ -0,0 +1,194 @@
+use std::collections::{BTreeSet, HashSet};
+
+use prost::bytes::Buf;
+use prost_types::FileDescriptorProto;
+
+use crate::{DescriptorError, DescriptorPool};
+
+use super::{stream::decode_file, EncodedFile};
+
+/// Builds a [`DescriptorPool`] from files which are received one at a time in any order, such as from the
+/// gRPC server reflection protocol or a schema registry.
+///
+/// Each file is added to the pool as soon as all of the files it imports have been added, and until then is
+/// held as a pending file. This means the [pool][Self::pool] only ever contains complete files whose types
+/// can all be resolved, and can be used while more files are still being received.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, FieldBuilder, FileDescriptorBuilder, IncrementalPool, MessageBuilder};
+/// # let mut files = DescriptorPool::new();
+/// # FileDescriptorBuilder::new("common.proto")
+/// #     .add_message(MessageBuilder::new("Id"))
+/// #     .add_to_pool(&mut files)
+/// #     .unwrap();
+/// # FileDescriptorBuilder::new("user.proto")
+/// #     .add_dependency("common.proto")
+/// #     .add_message(MessageBuilder::new("User").add_field(FieldBuilder::new("id", 1, "Id")))
+/// #     .add_to_pool(&mut files)
+/// #     .unwrap();
+/// # let common_proto = files.get_file_by_name("common.proto").unwrap().file_descriptor_proto().clone();
+/// # let user_proto = files.get_file_by_name("user.proto").unwrap().file_descriptor_proto().clone();
+/// let mut pool = IncrementalPool::new();
+///
+/// // The file is pending until its dependency is added.
+/// assert_eq!(pool.add_file_descriptor_proto(user_proto).unwrap(), 0);
+/// assert_eq!(pool.missing_dependencies(), ["common.proto"]);
+///
+/// assert_eq!(pool.add_file_descriptor_proto(common_proto).unwrap(), 2);
+/// assert!(pool.is_complete());
+/// let pool = pool.finalize().unwrap();
+/// assert!(pool.get_file_by_name("user.proto").is_some());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalPool {
+    pool: DescriptorPool,
+    pending: Vec<EncodedFile>,
+}
+
+impl IncrementalPool {
+    /// Creates a new [`IncrementalPool`], starting with an empty pool.
+    pub fn new() -> Self {
+        IncrementalPool::default()
+    }
+
+    /// Creates a new [`IncrementalPool`] which adds files to an existing pool.
+    pub fn with_pool(pool: DescriptorPool) -> Self {
+        IncrementalPool {
+            pool,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Adds a file, returning the number of files which were added to the pool as a result.
+    ///
+    /// If all of the file's dependencies are already in the pool, it is added immediately, along with any
+    /// pending files which were only waiting for it. Otherwise, it is held until its dependencies are added.
+    ///
+    /// Files which are identical to a file already in the pool or pending are ignored. If an error is
+    /// returned, for example because the file conflicts with another file or refers to a type which is
+    /// not defined in its dependencies, the file is discarded and this instance is not modified.
+    pub fn add_file_descriptor_proto(
+        &mut self,
+        file: FileDescriptorProto,
+    ) -> Result<usize, DescriptorError> {
+        self.add_file((file, None))
+    }
+
+    /// Decodes a file from its protobuf byte representation and adds it, as for
+    /// [`add_file_descriptor_proto`][Self::add_file_descriptor_proto].
+    ///
+    /// Like [`DescriptorPool::decode`], the encoded bytes are retained, so the values of custom options are
+    /// available from the resulting descriptors.
+    pub fn decode_file_descriptor_proto<B>(
+        &mut self,
+        mut bytes: B,
+    ) -> Result<usize, DescriptorError>
+    where
+        B: Buf,
+    {
+        let file = decode_file(bytes.copy_to_bytes(bytes.remaining()))?;
+        self.add_file(file)
+    }
+
+    /// Gets the pool containing the files which have been resolved so far.
+    pub fn pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    /// Gets an iterator over the files which are waiting for their dependencies to be added.
+    pub fn pending_files(&self) -> impl ExactSizeIterator<Item = &FileDescriptorProto> + '_ {
+        self.pending.iter().map(|(file, _)| file)
+    }
+
+    /// Gets the names of the files which are imported by a pending file, but have not been added yet, in
+    /// sorted order.
+    pub fn missing_dependencies(&self) -> Vec<&str> {
+        let missing: BTreeSet<&str> = self
+            .pending
+            .iter()
+            .flat_map(|(file, _)| file.dependency.iter())
+            .map(String::as_str)
+            .filter(|name| !self.is_pending(name) && self.pool.get_file_by_name(name).is_none())
+            .collect();
+        missing.into_iter().collect()
+    }
+
+    /// Returns `true` if there are no pending files.
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Adds all pending files to the pool and returns it.
+    ///
+    /// Returns an error if any pending files are still missing dependencies, or are otherwise invalid.
+    pub fn finalize(self) -> Result<DescriptorPool, DescriptorError> {
+        let mut pool = self.pool;
+        pool.add_encoded_files(self.pending)?;
+        Ok(pool)
+    }
+
+    fn add_file(&mut self, file: EncodedFile) -> Result<usize, DescriptorError> {
+        let existing = match self.pool.get_file_by_name(file.0.name()) {
+            Some(existing) => Some(existing.file_descriptor_proto() == &file.0),
+            None => self
+                .pending_files()
+                .find(|pending| pending.name() == file.0.name())
+                .map(|pending| pending == &file.0),
+        };
+        match existing {
+            Some(true) => return Ok(0),
+            Some(false) => return Err(DescriptorError::file_already_exists(file.0.name())),
+            None => self.pending.push(file),
+        }
+
+        let ready = self.ready_files();
+        if ready.is_empty() {
+            return Ok(0);
+        }
+
+        let files = self
+            .pending
+            .iter()
+            .filter(|(file, _)| ready.contains(file.name()))
+            .cloned();
+        match self.pool.add_encoded_files(files) {
+            Ok(()) => {
+                self.pending
+                    .retain(|(file, _)| !ready.contains(file.name()));
+                Ok(ready.len())
+            }
+            Err(err) => {
+                // The pool is unchanged on error, so only the new file needs to be removed.
+                self.pending.pop();
+                Err(err)
+            }
+        }
+    }
+
+    /// Gets the names of the pending files whose dependencies are all either in the pool or also ready.
+    fn ready_files(&self) -> HashSet<String> {
+        let mut ready = HashSet::new();
+        loop {
+            let ready_before = ready.len();
+            for (file, _) in &self.pending {
+                if !ready.contains(file.name())
+                    && file.dependency.iter().all(|dependency| {
+                        ready.contains(dependency)
+                            || self.pool.get_file_by_name(dependency).is_some()
+                    })
+                {
+                    ready.insert(file.name().to_owned());
+                }
+            }
+            if ready.len() == ready_before {
+                return ready;
+            }
+        }
+    }
+
+    fn is_pending(&self, name: &str) -> bool {
+        self.pending.iter().any(|(file, _)| file.name() == name)
+    }
+}
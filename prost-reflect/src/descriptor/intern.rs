@@ -0,0 +1,27 @@
+use std::{collections::HashSet, sync::Arc};
+
+/// A set of strings shared by the descriptors in a pool.
+///
+/// Short names such as `id` or `name` are typically used by many fields and enum values across a large
+/// set of files, so each distinct name is only stored once, and shared between the descriptors using it.
+#[derive(Clone, Default)]
+pub(super) struct Interner {
+    names: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Gets the shared copy of `name`, adding it to the set if it is not present yet.
+    pub fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(name) = self.names.get(name) {
+            return name.clone();
+        }
+
+        let name: Arc<str> = name.into();
+        self.names.insert(name.clone());
+        name
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.names.shrink_to_fit();
+    }
+}
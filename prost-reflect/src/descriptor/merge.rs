@@ -0,0 +1,230 @@
+use std::{collections::HashMap, fmt};
+
+use prost::{bytes::Bytes, Message};
+
+use crate::{DescriptorError, DescriptorPool, FileDescriptor};
+
+use super::FileDescriptorInner;
+
+/// Options to control the behavior of [`DescriptorPool::merge_with_options`].
+#[derive(Debug, Clone)]
+pub struct MergeOptions {
+    allow_identical_duplicates: bool,
+}
+
+/// A definition which is present in both pools passed to [`DescriptorPool::merge`].
+///
+/// Conflicts can be retrieved from the returned error using [`DescriptorError::merge_conflicts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    name: String,
+    existing_file: String,
+    other_file: String,
+    identical: bool,
+}
+
+impl DescriptorPool {
+    /// Adds all the files in `other` to this pool, failing if any definition in `other` conflicts with a
+    /// definition in this pool.
+    ///
+    /// This is equivalent to calling [`merge_with_options`][Self::merge_with_options] with the default
+    /// options, so any file present in both pools is reported as a conflict, even if the files are identical.
+    pub fn merge(&mut self, other: &DescriptorPool) -> Result<(), DescriptorError> {
+        self.merge_with_options(other, &MergeOptions::new())
+    }
+
+    /// Adds all the files in `other` to this pool, failing if any definition in `other` conflicts with a
+    /// definition in this pool.
+    ///
+    /// A conflict is reported for each file in `other` with the same name as a file in this pool, and for each
+    /// message, enum, extension or service in `other` with the same fully-qualified name as one defined in a
+    /// different file in this pool. If [`MergeOptions::allow_identical_duplicates`] is set, files whose
+    /// encoded bytes are identical in both pools are not reported, and are only added once.
+    ///
+    /// If there are any conflicts, this pool is not modified, and all conflicts are available from
+    /// [`DescriptorError::merge_conflicts`] on the returned error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, FileDescriptorBuilder, MergeOptions, MessageBuilder};
+    /// let mut pool = FileDescriptorBuilder::new("a.proto")
+    ///     .add_message(MessageBuilder::new("Shared"))
+    ///     .build()
+    ///     .unwrap();
+    /// let other = FileDescriptorBuilder::new("b.proto")
+    ///     .add_message(MessageBuilder::new("Shared"))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let err = pool.merge_with_options(&other, &MergeOptions::new()).unwrap_err();
+    /// assert_eq!(err.merge_conflicts().len(), 1);
+    /// assert_eq!(err.merge_conflicts()[0].name(), "Shared");
+    /// assert_eq!(err.merge_conflicts()[0].existing_file(), "a.proto");
+    /// assert_eq!(err.merge_conflicts()[0].other_file(), "b.proto");
+    /// ```
+    pub fn merge_with_options(
+        &mut self,
+        other: &DescriptorPool,
+        options: &MergeOptions,
+    ) -> Result<(), DescriptorError> {
+        let mut defined_names = HashMap::new();
+        for (file, names) in names_by_file(self) {
+            for name in names {
+                defined_names.insert(name, file.clone());
+            }
+        }
+        let mut other_names = names_by_file(other);
+
+        let mut conflicts = Vec::new();
+        for other_file in other.files() {
+            if let Some(existing_file) = self.get_file_by_name(other_file.name()) {
+                let identical =
+                    file_bytes(existing_file.file_inner()) == file_bytes(other_file.file_inner());
+                if !identical || !options.allow_identical_duplicates {
+                    conflicts.push(MergeConflict {
+                        name: other_file.name().to_owned(),
+                        existing_file: existing_file.name().to_owned(),
+                        other_file: other_file.name().to_owned(),
+                        identical,
+                    });
+                }
+                continue;
+            }
+
+            for name in other_names.remove(other_file.name()).unwrap_or_default() {
+                if let Some(existing_file) = defined_names.get(&name) {
+                    conflicts.push(MergeConflict {
+                        name,
+                        existing_file: existing_file.clone(),
+                        other_file: other_file.name().to_owned(),
+                        identical: false,
+                    });
+                }
+            }
+        }
+
+        if !conflicts.is_empty() {
+            return Err(DescriptorError::merge_conflict(conflicts));
+        }
+
+        self.add_encoded_files(
+            other
+                .inner
+                .files
+                .iter()
+                .map(|file| (file.raw.clone(), file.encoded.clone())),
+        )?;
+
+        let placeholders: Vec<String> = other
+            .files()
+            .filter(|file| file.is_placeholder())
+            .map(|file| file.name().to_owned())
+            .collect();
+        self.mark_placeholders(&placeholders);
+        Ok(())
+    }
+}
+
+impl MergeOptions {
+    /// Creates a new instance of [`MergeOptions`], with the default options chosen to detect all duplicates.
+    pub const fn new() -> Self {
+        MergeOptions {
+            allow_identical_duplicates: false,
+        }
+    }
+
+    /// Whether to allow files which are present in both pools, if their encoded bytes are identical.
+    ///
+    /// This is useful when both pools include common dependencies, such as the well-known types.
+    ///
+    /// The default value is `false`.
+    pub const fn allow_identical_duplicates(mut self, yes: bool) -> Self {
+        self.allow_identical_duplicates = yes;
+        self
+    }
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MergeConflict {
+    /// The name of the file, or the fully-qualified name of the message, enum, extension or service, which
+    /// is defined in both pools.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the file containing the definition in the pool being merged into.
+    pub fn existing_file(&self) -> &str {
+        &self.existing_file
+    }
+
+    /// The name of the file containing the definition in the pool being merged.
+    pub fn other_file(&self) -> &str {
+        &self.other_file
+    }
+
+    /// Returns `true` if this is a file which is present in both pools with identical contents. Such
+    /// conflicts can be allowed using [`MergeOptions::allow_identical_duplicates`].
+    pub fn is_identical(&self) -> bool {
+        self.identical
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.identical {
+            write!(f, "the file '{}' is present in both pools", self.name)
+        } else if self.name == self.other_file {
+            write!(f, "the file '{}' differs between the pools", self.name)
+        } else {
+            write!(
+                f,
+                "'{}' is defined in both '{}' and '{}'",
+                self.name, self.existing_file, self.other_file
+            )
+        }
+    }
+}
+
+/// Gets the full names of the top-level messages, enums, extensions and services defined in each file of the pool.
+fn names_by_file(pool: &DescriptorPool) -> HashMap<String, Vec<String>> {
+    let mut names: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add = |file: FileDescriptor, name: &str| {
+        names
+            .entry(file.name().to_owned())
+            .or_default()
+            .push(name.to_owned())
+    };
+    // Nested definitions can only conflict if their parent message does, so only top-level names are included.
+    for message in pool.all_messages() {
+        if message.parent_message().is_none() {
+            add(message.parent_file(), message.full_name());
+        }
+    }
+    for enum_desc in pool.all_enums() {
+        if enum_desc.parent_message().is_none() {
+            add(enum_desc.parent_file(), enum_desc.full_name());
+        }
+    }
+    for extension in pool.all_extensions() {
+        if extension.parent_message().is_none() {
+            add(extension.parent_file(), extension.full_name());
+        }
+    }
+    for service in pool.services() {
+        add(service.parent_file(), service.full_name());
+    }
+    names
+}
+
+fn file_bytes(file: &FileDescriptorInner) -> Bytes {
+    match &file.encoded {
+        Some(encoded) => encoded.clone(),
+        None => Bytes::from(file.raw.encode_to_vec()),
+    }
+}
@@ -1,10 +1,31 @@
+mod builder;
+mod closure;
+mod compile;
+mod compress;
 mod error;
+mod incremental;
+mod intern;
+mod merge;
+mod options;
+mod path;
+mod placeholder;
 mod service;
+mod source;
+mod stream;
 mod ty;
 
 pub use self::{
-    error::DescriptorError,
+    builder::{
+        EnumBuilder, FieldBuilder, FileDescriptorBuilder, MessageBuilder, MethodBuilder,
+        ServiceBuilder,
+    },
+    compress::Compression,
+    error::{DescriptorDiagnostic, DescriptorError, Location},
+    incremental::IncrementalPool,
+    merge::{MergeConflict, MergeOptions},
+    placeholder::PoolOptions,
     service::{MethodDescriptor, ServiceDescriptor},
+    stream::FileDescriptorSetDecoder,
     ty::{
         Cardinality, EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor, FieldDescriptor,
         Kind, MessageDescriptor, OneofDescriptor,
@@ -13,10 +34,13 @@ pub use self::{
 
 use std::{collections::HashMap, convert::TryInto, fmt, iter, ops::Range, sync::Arc};
 
-use prost::{bytes::Buf, Message};
+use prost::bytes::{Buf, Bytes};
 use prost_types::{FileDescriptorProto, FileDescriptorSet};
 
-use self::service::ServiceDescriptorInner;
+use self::{
+    path::{FILE_DEPENDENCY, FILE_PUBLIC_DEPENDENCY, FILE_SYNTAX},
+    service::ServiceDescriptorInner,
+};
 
 pub(crate) const MAP_ENTRY_KEY_NUMBER: u32 = 1;
 pub(crate) const MAP_ENTRY_VALUE_NUMBER: u32 = 2;
@@ -41,6 +65,7 @@ struct DescriptorPoolInner {
     file_names: HashMap<Box<str>, FileIndex>,
     type_map: ty::TypeMap,
     services: Vec<ServiceDescriptorInner>,
+    service_names: HashMap<Arc<str>, ServiceIndex>,
 }
 
 /// A single source file containing protobuf messages and services.
@@ -53,8 +78,15 @@ pub struct FileDescriptor {
 #[derive(Clone)]
 struct FileDescriptorInner {
     raw: FileDescriptorProto,
+    /// The encoded bytes of the file, if it was decoded from bytes. Unlike `raw`, this includes the
+    /// values of any custom options.
+    encoded: Option<Bytes>,
+    /// The index of each location in the source code info, by path.
+    locations: HashMap<Vec<i32>, usize>,
     syntax: Syntax,
     services: Range<ServiceIndex>,
+    /// Whether this file was created to stand in for a missing import or unresolved type.
+    placeholder: bool,
 }
 
 /// The syntax of a proto file.
@@ -66,6 +98,9 @@ pub enum Syntax {
     Proto3,
 }
 
+/// A file descriptor, along with its encoded bytes if it was decoded from bytes.
+type EncodedFile = (FileDescriptorProto, Option<Bytes>);
+
 type FileIndex = u32;
 type ServiceIndex = u32;
 type MethodIndex = u32;
@@ -98,14 +133,18 @@ impl DescriptorPool {
 
     /// Decodes a [`FileDescriptorSet`] from its protobuf byte representation and
     /// creates a new [`DescriptorPool`] wrapping it.
-    pub fn decode<B>(bytes: B) -> Result<Self, DescriptorError>
+    ///
+    /// Unlike [`DescriptorPool::from_file_descriptor_set`], the encoded bytes of each file are retained,
+    /// so the values of custom options are available from methods such as [`MessageDescriptor::options`].
+    pub fn decode<B>(mut bytes: B) -> Result<Self, DescriptorError>
     where
         B: Buf,
     {
-        DescriptorPool::from_file_descriptor_set(
-            FileDescriptorSet::decode(bytes)
-                .map_err(DescriptorError::decode_file_descriptor_set)?,
-        )
+        let files = stream::decode_file_descriptor_set(bytes.copy_to_bytes(bytes.remaining()))?;
+
+        let mut pool = DescriptorPool::new();
+        pool.add_encoded_files(files)?;
+        Ok(pool)
     }
 
     /// Adds a new [`FileDescriptorSet`] to this [`DescriptorPool`].
@@ -136,34 +175,53 @@ impl DescriptorPool {
     pub fn add_file_descriptor_protos<I>(&mut self, files: I) -> Result<(), DescriptorError>
     where
         I: IntoIterator<Item = FileDescriptorProto>,
+    {
+        self.add_encoded_files(files.into_iter().map(|file| (file, None)))
+    }
+
+    /// Adds a collection of file descriptors to this pool, along with their encoded bytes if available.
+    fn add_encoded_files<I>(&mut self, files: I) -> Result<(), DescriptorError>
+    where
+        I: IntoIterator<Item = EncodedFile>,
     {
         // Note we could use `Arc::make_mut` here but by always cloning we
         // avoid putting the pool into an inconsistent state on error.
         let mut inner = (*self.inner).clone();
 
-        let file_indices = inner.build_files(files)?;
+        // All problems with the files are collected, rather than returning the first.
+        let mut errors = Vec::new();
+        let file_indices = inner.build_files(files, &mut errors);
         let files = &mut inner.files;
 
-        inner.type_map.add_files(
+        if let Err(err) = inner.type_map.add_files(
             file_indices
                 .clone()
                 .map(|file_index| (file_index, &files[file_index as usize])),
-        )?;
+        ) {
+            errors.push(err);
+        }
         inner.type_map.shrink_to_fit();
 
         for file_index in file_indices {
             let file = &mut files[file_index as usize];
             let start: ServiceIndex = to_index(inner.services.len());
-            for service in &file.raw.service {
-                inner.services.push(ServiceDescriptorInner::from_raw(
-                    &file.raw,
-                    file_index,
-                    service,
-                    &inner.type_map,
-                )?);
+            for index in 0..file.raw.service.len() {
+                match ServiceDescriptorInner::from_raw(file, file_index, index, &inner.type_map) {
+                    Ok(service) => {
+                        let service_index = to_index(inner.services.len());
+                        inner
+                            .service_names
+                            .entry(service.full_name.clone())
+                            .or_insert(service_index);
+                        inner.services.push(service);
+                    }
+                    Err(err) => errors.push(err),
+                }
             }
             file.services = start..to_index(inner.services.len());
         }
+        inner.service_names.shrink_to_fit();
+        DescriptorError::combine(errors)?;
 
         if let Some(old_inner) = Arc::get_mut(&mut self.inner) {
             *old_inner = inner;
@@ -184,6 +242,96 @@ impl DescriptorPool {
         self.add_file_descriptor_protos(iter::once(file))
     }
 
+    /// Removes the file with the given name from this pool.
+    ///
+    /// This returns an error if the pool does not contain the file, or if it is imported by another file in
+    /// the pool, in which case the pool is not modified. Descriptors obtained from the pool before the file
+    /// was removed, including descriptors for the types defined in the file, remain valid and continue to
+    /// refer to the pool as it was before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, FileDescriptorBuilder, MessageBuilder};
+    /// let mut pool = FileDescriptorBuilder::new("a.proto")
+    ///     .add_message(MessageBuilder::new("A"))
+    ///     .build()
+    ///     .unwrap();
+    /// let message = pool.get_message_by_name("A").unwrap();
+    ///
+    /// pool.remove_file("a.proto").unwrap();
+    /// assert!(pool.get_message_by_name("A").is_none());
+    /// assert_eq!(message.full_name(), "A");
+    /// ```
+    pub fn remove_file(&mut self, name: &str) -> Result<(), DescriptorError> {
+        if self.get_file_by_name(name).is_none() {
+            return Err(DescriptorError::file_not_in_pool(name));
+        }
+        if let Some(dependent) = self
+            .file_descriptor_protos()
+            .find(|file| file.dependency.iter().any(|dependency| dependency == name))
+        {
+            return Err(DescriptorError::file_in_use(name, dependent.name()));
+        }
+
+        let files: Vec<EncodedFile> = self
+            .inner
+            .files
+            .iter()
+            .filter(|file| file.raw.name() != name)
+            .map(|file| (file.raw.clone(), file.encoded.clone()))
+            .collect();
+        self.rebuild(files)
+    }
+
+    /// Replaces the file with the same name as `file` in this pool.
+    ///
+    /// The new file is checked along with the files which import it: all of its dependencies must be in the
+    /// pool, and any types which other files in the pool use from it must still be defined. If any check
+    /// fails, an error is returned and the pool is not modified. This returns an error if the pool does not
+    /// contain a file with the same name; use [`add_file_descriptor_proto`][Self::add_file_descriptor_proto]
+    /// to add a new file.
+    ///
+    /// Descriptors obtained from the pool before the file was replaced remain valid and continue to refer to
+    /// the pool as it was before.
+    pub fn replace_file(&mut self, file: FileDescriptorProto) -> Result<(), DescriptorError> {
+        let index = match self.inner.file_names.get(file.name()) {
+            Some(&index) => index as usize,
+            None => return Err(DescriptorError::file_not_in_pool(file.name())),
+        };
+        if self.inner.files[index].raw == file {
+            return Ok(());
+        }
+
+        let mut files: Vec<EncodedFile> = self
+            .inner
+            .files
+            .iter()
+            .map(|file| (file.raw.clone(), file.encoded.clone()))
+            .collect();
+        files[index] = (file, None);
+        self.rebuild(files)
+    }
+
+    /// Replaces the contents of this pool with a new pool built from the given files, leaving it unchanged
+    /// on error.
+    fn rebuild<I>(&mut self, files: I) -> Result<(), DescriptorError>
+    where
+        I: IntoIterator<Item = EncodedFile>,
+    {
+        let placeholders: Vec<String> = self
+            .files()
+            .filter(|file| file.is_placeholder())
+            .map(|file| file.name().to_owned())
+            .collect();
+
+        let mut pool = DescriptorPool::new();
+        pool.add_encoded_files(files)?;
+        pool.mark_placeholders(&placeholders);
+        *self = pool;
+        Ok(())
+    }
+
     /// Gets an iterator over the file descriptors added to this pool.
     pub fn files(&self) -> impl ExactSizeIterator<Item = FileDescriptor> + '_ {
         FileDescriptor::iter(self)
@@ -239,57 +387,92 @@ impl DescriptorPool {
     pub fn get_enum_by_name(&self, name: &str) -> Option<EnumDescriptor> {
         EnumDescriptor::try_get_by_name(self, name)
     }
+
+    /// Gets an [`ExtensionDescriptor`] by its fully qualified name, for example `my.package.my_extension`.
+    pub fn get_extension_by_name(&self, name: &str) -> Option<ExtensionDescriptor> {
+        ExtensionDescriptor::try_get_by_name(self, name)
+    }
+
+    /// Gets a [`ServiceDescriptor`] by its fully qualified name, for example `my.package.MyService`.
+    pub fn get_service_by_name(&self, name: &str) -> Option<ServiceDescriptor> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        self.inner
+            .service_names
+            .get(name)
+            .map(|&index| ServiceDescriptor::new(self.clone(), index as _))
+    }
 }
 
 impl DescriptorPoolInner {
     fn build_files(
         &mut self,
-        files: impl IntoIterator<Item = FileDescriptorProto>,
-    ) -> Result<Range<FileIndex>, DescriptorError> {
+        files: impl IntoIterator<Item = EncodedFile>,
+        errors: &mut Vec<DescriptorError>,
+    ) -> Range<FileIndex> {
         let start = self.files.len();
 
-        for file in files {
+        for (file, encoded) in files {
             let syntax = match file.syntax.as_deref() {
-                None | Some("proto2") => Syntax::Proto2,
-                Some("proto3") => Syntax::Proto3,
-                Some(s) => return Err(DescriptorError::unknown_syntax(s)),
+                None | Some("proto2") => Ok(Syntax::Proto2),
+                Some("proto3") => Ok(Syntax::Proto3),
+                Some(s) => Err(DescriptorError::unknown_syntax(s)),
             };
 
-            match self.files.iter().find(|f| f.raw.name() == file.name()) {
+            match self.file_names.get(file.name()) {
                 None => {
                     let index = to_index(self.files.len());
                     self.file_names.insert(file.name().into(), index);
                     self.files.push(FileDescriptorInner {
+                        locations: source::index_locations(&file),
                         raw: file,
-                        syntax,
+                        encoded,
+                        syntax: *syntax.as_ref().unwrap_or(&Syntax::Proto2),
                         services: Default::default(),
+                        placeholder: false,
                     });
+                    if let Err(err) = syntax {
+                        errors.push(err.with_context(
+                            &self.files[index as usize],
+                            None,
+                            &[FILE_SYNTAX],
+                        ));
+                    }
                 }
                 // Skip duplicate files only if they match exactly
-                Some(existing_file) if existing_file.raw == file => continue,
-                Some(_) => return Err(DescriptorError::file_already_exists(file.name())),
+                Some(&existing) if self.files[existing as usize].raw == file => continue,
+                Some(_) => errors.push(DescriptorError::file_already_exists(file.name())),
             }
         }
 
         let end = self.files.len();
 
         for file in &self.files[start..end] {
-            for dependency in &file.raw.dependency {
+            for (index, dependency) in file.raw.dependency.iter().enumerate() {
                 if !self.file_names.contains_key(dependency.as_str()) {
-                    return Err(DescriptorError::file_not_found(file.raw.name(), dependency));
+                    errors.push(
+                        DescriptorError::file_not_found(file.raw.name(), dependency).with_context(
+                            file,
+                            None,
+                            &[FILE_DEPENDENCY, to_index(index) as i32],
+                        ),
+                    );
                 }
             }
-            for &dependency_index in &file.raw.public_dependency {
+            for (index, &dependency_index) in file.raw.public_dependency.iter().enumerate() {
                 if (dependency_index as usize) >= file.raw.dependency.len() {
-                    return Err(DescriptorError::file_not_found(
-                        file.raw.name(),
-                        dependency_index,
-                    ));
+                    errors.push(
+                        DescriptorError::file_not_found(file.raw.name(), dependency_index)
+                            .with_context(
+                                file,
+                                None,
+                                &[FILE_PUBLIC_DEPENDENCY, to_index(index) as i32],
+                            ),
+                    );
                 }
             }
         }
 
-        Ok(to_index(start)..to_index(end))
+        to_index(start)..to_index(end)
     }
 }
 
@@ -450,7 +633,7 @@ impl fmt::Debug for Syntax {
     }
 }
 
-fn make_full_name(namespace: &str, name: &str) -> Box<str> {
+fn make_full_name(namespace: &str, name: &str) -> Arc<str> {
     let namespace = namespace.trim_start_matches('.');
     if namespace.is_empty() {
         name.into()
@@ -459,7 +642,7 @@ fn make_full_name(namespace: &str, name: &str) -> Box<str> {
         full_name.push_str(namespace);
         full_name.push('.');
         full_name.push_str(name);
-        full_name.into_boxed_str()
+        full_name.into()
     }
 }
 
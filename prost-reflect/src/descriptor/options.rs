@@ -0,0 +1,265 @@
+use prost::{
+    encoding::{decode_key, decode_varint, WireType},
+    Message,
+};
+
+use crate::{
+    DescriptorPool, DynamicMessage, EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor,
+    FieldDescriptor, FileDescriptor, MessageDescriptor, MethodDescriptor, OneofDescriptor,
+    ServiceDescriptor,
+};
+
+const FILE_OPTIONS: u32 = 8;
+const MESSAGE_OPTIONS: u32 = 7;
+const FIELD_OPTIONS: u32 = 8;
+const ONEOF_OPTIONS: u32 = 2;
+const ENUM_OPTIONS: u32 = 3;
+const ENUM_VALUE_OPTIONS: u32 = 3;
+const SERVICE_OPTIONS: u32 = 3;
+const METHOD_OPTIONS: u32 = 4;
+
+impl FileDescriptor {
+    /// Gets the options of this file as a [`DynamicMessage`] of type `google.protobuf.FileOptions`,
+    /// including any custom options.
+    ///
+    /// See [`MessageDescriptor::options`] for more details.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            self,
+            &[],
+            FILE_OPTIONS,
+            "google.protobuf.FileOptions",
+            self.file_descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+impl MessageDescriptor {
+    /// Gets the options of this message as a [`DynamicMessage`] of type `google.protobuf.MessageOptions`,
+    /// including any custom options.
+    ///
+    /// Custom options are set as extension fields of the returned message, which can be read with
+    /// [`DynamicMessage::get_extension`]. The options type is looked up in the pool containing this
+    /// message, so any custom options defined in the pool are resolved. If the pool does not contain
+    /// `google/protobuf/descriptor.proto`, a built-in copy is used when the `reflect-well-known-types`
+    /// feature is enabled, and otherwise `None` is returned.
+    ///
+    /// Custom options are discarded when a [`FileDescriptorProto`][prost_types::FileDescriptorProto] is
+    /// decoded by `prost`, so their values are only available if the pool was created with
+    /// [`DescriptorPool::decode`] or a [`FileDescriptorSetDecoder`][crate::FileDescriptorSetDecoder],
+    /// which keep the encoded bytes of each file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, ReflectMessage};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let options = message_descriptor.options().unwrap();
+    /// assert_eq!(options.descriptor().full_name(), "google.protobuf.MessageOptions");
+    /// ```
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            &self.parent_file(),
+            &self.path(),
+            MESSAGE_OPTIONS,
+            "google.protobuf.MessageOptions",
+            self.descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+impl FieldDescriptor {
+    /// Gets the options of this field as a [`DynamicMessage`] of type `google.protobuf.FieldOptions`,
+    /// including any custom options.
+    ///
+    /// See [`MessageDescriptor::options`] for more details.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            &self.parent_file(),
+            &self.path(),
+            FIELD_OPTIONS,
+            "google.protobuf.FieldOptions",
+            self.field_descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+impl ExtensionDescriptor {
+    /// Gets the options of this extension field as a [`DynamicMessage`] of type
+    /// `google.protobuf.FieldOptions`, including any custom options.
+    ///
+    /// See [`MessageDescriptor::options`] for more details.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            &self.parent_file(),
+            &self.path(),
+            FIELD_OPTIONS,
+            "google.protobuf.FieldOptions",
+            self.field_descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+impl OneofDescriptor {
+    /// Gets the options of this oneof as a [`DynamicMessage`] of type `google.protobuf.OneofOptions`,
+    /// including any custom options.
+    ///
+    /// See [`MessageDescriptor::options`] for more details.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            &self.parent_file(),
+            &self.path(),
+            ONEOF_OPTIONS,
+            "google.protobuf.OneofOptions",
+            self.oneof_descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+impl EnumDescriptor {
+    /// Gets the options of this enum as a [`DynamicMessage`] of type `google.protobuf.EnumOptions`,
+    /// including any custom options.
+    ///
+    /// See [`MessageDescriptor::options`] for more details.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            &self.parent_file(),
+            &self.path(),
+            ENUM_OPTIONS,
+            "google.protobuf.EnumOptions",
+            self.enum_descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+impl EnumValueDescriptor {
+    /// Gets the options of this enum value as a [`DynamicMessage`] of type
+    /// `google.protobuf.EnumValueOptions`, including any custom options.
+    ///
+    /// See [`MessageDescriptor::options`] for more details.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            &self.parent_file(),
+            &self.path(),
+            ENUM_VALUE_OPTIONS,
+            "google.protobuf.EnumValueOptions",
+            self.enum_value_descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+impl ServiceDescriptor {
+    /// Gets the options of this service as a [`DynamicMessage`] of type `google.protobuf.ServiceOptions`,
+    /// including any custom options.
+    ///
+    /// See [`MessageDescriptor::options`] for more details.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            &self.parent_file(),
+            &self.path(),
+            SERVICE_OPTIONS,
+            "google.protobuf.ServiceOptions",
+            self.service_descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+impl MethodDescriptor {
+    /// Gets the options of this method as a [`DynamicMessage`] of type `google.protobuf.MethodOptions`,
+    /// including any custom options.
+    ///
+    /// See [`MessageDescriptor::options`] for more details.
+    pub fn options(&self) -> Option<DynamicMessage> {
+        options(
+            &self.parent_file(),
+            &self.path(),
+            METHOD_OPTIONS,
+            "google.protobuf.MethodOptions",
+            self.method_descriptor_proto().options.as_ref(),
+        )
+    }
+}
+
+fn options<T>(
+    file: &FileDescriptor,
+    path: &[i32],
+    number: u32,
+    name: &str,
+    raw: Option<&T>,
+) -> Option<DynamicMessage>
+where
+    T: Message,
+{
+    let desc = options_descriptor(file.parent_pool(), name)?;
+
+    if let Some(encoded) = &file.file_inner().encoded {
+        if let Some(message) = decode_options(&desc, encoded, path, number) {
+            return Some(message);
+        }
+    }
+
+    let mut message = DynamicMessage::new(desc);
+    if let Some(raw) = raw {
+        message.transcode_from(raw).ok()?;
+    }
+    Some(message)
+}
+
+fn options_descriptor(pool: &DescriptorPool, name: &str) -> Option<MessageDescriptor> {
+    if let Some(desc) = pool.get_message_by_name(name) {
+        return Some(desc);
+    }
+
+    #[cfg(feature = "reflect-well-known-types")]
+    return crate::reflect::well_known_types().get_message_by_name(name);
+    #[cfg(not(feature = "reflect-well-known-types"))]
+    return None;
+}
+
+/// Decodes the options of the element at `path` in an encoded `FileDescriptorProto`.
+fn decode_options(
+    desc: &MessageDescriptor,
+    mut buf: &[u8],
+    path: &[i32],
+    number: u32,
+) -> Option<DynamicMessage> {
+    for step in path.chunks(2) {
+        buf = *find_fields(buf, step[0] as u32)?.get(step[1] as usize)?;
+    }
+
+    // If the options field occurs multiple times, the occurrences are merged.
+    let mut message = DynamicMessage::new(desc.clone());
+    for value in find_fields(buf, number)? {
+        message.merge(value).ok()?;
+    }
+    Some(message)
+}
+
+/// Finds every occurrence of the length-delimited field `number` in an encoded message.
+fn find_fields(mut buf: &[u8], number: u32) -> Option<Vec<&[u8]>> {
+    let mut result = Vec::new();
+    while !buf.is_empty() {
+        let (tag, wire_type) = decode_key(&mut buf).ok()?;
+        let len = match wire_type {
+            WireType::Varint => {
+                decode_varint(&mut buf).ok()?;
+                0
+            }
+            WireType::SixtyFourBit => 8,
+            WireType::ThirtyTwoBit => 4,
+            WireType::LengthDelimited => decode_varint(&mut buf).ok()? as usize,
+            WireType::StartGroup | WireType::EndGroup => return None,
+        };
+        if len > buf.len() {
+            return None;
+        }
+
+        let (value, rest) = buf.split_at(len);
+        if tag == number && wire_type == WireType::LengthDelimited {
+            result.push(value);
+        }
+        buf = rest;
+    }
+    Some(result)
+}
@@ -0,0 +1,142 @@
+//! Computes the path of each descriptor within its file, as used by `SourceCodeInfo` locations.
+//!
+//! A path is a sequence of field numbers and indices in repeated fields leading from the
+//! `FileDescriptorProto` to the element.
+
+use crate::{
+    EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor, FieldDescriptor, MessageDescriptor,
+    MethodDescriptor, OneofDescriptor, ServiceDescriptor,
+};
+
+pub(super) const FILE_DEPENDENCY: i32 = 3;
+pub(super) const FILE_MESSAGE_TYPE: i32 = 4;
+pub(super) const FILE_ENUM_TYPE: i32 = 5;
+pub(super) const FILE_SERVICE: i32 = 6;
+pub(super) const FILE_EXTENSION: i32 = 7;
+pub(super) const FILE_PUBLIC_DEPENDENCY: i32 = 10;
+pub(super) const FILE_SYNTAX: i32 = 12;
+pub(super) const MESSAGE_FIELD: i32 = 2;
+pub(super) const MESSAGE_NESTED_TYPE: i32 = 3;
+pub(super) const MESSAGE_ENUM_TYPE: i32 = 4;
+pub(super) const MESSAGE_EXTENSION: i32 = 6;
+pub(super) const MESSAGE_ONEOF_DECL: i32 = 8;
+pub(super) const ENUM_VALUE: i32 = 2;
+pub(super) const SERVICE_METHOD: i32 = 2;
+
+impl MessageDescriptor {
+    pub(super) fn path(&self) -> Vec<i32> {
+        match self.parent_message() {
+            Some(parent) => {
+                let index = position(&parent.descriptor_proto().nested_type, |raw| {
+                    raw.name() == self.name()
+                });
+                with_path(parent.path(), &[MESSAGE_NESTED_TYPE, index])
+            }
+            None => {
+                let index = position(&self.parent_file_descriptor_proto().message_type, |raw| {
+                    raw.name() == self.name()
+                });
+                vec![FILE_MESSAGE_TYPE, index]
+            }
+        }
+    }
+}
+
+impl FieldDescriptor {
+    pub(super) fn path(&self) -> Vec<i32> {
+        let message = self.parent_message();
+        let index = position(&message.descriptor_proto().field, |raw| {
+            raw.number() as u32 == self.number()
+        });
+        with_path(message.path(), &[MESSAGE_FIELD, index])
+    }
+}
+
+impl ExtensionDescriptor {
+    pub(super) fn path(&self) -> Vec<i32> {
+        match self.parent_message() {
+            Some(parent) => {
+                let index = position(&parent.descriptor_proto().extension, |raw| {
+                    raw.name() == self.name()
+                });
+                with_path(parent.path(), &[MESSAGE_EXTENSION, index])
+            }
+            None => {
+                let index = position(&self.parent_file_descriptor_proto().extension, |raw| {
+                    raw.name() == self.name()
+                });
+                vec![FILE_EXTENSION, index]
+            }
+        }
+    }
+}
+
+impl OneofDescriptor {
+    pub(super) fn path(&self) -> Vec<i32> {
+        let message = self.parent_message();
+        let index = position(&message.descriptor_proto().oneof_decl, |raw| {
+            raw.name() == self.name()
+        });
+        with_path(message.path(), &[MESSAGE_ONEOF_DECL, index])
+    }
+}
+
+impl EnumDescriptor {
+    pub(super) fn path(&self) -> Vec<i32> {
+        match self.parent_message() {
+            Some(parent) => {
+                let index = position(&parent.descriptor_proto().enum_type, |raw| {
+                    raw.name() == self.name()
+                });
+                with_path(parent.path(), &[MESSAGE_ENUM_TYPE, index])
+            }
+            None => {
+                let index = position(&self.parent_file_descriptor_proto().enum_type, |raw| {
+                    raw.name() == self.name()
+                });
+                vec![FILE_ENUM_TYPE, index]
+            }
+        }
+    }
+}
+
+impl EnumValueDescriptor {
+    pub(super) fn path(&self) -> Vec<i32> {
+        let parent = self.parent_enum();
+        let index = position(&parent.enum_descriptor_proto().value, |raw| {
+            raw.name() == self.name()
+        });
+        with_path(parent.path(), &[ENUM_VALUE, index])
+    }
+}
+
+impl ServiceDescriptor {
+    pub(super) fn path(&self) -> Vec<i32> {
+        let index = position(&self.parent_file().file_descriptor_proto().service, |raw| {
+            raw.name() == self.name()
+        });
+        vec![FILE_SERVICE, index]
+    }
+}
+
+impl MethodDescriptor {
+    pub(super) fn path(&self) -> Vec<i32> {
+        let service = self.parent_service();
+        let index = position(&service.service_descriptor_proto().method, |raw| {
+            raw.name() == self.name()
+        });
+        with_path(service.path(), &[SERVICE_METHOD, index])
+    }
+}
+
+fn position<T>(items: &[T], f: impl FnMut(&T) -> bool) -> i32 {
+    items
+        .iter()
+        .position(f)
+        .expect("descriptor not found in parent") as i32
+}
+
+fn with_path(mut path: Vec<i32>, suffix: &[i32]) -> Vec<i32> {
+    path.extend_from_slice(suffix);
+    path
+}
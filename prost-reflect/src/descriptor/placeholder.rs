@@ -0,0 +1,409 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::Arc,
+};
+
+use prost::bytes::Buf;
+use prost_types::{
+    descriptor_proto::ExtensionRange, field_descriptor_proto, DescriptorProto, EnumDescriptorProto,
+    EnumValueDescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+};
+
+use crate::{DescriptorError, DescriptorPool, EnumDescriptor, FileDescriptor, MessageDescriptor};
+
+use super::{make_full_name, parse_name, parse_namespace, stream, EncodedFile};
+
+/// The field numbers accepted by placeholder messages as extensions, which is every valid field number.
+const PLACEHOLDER_EXTENSION_RANGE: (i32, i32) = (1, 536_870_912);
+/// The name of the single value defined by placeholder enums.
+const PLACEHOLDER_ENUM_VALUE: &str = "PLACEHOLDER_VALUE";
+
+/// Options to control how files are added to a [`DescriptorPool`], used by methods such as
+/// [`DescriptorPool::add_file_descriptor_protos_with_options`].
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    allow_unknown_dependencies: bool,
+}
+
+impl DescriptorPool {
+    /// Creates a [`DescriptorPool`] from a [`FileDescriptorSet`], with the given options.
+    ///
+    /// See [`DescriptorPool::from_file_descriptor_set`] and [`PoolOptions`] for details.
+    pub fn from_file_descriptor_set_with_options(
+        file_descriptor_set: FileDescriptorSet,
+        options: &PoolOptions,
+    ) -> Result<Self, DescriptorError> {
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_protos_with_options(file_descriptor_set.file, options)?;
+        Ok(pool)
+    }
+
+    /// Decodes a [`FileDescriptorSet`] from its protobuf byte representation and creates a new
+    /// [`DescriptorPool`] wrapping it, with the given options.
+    ///
+    /// See [`DescriptorPool::decode`] and [`PoolOptions`] for details.
+    pub fn decode_with_options<B>(
+        mut bytes: B,
+        options: &PoolOptions,
+    ) -> Result<Self, DescriptorError>
+    where
+        B: Buf,
+    {
+        let files = stream::decode_file_descriptor_set(bytes.copy_to_bytes(bytes.remaining()))?;
+
+        let mut pool = DescriptorPool::new();
+        pool.add_encoded_files_with_options(files, options)?;
+        Ok(pool)
+    }
+
+    /// Adds a collection of file descriptors to this pool, with the given options.
+    ///
+    /// See [`DescriptorPool::add_file_descriptor_protos`] and [`PoolOptions`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, FieldBuilder, FileDescriptorBuilder, Kind, MessageBuilder, PoolOptions};
+    /// # let mut files = DescriptorPool::new();
+    /// # FileDescriptorBuilder::new("common.proto")
+    /// #     .package("common")
+    /// #     .add_message(MessageBuilder::new("Id"))
+    /// #     .add_to_pool(&mut files)
+    /// #     .unwrap();
+    /// # FileDescriptorBuilder::new("user.proto")
+    /// #     .add_dependency("common.proto")
+    /// #     .add_message(MessageBuilder::new("User").add_field(FieldBuilder::new("id", 1, ".common.Id")))
+    /// #     .add_to_pool(&mut files)
+    /// #     .unwrap();
+    /// # let user_proto = files.get_file_by_name("user.proto").unwrap().file_descriptor_proto().clone();
+    /// // `user.proto` imports `common.proto`, which is not available.
+    /// let mut pool = DescriptorPool::new();
+    /// assert!(pool.add_file_descriptor_proto(user_proto.clone()).is_err());
+    ///
+    /// let options = PoolOptions::new().allow_unknown_dependencies(true);
+    /// pool.add_file_descriptor_protos_with_options([user_proto], &options).unwrap();
+    ///
+    /// let field = pool.get_message_by_name("User").unwrap().get_field_by_name("id").unwrap();
+    /// let id = match field.kind() {
+    ///     Kind::Message(id) => id,
+    ///     _ => unreachable!(),
+    /// };
+    /// assert_eq!(id.full_name(), "common.Id");
+    /// assert!(id.is_placeholder());
+    /// assert!(pool.get_file_by_name("common.proto").unwrap().is_placeholder());
+    /// ```
+    pub fn add_file_descriptor_protos_with_options<I>(
+        &mut self,
+        files: I,
+        options: &PoolOptions,
+    ) -> Result<(), DescriptorError>
+    where
+        I: IntoIterator<Item = FileDescriptorProto>,
+    {
+        self.add_encoded_files_with_options(
+            files.into_iter().map(|file| (file, None)).collect(),
+            options,
+        )
+    }
+
+    fn add_encoded_files_with_options(
+        &mut self,
+        files: Vec<EncodedFile>,
+        options: &PoolOptions,
+    ) -> Result<(), DescriptorError> {
+        if !options.allow_unknown_dependencies {
+            return self.add_encoded_files(files);
+        }
+
+        let placeholders = placeholder_files(self, &files);
+        let placeholder_names: Vec<String> = placeholders
+            .iter()
+            .map(|file| file.name().to_owned())
+            .collect();
+        self.add_encoded_files(
+            files
+                .into_iter()
+                .chain(placeholders.into_iter().map(|file| (file, None))),
+        )?;
+        self.mark_placeholders(&placeholder_names);
+        Ok(())
+    }
+
+    /// Marks the files with the given names as placeholders.
+    pub(super) fn mark_placeholders(&mut self, names: &[String]) {
+        if names.is_empty() {
+            return;
+        }
+
+        let inner = Arc::make_mut(&mut self.inner);
+        for name in names {
+            if let Some(&index) = inner.file_names.get(name.as_str()) {
+                inner.files[index as usize].placeholder = true;
+            }
+        }
+    }
+}
+
+impl PoolOptions {
+    /// Creates a new instance of [`PoolOptions`], with the default options chosen to match the
+    /// behavior of methods such as [`DescriptorPool::add_file_descriptor_protos`].
+    pub const fn new() -> Self {
+        PoolOptions {
+            allow_unknown_dependencies: false,
+        }
+    }
+
+    /// Whether to allow files to import files which are not in the pool, and refer to types which are
+    /// not defined.
+    ///
+    /// This is equivalent to the `allow_unknown_dependencies` option of the C++ protobuf library, and is
+    /// useful to inspect a partial set of files, for example when some imports were not included in a
+    /// file descriptor set.
+    ///
+    /// If enabled, an empty placeholder file is added to the pool for each missing import. Each message
+    /// or enum type which cannot be resolved is defined as a placeholder type, in a placeholder file
+    /// added to the pool for the type's package. The fully-qualified name of a placeholder type is the
+    /// type name as written in the referring file. It is an enum if the referring field has the enum type,
+    /// with a single value `PLACEHOLDER_VALUE` numbered zero, and otherwise is an empty message which
+    /// accepts any extension. Placeholders can be detected using [`FileDescriptor::is_placeholder`],
+    /// [`MessageDescriptor::is_placeholder`] and [`EnumDescriptor::is_placeholder`].
+    ///
+    /// Since placeholder files are part of the pool, the real file cannot be added to the same pool
+    /// later.
+    ///
+    /// The default value is `false`.
+    pub const fn allow_unknown_dependencies(mut self, yes: bool) -> Self {
+        self.allow_unknown_dependencies = yes;
+        self
+    }
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileDescriptor {
+    /// Returns `true` if this file was added to the pool in place of a missing import, or to define
+    /// placeholder types, when [`PoolOptions::allow_unknown_dependencies`] was set.
+    pub fn is_placeholder(&self) -> bool {
+        self.file_inner().placeholder
+    }
+}
+
+impl MessageDescriptor {
+    /// Returns `true` if this message was created in place of an unresolved type, when
+    /// [`PoolOptions::allow_unknown_dependencies`] was set.
+    pub fn is_placeholder(&self) -> bool {
+        self.parent_file().is_placeholder()
+    }
+}
+
+impl EnumDescriptor {
+    /// Returns `true` if this enum was created in place of an unresolved type, when
+    /// [`PoolOptions::allow_unknown_dependencies`] was set.
+    pub fn is_placeholder(&self) -> bool {
+        self.parent_file().is_placeholder()
+    }
+}
+
+/// Creates the placeholder files needed to add `files` to `pool`: one for each missing import, and one
+/// for each package containing unresolved types.
+fn placeholder_files(pool: &DescriptorPool, files: &[EncodedFile]) -> Vec<FileDescriptorProto> {
+    let mut file_names: HashSet<&str> = pool
+        .file_descriptor_protos()
+        .map(|file| file.name())
+        .collect();
+    file_names.extend(files.iter().map(|(file, _)| file.name()));
+
+    let mut resolver = Resolver {
+        defined: pool
+            .all_messages()
+            .map(|message| message.full_name().to_owned())
+            .chain(
+                pool.all_enums()
+                    .map(|enum_ty| enum_ty.full_name().to_owned()),
+            )
+            .collect(),
+        placeholders: BTreeMap::new(),
+    };
+    for (file, _) in files {
+        for message in &file.message_type {
+            resolver.define_message(file.package(), message);
+        }
+        for enum_ty in &file.enum_type {
+            resolver.define(make_full_name(file.package(), enum_ty.name()).to_string());
+        }
+    }
+
+    let mut missing_files = BTreeSet::new();
+    for (file, _) in files {
+        for dependency in &file.dependency {
+            if !file_names.contains(dependency.as_str()) {
+                missing_files.insert(dependency.clone());
+            }
+        }
+
+        for message in &file.message_type {
+            resolver.resolve_message(file.package(), message);
+        }
+        for extension in &file.extension {
+            resolver.resolve_extension(file.package(), extension);
+        }
+        for service in &file.service {
+            let namespace = make_full_name(file.package(), service.name());
+            for method in &service.method {
+                resolver.resolve(&namespace, method.input_type(), false);
+                resolver.resolve(&namespace, method.output_type(), false);
+            }
+        }
+    }
+
+    let mut result: Vec<FileDescriptorProto> = missing_files
+        .into_iter()
+        .map(|name| FileDescriptorProto {
+            name: Some(name),
+            ..Default::default()
+        })
+        .collect();
+
+    let mut packages: BTreeMap<&str, FileDescriptorProto> = BTreeMap::new();
+    for (full_name, &is_enum) in &resolver.placeholders {
+        let package = parse_namespace(full_name);
+        let file = packages
+            .entry(package)
+            .or_insert_with(|| FileDescriptorProto {
+                package: Some(package.to_owned()).filter(|package| !package.is_empty()),
+                ..Default::default()
+            });
+        if is_enum {
+            file.enum_type.push(EnumDescriptorProto {
+                name: Some(parse_name(full_name).to_owned()),
+                value: vec![EnumValueDescriptorProto {
+                    name: Some(PLACEHOLDER_ENUM_VALUE.to_owned()),
+                    number: Some(0),
+                    options: None,
+                }],
+                ..Default::default()
+            });
+        } else {
+            file.message_type.push(DescriptorProto {
+                name: Some(parse_name(full_name).to_owned()),
+                extension_range: vec![ExtensionRange {
+                    start: Some(PLACEHOLDER_EXTENSION_RANGE.0),
+                    end: Some(PLACEHOLDER_EXTENSION_RANGE.1),
+                    options: None,
+                }],
+                ..Default::default()
+            });
+        }
+    }
+
+    for (package, mut file) in packages {
+        let base_name = if package.is_empty() {
+            "placeholder".to_owned()
+        } else {
+            format!("{}.placeholder", package)
+        };
+        let mut name = format!("{}.proto", base_name);
+        let mut suffix = 1;
+        while file_names.contains(name.as_str()) || result.iter().any(|f| f.name() == name) {
+            suffix += 1;
+            name = format!("{}{}.proto", base_name, suffix);
+        }
+        file.name = Some(name);
+        result.push(file);
+    }
+
+    result
+}
+
+/// Tracks which type names are defined, and the placeholder types needed for names which are not.
+struct Resolver {
+    defined: HashSet<String>,
+    /// The full names of placeholder types, and whether each is an enum.
+    placeholders: BTreeMap<String, bool>,
+}
+
+impl Resolver {
+    fn define(&mut self, full_name: String) {
+        self.defined.insert(full_name);
+    }
+
+    fn define_message(&mut self, namespace: &str, message: &DescriptorProto) {
+        let full_name = make_full_name(namespace, message.name());
+        for nested in &message.nested_type {
+            self.define_message(&full_name, nested);
+        }
+        for enum_ty in &message.enum_type {
+            self.define(make_full_name(&full_name, enum_ty.name()).to_string());
+        }
+        self.define(full_name.to_string());
+    }
+
+    fn resolve_message(&mut self, namespace: &str, message: &DescriptorProto) {
+        let full_name = make_full_name(namespace, message.name());
+        for field in &message.field {
+            self.resolve_field(&full_name, field);
+        }
+        for extension in &message.extension {
+            self.resolve_extension(&full_name, extension);
+        }
+        for nested in &message.nested_type {
+            self.resolve_message(&full_name, nested);
+        }
+    }
+
+    fn resolve_extension(&mut self, namespace: &str, extension: &FieldDescriptorProto) {
+        self.resolve_field(namespace, extension);
+        self.resolve(namespace, extension.extendee(), false);
+    }
+
+    fn resolve_field(&mut self, namespace: &str, field: &FieldDescriptorProto) {
+        let ty = field
+            .r#type
+            .and_then(field_descriptor_proto::Type::from_i32);
+        match ty {
+            None
+            | Some(
+                field_descriptor_proto::Type::Message
+                | field_descriptor_proto::Type::Group
+                | field_descriptor_proto::Type::Enum,
+            ) => self.resolve(
+                namespace,
+                field.type_name(),
+                ty == Some(field_descriptor_proto::Type::Enum),
+            ),
+            Some(_) => (),
+        }
+    }
+
+    /// Adds a placeholder for `type_name` if it cannot be resolved relative to `namespace`, using the same
+    /// scoping rules as the pool.
+    fn resolve(&mut self, mut namespace: &str, type_name: &str, is_enum: bool) {
+        if type_name.is_empty() {
+            return;
+        }
+
+        let full_name = match type_name.strip_prefix('.') {
+            Some(full_name) => full_name,
+            None => loop {
+                if self
+                    .defined
+                    .contains(make_full_name(namespace, type_name).as_ref())
+                {
+                    return;
+                } else if namespace.is_empty() {
+                    break type_name;
+                } else {
+                    namespace = parse_namespace(namespace);
+                }
+            },
+        };
+
+        if self.defined.insert(full_name.to_owned()) {
+            self.placeholders.insert(full_name.to_owned(), is_enum);
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use std::fmt;
+use std::{fmt, sync::Arc};
 
 use prost_types::{FileDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto};
 
 use super::{
-    debug_fmt_iter, make_full_name, parse_name, parse_namespace, to_index, ty, DescriptorError,
-    DescriptorPool, FileDescriptor, FileIndex, MessageDescriptor, MethodIndex, ServiceIndex,
+    debug_fmt_iter, make_full_name, parse_name, parse_namespace,
+    path::{FILE_SERVICE, SERVICE_METHOD},
+    to_index, ty, DescriptorError, DescriptorPool, FileDescriptor, FileDescriptorInner, FileIndex,
+    MessageDescriptor, MethodIndex, ServiceIndex,
 };
 
 /// A protobuf service definition.
@@ -17,7 +19,7 @@ pub struct ServiceDescriptor {
 #[derive(Clone)]
 pub(super) struct ServiceDescriptorInner {
     file: FileIndex,
-    full_name: Box<str>,
+    pub(super) full_name: Arc<str>,
     methods: Box<[MethodDescriptorInner]>,
 }
 
@@ -30,7 +32,7 @@ pub struct MethodDescriptor {
 
 #[derive(Clone)]
 struct MethodDescriptorInner {
-    full_name: Box<str>,
+    full_name: Arc<str>,
     request_ty: ty::TypeId,
     response_ty: ty::TypeId,
     server_streaming: bool,
@@ -106,29 +108,44 @@ impl ServiceDescriptor {
 }
 
 impl ServiceDescriptorInner {
+    /// Builds the service at `index` in `file`, returning every problem found with its methods.
     pub(super) fn from_raw(
-        raw_file: &FileDescriptorProto,
+        file: &FileDescriptorInner,
         file_index: FileIndex,
-        raw_service: &ServiceDescriptorProto,
+        index: usize,
         type_map: &ty::TypeMap,
     ) -> Result<ServiceDescriptorInner, DescriptorError> {
-        let full_name = make_full_name(raw_file.package(), raw_service.name());
-        let methods = raw_service
-            .method
-            .iter()
-            .map(|raw_method| {
-                MethodDescriptorInner::from_raw(
-                    &full_name,
-                    raw_file,
-                    raw_service,
-                    raw_method,
-                    type_map,
-                )
-            })
-            .collect::<Result<_, DescriptorError>>()?;
+        let raw_service = &file.raw.service[index];
+        let full_name = make_full_name(file.raw.package(), raw_service.name());
+
+        let mut methods = Vec::with_capacity(raw_service.method.len());
+        let mut errors = Vec::new();
+        for (method_index, raw_method) in raw_service.method.iter().enumerate() {
+            match MethodDescriptorInner::from_raw(
+                &full_name,
+                &file.raw,
+                raw_service,
+                raw_method,
+                type_map,
+            ) {
+                Ok(method) => methods.push(method),
+                Err(err) => errors.push(err.with_context(
+                    file,
+                    Some(&make_full_name(&full_name, raw_method.name())),
+                    &[
+                        FILE_SERVICE,
+                        to_index(index) as i32,
+                        SERVICE_METHOD,
+                        to_index(method_index) as i32,
+                    ],
+                )),
+            }
+        }
+        DescriptorError::combine(errors)?;
+
         Ok(ServiceDescriptorInner {
             full_name,
-            methods,
+            methods: methods.into(),
             file: file_index,
         })
     }
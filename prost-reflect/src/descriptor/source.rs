@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use prost_types::{source_code_info::Location, FileDescriptorProto};
+
+use crate::{
+    DescriptorPool, EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor, FieldDescriptor,
+    MessageDescriptor, MethodDescriptor, OneofDescriptor, ServiceDescriptor,
+};
+
+macro_rules! impl_source_info {
+    ($($ty:ty => $name:literal;)*) => {
+        $(
+            impl $ty {
+                #[doc = concat!("Gets the location of this ", $name, " in its source file, including any comments attached to it.")]
+                ///
+                /// Returns `None` if the file does not include source code info. The protobuf compiler
+                /// only includes it when run with the `--include_source_info` flag.
+                pub fn location(&self) -> Option<&Location> {
+                    location(self.parent_pool(), self.parent_file().index(), &self.path())
+                }
+
+                #[doc = concat!("Gets the comment directly preceding this ", $name, ", if the file includes source code info.")]
+                ///
+                /// The comment is returned as written, without the comment markers, so it will
+                /// usually start with a space and end with a newline.
+                pub fn leading_comments(&self) -> Option<&str> {
+                    self.location()?.leading_comments.as_deref()
+                }
+
+                #[doc = concat!("Gets the comment directly following this ", $name, ", if the file includes source code info.")]
+                ///
+                /// The comment is returned as written, without the comment markers, so it will
+                /// usually start with a space and end with a newline.
+                pub fn trailing_comments(&self) -> Option<&str> {
+                    self.location()?.trailing_comments.as_deref()
+                }
+            }
+        )*
+    };
+}
+
+impl_source_info! {
+    MessageDescriptor => "message";
+    FieldDescriptor => "field";
+    ExtensionDescriptor => "extension";
+    OneofDescriptor => "oneof";
+    EnumDescriptor => "enum";
+    EnumValueDescriptor => "enum value";
+    ServiceDescriptor => "service";
+    MethodDescriptor => "method";
+}
+
+/// Builds an index of the locations in a file's source code info by path. If several locations have the
+/// same path, the first is used.
+pub(super) fn index_locations(file: &FileDescriptorProto) -> HashMap<Vec<i32>, usize> {
+    let mut locations = HashMap::new();
+    if let Some(info) = &file.source_code_info {
+        for (index, location) in info.location.iter().enumerate() {
+            locations.entry(location.path.clone()).or_insert(index);
+        }
+    }
+    locations
+}
+
+fn location<'a>(pool: &'a DescriptorPool, file: usize, path: &[i32]) -> Option<&'a Location> {
+    let file = &pool.inner.files[file];
+    let &index = file.locations.get(path)?;
+    Some(&file.raw.source_code_info.as_ref()?.location[index])
+}
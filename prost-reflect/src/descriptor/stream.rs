@@ -0,0 +1,212 @@
+use std::ops::Range;
+
+use prost::{bytes::Bytes, DecodeError, Message};
+use prost_types::FileDescriptorProto;
+
+use crate::{DescriptorError, DescriptorPool};
+
+use super::EncodedFile;
+
+const FILE_TAG: u64 = (1 << 3) | 2;
+const MAX_VARINT_LEN: usize = 10;
+
+/// Incrementally decodes an encoded `google.protobuf.FileDescriptorSet` which is received in chunks, for
+/// example from a network stream or an archive, and builds a [`DescriptorPool`] from it.
+///
+/// Chunks may be split at any point, and each file in the set is decoded as soon as all of its bytes have
+/// been received, so the whole encoded set never needs to be held in memory at once. Progress can be
+/// checked between chunks with [`bytes_read`][Self::bytes_read] and [`files_decoded`][Self::files_decoded].
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::FileDescriptorSetDecoder;
+/// # let bytes = include_bytes!("../file_descriptor_set.bin");
+/// let mut decoder = FileDescriptorSetDecoder::new();
+/// for chunk in bytes.chunks(64) {
+///     decoder.push(chunk).unwrap();
+///     println!("decoded {} files from {} bytes", decoder.files_decoded(), decoder.bytes_read());
+/// }
+/// let pool = decoder.finish().unwrap();
+/// assert!(pool.get_message_by_name("package.MyMessage").is_some());
+/// ```
+#[derive(Debug, Default)]
+pub struct FileDescriptorSetDecoder {
+    buf: Vec<u8>,
+    bytes_read: u64,
+    files: Vec<EncodedFile>,
+}
+
+impl FileDescriptorSetDecoder {
+    /// Creates a new, empty [`FileDescriptorSetDecoder`].
+    pub fn new() -> Self {
+        FileDescriptorSetDecoder::default()
+    }
+
+    /// Adds the next chunk of the encoded file descriptor set, returning the number of files which were
+    /// completed by this chunk.
+    ///
+    /// If an error is returned, the files decoded from earlier chunks are still available from
+    /// [`decoded_files`][Self::decoded_files], but no more chunks should be pushed.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<usize, DescriptorError> {
+        self.bytes_read += chunk.len() as u64;
+        self.buf.extend_from_slice(chunk);
+
+        let files_before = self.files.len();
+        let mut pos = 0;
+        while let Some((len, file)) = decode_field(&self.buf[pos..])? {
+            if let Some(file) = file {
+                let encoded = Bytes::copy_from_slice(&self.buf[pos..][file]);
+                self.files.push(decode_file(encoded)?);
+            }
+            pos += len;
+        }
+        self.buf.drain(..pos);
+        Ok(self.files.len() - files_before)
+    }
+
+    /// The total number of bytes pushed so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// The number of files which have been completely decoded so far.
+    pub fn files_decoded(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Gets an iterator over the files which have been completely decoded so far.
+    pub fn decoded_files(&self) -> impl ExactSizeIterator<Item = &FileDescriptorProto> + '_ {
+        self.files.iter().map(|(file, _)| file)
+    }
+
+    /// Builds a [`DescriptorPool`] from the decoded files.
+    ///
+    /// Returns an error if the input ended part way through a file, or if the files are not valid, as for
+    /// [`DescriptorPool::add_file_descriptor_protos`].
+    pub fn finish(self) -> Result<DescriptorPool, DescriptorError> {
+        if !self.buf.is_empty() {
+            return Err(DescriptorError::decode_file_descriptor_set(
+                DecodeError::new("unexpected end of file descriptor set"),
+            ));
+        }
+
+        let mut pool = DescriptorPool::new();
+        pool.add_encoded_files(self.files)?;
+        Ok(pool)
+    }
+}
+
+impl DescriptorPool {
+    /// Decodes a [`FileDescriptorSet`][prost_types::FileDescriptorSet] from an iterator of byte chunks, and
+    /// creates a new [`DescriptorPool`] wrapping it.
+    ///
+    /// This is equivalent to [`DescriptorPool::decode`] on the concatenation of the chunks, but does not
+    /// require the whole encoded set to be held in memory. To report progress while decoding, use
+    /// [`FileDescriptorSetDecoder`] directly.
+    pub fn decode_chunks<I, B>(chunks: I) -> Result<Self, DescriptorError>
+    where
+        I: IntoIterator<Item = B>,
+        B: AsRef<[u8]>,
+    {
+        let mut decoder = FileDescriptorSetDecoder::new();
+        for chunk in chunks {
+            decoder.push(chunk.as_ref())?;
+        }
+        decoder.finish()
+    }
+}
+
+/// Decodes an encoded `google.protobuf.FileDescriptorSet`, returning each file along with its encoded bytes.
+pub(super) fn decode_file_descriptor_set(
+    bytes: Bytes,
+) -> Result<Vec<EncodedFile>, DescriptorError> {
+    let mut files = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match decode_field(&bytes[pos..])? {
+            Some((len, file)) => {
+                if let Some(file) = file {
+                    files.push(decode_file(bytes.slice(pos + file.start..pos + file.end))?);
+                }
+                pos += len;
+            }
+            None => {
+                return Err(DescriptorError::decode_file_descriptor_set(
+                    DecodeError::new("unexpected end of file descriptor set"),
+                ))
+            }
+        }
+    }
+    Ok(files)
+}
+
+pub(super) fn decode_file(encoded: Bytes) -> Result<EncodedFile, DescriptorError> {
+    let file = FileDescriptorProto::decode(encoded.as_ref())
+        .map_err(DescriptorError::decode_file_descriptor_set)?;
+    Ok((file, Some(encoded)))
+}
+
+/// The length of a field, and the range of the encoded file within it if the field is a file.
+type FieldSpan = (usize, Option<Range<usize>>);
+
+/// Decodes the field at the start of `buf`, returning its length and, if it is a file, the range of the
+/// encoded file within `buf`. Returns `None` if more bytes are needed.
+fn decode_field(buf: &[u8]) -> Result<Option<FieldSpan>, DescriptorError> {
+    let (tag, tag_len) = match decode_varint(buf)? {
+        Some(tag) => tag,
+        None => return Ok(None),
+    };
+    let rest = &buf[tag_len..];
+
+    let value_len = match tag & 0b111 {
+        0 => match decode_varint(rest)? {
+            Some((_, len)) => len,
+            None => return Ok(None),
+        },
+        1 => 8,
+        2 => match decode_varint(rest)? {
+            Some((len, len_len)) => len_len + len as usize,
+            None => return Ok(None),
+        },
+        5 => 4,
+        wire_type => {
+            return Err(DescriptorError::decode_file_descriptor_set(
+                DecodeError::new(format!("invalid wire type value: {}", wire_type)),
+            ))
+        }
+    };
+    if rest.len() < value_len {
+        return Ok(None);
+    }
+
+    // Fields other than `file` are skipped, as they would be when decoding a `FileDescriptorSet`.
+    let file = if tag == FILE_TAG {
+        let (_, len_len) = decode_varint(rest)?.expect("length already decoded");
+        Some(tag_len + len_len..tag_len + value_len)
+    } else {
+        None
+    };
+
+    Ok(Some((tag_len + value_len, file)))
+}
+
+/// Decodes a varint from the start of `buf`, returning its value and length, or `None` if `buf` ends
+/// before the varint does.
+fn decode_varint(buf: &[u8]) -> Result<Option<(u64, usize)>, DescriptorError> {
+    let mut value = 0;
+    for (index, &byte) in buf.iter().take(MAX_VARINT_LEN).enumerate() {
+        value |= u64::from(byte & 0x7f) << (index * 7);
+        if byte < 0x80 {
+            return Ok(Some((value, index + 1)));
+        }
+    }
+
+    if buf.len() >= MAX_VARINT_LEN {
+        Err(DescriptorError::decode_file_descriptor_set(
+            DecodeError::new("invalid varint"),
+        ))
+    } else {
+        Ok(None)
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     collections::{BTreeMap, HashMap},
-    rc::Rc,
+    sync::Arc,
 };
 
 use prost::bytes::Bytes;
@@ -11,7 +11,12 @@ use prost_types::{
 
 use crate::{
     descriptor::{
-        make_full_name, parse_namespace, to_index,
+        make_full_name, parse_namespace,
+        path::{
+            FILE_ENUM_TYPE, FILE_EXTENSION, FILE_MESSAGE_TYPE, MESSAGE_ENUM_TYPE,
+            MESSAGE_EXTENSION, MESSAGE_FIELD, MESSAGE_NESTED_TYPE,
+        },
+        to_index,
         ty::{
             Cardinality, EnumDescriptorInner, EnumValueDescriptorInner, ExtensionDescriptorInner,
             FieldDescriptorInner, MessageDescriptorInner, OneofDescriptorInner, ParentKind, TypeId,
@@ -24,6 +29,9 @@ use crate::{
 };
 
 impl TypeMap {
+    /// Adds the types defined in the given files, returning every problem found with them.
+    ///
+    /// If an error is returned, the type map may be left in an inconsistent state, and must be discarded.
     pub fn add_files<'a>(
         &mut self,
         raw: impl Iterator<Item = (FileIndex, &'a FileDescriptorInner)>,
@@ -31,34 +39,38 @@ impl TypeMap {
         let mut messages = Vec::new();
         let mut enums = Vec::new();
         let mut extensions = Vec::new();
+        let mut errors = Vec::new();
 
-        self.iter_files(raw, &mut messages, &mut enums, &mut extensions)?;
+        self.iter_files(raw, &mut messages, &mut enums, &mut extensions, &mut errors);
 
         for enum_ in enums {
-            self.build_enum(enum_)?;
+            self.build_enum(enum_, &mut errors);
         }
 
         for message in messages {
-            self.build_message(message)?;
+            self.build_message(message, &mut errors);
         }
 
         for extension in extensions {
-            self.build_extension(extension)?;
+            self.build_extension(extension, &mut errors);
         }
 
-        Ok(())
+        DescriptorError::combine(errors)
     }
 
     fn build_message(
         &mut self,
         MessageProto {
             file,
+            raw_file,
+            path,
             full_name,
             message_proto,
             parent,
             syntax,
         }: MessageProto,
-    ) -> Result<(), DescriptorError> {
+        errors: &mut Vec<DescriptorError>,
+    ) {
         let is_map_entry = match &message_proto.options {
             Some(options) => options.map_entry(),
             None => false,
@@ -68,19 +80,26 @@ impl TypeMap {
             .oneof_decl
             .iter()
             .map(|oneof| OneofDescriptorInner {
-                name: oneof.name().into(),
+                name: self.names.intern(oneof.name()),
                 full_name: make_full_name(&full_name, oneof.name()),
                 fields: Vec::new(),
             })
             .collect();
 
-        let fields = message_proto
-            .field
-            .iter()
-            .map(|field_proto| {
-                self.build_message_field(&full_name, field_proto, syntax, &mut oneof_decls)
-            })
-            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        // Invalid fields are reported and skipped, so that the remaining fields are still checked.
+        let mut fields = BTreeMap::new();
+        for (index, field_proto) in message_proto.field.iter().enumerate() {
+            match self.build_message_field(&full_name, field_proto, syntax, &mut oneof_decls) {
+                Ok((number, field)) => {
+                    fields.insert(number, field);
+                }
+                Err(err) => errors.push(err.with_context(
+                    raw_file,
+                    Some(&make_full_name(&full_name, field_proto.name())),
+                    &with_path(&path, MESSAGE_FIELD, index),
+                )),
+            }
+        }
 
         oneof_decls
             .iter_mut()
@@ -99,15 +118,14 @@ impl TypeMap {
             && (!fields.contains_key(&MAP_ENTRY_KEY_NUMBER)
                 || !fields.contains_key(&MAP_ENTRY_VALUE_NUMBER))
         {
-            return Err(DescriptorError::invalid_map_entry(full_name));
+            errors.push(DescriptorError::invalid_map_entry(&full_name).with_context(
+                raw_file,
+                Some(&full_name),
+                &path,
+            ));
         }
 
-        let parent = match parent {
-            None => ParentKind::File,
-            Some(parent_name) => ParentKind::Message {
-                index: self.try_get_by_name(&parent_name)?.1,
-            },
-        };
+        let parent = self.parent_kind(parent);
 
         debug_assert_eq!(
             self.get_by_name(&full_name),
@@ -124,8 +142,6 @@ impl TypeMap {
             is_map_entry,
             extensions: vec![],
         });
-
-        Ok(())
     }
 
     fn build_message_field(
@@ -219,9 +235,9 @@ impl TypeMap {
             None => None,
         };
         let field = FieldDescriptorInner {
-            name: field_proto.name().into(),
+            name: self.names.intern(field_proto.name()),
             full_name: make_full_name(message_name, field_proto.name()),
-            json_name: field_proto.json_name().into(),
+            json_name: self.names.intern(field_proto.json_name()),
             is_group: field_proto.r#type == Some(field_descriptor_proto::Type::Group as i32),
             cardinality,
             is_packed,
@@ -256,50 +272,53 @@ impl TypeMap {
         &mut self,
         EnumProto {
             file,
+            raw_file,
+            path,
             full_name,
             enum_proto,
             parent,
             syntax,
         }: EnumProto,
-    ) -> Result<(), DescriptorError> {
+        errors: &mut Vec<DescriptorError>,
+    ) {
         let package_name = parse_namespace(&full_name);
         let mut values: Vec<_> = enum_proto
             .value
             .iter()
             .map(|value_proto| EnumValueDescriptorInner {
-                name: value_proto.name().into(),
+                name: self.names.intern(value_proto.name()),
                 number: value_proto.number(),
                 full_name: make_full_name(package_name, value_proto.name()),
             })
             .collect();
         values.sort_by_key(|v| v.number);
 
-        let value_names: HashMap<Box<str>, EnumValueIndex> = values
+        let value_names: HashMap<Arc<str>, EnumValueIndex> = values
             .iter()
             .enumerate()
             .map(|(index, value)| (value.name.clone(), to_index(index)))
             .collect();
 
-        let parent = match parent {
-            None => ParentKind::File,
-            Some(parent_name) => ParentKind::Message {
-                index: self.try_get_by_name(&parent_name)?.1,
-            },
-        };
+        let parent = self.parent_kind(parent);
 
         let default_value = if syntax == Syntax::Proto2 {
-            let name = enum_proto
+            enum_proto
                 .value
-                .get(0)
-                .ok_or_else(DescriptorError::empty_enum)?
-                .name();
-            value_names[name]
+                .first()
+                .map(|value| value_names[value.name()])
         } else {
-            values
-                .iter()
-                .position(|v| v.number == 0)
-                .map(to_index)
-                .ok_or_else(DescriptorError::empty_enum)?
+            values.iter().position(|v| v.number == 0).map(to_index)
+        };
+        let default_value = match default_value {
+            Some(default_value) => default_value,
+            None => {
+                errors.push(DescriptorError::empty_enum().with_context(
+                    raw_file,
+                    Some(&full_name),
+                    &path,
+                ));
+                0
+            }
         };
 
         debug_assert_eq!(
@@ -314,28 +333,51 @@ impl TypeMap {
             values,
             default_value,
         });
-        Ok(())
     }
 
     fn build_extension(
         &mut self,
         ExtensionProto {
             file,
+            raw_file,
+            path,
             namespace,
             field_proto,
             parent,
             syntax,
         }: ExtensionProto,
-    ) -> Result<(), DescriptorError> {
-        let (number, field) = self.build_message_field(&namespace, field_proto, syntax, &mut [])?;
-
-        let extendee = self.resolve_type_name(&namespace, field_proto.extendee())?;
-        if !extendee.is_message() {
-            return Err(DescriptorError::invalid_extendee_type(
-                field.full_name,
-                field_proto.extendee(),
-            ));
-        }
+        errors: &mut Vec<DescriptorError>,
+    ) {
+        let full_name = make_full_name(&namespace, field_proto.name());
+        let with_context =
+            |err: DescriptorError| err.with_context(raw_file, Some(&full_name), &path);
+
+        let field = self.build_message_field(&namespace, field_proto, syntax, &mut []);
+        let extendee = self
+            .resolve_type_name(&namespace, field_proto.extendee())
+            .and_then(|extendee| {
+                if extendee.is_message() {
+                    Ok(extendee)
+                } else {
+                    Err(DescriptorError::invalid_extendee_type(
+                        &full_name,
+                        field_proto.extendee(),
+                    ))
+                }
+            });
+        let ((number, field), extendee) = match (field, extendee) {
+            (Ok(field), Ok(extendee)) => (field, extendee),
+            (field, extendee) => {
+                errors.extend(
+                    field
+                        .err()
+                        .into_iter()
+                        .chain(extendee.err())
+                        .map(with_context),
+                );
+                return;
+            }
+        };
 
         let mut json_name = String::with_capacity(2 + field.full_name.len());
         json_name.push('[');
@@ -343,15 +385,13 @@ impl TypeMap {
         json_name.push(']');
         let json_name = json_name.into_boxed_str();
 
-        let parent = match parent {
-            None => ParentKind::File,
-            Some(parent_name) => ParentKind::Message {
-                index: self.try_get_by_name(&parent_name)?.1,
-            },
-        };
+        let parent = self.parent_kind(parent);
 
         let index = to_index(self.extensions.len());
         self.get_message_mut(extendee).extensions.push(index);
+        self.extension_names
+            .entry(field.full_name.clone())
+            .or_insert(index);
         self.extensions.push(ExtensionDescriptorInner {
             file,
             field,
@@ -360,8 +400,16 @@ impl TypeMap {
             extendee,
             json_name,
         });
+    }
 
-        Ok(())
+    /// Gets the parent of a type from the name of its parent message, if any.
+    fn parent_kind(&self, parent_name: Option<impl AsRef<str>>) -> ParentKind {
+        // The parent message is always defined, unless its name was also used for another type, in which
+        // case an error has already been reported.
+        match parent_name.and_then(|name| self.get_by_name(name.as_ref())) {
+            Some(parent) => ParentKind::Message { index: parent.1 },
+            None => ParentKind::File,
+        }
     }
 
     fn iter_files<'a>(
@@ -370,158 +418,197 @@ impl TypeMap {
         messages: &mut Vec<MessageProto<'a>>,
         enums: &mut Vec<EnumProto<'a>>,
         extensions: &mut Vec<ExtensionProto<'a>>,
-    ) -> Result<(), DescriptorError> {
+        errors: &mut Vec<DescriptorError>,
+    ) {
         for (file_index, file) in raw {
             let namespace = file.raw.package();
-
-            for message_proto in &file.raw.message_type {
-                let full_name = make_full_name(namespace, message_proto.name());
-                self.iter_message(
-                    file_index,
-                    &full_name,
-                    messages,
-                    enums,
-                    extensions,
-                    message_proto,
-                    file.syntax,
-                )?;
-
-                self.add_named_type(
-                    full_name.clone(),
-                    TypeId::new_message(to_index(self.messages.len() + messages.len())),
-                )?;
-                messages.push(MessageProto {
-                    file: file_index,
-                    full_name,
+            let mut types = TypeProtos {
+                file: file_index,
+                raw_file: file,
+                syntax: file.syntax,
+                messages: &mut *messages,
+                enums: &mut *enums,
+                extensions: &mut *extensions,
+                errors: &mut *errors,
+            };
+
+            for (index, message_proto) in file.raw.message_type.iter().enumerate() {
+                self.add_message(
+                    &mut types,
+                    namespace,
+                    None,
+                    vec![FILE_MESSAGE_TYPE, to_index(index) as i32],
                     message_proto,
-                    parent: None,
-                    syntax: file.syntax,
-                });
+                );
             }
 
-            for enum_proto in &file.raw.enum_type {
-                let full_name = make_full_name(namespace, enum_proto.name());
-
-                self.add_named_type(
-                    full_name.clone(),
-                    TypeId::new_enum(to_index(self.enums.len() + enums.len())),
-                )?;
-                enums.push(EnumProto {
-                    file: file_index,
-                    full_name,
+            for (index, enum_proto) in file.raw.enum_type.iter().enumerate() {
+                self.add_enum(
+                    &mut types,
+                    namespace,
+                    None,
+                    vec![FILE_ENUM_TYPE, to_index(index) as i32],
                     enum_proto,
-                    parent: None,
-                    syntax: file.syntax,
-                });
+                );
             }
 
-            for field_proto in &file.raw.extension {
-                extensions.push(ExtensionProto {
+            for (index, field_proto) in file.raw.extension.iter().enumerate() {
+                types.extensions.push(ExtensionProto {
                     file: file_index,
-                    namespace: namespace.into(),
+                    raw_file: file,
+                    path: vec![FILE_EXTENSION, to_index(index) as i32],
+                    namespace: self.names.intern(namespace),
                     field_proto,
                     parent: None,
                     syntax: file.syntax,
                 });
             }
         }
-
-        Ok(())
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn iter_message<'a>(
+    /// Registers the name of a message and the types nested within it.
+    fn add_message<'a>(
         &mut self,
-        file_index: FileIndex,
+        types: &mut TypeProtos<'a, '_>,
         namespace: &str,
-        messages: &mut Vec<MessageProto<'a>>,
-        enums: &mut Vec<EnumProto<'a>>,
-        extensions: &mut Vec<ExtensionProto<'a>>,
-        raw: &'a DescriptorProto,
-        syntax: Syntax,
-    ) -> Result<(), DescriptorError> {
-        for message_proto in &raw.nested_type {
-            let full_name = make_full_name(namespace, message_proto.name());
-            self.iter_message(
-                file_index,
+        parent: Option<&Arc<str>>,
+        path: Vec<i32>,
+        message_proto: &'a DescriptorProto,
+    ) {
+        let full_name = make_full_name(namespace, message_proto.name());
+
+        for (index, nested_proto) in message_proto.nested_type.iter().enumerate() {
+            self.add_message(
+                types,
                 &full_name,
-                messages,
-                enums,
-                extensions,
-                message_proto,
-                syntax,
-            )?;
-
-            self.add_named_type(
-                full_name.clone(),
-                TypeId::new_message(to_index(self.messages.len() + messages.len())),
-            )?;
-            messages.push(MessageProto {
-                file: file_index,
-                full_name,
-                message_proto,
-                parent: Some(namespace.into()),
-                syntax,
-            });
+                Some(&full_name),
+                with_path(&path, MESSAGE_NESTED_TYPE, index),
+                nested_proto,
+            );
         }
 
-        for enum_proto in &raw.enum_type {
-            let full_name = make_full_name(namespace, enum_proto.name());
-
-            self.add_named_type(
-                full_name.clone(),
-                TypeId::new_enum(to_index(self.enums.len() + enums.len())),
-            )?;
-            enums.push(EnumProto {
-                file: file_index,
-                full_name,
+        for (index, enum_proto) in message_proto.enum_type.iter().enumerate() {
+            self.add_enum(
+                types,
+                &full_name,
+                Some(&full_name),
+                with_path(&path, MESSAGE_ENUM_TYPE, index),
                 enum_proto,
-                parent: Some(namespace.into()),
-                syntax,
-            });
+            );
         }
 
-        for field_proto in &raw.extension {
-            extensions.push(ExtensionProto {
-                file: file_index,
-                namespace: namespace.into(),
+        for (index, field_proto) in message_proto.extension.iter().enumerate() {
+            types.extensions.push(ExtensionProto {
+                file: types.file,
+                raw_file: types.raw_file,
+                path: with_path(&path, MESSAGE_EXTENSION, index),
+                namespace: full_name.clone(),
                 field_proto,
-                parent: Some(namespace.into()),
-                syntax,
+                parent: Some(full_name.clone()),
+                syntax: types.syntax,
             });
         }
 
-        Ok(())
+        // Types whose names are already used are reported and skipped, so that the index of each type
+        // matches its position in the type map.
+        let ty = TypeId::new_message(to_index(self.messages.len() + types.messages.len()));
+        if let Err(err) = self.add_named_type(full_name.clone(), ty) {
+            types
+                .errors
+                .push(err.with_context(types.raw_file, Some(&full_name), &path));
+            return;
+        }
+        types.messages.push(MessageProto {
+            file: types.file,
+            raw_file: types.raw_file,
+            path,
+            full_name,
+            message_proto,
+            parent: parent.cloned(),
+            syntax: types.syntax,
+        });
+    }
+
+    fn add_enum<'a>(
+        &mut self,
+        types: &mut TypeProtos<'a, '_>,
+        namespace: &str,
+        parent: Option<&Arc<str>>,
+        path: Vec<i32>,
+        enum_proto: &'a EnumDescriptorProto,
+    ) {
+        let full_name = make_full_name(namespace, enum_proto.name());
+
+        let ty = TypeId::new_enum(to_index(self.enums.len() + types.enums.len()));
+        if let Err(err) = self.add_named_type(full_name.clone(), ty) {
+            types
+                .errors
+                .push(err.with_context(types.raw_file, Some(&full_name), &path));
+            return;
+        }
+        types.enums.push(EnumProto {
+            file: types.file,
+            raw_file: types.raw_file,
+            path,
+            full_name,
+            enum_proto,
+            parent: parent.cloned(),
+            syntax: types.syntax,
+        });
     }
 }
 
+/// The types found while registering the names defined in a file, to be built once all names are known.
+struct TypeProtos<'a, 'b> {
+    file: FileIndex,
+    raw_file: &'a FileDescriptorInner,
+    syntax: Syntax,
+    messages: &'b mut Vec<MessageProto<'a>>,
+    enums: &'b mut Vec<EnumProto<'a>>,
+    extensions: &'b mut Vec<ExtensionProto<'a>>,
+    errors: &'b mut Vec<DescriptorError>,
+}
+
 #[derive(Clone)]
 struct MessageProto<'a> {
     file: FileIndex,
-    full_name: Box<str>,
+    raw_file: &'a FileDescriptorInner,
+    path: Vec<i32>,
+    full_name: Arc<str>,
     message_proto: &'a DescriptorProto,
-    parent: Option<Box<str>>,
+    parent: Option<Arc<str>>,
     syntax: Syntax,
 }
 
 #[derive(Clone)]
 struct EnumProto<'a> {
     file: FileIndex,
-    full_name: Box<str>,
+    raw_file: &'a FileDescriptorInner,
+    path: Vec<i32>,
+    full_name: Arc<str>,
     enum_proto: &'a EnumDescriptorProto,
-    parent: Option<Box<str>>,
+    parent: Option<Arc<str>>,
     syntax: Syntax,
 }
 
 #[derive(Clone)]
 struct ExtensionProto<'a> {
     file: FileIndex,
-    namespace: Box<str>,
+    raw_file: &'a FileDescriptorInner,
+    path: Vec<i32>,
+    namespace: Arc<str>,
     field_proto: &'a FieldDescriptorProto,
-    parent: Option<Rc<str>>,
+    parent: Option<Arc<str>>,
     syntax: Syntax,
 }
 
+fn with_path(path: &[i32], field_number: i32, index: usize) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.push(field_number);
+    path.push(to_index(index) as i32);
+    path
+}
+
 /// From https://github.com/tokio-rs/prost/blob/c3b7037a7f2c56cef327b41ca32a8c4e9ce5a41c/prost-build/src/code_generator.rs#L887
 /// Based on [`google::protobuf::UnescapeCEscapeString`][1]
 /// [1]: https://github.com/google/protobuf/blob/3.3.x/src/google/protobuf/stubs/strutil.cc#L312-L322
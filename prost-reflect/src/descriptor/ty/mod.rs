@@ -9,6 +9,7 @@ use std::{
     },
     fmt,
     ops::{Range, RangeInclusive},
+    sync::Arc,
 };
 
 use prost::encoding::WireType;
@@ -22,14 +23,19 @@ use crate::descriptor::{
     DescriptorPool, FileDescriptor, MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER,
 };
 
-use super::{EnumIndex, EnumValueIndex, ExtensionIndex, FileIndex, MessageIndex, OneofIndex};
+use super::{
+    intern::Interner, EnumIndex, EnumValueIndex, ExtensionIndex, FileIndex, MessageIndex,
+    OneofIndex,
+};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(super) struct TypeId(field_descriptor_proto::Type, u32);
 
 #[derive(Clone, Default)]
 pub(super) struct TypeMap {
-    named_types: HashMap<Box<str>, TypeId>,
+    named_types: HashMap<Arc<str>, TypeId>,
+    extension_names: HashMap<Arc<str>, ExtensionIndex>,
+    names: Interner,
     messages: Vec<MessageDescriptorInner>,
     enums: Vec<EnumDescriptorInner>,
     extensions: Vec<ExtensionDescriptorInner>,
@@ -44,13 +50,13 @@ pub struct MessageDescriptor {
 
 #[derive(Clone)]
 struct MessageDescriptorInner {
-    full_name: Box<str>,
+    full_name: Arc<str>,
     file: FileIndex,
     parent: ParentKind,
     is_map_entry: bool,
     fields: BTreeMap<u32, FieldDescriptorInner>,
-    field_names: HashMap<Box<str>, u32>,
-    field_json_names: HashMap<Box<str>, u32>,
+    field_names: HashMap<Arc<str>, u32>,
+    field_json_names: HashMap<Arc<str>, u32>,
     oneof_decls: Box<[OneofDescriptorInner]>,
     extensions: Vec<ExtensionIndex>,
 }
@@ -64,8 +70,8 @@ pub struct OneofDescriptor {
 
 #[derive(Clone)]
 struct OneofDescriptorInner {
-    name: Box<str>,
-    full_name: Box<str>,
+    name: Arc<str>,
+    full_name: Arc<str>,
     fields: Vec<u32>,
 }
 
@@ -78,9 +84,9 @@ pub struct FieldDescriptor {
 
 #[derive(Clone)]
 struct FieldDescriptorInner {
-    name: Box<str>,
-    full_name: Box<str>,
-    json_name: Box<str>,
+    name: Arc<str>,
+    full_name: Arc<str>,
+    json_name: Arc<str>,
     is_group: bool,
     cardinality: Cardinality,
     is_packed: bool,
@@ -116,10 +122,10 @@ pub struct EnumDescriptor {
 
 #[derive(Clone)]
 struct EnumDescriptorInner {
-    full_name: Box<str>,
+    full_name: Arc<str>,
     file: FileIndex,
     parent: ParentKind,
-    value_names: HashMap<Box<str>, EnumValueIndex>,
+    value_names: HashMap<Arc<str>, EnumValueIndex>,
     values: Vec<EnumValueDescriptorInner>,
     default_value: EnumValueIndex,
 }
@@ -133,9 +139,9 @@ pub struct EnumValueDescriptor {
 
 #[derive(Clone)]
 struct EnumValueDescriptorInner {
-    name: Box<str>,
+    name: Arc<str>,
     number: i32,
-    full_name: Box<str>,
+    full_name: Arc<str>,
 }
 
 /// The type of a protobuf message field.
@@ -444,6 +450,11 @@ impl MessageDescriptor {
         self.extensions().find(|ext| ext.json_name() == name)
     }
 
+    /// Gets the explicit default value of the field with the given number, borrowed from this descriptor.
+    pub(crate) fn field_default_value(&self, number: u32) -> Option<&crate::Value> {
+        self.inner().fields.get(&number)?.default_value.as_ref()
+    }
+
     fn inner(&self) -> &MessageDescriptorInner {
         self.pool.inner.type_map.get_message(self.index)
     }
@@ -618,6 +629,17 @@ impl ExtensionDescriptor {
             })
     }
 
+    pub(in crate::descriptor) fn try_get_by_name(
+        pool: &DescriptorPool,
+        name: &str,
+    ) -> Option<Self> {
+        let index = pool.inner.type_map.get_extension_by_name(name)?;
+        Some(ExtensionDescriptor {
+            pool: pool.clone(),
+            index,
+        })
+    }
+
     /// Gets a reference to the [`DescriptorPool`] this extension field is defined in.
     pub fn parent_pool(&self) -> &DescriptorPool {
         &self.pool
@@ -1131,6 +1153,8 @@ impl fmt::Debug for OneofDescriptor {
 impl TypeMap {
     pub fn shrink_to_fit(&mut self) {
         self.named_types.shrink_to_fit();
+        self.extension_names.shrink_to_fit();
+        self.names.shrink_to_fit();
         self.messages.shrink_to_fit();
         self.enums.shrink_to_fit();
         self.extensions.shrink_to_fit();
@@ -1146,6 +1170,11 @@ impl TypeMap {
         self.named_types.get(full_name).copied()
     }
 
+    pub fn get_extension_by_name(&self, full_name: &str) -> Option<ExtensionIndex> {
+        let full_name = full_name.strip_prefix('.').unwrap_or(full_name);
+        self.extension_names.get(full_name).copied()
+    }
+
     pub fn resolve_type_name(
         &self,
         mut namespace: &str,
@@ -1166,10 +1195,10 @@ impl TypeMap {
         }
     }
 
-    fn add_named_type(&mut self, full_name: Box<str>, ty: TypeId) -> Result<(), DescriptorError> {
+    fn add_named_type(&mut self, full_name: Arc<str>, ty: TypeId) -> Result<(), DescriptorError> {
         let full_name = full_name
             .strip_prefix('.')
-            .map(Box::from)
+            .map(Arc::from)
             .unwrap_or(full_name);
         match self.named_types.entry(full_name) {
             hash_map::Entry::Occupied(entry) => {
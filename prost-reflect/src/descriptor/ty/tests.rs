@@ -458,3 +458,47 @@ fn extension_extendee_type_not_message() {
         "invalid type 'my.package.MyMessage' for extension 'my.package.my_extension'"
     );
 }
+
+#[test]
+fn names_are_shared() {
+    fn message(name: &str) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_owned()),
+            field: vec![FieldDescriptorProto {
+                name: Some("id".to_owned()),
+                number: Some(1),
+                label: Some(Label::Optional as i32),
+                r#type: Some(Type::Int32 as i32),
+                json_name: Some("id".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    let mut descriptor_pool = DescriptorPool::new();
+    descriptor_pool
+        .add_file_descriptor_proto(FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            message_type: vec![message("First"), message("Second")],
+            ..Default::default()
+        })
+        .unwrap();
+    descriptor_pool
+        .add_file_descriptor_proto(FileDescriptorProto {
+            name: Some("otherfile.proto".to_owned()),
+            message_type: vec![message("Third")],
+            ..Default::default()
+        })
+        .unwrap();
+
+    let fields: Vec<_> = descriptor_pool
+        .all_messages()
+        .map(|message| message.get_field(1).unwrap())
+        .collect();
+    assert_eq!(fields.len(), 3);
+    for field in &fields[1..] {
+        assert!(std::ptr::eq(field.name(), fields[0].name()));
+        assert!(std::ptr::eq(field.json_name(), fields[0].json_name()));
+    }
+}
@@ -0,0 +1,114 @@
+use std::{error::Error, fmt};
+
+use prost::DecodeError;
+use prost_types::Any;
+
+use crate::{
+    resolver::{self, ResolveError, SchemaResolver},
+    DynamicMessage, ReflectMessage,
+};
+
+const TYPE_URL_PREFIX: &str = "type.googleapis.com/";
+
+/// An error returned when unpacking a `google.protobuf.Any` message.
+#[derive(Debug)]
+pub struct UnpackAnyError {
+    type_url: String,
+    kind: UnpackAnyErrorKind,
+}
+
+#[derive(Debug)]
+enum UnpackAnyErrorKind {
+    InvalidTypeUrl,
+    TypeMismatch { expected: String },
+    Decode(DecodeError),
+}
+
+impl DynamicMessage {
+    /// Encodes this message into a `google.protobuf.Any`.
+    ///
+    /// The type URL is the full name of the message type, prefixed with `type.googleapis.com/`.
+    pub fn pack_any(&self) -> Any {
+        pack_any(self)
+    }
+
+    /// Decodes the payload of a `google.protobuf.Any`, using `resolver` to look up its message type.
+    ///
+    /// This is equivalent to [`resolver::unpack_any`]. A [`DescriptorPool`][crate::DescriptorPool] may
+    /// be used as the resolver, in which case any type URL prefix is accepted, not just
+    /// `type.googleapis.com/`.
+    pub fn unpack_any(any: &Any, resolver: &dyn SchemaResolver) -> Result<Self, ResolveError> {
+        resolver::unpack_any(any, resolver)
+    }
+}
+
+pub(crate) fn pack_any<T>(message: &T) -> Any
+where
+    T: ReflectMessage,
+{
+    Any {
+        type_url: format!("{}{}", TYPE_URL_PREFIX, message.descriptor().full_name()),
+        value: message.encode_to_vec(),
+    }
+}
+
+pub(crate) fn unpack_any<T>(any: &Any) -> Result<T, UnpackAnyError>
+where
+    T: ReflectMessage + Default,
+{
+    let expected = T::default().descriptor();
+    if type_name(&any.type_url)? != expected.full_name() {
+        return Err(UnpackAnyError::new(
+            &any.type_url,
+            UnpackAnyErrorKind::TypeMismatch {
+                expected: expected.full_name().to_owned(),
+            },
+        ));
+    }
+    T::decode(any.value.as_slice())
+        .map_err(|err| UnpackAnyError::new(&any.type_url, UnpackAnyErrorKind::Decode(err)))
+}
+
+fn type_name(type_url: &str) -> Result<&str, UnpackAnyError> {
+    match type_url.rsplit_once('/') {
+        Some((_, name)) if !name.is_empty() => Ok(name),
+        _ => Err(UnpackAnyError::new(
+            type_url,
+            UnpackAnyErrorKind::InvalidTypeUrl,
+        )),
+    }
+}
+
+impl UnpackAnyError {
+    fn new(type_url: &str, kind: UnpackAnyErrorKind) -> Self {
+        UnpackAnyError {
+            type_url: type_url.to_owned(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for UnpackAnyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            UnpackAnyErrorKind::InvalidTypeUrl => write!(f, "invalid type url '{}'", self.type_url),
+            UnpackAnyErrorKind::TypeMismatch { expected } => write!(
+                f,
+                "type url '{}' does not match the expected message type '{}'",
+                self.type_url, expected
+            ),
+            UnpackAnyErrorKind::Decode(_) => {
+                write!(f, "failed to decode payload of type '{}'", self.type_url)
+            }
+        }
+    }
+}
+
+impl Error for UnpackAnyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            UnpackAnyErrorKind::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+
+use proptest::{
+    collection,
+    prelude::*,
+    strategy::{BoxedStrategy, Just, Union},
+};
+use prost::bytes::Bytes;
+
+use crate::{
+    Cardinality, DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, Syntax, Value,
+};
+
+use super::initialized::initialized_message;
+
+/// Options to control generation of arbitrary messages.
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+pub struct ArbitraryOptions {
+    max_depth: u32,
+    max_collection_size: usize,
+    string_pattern: Option<String>,
+    unknown_enum_values: bool,
+    non_finite_floats: bool,
+}
+
+impl DynamicMessage {
+    /// Returns a [`Strategy`] which generates arbitrary instances of the message type described by `desc`.
+    ///
+    /// This is equivalent to [`arbitrary_with_options`][Self::arbitrary_with_options] with the
+    /// default options.
+    #[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+    pub fn arbitrary(desc: MessageDescriptor) -> BoxedStrategy<DynamicMessage> {
+        Self::arbitrary_with_options(desc, &ArbitraryOptions::default())
+    }
+
+    /// Returns a [`Strategy`] which generates arbitrary instances of the message type described by `desc`,
+    /// using the generation parameters specified by `options`.
+    ///
+    /// Generated messages are always valid for their descriptor: at most one field of each oneof is set, required
+    /// fields are always populated, and fields without presence are only set to non-default values. Extension fields
+    /// and unknown fields are never generated.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the pattern set by [`ArbitraryOptions::string_pattern`] is not a valid regular expression.
+    #[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+    pub fn arbitrary_with_options(
+        desc: MessageDescriptor,
+        options: &ArbitraryOptions,
+    ) -> BoxedStrategy<DynamicMessage> {
+        let string = match &options.string_pattern {
+            Some(pattern) => proptest::string::string_regex(pattern)
+                .unwrap_or_else(|err| panic!("invalid string pattern {:?}: {}", pattern, err))
+                .boxed(),
+            None => any::<String>().boxed(),
+        };
+
+        let mut generator = Generator {
+            options,
+            string,
+            cache: HashMap::new(),
+        };
+        generator.message(&desc, 0)
+    }
+}
+
+impl ArbitraryOptions {
+    /// Creates a new instance of [`ArbitraryOptions`], with the default options.
+    pub const fn new() -> Self {
+        ArbitraryOptions {
+            max_depth: 4,
+            max_collection_size: 4,
+            string_pattern: None,
+            unknown_enum_values: false,
+            non_finite_floats: false,
+        }
+    }
+
+    /// The maximum depth of nested messages to generate.
+    ///
+    /// Once this depth is reached, message fields are left unset, and repeated or map fields of message
+    /// type are left empty. Required message fields are set to a message with only its own required fields
+    /// set, to their default values.
+    ///
+    /// The default value is `4`.
+    pub const fn max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// The maximum number of elements to generate for repeated and map fields, and the maximum length
+    /// of generated `bytes` values.
+    ///
+    /// The default value is `4`.
+    pub const fn max_collection_size(mut self, size: usize) -> Self {
+        self.max_collection_size = size;
+        self
+    }
+
+    /// A regular expression which generated `string` values will match.
+    ///
+    /// This can be used to restrict strings to a particular alphabet, for example `"[a-z]{0,8}"`. See
+    /// [`proptest::string::string_regex`] for the supported syntax.
+    ///
+    /// By default, strings are generated from any valid unicode characters.
+    pub fn string_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.string_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Whether to generate enum numbers which do not correspond to any value of the enum type.
+    ///
+    /// Unknown values are only generated for enums defined in proto3 files, since proto2 enums are closed
+    /// and unknown values would not be preserved when decoding.
+    ///
+    /// Regardless of this option, every value defined for an enum type may be generated.
+    ///
+    /// The default value is `false`.
+    pub const fn unknown_enum_values(mut self, yes: bool) -> Self {
+        self.unknown_enum_values = yes;
+        self
+    }
+
+    /// Whether to generate infinite and NaN values for `float` and `double` fields.
+    ///
+    /// Since NaN is not equal to itself, messages containing it will not compare equal after round-tripping.
+    ///
+    /// The default value is `false`.
+    pub const fn non_finite_floats(mut self, yes: bool) -> Self {
+        self.non_finite_floats = yes;
+        self
+    }
+}
+
+impl Default for ArbitraryOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Generator<'a> {
+    options: &'a ArbitraryOptions,
+    string: BoxedStrategy<String>,
+    cache: HashMap<(String, u32), BoxedStrategy<DynamicMessage>>,
+}
+
+type FieldStrategy = BoxedStrategy<Option<(FieldDescriptor, Value)>>;
+
+impl<'a> Generator<'a> {
+    fn message(&mut self, desc: &MessageDescriptor, depth: u32) -> BoxedStrategy<DynamicMessage> {
+        let key = (desc.full_name().to_owned(), depth);
+        if let Some(strategy) = self.cache.get(&key) {
+            return strategy.clone();
+        }
+
+        let mut fields: Vec<FieldStrategy> = Vec::new();
+        for field in desc.fields() {
+            if field.containing_oneof().is_some() {
+                continue;
+            }
+
+            match self.field(&field, depth) {
+                Some(value) => {
+                    if field.cardinality() == Cardinality::Required {
+                        fields.push(
+                            value
+                                .prop_map(move |value| Some((field.clone(), value)))
+                                .boxed(),
+                        );
+                    } else {
+                        fields.push(
+                            proptest::option::of(value)
+                                .prop_map(move |value| value.map(|value| (field.clone(), value)))
+                                .boxed(),
+                        );
+                    }
+                }
+                None => {
+                    if field.cardinality() == Cardinality::Required {
+                        // Required message fields beyond the maximum depth only have their own required
+                        // fields set.
+                        let value = match field.kind() {
+                            Kind::Message(message_desc) => {
+                                Value::Message(initialized_message(&message_desc))
+                            }
+                            _ => Value::default_value_for_field(&field),
+                        };
+                        fields.push(Just(Some((field.clone(), value))).boxed());
+                    }
+                }
+            }
+        }
+
+        for oneof in desc.oneofs() {
+            let mut options: Vec<FieldStrategy> = vec![Just(None).boxed()];
+            for field in oneof.fields() {
+                if let Some(value) = self.field(&field, depth) {
+                    options.push(
+                        value
+                            .prop_map(move |value| Some((field.clone(), value)))
+                            .boxed(),
+                    );
+                }
+            }
+            fields.push(Union::new(options).boxed());
+        }
+
+        let desc = desc.clone();
+        let strategy = fields
+            .prop_map(move |values| {
+                let mut message = DynamicMessage::new(desc.clone());
+                for (field, value) in values.into_iter().flatten() {
+                    if field.supports_presence() || !value.is_default_for_field(&field) {
+                        message.set_field(&field, value);
+                    }
+                }
+                message
+            })
+            .boxed();
+
+        self.cache.insert(key, strategy.clone());
+        strategy
+    }
+
+    /// Returns `None` if the field cannot be populated without exceeding the maximum depth.
+    fn field(&mut self, field: &FieldDescriptor, depth: u32) -> Option<BoxedStrategy<Value>> {
+        let size = 0..=self.options.max_collection_size;
+        if field.is_map() {
+            let entry = match field.kind() {
+                Kind::Message(entry) => entry,
+                _ => unreachable!("map field must be a message"),
+            };
+            let key = self.map_key(&entry.map_entry_key_field().kind());
+            let value = self.value(&entry.map_entry_value_field().kind(), depth);
+            Some(match value {
+                Some(value) => collection::hash_map(key, value, size)
+                    .prop_map(Value::Map)
+                    .boxed(),
+                None => Just(Value::Map(HashMap::new())).boxed(),
+            })
+        } else if field.is_list() {
+            Some(match self.value(&field.kind(), depth) {
+                Some(value) => collection::vec(value, size).prop_map(Value::List).boxed(),
+                None => Just(Value::List(Vec::new())).boxed(),
+            })
+        } else {
+            self.value(&field.kind(), depth)
+        }
+    }
+
+    fn value(&mut self, kind: &Kind, depth: u32) -> Option<BoxedStrategy<Value>> {
+        let strategy = match kind {
+            Kind::Double => self.double().prop_map(Value::F64).boxed(),
+            Kind::Float => self.float().prop_map(Value::F32).boxed(),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                any::<i32>().prop_map(Value::I32).boxed()
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                any::<i64>().prop_map(Value::I64).boxed()
+            }
+            Kind::Uint32 | Kind::Fixed32 => any::<u32>().prop_map(Value::U32).boxed(),
+            Kind::Uint64 | Kind::Fixed64 => any::<u64>().prop_map(Value::U64).boxed(),
+            Kind::Bool => any::<bool>().prop_map(Value::Bool).boxed(),
+            Kind::String => self.string.clone().prop_map(Value::String).boxed(),
+            Kind::Bytes => collection::vec(any::<u8>(), 0..=self.options.max_collection_size)
+                .prop_map(|bytes| Value::Bytes(Bytes::from(bytes)))
+                .boxed(),
+            Kind::Enum(enum_desc) => {
+                let values: Vec<i32> = enum_desc.values().map(|value| value.number()).collect();
+                let known = proptest::sample::select(values);
+                if self.options.unknown_enum_values
+                    && enum_desc.parent_file().syntax() == Syntax::Proto3
+                {
+                    prop_oneof![3 => known, 1 => any::<i32>()]
+                        .prop_map(Value::EnumNumber)
+                        .boxed()
+                } else {
+                    known.prop_map(Value::EnumNumber).boxed()
+                }
+            }
+            Kind::Message(message_desc) => {
+                if depth >= self.options.max_depth {
+                    return None;
+                }
+                self.message(message_desc, depth + 1)
+                    .prop_map(Value::Message)
+                    .boxed()
+            }
+        };
+        Some(strategy)
+    }
+
+    fn map_key(&self, kind: &Kind) -> BoxedStrategy<MapKey> {
+        match kind {
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                any::<i32>().prop_map(MapKey::I32).boxed()
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                any::<i64>().prop_map(MapKey::I64).boxed()
+            }
+            Kind::Uint32 | Kind::Fixed32 => any::<u32>().prop_map(MapKey::U32).boxed(),
+            Kind::Uint64 | Kind::Fixed64 => any::<u64>().prop_map(MapKey::U64).boxed(),
+            Kind::Bool => any::<bool>().prop_map(MapKey::Bool).boxed(),
+            Kind::String => self.string.clone().prop_map(MapKey::String).boxed(),
+            _ => unreachable!("invalid type for map key"),
+        }
+    }
+
+    fn double(&self) -> BoxedStrategy<f64> {
+        use proptest::num::f64::*;
+
+        if self.options.non_finite_floats {
+            ANY.boxed()
+        } else {
+            (POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO).boxed()
+        }
+    }
+
+    fn float(&self) -> BoxedStrategy<f32> {
+        use proptest::num::f32::*;
+
+        if self.options.non_finite_floats {
+            ANY.boxed()
+        } else {
+            (POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO).boxed()
+        }
+    }
+}
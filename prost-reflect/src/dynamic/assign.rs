@@ -0,0 +1,398 @@
+use std::{error::Error, fmt, fs, io};
+
+use prost::bytes::Bytes;
+
+use crate::{DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, Value};
+
+use super::bind::{expected_type, parse_map_key, parse_value};
+
+/// An error returned by [`DynamicMessage::assign`].
+#[derive(Debug)]
+pub struct AssignError {
+    assignment: String,
+    kind: AssignErrorKind,
+}
+
+#[derive(Debug)]
+enum AssignErrorKind {
+    Syntax {
+        message: &'static str,
+    },
+    FieldNotFound {
+        message: String,
+        name: String,
+    },
+    NotAMessage {
+        field: String,
+    },
+    NotIndexable {
+        field: String,
+    },
+    NotAList {
+        field: String,
+    },
+    MissingIndex {
+        field: String,
+    },
+    IndexOutOfRange {
+        field: String,
+        index: String,
+        len: usize,
+    },
+    MessageValue {
+        field: String,
+        value: String,
+    },
+    InvalidValue {
+        field: String,
+        value: String,
+        expected: String,
+    },
+    ReadFile {
+        path: String,
+        err: io::Error,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Set,
+    Append,
+}
+
+struct Segment<'a> {
+    name: &'a str,
+    index: Option<&'a str>,
+}
+
+impl DynamicMessage {
+    /// Updates this message from a shorthand assignment, such as one passed on the command line.
+    ///
+    /// The assignment has the form `path=value` or `path+=value`, where `path` is a sequence of field
+    /// names separated by `.`. Fields may be named by their name or their JSON name. For example:
+    ///
+    /// - `name=value` sets a singular field.
+    /// - `nested.field=3` sets a field of a nested message, creating the message if it is not set.
+    /// - `list+=x` appends to a repeated field, and `list=x` replaces its contents with a single value.
+    /// - `list[0]=x` sets an existing element of a repeated field.
+    /// - `labels[env]=prod` inserts an entry into a map field.
+    /// - `nested={}` sets a message field to an empty message, and `list+={}` appends an empty message.
+    ///
+    /// Values are parsed according to the type of the field. Numbers and booleans use their usual text
+    /// representation, and enums may be given by value name or by number. Strings are used as-is. Bytes
+    /// are also used as-is, except that a value starting with `@` names a file whose contents are used
+    /// instead, and a leading `@@` is replaced by a literal `@`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_types::FileDescriptorSet;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor);
+    /// message.assign("foo=150").unwrap();
+    /// assert_eq!(message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    ///
+    /// let err = message.assign("foo=bar").unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "invalid assignment 'foo=bar': invalid value 'bar' for field 'package.MyMessage.foo': expected a 32-bit integer",
+    /// );
+    /// ```
+    pub fn assign(&mut self, assignment: &str) -> Result<(), AssignError> {
+        let result = parse_assignment(assignment)
+            .and_then(|(path, operator, value)| assign_path(self, &path, operator, value));
+        result.map_err(|kind| AssignError {
+            assignment: assignment.to_owned(),
+            kind,
+        })
+    }
+
+    /// Applies a sequence of shorthand assignments to this message, in order.
+    ///
+    /// See [`assign`][DynamicMessage::assign] for the syntax of each assignment. Assignments are applied
+    /// until one fails, in which case the earlier assignments are not reverted.
+    pub fn assign_all<I>(&mut self, assignments: I) -> Result<(), AssignError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        for assignment in assignments {
+            self.assign(assignment.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AssignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid assignment '{}': ", self.assignment)?;
+        match &self.kind {
+            AssignErrorKind::Syntax { message } => f.write_str(message),
+            AssignErrorKind::FieldNotFound { message, name } => {
+                write!(f, "message '{}' has no field named '{}'", message, name)
+            }
+            AssignErrorKind::NotAMessage { field } => {
+                write!(f, "field '{}' is not a message", field)
+            }
+            AssignErrorKind::NotIndexable { field } => write!(
+                f,
+                "field '{}' is not a repeated or map field, and cannot be indexed",
+                field
+            ),
+            AssignErrorKind::NotAList { field } => write!(
+                f,
+                "field '{}' is not a repeated field, so '+=' cannot be used",
+                field
+            ),
+            AssignErrorKind::MissingIndex { field } => write!(
+                f,
+                "field '{}' must be indexed, for example '{}[key]'",
+                field,
+                field.rsplit('.').next().unwrap_or(field)
+            ),
+            AssignErrorKind::IndexOutOfRange { field, index, len } => write!(
+                f,
+                "index '{}' is out of range for field '{}' with {} elements",
+                index, field, len
+            ),
+            AssignErrorKind::MessageValue { field, value } => write!(
+                f,
+                "invalid value '{}' for message field '{}': set its fields individually, or use '{{}}' for an empty message",
+                value, field
+            ),
+            AssignErrorKind::InvalidValue {
+                field,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value '{}' for field '{}': expected {}",
+                value, field, expected
+            ),
+            AssignErrorKind::ReadFile { path, err } => {
+                write!(f, "failed to read file '{}': {}", path, err)
+            }
+        }
+    }
+}
+
+impl Error for AssignError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            AssignErrorKind::ReadFile { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+fn parse_assignment(
+    assignment: &str,
+) -> Result<(Vec<Segment<'_>>, Operator, &str), AssignErrorKind> {
+    let mut in_index = false;
+    let mut operator = None;
+    for (pos, ch) in assignment.char_indices() {
+        match ch {
+            '[' => in_index = true,
+            ']' => in_index = false,
+            '=' if !in_index => {
+                operator = Some(if assignment[..pos].ends_with('+') {
+                    (pos - 1, Operator::Append)
+                } else {
+                    (pos, Operator::Set)
+                });
+                break;
+            }
+            _ => (),
+        }
+    }
+
+    let (path_end, operator) = operator.ok_or(AssignErrorKind::Syntax {
+        message: "expected '=' or '+='",
+    })?;
+    let path = parse_path(&assignment[..path_end])?;
+    let value = &assignment[path_end..][if operator == Operator::Append { 2 } else { 1 }..];
+    Ok((path, operator, value))
+}
+
+fn parse_path(mut path: &str) -> Result<Vec<Segment<'_>>, AssignErrorKind> {
+    let mut segments = Vec::new();
+    loop {
+        let end = path.find(|ch| ch == '.' || ch == '[').unwrap_or(path.len());
+        let name = &path[..end];
+        if name.is_empty() {
+            return Err(AssignErrorKind::Syntax {
+                message: "expected a field name",
+            });
+        }
+        path = &path[end..];
+
+        let mut index = None;
+        if let Some(rest) = path.strip_prefix('[') {
+            let end = rest.find(']').ok_or(AssignErrorKind::Syntax {
+                message: "expected ']'",
+            })?;
+            index = Some(&rest[..end]);
+            path = &rest[end + 1..];
+        }
+        segments.push(Segment { name, index });
+
+        if path.is_empty() {
+            return Ok(segments);
+        }
+        path = path.strip_prefix('.').ok_or(AssignErrorKind::Syntax {
+            message: "expected '.' or '=' after ']'",
+        })?;
+    }
+}
+
+fn assign_path(
+    message: &mut DynamicMessage,
+    path: &[Segment<'_>],
+    operator: Operator,
+    value: &str,
+) -> Result<(), AssignErrorKind> {
+    let (segment, rest) = path.split_first().expect("path is not empty");
+    let field = find_field(&message.desc, segment.name)?;
+
+    let target = match segment.index {
+        Some(index) if field.is_map() => {
+            let entry = field.kind();
+            let entry = entry.as_message().expect("map field must be a message");
+            let key_kind = entry.map_entry_key_field().kind();
+            let value_kind = entry.map_entry_value_field().kind();
+
+            let key =
+                parse_map_key(&key_kind, index).ok_or_else(|| AssignErrorKind::InvalidValue {
+                    field: field.full_name().to_owned(),
+                    value: index.to_owned(),
+                    expected: expected_type(&key_kind),
+                })?;
+            let map = message
+                .get_field_mut(&field)
+                .as_map_mut()
+                .expect("expected map value");
+            if rest.is_empty() && operator == Operator::Set {
+                let value = parse_field_value(&field, &value_kind, value)?;
+                map.insert(key, value);
+                return Ok(());
+            }
+            map.entry(key)
+                .or_insert_with(|| Value::default_value(&value_kind))
+        }
+        Some(index) if field.is_list() => {
+            let list = message
+                .get_field_mut(&field)
+                .as_list_mut()
+                .expect("expected list value");
+            let element = match index.parse::<usize>() {
+                Ok(i) if i < list.len() => &mut list[i],
+                _ => {
+                    return Err(AssignErrorKind::IndexOutOfRange {
+                        field: field.full_name().to_owned(),
+                        index: index.to_owned(),
+                        len: list.len(),
+                    })
+                }
+            };
+            if rest.is_empty() && operator == Operator::Set {
+                *element = parse_field_value(&field, &field.kind(), value)?;
+                return Ok(());
+            }
+            element
+        }
+        Some(_) => {
+            return Err(AssignErrorKind::NotIndexable {
+                field: field.full_name().to_owned(),
+            })
+        }
+        None if field.is_map() || (field.is_list() && !rest.is_empty()) => {
+            return Err(AssignErrorKind::MissingIndex {
+                field: field.full_name().to_owned(),
+            })
+        }
+        None if rest.is_empty() => {
+            let value = parse_field_value(&field, &field.kind(), value)?;
+            match operator {
+                Operator::Set if field.is_list() => {
+                    message.set_field(&field, Value::List(vec![value]))
+                }
+                Operator::Set => message.set_field(&field, value),
+                Operator::Append => match message.get_field_mut(&field).as_list_mut() {
+                    Some(list) => list.push(value),
+                    None => {
+                        return Err(AssignErrorKind::NotAList {
+                            field: field.full_name().to_owned(),
+                        })
+                    }
+                },
+            }
+            return Ok(());
+        }
+        None => message.get_field_mut(&field),
+    };
+
+    match target.as_message_mut() {
+        Some(nested) if !rest.is_empty() => assign_path(nested, rest, operator, value),
+        Some(_) => Err(AssignErrorKind::NotAList {
+            field: field.full_name().to_owned(),
+        }),
+        None if rest.is_empty() => Err(AssignErrorKind::NotAList {
+            field: field.full_name().to_owned(),
+        }),
+        None => Err(AssignErrorKind::NotAMessage {
+            field: field.full_name().to_owned(),
+        }),
+    }
+}
+
+fn find_field(desc: &MessageDescriptor, name: &str) -> Result<FieldDescriptor, AssignErrorKind> {
+    desc.get_field_by_name(name)
+        .or_else(|| desc.get_field_by_json_name(name))
+        .ok_or_else(|| AssignErrorKind::FieldNotFound {
+            message: desc.full_name().to_owned(),
+            name: name.to_owned(),
+        })
+}
+
+fn parse_field_value(
+    field: &FieldDescriptor,
+    kind: &Kind,
+    value: &str,
+) -> Result<Value, AssignErrorKind> {
+    let invalid_value = || AssignErrorKind::InvalidValue {
+        field: field.full_name().to_owned(),
+        value: value.to_owned(),
+        expected: expected_type(kind),
+    };
+
+    match kind {
+        Kind::Message(message_desc) => {
+            if value == "{}" {
+                Ok(Value::Message(DynamicMessage::new(message_desc.clone())))
+            } else {
+                Err(AssignErrorKind::MessageValue {
+                    field: field.full_name().to_owned(),
+                    value: value.to_owned(),
+                })
+            }
+        }
+        Kind::String => Ok(Value::String(value.to_owned())),
+        Kind::Bytes => {
+            let contents = match value.strip_prefix('@') {
+                Some(path) if !path.starts_with('@') => {
+                    fs::read(path).map_err(|err| AssignErrorKind::ReadFile {
+                        path: path.to_owned(),
+                        err,
+                    })?
+                }
+                Some(literal) => literal.as_bytes().to_vec(),
+                None => value.as_bytes().to_vec(),
+            };
+            Ok(Value::Bytes(Bytes::from(contents)))
+        }
+        kind => parse_value(kind, value).ok_or_else(invalid_value),
+    }
+}
@@ -0,0 +1,368 @@
+use std::{env, error::Error, fmt};
+
+use prost::bytes::Bytes;
+
+use crate::{DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value};
+
+/// Options to control how keys are mapped to fields by [`DynamicMessage::bind`] and
+/// [`DynamicMessage::bind_env`].
+#[derive(Debug, Clone)]
+pub struct BindOptions {
+    prefix: String,
+    separator: String,
+    list_separator: char,
+    ignore_unknown_keys: bool,
+}
+
+/// An error returned by [`DynamicMessage::bind`] or [`DynamicMessage::bind_env`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindError {
+    key: String,
+    kind: BindErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum BindErrorKind {
+    FieldNotFound {
+        message: String,
+        name: String,
+    },
+    MissingMapKey {
+        field: String,
+    },
+    NotAMessage {
+        field: String,
+    },
+    MessageValue {
+        field: String,
+    },
+    InvalidValue {
+        field: String,
+        value: String,
+        expected: String,
+    },
+}
+
+impl DynamicMessage {
+    /// Sets fields of this message from a set of key-value pairs, such as command line flags or the
+    /// contents of a configuration file.
+    ///
+    /// Keys which start with the [prefix][BindOptions::prefix] are split by the
+    /// [separator][BindOptions::separator] into a path of field names, which are matched against the
+    /// field names or JSON names of the message, ignoring ASCII case. For example, with the default
+    /// options, the key `server.port` sets the `port` field of the `server` field.
+    ///
+    /// Values are converted to the type of the field:
+    ///
+    /// - Numbers and booleans are parsed from their usual text representation. Booleans also accept `1`
+    ///   and `0`.
+    /// - Strings are used as-is, and bytes fields are set to the UTF-8 encoding of the value.
+    /// - Enums may be given by value name or by number.
+    /// - Repeated fields are set from a list of values separated by the
+    ///   [list separator][BindOptions::list_separator], replacing any existing values.
+    /// - Map fields take their key from the next segment of the path, for example `labels.env=prod`.
+    ///
+    /// Binding stops at the first invalid key or value, in which case the fields set by earlier pairs are
+    /// not reverted.
+    pub fn bind<I, K, V>(&mut self, pairs: I, options: &BindOptions) -> Result<(), BindError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (key, value) in pairs {
+            let key = key.as_ref();
+            let path = match key.strip_prefix(options.prefix.as_str()) {
+                Some(path) => path,
+                None => continue,
+            };
+
+            let segments: Vec<&str> = path.split(options.separator.as_str()).collect();
+            match bind_path(self, &segments, value.as_ref(), options) {
+                Ok(()) => (),
+                Err(BindErrorKind::FieldNotFound { .. }) if options.ignore_unknown_keys => (),
+                Err(kind) => {
+                    return Err(BindError {
+                        key: key.to_owned(),
+                        kind,
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets fields of this message from the environment variables of the current process.
+    ///
+    /// This is equivalent to calling [`bind`][DynamicMessage::bind] with [`std::env::vars`], except that
+    /// variables whose name or value is not valid unicode are skipped. Typically the options are created
+    /// with [`BindOptions::env`], so that for example `APP_SERVER__PORT=8080` sets the `port` field of the
+    /// `server` field.
+    pub fn bind_env(&mut self, options: &BindOptions) -> Result<(), BindError> {
+        let vars = env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)));
+        self.bind(vars, options)
+    }
+}
+
+impl BindOptions {
+    /// Creates a new instance of [`BindOptions`], with the default options.
+    ///
+    /// By default there is no prefix, path segments are separated by `.`, and list values by `,`.
+    pub fn new() -> Self {
+        BindOptions {
+            prefix: String::new(),
+            separator: ".".to_owned(),
+            list_separator: ',',
+            ignore_unknown_keys: false,
+        }
+    }
+
+    /// Creates options for binding environment variables which start with `prefix` followed by `_`, and
+    /// whose path segments are separated by `__`.
+    ///
+    /// For example, `BindOptions::env("APP")` maps `APP_SERVER__PORT` to the field path `server.port`.
+    pub fn env(prefix: &str) -> Self {
+        BindOptions::new()
+            .prefix(format!("{}_", prefix))
+            .separator("__")
+    }
+
+    /// Only keys which start with `prefix` are bound, and all other keys are skipped. The prefix is removed
+    /// before the key is split into a path.
+    ///
+    /// The default value is the empty string, so all keys are bound.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// The string separating the field names in a key.
+    ///
+    /// The default value is `.`.
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// The character separating the elements of a value for a repeated field.
+    ///
+    /// The default value is `,`.
+    pub const fn list_separator(mut self, separator: char) -> Self {
+        self.list_separator = separator;
+        self
+    }
+
+    /// Whether to skip keys which do not match any field, instead of returning an error.
+    ///
+    /// The default value is `false`.
+    pub const fn ignore_unknown_keys(mut self, yes: bool) -> Self {
+        self.ignore_unknown_keys = yes;
+        self
+    }
+}
+
+impl Default for BindOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BindError {
+    /// Gets the key which could not be bound.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid key '{}': ", self.key)?;
+        match &self.kind {
+            BindErrorKind::FieldNotFound { message, name } => {
+                write!(f, "message '{}' has no field named '{}'", message, name)
+            }
+            BindErrorKind::MissingMapKey { field } => {
+                write!(f, "missing map key for field '{}'", field)
+            }
+            BindErrorKind::NotAMessage { field } => {
+                write!(f, "field '{}' is not a message", field)
+            }
+            BindErrorKind::MessageValue { field } => write!(
+                f,
+                "field '{}' is a message, and its fields must be set individually",
+                field
+            ),
+            BindErrorKind::InvalidValue {
+                field,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value '{}' for field '{}': expected {}",
+                value, field, expected
+            ),
+        }
+    }
+}
+
+impl Error for BindError {}
+
+fn bind_path(
+    message: &mut DynamicMessage,
+    segments: &[&str],
+    value: &str,
+    options: &BindOptions,
+) -> Result<(), BindErrorKind> {
+    let (name, rest) = segments.split_first().expect("path is not empty");
+    let field = find_field(&message.desc, name).ok_or_else(|| BindErrorKind::FieldNotFound {
+        message: message.desc.full_name().to_owned(),
+        name: (*name).to_owned(),
+    })?;
+
+    if field.is_map() {
+        let (key, rest) = rest
+            .split_first()
+            .ok_or_else(|| BindErrorKind::MissingMapKey {
+                field: field.full_name().to_owned(),
+            })?;
+        let entry = field.kind();
+        let entry = entry.as_message().expect("map field must be a message");
+        let key_field = entry.map_entry_key_field();
+        let value_field = entry.map_entry_value_field();
+
+        let key = parse_map_key(&key_field.kind(), key)
+            .ok_or_else(|| invalid_value(&field, key, &key_field.kind()))?;
+        let map = message
+            .get_field_mut(&field)
+            .as_map_mut()
+            .expect("expected map value");
+        if rest.is_empty() {
+            let value = parse_scalar(&value_field, value)?;
+            map.insert(key, value);
+        } else {
+            let nested = map
+                .entry(key)
+                .or_insert_with(|| Value::default_value(&value_field.kind()));
+            match nested.as_message_mut() {
+                Some(nested) => bind_path(nested, rest, value, options)?,
+                None => {
+                    return Err(BindErrorKind::NotAMessage {
+                        field: value_field.full_name().to_owned(),
+                    })
+                }
+            }
+        }
+    } else if !rest.is_empty() {
+        match message.get_field_mut(&field).as_message_mut() {
+            Some(nested) if !field.is_list() => bind_path(nested, rest, value, options)?,
+            _ => {
+                return Err(BindErrorKind::NotAMessage {
+                    field: field.full_name().to_owned(),
+                })
+            }
+        }
+    } else if field.is_list() {
+        let values = if value.is_empty() {
+            Vec::new()
+        } else {
+            value
+                .split(options.list_separator)
+                .map(|element| parse_scalar(&field, element))
+                .collect::<Result<_, _>>()?
+        };
+        message.set_field(&field, Value::List(values));
+    } else {
+        let value = parse_scalar(&field, value)?;
+        message.set_field(&field, value);
+    }
+
+    Ok(())
+}
+
+pub(super) fn find_field(desc: &MessageDescriptor, name: &str) -> Option<FieldDescriptor> {
+    desc.get_field_by_name(name)
+        .or_else(|| desc.get_field_by_json_name(name))
+        .or_else(|| {
+            desc.fields().find(|field| {
+                field.name().eq_ignore_ascii_case(name)
+                    || field.json_name().eq_ignore_ascii_case(name)
+            })
+        })
+}
+
+/// Parses the text representation of a single value of `field`, which must not be a message.
+fn parse_scalar(field: &FieldDescriptor, value: &str) -> Result<Value, BindErrorKind> {
+    let kind = field.kind();
+    if kind.as_message().is_some() {
+        return Err(BindErrorKind::MessageValue {
+            field: field.full_name().to_owned(),
+        });
+    }
+    parse_value(&kind, value).ok_or_else(|| invalid_value(field, value, &kind))
+}
+
+pub(super) fn parse_value(kind: &Kind, value: &str) -> Option<Value> {
+    match kind {
+        Kind::Double => value.parse().ok().map(Value::F64),
+        Kind::Float => value.parse().ok().map(Value::F32),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => value.parse().ok().map(Value::I32),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => value.parse().ok().map(Value::I64),
+        Kind::Uint32 | Kind::Fixed32 => value.parse().ok().map(Value::U32),
+        Kind::Uint64 | Kind::Fixed64 => value.parse().ok().map(Value::U64),
+        Kind::Bool => parse_bool(value).map(Value::Bool),
+        Kind::String => Some(Value::String(value.to_owned())),
+        Kind::Bytes => Some(Value::Bytes(Bytes::copy_from_slice(value.as_bytes()))),
+        Kind::Enum(enum_desc) => match enum_desc.get_value_by_name(value) {
+            Some(enum_value) => Some(Value::EnumNumber(enum_value.number())),
+            None => value.parse().ok().map(Value::EnumNumber),
+        },
+        Kind::Message(_) => None,
+    }
+}
+
+pub(super) fn parse_map_key(kind: &Kind, key: &str) -> Option<MapKey> {
+    match kind {
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => key.parse().ok().map(MapKey::I32),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => key.parse().ok().map(MapKey::I64),
+        Kind::Uint32 | Kind::Fixed32 => key.parse().ok().map(MapKey::U32),
+        Kind::Uint64 | Kind::Fixed64 => key.parse().ok().map(MapKey::U64),
+        Kind::Bool => parse_bool(key).map(MapKey::Bool),
+        Kind::String => Some(MapKey::String(key.to_owned())),
+        _ => None,
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    if value.eq_ignore_ascii_case("true") || value == "1" {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("false") || value == "0" {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn invalid_value(field: &FieldDescriptor, value: &str, kind: &Kind) -> BindErrorKind {
+    BindErrorKind::InvalidValue {
+        field: field.full_name().to_owned(),
+        value: value.to_owned(),
+        expected: expected_type(kind),
+    }
+}
+
+pub(super) fn expected_type(kind: &Kind) -> String {
+    match kind {
+        Kind::Double => "a double".to_owned(),
+        Kind::Float => "a float".to_owned(),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => "a 32-bit integer".to_owned(),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => "a 64-bit integer".to_owned(),
+        Kind::Uint32 | Kind::Fixed32 => "an unsigned 32-bit integer".to_owned(),
+        Kind::Uint64 | Kind::Fixed64 => "an unsigned 64-bit integer".to_owned(),
+        Kind::Bool => "a boolean".to_owned(),
+        Kind::String => "a string".to_owned(),
+        Kind::Bytes => "bytes".to_owned(),
+        Kind::Enum(enum_desc) => format!("a value of enum '{}'", enum_desc.full_name()),
+        Kind::Message(message_desc) => format!("a message of type '{}'", message_desc.full_name()),
+    }
+}
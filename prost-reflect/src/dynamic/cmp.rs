@@ -0,0 +1,195 @@
+use std::cmp::Ordering;
+
+use crate::{DynamicMessage, MapKey, Value};
+
+use super::{fields::ValueAndDescriptor, unknown::UnknownFieldValue};
+
+impl PartialOrd for Value {
+    /// Compares two values of the same type.
+    ///
+    /// Scalar values of the same variant are compared by their natural order, with floating point values
+    /// compared as by [`f64::partial_cmp`] except that NaN values are equal to each other, consistent
+    /// with [`PartialEq`], and lists are compared lexicographically. Values of different
+    /// variants, and messages and maps which are not equal, are not comparable. Use
+    /// [`total_cmp`][Value::total_cmp] to order any values.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Bool(l), Value::Bool(r)) => l.partial_cmp(r),
+            (Value::I32(l), Value::I32(r)) => l.partial_cmp(r),
+            (Value::I64(l), Value::I64(r)) => l.partial_cmp(r),
+            (Value::U32(l), Value::U32(r)) => l.partial_cmp(r),
+            (Value::U64(l), Value::U64(r)) => l.partial_cmp(r),
+            (Value::F32(l), Value::F32(r)) if l.is_nan() && r.is_nan() => Some(Ordering::Equal),
+            (Value::F64(l), Value::F64(r)) if l.is_nan() && r.is_nan() => Some(Ordering::Equal),
+            (Value::F32(l), Value::F32(r)) => l.partial_cmp(r),
+            (Value::F64(l), Value::F64(r)) => l.partial_cmp(r),
+            (Value::String(l), Value::String(r)) => l.partial_cmp(r),
+            (Value::Bytes(l), Value::Bytes(r)) => l.partial_cmp(r),
+            (Value::EnumNumber(l), Value::EnumNumber(r)) => l.partial_cmp(r),
+            (Value::List(l), Value::List(r)) => l.partial_cmp(r),
+            (Value::Message(_), Value::Message(_)) | (Value::Map(_), Value::Map(_))
+                if self == other =>
+            {
+                Some(Ordering::Equal)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Value {
+    /// Returns an ordering between this value and `other`, which is a total order over all values.
+    ///
+    /// Values of different variants are ordered by the order of the variants in [`Value`]. Scalar values
+    /// of the same variant are compared by their natural order, except that floating point values are
+    /// ordered as by `f64::total_cmp`, so `-0.0` is less than `0.0`, and NaN values are ordered after
+    /// infinity or before negative infinity, depending on their sign. Lists are compared
+    /// lexicographically, and maps are compared as lists of entries sorted by key.
+    ///
+    /// Messages are ordered by the full name of their type, and then as lists of their set fields in field
+    /// number order, where each field is compared by its number and then its value. Unknown fields are
+    /// compared by their encoded bytes.
+    ///
+    /// This can be used to sort repeated fields deterministically, for example with
+    /// `values.sort_by(Value::total_cmp)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::Value;
+    /// let mut values = vec![Value::F64(1.0), Value::F64(f64::NAN), Value::F64(-0.0), Value::F64(0.0)];
+    /// values.sort_by(Value::total_cmp);
+    /// assert_eq!(values[0].as_f64().unwrap().to_bits(), (-0.0f64).to_bits());
+    /// assert_eq!(values[1].as_f64().unwrap().to_bits(), 0.0f64.to_bits());
+    /// assert_eq!(values[2], Value::F64(1.0));
+    /// assert!(values[3].as_f64().unwrap().is_nan());
+    /// ```
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Bool(l), Value::Bool(r)) => l.cmp(r),
+            (Value::I32(l), Value::I32(r)) => l.cmp(r),
+            (Value::I64(l), Value::I64(r)) => l.cmp(r),
+            (Value::U32(l), Value::U32(r)) => l.cmp(r),
+            (Value::U64(l), Value::U64(r)) => l.cmp(r),
+            (Value::F32(l), Value::F32(r)) => total_cmp_f32(*l, *r),
+            (Value::F64(l), Value::F64(r)) => total_cmp_f64(*l, *r),
+            (Value::String(l), Value::String(r)) => l.cmp(r),
+            (Value::Bytes(l), Value::Bytes(r)) => l.cmp(r),
+            (Value::EnumNumber(l), Value::EnumNumber(r)) => l.cmp(r),
+            (Value::Message(l), Value::Message(r)) => total_cmp_message(l, r),
+            (Value::List(l), Value::List(r)) => {
+                total_cmp_iter(l.iter(), r.iter(), Value::total_cmp)
+            }
+            (Value::Map(l), Value::Map(r)) => {
+                let mut l: Vec<(&MapKey, &Value)> = l.iter().collect();
+                l.sort_by_key(|(key, _)| *key);
+                let mut r: Vec<(&MapKey, &Value)> = r.iter().collect();
+                r.sort_by_key(|(key, _)| *key);
+                total_cmp_iter(l.into_iter(), r.into_iter(), |(lk, lv), (rk, rv)| {
+                    lk.cmp(rk).then_with(|| lv.total_cmp(rv))
+                })
+            }
+            _ => variant_index(self).cmp(&variant_index(other)),
+        }
+    }
+}
+
+fn variant_index(value: &Value) -> u8 {
+    match value {
+        Value::Bool(_) => 0,
+        Value::I32(_) => 1,
+        Value::I64(_) => 2,
+        Value::U32(_) => 3,
+        Value::U64(_) => 4,
+        Value::F32(_) => 5,
+        Value::F64(_) => 6,
+        Value::String(_) => 7,
+        Value::Bytes(_) => 8,
+        Value::EnumNumber(_) => 9,
+        Value::Message(_) => 10,
+        Value::List(_) => 11,
+        Value::Map(_) => 12,
+    }
+}
+
+fn total_cmp_message(l: &DynamicMessage, r: &DynamicMessage) -> Ordering {
+    l.desc.full_name().cmp(r.desc.full_name()).then_with(|| {
+        total_cmp_iter(
+            l.fields.iter(&l.desc),
+            r.fields.iter(&r.desc),
+            |l, r| match (l, r) {
+                (ValueAndDescriptor::Field(lv, ld), ValueAndDescriptor::Field(rv, rd)) => {
+                    ld.number().cmp(&rd.number()).then_with(|| lv.total_cmp(rv))
+                }
+                (ValueAndDescriptor::Extension(lv, ld), ValueAndDescriptor::Extension(rv, rd)) => {
+                    ld.number().cmp(&rd.number()).then_with(|| lv.total_cmp(rv))
+                }
+                (ValueAndDescriptor::Unknown(ln, lv), ValueAndDescriptor::Unknown(rn, rv)) => ln
+                    .cmp(&rn)
+                    .then_with(|| encode_unknown(ln, lv).cmp(&encode_unknown(rn, rv))),
+                (l, r) => field_number(&l)
+                    .cmp(&field_number(&r))
+                    .then_with(|| field_rank(&l).cmp(&field_rank(&r))),
+            },
+        )
+    })
+}
+
+fn field_number(field: &ValueAndDescriptor) -> u32 {
+    match field {
+        ValueAndDescriptor::Field(_, desc) => desc.number(),
+        ValueAndDescriptor::Extension(_, desc) => desc.number(),
+        ValueAndDescriptor::Unknown(number, _) => *number,
+    }
+}
+
+fn field_rank(field: &ValueAndDescriptor) -> u8 {
+    match field {
+        ValueAndDescriptor::Field(..) => 0,
+        ValueAndDescriptor::Extension(..) => 1,
+        ValueAndDescriptor::Unknown(..) => 2,
+    }
+}
+
+fn encode_unknown(number: u32, values: &[UnknownFieldValue]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for value in values {
+        value.encode_field(number, &mut buf);
+    }
+    buf
+}
+
+fn total_cmp_iter<T>(
+    mut l: impl Iterator<Item = T>,
+    mut r: impl Iterator<Item = T>,
+    mut cmp: impl FnMut(T, T) -> Ordering,
+) -> Ordering {
+    loop {
+        match (l.next(), r.next()) {
+            (Some(l), Some(r)) => match cmp(l, r) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            },
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (None, None) => return Ordering::Equal,
+        }
+    }
+}
+
+// Equivalent to `f32::total_cmp` and `f64::total_cmp`, which require a newer version of Rust.
+fn total_cmp_f32(l: f32, r: f32) -> Ordering {
+    let mut l = l.to_bits() as i32;
+    let mut r = r.to_bits() as i32;
+    l ^= (((l >> 31) as u32) >> 1) as i32;
+    r ^= (((r >> 31) as u32) >> 1) as i32;
+    l.cmp(&r)
+}
+
+fn total_cmp_f64(l: f64, r: f64) -> Ordering {
+    let mut l = l.to_bits() as i64;
+    let mut r = r.to_bits() as i64;
+    l ^= (((l >> 63) as u64) >> 1) as i64;
+    r ^= (((r >> 63) as u64) >> 1) as i64;
+    l.cmp(&r)
+}
@@ -0,0 +1,269 @@
+use std::{collections::HashMap, convert::TryFrom, error::Error, fmt};
+
+use prost::bytes::Bytes;
+
+use crate::{DynamicMessage, Kind, MapKey, MessageDescriptor, SetFieldError, Value};
+
+use super::fields::ValueAndDescriptor;
+
+/// An error returned when converting a [`Value`] to a type other than the one it holds.
+///
+/// The original value can be recovered with [`into_value`][TryFromValueError::into_value].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TryFromValueError {
+    expected: &'static str,
+    value: Value,
+}
+
+macro_rules! value_conversions {
+    ($($ty:ty, $variant:ident, $wanted:literal;)*) => {
+        $(
+            impl From<$ty> for Value {
+                #[doc = concat!("Converts the value to a [`Value::", stringify!($variant), "`].")]
+                fn from(value: $ty) -> Self {
+                    Value::$variant(value)
+                }
+            }
+
+            impl TryFrom<Value> for $ty {
+                type Error = TryFromValueError;
+
+                #[doc = concat!("Converts a [`Value::", stringify!($variant), "`] to ", $wanted, ".")]
+                ///
+                /// Returns an error for any other variant. Values are not converted between numeric types.
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    match value {
+                        Value::$variant(value) => Ok(value),
+                        value => Err(TryFromValueError::new($wanted, value)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+value_conversions! {
+    bool, Bool, "a bool";
+    i32, I32, "a 32-bit integer";
+    i64, I64, "a 64-bit integer";
+    u32, U32, "an unsigned 32-bit integer";
+    u64, U64, "an unsigned 64-bit integer";
+    f32, F32, "a float";
+    f64, F64, "a double";
+    String, String, "a string";
+    Bytes, Bytes, "bytes";
+    DynamicMessage, Message, "a message";
+    Vec<Value>, List, "a list";
+    HashMap<MapKey, Value>, Map, "a map";
+}
+
+impl From<&str> for Value {
+    /// Converts the string to a [`Value::String`].
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    /// Converts the bytes to a [`Value::Bytes`].
+    fn from(value: Vec<u8>) -> Self {
+        Value::Bytes(value.into())
+    }
+}
+
+impl TryFrom<Value> for Vec<u8> {
+    type Error = TryFromValueError;
+
+    /// Converts a [`Value::Bytes`] to a vector of bytes.
+    ///
+    /// Returns an error for any other variant.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bytes(value) => Ok(value.to_vec()),
+            value => Err(TryFromValueError::new("bytes", value)),
+        }
+    }
+}
+
+impl Value {
+    /// Converts this value into a list, or returns an error if it is not a [`Value::List`].
+    pub fn try_into_list(self) -> Result<Vec<Value>, TryFromValueError> {
+        Vec::try_from(self)
+    }
+
+    /// Converts this value into a map, or returns an error if it is not a [`Value::Map`].
+    pub fn try_into_map(self) -> Result<HashMap<MapKey, Value>, TryFromValueError> {
+        HashMap::try_from(self)
+    }
+}
+
+impl TryFromValueError {
+    fn new(expected: &'static str, value: Value) -> Self {
+        TryFromValueError { expected, value }
+    }
+
+    /// Gets the value which could not be converted.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Converts this error into the value which could not be converted.
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+}
+
+impl fmt::Display for TryFromValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {}, but got {}",
+            self.expected,
+            variant_name(&self.value)
+        )
+    }
+}
+
+impl Error for TryFromValueError {}
+
+impl DynamicMessage {
+    /// Converts this message to a map of its field values, keyed by field name.
+    ///
+    /// Only fields which are set are included. Nested messages are converted recursively to
+    /// [`Value::Map`]s with [`MapKey::String`] keys, including messages in lists and map fields. Other
+    /// values are unchanged, so enum values are represented by their number. Extensions and unknown
+    /// fields are not included.
+    ///
+    /// This is useful for passing messages to templating engines or other dynamically-typed code. Use
+    /// [`from_map`][Self::from_map] to convert the map back to a message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor.clone());
+    /// message.set_field_by_name("foo", Value::I32(150));
+    ///
+    /// let map = message.to_map();
+    /// assert_eq!(map["foo"], Value::I32(150));
+    /// assert_eq!(DynamicMessage::from_map(message_descriptor, map).unwrap(), message);
+    /// ```
+    pub fn to_map(&self) -> HashMap<String, Value> {
+        self.fields
+            .iter(&self.desc)
+            .filter_map(|field| match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    Some((field_desc.name().to_owned(), value_to_map(value)))
+                }
+                ValueAndDescriptor::Extension(..) | ValueAndDescriptor::Unknown(..) => None,
+            })
+            .collect()
+    }
+
+    /// Creates a message of type `desc` from a map of field values keyed by field name, as returned by
+    /// [`to_map`][Self::to_map].
+    ///
+    /// Values for message fields may be either [`Value::Message`]s or [`Value::Map`]s with
+    /// [`MapKey::String`] keys, which are converted recursively. All other values must have the type
+    /// expected by their field, as for [`try_set_field`][Self::try_set_field].
+    ///
+    /// Returns an error if the message has no field with one of the names in `map`, or if a value is not
+    /// valid for its field.
+    pub fn from_map(
+        desc: MessageDescriptor,
+        map: HashMap<String, Value>,
+    ) -> Result<DynamicMessage, SetFieldError> {
+        let mut message = DynamicMessage::new(desc);
+        for (name, value) in map {
+            let value = match message.desc.get_field_by_name(&name) {
+                Some(field_desc) => value_from_map(value, &field_desc.kind())?,
+                None => value,
+            };
+            message.try_set_field_by_name(&name, value)?;
+        }
+        Ok(message)
+    }
+}
+
+fn value_to_map(value: &Value) -> Value {
+    match value {
+        Value::Message(message) => Value::Map(
+            message
+                .to_map()
+                .into_iter()
+                .map(|(name, value)| (MapKey::String(name), value))
+                .collect(),
+        ),
+        Value::List(values) => Value::List(values.iter().map(value_to_map).collect()),
+        Value::Map(values) => Value::Map(
+            values
+                .iter()
+                .map(|(key, value)| (key.clone(), value_to_map(value)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Converts the maps representing messages in a value for a field of type `kind` back to messages.
+///
+/// Values which do not have the expected shape are returned unchanged, so that setting the field reports
+/// the type mismatch.
+fn value_from_map(value: Value, kind: &Kind) -> Result<Value, SetFieldError> {
+    let message_desc = match kind {
+        Kind::Message(message_desc) => message_desc,
+        _ => return Ok(value),
+    };
+
+    match value {
+        Value::List(values) => Ok(Value::List(
+            values
+                .into_iter()
+                .map(|value| value_from_map(value, kind))
+                .collect::<Result<_, _>>()?,
+        )),
+        Value::Map(values) if message_desc.is_map_entry() => {
+            let value_kind = message_desc.map_entry_value_field().kind();
+            Ok(Value::Map(
+                values
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, value_from_map(value, &value_kind)?)))
+                    .collect::<Result<_, _>>()?,
+            ))
+        }
+        Value::Map(values) if values.keys().all(|key| matches!(key, MapKey::String(_))) => {
+            let fields = values
+                .into_iter()
+                .map(|(key, value)| match key {
+                    MapKey::String(name) => (name, value),
+                    _ => unreachable!(),
+                })
+                .collect();
+            Ok(Value::Message(DynamicMessage::from_map(
+                message_desc.clone(),
+                fields,
+            )?))
+        }
+        value => Ok(value),
+    }
+}
+
+pub(super) fn variant_name(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "a bool",
+        Value::I32(_) => "a 32-bit integer",
+        Value::I64(_) => "a 64-bit integer",
+        Value::U32(_) => "an unsigned 32-bit integer",
+        Value::U64(_) => "an unsigned 64-bit integer",
+        Value::F32(_) => "a float",
+        Value::F64(_) => "a double",
+        Value::String(_) => "a string",
+        Value::Bytes(_) => "bytes",
+        Value::EnumNumber(_) => "an enum number",
+        Value::Message(_) => "a message",
+        Value::List(_) => "a list",
+        Value::Map(_) => "a map",
+    }
+}
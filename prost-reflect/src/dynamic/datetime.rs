@@ -0,0 +1,352 @@
+use std::{
+    convert::TryFrom,
+    error::Error,
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{DynamicMessage, MessageDescriptor, Value};
+
+use super::convert::variant_name;
+
+const TIMESTAMP_NAME: &str = "google.protobuf.Timestamp";
+const DURATION_NAME: &str = "google.protobuf.Duration";
+
+const NANOS_PER_SECOND: u32 = 1_000_000_000;
+const MAX_NANOS: u32 = 999_999_999;
+/// The earliest timestamp which can be represented in RFC 3339 format, `0001-01-01T00:00:00Z`.
+const MIN_TIMESTAMP_SECONDS: i64 = -62_135_596_800;
+/// The latest timestamp which can be represented in RFC 3339 format, `9999-12-31T23:59:59Z`.
+const MAX_TIMESTAMP_SECONDS: i64 = 253_402_300_799;
+/// The largest duration which can be represented in the JSON mapping, roughly 10,000 years.
+const MAX_DURATION_SECONDS: u64 = 315_576_000_000;
+
+/// An error that may occur when converting between `google.protobuf.Timestamp` or
+/// `google.protobuf.Duration` messages and native date and time types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeConversionError {
+    kind: TimeConversionErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TimeConversionErrorKind {
+    MessageType {
+        expected: &'static str,
+        actual: String,
+    },
+    NotAMessage {
+        actual: &'static str,
+    },
+    TimestampOutOfRange,
+    DurationOutOfRange,
+    InconsistentSigns,
+    NegativeDuration,
+}
+
+impl DynamicMessage {
+    /// Creates a `google.protobuf.Timestamp` message of type `desc` representing `time`.
+    ///
+    /// Returns an error if `desc` is not the `google.protobuf.Timestamp` message, or if `time` is
+    /// outside the range of valid timestamps, from `0001-01-01T00:00:00Z` to
+    /// `9999-12-31T23:59:59.999999999Z` inclusive.
+    pub fn from_system_time(
+        desc: MessageDescriptor,
+        time: SystemTime,
+    ) -> Result<DynamicMessage, TimeConversionError> {
+        let (seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => (
+                i64::try_from(duration.as_secs()).map_err(|_| timestamp_out_of_range())?,
+                duration.subsec_nanos(),
+            ),
+            Err(err) => {
+                let duration = err.duration();
+                let seconds =
+                    i64::try_from(duration.as_secs()).map_err(|_| timestamp_out_of_range())?;
+                match duration.subsec_nanos() {
+                    0 => (-seconds, 0),
+                    nanos => (-seconds - 1, NANOS_PER_SECOND - nanos),
+                }
+            }
+        };
+        new_timestamp(desc, seconds, nanos)
+    }
+
+    /// Converts this `google.protobuf.Timestamp` message to a [`SystemTime`].
+    ///
+    /// Returns an error if this is not a `google.protobuf.Timestamp` message, or if its value is not a
+    /// valid timestamp.
+    pub fn to_system_time(&self) -> Result<SystemTime, TimeConversionError> {
+        let (seconds, nanos) = get_timestamp(self)?;
+        let time = if seconds >= 0 {
+            UNIX_EPOCH.checked_add(Duration::new(seconds as u64, nanos))
+        } else {
+            UNIX_EPOCH
+                .checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+                .and_then(|time| time.checked_add(Duration::from_nanos(nanos.into())))
+        };
+        time.ok_or_else(timestamp_out_of_range)
+    }
+
+    /// Creates a `google.protobuf.Duration` message of type `desc` representing `duration`.
+    ///
+    /// Returns an error if `desc` is not the `google.protobuf.Duration` message, or if `duration` is
+    /// longer than the largest valid duration of 315,576,000,000 seconds, roughly 10,000 years.
+    pub fn from_std_duration(
+        desc: MessageDescriptor,
+        duration: Duration,
+    ) -> Result<DynamicMessage, TimeConversionError> {
+        if duration.as_secs() > MAX_DURATION_SECONDS {
+            return Err(TimeConversionErrorKind::DurationOutOfRange.into());
+        }
+        new_duration(
+            desc,
+            duration.as_secs() as i64,
+            duration.subsec_nanos() as i32,
+        )
+    }
+
+    /// Converts this `google.protobuf.Duration` message to a [`Duration`][std::time::Duration].
+    ///
+    /// Returns an error if this is not a `google.protobuf.Duration` message, if its value is not a valid
+    /// duration, or if it is negative.
+    pub fn to_std_duration(&self) -> Result<Duration, TimeConversionError> {
+        let (seconds, nanos) = get_duration(self)?;
+        if seconds < 0 || nanos < 0 {
+            return Err(TimeConversionErrorKind::NegativeDuration.into());
+        }
+        Ok(Duration::new(seconds as u64, nanos as u32))
+    }
+
+    /// Creates a `google.protobuf.Timestamp` message of type `desc` representing `date_time`.
+    ///
+    /// Returns an error if `desc` is not the `google.protobuf.Timestamp` message, or if `date_time` is
+    /// outside the range of valid timestamps. Leap seconds are represented as the last nanosecond of the
+    /// preceding second.
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn from_chrono_date_time(
+        desc: MessageDescriptor,
+        date_time: chrono::DateTime<chrono::Utc>,
+    ) -> Result<DynamicMessage, TimeConversionError> {
+        new_timestamp(
+            desc,
+            date_time.timestamp(),
+            date_time.timestamp_subsec_nanos().min(MAX_NANOS),
+        )
+    }
+
+    /// Converts this `google.protobuf.Timestamp` message to a [`DateTime<Utc>`][chrono::DateTime].
+    ///
+    /// Returns an error if this is not a `google.protobuf.Timestamp` message, or if its value is not a
+    /// valid timestamp.
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn to_chrono_date_time(
+        &self,
+    ) -> Result<chrono::DateTime<chrono::Utc>, TimeConversionError> {
+        let (seconds, nanos) = get_timestamp(self)?;
+        chrono::DateTime::from_timestamp(seconds, nanos).ok_or_else(timestamp_out_of_range)
+    }
+
+    /// Creates a `google.protobuf.Timestamp` message of type `desc` representing `date_time`.
+    ///
+    /// Returns an error if `desc` is not the `google.protobuf.Timestamp` message, or if `date_time` is
+    /// outside the range of valid timestamps.
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    pub fn from_offset_date_time(
+        desc: MessageDescriptor,
+        date_time: time::OffsetDateTime,
+    ) -> Result<DynamicMessage, TimeConversionError> {
+        new_timestamp(desc, date_time.unix_timestamp(), date_time.nanosecond())
+    }
+
+    /// Converts this `google.protobuf.Timestamp` message to an [`OffsetDateTime`][time::OffsetDateTime]
+    /// in UTC.
+    ///
+    /// Returns an error if this is not a `google.protobuf.Timestamp` message, or if its value is not a
+    /// valid timestamp.
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    pub fn to_offset_date_time(&self) -> Result<time::OffsetDateTime, TimeConversionError> {
+        let (seconds, nanos) = get_timestamp(self)?;
+        time::OffsetDateTime::from_unix_timestamp_nanos(
+            i128::from(seconds) * i128::from(NANOS_PER_SECOND) + i128::from(nanos),
+        )
+        .map_err(|_| timestamp_out_of_range())
+    }
+}
+
+impl TryFrom<&Value> for SystemTime {
+    type Error = TimeConversionError;
+
+    /// Converts a [`Value::Message`] containing a `google.protobuf.Timestamp` to a [`SystemTime`].
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        as_message(value)?.to_system_time()
+    }
+}
+
+impl TryFrom<&Value> for Duration {
+    type Error = TimeConversionError;
+
+    /// Converts a [`Value::Message`] containing a non-negative `google.protobuf.Duration` to a
+    /// [`Duration`].
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        as_message(value)?.to_std_duration()
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl TryFrom<&Value> for chrono::DateTime<chrono::Utc> {
+    type Error = TimeConversionError;
+
+    /// Converts a [`Value::Message`] containing a `google.protobuf.Timestamp` to a
+    /// [`DateTime<Utc>`][chrono::DateTime].
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        as_message(value)?.to_chrono_date_time()
+    }
+}
+
+#[cfg(feature = "time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+impl TryFrom<&Value> for time::OffsetDateTime {
+    type Error = TimeConversionError;
+
+    /// Converts a [`Value::Message`] containing a `google.protobuf.Timestamp` to an
+    /// [`OffsetDateTime`][time::OffsetDateTime].
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        as_message(value)?.to_offset_date_time()
+    }
+}
+
+impl fmt::Display for TimeConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TimeConversionErrorKind::MessageType { expected, actual } => write!(
+                f,
+                "expected a message of type '{}', but got '{}'",
+                expected, actual
+            ),
+            TimeConversionErrorKind::NotAMessage { actual } => {
+                write!(f, "expected a message, but got {}", actual)
+            }
+            TimeConversionErrorKind::TimestampOutOfRange => write!(f, "timestamp out of range"),
+            TimeConversionErrorKind::DurationOutOfRange => write!(f, "duration out of range"),
+            TimeConversionErrorKind::InconsistentSigns => {
+                write!(f, "inconsistent signs for duration")
+            }
+            TimeConversionErrorKind::NegativeDuration => write!(f, "duration is negative"),
+        }
+    }
+}
+
+impl Error for TimeConversionError {}
+
+impl From<TimeConversionErrorKind> for TimeConversionError {
+    fn from(kind: TimeConversionErrorKind) -> Self {
+        TimeConversionError { kind }
+    }
+}
+
+fn timestamp_out_of_range() -> TimeConversionError {
+    TimeConversionErrorKind::TimestampOutOfRange.into()
+}
+
+fn as_message(value: &Value) -> Result<&DynamicMessage, TimeConversionError> {
+    match value {
+        Value::Message(message) => Ok(message),
+        value => Err(TimeConversionErrorKind::NotAMessage {
+            actual: variant_name(value),
+        }
+        .into()),
+    }
+}
+
+fn check_message_type(
+    desc: &MessageDescriptor,
+    expected: &'static str,
+) -> Result<(), TimeConversionError> {
+    if desc.full_name() == expected {
+        Ok(())
+    } else {
+        Err(TimeConversionErrorKind::MessageType {
+            expected,
+            actual: desc.full_name().to_owned(),
+        }
+        .into())
+    }
+}
+
+fn get_seconds_and_nanos(message: &DynamicMessage) -> (i64, i32) {
+    let seconds = message
+        .get_field_by_name("seconds")
+        .and_then(|value| value.as_i64())
+        .unwrap_or_default();
+    let nanos = message
+        .get_field_by_name("nanos")
+        .and_then(|value| value.as_i32())
+        .unwrap_or_default();
+    (seconds, nanos)
+}
+
+fn new_timestamp(
+    desc: MessageDescriptor,
+    seconds: i64,
+    nanos: u32,
+) -> Result<DynamicMessage, TimeConversionError> {
+    check_message_type(&desc, TIMESTAMP_NAME)?;
+    check_timestamp(seconds, nanos as i32)?;
+
+    let mut message = DynamicMessage::new(desc);
+    message.set_field_by_name("seconds", Value::I64(seconds));
+    message.set_field_by_name("nanos", Value::I32(nanos as i32));
+    Ok(message)
+}
+
+fn get_timestamp(message: &DynamicMessage) -> Result<(i64, u32), TimeConversionError> {
+    check_message_type(&message.desc, TIMESTAMP_NAME)?;
+    let (seconds, nanos) = get_seconds_and_nanos(message);
+    check_timestamp(seconds, nanos)?;
+    Ok((seconds, nanos as u32))
+}
+
+fn check_timestamp(seconds: i64, nanos: i32) -> Result<(), TimeConversionError> {
+    if !(MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS).contains(&seconds)
+        || !(0..=MAX_NANOS as i32).contains(&nanos)
+    {
+        return Err(timestamp_out_of_range());
+    }
+    Ok(())
+}
+
+fn new_duration(
+    desc: MessageDescriptor,
+    seconds: i64,
+    nanos: i32,
+) -> Result<DynamicMessage, TimeConversionError> {
+    check_message_type(&desc, DURATION_NAME)?;
+    check_duration(seconds, nanos)?;
+
+    let mut message = DynamicMessage::new(desc);
+    message.set_field_by_name("seconds", Value::I64(seconds));
+    message.set_field_by_name("nanos", Value::I32(nanos));
+    Ok(message)
+}
+
+fn get_duration(message: &DynamicMessage) -> Result<(i64, i32), TimeConversionError> {
+    check_message_type(&message.desc, DURATION_NAME)?;
+    let (seconds, nanos) = get_seconds_and_nanos(message);
+    check_duration(seconds, nanos)?;
+    Ok((seconds, nanos))
+}
+
+fn check_duration(seconds: i64, nanos: i32) -> Result<(), TimeConversionError> {
+    if seconds.unsigned_abs() > MAX_DURATION_SECONDS || nanos.unsigned_abs() > MAX_NANOS {
+        return Err(TimeConversionErrorKind::DurationOutOfRange.into());
+    }
+    if (seconds < 0 && nanos > 0) || (seconds > 0 && nanos < 0) {
+        return Err(TimeConversionErrorKind::InconsistentSigns.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use crate::{DynamicMessage, Kind, MapKey, Value};
+
+use super::fields::ValueAndDescriptor;
+
+impl fmt::Debug for DynamicMessage {
+    /// Formats the internal representation of this message.
+    ///
+    /// The alternate flag (`{:#?}`) instead formats the message using names from its descriptor, with
+    /// fields named by their field name, enum values by their value name and map entries by their key,
+    /// for example `package.MyMessage { foo: 150, kind: FOO }`. Extensions are named by their full name
+    /// in brackets, and unknown fields by their number.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return fmt_named(self, f);
+        }
+
+        let mut s = f.debug_struct("DynamicMessage");
+        s.field("desc", &self.desc).field("fields", &self.fields);
+        #[cfg(feature = "serde")]
+        s.field("unknown_json_fields", &self.unknown_json_fields);
+        s.finish()
+    }
+}
+
+fn fmt_named(message: &DynamicMessage, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut s = f.debug_struct(message.desc.full_name());
+    for field in message.fields.iter(&message.desc) {
+        match field {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                s.field(
+                    field_desc.name(),
+                    &NamedValue {
+                        value,
+                        kind: &field_desc.kind(),
+                    },
+                );
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                s.field(
+                    &format!("[{}]", extension_desc.full_name()),
+                    &NamedValue {
+                        value,
+                        kind: &extension_desc.kind(),
+                    },
+                );
+            }
+            ValueAndDescriptor::Unknown(number, unknowns) => {
+                s.field(&number.to_string(), &unknowns);
+            }
+        }
+    }
+    s.finish()
+}
+
+/// Formats a value using the names from the descriptor of the field containing it.
+struct NamedValue<'a> {
+    value: &'a Value,
+    kind: &'a Kind,
+}
+
+/// Formats a map key without the name of its variant.
+struct NamedMapKey<'a>(&'a MapKey);
+
+impl fmt::Debug for NamedValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Value::Bool(value) => fmt::Debug::fmt(value, f),
+            Value::I32(value) => fmt::Debug::fmt(value, f),
+            Value::I64(value) => fmt::Debug::fmt(value, f),
+            Value::U32(value) => fmt::Debug::fmt(value, f),
+            Value::U64(value) => fmt::Debug::fmt(value, f),
+            Value::F32(value) => fmt::Debug::fmt(value, f),
+            Value::F64(value) => fmt::Debug::fmt(value, f),
+            Value::String(value) => fmt::Debug::fmt(value, f),
+            Value::Bytes(value) => fmt::Debug::fmt(value, f),
+            Value::EnumNumber(number) => {
+                match self.kind.as_enum().and_then(|e| e.get_value(*number)) {
+                    Some(value) => f.write_str(value.name()),
+                    None => fmt::Debug::fmt(number, f),
+                }
+            }
+            Value::Message(message) => fmt::Debug::fmt(message, f),
+            Value::List(values) => f
+                .debug_list()
+                .entries(values.iter().map(|value| NamedValue {
+                    value,
+                    kind: self.kind,
+                }))
+                .finish(),
+            Value::Map(values) => {
+                let value_kind = match self.kind.as_message() {
+                    Some(entry) => entry.map_entry_value_field().kind(),
+                    None => return fmt::Debug::fmt(values, f),
+                };
+
+                let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+                f.debug_map()
+                    .entries(entries.into_iter().map(|(key, value)| {
+                        (
+                            NamedMapKey(key),
+                            NamedValue {
+                                value,
+                                kind: &value_kind,
+                            },
+                        )
+                    }))
+                    .finish()
+            }
+        }
+    }
+}
+
+impl fmt::Debug for NamedMapKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            MapKey::Bool(value) => fmt::Debug::fmt(value, f),
+            MapKey::I32(value) => fmt::Debug::fmt(value, f),
+            MapKey::I64(value) => fmt::Debug::fmt(value, f),
+            MapKey::U32(value) => fmt::Debug::fmt(value, f),
+            MapKey::U64(value) => fmt::Debug::fmt(value, f),
+            MapKey::String(value) => fmt::Debug::fmt(value, f),
+        }
+    }
+}
@@ -0,0 +1,455 @@
+use std::{fmt, str};
+
+use prost::{
+    bytes::{Buf, BufMut, Bytes},
+    encoding::{decode_key, decode_varint, encode_varint, skip_field, DecodeContext, WireType},
+    DecodeError, Message,
+};
+
+use crate::{
+    descriptor::{MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER},
+    DynamicMessage, Kind, MessageDescriptor, Syntax,
+};
+
+use super::{fields::FieldDescriptorLike, unknown::UnknownFieldValue, UninitializedError};
+
+/// Options to control decoding of protobuf messages, used by [`DynamicMessage::decode_with_options`],
+/// [`DynamicMessage::merge_with_options`] and [`DynamicMessage::decode_into_with_options`].
+///
+/// Decoding with [`Message::decode`] uses the default options.
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    utf8_validation: Option<Utf8Validation>,
+    check_initialized: bool,
+}
+
+/// Options to control encoding of protobuf messages, used by
+/// [`DynamicMessage::encode_to_vec_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    check_initialized: bool,
+}
+
+/// How to handle `string` fields which contain invalid UTF-8 when decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Utf8Validation {
+    /// Fail to decode the message.
+    Strict,
+    /// Replace invalid sequences with the replacement character `U+FFFD`.
+    Replace,
+    /// Keep the encoded field, including any earlier values for the same field, as an unknown field
+    /// so that it is written back unchanged when the message is encoded.
+    PreserveUnknown,
+}
+
+/// Wraps a message to decode it with a set of options.
+pub(super) struct WithDecodeOptions<'a> {
+    pub message: &'a mut DynamicMessage,
+    pub options: &'a DecodeOptions,
+}
+
+impl DecodeOptions {
+    /// Creates a new instance of [`DecodeOptions`], with the default options.
+    pub const fn new() -> Self {
+        DecodeOptions {
+            utf8_validation: None,
+            check_initialized: false,
+        }
+    }
+
+    /// How to handle `string` fields containing invalid UTF-8.
+    ///
+    /// By default, this depends on the syntax of the file defining the field, following the
+    /// behaviour of other protobuf runtimes: [`Strict`][Utf8Validation::Strict] for `proto3` files,
+    /// and [`PreserveUnknown`][Utf8Validation::PreserveUnknown] for `proto2` files, which do not
+    /// require strings to be valid UTF-8.
+    pub const fn utf8_validation(mut self, utf8_validation: Utf8Validation) -> Self {
+        self.utf8_validation = Some(utf8_validation);
+        self
+    }
+
+    /// Whether to fail to decode a message which is missing required fields, as checked by
+    /// [`DynamicMessage::is_initialized`].
+    ///
+    /// The default value is `false`.
+    pub const fn check_initialized(mut self, yes: bool) -> Self {
+        self.check_initialized = yes;
+        self
+    }
+
+    fn preserve_invalid_utf8(&self, field_desc: &impl FieldDescriptorLike) -> bool {
+        match self.utf8_validation {
+            Some(utf8_validation) => utf8_validation == Utf8Validation::PreserveUnknown,
+            None => field_desc.syntax() == Syntax::Proto2,
+        }
+    }
+
+    /// Whether invalid UTF-8 in the given string field causes decoding to fail.
+    pub(super) fn reject_invalid_utf8(&self, field_desc: &impl FieldDescriptorLike) -> bool {
+        match self.utf8_validation {
+            Some(utf8_validation) => utf8_validation == Utf8Validation::Strict,
+            None => field_desc.syntax() != Syntax::Proto2,
+        }
+    }
+
+    pub(super) fn replace_invalid_utf8(&self) -> bool {
+        self.utf8_validation == Some(Utf8Validation::Replace)
+    }
+}
+
+impl EncodeOptions {
+    /// Creates a new instance of [`EncodeOptions`], with the default options.
+    pub const fn new() -> Self {
+        EncodeOptions {
+            check_initialized: false,
+        }
+    }
+
+    /// Whether to fail to encode a message which is missing required fields, as checked by
+    /// [`DynamicMessage::is_initialized`].
+    ///
+    /// The default value is `false`.
+    pub const fn check_initialized(mut self, yes: bool) -> Self {
+        self.check_initialized = yes;
+        self
+    }
+}
+
+impl DynamicMessage {
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from the buffer,
+    /// using the given options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DecodeOptions, DynamicMessage, FieldBuilder, FileDescriptorBuilder, MessageBuilder, Utf8Validation, Value};
+    /// # let pool = FileDescriptorBuilder::new("person.proto")
+    /// #     .add_message(MessageBuilder::new("Person").add_field(FieldBuilder::new("name", 1, "string")))
+    /// #     .build()
+    /// #     .unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("Person").unwrap();
+    /// let bytes = b"\x0a\x03a\xffb";
+    /// assert!(DynamicMessage::decode_with_options(
+    ///     message_descriptor.clone(),
+    ///     bytes.as_ref(),
+    ///     &DecodeOptions::new().utf8_validation(Utf8Validation::Strict),
+    /// )
+    /// .is_err());
+    ///
+    /// let message = DynamicMessage::decode_with_options(
+    ///     message_descriptor,
+    ///     bytes.as_ref(),
+    ///     &DecodeOptions::new().utf8_validation(Utf8Validation::Replace),
+    /// )
+    /// .unwrap();
+    /// assert_eq!(message.get_field_by_name("name").unwrap().as_str(), Some("a\u{fffd}b"));
+    /// ```
+    pub fn decode_with_options<B>(
+        desc: MessageDescriptor,
+        buf: B,
+        options: &DecodeOptions,
+    ) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+    {
+        let mut message = DynamicMessage::new(desc);
+        message.merge_with_options(buf, options)?;
+        Ok(message)
+    }
+
+    /// Decodes an instance of the message type specified by this message's descriptor from the buffer,
+    /// using the given options, and merges it into this message.
+    ///
+    /// If [`check_initialized`][DecodeOptions::check_initialized] is set and the merged message is
+    /// missing required fields, an error is returned but the message is still modified.
+    pub fn merge_with_options<B>(
+        &mut self,
+        buf: B,
+        options: &DecodeOptions,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        WithDecodeOptions {
+            message: self,
+            options,
+        }
+        .merge(buf)?;
+
+        if options.check_initialized {
+            self.check_initialized()
+                .map_err(|err| DecodeError::new(err.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Encodes this message into a new `Vec<u8>`, using the given options.
+    pub fn encode_to_vec_with_options(
+        &self,
+        options: &EncodeOptions,
+    ) -> Result<Vec<u8>, UninitializedError> {
+        if options.check_initialized {
+            self.check_initialized()?;
+        }
+        Ok(self.encode_to_vec())
+    }
+
+    /// Clears this message and decodes a new instance of its message type from the buffer into it.
+    ///
+    /// This is equivalent to calling [`clear`][Message::clear] followed by [`merge`][Message::merge], but
+    /// reuses the memory allocated for strings, lists, maps and nested messages where possible. When
+    /// decoding many messages of the same type, reusing one [`DynamicMessage`] with this method avoids
+    /// allocating new values for each message.
+    ///
+    /// If an error is returned, the message may contain some of the decoded fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, FieldBuilder, FileDescriptorBuilder, MessageBuilder};
+    /// # let pool = FileDescriptorBuilder::new("person.proto")
+    /// #     .add_message(MessageBuilder::new("Person").add_field(FieldBuilder::new("name", 1, "string")))
+    /// #     .build()
+    /// #     .unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("Person").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor);
+    /// for bytes in [b"\x0a\x05alice".as_ref(), b"\x0a\x03bob".as_ref(), b"".as_ref()] {
+    ///     message.decode_into(bytes).unwrap();
+    ///     assert_eq!(message.encode_to_vec(), bytes);
+    /// }
+    /// assert!(!message.has_field_by_name("name"));
+    /// ```
+    pub fn decode_into<B>(&mut self, buf: B) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        self.decode_into_with_options(buf, &DecodeOptions::new())
+    }
+
+    /// Clears this message and decodes a new instance of its message type from the buffer into it, using
+    /// the given options.
+    ///
+    /// See [`decode_into`][Self::decode_into] and [`merge_with_options`][Self::merge_with_options].
+    pub fn decode_into_with_options<B>(
+        &mut self,
+        buf: B,
+        options: &DecodeOptions,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        self.fields.clear_for_reuse();
+        #[cfg(feature = "serde")]
+        {
+            self.unknown_json_fields = None;
+        }
+        self.merge_with_options(buf, options)
+    }
+
+    pub(super) fn merge_field_with_options<B>(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+        options: &DecodeOptions,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        if let Some(field_desc) = self.desc.get_field(number) {
+            if is_preserved_string(&field_desc, wire_type, options)
+                && !self.is_valid_string_in_place(&field_desc, buf.chunk(), ctx.clone())
+            {
+                if let Some(mut record) =
+                    self.take_preserved_string(&field_desc, buf, ctx.clone())?
+                {
+                    self.get_field_mut(&field_desc).merge_field(
+                        &field_desc,
+                        wire_type,
+                        &mut record,
+                        ctx,
+                        options,
+                    )?;
+                }
+                Ok(())
+            } else {
+                self.get_field_mut(&field_desc).merge_field(
+                    &field_desc,
+                    wire_type,
+                    buf,
+                    ctx,
+                    options,
+                )
+            }
+        } else if let Some(extension_desc) = self.desc.get_extension(number) {
+            if is_preserved_string(&extension_desc, wire_type, options)
+                && !self.is_valid_string_in_place(&extension_desc, buf.chunk(), ctx.clone())
+            {
+                if let Some(mut record) =
+                    self.take_preserved_string(&extension_desc, buf, ctx.clone())?
+                {
+                    self.get_extension_mut(&extension_desc).merge_field(
+                        &extension_desc,
+                        wire_type,
+                        &mut record,
+                        ctx,
+                        options,
+                    )?;
+                }
+                Ok(())
+            } else {
+                self.get_extension_mut(&extension_desc).merge_field(
+                    &extension_desc,
+                    wire_type,
+                    buf,
+                    ctx,
+                    options,
+                )
+            }
+        } else {
+            let field = UnknownFieldValue::decode(number, wire_type, buf, ctx)?;
+            self.fields.add_unknown(number, field);
+            Ok(())
+        }
+    }
+
+    /// Checks whether a length-delimited string field, or map field with string keys or values, is entirely
+    /// contained in `chunk` and is valid UTF-8, so it can be merged as usual without being preserved. This
+    /// avoids copying valid values, which are by far the most common.
+    fn is_valid_string_in_place(
+        &self,
+        field_desc: &impl FieldDescriptorLike,
+        mut chunk: &[u8],
+        ctx: DecodeContext,
+    ) -> bool {
+        if self.fields.is_unknown(field_desc.number()) {
+            return false;
+        }
+        match decode_varint(&mut chunk) {
+            Ok(len) if len <= chunk.len() as u64 => {
+                is_valid_utf8(field_desc, &chunk[..len as usize], ctx).unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Reads a length-delimited string field, or map field with string keys or values. If it contains
+    /// invalid UTF-8, or earlier values of the field were preserved, it is added to the unknown fields
+    /// and `None` is returned. Otherwise, the length-delimited record is returned to be merged as usual.
+    fn take_preserved_string<B>(
+        &mut self,
+        field_desc: &impl FieldDescriptorLike,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<Option<impl Buf>, DecodeError>
+    where
+        B: Buf,
+    {
+        let mut payload = Bytes::new();
+        prost::encoding::bytes::merge(WireType::LengthDelimited, &mut payload, buf, ctx.clone())?;
+
+        let number = field_desc.number();
+        if !self.fields.is_unknown(number) && is_valid_utf8(field_desc, &payload, ctx)? {
+            let mut len = Vec::with_capacity(10);
+            encode_varint(payload.len() as u64, &mut len);
+            Ok(Some(Bytes::from(len).chain(payload)))
+        } else {
+            self.fields.make_unknown(field_desc);
+            self.fields
+                .add_unknown(number, UnknownFieldValue::LengthDelimited(payload));
+            Ok(None)
+        }
+    }
+}
+
+fn is_preserved_string(
+    field_desc: &impl FieldDescriptorLike,
+    wire_type: WireType,
+    options: &DecodeOptions,
+) -> bool {
+    if wire_type != WireType::LengthDelimited {
+        return false;
+    }
+
+    let has_string = match field_desc.kind() {
+        Kind::String => true,
+        Kind::Message(map_entry) if field_desc.is_map() => {
+            map_entry.map_entry_key_field().kind() == Kind::String
+                || map_entry.map_entry_value_field().kind() == Kind::String
+        }
+        _ => false,
+    };
+    has_string && options.preserve_invalid_utf8(field_desc)
+}
+
+fn is_valid_utf8(
+    field_desc: &impl FieldDescriptorLike,
+    mut payload: &[u8],
+    ctx: DecodeContext,
+) -> Result<bool, DecodeError> {
+    let map_entry = match field_desc.kind() {
+        Kind::Message(map_entry) if field_desc.is_map() => map_entry,
+        _ => return Ok(str::from_utf8(payload).is_ok()),
+    };
+
+    while payload.has_remaining() {
+        let (number, wire_type) = decode_key(&mut payload)?;
+        let is_string = match number {
+            MAP_ENTRY_KEY_NUMBER => map_entry.map_entry_key_field().kind() == Kind::String,
+            MAP_ENTRY_VALUE_NUMBER => map_entry.map_entry_value_field().kind() == Kind::String,
+            _ => false,
+        };
+
+        if is_string && wire_type == WireType::LengthDelimited {
+            let mut value = Bytes::new();
+            prost::encoding::bytes::merge(wire_type, &mut value, &mut payload, ctx.clone())?;
+            if str::from_utf8(&value).is_err() {
+                return Ok(false);
+            }
+        } else {
+            skip_field(wire_type, number, &mut payload, ctx.clone())?;
+        }
+    }
+    Ok(true)
+}
+
+impl Message for WithDecodeOptions<'_> {
+    fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+        Self: Sized,
+    {
+        self.message.encode_raw(buf)
+    }
+
+    fn merge_field<B>(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        self.message
+            .merge_field_with_options(number, wire_type, buf, ctx, self.options)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.message.encoded_len()
+    }
+
+    fn clear(&mut self) {
+        self.message.clear()
+    }
+}
+
+impl fmt::Debug for WithDecodeOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.message, f)
+    }
+}
@@ -0,0 +1,111 @@
+use std::{convert::TryFrom, io};
+
+use prost::{
+    encoding::{encode_varint, WireType},
+    Message,
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{DynamicMessage, MessageDescriptor};
+
+use super::delimited::{check_message_len, read_varint_async};
+
+impl DynamicMessage {
+    /// Decodes a message of type `desc` by reading `reader` until the end of the stream.
+    ///
+    /// Unlike reading the stream into a buffer and calling [`decode`][DynamicMessage::decode], the message is
+    /// decoded a field at a time as it is read, so only a single top-level field is buffered at once. This
+    /// reduces peak memory usage for large messages with many fields, such as streamed blobs consisting of a
+    /// large repeated field. Since the stream is read in small pieces, `reader` should be buffered.
+    ///
+    /// Decoding errors are returned as [`io::Error`]s of kind [`InvalidData`][io::ErrorKind::InvalidData],
+    /// and a stream which ends in the middle of a field returns an error of kind
+    /// [`UnexpectedEof`][io::ErrorKind::UnexpectedEof].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// # futures_executor::block_on(async {
+    /// let bytes = [0x08, 0x96, 0x01];
+    /// let message = DynamicMessage::decode_async(message_descriptor, bytes.as_ref()).await.unwrap();
+    /// assert_eq!(message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    /// # });
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub async fn decode_async<R>(desc: MessageDescriptor, mut reader: R) -> io::Result<Self>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut message = DynamicMessage::new(desc);
+        let mut buf = Vec::new();
+        while read_field_async(&mut reader, &mut buf).await? {
+            // Merging each field in turn is equivalent to decoding their concatenation.
+            message
+                .merge(buf.as_slice())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            buf.clear();
+        }
+        Ok(message)
+    }
+}
+
+/// Reads the encoding of a single field, including its key, and appends it to `buf`. Returns `false` if
+/// the stream ends before the start of the field.
+async fn read_field_async<R>(reader: &mut R, buf: &mut Vec<u8>) -> io::Result<bool>
+where
+    R: AsyncRead + Unpin,
+{
+    // Groups are read in their entirety, so track how many are open.
+    let mut depth = 0u32;
+    loop {
+        let key = match read_varint_async(reader).await? {
+            Some(key) => key,
+            None if depth == 0 && buf.is_empty() => return Ok(false),
+            None => return Err(io::ErrorKind::UnexpectedEof.into()),
+        };
+        encode_varint(key, buf);
+
+        let wire_type = WireType::try_from(key & 0x07)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        match wire_type {
+            WireType::Varint => {
+                let value = read_varint_async(reader)
+                    .await?
+                    .ok_or(io::ErrorKind::UnexpectedEof)?;
+                encode_varint(value, buf);
+            }
+            WireType::SixtyFourBit => read_exact_async(reader, buf, 8).await?,
+            WireType::ThirtyTwoBit => read_exact_async(reader, buf, 4).await?,
+            WireType::LengthDelimited => {
+                let len = read_varint_async(reader)
+                    .await?
+                    .ok_or(io::ErrorKind::UnexpectedEof)?;
+                encode_varint(len, buf);
+                read_exact_async(reader, buf, len).await?;
+            }
+            WireType::StartGroup => depth += 1,
+            WireType::EndGroup => {
+                depth = depth.checked_sub(1).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "unexpected end group tag")
+                })?;
+            }
+        }
+
+        if depth == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+async fn read_exact_async<R>(reader: &mut R, buf: &mut Vec<u8>, len: u64) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    let start = buf.len();
+    reader.take(len).read_to_end(buf).await?;
+    check_message_len(&buf[start..], len)
+}
@@ -0,0 +1,186 @@
+use std::io::{self, Read, Write};
+
+use prost::Message;
+
+use crate::{DynamicMessage, MessageDescriptor};
+
+impl DynamicMessage {
+    /// Encodes this message with a varint length prefix and writes it to `writer`.
+    ///
+    /// This is the framing used by `writeDelimitedTo` in the Java protobuf library and `protodelim` in
+    /// Go, so the output can be read by [`DynamicMessage::read_length_delimited`] or by those libraries.
+    pub fn encode_length_delimited_to_writer<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(&self.encode_length_delimited_to_vec())
+    }
+
+    /// Returns an iterator which reads length-delimited messages of type `desc` from `reader`, until the
+    /// end of the stream.
+    ///
+    /// Decoding errors are returned as [`io::Error`]s of kind [`InvalidData`][io::ErrorKind::InvalidData],
+    /// and a stream which ends in the middle of a message returns an error of kind
+    /// [`UnexpectedEof`][io::ErrorKind::UnexpectedEof]. Since length delimiters are read a byte at a time,
+    /// `reader` should be buffered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor.clone());
+    /// message.set_field_by_name("foo", Value::I32(150));
+    ///
+    /// let mut buf = Vec::new();
+    /// message.encode_length_delimited_to_writer(&mut buf).unwrap();
+    /// message.encode_length_delimited_to_writer(&mut buf).unwrap();
+    ///
+    /// let messages = DynamicMessage::read_length_delimited(message_descriptor, buf.as_slice())
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    /// assert_eq!(messages, vec![message.clone(), message]);
+    /// ```
+    pub fn read_length_delimited<R>(desc: MessageDescriptor, reader: R) -> LengthDelimitedReader<R>
+    where
+        R: Read,
+    {
+        LengthDelimitedReader {
+            desc,
+            reader,
+            buf: Vec::new(),
+            done: false,
+        }
+    }
+}
+
+/// An iterator over length-delimited messages read from a stream.
+///
+/// This type is returned by [`DynamicMessage::read_length_delimited`].
+#[derive(Debug)]
+pub struct LengthDelimitedReader<R> {
+    desc: MessageDescriptor,
+    reader: R,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl<R> LengthDelimitedReader<R> {
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_message(&mut self) -> io::Result<Option<DynamicMessage>>
+    where
+        R: Read,
+    {
+        let len = match read_length_delimiter(&mut self.reader)? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+
+        self.buf.clear();
+        (&mut self.reader).take(len).read_to_end(&mut self.buf)?;
+        check_message_len(&self.buf, len)?;
+
+        DynamicMessage::decode(self.desc.clone(), self.buf.as_slice())
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl<R> Iterator for LengthDelimitedReader<R>
+where
+    R: Read,
+{
+    type Item = io::Result<DynamicMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.read_message().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+/// Reads a varint length delimiter, returning `None` if the stream ends before the first byte.
+pub(crate) fn read_length_delimiter<R>(reader: &mut R) -> io::Result<Option<u64>>
+where
+    R: Read,
+{
+    let mut value = 0;
+    for i in 0..10 {
+        let mut byte = [0];
+        loop {
+            match reader.read(&mut byte) {
+                Ok(0) if i == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        if let Some(value) = push_varint_byte(&mut value, i, byte[0])? {
+            return Ok(Some(value));
+        }
+    }
+    unreachable!()
+}
+
+/// Reads a varint, returning `None` if the stream ends before the first byte.
+#[cfg(feature = "tokio")]
+pub(crate) async fn read_varint_async<R>(reader: &mut R) -> io::Result<Option<u64>>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut value = 0;
+    for i in 0..10 {
+        let mut byte = [0];
+        match reader.read(&mut byte).await? {
+            0 if i == 0 => return Ok(None),
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            _ => (),
+        }
+        if let Some(value) = push_varint_byte(&mut value, i, byte[0])? {
+            return Ok(Some(value));
+        }
+    }
+    unreachable!()
+}
+
+/// Adds the `i`th byte of a varint to `value`, returning the decoded value if it is the last byte.
+pub(crate) fn push_varint_byte(value: &mut u64, i: u32, byte: u8) -> io::Result<Option<u64>> {
+    if i == 9 && byte > 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid length delimiter",
+        ));
+    }
+
+    *value |= u64::from(byte & 0x7f) << (7 * i);
+    if byte < 0x80 {
+        Ok(Some(*value))
+    } else {
+        Ok(None)
+    }
+}
+
+pub(crate) fn check_message_len(buf: &[u8], len: u64) -> io::Result<()> {
+    if buf.len() as u64 == len {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "stream ended in the middle of a message",
+        ))
+    }
+}
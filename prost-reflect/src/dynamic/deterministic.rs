@@ -0,0 +1,104 @@
+use prost::{
+    bytes::BufMut,
+    encoding::{encode_key, encode_varint, WireType},
+    Message,
+};
+
+use crate::{
+    descriptor::{MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER},
+    DynamicMessage, Kind, MapKey, Value,
+};
+
+use super::fields::{FieldDescriptorLike, ValueAndDescriptor};
+
+impl DynamicMessage {
+    /// Encodes this message into a new `Vec<u8>` using a deterministic encoding.
+    ///
+    /// The default encoding writes map entries in the iteration order of the underlying `HashMap`,
+    /// so two equal messages may be encoded differently. This method instead writes map entries
+    /// sorted by key, in this message and in all nested messages. Fields are always written in order
+    /// of field number, and unknown fields are written in order of field number, then in the order
+    /// they were decoded or added. The output is therefore stable for equal messages, which makes it
+    /// suitable for hashing or signing.
+    ///
+    /// The output is a valid encoding of the message, and has the same length as the output of
+    /// [`encode_to_vec`][Message::encode_to_vec]. Note that the deterministic encoding is not
+    /// guaranteed to be stable across versions of this library.
+    pub fn encode_deterministic(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        self.encode_deterministic_raw(&mut buf);
+        buf
+    }
+
+    fn encode_deterministic_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        for field in self.fields.iter(&self.desc) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    encode_field(value, &field_desc, buf)
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    encode_field(value, &extension_desc, buf)
+                }
+                ValueAndDescriptor::Unknown(number, unknowns) => {
+                    for unknown in unknowns {
+                        unknown.encode_field(number, buf);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn encode_field<B>(value: &Value, field_desc: &impl FieldDescriptorLike, buf: &mut B)
+where
+    B: BufMut,
+{
+    let number = field_desc.number();
+    match (value, field_desc.kind()) {
+        (Value::Message(message), Kind::Message(_)) => {
+            encode_message(number, message, field_desc.is_group(), buf)
+        }
+        (Value::List(values), Kind::Message(_)) if field_desc.is_list() => {
+            for value in values {
+                if let Value::Message(message) = value {
+                    encode_message(number, message, field_desc.is_group(), buf);
+                }
+            }
+        }
+        (Value::Map(values), Kind::Message(map_entry)) if field_desc.is_map() => {
+            let key_desc = map_entry.get_field(MAP_ENTRY_KEY_NUMBER).unwrap();
+            let value_desc = map_entry.get_field(MAP_ENTRY_VALUE_NUMBER).unwrap();
+
+            let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            for (key, value) in entries {
+                let len = key.encoded_len(&key_desc) + value.encoded_len(&value_desc);
+
+                encode_key(number, WireType::LengthDelimited, buf);
+                encode_varint(len as u64, buf);
+
+                key.encode_field(&key_desc, buf);
+                encode_field(value, &value_desc, buf);
+            }
+        }
+        (value, _) => value.encode_field(field_desc, buf),
+    }
+}
+
+fn encode_message<B>(number: u32, message: &DynamicMessage, is_group: bool, buf: &mut B)
+where
+    B: BufMut,
+{
+    if is_group {
+        encode_key(number, WireType::StartGroup, buf);
+        message.encode_deterministic_raw(buf);
+        encode_key(number, WireType::EndGroup, buf);
+    } else {
+        encode_key(number, WireType::LengthDelimited, buf);
+        encode_varint(message.encoded_len() as u64, buf);
+        message.encode_deterministic_raw(buf);
+    }
+}
@@ -0,0 +1,635 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use prost_types::FieldMask;
+
+use crate::{DynamicMessage, Kind, MapKey, Value};
+
+use super::{
+    fields::ValueAndDescriptor,
+    snapshot::{join_path, map_key_to_string, unknown_fields_to_string, value_to_string},
+    unknown::UnknownFieldValue,
+};
+
+const UNSET: &str = "<unset>";
+
+/// The differences between two messages, returned by [`DynamicMessage::diff`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageDiff {
+    differences: Vec<Difference>,
+}
+
+/// Options to control how messages are compared by [`DynamicMessage::equals_with`] and
+/// [`DynamicMessage::diff_with`].
+#[derive(Debug, Clone, Default)]
+pub struct CompareOptions {
+    ignored_paths: Vec<String>,
+    set_paths: Vec<String>,
+    repeated_as_set: bool,
+    ignore_unknown_fields: bool,
+    float_tolerance: FloatTolerance,
+    field_float_tolerances: Vec<(String, FloatTolerance)>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct FloatTolerance {
+    fraction_and_margin: Option<(f64, f64)>,
+    max_ulps: Option<u64>,
+}
+
+/// A single difference between two messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    path: String,
+    left: String,
+    right: String,
+}
+
+impl DynamicMessage {
+    /// Compares this message with `other`, returning every difference between them, except for fields
+    /// matching a path in `ignore`.
+    ///
+    /// Paths in `ignore` are sequences of field names separated by `.`, as in a `google.protobuf.FieldMask`. A
+    /// path matches the named field and all fields nested within it. Paths pass through repeated and map
+    /// fields without an index, so `"items.id"` ignores the `id` field of every element of `items`.
+    ///
+    /// Unlike the [`PartialEq`] implementation, fields without presence which are set to their default value
+    /// are treated the same as unset fields. Fields with presence, such as `optional` and message fields, are
+    /// still reported as differing from an unset field when set to their default value. As for [`PartialEq`],
+    /// `NaN` floating point values are considered equal.
+    pub fn diff(&self, other: &DynamicMessage, ignore: &FieldMask) -> MessageDiff {
+        let options = CompareOptions {
+            ignored_paths: ignore.paths.clone(),
+            ..Default::default()
+        };
+        self.diff_with(other, &options)
+    }
+
+    /// Compares this message with `other` using the given options, returning every difference between
+    /// them.
+    ///
+    /// As for [`diff`][Self::diff], fields without presence which are set to their default value are treated
+    /// the same as unset fields, and `NaN` floating point values are considered equal.
+    pub fn diff_with(&self, other: &DynamicMessage, options: &CompareOptions) -> MessageDiff {
+        let mut diff = MessageDiff::default();
+        let mut differ = Differ {
+            options,
+            diff: &mut diff,
+        };
+        differ.message("", "", self, other);
+        diff
+    }
+
+    /// Returns `true` if this message is equal to `other` when compared using the given options.
+    ///
+    /// This supports the features of the C++ `MessageDifferencer` commonly used by tests, such as ignoring
+    /// fields, comparing repeated fields regardless of order and allowing small differences between
+    /// floating point values. Use [`diff_with`][Self::diff_with] to find the differences between messages
+    /// which are not equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{CompareOptions, DescriptorPool, DynamicMessage, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut left = DynamicMessage::new(message_descriptor.clone());
+    /// left.set_field_by_name("foo", Value::I32(1));
+    /// let mut right = DynamicMessage::new(message_descriptor);
+    /// right.set_field_by_name("foo", Value::I32(2));
+    ///
+    /// assert!(!left.equals_with(&right, &CompareOptions::new()));
+    /// assert!(left.equals_with(&right, &CompareOptions::new().ignore("foo")));
+    /// ```
+    pub fn equals_with(&self, other: &DynamicMessage, options: &CompareOptions) -> bool {
+        self.diff_with(other, options).is_empty()
+    }
+}
+
+impl CompareOptions {
+    /// Creates a new instance of [`CompareOptions`], with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignores a field, and all fields nested within it, when comparing messages.
+    ///
+    /// The path is a sequence of field names separated by `.`, relative to the root message, as in a
+    /// `google.protobuf.FieldMask`. Paths pass through repeated and map fields without an index, so
+    /// `"items.id"` ignores the `id` field of every element of `items`. Extension fields are named by
+    /// their full name in brackets, for example `"[my.package.ext]"`.
+    pub fn ignore(mut self, path: impl Into<String>) -> Self {
+        self.ignored_paths.push(path.into());
+        self
+    }
+
+    /// Compares the repeated field at `path` as a set, ignoring the order of its elements.
+    ///
+    /// Paths are interpreted as for [`ignore`][Self::ignore]. Duplicate elements must occur the same
+    /// number of times in both lists.
+    pub fn treat_as_set(mut self, path: impl Into<String>) -> Self {
+        self.set_paths.push(path.into());
+        self
+    }
+
+    /// Whether to compare all repeated fields as sets, ignoring the order of their elements, as for
+    /// [`treat_as_set`][Self::treat_as_set].
+    ///
+    /// The default value is `false`.
+    pub fn repeated_as_set(mut self, yes: bool) -> Self {
+        self.repeated_as_set = yes;
+        self
+    }
+
+    /// Whether to ignore unknown fields when comparing messages.
+    ///
+    /// The default value is `false`.
+    pub fn ignore_unknown_fields(mut self, yes: bool) -> Self {
+        self.ignore_unknown_fields = yes;
+        self
+    }
+
+    /// Allows floating point values to differ by a small amount.
+    ///
+    /// Two values are considered equal if the absolute difference between them is at most `margin`, or at
+    /// most `fraction` times the larger of their absolute values. This matches
+    /// `MessageDifferencer::SetFractionAndMargin` in the C++ implementation.
+    ///
+    /// By default, floating point values must be exactly equal. If [`float_ulps`][Self::float_ulps] is also
+    /// set, values are equal if they are within either tolerance.
+    pub fn float_tolerance(mut self, fraction: f64, margin: f64) -> Self {
+        self.float_tolerance.fraction_and_margin = Some((fraction, margin));
+        self
+    }
+
+    /// Allows floating point values to differ by at most `max_ulps` units in the last place.
+    ///
+    /// This is the number of representable values between them, so it scales with the magnitude of the
+    /// values. `float` fields are compared using the precision of `float` rather than `double`. Values of
+    /// different signs are only equal if they are both within `max_ulps` of zero.
+    ///
+    /// By default, floating point values must be exactly equal.
+    pub fn float_ulps(mut self, max_ulps: u64) -> Self {
+        self.float_tolerance.max_ulps = Some(max_ulps);
+        self
+    }
+
+    /// Sets the tolerance used to compare floating point values in the field at `path`, and all fields
+    /// nested within it, as for [`float_tolerance`][Self::float_tolerance].
+    ///
+    /// Paths are interpreted as for [`ignore`][Self::ignore]. A tolerance set for a field replaces the
+    /// global tolerance, including any set by [`float_ulps`][Self::float_ulps], and if several paths match a
+    /// field, the longest is used.
+    pub fn field_float_tolerance(
+        mut self,
+        path: impl Into<String>,
+        fraction: f64,
+        margin: f64,
+    ) -> Self {
+        self.field_float_tolerance_mut(path.into())
+            .fraction_and_margin = Some((fraction, margin));
+        self
+    }
+
+    /// Sets the maximum number of units in the last place by which floating point values in the field at
+    /// `path`, and all fields nested within it, may differ, as for [`float_ulps`][Self::float_ulps].
+    ///
+    /// Paths are interpreted as for [`field_float_tolerance`][Self::field_float_tolerance].
+    pub fn field_float_ulps(mut self, path: impl Into<String>, max_ulps: u64) -> Self {
+        self.field_float_tolerance_mut(path.into()).max_ulps = Some(max_ulps);
+        self
+    }
+
+    fn field_float_tolerance_mut(&mut self, path: String) -> &mut FloatTolerance {
+        let index = match self
+            .field_float_tolerances
+            .iter()
+            .position(|(existing, _)| *existing == path)
+        {
+            Some(index) => index,
+            None => {
+                self.field_float_tolerances
+                    .push((path, FloatTolerance::default()));
+                self.field_float_tolerances.len() - 1
+            }
+        };
+        &mut self.field_float_tolerances[index].1
+    }
+
+    fn is_ignored(&self, mask_path: &str) -> bool {
+        self.ignored_paths
+            .iter()
+            .any(|ignored| path_matches(mask_path, ignored))
+    }
+
+    fn is_set(&self, mask_path: &str) -> bool {
+        self.repeated_as_set || self.set_paths.iter().any(|path| path == mask_path)
+    }
+
+    /// Compares two floating point values, where `ulps` is the number of units in the last place between
+    /// them at their original precision.
+    fn floats_equal(&self, mask_path: &str, left: f64, right: f64, ulps: u64) -> bool {
+        if left == right || (left.is_nan() && right.is_nan()) {
+            return true;
+        }
+        // Infinite values are only equal to themselves.
+        if !left.is_finite() || !right.is_finite() {
+            return false;
+        }
+
+        let tolerance = self
+            .field_float_tolerances
+            .iter()
+            .filter(|(path, _)| path_matches(mask_path, path))
+            .max_by_key(|(path, _)| path.len())
+            .map_or(self.float_tolerance, |(_, tolerance)| *tolerance);
+        if let Some(max_ulps) = tolerance.max_ulps {
+            if ulps <= max_ulps {
+                return true;
+            }
+        }
+        if let Some((fraction, margin)) = tolerance.fraction_and_margin {
+            let difference = (left - right).abs();
+            if difference <= margin || difference <= fraction * left.abs().max(right.abs()) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl MessageDiff {
+    /// Returns `true` if there are no differences between the messages.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Gets an iterator over the differences between the messages, ordered by field number.
+    pub fn differences(&self) -> impl ExactSizeIterator<Item = &Difference> + '_ {
+        self.differences.iter()
+    }
+}
+
+impl fmt::Display for MessageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, difference) in self.differences.iter().enumerate() {
+            if index != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+impl Difference {
+    /// The path to the differing field, for example `items[0].id`, or an empty string if the messages
+    /// have different types.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The value in the left-hand message, formatted as in [`DynamicMessage::to_snapshot`].
+    pub fn left(&self) -> &str {
+        &self.left
+    }
+
+    /// The value in the right-hand message, formatted as in [`DynamicMessage::to_snapshot`].
+    pub fn right(&self) -> &str {
+        &self.right
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "<root>"
+        } else {
+            &self.path
+        };
+        if self.left.contains('\n') || self.right.contains('\n') {
+            write!(
+                f,
+                "{}:\n  left: {}\n  right: {}",
+                path, self.left, self.right
+            )
+        } else {
+            write!(f, "{}: {} != {}", path, self.left, self.right)
+        }
+    }
+}
+
+struct Differ<'a> {
+    options: &'a CompareOptions,
+    diff: &'a mut MessageDiff,
+}
+
+enum Entry<'a> {
+    Field(&'a Value, Kind, String, bool),
+    Unknown(&'a [UnknownFieldValue]),
+}
+
+impl<'a> Differ<'a> {
+    fn push(&mut self, path: &str, left: String, right: String) {
+        self.diff.differences.push(Difference {
+            path: path.to_owned(),
+            left,
+            right,
+        });
+    }
+
+    fn message(
+        &mut self,
+        path: &str,
+        mask_path: &str,
+        left: &DynamicMessage,
+        right: &DynamicMessage,
+    ) {
+        if left.desc != right.desc {
+            self.push(
+                path,
+                left.desc.full_name().to_owned(),
+                right.desc.full_name().to_owned(),
+            );
+            return;
+        }
+
+        let left_entries = entries(left);
+        let right_entries = entries(right);
+        let numbers: BTreeSet<u32> = left_entries
+            .keys()
+            .chain(right_entries.keys())
+            .copied()
+            .collect();
+
+        for number in numbers {
+            match (left_entries.get(&number), right_entries.get(&number)) {
+                (Some(Entry::Field(left_value, kind, name, presence)), right_entry) => {
+                    let field_path = join_path(path, name);
+                    let field_mask_path = join_path(mask_path, name);
+                    if self.options.is_ignored(&field_mask_path) {
+                        continue;
+                    }
+                    match right_entry {
+                        Some(Entry::Field(right_value, ..)) => {
+                            self.value(&field_path, &field_mask_path, kind, left_value, right_value)
+                        }
+                        _ if *presence => self.push(
+                            &field_path,
+                            value_to_string(left_value, kind),
+                            UNSET.to_owned(),
+                        ),
+                        _ => {
+                            let default = default_like(left_value, kind);
+                            self.value(&field_path, &field_mask_path, kind, left_value, &default)
+                        }
+                    }
+                }
+                (left_entry, Some(Entry::Field(right_value, kind, name, presence))) => {
+                    let field_path = join_path(path, name);
+                    let field_mask_path = join_path(mask_path, name);
+                    if self.options.is_ignored(&field_mask_path) {
+                        continue;
+                    }
+                    match left_entry {
+                        Some(Entry::Field(..)) => unreachable!(),
+                        _ if *presence => self.push(
+                            &field_path,
+                            UNSET.to_owned(),
+                            value_to_string(right_value, kind),
+                        ),
+                        _ => {
+                            let default = default_like(right_value, kind);
+                            self.value(&field_path, &field_mask_path, kind, &default, right_value)
+                        }
+                    }
+                }
+                _ if self.options.ignore_unknown_fields => (),
+                (left_entry, right_entry) => {
+                    let left_unknown = match left_entry {
+                        Some(Entry::Unknown(unknowns)) => *unknowns,
+                        _ => &[],
+                    };
+                    let right_unknown = match right_entry {
+                        Some(Entry::Unknown(unknowns)) => *unknowns,
+                        _ => &[],
+                    };
+                    if left_unknown != right_unknown {
+                        let field_path = join_path(path, &number.to_string());
+                        self.push(
+                            &field_path,
+                            unknown_string(left_unknown),
+                            unknown_string(right_unknown),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, path: &str, mask_path: &str, kind: &Kind, left: &Value, right: &Value) {
+        match (left, right) {
+            (Value::Message(left), Value::Message(right)) => {
+                self.message(path, mask_path, left, right)
+            }
+            (Value::List(left), Value::List(right)) if self.options.is_set(mask_path) => {
+                self.set(path, mask_path, kind, left, right)
+            }
+            (Value::List(left), Value::List(right)) => {
+                for index in 0..left.len().max(right.len()) {
+                    let element_path = format!("{}[{}]", path, index);
+                    match (left.get(index), right.get(index)) {
+                        (Some(left), Some(right)) => {
+                            self.value(&element_path, mask_path, kind, left, right)
+                        }
+                        (Some(left), None) => {
+                            self.push(&element_path, value_to_string(left, kind), UNSET.to_owned())
+                        }
+                        (None, Some(right)) => self.push(
+                            &element_path,
+                            UNSET.to_owned(),
+                            value_to_string(right, kind),
+                        ),
+                        (None, None) => unreachable!(),
+                    }
+                }
+            }
+            (Value::Map(left), Value::Map(right)) => {
+                let value_kind = kind
+                    .as_message()
+                    .expect("map field must be a message")
+                    .map_entry_value_field()
+                    .kind();
+                let keys: BTreeSet<&MapKey> = left.keys().chain(right.keys()).collect();
+                for key in keys {
+                    let entry_path = format!("{}[{}]", path, map_key_to_string(key));
+                    match (left.get(key), right.get(key)) {
+                        (Some(left), Some(right)) => {
+                            self.value(&entry_path, mask_path, &value_kind, left, right)
+                        }
+                        (Some(left), None) => self.push(
+                            &entry_path,
+                            value_to_string(left, &value_kind),
+                            UNSET.to_owned(),
+                        ),
+                        (None, Some(right)) => self.push(
+                            &entry_path,
+                            UNSET.to_owned(),
+                            value_to_string(right, &value_kind),
+                        ),
+                        (None, None) => unreachable!(),
+                    }
+                }
+            }
+            (Value::F32(l), Value::F32(r))
+                if self
+                    .options
+                    .floats_equal(mask_path, *l as f64, *r as f64, ulps_f32(*l, *r)) => {}
+            (Value::F64(l), Value::F64(r))
+                if self
+                    .options
+                    .floats_equal(mask_path, *l, *r, ulps_f64(*l, *r)) => {}
+            (left, right) => {
+                if left != right {
+                    self.push(
+                        path,
+                        value_to_string(left, kind),
+                        value_to_string(right, kind),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Compares two lists ignoring the order of their elements, reporting the elements of each list
+    /// which have no equal element in the other.
+    fn set(&mut self, path: &str, mask_path: &str, kind: &Kind, left: &[Value], right: &[Value]) {
+        let mut matched = vec![false; right.len()];
+        let mut unmatched = Vec::new();
+        for (index, left) in left.iter().enumerate() {
+            let position = (0..right.len()).find(|&position| {
+                !matched[position] && self.equal(mask_path, kind, left, &right[position])
+            });
+            match position {
+                Some(position) => matched[position] = true,
+                None => unmatched.push(index),
+            }
+        }
+
+        for index in unmatched {
+            self.push(
+                &format!("{}[{}]", path, index),
+                value_to_string(&left[index], kind),
+                UNSET.to_owned(),
+            );
+        }
+        for (index, right) in right.iter().enumerate() {
+            if !matched[index] {
+                self.push(
+                    &format!("{}[{}]", path, index),
+                    UNSET.to_owned(),
+                    value_to_string(right, kind),
+                );
+            }
+        }
+    }
+
+    fn equal(&self, mask_path: &str, kind: &Kind, left: &Value, right: &Value) -> bool {
+        let mut diff = MessageDiff::default();
+        Differ {
+            options: self.options,
+            diff: &mut diff,
+        }
+        .value("", mask_path, kind, left, right);
+        diff.is_empty()
+    }
+}
+
+/// Gets the number of representable values between two finite `float` values.
+fn ulps_f32(left: f32, right: f32) -> u64 {
+    fn ordered(value: f32) -> i64 {
+        let bits = value.to_bits();
+        if bits >> 31 == 0 {
+            bits as i64
+        } else {
+            -((bits & !(1 << 31)) as i64)
+        }
+    }
+
+    (ordered(left) - ordered(right)).unsigned_abs()
+}
+
+/// Gets the number of representable values between two finite `double` values.
+fn ulps_f64(left: f64, right: f64) -> u64 {
+    fn ordered(value: f64) -> i128 {
+        let bits = value.to_bits();
+        if bits >> 63 == 0 {
+            bits as i128
+        } else {
+            -((bits & !(1 << 63)) as i128)
+        }
+    }
+
+    (ordered(left) - ordered(right)).unsigned_abs() as u64
+}
+
+fn path_matches(mask_path: &str, path: &str) -> bool {
+    mask_path == path
+        || (mask_path.starts_with(path) && mask_path.as_bytes().get(path.len()) == Some(&b'.'))
+}
+
+fn entries(message: &DynamicMessage) -> BTreeMap<u32, Entry<'_>> {
+    message
+        .fields
+        .iter(&message.desc)
+        .map(|field| match field {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                let presence =
+                    field_desc.supports_presence() && !field_desc.is_list() && !field_desc.is_map();
+                (
+                    field_desc.number(),
+                    Entry::Field(
+                        value,
+                        field_desc.kind(),
+                        field_desc.name().to_owned(),
+                        presence,
+                    ),
+                )
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                let presence = extension_desc.supports_presence()
+                    && !extension_desc.is_list()
+                    && !extension_desc.is_map();
+                (
+                    extension_desc.number(),
+                    Entry::Field(
+                        value,
+                        extension_desc.kind(),
+                        format!("[{}]", extension_desc.full_name()),
+                        presence,
+                    ),
+                )
+            }
+            ValueAndDescriptor::Unknown(number, unknowns) => (number, Entry::Unknown(unknowns)),
+        })
+        .collect()
+}
+
+/// Gets the default value for a field without presence, which has the same shape as `value`.
+fn default_like(value: &Value, kind: &Kind) -> Value {
+    match value {
+        Value::List(_) => Value::List(Vec::new()),
+        Value::Map(_) => Value::Map(Default::default()),
+        _ => Value::default_value(kind),
+    }
+}
+
+fn unknown_string(unknowns: &[UnknownFieldValue]) -> String {
+    if unknowns.is_empty() {
+        UNSET.to_owned()
+    } else {
+        unknown_fields_to_string(unknowns)
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use prost::{bytes::Bytes, Message};
+
+use crate::{DynamicMessage, FieldDescriptor, Kind, MapKey, Value};
+
+/// Options to control the values generated by [`DynamicMessage::fill_example`].
+#[derive(Debug, Clone)]
+pub struct FillOptions {
+    max_depth: u32,
+    repeated_len: usize,
+}
+
+impl DynamicMessage {
+    /// Populates the fields of this message with plausible example values.
+    ///
+    /// This is intended for generating documentation examples and responses for mock servers. The values
+    /// chosen are deterministic, so the same descriptor and options always produce the same message:
+    ///
+    /// - Enum fields are set to the first declared value with a non-zero number, since the zero value is
+    ///   conventionally reserved for `UNSPECIFIED`.
+    /// - String fields are set to a value in a format hinted by the field name where possible (for example
+    ///   email addresses, URLs, UUIDs, dates and timestamps), or a descriptive placeholder otherwise.
+    /// - Repeated and map fields are filled with [`repeated_len`][FillOptions::repeated_len] elements. Map fields
+    ///   with `bool` keys have at most two entries, for `false` and `true`.
+    /// - In each oneof, only the first field which can be populated is set. Message fields beyond the
+    ///   [maximum depth][FillOptions::max_depth] cannot be populated.
+    /// - Well-known types such as `google.protobuf.Timestamp` are given values which are valid in the JSON mapping.
+    ///   `google.protobuf.Any` fields are left unset, since there is no type to pack into them.
+    ///
+    /// This message is cleared first, so fields which are not populated are left unset.
+    pub fn fill_example(&mut self, options: &FillOptions) {
+        self.clear();
+        fill_message(self, options, 0);
+    }
+}
+
+impl FillOptions {
+    /// Creates a new instance of [`FillOptions`], with the default options.
+    pub const fn new() -> Self {
+        FillOptions {
+            max_depth: 3,
+            repeated_len: 2,
+        }
+    }
+
+    /// The maximum depth of nested messages to populate. Message fields beyond this depth are left unset.
+    ///
+    /// The default value is `3`.
+    pub const fn max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// The number of elements to add to repeated and map fields.
+    ///
+    /// The default value is `2`.
+    pub const fn repeated_len(mut self, len: usize) -> Self {
+        self.repeated_len = len;
+        self
+    }
+}
+
+impl Default for FillOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn fill_message(message: &mut DynamicMessage, options: &FillOptions, depth: u32) {
+    let desc = message.desc.clone();
+    match desc.full_name() {
+        "google.protobuf.Any" => return,
+        "google.protobuf.Timestamp" => {
+            // 2022-01-01T00:00:00Z
+            message.set_field_by_name("seconds", Value::I64(1_640_995_200));
+            message.set_field_by_name("nanos", Value::I32(0));
+            return;
+        }
+        "google.protobuf.Duration" => {
+            message.set_field_by_name("seconds", Value::I64(1));
+            message.set_field_by_name("nanos", Value::I32(0));
+            return;
+        }
+        "google.protobuf.FieldMask" => {
+            message.set_field_by_name(
+                "paths",
+                Value::List(vec![Value::String("example".to_owned())]),
+            );
+            return;
+        }
+        _ => (),
+    }
+
+    for field in desc.fields() {
+        if let Some(oneof) = field.containing_oneof() {
+            if oneof.fields().any(|other| message.has_field(&other)) {
+                continue;
+            }
+        }
+
+        if let Some(value) = field_value(&field, options, depth) {
+            message.set_field(&field, value);
+        }
+    }
+}
+
+fn field_value(field: &FieldDescriptor, options: &FillOptions, depth: u32) -> Option<Value> {
+    let kind = field.kind();
+    if field.is_map() {
+        let entry = kind.as_message().unwrap();
+        let key_kind = entry.map_entry_key_field().kind();
+        let value_field = entry.map_entry_value_field();
+
+        let len = match key_kind {
+            Kind::Bool => options.repeated_len.min(2),
+            _ => options.repeated_len,
+        };
+        let mut map = HashMap::with_capacity(len);
+        for index in 0..len {
+            if let Some(value) = value(&value_field, &value_field.kind(), options, depth) {
+                map.insert(map_key(&key_kind, index), value);
+            }
+        }
+        Some(Value::Map(map))
+    } else if field.is_list() {
+        let mut list = Vec::with_capacity(options.repeated_len);
+        for _ in 0..options.repeated_len {
+            if let Some(value) = value(field, &kind, options, depth) {
+                list.push(value);
+            }
+        }
+        Some(Value::List(list))
+    } else {
+        value(field, &kind, options, depth)
+    }
+}
+
+fn value(field: &FieldDescriptor, kind: &Kind, options: &FillOptions, depth: u32) -> Option<Value> {
+    Some(match kind {
+        Kind::Double => Value::F64(1.5),
+        Kind::Float => Value::F32(1.5),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(1),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(1),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(1),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(1),
+        Kind::Bool => Value::Bool(true),
+        Kind::String => Value::String(example_string(field.name())),
+        Kind::Bytes => Value::Bytes(Bytes::from_static(b"example")),
+        Kind::Enum(enum_desc) => {
+            let number = enum_desc
+                .enum_descriptor_proto()
+                .value
+                .iter()
+                .map(|value| value.number())
+                .find(|&number| number != 0)
+                .unwrap_or_else(|| enum_desc.default_value().number());
+            Value::EnumNumber(number)
+        }
+        Kind::Message(message_desc) => {
+            if depth >= options.max_depth || message_desc.full_name() == "google.protobuf.Any" {
+                return None;
+            }
+
+            let mut message = DynamicMessage::new(message_desc.clone());
+            fill_message(&mut message, options, depth + 1);
+            Value::Message(message)
+        }
+    })
+}
+
+fn map_key(kind: &Kind, index: usize) -> MapKey {
+    let number = index + 1;
+    match kind {
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => MapKey::I32(number as i32),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => MapKey::I64(number as i64),
+        Kind::Uint32 | Kind::Fixed32 => MapKey::U32(number as u32),
+        Kind::Uint64 | Kind::Fixed64 => MapKey::U64(number as u64),
+        Kind::Bool => MapKey::Bool(index % 2 == 1),
+        Kind::String => MapKey::String(format!("key{}", number)),
+        _ => unreachable!("invalid type for map key"),
+    }
+}
+
+fn example_string(field_name: &str) -> String {
+    let name = field_name.to_ascii_lowercase();
+    let words: Vec<&str> = name.split('_').collect();
+    let has_word = |word: &str| words.contains(&word);
+
+    if name.contains("email") {
+        "user@example.com".to_owned()
+    } else if has_word("url") || has_word("uri") || has_word("link") || has_word("website") {
+        "https://example.com".to_owned()
+    } else if has_word("uuid") || has_word("guid") || has_word("id") {
+        "123e4567-e89b-12d3-a456-426614174000".to_owned()
+    } else if has_word("phone") {
+        "+1-202-555-0100".to_owned()
+    } else if has_word("host") || has_word("hostname") || has_word("domain") {
+        "example.com".to_owned()
+    } else if has_word("timestamp") || has_word("time") || name.ends_with("_at") {
+        "2022-01-01T00:00:00Z".to_owned()
+    } else if has_word("date") {
+        "2022-01-01".to_owned()
+    } else {
+        format!("example {}", words.join(" "))
+    }
+}
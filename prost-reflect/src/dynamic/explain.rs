@@ -0,0 +1,306 @@
+use std::fmt;
+
+use prost::{
+    bytes::{Buf, Bytes},
+    encoding::{self, WireType},
+    Message,
+};
+
+use crate::{
+    wire::{RawField, RawFields},
+    DynamicMessage, Kind, MessageDescriptor, Value,
+};
+
+use super::snapshot::value_to_string;
+
+/// An annotated breakdown of the encoding of a message, returned by [`DynamicMessage::explain`].
+///
+/// The explanation can be displayed as text, with one line per field giving its byte range, name, field
+/// number, wire type and value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    entries: Vec<ExplainedField>,
+    encoded_len: usize,
+}
+
+/// A field of an encoded message, as described by an [`Explanation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainedField {
+    offset: usize,
+    len: usize,
+    header_len: usize,
+    depth: usize,
+    name: String,
+    number: u32,
+    wire_type: &'static str,
+    value: Option<String>,
+}
+
+impl DynamicMessage {
+    /// Explains the encoding of this message, listing the byte range of each field along with its name,
+    /// wire type and decoded value.
+    ///
+    /// Fields of nested messages and groups, including map entries, are listed after the field containing
+    /// them, with a greater [`depth`][ExplainedField::depth]. Extensions are named by their full name in
+    /// brackets, and unknown fields by their number. This is intended for debugging changes in the
+    /// encoded size of messages, and for showing how the protobuf encoding works.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor);
+    /// message.set_field_by_name("foo", Value::I32(150));
+    ///
+    /// let explanation = message.explain();
+    /// assert_eq!(explanation.encoded_len(), 3);
+    /// assert_eq!(explanation.to_string(), "0..3  foo (1, varint) = 150\n");
+    /// ```
+    pub fn explain(&self) -> Explanation {
+        let encoded = Bytes::from(self.encode_to_vec());
+        let mut entries = Vec::new();
+        explain_message(&mut entries, Some(&self.desc), encoded.clone(), 0, 0);
+        Explanation {
+            entries,
+            encoded_len: encoded.len(),
+        }
+    }
+}
+
+impl Explanation {
+    /// Gets the fields of the encoded message, in the order they appear in the encoding.
+    pub fn entries(&self) -> &[ExplainedField] {
+        &self.entries
+    }
+
+    /// Gets the total length of the encoded message, in bytes.
+    pub fn encoded_len(&self) -> usize {
+        self.encoded_len
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| format!("{}..{}", entry.offset, entry.offset + entry.len))
+            .collect();
+        let width = ranges.iter().map(String::len).max().unwrap_or(0);
+
+        for (entry, range) in self.entries.iter().zip(ranges) {
+            write!(
+                f,
+                "{:width$}  {:indent$}{} ({}, {})",
+                range,
+                "",
+                entry.name,
+                entry.number,
+                entry.wire_type,
+                width = width,
+                indent = entry.depth * 2,
+            )?;
+            if let Some(value) = &entry.value {
+                write!(f, " = {}", value)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl ExplainedField {
+    /// Gets the offset of the start of this field in the encoded message, in bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Gets the length of this field in the encoded message, in bytes, including its key.
+    pub fn encoded_len(&self) -> usize {
+        self.len
+    }
+
+    /// Gets the number of bytes used by the key of this field, and its length prefix or end group tag if
+    /// it has one. This is the overhead of encoding the field in addition to its value.
+    pub fn header_len(&self) -> usize {
+        self.header_len
+    }
+
+    /// Gets the nesting depth of this field, which is `0` for fields of the explained message, `1` for
+    /// the fields of a message nested in it, and so on.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Gets the name of this field.
+    ///
+    /// Extensions are named by their full name in brackets, and unknown fields by their number.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the field number of this field.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the name of the wire type of this field, for example `varint` or `length-delimited`.
+    pub fn wire_type(&self) -> &str {
+        self.wire_type
+    }
+
+    /// Gets the decoded value of this field.
+    ///
+    /// Returns `None` for nested messages and groups, whose fields are listed separately.
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+}
+
+fn explain_message(
+    entries: &mut Vec<ExplainedField>,
+    desc: Option<&MessageDescriptor>,
+    buf: Bytes,
+    base: usize,
+    depth: usize,
+) {
+    let mut raw_fields = RawFields::new(buf);
+    let mut offset = base;
+    while let Some(Ok(field)) = raw_fields.next() {
+        let end = base + raw_fields.offset();
+        let number = field.number();
+        let wire_type = field.wire_type();
+        let value_len = field.value().len();
+
+        let (name, kind) = match desc.and_then(|desc| desc.get_field(number)) {
+            Some(field_desc) => (field_desc.name().to_owned(), Some(field_desc.kind())),
+            None => match desc.and_then(|desc| desc.get_extension(number)) {
+                Some(extension_desc) => (
+                    format!("[{}]", extension_desc.full_name()),
+                    Some(extension_desc.kind()),
+                ),
+                None => (number.to_string(), None),
+            },
+        };
+
+        let nested = match (&kind, wire_type) {
+            (Some(Kind::Message(message_desc)), WireType::LengthDelimited)
+            | (Some(Kind::Message(message_desc)), WireType::StartGroup) => {
+                Some(Some(message_desc.clone()))
+            }
+            (None, WireType::StartGroup) => Some(None),
+            _ => None,
+        };
+
+        let value = match (&nested, desc) {
+            (Some(_), _) => None,
+            (None, Some(desc)) if kind.is_some() => Some(decode_value(desc, &field)),
+            (None, _) => Some(unknown_value_to_string(&field)),
+        };
+
+        entries.push(ExplainedField {
+            offset,
+            len: end - offset,
+            header_len: end - offset - value_len,
+            depth,
+            name,
+            number,
+            wire_type: wire_type_name(wire_type),
+            value,
+        });
+
+        if let Some(nested_desc) = nested {
+            let value_offset = match wire_type {
+                WireType::StartGroup => offset + encoding::key_len(number),
+                _ => end - value_len,
+            };
+            explain_message(
+                entries,
+                nested_desc.as_ref(),
+                field.value().clone(),
+                value_offset,
+                depth + 1,
+            );
+        }
+
+        offset = end;
+    }
+}
+
+fn decode_value(desc: &MessageDescriptor, field: &RawField) -> String {
+    // Decode the field on its own, so the value is interpreted exactly as it would be when decoding the
+    // whole message.
+    let mut buf = Vec::with_capacity(field.encoded_len());
+    field.encode(&mut buf);
+    let mut message = DynamicMessage::new(desc.clone());
+    if message.merge(buf.as_slice()).is_err() {
+        return unknown_value_to_string(field);
+    }
+
+    let (value, kind) = match desc.get_field(field.number()) {
+        Some(field_desc) => (
+            message.get_field(&field_desc).into_owned(),
+            field_desc.kind(),
+        ),
+        None => match desc.get_extension(field.number()) {
+            Some(extension_desc) => (
+                message.get_extension(&extension_desc).into_owned(),
+                extension_desc.kind(),
+            ),
+            None => return unknown_value_to_string(field),
+        },
+    };
+
+    match value {
+        Value::List(values)
+            if field.wire_type() == WireType::LengthDelimited && is_packed(&kind) =>
+        {
+            let values: Vec<String> = values
+                .iter()
+                .map(|value| value_to_string(value, &kind))
+                .collect();
+            format!("[{}]", values.join(", "))
+        }
+        Value::List(values) => match values.first() {
+            Some(value) => value_to_string(value, &kind),
+            None => unknown_value_to_string(field),
+        },
+        value => value_to_string(&value, &kind),
+    }
+}
+
+fn is_packed(kind: &Kind) -> bool {
+    !matches!(kind, Kind::String | Kind::Bytes | Kind::Message(_))
+}
+
+fn unknown_value_to_string(field: &RawField) -> String {
+    let mut value = field.value().clone();
+    match field.wire_type() {
+        WireType::Varint => encoding::decode_varint(&mut value)
+            .map(|value| value.to_string())
+            .unwrap_or_default(),
+        WireType::ThirtyTwoBit => format!("0x{:08x}", value.get_u32_le()),
+        WireType::SixtyFourBit => format!("0x{:016x}", value.get_u64_le()),
+        WireType::LengthDelimited | WireType::StartGroup | WireType::EndGroup => {
+            let mut output = String::from("b\"");
+            for &byte in value.as_ref() {
+                output.extend(std::ascii::escape_default(byte).map(char::from));
+            }
+            output.push('"');
+            output
+        }
+    }
+}
+
+fn wire_type_name(wire_type: WireType) -> &'static str {
+    match wire_type {
+        WireType::Varint => "varint",
+        WireType::SixtyFourBit => "fixed64",
+        WireType::LengthDelimited => "length-delimited",
+        WireType::StartGroup => "group",
+        WireType::EndGroup => "end group",
+        WireType::ThirtyTwoBit => "fixed32",
+    }
+}
@@ -1,14 +1,20 @@
 use std::{
     borrow::Cow,
-    collections::btree_map::{self, BTreeMap},
     fmt,
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
+use prost::encoding::{decode_key, DecodeContext};
+
 use crate::{
-    ExtensionDescriptor, FieldDescriptor, Kind, MessageDescriptor, OneofDescriptor, Value,
+    ExtensionDescriptor, FieldDescriptor, Kind, MessageDescriptor, OneofDescriptor, Syntax, Value,
 };
 
-use super::unknown::UnknownField;
+use super::unknown::UnknownFieldValue;
 
 pub(super) trait FieldDescriptorLike: fmt::Debug {
     fn number(&self) -> u32;
@@ -23,34 +29,102 @@ pub(super) trait FieldDescriptorLike: fmt::Debug {
     fn is_map(&self) -> bool;
     fn is_packed(&self) -> bool;
     fn is_packable(&self) -> bool;
+    fn syntax(&self) -> Syntax;
     fn has(&self, value: &Value) -> bool {
         self.supports_presence() || !self.is_default_value(value)
     }
 }
 
 /// A set of extension fields in a protobuf message.
-#[derive(Default, Debug, Clone, PartialEq)]
 pub(super) struct DynamicMessageFieldSet {
-    fields: BTreeMap<u32, ValueOrUnknown>,
+    /// The values of the fields which are set, sorted by field number.
+    ///
+    /// Fields are usually decoded in order of their numbers, so new values can typically be pushed onto the
+    /// end, avoiding the per-entry allocations of a map.
+    ///
+    /// The values are shared between clones of a message, and only copied when one of the clones is
+    /// modified. Nested messages are shared in the same way, so copying the values does not copy them,
+    /// but strings, lists and maps are copied.
+    fields: Arc<Vec<(u32, ValueOrUnknown)>>,
+    /// The encoded length of the fields, or [`UNKNOWN_LEN`] if it has not been computed since they were
+    /// last modified.
+    ///
+    /// Encoding a message typically computes its length before writing it, and the length of a nested
+    /// message is needed again when writing its parent, so caching it avoids traversing the values twice.
+    encoded_len: AtomicUsize,
 }
 
+const UNKNOWN_LEN: usize = usize::MAX;
+
 #[derive(Debug, Clone, PartialEq)]
 pub(super) enum ValueOrUnknown {
     Value(Value),
-    Unknown(Vec<UnknownField>),
+    Unknown(Vec<UnknownFieldValue>),
+    /// A value which has been cleared by [`DynamicMessageFieldSet::clear_for_reuse`], and is treated as
+    /// unset. It is kept so that its allocations can be reused if the field is set again.
+    Cleared(Value),
 }
 
 pub(super) enum ValueAndDescriptor<'a> {
     Field(&'a Value, FieldDescriptor),
     Extension(&'a Value, ExtensionDescriptor),
-    Unknown(u32, &'a [UnknownField]),
+    Unknown(u32, &'a [UnknownFieldValue]),
 }
 
 impl DynamicMessageFieldSet {
+    /// Gets the values for modification, copying them first if they are shared with another message.
+    ///
+    /// All modifications of the values, including of nested messages, go through this method, so it also
+    /// invalidates the cached encoded length.
+    fn fields_mut(&mut self) -> &mut Vec<(u32, ValueOrUnknown)> {
+        *self.encoded_len.get_mut() = UNKNOWN_LEN;
+        Arc::make_mut(&mut self.fields)
+    }
+
+    /// Gets the cached encoded length of the fields, or computes it with `f` if they have been modified
+    /// since it was last computed.
+    pub(super) fn encoded_len(&self, f: impl FnOnce() -> usize) -> usize {
+        match self.encoded_len.load(Ordering::Relaxed) {
+            UNKNOWN_LEN => {
+                let len = f();
+                self.encoded_len.store(len, Ordering::Relaxed);
+                len
+            }
+            len => len,
+        }
+    }
+
+    fn find(&self, number: u32) -> Result<usize, usize> {
+        match self.fields.last() {
+            Some(&(last, _)) if last < number => Err(self.fields.len()),
+            None => Err(0),
+            Some(_) => self
+                .fields
+                .binary_search_by_key(&number, |&(number, _)| number),
+        }
+    }
+
+    fn get_entry(&self, number: u32) -> Option<&ValueOrUnknown> {
+        match self.find(number) {
+            Ok(index) => match &self.fields[index].1 {
+                ValueOrUnknown::Cleared(_) => None,
+                value => Some(value),
+            },
+            Err(_) => None,
+        }
+    }
+
+    fn insert_entry(&mut self, number: u32, value: ValueOrUnknown) {
+        match self.find(number) {
+            Ok(index) => self.fields_mut()[index].1 = value,
+            Err(index) => self.fields_mut().insert(index, (number, value)),
+        }
+    }
+
     fn get_value(&self, number: u32) -> Option<&Value> {
-        match self.fields.get(&number) {
+        match self.get_entry(number) {
             Some(ValueOrUnknown::Value(value)) => Some(value),
-            Some(ValueOrUnknown::Unknown(_)) | None => None,
+            Some(ValueOrUnknown::Unknown(_) | ValueOrUnknown::Cleared(_)) | None => None,
         }
     }
 
@@ -67,19 +141,35 @@ impl DynamicMessageFieldSet {
         }
     }
 
+    pub(super) fn get_opt(&self, desc: &impl FieldDescriptorLike) -> Option<&Value> {
+        self.get_value(desc.number())
+            .filter(|value| desc.has(value))
+    }
+
     pub(super) fn get_mut(&mut self, desc: &impl FieldDescriptorLike) -> &mut Value {
         self.clear_oneof_fields(desc);
-        match self.fields.entry(desc.number()) {
-            btree_map::Entry::Occupied(entry) => match entry.into_mut() {
+        match self.find(desc.number()) {
+            Ok(index) => match &mut self.fields_mut()[index].1 {
                 ValueOrUnknown::Value(value) => value,
                 value @ ValueOrUnknown::Unknown(_) => {
                     *value = ValueOrUnknown::Value(desc.default_value());
                     value.unwrap_value_mut()
                 }
+                value @ ValueOrUnknown::Cleared(_) => {
+                    if let ValueOrUnknown::Cleared(cleared) = value {
+                        *value = ValueOrUnknown::Value(reuse_value(take_value(cleared), desc));
+                    }
+                    value.unwrap_value_mut()
+                }
             },
-            btree_map::Entry::Vacant(entry) => entry
-                .insert(ValueOrUnknown::Value(desc.default_value()))
-                .unwrap_value_mut(),
+            Err(index) => {
+                let fields = self.fields_mut();
+                fields.insert(
+                    index,
+                    (desc.number(), ValueOrUnknown::Value(desc.default_value())),
+                );
+                fields[index].1.unwrap_value_mut()
+            }
         }
     }
 
@@ -92,8 +182,7 @@ impl DynamicMessageFieldSet {
         );
 
         self.clear_oneof_fields(desc);
-        self.fields
-            .insert(desc.number(), ValueOrUnknown::Value(value));
+        self.insert_entry(desc.number(), ValueOrUnknown::Value(value));
     }
 
     fn clear_oneof_fields(&mut self, desc: &impl FieldDescriptorLike) {
@@ -106,22 +195,116 @@ impl DynamicMessageFieldSet {
         }
     }
 
-    pub(crate) fn add_unknown(&mut self, number: u32, unknown: UnknownField) {
-        match self.fields.entry(number) {
-            btree_map::Entry::Occupied(mut entry) => match entry.get_mut() {
+    pub(crate) fn add_unknown(&mut self, number: u32, unknown: UnknownFieldValue) {
+        match self.find(number) {
+            Ok(index) => match &mut self.fields_mut()[index].1 {
                 ValueOrUnknown::Value(_) => {
                     panic!("expected no field to be found with number {}", number)
                 }
                 ValueOrUnknown::Unknown(unknowns) => unknowns.push(unknown),
+                value @ ValueOrUnknown::Cleared(_) => {
+                    *value = ValueOrUnknown::Unknown(vec![unknown])
+                }
             },
-            btree_map::Entry::Vacant(entry) => {
-                entry.insert(ValueOrUnknown::Unknown(vec![unknown]));
+            Err(index) => {
+                self.fields_mut()
+                    .insert(index, (number, ValueOrUnknown::Unknown(vec![unknown])));
             }
         }
     }
 
+    pub(super) fn is_unknown(&self, number: u32) -> bool {
+        matches!(self.get_entry(number), Some(ValueOrUnknown::Unknown(_)))
+    }
+
+    /// Replaces the value of a field with its encoding as unknown fields, so that further values can be
+    /// added with [`add_unknown`][Self::add_unknown].
+    pub(super) fn make_unknown(&mut self, desc: &impl FieldDescriptorLike) {
+        self.clear_oneof_fields(desc);
+        let value = match self.find(desc.number()) {
+            Ok(index) if matches!(self.fields[index].1, ValueOrUnknown::Value(_)) => {
+                match self.fields_mut().remove(index).1 {
+                    ValueOrUnknown::Value(value) => value,
+                    ValueOrUnknown::Unknown(_) | ValueOrUnknown::Cleared(_) => unreachable!(),
+                }
+            }
+            _ => return,
+        };
+        self.add_unknown_value(desc, &value);
+    }
+
+    /// Adds the encoding of a value of a field as unknown fields, after any existing unknown fields with
+    /// the same number.
+    pub(super) fn add_unknown_value(&mut self, desc: &impl FieldDescriptorLike, value: &Value) {
+        if !desc.has(value) {
+            return;
+        }
+
+        let mut buf = Vec::new();
+        value.encode_field(desc, &mut buf);
+        let mut buf = buf.as_slice();
+        while !buf.is_empty() {
+            let (number, wire_type) = decode_key(&mut buf).expect("field encoding is valid");
+            let unknown =
+                UnknownFieldValue::decode(number, wire_type, &mut buf, DecodeContext::default())
+                    .expect("field encoding is valid");
+            self.add_unknown(number, unknown);
+        }
+    }
+
+    pub(super) fn iter_unknown(&self) -> impl Iterator<Item = (u32, &UnknownFieldValue)> {
+        self.fields
+            .iter()
+            .filter_map(|(number, value)| match value {
+                ValueOrUnknown::Value(_) | ValueOrUnknown::Cleared(_) => None,
+                ValueOrUnknown::Unknown(unknowns) => Some((*number, unknowns)),
+            })
+            .flat_map(|(number, unknowns)| unknowns.iter().map(move |unknown| (number, unknown)))
+    }
+
+    /// Removes the unknown fields whose numbers do not match a field or extension of `desc`. Values of known
+    /// fields which were preserved as unknown fields are left in place.
+    pub(super) fn take_unknown(
+        &mut self,
+        desc: &MessageDescriptor,
+    ) -> Vec<(u32, UnknownFieldValue)> {
+        if !self.has_unknown() {
+            return Vec::new();
+        }
+
+        let mut result = Vec::new();
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for (number, value) in self.fields_mut().drain(..) {
+            match value {
+                ValueOrUnknown::Unknown(unknowns)
+                    if desc.get_field(number).is_none() && desc.get_extension(number).is_none() =>
+                {
+                    result.extend(unknowns.into_iter().map(|unknown| (number, unknown)))
+                }
+                value => fields.push((number, value)),
+            }
+        }
+        self.fields = Arc::new(fields);
+        result
+    }
+
+    pub(super) fn clear_unknown(&mut self) {
+        if self.has_unknown() {
+            self.fields_mut()
+                .retain(|(_, value)| !matches!(value, ValueOrUnknown::Unknown(_)));
+        }
+    }
+
+    fn has_unknown(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|(_, value)| matches!(value, ValueOrUnknown::Unknown(_)))
+    }
+
     pub(super) fn clear(&mut self, desc: &impl FieldDescriptorLike) {
-        self.fields.remove(&desc.number());
+        if let Ok(index) = self.find(desc.number()) {
+            self.fields_mut().remove(index);
+        }
     }
 
     pub(crate) fn iter<'a>(
@@ -130,7 +313,7 @@ impl DynamicMessageFieldSet {
     ) -> impl Iterator<Item = ValueAndDescriptor> + 'a {
         self.fields
             .iter()
-            .filter_map(move |(&number, value)| match value {
+            .filter_map(move |&(number, ref value)| match value {
                 ValueOrUnknown::Value(value) => {
                     if let Some(field) = message.get_field(number) {
                         if field.has(value) {
@@ -151,11 +334,136 @@ impl DynamicMessageFieldSet {
                 ValueOrUnknown::Unknown(unknown) => {
                     Some(ValueAndDescriptor::Unknown(number, unknown.as_slice()))
                 }
+                ValueOrUnknown::Cleared(_) => None,
             })
     }
 
+    pub(super) fn values_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.fields_mut()
+            .iter_mut()
+            .filter_map(|(_, value)| match value {
+                ValueOrUnknown::Value(value) => Some(value),
+                ValueOrUnknown::Unknown(_) | ValueOrUnknown::Cleared(_) => None,
+            })
+    }
+
+    /// Clears all fields, keeping their values so that the allocations of strings, lists, maps and nested
+    /// messages can be reused when the fields are set again.
+    ///
+    /// Unknown fields are discarded.
+    pub(super) fn clear_for_reuse(&mut self) {
+        *self.encoded_len.get_mut() = UNKNOWN_LEN;
+        let fields = match Arc::get_mut(&mut self.fields) {
+            Some(fields) => fields,
+            // The values are shared with another message, so cannot be reused.
+            None => return self.clear_all(),
+        };
+        fields.retain(|(_, value)| !matches!(value, ValueOrUnknown::Unknown(_)));
+        for (_, value) in fields {
+            if let ValueOrUnknown::Value(cleared) = value {
+                *value = ValueOrUnknown::Cleared(take_value(cleared));
+            }
+        }
+    }
+
     pub(super) fn clear_all(&mut self) {
-        self.fields.clear();
+        *self.encoded_len.get_mut() = UNKNOWN_LEN;
+        match Arc::get_mut(&mut self.fields) {
+            Some(fields) => fields.clear(),
+            None => self.fields = Arc::default(),
+        }
+    }
+}
+
+impl Default for DynamicMessageFieldSet {
+    fn default() -> Self {
+        DynamicMessageFieldSet {
+            fields: Arc::default(),
+            encoded_len: AtomicUsize::new(UNKNOWN_LEN),
+        }
+    }
+}
+
+impl Clone for DynamicMessageFieldSet {
+    fn clone(&self) -> Self {
+        DynamicMessageFieldSet {
+            fields: self.fields.clone(),
+            encoded_len: AtomicUsize::new(self.encoded_len.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl fmt::Debug for DynamicMessageFieldSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicMessageFieldSet")
+            .field("fields", &self.fields)
+            .finish()
+    }
+}
+
+impl PartialEq for DynamicMessageFieldSet {
+    fn eq(&self, other: &Self) -> bool {
+        fn set_fields(
+            fields: &[(u32, ValueOrUnknown)],
+        ) -> impl Iterator<Item = &(u32, ValueOrUnknown)> {
+            fields
+                .iter()
+                .filter(|(_, value)| !matches!(value, ValueOrUnknown::Cleared(_)))
+        }
+
+        set_fields(&self.fields).eq(set_fields(&other.fields))
+    }
+}
+
+impl Hash for DynamicMessageFieldSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut len = 0usize;
+        for (number, value) in self.fields.iter() {
+            match value {
+                ValueOrUnknown::Value(value) => {
+                    number.hash(state);
+                    0u8.hash(state);
+                    value.hash(state);
+                }
+                ValueOrUnknown::Unknown(unknowns) => {
+                    number.hash(state);
+                    1u8.hash(state);
+                    unknowns.hash(state);
+                }
+                ValueOrUnknown::Cleared(_) => continue,
+            }
+            len += 1;
+        }
+        len.hash(state);
+    }
+}
+
+fn take_value(value: &mut Value) -> Value {
+    std::mem::replace(value, Value::Bool(false))
+}
+
+/// Resets a value which was previously cleared to the default value of a field, reusing its allocation
+/// if possible.
+fn reuse_value(value: Value, desc: &impl FieldDescriptorLike) -> Value {
+    match (value, desc.default_value()) {
+        (Value::String(mut value), Value::String(default)) => {
+            value.clear();
+            value.push_str(&default);
+            Value::String(value)
+        }
+        (Value::List(mut values), Value::List(_)) => {
+            values.clear();
+            Value::List(values)
+        }
+        (Value::Map(mut values), Value::Map(_)) => {
+            values.clear();
+            Value::Map(values)
+        }
+        (Value::Message(mut message), Value::Message(default)) if message.desc == default.desc => {
+            message.fields.clear_for_reuse();
+            Value::Message(message)
+        }
+        (_, default) => default,
     }
 }
 
@@ -163,7 +471,7 @@ impl ValueOrUnknown {
     fn unwrap_value_mut(&mut self) -> &mut Value {
         match self {
             ValueOrUnknown::Value(value) => value,
-            ValueOrUnknown::Unknown(_) => unreachable!(),
+            ValueOrUnknown::Unknown(_) | ValueOrUnknown::Cleared(_) => unreachable!(),
         }
     }
 }
@@ -216,6 +524,10 @@ impl FieldDescriptorLike for FieldDescriptor {
     fn is_packable(&self) -> bool {
         self.is_packable()
     }
+
+    fn syntax(&self) -> Syntax {
+        self.parent_file().syntax()
+    }
 }
 
 impl FieldDescriptorLike for ExtensionDescriptor {
@@ -266,4 +578,8 @@ impl FieldDescriptorLike for ExtensionDescriptor {
     fn is_packable(&self) -> bool {
         self.is_packable()
     }
+
+    fn syntax(&self) -> Syntax {
+        self.parent_file().syntax()
+    }
 }
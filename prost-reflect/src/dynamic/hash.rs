@@ -0,0 +1,98 @@
+use std::{
+    hash::{Hash, Hasher},
+    mem,
+};
+
+use crate::{DynamicMessage, MapKey, Value};
+
+/// The bits hashed for all NaN values, since they are all equal to each other.
+const NAN_BITS: u64 = 0x7ff8_0000_0000_0000;
+
+impl Eq for DynamicMessage {}
+
+impl Hash for DynamicMessage {
+    /// Hashes this message, consistently with its implementation of [`PartialEq`].
+    ///
+    /// The hash includes the full name of the message type, and the number and value of each field which
+    /// is set, including extensions and unknown fields, in field number order. Unknown JSON fields are
+    /// not included. See the implementation of [`Hash`] for [`Value`] for how values are hashed.
+    ///
+    /// Messages cache their encoded length using interior mutability, which causes clippy's
+    /// `mutable_key_type` lint to warn when they are used as keys. The cached length does not affect the
+    /// hash, so the lint may be safely allowed.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.desc.full_name().hash(state);
+        self.fields.hash(state);
+    }
+}
+
+impl PartialEq for Value {
+    /// Compares two values for equality.
+    ///
+    /// Floating point values are equal if they compare equal as numbers, so `0.0` is equal to `-0.0`, or
+    /// if both are NaN. Treating NaN as equal to itself makes this a total equivalence relation, as
+    /// required by [`Eq`] and [`Hash`].
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::I32(l), Value::I32(r)) => l == r,
+            (Value::I64(l), Value::I64(r)) => l == r,
+            (Value::U32(l), Value::U32(r)) => l == r,
+            (Value::U64(l), Value::U64(r)) => l == r,
+            (Value::F32(l), Value::F32(r)) => l == r || (l.is_nan() && r.is_nan()),
+            (Value::F64(l), Value::F64(r)) => l == r || (l.is_nan() && r.is_nan()),
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Bytes(l), Value::Bytes(r)) => l == r,
+            (Value::EnumNumber(l), Value::EnumNumber(r)) => l == r,
+            (Value::Message(l), Value::Message(r)) => l == r,
+            (Value::List(l), Value::List(r)) => l == r,
+            (Value::Map(l), Value::Map(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    /// Hashes this value, consistently with its implementation of [`PartialEq`].
+    ///
+    /// Floating point values are hashed by their bit pattern, except that `0.0` and `-0.0` hash the same,
+    /// as do all NaN values, since they compare equal. Maps are hashed with their entries sorted by key,
+    /// so that equal maps hash the same regardless of their iteration order. Unknown fields of messages
+    /// are hashed by their raw value, so messages whose unknown fields are encoded differently hash
+    /// differently.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::Bool(value) => value.hash(state),
+            Value::I32(value) => value.hash(state),
+            Value::I64(value) => value.hash(state),
+            Value::U32(value) => value.hash(state),
+            Value::U64(value) => value.hash(state),
+            Value::F32(value) => hash_float(*value as f64, state),
+            Value::F64(value) => hash_float(*value, state),
+            Value::String(value) => value.hash(state),
+            Value::Bytes(value) => value.hash(state),
+            Value::EnumNumber(value) => value.hash(state),
+            Value::Message(value) => value.hash(state),
+            Value::List(values) => values.hash(state),
+            Value::Map(values) => {
+                let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+                entries.hash(state);
+            }
+        }
+    }
+}
+
+fn hash_float<H: Hasher>(value: f64, state: &mut H) {
+    let bits = if value.is_nan() {
+        NAN_BITS
+    } else if value == 0.0 {
+        0
+    } else {
+        value.to_bits()
+    };
+    bits.hash(state);
+}
@@ -0,0 +1,250 @@
+use std::{
+    borrow::Cow,
+    ops::{Index, IndexMut},
+};
+
+use prost::bytes::Bytes;
+
+use crate::{DynamicMessage, FieldDescriptor, Kind, MapKey, Value};
+
+static DEFAULT_BOOL: Value = Value::Bool(false);
+static DEFAULT_I32: Value = Value::I32(0);
+static DEFAULT_I64: Value = Value::I64(0);
+static DEFAULT_U32: Value = Value::U32(0);
+static DEFAULT_U64: Value = Value::U64(0);
+static DEFAULT_F32: Value = Value::F32(0.0);
+static DEFAULT_F64: Value = Value::F64(0.0);
+static DEFAULT_STRING: Value = Value::String(String::new());
+static DEFAULT_BYTES: Value = Value::Bytes(Bytes::new());
+static DEFAULT_ENUM: Value = Value::EnumNumber(0);
+static DEFAULT_LIST: Value = Value::List(Vec::new());
+
+/// Gets the value of a field by name, for quick exploration of a message.
+///
+/// If the field is unset, its default value is returned. Messages, maps and enums whose default value
+/// is not zero have no default value which can be borrowed, so for these fields
+/// [`get_field`][DynamicMessage::get_field] should be used instead.
+///
+/// # Panics
+///
+/// Panics if the message has no field with the given name, or if the field is unset and its default
+/// value cannot be borrowed.
+///
+/// # Examples
+///
+/// ```
+/// # use prost::Message;
+/// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+/// assert_eq!(dynamic_message["foo"], Value::I32(150));
+/// ```
+impl<'a> Index<&'a str> for DynamicMessage {
+    type Output = Value;
+
+    fn index(&self, name: &'a str) -> &Value {
+        let field_desc = self.desc.get_field_by_name(name).unwrap_or_else(|| {
+            panic!(
+                "message '{}' has no field named '{}'",
+                self.desc.full_name(),
+                name
+            )
+        });
+        self.field_ref(&field_desc)
+    }
+}
+
+/// Gets the value of a field by number.
+///
+/// See the `Index<&str>` implementation for more details.
+impl Index<usize> for DynamicMessage {
+    type Output = Value;
+
+    fn index(&self, number: usize) -> &Value {
+        let field_desc = self.field_by_number(number);
+        self.field_ref(&field_desc)
+    }
+}
+
+/// Gets a mutable reference to the value of a field by name. If the field is unset, it is inserted
+/// with its default value.
+///
+/// # Panics
+///
+/// Panics if the message has no field with the given name.
+impl<'a> IndexMut<&'a str> for DynamicMessage {
+    fn index_mut(&mut self, name: &'a str) -> &mut Value {
+        let field_desc = self.desc.get_field_by_name(name).unwrap_or_else(|| {
+            panic!(
+                "message '{}' has no field named '{}'",
+                self.desc.full_name(),
+                name
+            )
+        });
+        self.get_field_mut(&field_desc)
+    }
+}
+
+/// Gets a mutable reference to the value of a field by number. If the field is unset, it is inserted
+/// with its default value.
+///
+/// # Panics
+///
+/// Panics if the message has no field with the given number.
+impl IndexMut<usize> for DynamicMessage {
+    fn index_mut(&mut self, number: usize) -> &mut Value {
+        let field_desc = self.field_by_number(number);
+        self.get_field_mut(&field_desc)
+    }
+}
+
+/// Gets the value of a field of a message by name, or the value of an entry with a string key in a map.
+///
+/// # Panics
+///
+/// Panics if the value is not a message or map, if the message has no field with the given name, or
+/// if the map has no entry with the given key.
+impl<'a> Index<&'a str> for Value {
+    type Output = Value;
+
+    fn index(&self, name: &'a str) -> &Value {
+        match self {
+            Value::Message(message) => &message[name],
+            Value::Map(map) => map
+                .get(&MapKey::String(name.to_owned()))
+                .unwrap_or_else(|| panic!("map has no entry with key '{}'", name)),
+            _ => panic!("cannot index {} with a string", type_name(self)),
+        }
+    }
+}
+
+/// Gets an element of a list by position, or the value of a field of a message by number.
+///
+/// # Panics
+///
+/// Panics if the value is not a list or message, if the index is out of bounds for the list, or if
+/// the message has no field with the given number.
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        match self {
+            Value::List(list) => list.get(index).unwrap_or_else(|| {
+                panic!(
+                    "index {} is out of bounds for list of length {}",
+                    index,
+                    list.len()
+                )
+            }),
+            Value::Message(message) => &message[index],
+            _ => panic!("cannot index {} with an integer", type_name(self)),
+        }
+    }
+}
+
+/// Gets a mutable reference to the value of a field of a message by name, or the value of an entry
+/// with a string key in a map.
+///
+/// # Panics
+///
+/// Panics if the value is not a message or map, if the message has no field with the given name, or
+/// if the map has no entry with the given key.
+impl<'a> IndexMut<&'a str> for Value {
+    fn index_mut(&mut self, name: &'a str) -> &mut Value {
+        match self {
+            Value::Message(message) => &mut message[name],
+            Value::Map(map) => map
+                .get_mut(&MapKey::String(name.to_owned()))
+                .unwrap_or_else(|| panic!("map has no entry with key '{}'", name)),
+            value => panic!("cannot index {} with a string", type_name(value)),
+        }
+    }
+}
+
+/// Gets a mutable reference to an element of a list by position, or the value of a field of a
+/// message by number.
+///
+/// # Panics
+///
+/// Panics if the value is not a list or message, if the index is out of bounds for the list, or if
+/// the message has no field with the given number.
+impl IndexMut<usize> for Value {
+    fn index_mut(&mut self, index: usize) -> &mut Value {
+        match self {
+            Value::List(list) => {
+                let len = list.len();
+                list.get_mut(index).unwrap_or_else(|| {
+                    panic!(
+                        "index {} is out of bounds for list of length {}",
+                        index, len
+                    )
+                })
+            }
+            Value::Message(message) => &mut message[index],
+            value => panic!("cannot index {} with an integer", type_name(value)),
+        }
+    }
+}
+
+impl DynamicMessage {
+    fn field_by_number(&self, number: usize) -> FieldDescriptor {
+        let field_desc = if number <= u32::MAX as usize {
+            self.desc.get_field(number as u32)
+        } else {
+            None
+        };
+        field_desc.unwrap_or_else(|| {
+            panic!(
+                "message '{}' has no field with number {}",
+                self.desc.full_name(),
+                number
+            )
+        })
+    }
+
+    fn field_ref(&self, field_desc: &FieldDescriptor) -> &Value {
+        if let Cow::Borrowed(value) = self.fields.get(field_desc) {
+            return value;
+        }
+        if let Some(value) = self.desc.field_default_value(field_desc.number()) {
+            return value;
+        }
+        if field_desc.is_list() {
+            return &DEFAULT_LIST;
+        }
+
+        let kind = field_desc.kind();
+        match kind {
+            Kind::Double => &DEFAULT_F64,
+            Kind::Float => &DEFAULT_F32,
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => &DEFAULT_I32,
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => &DEFAULT_I64,
+            Kind::Uint32 | Kind::Fixed32 => &DEFAULT_U32,
+            Kind::Uint64 | Kind::Fixed64 => &DEFAULT_U64,
+            Kind::Bool => &DEFAULT_BOOL,
+            Kind::String => &DEFAULT_STRING,
+            Kind::Bytes => &DEFAULT_BYTES,
+            Kind::Enum(enum_desc) if enum_desc.default_value().number() == 0 => &DEFAULT_ENUM,
+            _ => panic!(
+                "field '{}' of message '{}' is not set, use `get_field` to get its default value",
+                field_desc.name(),
+                self.desc.full_name()
+            ),
+        }
+    }
+}
+
+pub(super) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "a bool",
+        Value::I32(_) | Value::I64(_) | Value::U32(_) | Value::U64(_) => "an integer",
+        Value::F32(_) | Value::F64(_) => "a float",
+        Value::String(_) => "a string",
+        Value::Bytes(_) => "bytes",
+        Value::EnumNumber(_) => "an enum",
+        Value::Message(_) => "a message",
+        Value::List(_) => "a list",
+        Value::Map(_) => "a map",
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+};
+
+use crate::{
+    Cardinality, DynamicMessage, ExtensionDescriptor, FieldDescriptor, Kind, MapKey,
+    MessageDescriptor, Value,
+};
+
+use super::{fields::ValueAndDescriptor, snapshot::map_key_to_string};
+
+/// An error returned when a message is missing `proto2` required fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UninitializedError {
+    missing_fields: Vec<String>,
+}
+
+impl DynamicMessage {
+    /// Returns `true` if all `proto2` required fields are set, in this message and in all nested
+    /// messages.
+    ///
+    /// Messages defined in `proto3` files have no required fields, so are always initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, FieldBuilder, FileDescriptorBuilder, MessageBuilder, Syntax, Value};
+    /// # let pool = FileDescriptorBuilder::new("person.proto")
+    /// #     .syntax(Syntax::Proto2)
+    /// #     .add_message(MessageBuilder::new("Person").add_field(FieldBuilder::new("name", 1, "string").required()))
+    /// #     .build()
+    /// #     .unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("Person").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor);
+    /// assert!(!message.is_initialized());
+    /// assert_eq!(message.check_initialized().unwrap_err().missing_fields().collect::<Vec<_>>(), ["name"]);
+    ///
+    /// message.set_field_by_name("name", Value::String("Alice".to_owned()));
+    /// assert!(message.is_initialized());
+    /// ```
+    pub fn is_initialized(&self) -> bool {
+        self.check_initialized().is_ok()
+    }
+
+    /// Checks that all `proto2` required fields are set, in this message and in all nested messages,
+    /// returning an error listing the paths of any missing fields.
+    ///
+    /// Paths use the same format as [`Visit`], for example `items[0].id`.
+    pub fn check_initialized(&self) -> Result<(), UninitializedError> {
+        let required = types_with_required_fields(&self.desc);
+        if required.is_empty() {
+            return Ok(());
+        }
+
+        let mut missing_fields = Vec::new();
+        find_missing_fields(self, &required, &mut Vec::new(), &mut missing_fields);
+        if missing_fields.is_empty() {
+            Ok(())
+        } else {
+            Err(UninitializedError { missing_fields })
+        }
+    }
+}
+
+/// Creates a message with only its required fields set, to their default values, so that it is initialized.
+///
+/// This is used to fill required message fields beyond the maximum depth when generating messages. A message
+/// type which contains itself through required fields can never be initialized, so such fields are left unset.
+pub(super) fn initialized_message(desc: &MessageDescriptor) -> DynamicMessage {
+    fn build(desc: &MessageDescriptor, stack: &mut Vec<MessageDescriptor>) -> DynamicMessage {
+        let mut message = DynamicMessage::new(desc.clone());
+        stack.push(desc.clone());
+        for field_desc in desc.fields() {
+            if field_desc.cardinality() != Cardinality::Required {
+                continue;
+            }
+            let value = match field_desc.kind() {
+                Kind::Message(message_desc) if stack.contains(&message_desc) => continue,
+                Kind::Message(message_desc) => Value::Message(build(&message_desc, stack)),
+                _ => Value::default_value_for_field(&field_desc),
+            };
+            message.set_field(&field_desc, value);
+        }
+        stack.pop();
+        message
+    }
+
+    build(desc, &mut Vec::new())
+}
+
+/// A segment of the path to a nested message, which is only formatted if a missing field is found.
+enum PathSegment<'a> {
+    Field(FieldDescriptor),
+    Extension(ExtensionDescriptor),
+    Index(usize),
+    Key(&'a MapKey),
+}
+
+/// Gets the names of the message types reachable from `desc` which contain required fields, either
+/// directly or in a nested message. If the set is empty, messages of type `desc` are always initialized.
+fn types_with_required_fields(desc: &MessageDescriptor) -> HashSet<String> {
+    let mut reachable = HashMap::new();
+    let mut stack = vec![desc.clone()];
+    while let Some(desc) = stack.pop() {
+        if reachable.contains_key(desc.full_name()) {
+            continue;
+        }
+        stack.extend(nested_message_types(&desc));
+        reachable.insert(desc.full_name().to_owned(), desc);
+    }
+
+    let mut required: HashSet<String> = reachable
+        .values()
+        .filter(|desc| {
+            desc.fields()
+                .any(|field_desc| field_desc.cardinality() == Cardinality::Required)
+        })
+        .map(|desc| desc.full_name().to_owned())
+        .collect();
+    loop {
+        let containing: Vec<String> = reachable
+            .values()
+            .filter(|desc| !required.contains(desc.full_name()))
+            .filter(|desc| {
+                nested_message_types(desc).any(|nested| required.contains(nested.full_name()))
+            })
+            .map(|desc| desc.full_name().to_owned())
+            .collect();
+        if containing.is_empty() {
+            return required;
+        }
+        required.extend(containing);
+    }
+}
+
+/// Gets the message types of the fields and extensions of `desc`, including map entry types.
+fn nested_message_types(desc: &MessageDescriptor) -> impl Iterator<Item = MessageDescriptor> + '_ {
+    desc.fields()
+        .map(|field_desc| field_desc.kind())
+        .chain(
+            desc.extensions()
+                .map(|extension_desc| extension_desc.kind()),
+        )
+        .filter_map(|kind| match kind {
+            Kind::Message(message_desc) => Some(message_desc),
+            _ => None,
+        })
+}
+
+fn find_missing_fields<'a>(
+    message: &'a DynamicMessage,
+    required: &HashSet<String>,
+    path: &mut Vec<PathSegment<'a>>,
+    missing_fields: &mut Vec<String>,
+) {
+    for field_desc in message.desc.fields() {
+        if field_desc.cardinality() == Cardinality::Required && !message.has_field(&field_desc) {
+            let mut missing_field = format_path(path);
+            if !missing_field.is_empty() {
+                missing_field.push('.');
+            }
+            missing_field.push_str(field_desc.name());
+            missing_fields.push(missing_field);
+        }
+    }
+
+    for field in message.fields.iter(&message.desc) {
+        let (value, kind, segment) = match field {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                (value, field_desc.kind(), PathSegment::Field(field_desc))
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => (
+                value,
+                extension_desc.kind(),
+                PathSegment::Extension(extension_desc),
+            ),
+            ValueAndDescriptor::Unknown(..) => continue,
+        };
+        match kind {
+            Kind::Message(message_desc) if required.contains(message_desc.full_name()) => (),
+            _ => continue,
+        }
+
+        path.push(segment);
+        match value {
+            Value::Message(message) => find_missing_fields(message, required, path, missing_fields),
+            Value::List(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    if let Value::Message(message) = value {
+                        path.push(PathSegment::Index(index));
+                        find_missing_fields(message, required, path, missing_fields);
+                        path.pop();
+                    }
+                }
+            }
+            Value::Map(values) => {
+                let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+                for (key, value) in entries {
+                    if let Value::Message(message) = value {
+                        path.push(PathSegment::Key(key));
+                        find_missing_fields(message, required, path, missing_fields);
+                        path.pop();
+                    }
+                }
+            }
+            _ => (),
+        }
+        path.pop();
+    }
+}
+
+/// Formats a path in the same format as [`Visit`][crate::Visit].
+fn format_path(path: &[PathSegment<'_>]) -> String {
+    let mut output = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Field(field_desc) => {
+                if !output.is_empty() {
+                    output.push('.');
+                }
+                output.push_str(field_desc.name());
+            }
+            PathSegment::Extension(extension_desc) => {
+                if !output.is_empty() {
+                    output.push('.');
+                }
+                output.push('[');
+                output.push_str(extension_desc.full_name());
+                output.push(']');
+            }
+            PathSegment::Index(index) => output.push_str(&format!("[{}]", index)),
+            PathSegment::Key(key) => output.push_str(&format!("[{}]", map_key_to_string(key))),
+        }
+    }
+    output
+}
+
+impl UninitializedError {
+    /// Gets the paths of the required fields which are not set.
+    pub fn missing_fields(&self) -> impl ExactSizeIterator<Item = &str> + '_ {
+        self.missing_fields.iter().map(String::as_str)
+    }
+}
+
+impl fmt::Display for UninitializedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "message is missing required fields: {}",
+            self.missing_fields.join(", ")
+        )
+    }
+}
+
+impl Error for UninitializedError {}
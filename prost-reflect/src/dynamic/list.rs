@@ -0,0 +1,90 @@
+use crate::{DynamicMessage, FieldDescriptor, Value};
+
+use super::set::{check_element, SetFieldError};
+
+impl DynamicMessage {
+    /// Appends a value to the given repeated field.
+    ///
+    /// Returns an error if the field is not a repeated field, or the value is not a valid element for
+    /// it. See [`try_set_field`][Self::try_set_field] for the checks performed.
+    pub fn push_field_value(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        value: Value,
+    ) -> Result<(), SetFieldError> {
+        check_element(field_desc, &value)?;
+        self.list_mut(field_desc).push(value);
+        Ok(())
+    }
+
+    /// Appends several values to the given repeated field.
+    ///
+    /// All values are checked before any are added, so if an error is returned the field is not
+    /// modified. See [`push_field_value`][Self::push_field_value] for more details.
+    pub fn extend_field<I>(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        values: I,
+    ) -> Result<(), SetFieldError>
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        let values: Vec<Value> = values.into_iter().collect();
+        for value in &values {
+            check_element(field_desc, value)?;
+        }
+        self.list_mut(field_desc).extend(values);
+        Ok(())
+    }
+
+    /// Inserts a value into the given repeated field at position `index`, shifting all elements after
+    /// it to the right.
+    ///
+    /// Returns an error if `index` is greater than the length of the list. See
+    /// [`push_field_value`][Self::push_field_value] for more details.
+    pub fn insert_at(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        index: usize,
+        value: Value,
+    ) -> Result<(), SetFieldError> {
+        check_element(field_desc, &value)?;
+        let len = self.list_len(field_desc);
+        if index > len {
+            return Err(SetFieldError::index_out_of_range(field_desc, index, len));
+        }
+        self.list_mut(field_desc).insert(index, value);
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `index` of the given repeated field, shifting all
+    /// elements after it to the left.
+    ///
+    /// Returns an error if the field is not a repeated field, or if `index` is out of bounds.
+    pub fn remove_at(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        index: usize,
+    ) -> Result<Value, SetFieldError> {
+        if !field_desc.is_list() {
+            return Err(SetFieldError::not_a_list(field_desc));
+        }
+        let len = self.list_len(field_desc);
+        if index >= len {
+            return Err(SetFieldError::index_out_of_range(field_desc, index, len));
+        }
+        Ok(self.list_mut(field_desc).remove(index))
+    }
+
+    fn list_len(&self, field_desc: &FieldDescriptor) -> usize {
+        self.get_field_opt(field_desc)
+            .and_then(Value::as_list)
+            .map_or(0, <[Value]>::len)
+    }
+
+    fn list_mut(&mut self, field_desc: &FieldDescriptor) -> &mut Vec<Value> {
+        self.get_field_mut(field_desc)
+            .as_list_mut()
+            .expect("repeated field should hold a list")
+    }
+}
@@ -0,0 +1,264 @@
+use std::fmt;
+
+use crate::{DynamicMessage, Kind, MapKey, Value};
+
+use super::{
+    fields::ValueAndDescriptor,
+    snapshot::{join_path, map_key_to_string, unknown_fields_to_string, value_to_string},
+};
+
+const REDACTED: &str = "[redacted]";
+
+/// Options to control the output of [`DynamicMessage::log_fields`].
+#[derive(Debug, Clone)]
+pub struct LogOptions {
+    redacted_paths: Vec<String>,
+    max_value_len: usize,
+}
+
+/// A message flattened into key-value pairs for structured logging, returned by
+/// [`DynamicMessage::log_fields`].
+///
+/// The fields can be iterated over, or formatted with [`Display`][fmt::Display] in the
+/// [logfmt](https://brandur.org/logfmt) style, for example `id=5 user.name="Alice" tags.0=admin`. This
+/// is convenient for logging libraries which require field names to be known at compile time, such as
+/// `tracing`:
+///
+/// ```ignore
+/// tracing::info!(request = %message.log_fields(&LogOptions::new()), "handling request");
+/// ```
+///
+/// With the `log` feature enabled, this type also implements `log::kv::Source`, so each field can be
+/// attached to a `log` record as a separate key-value pair.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFields {
+    fields: Vec<(String, String)>,
+}
+
+impl DynamicMessage {
+    /// Flattens this message into key-value pairs suitable for structured logging.
+    ///
+    /// Each scalar value in the message produces one pair, keyed by its path from the root message:
+    ///
+    /// - Nested message fields are joined with `.`, for example `user.name`.
+    /// - Elements of repeated fields are keyed by their index, for example `tags.0`, and entries of map
+    ///   fields by their key, for example `labels.env`. Map entries are ordered by key.
+    /// - Extension fields are named by their full name in brackets, and unknown fields by their number.
+    ///
+    /// Values are formatted as for [`to_snapshot`][Self::to_snapshot], except that strings are not quoted,
+    /// bytes are summarized by their length, and values longer than [`LogOptions::max_value_len`] are
+    /// truncated. Fields which are not set are omitted. Fields matching a path set with
+    /// [`LogOptions::redact`] produce a single pair with the value `[redacted]`.
+    pub fn log_fields(&self, options: &LogOptions) -> LogFields {
+        let mut fields = Vec::new();
+        collect_message(&mut fields, self, options, "", "");
+        LogFields { fields }
+    }
+}
+
+impl LogOptions {
+    /// Creates a new instance of [`LogOptions`], with the default options.
+    pub fn new() -> Self {
+        LogOptions {
+            redacted_paths: Vec::new(),
+            max_value_len: 256,
+        }
+    }
+
+    /// Redacts the value of a field, such as a password or personal information.
+    ///
+    /// The path is a sequence of field names separated by `.`, relative to the root message, as for
+    /// [`SnapshotOptions::redact`][crate::SnapshotOptions::redact]. Paths do not include the indices of
+    /// repeated fields or the keys of map fields, so `"users.email"` redacts the `email` field of every
+    /// element of `users`.
+    pub fn redact(mut self, path: impl Into<String>) -> Self {
+        self.redacted_paths.push(path.into());
+        self
+    }
+
+    /// The maximum length in bytes of a logged value. Longer values are truncated and suffixed with `...`.
+    ///
+    /// The default value is `256`.
+    pub fn max_value_len(mut self, len: usize) -> Self {
+        self.max_value_len = len;
+        self
+    }
+
+    fn is_redacted(&self, path: &str) -> bool {
+        self.redacted_paths.iter().any(|redacted| redacted == path)
+    }
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogFields {
+    /// Gets an iterator over the key-value pairs, in the order they appear in the message.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&str, &str)> + '_ {
+        self.fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Gets the value for a key, if present.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.iter().find(|&(k, _)| k == key).map(|(_, value)| value)
+    }
+
+    /// The number of key-value pairs.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns `true` if there are no key-value pairs.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl fmt::Display for LogFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (key, value)) in self.iter().enumerate() {
+            if index != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}=", key)?;
+            if value.is_empty()
+                || value.contains(|c: char| c == ' ' || c == '"' || c == '=' || c.is_control())
+            {
+                write!(f, "{:?}", value)?;
+            } else {
+                f.write_str(value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "log")]
+#[cfg_attr(docsrs, doc(cfg(feature = "log")))]
+impl log::kv::Source for LogFields {
+    fn visit<'kvs>(
+        &'kvs self,
+        visitor: &mut dyn log::kv::VisitSource<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        for (key, value) in self.iter() {
+            visitor.visit_pair(log::kv::Key::from_str(key), log::kv::Value::from(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the fields of `message`. `path` is the path used for redaction, and `key` is the key prefix,
+/// which also includes list indices and map keys.
+fn collect_message(
+    fields: &mut Vec<(String, String)>,
+    message: &DynamicMessage,
+    options: &LogOptions,
+    path: &str,
+    key: &str,
+) {
+    for field in message.fields.iter(&message.desc) {
+        match field {
+            ValueAndDescriptor::Field(value, field_desc) => collect_field(
+                fields,
+                value,
+                &field_desc.kind(),
+                options,
+                &join_path(path, field_desc.name()),
+                &join_path(key, field_desc.name()),
+            ),
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                let name = format!("[{}]", extension_desc.full_name());
+                collect_field(
+                    fields,
+                    value,
+                    &extension_desc.kind(),
+                    options,
+                    &join_path(path, &name),
+                    &join_path(key, &name),
+                )
+            }
+            ValueAndDescriptor::Unknown(number, unknowns) => {
+                let name = number.to_string();
+                let value = if options.is_redacted(&join_path(path, &name)) {
+                    REDACTED.to_owned()
+                } else {
+                    truncate(unknown_fields_to_string(unknowns), options)
+                };
+                fields.push((join_path(key, &name), value));
+            }
+        }
+    }
+}
+
+fn collect_field(
+    fields: &mut Vec<(String, String)>,
+    value: &Value,
+    kind: &Kind,
+    options: &LogOptions,
+    path: &str,
+    key: &str,
+) {
+    if options.is_redacted(path) {
+        fields.push((key.to_owned(), REDACTED.to_owned()));
+        return;
+    }
+
+    match value {
+        Value::List(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let key = join_path(key, &index.to_string());
+                collect_value(fields, value, kind, options, path, &key);
+            }
+        }
+        Value::Map(values) => {
+            let entry = kind.as_message().expect("map field must be a message");
+            let value_kind = entry.map_entry_value_field().kind();
+
+            let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            for (map_key, value) in entries {
+                let map_key = match map_key {
+                    MapKey::String(map_key) => map_key.clone(),
+                    map_key => map_key_to_string(map_key),
+                };
+                let key = join_path(key, &map_key);
+                collect_value(fields, value, &value_kind, options, path, &key);
+            }
+        }
+        value => collect_value(fields, value, kind, options, path, key),
+    }
+}
+
+fn collect_value(
+    fields: &mut Vec<(String, String)>,
+    value: &Value,
+    kind: &Kind,
+    options: &LogOptions,
+    path: &str,
+    key: &str,
+) {
+    let value = match value {
+        Value::Message(message) => return collect_message(fields, message, options, path, key),
+        Value::String(value) => value.clone(),
+        Value::Bytes(value) => format!("<{} bytes>", value.len()),
+        value => value_to_string(value, kind),
+    };
+    fields.push((key.to_owned(), truncate(value, options)));
+}
+
+fn truncate(mut value: String, options: &LogOptions) -> String {
+    if value.len() > options.max_value_len {
+        let mut len = options.max_value_len;
+        while !value.is_char_boundary(len) {
+            len -= 1;
+        }
+        value.truncate(len);
+        value.push_str("...");
+    }
+    value
+}
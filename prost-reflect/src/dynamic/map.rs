@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use crate::{DynamicMessage, FieldDescriptor, MapKey, Value};
+
+use super::set::{check_map_entry, SetFieldError};
+
+impl DynamicMessage {
+    /// Gets the value of the entry with the given key in a map field.
+    ///
+    /// Returns `None` if the field is not a map field, or it has no entry with the given key.
+    pub fn get_map_value(&self, field_desc: &FieldDescriptor, key: &MapKey) -> Option<&Value> {
+        self.get_field_opt(field_desc)?.as_map()?.get(key)
+    }
+
+    /// Inserts an entry into a map field, returning the previous value for the key if there was one.
+    ///
+    /// Returns an error if the field is not a map field, or the key or value is not valid for it.
+    /// See [`try_set_field`][Self::try_set_field] for the checks performed on the value. If an error
+    /// is returned, the message is not modified.
+    pub fn insert_map_value(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        key: MapKey,
+        value: Value,
+    ) -> Result<Option<Value>, SetFieldError> {
+        check_map_entry(field_desc, &key, Some(&value))?;
+        Ok(self.map_mut(field_desc).insert(key, value))
+    }
+
+    /// Removes the entry with the given key from a map field, returning its value if there was one.
+    ///
+    /// Returns an error if the field is not a map field, or the key type is not valid for it.
+    pub fn remove_map_value(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        key: &MapKey,
+    ) -> Result<Option<Value>, SetFieldError> {
+        check_map_entry(field_desc, key, None)?;
+        if !self.has_field(field_desc) {
+            return Ok(None);
+        }
+        Ok(self.map_mut(field_desc).remove(key))
+    }
+
+    fn map_mut(&mut self, field_desc: &FieldDescriptor) -> &mut HashMap<MapKey, Value> {
+        self.get_field_mut(field_desc)
+            .as_map_mut()
+            .expect("map field should hold a map")
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{DynamicMessage, Value};
+
+use super::fields::{FieldDescriptorLike, ValueAndDescriptor};
+
+impl DynamicMessage {
+    /// Merges the fields of `other` into this message, following the protobuf merge semantics.
+    ///
+    /// This gives the same result as merging the encoded bytes of `other` into this message, without
+    /// going through the byte representation:
+    ///
+    /// - Singular scalar fields which are set in `other` overwrite the value in this message.
+    /// - Singular message fields are merged recursively.
+    /// - Repeated fields are concatenated.
+    /// - Map entries in `other` are inserted, replacing any entries with the same key.
+    /// - Unknown fields of `other` are appended. If a field is stored as unknown fields in either message,
+    ///   for example a `proto2` string containing invalid UTF-8, it remains so in the merged message.
+    ///
+    /// Setting a field of a oneof clears the other fields of the oneof, as usual.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `other` does not have the same message type as this message.
+    pub fn merge_from(&mut self, other: &DynamicMessage) {
+        assert_eq!(
+            self.desc, other.desc,
+            "cannot merge messages of different types"
+        );
+
+        for field in other.fields.iter(&other.desc) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    self.merge_value(&field_desc, value)
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    self.merge_value(&extension_desc, value)
+                }
+                ValueAndDescriptor::Unknown(number, unknowns) => {
+                    // Unknown fields with a known number hold values preserved when decoding, so any
+                    // existing value must be preserved along with them to keep the field order.
+                    self.make_unknown(number);
+                    for unknown in unknowns {
+                        self.fields.add_unknown(number, unknown.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    fn merge_value(&mut self, desc: &impl FieldDescriptorLike, value: &Value) {
+        if self.fields.is_unknown(desc.number()) {
+            self.fields.add_unknown_value(desc, value);
+            return;
+        }
+
+        match (self.fields.get_mut(desc), value) {
+            (Value::List(list), Value::List(other)) => list.extend(other.iter().cloned()),
+            (Value::Map(map), Value::Map(other)) => map.extend(
+                other
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            ),
+            (Value::Message(message), Value::Message(other)) => message.merge_from(other),
+            (existing, _) => *existing = value.clone(),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::{borrow::Cow, str};
+
 use prost::{
     bytes::{Buf, BufMut},
     encoding::{DecodeContext, WireType},
@@ -10,8 +12,8 @@ use crate::{
 };
 
 use super::{
+    decode::{DecodeOptions, WithDecodeOptions},
     fields::{FieldDescriptorLike, ValueAndDescriptor},
-    unknown::UnknownField,
 };
 
 impl Message for DynamicMessage {
@@ -48,45 +50,37 @@ impl Message for DynamicMessage {
         B: Buf,
         Self: Sized,
     {
-        if let Some(field_desc) = self.desc.get_field(number) {
-            self.get_field_mut(&field_desc)
-                .merge_field(&field_desc, wire_type, buf, ctx)
-        } else if let Some(extension_desc) = self.desc.get_extension(number) {
-            self.get_extension_mut(&extension_desc).merge_field(
-                &extension_desc,
-                wire_type,
-                buf,
-                ctx,
-            )
-        } else {
-            let field = UnknownField::decode(number, wire_type, buf, ctx)?;
-            self.fields.add_unknown(number, field);
-            Ok(())
-        }
+        self.merge_field_with_options(number, wire_type, buf, ctx, &DecodeOptions::new())
     }
 
     fn encoded_len(&self) -> usize {
-        let mut len = 0;
-        for field in self.fields.iter(&self.desc) {
-            match field {
-                ValueAndDescriptor::Field(value, field_desc) => {
-                    len += value.encoded_len(&field_desc);
-                }
-                ValueAndDescriptor::Extension(value, extension_desc) => {
-                    len += value.encoded_len(&extension_desc);
-                }
-                ValueAndDescriptor::Unknown(number, unknowns) => {
-                    for unknown in unknowns {
-                        len += unknown.encoded_len(number)
+        self.fields.encoded_len(|| {
+            let mut len = 0;
+            for field in self.fields.iter(&self.desc) {
+                match field {
+                    ValueAndDescriptor::Field(value, field_desc) => {
+                        len += value.encoded_len(&field_desc);
+                    }
+                    ValueAndDescriptor::Extension(value, extension_desc) => {
+                        len += value.encoded_len(&extension_desc);
+                    }
+                    ValueAndDescriptor::Unknown(number, unknowns) => {
+                        for unknown in unknowns {
+                            len += unknown.encoded_len(number)
+                        }
                     }
                 }
             }
-        }
-        len
+            len
+        })
     }
 
     fn clear(&mut self) {
         self.fields.clear_all();
+        #[cfg(feature = "serde")]
+        {
+            self.unknown_json_fields = None;
+        }
     }
 }
 
@@ -286,6 +280,7 @@ impl Value {
         wire_type: WireType,
         buf: &mut B,
         ctx: DecodeContext,
+        options: &DecodeOptions,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
@@ -330,9 +325,7 @@ impl Value {
             (Value::F64(value), Kind::Double) => {
                 prost::encoding::double::merge(wire_type, value, buf, ctx)
             }
-            (Value::String(value), Kind::String) => {
-                prost::encoding::string::merge(wire_type, value, buf, ctx)
-            }
+            (Value::String(value), Kind::String) => merge_string(wire_type, value, buf, options),
             (Value::Bytes(value), Kind::Bytes) => {
                 prost::encoding::bytes::merge(wire_type, value, buf, ctx)
             }
@@ -340,23 +333,30 @@ impl Value {
                 prost::encoding::int32::merge(wire_type, value, buf, ctx)
             }
             (Value::Message(message), Kind::Message(_)) => {
+                let mut message = WithDecodeOptions { message, options };
                 if field_desc.is_group() {
-                    prost::encoding::group::merge(field_desc.number(), wire_type, message, buf, ctx)
+                    prost::encoding::group::merge(
+                        field_desc.number(),
+                        wire_type,
+                        &mut message,
+                        buf,
+                        ctx,
+                    )
                 } else {
-                    prost::encoding::message::merge(wire_type, message, buf, ctx)
+                    prost::encoding::message::merge(wire_type, &mut message, buf, ctx)
                 }
             }
             (Value::List(values), field_kind) if field_desc.is_list() => {
                 if wire_type == WireType::LengthDelimited && field_desc.is_packable() {
                     prost::encoding::merge_loop(values, buf, ctx, |values, buf, ctx| {
                         let mut value = Value::default_value(&field_kind);
-                        value.merge_field(field_desc, field_kind.wire_type(), buf, ctx)?;
+                        value.merge_field(field_desc, field_kind.wire_type(), buf, ctx, options)?;
                         values.push(value);
                         Ok(())
                     })
                 } else {
                     let mut value = Value::default_value(&field_kind);
-                    value.merge_field(field_desc, wire_type, buf, ctx)?;
+                    value.merge_field(field_desc, wire_type, buf, ctx, options)?;
                     values.push(value);
                     Ok(())
                 }
@@ -374,9 +374,11 @@ impl Value {
                     |(key, value), buf, ctx| {
                         let (number, wire_type) = prost::encoding::decode_key(buf)?;
                         match number {
-                            MAP_ENTRY_KEY_NUMBER => key.merge_field(&key_desc, wire_type, buf, ctx),
+                            MAP_ENTRY_KEY_NUMBER => {
+                                key.merge_field(&key_desc, wire_type, buf, ctx, options)
+                            }
                             MAP_ENTRY_VALUE_NUMBER => {
-                                value.merge_field(&value_desc, wire_type, buf, ctx)
+                                value.merge_field(&value_desc, wire_type, buf, ctx, options)
                             }
                             _ => prost::encoding::skip_field(wire_type, number, buf, ctx),
                         }
@@ -554,7 +556,7 @@ impl Value {
 }
 
 impl MapKey {
-    fn encode_field<B>(&self, field_desc: &FieldDescriptor, buf: &mut B)
+    pub(super) fn encode_field<B>(&self, field_desc: &FieldDescriptor, buf: &mut B)
     where
         B: BufMut,
     {
@@ -601,12 +603,13 @@ impl MapKey {
         }
     }
 
-    fn merge_field<B>(
+    pub(super) fn merge_field<B>(
         &mut self,
         field_desc: &FieldDescriptor,
         wire_type: WireType,
         buf: &mut B,
         ctx: DecodeContext,
+        options: &DecodeOptions,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
@@ -645,9 +648,7 @@ impl MapKey {
             (MapKey::U64(value), Kind::Fixed64) => {
                 prost::encoding::fixed64::merge(wire_type, value, buf, ctx)
             }
-            (MapKey::String(value), Kind::String) => {
-                prost::encoding::string::merge(wire_type, value, buf, ctx)
-            }
+            (MapKey::String(value), Kind::String) => merge_string(wire_type, value, buf, options),
             (value, ty) => panic!(
                 "mismatch between DynamicMessage value {:?} and type {:?}",
                 value, ty
@@ -655,7 +656,7 @@ impl MapKey {
         }
     }
 
-    fn encoded_len(&self, field_desc: &FieldDescriptor) -> usize {
+    pub(super) fn encoded_len(&self, field_desc: &FieldDescriptor) -> usize {
         if !field_desc.supports_presence() && self.is_default(&field_desc.kind()) {
             return 0;
         }
@@ -700,6 +701,53 @@ impl MapKey {
     }
 }
 
+fn merge_string<B>(
+    wire_type: WireType,
+    value: &mut String,
+    buf: &mut B,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError>
+where
+    B: Buf,
+{
+    prost::encoding::check_wire_type(WireType::LengthDelimited, wire_type)?;
+    let len = prost::encoding::decode_varint(buf)?;
+    if len > buf.remaining() as u64 {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    let len = len as usize;
+
+    if buf.chunk().len() >= len {
+        // The payload is contiguous, as it always is for slices and `Bytes`, so it can be validated in
+        // place and copied once into the existing allocation of the string.
+        let string = utf8_payload(&buf.chunk()[..len], options)?;
+        value.clear();
+        value.push_str(&string);
+        buf.advance(len);
+    } else {
+        let mut payload = Vec::with_capacity(len);
+        payload.put(buf.take(len));
+        *value = match String::from_utf8(payload) {
+            Ok(string) => string,
+            Err(err) => utf8_payload(err.as_bytes(), options)?.into_owned(),
+        };
+    }
+    Ok(())
+}
+
+fn utf8_payload<'a>(
+    payload: &'a [u8],
+    options: &DecodeOptions,
+) -> Result<Cow<'a, str>, DecodeError> {
+    match str::from_utf8(payload) {
+        Ok(string) => Ok(Cow::Borrowed(string)),
+        Err(_) if options.replace_invalid_utf8() => Ok(String::from_utf8_lossy(payload)),
+        Err(_) => Err(DecodeError::new(
+            "invalid string value: data is not UTF-8 encoded",
+        )),
+    }
+}
+
 fn encode_packed_list<T, I, B, E, L>(number: u32, iter: I, buf: &mut B, encode: E, encoded_len: L)
 where
     I: IntoIterator<Item = T> + Clone,
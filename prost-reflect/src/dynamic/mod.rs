@@ -1,13 +1,85 @@
+mod any;
+#[cfg(feature = "proptest")]
+mod arbitrary;
+mod assign;
+mod bind;
+mod cmp;
+mod convert;
+mod datetime;
+mod debug;
+mod decode;
+#[cfg(feature = "tokio")]
+mod decode_async;
+pub(crate) mod delimited;
+mod deterministic;
+mod diff;
+mod example;
+mod explain;
 mod fields;
+mod generate;
+mod hash;
+mod index;
+mod initialized;
+mod list;
+mod logging;
+mod map;
+mod merge;
 mod message;
+mod mutate;
+mod profile;
+mod rng;
 #[cfg(feature = "serde")]
 mod serde;
+mod set;
+mod snapshot;
+mod stream;
+#[cfg(feature = "text-format")]
+mod text_format;
+mod typed;
 mod unknown;
+#[cfg(feature = "arbitrary")]
+mod unstructured;
+#[cfg(feature = "validate")]
+mod validate;
+mod verify;
+mod visit;
 
 use std::{borrow::Cow, collections::HashMap};
 
+pub use self::any::UnpackAnyError;
+pub(crate) use self::any::{pack_any, unpack_any};
+#[cfg(feature = "proptest")]
+pub use self::arbitrary::ArbitraryOptions;
+pub use self::assign::AssignError;
+pub use self::bind::{BindError, BindOptions};
+pub use self::convert::TryFromValueError;
+pub use self::datetime::TimeConversionError;
+pub use self::decode::{DecodeOptions, EncodeOptions, Utf8Validation};
+pub use self::delimited::LengthDelimitedReader;
+pub use self::diff::{CompareOptions, Difference, MessageDiff};
+pub use self::example::FillOptions;
+pub use self::explain::{ExplainedField, Explanation};
+pub use self::generate::GenerateOptions;
+pub use self::initialized::UninitializedError;
+pub use self::logging::{LogFields, LogOptions};
+pub use self::mutate::Mutator;
+pub use self::profile::{CorpusProfiler, FieldStatistics};
 #[cfg(feature = "serde")]
-pub use self::serde::{DeserializeOptions, SerializeOptions};
+pub use self::serde::{
+    BytesEncoding, DeserializeOptions, DurationEncoding, NonFiniteFloatEncoding, SerializeOptions,
+    TimestampEncoding, UnknownJsonField,
+};
+#[cfg(feature = "serde-json")]
+pub use self::serde::{JsonDeserializeHandler, JsonSerializeHandler};
+pub use self::set::SetFieldError;
+pub use self::snapshot::SnapshotOptions;
+#[cfg(feature = "text-format")]
+pub use self::text_format::{FormatOptions, ParseError};
+pub use self::unknown::UnknownField;
+#[cfg(feature = "validate")]
+pub use self::validate::{ValidationError, Validator, Violation};
+pub use self::verify::VerifyError;
+pub use self::visit::Visit;
 
 use prost::{
     bytes::{Buf, Bytes},
@@ -23,17 +95,32 @@ use crate::{
 ///
 /// It wraps a [`MessageDescriptor`] and the [`Value`] for each field of the message, and implements
 /// [`Message`][`prost::Message`].
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Cloning a [`DynamicMessage`] does not copy its fields, since they are shared between the clones. When one of
+/// the clones is modified, the fields of each message along the path to the modified value are copied. Nested
+/// messages which are not modified remain shared, but strings, lists and maps are copied.
+///
+/// The alternate [`Debug`](std::fmt::Debug) format (`{:#?}`) prints the message using the field names and
+/// enum value names from its descriptor.
+#[derive(Clone, PartialEq)]
 pub struct DynamicMessage {
     desc: MessageDescriptor,
     fields: DynamicMessageFieldSet,
+    // Boxed since unknown JSON fields are rare, and messages are often stored inline in a `Value`.
+    #[cfg(feature = "serde")]
+    #[allow(clippy::box_collection)]
+    unknown_json_fields: Option<Box<Vec<UnknownJsonField>>>,
 }
 
 /// A dynamically-typed protobuf value.
 ///
 /// Note this type may map to multiple possible protobuf wire formats, so it must be
 /// serialized as part of a DynamicMessage.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Floating point values are compared by value, except that all NaN values are equal to each other, so
+/// that equality is reflexive and values can be used as keys in a [`HashMap`] or
+/// [`HashSet`](std::collections::HashSet).
+#[derive(Debug, Clone)]
 pub enum Value {
     /// A boolean value, encoded as the `bool` protobuf type.
     Bool(bool),
@@ -86,11 +173,17 @@ impl DynamicMessage {
         DynamicMessage {
             fields: DynamicMessageFieldSet::default(),
             desc,
+            #[cfg(feature = "serde")]
+            unknown_json_fields: None,
         }
     }
 
     /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from the buffer and merges it into a
     /// new instance of [`DynamicMessage`].
+    ///
+    /// If `buf` is a [`Bytes`], the values of `bytes` fields and unknown fields are slices of it rather than
+    /// copies, which makes decoding messages containing large blobs much cheaper. Since [`Value::String`]
+    /// owns its contents, `string` fields are still copied, once, after being validated.
     pub fn decode<B>(desc: MessageDescriptor, buf: B) -> Result<Self, DecodeError>
     where
         B: Buf,
@@ -117,6 +210,14 @@ impl DynamicMessage {
         self.fields.get(field_desc)
     }
 
+    /// Gets a reference to the value of the given field, or `None` if it is unset.
+    ///
+    /// A field is unset if [`has_field`][Self::has_field] returns `false` for it. Unlike
+    /// [`get_field`][Self::get_field], this method never constructs a default value.
+    pub fn get_field_opt(&self, field_desc: &FieldDescriptor) -> Option<&Value> {
+        self.fields.get_opt(field_desc)
+    }
+
     /// Gets a mutable reference to the value ofthe given field. If the field is not set,
     /// it is inserted with its default value.
     pub fn get_field_mut(&mut self, field_desc: &FieldDescriptor) -> &mut Value {
@@ -128,7 +229,8 @@ impl DynamicMessage {
     /// # Panics
     ///
     /// This method may panic if the value type is not compatible with the field type, as defined
-    /// by [`Value::is_valid_for_field`].
+    /// by [`Value::is_valid_for_field`]. Use [`try_set_field`][Self::try_set_field] to check the
+    /// value and get an error instead.
     pub fn set_field(&mut self, field_desc: &FieldDescriptor, value: Value) {
         self.fields.set(field_desc, value);
     }
@@ -285,6 +387,70 @@ impl DynamicMessage {
         self.fields.clear(extension_desc)
     }
 
+    /// Gets an iterator over the unknown fields of this message, ordered by field number.
+    ///
+    /// Unknown fields are fields found when decoding whose number does not match any field or
+    /// extension of the message type. They are preserved when the message is re-encoded.
+    pub fn unknown_fields(&self) -> impl Iterator<Item = UnknownField> + '_ {
+        self.fields
+            .iter_unknown()
+            .map(|(number, value)| UnknownField::from_value(number, value))
+    }
+
+    /// Gets an iterator over the unknown fields of this message with the given field number.
+    pub fn get_unknown_fields(&self, number: u32) -> impl Iterator<Item = UnknownField> + '_ {
+        self.unknown_fields()
+            .filter(move |field| field.number() == number)
+    }
+
+    /// Adds an unknown field to this message, after any existing unknown fields with the same number.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the field number matches a field or extension of the message type.
+    pub fn add_unknown_field(&mut self, field: UnknownField) {
+        assert!(
+            self.desc.get_field(field.number()).is_none()
+                && self.desc.get_extension(field.number()).is_none(),
+            "field number {} is not unknown for message '{}'",
+            field.number(),
+            self.desc.full_name()
+        );
+
+        let value = field
+            .to_value()
+            .expect("unknown field value was validated on construction");
+        self.fields.add_unknown(field.number(), value);
+    }
+
+    /// Replaces the value of the field or extension with the given number, if it has one, with its
+    /// encoding as unknown fields.
+    fn make_unknown(&mut self, number: u32) {
+        if let Some(field_desc) = self.desc.get_field(number) {
+            self.fields.make_unknown(&field_desc);
+        } else if let Some(extension_desc) = self.desc.get_extension(number) {
+            self.fields.make_unknown(&extension_desc);
+        }
+    }
+
+    /// Removes all unknown fields from this message and returns them, ordered by field number.
+    ///
+    /// Values of known fields which could not be decoded and were preserved as unknown fields, such as
+    /// `proto2` strings containing invalid UTF-8, are not removed, so the returned fields can always be
+    /// added back with [`add_unknown_field`][Self::add_unknown_field].
+    pub fn take_unknown_fields(&mut self) -> Vec<UnknownField> {
+        self.fields
+            .take_unknown(&self.desc)
+            .into_iter()
+            .map(|(number, value)| UnknownField::from_value(number, &value))
+            .collect()
+    }
+
+    /// Removes all unknown fields from this message.
+    pub fn clear_unknown_fields(&mut self) {
+        self.fields.clear_unknown()
+    }
+
     /// Merge a strongly-typed message into this one.
     ///
     /// The message should be compatible with the type specified by
@@ -816,6 +982,9 @@ impl From<MapKey> for Value {
 
 #[test]
 fn type_sizes() {
+    #[cfg(not(feature = "serde"))]
+    assert_eq!(std::mem::size_of::<DynamicMessage>(), 32);
+    #[cfg(feature = "serde")]
     assert_eq!(std::mem::size_of::<DynamicMessage>(), 40);
     assert_eq!(std::mem::size_of::<Value>(), 56);
 }
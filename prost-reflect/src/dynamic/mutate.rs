@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+
+use prost::bytes::Bytes;
+
+use crate::{DynamicMessage, FieldDescriptor, Kind, MapKey, Value};
+
+use super::{rng::Rng, unknown::UnknownFieldValue};
+
+/// The maximum field number allowed by the protobuf specification.
+const MAX_FIELD_NUMBER: u32 = 536_870_911;
+/// Field numbers reserved for the protobuf implementation.
+const RESERVED_FIELD_NUMBERS: std::ops::Range<u32> = 19_000..20_000;
+
+/// Applies random, schema-aware mutations to a [`DynamicMessage`].
+///
+/// This is intended for coverage-guided fuzzing of code which consumes protobuf messages, in the style of
+/// [libprotobuf-mutator](https://github.com/google/libprotobuf-mutator). Rather than flipping bits in the
+/// encoded bytes, which mostly produces input that fails to decode, the mutator makes small structural changes
+/// to a decoded message, such as:
+///
+/// - replacing numeric values with boundary values like `0`, `-1`, `i32::MIN` or `NaN`.
+/// - setting enum fields to another declared value, or to a number not declared by the enum.
+/// - clearing fields, including required fields.
+/// - adding, removing or mutating elements of repeated and map fields.
+/// - injecting unknown fields.
+///
+/// The mutator is deterministic: two instances created with the same seed will apply the same sequence of
+/// mutations to equal messages.
+///
+/// # Examples
+///
+/// ```
+/// # use prost::Message;
+/// # use prost_reflect::{DynamicMessage, DescriptorPool, Mutator};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let mut message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+/// let mut mutator = Mutator::new(42);
+/// mutator.mutate(&mut message);
+/// let bytes = message.encode_to_vec();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mutator {
+    rng: Rng,
+}
+
+impl Mutator {
+    /// Creates a new [`Mutator`], using `seed` to initialize its random number generator.
+    pub fn new(seed: u64) -> Self {
+        Mutator {
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Applies a single random mutation to `message` or one of its nested messages.
+    ///
+    /// The resulting message can always be encoded, but may no longer be valid for its schema (for example,
+    /// if a required field was cleared).
+    pub fn mutate(&mut self, message: &mut DynamicMessage) {
+        // Descend into a set nested message with probability 1/2 at each level.
+        if self.rng.chance(2) {
+            if let Some(nested) = self.choose_nested_message(message) {
+                self.mutate(nested);
+                return;
+            }
+        }
+
+        match self.rng.below(4) {
+            0 => self.clear_field(message),
+            1 => self.add_unknown_field(message),
+            _ => self.mutate_field(message),
+        }
+    }
+
+    fn choose_nested_message<'a>(
+        &mut self,
+        message: &'a mut DynamicMessage,
+    ) -> Option<&'a mut DynamicMessage> {
+        let fields: Vec<FieldDescriptor> = message
+            .desc
+            .fields()
+            .filter(|field| field.kind().as_message().is_some() && message.has_field(field))
+            .collect();
+        if fields.is_empty() {
+            return None;
+        }
+
+        let field = &fields[self.rng.below(fields.len())];
+        match message.get_field_mut(field) {
+            Value::Message(nested) => Some(nested),
+            Value::List(values) => {
+                let index = self.rng.below(values.len());
+                values[index].as_message_mut()
+            }
+            Value::Map(values) => {
+                let key = self.choose_map_key(values);
+                values.get_mut(&key).and_then(Value::as_message_mut)
+            }
+            _ => None,
+        }
+    }
+
+    fn clear_field(&mut self, message: &mut DynamicMessage) {
+        let fields: Vec<FieldDescriptor> = message
+            .desc
+            .fields()
+            .filter(|field| message.has_field(field))
+            .collect();
+        if fields.is_empty() {
+            return self.mutate_field(message);
+        }
+
+        let field = &fields[self.rng.below(fields.len())];
+        message.clear_field(field);
+    }
+
+    fn add_unknown_field(&mut self, message: &mut DynamicMessage) {
+        let number = loop {
+            let number = match self.rng.below(2) {
+                0 => message.desc.fields().map(|f| f.number()).max().unwrap_or(0) + 1,
+                _ => 1 + self.rng.below(MAX_FIELD_NUMBER as usize) as u32,
+            };
+            if number <= MAX_FIELD_NUMBER
+                && !RESERVED_FIELD_NUMBERS.contains(&number)
+                && message.desc.get_field(number).is_none()
+                && message.desc.get_extension(number).is_none()
+            {
+                break number;
+            }
+        };
+
+        let field = match self.rng.below(4) {
+            0 => UnknownFieldValue::Varint(self.boundary_u64()),
+            1 => UnknownFieldValue::SixtyFourBit(self.rng.next_u64().to_le_bytes()),
+            2 => UnknownFieldValue::LengthDelimited(self.bytes()),
+            _ => UnknownFieldValue::ThirtyTwoBit((self.rng.next_u64() as u32).to_le_bytes()),
+        };
+        message.fields.add_unknown(number, field);
+    }
+
+    fn mutate_field(&mut self, message: &mut DynamicMessage) {
+        let len = message.desc.fields().len();
+        if len == 0 {
+            return self.add_unknown_field(message);
+        }
+
+        let field = message.desc.fields().nth(self.rng.below(len)).unwrap();
+        let kind = field.kind();
+        if field.is_map() {
+            let entry = kind.as_message().unwrap();
+            let key_kind = entry.map_entry_key_field().kind();
+            let value_kind = entry.map_entry_value_field().kind();
+            let values = match message.get_field_mut(&field) {
+                Value::Map(values) => values,
+                _ => unreachable!(),
+            };
+
+            if values.is_empty() || self.rng.chance(3) {
+                let key = self.map_key(&key_kind);
+                let value = self.new_value(&value_kind);
+                values.insert(key, value);
+            } else if self.rng.chance(2) {
+                let key = self.choose_map_key(values);
+                values.remove(&key);
+            } else {
+                let key = self.choose_map_key(values);
+                self.mutate_value(&value_kind, values.get_mut(&key).unwrap());
+            }
+        } else if field.is_list() {
+            let values = match message.get_field_mut(&field) {
+                Value::List(values) => values,
+                _ => unreachable!(),
+            };
+
+            if values.is_empty() || self.rng.chance(3) {
+                let index = self.rng.below(values.len() + 1);
+                let value = self.new_value(&kind);
+                values.insert(index, value);
+            } else if self.rng.chance(2) {
+                let index = self.rng.below(values.len());
+                values.remove(index);
+            } else {
+                let index = self.rng.below(values.len());
+                self.mutate_value(&kind, &mut values[index]);
+            }
+        } else {
+            let value = message.get_field_mut(&field);
+            self.mutate_value(&kind, value);
+        }
+    }
+
+    /// Chooses a key from a non-empty map. Keys are sorted first, since the iteration order of `HashMap`
+    /// is not deterministic.
+    fn choose_map_key(&mut self, values: &HashMap<MapKey, Value>) -> MapKey {
+        let mut keys: Vec<&MapKey> = values.keys().collect();
+        keys.sort();
+        keys[self.rng.below(keys.len())].clone()
+    }
+
+    fn mutate_value(&mut self, kind: &Kind, value: &mut Value) {
+        match value {
+            Value::Bool(value) => *value = !*value,
+            Value::I32(value) => {
+                *value = self.mutate_i64(*value as i64, i32::MIN as i64, i32::MAX as i64) as i32
+            }
+            Value::I64(value) => *value = self.mutate_i64(*value, i64::MIN, i64::MAX),
+            Value::U32(value) => *value = self.mutate_u64(*value as u64, u32::MAX as u64) as u32,
+            Value::U64(value) => *value = self.mutate_u64(*value, u64::MAX),
+            Value::F32(value) => *value = self.mutate_f64(*value as f64) as f32,
+            Value::F64(value) => *value = self.mutate_f64(*value),
+            Value::String(value) => self.mutate_string(value),
+            Value::Bytes(value) => *value = self.mutate_bytes(value),
+            Value::EnumNumber(value) => {
+                *value = self.enum_number(kind, Some(*value));
+            }
+            Value::Message(message) => self.mutate(message),
+            Value::List(_) | Value::Map(_) => {
+                unreachable!("nested lists and maps are not supported")
+            }
+        }
+    }
+
+    fn new_value(&mut self, kind: &Kind) -> Value {
+        let mut value = Value::default_value(kind);
+        match &mut value {
+            Value::EnumNumber(number) => *number = self.enum_number(kind, None),
+            Value::Message(_) => (),
+            value => self.mutate_value(kind, value),
+        }
+        value
+    }
+
+    fn map_key(&mut self, kind: &Kind) -> MapKey {
+        match kind {
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                MapKey::I32(self.mutate_i64(0, i32::MIN as i64, i32::MAX as i64) as i32)
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                MapKey::I64(self.mutate_i64(0, i64::MIN, i64::MAX))
+            }
+            Kind::Uint32 | Kind::Fixed32 => MapKey::U32(self.mutate_u64(0, u32::MAX as u64) as u32),
+            Kind::Uint64 | Kind::Fixed64 => MapKey::U64(self.mutate_u64(0, u64::MAX)),
+            Kind::Bool => MapKey::Bool(self.rng.chance(2)),
+            Kind::String => {
+                let mut key = String::new();
+                self.mutate_string(&mut key);
+                MapKey::String(key)
+            }
+            _ => unreachable!("invalid type for map key"),
+        }
+    }
+
+    fn enum_number(&mut self, kind: &Kind, current: Option<i32>) -> i32 {
+        let enum_desc = kind.as_enum().expect("expected enum type");
+        let values: Vec<i32> = enum_desc
+            .values()
+            .map(|value| value.number())
+            .filter(|&number| Some(number) != current)
+            .collect();
+
+        if values.is_empty() || self.rng.chance(4) {
+            // Pick a number which is not declared by the enum.
+            let max = enum_desc
+                .values()
+                .map(|value| value.number())
+                .max()
+                .unwrap_or(0);
+            let min = enum_desc
+                .values()
+                .map(|value| value.number())
+                .min()
+                .unwrap_or(0);
+            let candidates = [max.wrapping_add(1), min.wrapping_sub(1), i32::MAX, i32::MIN];
+            let number = candidates[self.rng.below(candidates.len())];
+            if enum_desc.get_value(number).is_none() {
+                return number;
+            }
+            return max.wrapping_add(1);
+        }
+
+        values[self.rng.below(values.len())]
+    }
+
+    fn mutate_i64(&mut self, value: i64, min: i64, max: i64) -> i64 {
+        match self.rng.below(4) {
+            0 => [0, 1, -1, min, max, min + 1, max - 1][self.rng.below(7)].clamp(min, max),
+            1 => value.wrapping_add(1).clamp(min, max),
+            2 => value.wrapping_sub(1).clamp(min, max),
+            _ => (value ^ (1 << self.rng.below(64))).clamp(min, max),
+        }
+    }
+
+    fn mutate_u64(&mut self, value: u64, max: u64) -> u64 {
+        match self.rng.below(4) {
+            0 => [0, 1, max, max - 1, max / 2 + 1][self.rng.below(5)],
+            1 => value.wrapping_add(1).min(max),
+            2 => value.wrapping_sub(1).min(max),
+            _ => (value ^ (1 << self.rng.below(64))).min(max),
+        }
+    }
+
+    fn mutate_f64(&mut self, value: f64) -> f64 {
+        const BOUNDARIES: [f64; 11] = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.0,
+            f64::MIN,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::EPSILON,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NAN,
+        ];
+
+        match self.rng.below(3) {
+            0 => BOUNDARIES[self.rng.below(BOUNDARIES.len())],
+            1 => -value,
+            _ => f64::from_bits(value.to_bits() ^ (1 << self.rng.below(64))),
+        }
+    }
+
+    fn mutate_string(&mut self, value: &mut String) {
+        match self.rng.below(4) {
+            0 => value.clear(),
+            1 => {
+                const CHARS: [char; 8] = ['a', '0', ' ', '\0', '\u{7f}', 'é', '\u{fffd}', '😀'];
+                value.push(CHARS[self.rng.below(CHARS.len())]);
+            }
+            2 => {
+                if let Some(index) = value.char_indices().map(|(i, _)| i).next_back() {
+                    value.truncate(index);
+                }
+            }
+            _ => value.push_str(&value.clone()),
+        }
+    }
+
+    fn mutate_bytes(&mut self, value: &Bytes) -> Bytes {
+        let mut value = value.to_vec();
+        match self.rng.below(3) {
+            0 => value.clear(),
+            1 => value.push(self.rng.next_u64() as u8),
+            _ => {
+                if value.is_empty() {
+                    value.push(0);
+                } else {
+                    let index = self.rng.below(value.len());
+                    value[index] ^= 1 << self.rng.below(8);
+                }
+            }
+        }
+        Bytes::from(value)
+    }
+
+    fn bytes(&mut self) -> Bytes {
+        let len = self.rng.below(16);
+        (0..len).map(|_| self.rng.next_u64() as u8).collect()
+    }
+
+    fn boundary_u64(&mut self) -> u64 {
+        [0, 1, u32::MAX as u64, u64::MAX, self.rng.next_u64()][self.rng.below(5)]
+    }
+}
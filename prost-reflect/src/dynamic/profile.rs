@@ -0,0 +1,293 @@
+use std::collections::{BTreeMap, HashSet};
+
+use prost::{bytes::Buf, DecodeError, Message};
+
+use crate::{DynamicMessage, MessageDescriptor, Value};
+
+use super::{
+    fields::{FieldDescriptorLike, ValueAndDescriptor},
+    snapshot::{join_path, map_key_to_string, unknown_fields_to_string, value_to_string},
+};
+
+/// Collects per-field statistics over a corpus of messages of the same type.
+///
+/// This is useful for finding fields which are never set, or which account for a large share of the
+/// encoded size, before cleaning up a schema.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{CorpusProfiler, DynamicMessage, DescriptorPool, Value};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let mut profiler = CorpusProfiler::new(message_descriptor);
+/// profiler.add(b"\x08\x96\x01".as_ref()).unwrap();
+/// profiler.add(b"".as_ref()).unwrap();
+///
+/// let foo = profiler.fields().find(|field| field.path() == "foo").unwrap();
+/// assert_eq!(foo.fill_rate(), 0.5);
+/// assert_eq!(foo.encoded_len(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CorpusProfiler {
+    desc: MessageDescriptor,
+    max_distinct_values: usize,
+    encoded_len: u64,
+    message_counts: BTreeMap<String, u64>,
+    fields: BTreeMap<String, FieldCounts>,
+}
+
+/// Statistics for a single field, returned by [`CorpusProfiler::fields`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldStatistics {
+    path: String,
+    message_count: u64,
+    present_count: u64,
+    value_count: u64,
+    encoded_len: u64,
+    distinct_values: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct FieldCounts {
+    parent_path: String,
+    present_count: u64,
+    value_count: u64,
+    encoded_len: u64,
+    // Set to None once the number of distinct values exceeds the limit.
+    distinct_values: Option<HashSet<String>>,
+}
+
+impl CorpusProfiler {
+    /// Creates a new profiler for messages of the type described by `desc`.
+    pub fn new(desc: MessageDescriptor) -> Self {
+        CorpusProfiler {
+            desc,
+            max_distinct_values: 1000,
+            encoded_len: 0,
+            message_counts: BTreeMap::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// The maximum number of distinct values to track for each field. Once a field has more distinct
+    /// values than this, [`FieldStatistics::distinct_values`] returns `None` for it.
+    ///
+    /// The default value is `1000`.
+    pub fn max_distinct_values(mut self, max: usize) -> Self {
+        self.max_distinct_values = max;
+        self
+    }
+
+    /// Decodes a message from `buf` and adds it to the corpus.
+    pub fn add<B>(&mut self, buf: B) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        let message = DynamicMessage::decode(self.desc.clone(), buf)?;
+        self.add_message(&message);
+        Ok(())
+    }
+
+    /// Adds a decoded message to the corpus.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message's type does not match the descriptor this profiler was created with.
+    pub fn add_message(&mut self, message: &DynamicMessage) {
+        assert_eq!(
+            message.desc, self.desc,
+            "message type does not match the profiled type"
+        );
+        self.encoded_len += message.encoded_len() as u64;
+        self.visit_message(message, "");
+    }
+
+    /// The number of messages added to the corpus.
+    pub fn message_count(&self) -> u64 {
+        self.message_counts.get("").copied().unwrap_or(0)
+    }
+
+    /// The total encoded size in bytes of all messages added to the corpus.
+    pub fn encoded_len(&self) -> u64 {
+        self.encoded_len
+    }
+
+    /// Gets an iterator over the statistics for every field seen in the corpus, ordered by path.
+    ///
+    /// This includes all fields declared in the root message type and in every nested message which
+    /// appeared in the corpus, even if they were never set, as well as any extensions and unknown fields
+    /// which were present. Paths are formatted as for [`SnapshotOptions::redact`][crate::SnapshotOptions::redact],
+    /// with unknown fields named by their field number.
+    pub fn fields(&self) -> impl Iterator<Item = FieldStatistics> + '_ {
+        self.fields
+            .iter()
+            .map(move |(path, counts)| FieldStatistics {
+                path: path.clone(),
+                message_count: self
+                    .message_counts
+                    .get(&counts.parent_path)
+                    .copied()
+                    .unwrap_or(0),
+                present_count: counts.present_count,
+                value_count: counts.value_count,
+                encoded_len: counts.encoded_len,
+                distinct_values: counts.distinct_values.as_ref().map(HashSet::len),
+            })
+    }
+
+    fn visit_message(&mut self, message: &DynamicMessage, path: &str) {
+        *self.message_counts.entry(path.to_owned()).or_insert(0) += 1;
+
+        for field_desc in message.desc.fields() {
+            let field_path = join_path(path, field_desc.name());
+            self.field_counts(&field_path, path);
+        }
+
+        for field in message.fields.iter(&message.desc) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    let field_path = join_path(path, field_desc.name());
+                    self.visit_field(&field_path, path, value, &field_desc);
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    let name = format!("[{}]", extension_desc.full_name());
+                    let field_path = join_path(path, &name);
+                    self.visit_field(&field_path, path, value, &extension_desc);
+                }
+                ValueAndDescriptor::Unknown(number, unknowns) => {
+                    let field_path = join_path(path, &number.to_string());
+                    let max_distinct_values = self.max_distinct_values;
+                    let counts = self.field_counts(&field_path, path);
+                    counts.present_count += 1;
+                    for unknown in unknowns {
+                        counts.value_count += 1;
+                        counts.encoded_len += unknown.encoded_len(number) as u64;
+                        counts.insert_distinct(
+                            unknown_fields_to_string(std::slice::from_ref(unknown)),
+                            max_distinct_values,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_field(
+        &mut self,
+        path: &str,
+        parent_path: &str,
+        value: &Value,
+        field_desc: &impl FieldDescriptorLike,
+    ) {
+        let kind = field_desc.kind();
+        let max_distinct_values = self.max_distinct_values;
+
+        let counts = self.field_counts(path, parent_path);
+        counts.present_count += 1;
+        counts.encoded_len += value.encoded_len(field_desc) as u64;
+
+        let mut messages = Vec::new();
+        match value {
+            Value::List(values) => {
+                for value in values {
+                    counts.value_count += 1;
+                    counts.insert_distinct(value_to_string(value, &kind), max_distinct_values);
+                    if let Value::Message(message) = value {
+                        messages.push(message);
+                    }
+                }
+            }
+            Value::Map(values) => {
+                for (key, value) in values {
+                    counts.value_count += 1;
+                    counts.insert_distinct(map_key_to_string(key), max_distinct_values);
+                    if let Value::Message(message) = value {
+                        messages.push(message);
+                    }
+                }
+            }
+            value => {
+                counts.value_count += 1;
+                counts.insert_distinct(value_to_string(value, &kind), max_distinct_values);
+                if let Value::Message(message) = value {
+                    messages.push(message);
+                }
+            }
+        }
+
+        for message in messages {
+            self.visit_message(message, path);
+        }
+    }
+
+    fn field_counts(&mut self, path: &str, parent_path: &str) -> &mut FieldCounts {
+        self.fields
+            .entry(path.to_owned())
+            .or_insert_with(|| FieldCounts {
+                parent_path: parent_path.to_owned(),
+                present_count: 0,
+                value_count: 0,
+                encoded_len: 0,
+                distinct_values: Some(HashSet::new()),
+            })
+    }
+}
+
+impl FieldCounts {
+    fn insert_distinct(&mut self, value: String, max: usize) {
+        if let Some(distinct_values) = &mut self.distinct_values {
+            distinct_values.insert(value);
+            if distinct_values.len() > max {
+                self.distinct_values = None;
+            }
+        }
+    }
+}
+
+impl FieldStatistics {
+    /// The path to this field from the root message, for example `items.id`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The number of messages which could contain this field. For fields of the root message this is the
+    /// number of messages in the corpus, and for fields of nested messages it is the number of times
+    /// the nested message appeared.
+    pub fn message_count(&self) -> u64 {
+        self.message_count
+    }
+
+    /// The number of messages in which this field was set, as defined by
+    /// [`DynamicMessage::has_field`].
+    pub fn present_count(&self) -> u64 {
+        self.present_count
+    }
+
+    /// The proportion of messages in which this field was set, between `0.0` and `1.0`.
+    pub fn fill_rate(&self) -> f64 {
+        if self.message_count == 0 {
+            0.0
+        } else {
+            self.present_count as f64 / self.message_count as f64
+        }
+    }
+
+    /// The total number of values seen for this field. For repeated fields each element is counted
+    /// separately, and for map fields each entry.
+    pub fn value_count(&self) -> u64 {
+        self.value_count
+    }
+
+    /// The number of distinct values seen for this field, or `None` if there were more than
+    /// [`CorpusProfiler::max_distinct_values`]. For map fields, distinct keys are counted.
+    pub fn distinct_values(&self) -> Option<usize> {
+        self.distinct_values
+    }
+
+    /// The total number of bytes this field contributed to the encoded messages, including field tags
+    /// and length prefixes.
+    pub fn encoded_len(&self) -> u64 {
+        self.encoded_len
+    }
+}
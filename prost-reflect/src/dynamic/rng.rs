@@ -0,0 +1,33 @@
+/// A small, fast pseudo-random number generator, used where output must be reproducible from a seed.
+///
+/// This implements SplitMix64, which has a fixed output sequence for a given seed, unlike the generators
+/// provided by external crates, which may change between versions.
+#[derive(Debug, Clone)]
+pub(super) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(super) fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Returns `true` with probability `1 / n`.
+    pub(super) fn chance(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    /// Returns a number in the range `0..n`.
+    pub(super) fn below(&mut self, n: usize) -> usize {
+        debug_assert!(n > 0);
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub(super) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
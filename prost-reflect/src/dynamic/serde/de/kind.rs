@@ -4,48 +4,58 @@ use prost::bytes::Bytes;
 use serde::de::{DeserializeSeed, Deserializer, Error, IgnoredAny, MapAccess, SeqAccess, Visitor};
 
 use crate::{
-    dynamic::{serde::DeserializeOptions, DynamicMessage, MapKey, Value},
+    dynamic::{
+        serde::{BytesEncoding, DeserializeOptions, UnknownJsonField},
+        DynamicMessage, MapKey, Value,
+    },
     EnumDescriptor, Kind, MessageDescriptor, ReflectMessage,
 };
 
 use super::{
-    deserialize_enum, deserialize_message, FieldDescriptorSeed, OptionalFieldDescriptorSeed,
+    deserialize_enum, deserialize_message, find_field, is_human_readable, push_error_path,
+    FieldDescriptorSeed, OptionalFieldDescriptorSeed, PathSegment,
 };
 
+/// Deserializes a value of the given kind, or `None` if it is an unrecognized enum value which should
+/// be skipped.
 pub struct KindSeed<'a>(pub &'a Kind, pub &'a DeserializeOptions);
 
 impl<'a, 'de> DeserializeSeed<'de> for KindSeed<'a> {
-    type Value = Value;
+    type Value = Option<Value>;
 
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
-        match self.0 {
-            Kind::Double => deserializer.deserialize_any(DoubleVisitor).map(Value::F64),
-            Kind::Float => deserializer.deserialize_any(FloatVisitor).map(Value::F32),
+        let value = match self.0 {
+            Kind::Double => Value::F64(deserializer.deserialize_any(DoubleVisitor)?),
+            Kind::Float => Value::F32(deserializer.deserialize_any(FloatVisitor)?),
             Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
-                deserializer.deserialize_any(Int32Visitor).map(Value::I32)
+                Value::I32(deserializer.deserialize_any(Int32Visitor)?)
             }
             Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
-                deserializer.deserialize_any(Int64Visitor).map(Value::I64)
+                Value::I64(deserializer.deserialize_any(Int64Visitor)?)
             }
             Kind::Uint32 | Kind::Fixed32 => {
-                deserializer.deserialize_any(Uint32Visitor).map(Value::U32)
+                Value::U32(deserializer.deserialize_any(Uint32Visitor)?)
             }
             Kind::Uint64 | Kind::Fixed64 => {
-                deserializer.deserialize_any(Uint64Visitor).map(Value::U64)
+                Value::U64(deserializer.deserialize_any(Uint64Visitor)?)
             }
-            Kind::Bool => deserializer.deserialize_any(BoolVisitor).map(Value::Bool),
-            Kind::String => deserializer
-                .deserialize_string(StringVisitor)
-                .map(Value::String),
-            Kind::Bytes => deserializer.deserialize_str(BytesVisitor).map(Value::Bytes),
-            Kind::Message(desc) => {
-                deserialize_message(desc, deserializer, self.1).map(Value::Message)
+            Kind::Bool => Value::Bool(deserializer.deserialize_any(BoolVisitor)?),
+            Kind::String => Value::String(deserializer.deserialize_string(StringVisitor)?),
+            Kind::Bytes if !is_human_readable(&deserializer, self.1) => {
+                Value::Bytes(deserializer.deserialize_bytes(BytesVisitor(self.1))?)
             }
-            Kind::Enum(desc) => deserialize_enum(desc, deserializer).map(Value::EnumNumber),
-        }
+            Kind::Bytes => Value::Bytes(deserializer.deserialize_str(BytesVisitor(self.1))?),
+            Kind::Message(desc) => Value::Message(deserialize_message(desc, deserializer, self.1)?),
+            Kind::Enum(desc) => match deserialize_enum(desc, deserializer, self.1)? {
+                Some(number) => Value::EnumNumber(number),
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(value))
     }
 }
 
@@ -59,10 +69,10 @@ pub struct Int64Visitor;
 pub struct Uint64Visitor;
 pub struct StringVisitor;
 pub struct BoolVisitor;
-pub struct BytesVisitor;
+pub struct BytesVisitor<'a>(pub &'a DeserializeOptions);
 pub struct MessageVisitor<'a>(pub &'a MessageDescriptor, pub &'a DeserializeOptions);
 pub struct MessageVisitorInner<'a>(pub &'a mut DynamicMessage, pub &'a DeserializeOptions);
-pub struct EnumVisitor<'a>(pub &'a EnumDescriptor);
+pub struct EnumVisitor<'a>(pub &'a EnumDescriptor, pub &'a DeserializeOptions);
 
 impl<'a, 'de> Visitor<'de> for ListVisitor<'a> {
     type Value = Vec<Value>;
@@ -78,8 +88,16 @@ impl<'a, 'de> Visitor<'de> for ListVisitor<'a> {
     {
         let mut result = Vec::with_capacity(seq.size_hint().unwrap_or(0));
 
-        while let Some(value) = seq.next_element_seed(KindSeed(self.0, self.1))? {
-            result.push(value)
+        let mut index = 0;
+        while let Some(value) =
+            push_error_path(seq.next_element_seed(KindSeed(self.0, self.1)), || {
+                PathSegment::Index(index)
+            })?
+        {
+            if let Some(value) = value {
+                result.push(value)
+            }
+            index += 1;
         }
 
         Ok(result)
@@ -104,35 +122,41 @@ impl<'a, 'de> Visitor<'de> for MapVisitor<'a> {
         let value_desc = map_entry_message.map_entry_value_field();
 
         while let Some(key_str) = map.next_key::<Cow<str>>()? {
-            let key = match key_kind {
-                Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
-                    MapKey::I32(i32::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
-                    MapKey::I64(i64::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Uint32 | Kind::Fixed32 => {
-                    MapKey::U32(u32::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Uint64 | Kind::Fixed64 => {
-                    MapKey::U64(u64::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Bool => {
-                    MapKey::Bool(bool::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::String => MapKey::String(key_str.into_owned()),
-                _ => unreachable!("invalid type for map key"),
-            };
-
-            let value = map.next_value_seed(FieldDescriptorSeed(&value_desc, self.1))?;
-
-            result.insert(key, value);
+            let key = push_error_path(parse_map_key(&key_kind, key_str.as_ref()), || {
+                PathSegment::Key(key_str.to_string())
+            })?;
+
+            if let Some(value) = push_error_path(
+                map.next_value_seed(FieldDescriptorSeed(&value_desc, self.1)),
+                || PathSegment::Key(key_str.into_owned()),
+            )? {
+                result.insert(key, value);
+            }
         }
 
         Ok(result)
     }
 }
 
+pub(super) fn parse_map_key<E>(key_kind: &Kind, key_str: &str) -> Result<MapKey, E>
+where
+    E: Error,
+{
+    Ok(match key_kind {
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+            MapKey::I32(i32::from_str(key_str).map_err(Error::custom)?)
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+            MapKey::I64(i64::from_str(key_str).map_err(Error::custom)?)
+        }
+        Kind::Uint32 | Kind::Fixed32 => MapKey::U32(u32::from_str(key_str).map_err(Error::custom)?),
+        Kind::Uint64 | Kind::Fixed64 => MapKey::U64(u64::from_str(key_str).map_err(Error::custom)?),
+        Kind::Bool => MapKey::Bool(bool::from_str(key_str).map_err(Error::custom)?),
+        Kind::String => MapKey::String(key_str.to_owned()),
+        _ => unreachable!("invalid type for map key"),
+    })
+}
+
 impl<'de> Visitor<'de> for DoubleVisitor {
     type Value = f64;
 
@@ -479,11 +503,41 @@ impl<'de> Visitor<'de> for BoolVisitor {
     }
 }
 
-impl<'de> Visitor<'de> for BytesVisitor {
+impl<'a, 'de> Visitor<'de> for BytesVisitor<'a> {
     type Value = Bytes;
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "a base64-encoded string")
+        match self.0.bytes_encoding {
+            BytesEncoding::Base64 | BytesEncoding::Base64UrlSafe => {
+                write!(f, "a base64-encoded string")
+            }
+            BytesEncoding::Hex => write!(f, "a hex or base64-encoded string"),
+        }
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(Bytes::copy_from_slice(v))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(v.into())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut buf = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            buf.push(byte);
+        }
+        Ok(buf.into())
     }
 
     #[inline]
@@ -493,6 +547,12 @@ impl<'de> Visitor<'de> for BytesVisitor {
     {
         use base64::{decode_config_buf, DecodeError, STANDARD, URL_SAFE};
 
+        if self.0.bytes_encoding == BytesEncoding::Hex {
+            if let Some(buf) = decode_hex(v) {
+                return Ok(buf.into());
+            }
+        }
+
         let mut buf = Vec::new();
         match decode_config_buf(v, STANDARD.decode_allow_trailing_bits(true), &mut buf) {
             Ok(()) => Ok(buf.into()),
@@ -508,6 +568,21 @@ impl<'de> Visitor<'de> for BytesVisitor {
     }
 }
 
+fn decode_hex(v: &str) -> Option<Vec<u8>> {
+    if v.len() % 2 != 0 {
+        return None;
+    }
+
+    v.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16)?;
+            let lo = (pair[1] as char).to_digit(16)?;
+            Some((hi * 16 + lo) as u8)
+        })
+        .collect()
+}
+
 impl<'a, 'de> Visitor<'de> for MessageVisitor<'a> {
     type Value = DynamicMessage;
 
@@ -540,13 +615,11 @@ impl<'a, 'de> Visitor<'de> for MessageVisitorInner<'a> {
     {
         let desc = self.0.descriptor();
         while let Some(key) = map.next_key::<Cow<str>>()? {
-            if let Some(field) = desc
-                .get_field_by_json_name(key.as_ref())
-                .or_else(|| desc.get_field_by_name(key.as_ref()))
-            {
-                if let Some(value) =
-                    map.next_value_seed(OptionalFieldDescriptorSeed(&field, self.1))?
-                {
+            if let Some(field) = find_field(&desc, key.as_ref(), self.1) {
+                if let Some(value) = push_error_path(
+                    map.next_value_seed(OptionalFieldDescriptorSeed(&field, self.1)),
+                    || PathSegment::Field(key.to_string()),
+                )? {
                     if let Some(oneof_desc) = field.containing_oneof() {
                         for field in oneof_desc.fields() {
                             if self.0.has_field(&field) {
@@ -561,13 +634,23 @@ impl<'a, 'de> Visitor<'de> for MessageVisitorInner<'a> {
                     self.0.set_field(&field, value);
                 }
             } else if let Some(extension_desc) = desc.get_extension_by_json_name(key.as_ref()) {
-                if let Some(value) =
-                    map.next_value_seed(OptionalFieldDescriptorSeed(&extension_desc, self.1))?
-                {
+                if let Some(value) = push_error_path(
+                    map.next_value_seed(OptionalFieldDescriptorSeed(&extension_desc, self.1)),
+                    || PathSegment::Field(key.to_string()),
+                )? {
                     self.0.set_extension(&extension_desc, value);
                 }
             } else if self.1.deny_unknown_fields {
                 return Err(Error::custom(format!("unrecognized field name '{}'", key)));
+            } else if self.1.capture_unknown_fields {
+                let fields = self
+                    .0
+                    .unknown_json_fields
+                    .get_or_insert_with(Default::default);
+                fields.push(UnknownJsonField {
+                    name: key.into_owned(),
+                    value: map.next_value()?,
+                });
             } else {
                 let _ = map.next_value::<IgnoredAny>()?;
             }
@@ -578,7 +661,7 @@ impl<'a, 'de> Visitor<'de> for MessageVisitorInner<'a> {
 }
 
 impl<'a, 'de> Visitor<'de> for EnumVisitor<'a> {
-    type Value = i32;
+    type Value = Option<i32>;
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "a string or integer")
@@ -590,7 +673,8 @@ impl<'a, 'de> Visitor<'de> for EnumVisitor<'a> {
         E: Error,
     {
         match self.0.get_value_by_name(v) {
-            Some(e) => Ok(e.number()),
+            Some(e) => Ok(Some(e.number())),
+            None if self.1.ignore_unknown_enum_values => Ok(None),
             None => Err(Error::custom(format!("unrecognized enum value '{}'", v))),
         }
     }
@@ -600,7 +684,7 @@ impl<'a, 'de> Visitor<'de> for EnumVisitor<'a> {
     where
         E: Error,
     {
-        Ok(v)
+        Ok(Some(v))
     }
 
     #[inline]
@@ -608,7 +692,11 @@ impl<'a, 'de> Visitor<'de> for EnumVisitor<'a> {
     where
         E: Error,
     {
-        self.visit_i32(v.try_into().map_err(Error::custom)?)
+        match v.try_into() {
+            Ok(v) => self.visit_i32(v),
+            Err(_) if self.1.ignore_unknown_enum_values => Ok(None),
+            Err(err) => Err(Error::custom(err)),
+        }
     }
 
     #[inline]
@@ -616,6 +704,10 @@ impl<'a, 'de> Visitor<'de> for EnumVisitor<'a> {
     where
         E: Error,
     {
-        self.visit_i32(v.try_into().map_err(Error::custom)?)
+        match v.try_into() {
+            Ok(v) => self.visit_i32(v),
+            Err(_) if self.1.ignore_unknown_enum_values => Ok(None),
+            Err(err) => Err(Error::custom(err)),
+        }
     }
 }
@@ -1,14 +1,19 @@
 mod kind;
+mod patch;
 mod wkt;
 
-use std::fmt;
+use std::{cell::RefCell, fmt};
 
 use prost::Message;
 use serde::de::{DeserializeSeed, Deserializer, Error, Visitor};
 
 use crate::{
-    dynamic::{fields::FieldDescriptorLike, serde::DeserializeOptions, DynamicMessage, Value},
-    EnumDescriptor, Kind, MessageDescriptor,
+    dynamic::{
+        fields::FieldDescriptorLike,
+        serde::{DeserializeOptions, DurationEncoding, TimestampEncoding},
+        DynamicMessage, Value,
+    },
+    EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor,
 };
 
 pub(super) fn deserialize_message<'de, D>(
@@ -19,16 +24,54 @@ pub(super) fn deserialize_message<'de, D>(
 where
     D: Deserializer<'de>,
 {
+    #[cfg(feature = "serde-json")]
+    if let Some(handler) = options.json_handler_for(desc.full_name()) {
+        let value = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+        return handler
+            .deserialize(desc, value, options)
+            .map_err(Error::custom);
+    }
+
     match desc.full_name() {
         "google.protobuf.Any" => deserializer
             .deserialize_any(wkt::GoogleProtobufAnyVisitor(desc.parent_pool(), options))
             .and_then(|timestamp| make_message(desc, timestamp)),
-        "google.protobuf.Timestamp" => deserializer
-            .deserialize_str(wkt::GoogleProtobufTimestampVisitor)
+        "google.protobuf.Timestamp" if !is_human_readable(&deserializer, options) => deserializer
+            .deserialize_struct(
+                "Timestamp",
+                &wkt::SECONDS_NANOS_FIELDS,
+                wkt::GoogleProtobufSecondsNanosVisitor(options),
+            )
+            .and_then(|(seconds, nanos)| wkt::make_timestamp(seconds, nanos))
             .and_then(|timestamp| make_message(desc, timestamp)),
-        "google.protobuf.Duration" => deserializer
-            .deserialize_str(wkt::GoogleProtobufDurationVisitor)
+        "google.protobuf.Timestamp" => {
+            let visitor = wkt::GoogleProtobufTimestampVisitor(options);
+            match options.timestamp_encoding {
+                TimestampEncoding::Rfc3339 => deserializer.deserialize_str(visitor),
+                TimestampEncoding::EpochSeconds | TimestampEncoding::EpochMillis => {
+                    deserializer.deserialize_any(visitor)
+                }
+            }
+            .and_then(|timestamp| make_message(desc, timestamp))
+        }
+        "google.protobuf.Duration" if !is_human_readable(&deserializer, options) => deserializer
+            .deserialize_struct(
+                "Duration",
+                &wkt::SECONDS_NANOS_FIELDS,
+                wkt::GoogleProtobufSecondsNanosVisitor(options),
+            )
+            .and_then(|(seconds, nanos)| wkt::make_duration(seconds, nanos))
             .and_then(|duration| make_message(desc, duration)),
+        "google.protobuf.Duration" => {
+            let visitor = wkt::GoogleProtobufDurationVisitor(options);
+            match options.duration_encoding {
+                DurationEncoding::String => deserializer.deserialize_str(visitor),
+                DurationEncoding::Seconds | DurationEncoding::Object => {
+                    deserializer.deserialize_any(visitor)
+                }
+            }
+            .and_then(|duration| make_message(desc, duration))
+        }
         "google.protobuf.FloatValue" => deserializer
             .deserialize_any(kind::FloatVisitor)
             .and_then(|v| make_message(desc, v)),
@@ -53,8 +96,11 @@ where
         "google.protobuf.StringValue" => deserializer
             .deserialize_any(kind::StringVisitor)
             .and_then(|v| make_message(desc, v)),
+        "google.protobuf.BytesValue" if !is_human_readable(&deserializer, options) => deserializer
+            .deserialize_bytes(kind::BytesVisitor(options))
+            .and_then(|v| make_message(desc, v)),
         "google.protobuf.BytesValue" => deserializer
-            .deserialize_any(kind::BytesVisitor)
+            .deserialize_any(kind::BytesVisitor(options))
             .and_then(|v| make_message(desc, v)),
         "google.protobuf.FieldMask" => deserializer
             .deserialize_str(wkt::GoogleProtobufFieldMaskVisitor)
@@ -75,14 +121,134 @@ where
     }
 }
 
-fn deserialize_enum<'de, D>(desc: &EnumDescriptor, deserializer: D) -> Result<i32, D::Error>
+thread_local! {
+    /// The path to the value which failed to deserialize, innermost segment first. Segments are
+    /// recorded as an error propagates out of nested messages, lists and maps, so the path costs nothing
+    /// unless deserialization fails.
+    static ERROR_PATH: RefCell<Vec<PathSegment>> = RefCell::new(Vec::new());
+}
+
+enum PathSegment {
+    Field(String),
+    Index(usize),
+    Key(String),
+}
+
+/// Runs a top-level deserialization, prefixing any error with the path to the value which caused it,
+/// for example `servers[3].tls.minVersion: unrecognized enum value 'TLS_1_4'`.
+pub(super) fn with_error_path<T, E>(f: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+where
+    E: Error,
+{
+    ERROR_PATH.with(|path| path.borrow_mut().clear());
+    f().map_err(|err| {
+        let path = ERROR_PATH.with(|path| path.take());
+        if path.is_empty() {
+            return err;
+        }
+
+        let mut message = String::new();
+        for segment in path.iter().rev() {
+            match segment {
+                PathSegment::Field(name) if message.is_empty() => message.push_str(name),
+                PathSegment::Field(name) => {
+                    message.push('.');
+                    message.push_str(name);
+                }
+                PathSegment::Index(index) => message.push_str(&format!("[{}]", index)),
+                PathSegment::Key(key) => message.push_str(&format!("[{:?}]", key)),
+            }
+        }
+        Error::custom(format!("{}: {}", message, err))
+    })
+}
+
+/// Records `segment` as part of the error path if `result` is an error.
+fn push_error_path<T, E>(
+    result: Result<T, E>,
+    segment: impl FnOnce() -> PathSegment,
+) -> Result<T, E> {
+    if result.is_err() {
+        ERROR_PATH.with(|path| path.borrow_mut().push(segment()));
+    }
+    result
+}
+
+pub(super) fn apply_merge_patch<'de, D>(
+    message: &mut DynamicMessage,
+    deserializer: D,
+    options: &DeserializeOptions,
+) -> Result<(), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_map(patch::MergePatchVisitor(message, options))
+}
+
+fn is_human_readable<'de, D>(deserializer: &D, options: &DeserializeOptions) -> bool
+where
+    D: Deserializer<'de>,
+{
+    options
+        .human_readable
+        .unwrap_or_else(|| deserializer.is_human_readable())
+}
+
+/// Deserializes an enum value, returning `None` if it is not recognized and
+/// [`DeserializeOptions::ignore_unknown_enum_values`] is set.
+fn deserialize_enum<'de, D>(
+    desc: &EnumDescriptor,
+    deserializer: D,
+    options: &DeserializeOptions,
+) -> Result<Option<i32>, D::Error>
 where
     D: Deserializer<'de>,
 {
     match desc.full_name() {
-        "google.protobuf.NullValue" => deserializer.deserialize_any(wkt::GoogleProtobufNullVisitor),
-        _ => deserializer.deserialize_any(kind::EnumVisitor(desc)),
+        "google.protobuf.NullValue" => deserializer
+            .deserialize_any(wkt::GoogleProtobufNullVisitor)
+            .map(Some),
+        _ => deserializer.deserialize_any(kind::EnumVisitor(desc, options)),
+    }
+}
+
+/// Looks up the field of `desc` named by the JSON key `key`, using the matching rules configured in
+/// `options`.
+fn find_field(
+    desc: &MessageDescriptor,
+    key: &str,
+    options: &DeserializeOptions,
+) -> Option<FieldDescriptor> {
+    if let Some(field) = desc
+        .get_field_by_json_name(key)
+        .or_else(|| desc.get_field_by_name(key))
+    {
+        return Some(field);
     }
+
+    if let Some(aliases) = &options.field_aliases {
+        if let Some(name) = aliases.get(&(desc.full_name().to_owned(), key.to_owned())) {
+            return desc.get_field_by_name(name);
+        }
+    }
+
+    if options.case_insensitive_field_names {
+        if let Some(field) = desc.fields().find(|field| {
+            field.json_name().eq_ignore_ascii_case(key) || field.name().eq_ignore_ascii_case(key)
+        }) {
+            return Some(field);
+        }
+
+        if let Some(aliases) = &options.field_aliases {
+            if let Some(((_, _), name)) = aliases.iter().find(|((message, alias), _)| {
+                message == desc.full_name() && alias.eq_ignore_ascii_case(key)
+            }) {
+                return desc.get_field_by_name(name);
+            }
+        }
+    }
+
+    None
 }
 
 struct MessageSeed<'a>(&'a MessageDescriptor, &'a DeserializeOptions);
@@ -104,7 +270,7 @@ impl<'a, 'de, T> DeserializeSeed<'de> for FieldDescriptorSeed<'a, T>
 where
     T: FieldDescriptorLike,
 {
-    type Value = Value;
+    type Value = Option<Value>;
 
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
@@ -113,11 +279,11 @@ where
         if self.0.is_list() {
             deserializer
                 .deserialize_any(kind::ListVisitor(&self.0.kind(), self.1))
-                .map(Value::List)
+                .map(|list| Some(Value::List(list)))
         } else if self.0.is_map() {
             deserializer
                 .deserialize_any(kind::MapVisitor(&self.0.kind(), self.1))
-                .map(Value::Map)
+                .map(|map| Some(Value::Map(map)))
         } else {
             kind::KindSeed(&self.0.kind(), self.1).deserialize(deserializer)
         }
@@ -184,9 +350,7 @@ where
     where
         D: Deserializer<'de>,
     {
-        FieldDescriptorSeed(self.0, self.1)
-            .deserialize(deserializer)
-            .map(Some)
+        FieldDescriptorSeed(self.0, self.1).deserialize(deserializer)
     }
 }
 
@@ -0,0 +1,185 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use serde::de::{DeserializeSeed, Deserializer, Error, IgnoredAny, MapAccess, Visitor};
+
+use crate::{
+    dynamic::{
+        fields::FieldDescriptorLike,
+        serde::{DeserializeOptions, UnknownJsonField},
+        DynamicMessage, MapKey, Value,
+    },
+    FieldDescriptor, Kind, ReflectMessage,
+};
+
+use super::{find_field, kind::parse_map_key, push_error_path, FieldDescriptorSeed, PathSegment};
+
+/// Applies a JSON merge patch to a message, as described by [RFC 7386](https://www.rfc-editor.org/rfc/rfc7386).
+pub struct MergePatchVisitor<'a>(pub &'a mut DynamicMessage, pub &'a DeserializeOptions);
+
+impl<'a, 'de> Visitor<'de> for MergePatchVisitor<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let desc = self.0.descriptor();
+        while let Some(key) = map.next_key::<Cow<str>>()? {
+            if let Some(field) = find_field(&desc, key.as_ref(), self.1) {
+                let patch = map.next_value()?;
+                push_error_path(patch_field(self.0, &field, patch, self.1), || {
+                    PathSegment::Field(key.to_string())
+                })?;
+            } else if let Some(extension_desc) = desc.get_extension_by_json_name(key.as_ref()) {
+                let patch = map.next_value()?;
+                push_error_path(patch_field(self.0, &extension_desc, patch, self.1), || {
+                    PathSegment::Field(key.to_string())
+                })?;
+            } else if self.1.deny_unknown_fields {
+                return Err(Error::custom(format!("unrecognized field name '{}'", key)));
+            } else if self.1.capture_unknown_fields {
+                let name = key.into_owned();
+                let value = map.next_value()?;
+                let fields = self
+                    .0
+                    .unknown_json_fields
+                    .get_or_insert_with(Default::default);
+                fields.retain(|field| field.name != name);
+                fields.push(UnknownJsonField { name, value });
+            } else {
+                let _ = map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn patch_field<E>(
+    message: &mut DynamicMessage,
+    desc: &impl FieldDescriptorLike,
+    patch: serde_value::Value,
+    options: &DeserializeOptions,
+) -> Result<(), E>
+where
+    E: Error,
+{
+    match patch {
+        serde_value::Value::Unit | serde_value::Value::Option(None) => message.fields.clear(desc),
+        serde_value::Value::Map(entries) if desc.is_map() => {
+            if let Value::Map(map) = message.fields.get_mut(desc) {
+                patch_map(map, desc, entries, options)?;
+            }
+        }
+        serde_value::Value::Map(entries) if is_patchable_message(desc, options) => {
+            if let Value::Message(nested) = message.fields.get_mut(desc) {
+                patch_message(nested, entries, options)?;
+            }
+        }
+        patch => {
+            if let Some(value) = FieldDescriptorSeed(desc, options)
+                .deserialize(patch)
+                .map_err(Error::custom)?
+            {
+                message.fields.set(desc, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn patch_map<E>(
+    map: &mut HashMap<MapKey, Value>,
+    desc: &impl FieldDescriptorLike,
+    entries: BTreeMap<serde_value::Value, serde_value::Value>,
+    options: &DeserializeOptions,
+) -> Result<(), E>
+where
+    E: Error,
+{
+    let map_entry_message = desc.kind();
+    let map_entry_message = map_entry_message.as_message().unwrap();
+    let key_kind = map_entry_message.map_entry_key_field().kind();
+    let value_desc = map_entry_message.map_entry_value_field();
+
+    for (key, patch) in entries {
+        let key = match key {
+            serde_value::Value::String(key) => key,
+            _ => return Err(Error::custom("expected a string map key")),
+        };
+
+        push_error_path(
+            patch_map_entry(map, &key_kind, &value_desc, &key, patch, options),
+            || PathSegment::Key(key),
+        )?;
+    }
+    Ok(())
+}
+
+fn patch_map_entry<E>(
+    map: &mut HashMap<MapKey, Value>,
+    key_kind: &Kind,
+    value_desc: &FieldDescriptor,
+    key: &str,
+    patch: serde_value::Value,
+    options: &DeserializeOptions,
+) -> Result<(), E>
+where
+    E: Error,
+{
+    let key = parse_map_key(key_kind, key)?;
+
+    match patch {
+        serde_value::Value::Unit | serde_value::Value::Option(None) => {
+            map.remove(&key);
+        }
+        serde_value::Value::Map(entries) if is_patchable_message(value_desc, options) => {
+            let value = map
+                .entry(key)
+                .or_insert_with(|| Value::default_value_for_field(value_desc));
+            if let Value::Message(nested) = value {
+                patch_message(nested, entries, options)?;
+            }
+        }
+        patch => {
+            if let Some(value) = FieldDescriptorSeed(value_desc, options)
+                .deserialize(patch)
+                .map_err(Error::custom)?
+            {
+                map.insert(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn patch_message<E>(
+    message: &mut DynamicMessage,
+    entries: BTreeMap<serde_value::Value, serde_value::Value>,
+    options: &DeserializeOptions,
+) -> Result<(), E>
+where
+    E: Error,
+{
+    serde_value::Value::Map(entries)
+        .deserialize_map(MergePatchVisitor(message, options))
+        .map_err(Error::custom)
+}
+
+/// Returns whether a field is a singular message whose JSON representation is an object of its fields,
+/// and so may be patched recursively.
+fn is_patchable_message(desc: &impl FieldDescriptorLike, options: &DeserializeOptions) -> bool {
+    !desc.is_list()
+        && !desc.is_map()
+        && desc.kind().as_message().map_or(false, |message| {
+            !options.has_special_mapping(message.full_name())
+        })
+}
@@ -1,6 +1,7 @@
 use std::{
     borrow::Cow,
     collections::{BTreeMap, HashMap},
+    convert::TryFrom,
     fmt,
     str::FromStr,
 };
@@ -8,27 +9,36 @@ use std::{
 use prost::Message;
 use serde::de::{
     DeserializeSeed, Deserializer, Error, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess,
-    Visitor,
+    Unexpected, Visitor,
 };
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 use crate::{
     dynamic::{
         serde::{
-            case::camel_case_to_snake_case, is_well_known_type, DeserializeOptions,
-            MAX_DURATION_NANOS, MAX_DURATION_SECONDS, MAX_TIMESTAMP_SECONDS, MIN_TIMESTAMP_SECONDS,
+            case::camel_case_to_snake_case, resolve_any_type, DeserializeOptions, DurationEncoding,
+            TimestampEncoding, MAX_DURATION_NANOS, MAX_DURATION_SECONDS, MAX_TIMESTAMP_SECONDS,
+            MIN_TIMESTAMP_SECONDS,
         },
-        DynamicMessage,
+        DynamicMessage, Value,
     },
-    DescriptorPool,
+    DescriptorPool, Kind,
 };
 
-use super::{deserialize_message, kind::MessageVisitorInner, MessageSeed};
+use super::{
+    deserialize_message,
+    kind::{KindSeed, MessageVisitorInner},
+    push_error_path, MessageSeed, PathSegment,
+};
 
 pub struct GoogleProtobufAnyVisitor<'a>(pub &'a DescriptorPool, pub &'a DeserializeOptions);
 pub struct GoogleProtobufNullVisitor;
-pub struct GoogleProtobufTimestampVisitor;
-pub struct GoogleProtobufDurationVisitor;
+pub struct GoogleProtobufTimestampVisitor<'a>(pub &'a DeserializeOptions);
+pub struct GoogleProtobufDurationVisitor<'a>(pub &'a DeserializeOptions);
+pub struct GoogleProtobufSecondsNanosVisitor<'a>(pub &'a DeserializeOptions);
+
+/// The fields of the compact representation of timestamps and durations used by binary formats.
+pub const SECONDS_NANOS_FIELDS: [&str; 2] = ["seconds", "nanos"];
 pub struct GoogleProtobufFieldMaskVisitor;
 pub struct GoogleProtobufListVisitor;
 pub struct GoogleProtobufStructVisitor;
@@ -61,71 +71,66 @@ impl<'a, 'de> Visitor<'de> for GoogleProtobufAnyVisitor<'a> {
             }
         };
 
-        if let Some(message_name) = type_url.strip_prefix("type.googleapis.com/") {
-            let message_desc = self
-                .0
-                .get_message_by_name(message_name)
-                .ok_or_else(|| Error::custom(format!("message '{}' not found", message_name)))?;
-
-            let payload_message = if is_well_known_type(message_name) {
-                let payload_message = match buffered_entries.remove("value") {
-                    Some(value) => {
-                        deserialize_message(&message_desc, value, self.1).map_err(Error::custom)?
-                    }
-                    None => loop {
-                        match map.next_key::<Cow<str>>()? {
-                            Some(key) if key == "value" => {
-                                break map.next_value_seed(MessageSeed(&message_desc, self.1))?
-                            }
-                            Some(key) => {
-                                if self.1.deny_unknown_fields {
-                                    return Err(Error::custom(format!(
-                                        "unrecognized field name '{}'",
-                                        key
-                                    )));
-                                } else {
-                                    let _ = map.next_value::<IgnoredAny>()?;
-                                }
+        let message_desc = resolve_any_type(self.0, &type_url, self.1.resolver.as_deref())
+            .map_err(Error::custom)?;
+
+        let payload_message = if self.1.has_special_mapping(message_desc.full_name()) {
+            let payload_message = match buffered_entries.remove("value") {
+                Some(value) => push_error_path(
+                    deserialize_message(&message_desc, value, self.1).map_err(Error::custom),
+                    || PathSegment::Field("value".to_owned()),
+                )?,
+                None => loop {
+                    match map.next_key::<Cow<str>>()? {
+                        Some(key) if key == "value" => {
+                            break push_error_path(
+                                map.next_value_seed(MessageSeed(&message_desc, self.1)),
+                                || PathSegment::Field("value".to_owned()),
+                            )?
+                        }
+                        Some(key) => {
+                            if self.1.deny_unknown_fields {
+                                return Err(Error::custom(format!(
+                                    "unrecognized field name '{}'",
+                                    key
+                                )));
+                            } else {
+                                let _ = map.next_value::<IgnoredAny>()?;
                             }
-                            None => return Err(Error::custom("expected '@type' field")),
                         }
-                    },
-                };
-
-                if self.1.deny_unknown_fields {
-                    if let Some(key) = buffered_entries.keys().next() {
-                        return Err(Error::custom(format!("unrecognized field name '{}'", key)));
+                        None => return Err(Error::custom("expected '@type' field")),
                     }
-                    if let Some(key) = map.next_key::<Cow<str>>()? {
-                        return Err(Error::custom(format!("unrecognized field name '{}'", key)));
-                    }
-                } else {
-                    drop(buffered_entries);
-                    while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
-                }
+                },
+            };
 
-                payload_message
+            if self.1.deny_unknown_fields {
+                if let Some(key) = buffered_entries.keys().next() {
+                    return Err(Error::custom(format!("unrecognized field name '{}'", key)));
+                }
+                if let Some(key) = map.next_key::<Cow<str>>()? {
+                    return Err(Error::custom(format!("unrecognized field name '{}'", key)));
+                }
             } else {
-                let mut payload_message = DynamicMessage::new(message_desc);
+                drop(buffered_entries);
+                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+            }
 
-                buffered_entries
-                    .into_deserializer()
-                    .deserialize_map(MessageVisitorInner(&mut payload_message, self.1))
-                    .map_err(Error::custom)?;
+            payload_message
+        } else {
+            let mut payload_message = DynamicMessage::new(message_desc);
 
-                MessageVisitorInner(&mut payload_message, self.1).visit_map(map)?;
+            buffered_entries
+                .into_deserializer()
+                .deserialize_map(MessageVisitorInner(&mut payload_message, self.1))
+                .map_err(Error::custom)?;
 
-                payload_message
-            };
+            MessageVisitorInner(&mut payload_message, self.1).visit_map(map)?;
 
-            let value = payload_message.encode_to_vec();
-            Ok(prost_types::Any { type_url, value })
-        } else {
-            Err(Error::custom(format!(
-                "unsupported type url '{}'",
-                type_url
-            )))
-        }
+            payload_message
+        };
+
+        let value = payload_message.encode_to_vec();
+        Ok(prost_types::Any { type_url, value })
     }
 }
 
@@ -156,11 +161,80 @@ impl<'de> Visitor<'de> for GoogleProtobufNullVisitor {
     }
 }
 
-impl<'de> Visitor<'de> for GoogleProtobufTimestampVisitor {
+impl<'a, 'de> Visitor<'de> for GoogleProtobufTimestampVisitor<'a> {
     type Value = prost_types::Timestamp;
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "a rfc3339 timestamp string")
+        match self.0.timestamp_encoding {
+            TimestampEncoding::Rfc3339 => write!(f, "a rfc3339 timestamp string"),
+            TimestampEncoding::EpochSeconds => {
+                write!(f, "a rfc3339 timestamp string or a number of seconds")
+            }
+            TimestampEncoding::EpochMillis => {
+                write!(f, "a rfc3339 timestamp string or a number of milliseconds")
+            }
+        }
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        let timestamp = match self.0.timestamp_encoding {
+            TimestampEncoding::Rfc3339 => {
+                return Err(Error::invalid_type(Unexpected::Signed(v), &self))
+            }
+            TimestampEncoding::EpochSeconds => prost_types::Timestamp {
+                seconds: v,
+                nanos: 0,
+            },
+            TimestampEncoding::EpochMillis => prost_types::Timestamp {
+                seconds: v.div_euclid(1000),
+                nanos: v.rem_euclid(1000) as i32 * 1_000_000,
+            },
+        };
+
+        check_timestamp(timestamp)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        match i64::try_from(v) {
+            Ok(v) => self.visit_i64(v),
+            Err(_) if self.0.timestamp_encoding == TimestampEncoding::Rfc3339 => {
+                Err(Error::invalid_type(Unexpected::Unsigned(v), &self))
+            }
+            Err(_) => Err(Error::custom("timestamp out of range")),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        let seconds = match self.0.timestamp_encoding {
+            TimestampEncoding::Rfc3339 => {
+                return Err(Error::invalid_type(Unexpected::Float(v), &self))
+            }
+            TimestampEncoding::EpochSeconds => v,
+            TimestampEncoding::EpochMillis => v / 1000.0,
+        };
+        if !seconds.is_finite()
+            || seconds < MIN_TIMESTAMP_SECONDS as f64
+            || seconds >= (MAX_TIMESTAMP_SECONDS + 1) as f64
+        {
+            return Err(Error::custom("timestamp out of range"));
+        }
+
+        let whole = seconds.floor();
+        let mut timestamp = prost_types::Timestamp {
+            seconds: whole as i64,
+            nanos: ((seconds - whole) * 1e9).round() as i32,
+        };
+        timestamp.normalize();
+        check_timestamp(timestamp)
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
@@ -178,20 +252,102 @@ impl<'de> Visitor<'de> for GoogleProtobufTimestampVisitor {
             nanos: datetime.nanosecond() as i32,
         };
 
-        if timestamp.seconds < MIN_TIMESTAMP_SECONDS || timestamp.seconds > MAX_TIMESTAMP_SECONDS {
-            return Err(Error::custom("timestamp out of range"));
-        }
-
         timestamp.normalize();
-        Ok(timestamp)
+        check_timestamp(timestamp)
+    }
+}
+
+/// Builds a timestamp from the fields of its message representation, checking they are valid.
+pub fn make_timestamp<E>(seconds: i64, nanos: i32) -> Result<prost_types::Timestamp, E>
+where
+    E: Error,
+{
+    if nanos < 0 || nanos as u32 > MAX_DURATION_NANOS {
+        return Err(Error::custom("timestamp out of range"));
+    }
+    check_timestamp(prost_types::Timestamp { seconds, nanos })
+}
+
+fn check_timestamp<E>(timestamp: prost_types::Timestamp) -> Result<prost_types::Timestamp, E>
+where
+    E: Error,
+{
+    if timestamp.seconds < MIN_TIMESTAMP_SECONDS || timestamp.seconds > MAX_TIMESTAMP_SECONDS {
+        return Err(Error::custom("timestamp out of range"));
     }
+
+    Ok(timestamp)
 }
 
-impl<'de> Visitor<'de> for GoogleProtobufDurationVisitor {
+impl<'a, 'de> Visitor<'de> for GoogleProtobufDurationVisitor<'a> {
     type Value = prost_types::Duration;
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "a duration string")
+        match self.0.duration_encoding {
+            DurationEncoding::String => write!(f, "a duration string"),
+            DurationEncoding::Seconds | DurationEncoding::Object => {
+                write!(f, "a duration string, number of seconds or object")
+            }
+        }
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        if self.0.duration_encoding == DurationEncoding::String {
+            return Err(Error::invalid_type(Unexpected::Signed(v), &self));
+        }
+
+        check_duration(prost_types::Duration {
+            seconds: v,
+            nanos: 0,
+        })
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        match i64::try_from(v) {
+            Ok(v) => self.visit_i64(v),
+            Err(_) if self.0.duration_encoding == DurationEncoding::String => {
+                Err(Error::invalid_type(Unexpected::Unsigned(v), &self))
+            }
+            Err(_) => Err(Error::custom("duration out of range")),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        if self.0.duration_encoding == DurationEncoding::String {
+            return Err(Error::invalid_type(Unexpected::Float(v), &self));
+        }
+        if !v.is_finite() || v.abs() >= (MAX_DURATION_SECONDS + 1) as f64 {
+            return Err(Error::custom("duration out of range"));
+        }
+
+        let seconds = v.trunc();
+        let mut duration = prost_types::Duration {
+            seconds: seconds as i64,
+            nanos: ((v - seconds) * 1e9).round() as i32,
+        };
+        duration.normalize();
+        check_duration(duration)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        if self.0.duration_encoding == DurationEncoding::String {
+            return Err(Error::invalid_type(Unexpected::Map, &self));
+        }
+
+        let (seconds, nanos) = GoogleProtobufSecondsNanosVisitor(self.0).visit_map(map)?;
+        make_duration(seconds, nanos)
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
@@ -247,6 +403,76 @@ impl<'de> Visitor<'de> for GoogleProtobufDurationVisitor {
     }
 }
 
+/// Builds a duration from the fields of its message representation, checking they are valid.
+pub fn make_duration<E>(seconds: i64, nanos: i32) -> Result<prost_types::Duration, E>
+where
+    E: Error,
+{
+    if nanos.unsigned_abs() > MAX_DURATION_NANOS {
+        return Err(Error::custom("duration out of range"));
+    }
+    if (seconds < 0 && nanos > 0) || (seconds > 0 && nanos < 0) {
+        return Err(Error::custom("inconsistent signs for duration"));
+    }
+    check_duration(prost_types::Duration { seconds, nanos })
+}
+
+fn check_duration<E>(duration: prost_types::Duration) -> Result<prost_types::Duration, E>
+where
+    E: Error,
+{
+    if duration.seconds.unsigned_abs() > MAX_DURATION_SECONDS {
+        return Err(Error::custom("duration out of range"));
+    }
+
+    Ok(duration)
+}
+
+impl<'a, 'de> Visitor<'de> for GoogleProtobufSecondsNanosVisitor<'a> {
+    type Value = (i64, i32);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a struct with seconds and nanos fields")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut seconds = 0;
+        let mut nanos = 0;
+        while let Some(key) = map.next_key::<Cow<str>>()? {
+            match key.as_ref() {
+                "seconds" => match map.next_value_seed(KindSeed(&Kind::Int64, self.0))? {
+                    Some(Value::I64(value)) => seconds = value,
+                    _ => unreachable!(),
+                },
+                "nanos" => match map.next_value_seed(KindSeed(&Kind::Int32, self.0))? {
+                    Some(Value::I32(value)) => nanos = value,
+                    _ => unreachable!(),
+                },
+                _ => return Err(Error::unknown_field(&key, &SECONDS_NANOS_FIELDS)),
+            }
+        }
+
+        Ok((seconds, nanos))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let seconds = seq
+            .next_element()?
+            .ok_or_else(|| Error::invalid_length(0, &self))?;
+        let nanos = seq
+            .next_element()?
+            .ok_or_else(|| Error::invalid_length(1, &self))?;
+
+        Ok((seconds, nanos))
+    }
+}
+
 impl<'de> Visitor<'de> for GoogleProtobufFieldMaskVisitor {
     type Value = prost_types::FieldMask;
 
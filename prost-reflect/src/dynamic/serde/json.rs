@@ -0,0 +1,52 @@
+use crate::{DeserializeOptions, DynamicMessage, MessageDescriptor, SerializeOptions};
+
+impl DynamicMessage {
+    /// Converts this message into a [`serde_json::Value`] using the encoding specified by `options`.
+    ///
+    /// This is equivalent to serializing with [`serde_json::value::Serializer`], and avoids
+    /// formatting the message as a string when the JSON is needed in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, SerializeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor);
+    /// message.set_field_by_name("foo", Value::I32(150));
+    ///
+    /// let json = message.to_json_value(&SerializeOptions::new()).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "foo": 150 }));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+    pub fn to_json_value(
+        &self,
+        options: &SerializeOptions,
+    ) -> Result<serde_json::Value, serde_json::Error> {
+        self.serialize_with_options(serde_json::value::Serializer, options)
+    }
+
+    /// Creates an instance of the message type described by `desc` from a [`serde_json::Value`], using
+    /// the encoding specified by `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, DeserializeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let json = serde_json::json!({ "foo": 150 });
+    /// let message =
+    ///     DynamicMessage::from_json_value(message_descriptor, json, &DeserializeOptions::new())
+    ///         .unwrap();
+    /// assert_eq!(message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+    pub fn from_json_value(
+        desc: MessageDescriptor,
+        value: serde_json::Value,
+        options: &DeserializeOptions,
+    ) -> Result<Self, serde_json::Error> {
+        Self::deserialize_with_options(desc, value, options)
+    }
+}
@@ -1,13 +1,24 @@
 mod case;
 mod de;
+#[cfg(feature = "serde-json")]
+mod json;
 mod ser;
 
+use std::{collections::HashMap, fmt, sync::Arc};
+
+#[cfg(feature = "serde-json")]
+use std::error::Error;
+
 use serde::{
     de::{DeserializeSeed, Deserializer},
     ser::{Serialize, Serializer},
 };
 
-use crate::{DynamicMessage, MessageDescriptor};
+use prost_types::FieldMask;
+
+use crate::{
+    field_mask::Tree, resolver::SchemaResolver, DescriptorPool, DynamicMessage, MessageDescriptor,
+};
 
 /// Options to control serialization of messages.
 #[derive(Debug, Clone)]
@@ -17,6 +28,17 @@ pub struct SerializeOptions {
     use_enum_numbers: bool,
     use_proto_field_name: bool,
     skip_default_fields: bool,
+    sort_keys: bool,
+    bytes_encoding: BytesEncoding,
+    timestamp_encoding: TimestampEncoding,
+    duration_encoding: DurationEncoding,
+    non_finite_floats: NonFiniteFloatEncoding,
+    float_precision: Option<u32>,
+    human_readable: Option<bool>,
+    field_mask: Option<Arc<Tree>>,
+    resolver: Option<Arc<dyn SchemaResolver>>,
+    #[cfg(feature = "serde-json")]
+    handlers: Option<Arc<HashMap<String, Arc<dyn JsonSerializeHandler>>>>,
 }
 
 /// Options to control deserialization of messages.
@@ -24,6 +46,112 @@ pub struct SerializeOptions {
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 pub struct DeserializeOptions {
     deny_unknown_fields: bool,
+    ignore_unknown_enum_values: bool,
+    capture_unknown_fields: bool,
+    case_insensitive_field_names: bool,
+    human_readable: Option<bool>,
+    field_aliases: Option<Arc<HashMap<(String, String), String>>>,
+    bytes_encoding: BytesEncoding,
+    timestamp_encoding: TimestampEncoding,
+    duration_encoding: DurationEncoding,
+    resolver: Option<Arc<dyn SchemaResolver>>,
+    #[cfg(feature = "serde-json")]
+    handlers: Option<Arc<HashMap<String, Arc<dyn JsonDeserializeHandler>>>>,
+}
+
+/// The encoding used to represent `bytes` fields as JSON strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub enum BytesEncoding {
+    /// Standard base64 encoding with padding, as required by the canonical JSON mapping.
+    Base64,
+    /// URL-safe base64 encoding with padding, using `-` and `_` instead of `+` and `/`.
+    Base64UrlSafe,
+    /// Lowercase hexadecimal encoding, with two digits per byte.
+    Hex,
+}
+
+/// The encoding used to represent `google.protobuf.Timestamp` values in JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub enum TimestampEncoding {
+    /// An [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339) string, such as
+    /// `"1972-01-01T10:00:20.021Z"`, as required by the canonical JSON mapping.
+    Rfc3339,
+    /// A number of seconds since the Unix epoch. Timestamps with a fractional number of seconds are
+    /// represented as floating point numbers, which may lose precision.
+    EpochSeconds,
+    /// A number of milliseconds since the Unix epoch. Timestamps with a fractional number of
+    /// milliseconds are represented as floating point numbers, which may lose precision.
+    EpochMillis,
+}
+
+/// The encoding used to represent `google.protobuf.Duration` values in JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub enum DurationEncoding {
+    /// A string with the suffix `s`, such as `"1.5s"`, as required by the canonical JSON mapping.
+    String,
+    /// A number of seconds. Durations with a fractional number of seconds are represented as floating
+    /// point numbers, which may lose precision.
+    Seconds,
+    /// An object with the integer fields `seconds` and `nanos`, such as
+    /// `{"seconds": 1, "nanos": 500000000}`.
+    Object,
+}
+
+/// The encoding used to represent non-finite `float` and `double` values, such as NaN and infinity, in
+/// JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub enum NonFiniteFloatEncoding {
+    /// The strings `"NaN"`, `"Infinity"` and `"-Infinity"`, as required by the canonical JSON mapping.
+    String,
+    /// A JSON `null`. Note that this cannot be deserialized back into the original value.
+    Null,
+    /// Serialization fails with an error.
+    Error,
+}
+
+/// A custom JSON mapping used to serialize messages of a given type.
+///
+/// Handlers are registered with [`SerializeOptions::json_handler`], and take precedence over the
+/// built-in mapping, including the special representations of well-known types.
+#[cfg(feature = "serde-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+pub trait JsonSerializeHandler: fmt::Debug + Send + Sync {
+    /// Converts `message` into its JSON representation.
+    fn serialize(
+        &self,
+        message: &DynamicMessage,
+        options: &SerializeOptions,
+    ) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>>;
+}
+
+/// A custom JSON mapping used to deserialize messages of a given type.
+///
+/// Handlers are registered with [`DeserializeOptions::json_handler`], and take precedence over the
+/// built-in mapping, including the special representations of well-known types.
+#[cfg(feature = "serde-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+pub trait JsonDeserializeHandler: fmt::Debug + Send + Sync {
+    /// Creates a message of the type described by `desc` from its JSON representation.
+    fn deserialize(
+        &self,
+        desc: &MessageDescriptor,
+        value: serde_json::Value,
+        options: &DeserializeOptions,
+    ) -> Result<DynamicMessage, Box<dyn Error + Send + Sync>>;
+}
+
+/// A JSON field which did not match any field of the message it was deserialized into.
+///
+/// Unknown fields are only kept if [`DeserializeOptions::capture_unknown_fields`] is enabled.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub struct UnknownJsonField {
+    name: String,
+    value: serde_value::Value,
 }
 
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
@@ -64,6 +192,47 @@ impl DynamicMessage {
         ser::serialize_message(self, serializer, options)
     }
 
+    /// Serialize the protobuf-encoded message `buf` of the type described by `desc` into `serializer`, using
+    /// the encoding specified by `options`.
+    ///
+    /// The output is the same as decoding `buf` with [`DynamicMessage::decode`] and then calling
+    /// [`serialize_with_options`][DynamicMessage::serialize_with_options], but nested messages are
+    /// serialized directly from their encoded bytes rather than first being decoded into a `DynamicMessage`.
+    /// Messages containing groups, well-known types, and types with a custom JSON handler are still decoded
+    /// before being serialized.
+    ///
+    /// Errors decoding `buf` are reported as custom errors of the serializer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, SerializeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut serializer = serde_json::Serializer::new(vec![]);
+    /// DynamicMessage::serialize_encoded(
+    ///     message_descriptor,
+    ///     b"\x08\x96\x01".as_ref(),
+    ///     &mut serializer,
+    ///     &SerializeOptions::new(),
+    /// )
+    /// .unwrap();
+    /// assert_eq!(serializer.into_inner(), b"{\"foo\":150}");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn serialize_encoded<S>(
+        desc: MessageDescriptor,
+        buf: &[u8],
+        serializer: S,
+        options: &SerializeOptions,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ser::serialize_encoded(desc, buf, serializer, options)
+    }
+
     /// Deserialize an instance of the message type described by `desc` from `deserializer`.
     #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
     pub fn deserialize<'de, D>(desc: MessageDescriptor, deserializer: D) -> Result<Self, D::Error>
@@ -75,6 +244,10 @@ impl DynamicMessage {
 
     /// Deserialize an instance of the message type described by `desc` from `deserializer`, using
     /// the encoding specified by `options`.
+    ///
+    /// If a nested value is invalid, the error message is prefixed with the path to it, for example
+    /// `servers[3].tls.minVersion: unrecognized enum value 'TLS_1_4'`. Path components are the JSON keys
+    /// as they appear in the input.
     #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
     pub fn deserialize_with_options<'de, D>(
         desc: MessageDescriptor,
@@ -84,7 +257,89 @@ impl DynamicMessage {
     where
         D: Deserializer<'de>,
     {
-        de::deserialize_message(&desc, deserializer, options)
+        de::with_error_path(|| de::deserialize_message(&desc, deserializer, options))
+    }
+
+    /// Gets the unknown JSON fields captured when this message was deserialized.
+    ///
+    /// This is always empty unless [`DeserializeOptions::capture_unknown_fields`] was enabled.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn unknown_json_fields(&self) -> &[UnknownJsonField] {
+        match &self.unknown_json_fields {
+            Some(fields) => fields,
+            None => &[],
+        }
+    }
+
+    /// Clears the unknown JSON fields captured when this message was deserialized.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn clear_unknown_json_fields(&mut self) {
+        self.unknown_json_fields = None;
+    }
+
+    /// Applies a [JSON merge patch](https://www.rfc-editor.org/rfc/rfc7386) to this message.
+    ///
+    /// The patch must be an object. Fields in the patch which are `null` are cleared, and all other fields
+    /// are set to the value in the patch, using the [canonical JSON encoding](https://developers.google.com/protocol-buffers/docs/proto3#json).
+    /// If a singular message field is given an object value, the object is applied recursively as a patch
+    /// to the existing message. Similarly, entries of map fields are merged, with `null` values removing
+    /// an entry. Repeated fields and well-known types with a special JSON representation, such as
+    /// `google.protobuf.Struct`, are replaced entirely.
+    ///
+    /// If an error occurs, some fields of the patch may already have been applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor);
+    /// message.set_field_by_name("foo", Value::I32(150));
+    ///
+    /// let mut deserializer = serde_json::de::Deserializer::from_str(r#"{ "foo": null }"#);
+    /// message.apply_merge_patch(&mut deserializer).unwrap();
+    /// deserializer.end().unwrap();
+    ///
+    /// assert!(!message.has_field_by_name("foo"));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn apply_merge_patch<'de, D>(&mut self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.apply_merge_patch_with_options(deserializer, &Default::default())
+    }
+
+    /// Applies a [JSON merge patch](https://www.rfc-editor.org/rfc/rfc7386) to this message, using the
+    /// encoding specified by `options`.
+    ///
+    /// See [`apply_merge_patch`][Self::apply_merge_patch] for more details.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn apply_merge_patch_with_options<'de, D>(
+        &mut self,
+        deserializer: D,
+        options: &DeserializeOptions,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de::with_error_path(|| de::apply_merge_patch(self, deserializer, options))
+    }
+}
+
+impl UnknownJsonField {
+    /// Gets the JSON key of this field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the value of this field, as it appeared in the input.
+    ///
+    /// The value can be converted to any serde data format, for example using
+    /// `serde_json::to_value`.
+    pub fn value(&self) -> &(impl Serialize + fmt::Debug) {
+        &self.value
     }
 }
 
@@ -94,6 +349,17 @@ impl DeserializeOptions {
     pub const fn new() -> Self {
         DeserializeOptions {
             deny_unknown_fields: true,
+            ignore_unknown_enum_values: false,
+            capture_unknown_fields: false,
+            case_insensitive_field_names: false,
+            human_readable: None,
+            field_aliases: None,
+            bytes_encoding: BytesEncoding::Base64,
+            timestamp_encoding: TimestampEncoding::Rfc3339,
+            duration_encoding: DurationEncoding::String,
+            resolver: None,
+            #[cfg(feature = "serde-json")]
+            handlers: None,
         }
     }
 
@@ -104,6 +370,161 @@ impl DeserializeOptions {
         self.deny_unknown_fields = yes;
         self
     }
+
+    /// Whether to keep the keys and values of unknown JSON fields when
+    /// [`deny_unknown_fields`][Self::deny_unknown_fields] is disabled, instead of discarding them.
+    ///
+    /// Captured fields are stored in the message they were found in, and can be accessed with
+    /// [`DynamicMessage::unknown_json_fields`]. They are written back out when the message is
+    /// serialized to JSON, so a message can be round-tripped without losing data.
+    ///
+    /// The default value is `false`.
+    pub const fn capture_unknown_fields(mut self, yes: bool) -> Self {
+        self.capture_unknown_fields = yes;
+        self
+    }
+
+    /// Whether to ignore enum values which are not recognized, instead of returning an error.
+    ///
+    /// If `true`, enum values given as a string which is not the name of a value of the enum, or as a
+    /// number which does not fit in a 32-bit integer, are skipped. A singular field with such a value
+    /// is left unset, and the value is omitted from repeated fields and map fields.
+    ///
+    /// The default value is `false`.
+    pub const fn ignore_unknown_enum_values(mut self, yes: bool) -> Self {
+        self.ignore_unknown_enum_values = yes;
+        self
+    }
+
+    /// Whether to match JSON keys to field names ignoring ASCII case.
+    ///
+    /// Keys are first matched exactly against the JSON name and the proto name of each field, and
+    /// then against any aliases registered with [`field_alias`][Self::field_alias]. If this option
+    /// is enabled and no exact match is found, the same names are compared ignoring case.
+    ///
+    /// The default value is `false`.
+    pub const fn case_insensitive_field_names(mut self, yes: bool) -> Self {
+        self.case_insensitive_field_names = yes;
+        self
+    }
+
+    /// Whether to expect the JSON mapping, or the compact encoding produced by
+    /// [`SerializeOptions::human_readable`] for binary formats.
+    ///
+    /// If `None`, the result of [`Deserializer::is_human_readable`] is used.
+    ///
+    /// The default value is `None`.
+    pub const fn human_readable(mut self, yes: Option<bool>) -> Self {
+        self.human_readable = yes;
+        self
+    }
+
+    /// Registers an alternative JSON key for a field.
+    ///
+    /// `field` is the fully-qualified name of the field, for example `package.MyMessage.foo`. When
+    /// deserializing a message of the containing type, a key equal to `alias` sets this field.
+    /// Aliases take precedence over case-insensitive matching, but not over the field's own names.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `field` is not a qualified name containing at least one `.`.
+    pub fn field_alias(mut self, field: &str, alias: impl Into<String>) -> Self {
+        let (message, name) = field
+            .rsplit_once('.')
+            .unwrap_or_else(|| panic!("'{}' is not a fully-qualified field name", field));
+        Arc::make_mut(self.field_aliases.get_or_insert_with(Default::default))
+            .insert((message.to_owned(), alias.into()), name.to_owned());
+        self
+    }
+
+    /// The encoding expected for `bytes` fields.
+    ///
+    /// Both the standard and URL-safe base64 alphabets are always accepted, as required by the
+    /// canonical JSON mapping, so [`BytesEncoding::Base64`] and [`BytesEncoding::Base64UrlSafe`]
+    /// behave identically. If [`BytesEncoding::Hex`] is chosen, strings are decoded as hexadecimal
+    /// where possible, and as base64 otherwise. Note that some strings, such as `"abcd"`, are valid in
+    /// both encodings.
+    ///
+    /// The default value is [`BytesEncoding::Base64`].
+    pub const fn bytes_encoding(mut self, encoding: BytesEncoding) -> Self {
+        self.bytes_encoding = encoding;
+        self
+    }
+
+    /// The encoding expected for `google.protobuf.Timestamp` values.
+    ///
+    /// RFC 3339 strings are always accepted. If [`TimestampEncoding::EpochSeconds`] or
+    /// [`TimestampEncoding::EpochMillis`] is chosen, numbers are also accepted, and interpreted as
+    /// seconds or milliseconds since the Unix epoch respectively.
+    ///
+    /// The default value is [`TimestampEncoding::Rfc3339`].
+    pub const fn timestamp_encoding(mut self, encoding: TimestampEncoding) -> Self {
+        self.timestamp_encoding = encoding;
+        self
+    }
+
+    /// The encoding expected for `google.protobuf.Duration` values.
+    ///
+    /// Strings such as `"1.5s"` are always accepted. If [`DurationEncoding::Seconds`] or
+    /// [`DurationEncoding::Object`] is chosen, both numbers of seconds and objects with `seconds` and
+    /// `nanos` fields are also accepted.
+    ///
+    /// The default value is [`DurationEncoding::String`].
+    pub const fn duration_encoding(mut self, encoding: DurationEncoding) -> Self {
+        self.duration_encoding = encoding;
+        self
+    }
+
+    /// Sets a resolver used to look up the message types of `google.protobuf.Any` fields.
+    ///
+    /// Type URLs are passed to the resolver first, and looked up in the descriptor pool of the message
+    /// being deserialized if the resolver does not recognize them. This allows payload types to come
+    /// from a schema registry instead of the pool, and allows type URLs with prefixes other than
+    /// `type.googleapis.com/`.
+    ///
+    /// By default no resolver is set.
+    pub fn resolver(mut self, resolver: impl SchemaResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Registers a custom JSON mapping for the message type named `message`, for example
+    /// `google.type.Date`.
+    ///
+    /// The handler is used for all messages of this type, including `google.protobuf.Any` payloads,
+    /// which are expected in the `{"@type": ..., "value": ...}` form used for well-known types.
+    /// Registering a handler for a well-known type replaces its built-in mapping.
+    ///
+    /// By default no handlers are registered.
+    #[cfg(feature = "serde-json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+    pub fn json_handler(
+        mut self,
+        message: impl Into<String>,
+        handler: impl JsonDeserializeHandler + 'static,
+    ) -> Self {
+        Arc::make_mut(self.handlers.get_or_insert_with(Default::default))
+            .insert(message.into(), Arc::new(handler));
+        self
+    }
+
+    /// Returns whether messages of this type have a JSON representation other than an object of
+    /// their fields.
+    fn has_special_mapping(&self, full_name: &str) -> bool {
+        #[cfg(feature = "serde-json")]
+        if self.json_handler_for(full_name).is_some() {
+            return true;
+        }
+        is_well_known_type(full_name)
+    }
+
+    #[cfg(feature = "serde-json")]
+    fn json_handler_for(&self, full_name: &str) -> Option<&dyn JsonDeserializeHandler> {
+        self.handlers
+            .as_ref()
+            .and_then(|handlers| handlers.get(full_name))
+            .map(|handler| &**handler)
+    }
 }
 
 impl Default for DeserializeOptions {
@@ -121,6 +542,17 @@ impl SerializeOptions {
             use_enum_numbers: false,
             use_proto_field_name: false,
             skip_default_fields: true,
+            sort_keys: false,
+            bytes_encoding: BytesEncoding::Base64,
+            timestamp_encoding: TimestampEncoding::Rfc3339,
+            duration_encoding: DurationEncoding::String,
+            non_finite_floats: NonFiniteFloatEncoding::String,
+            float_precision: None,
+            human_readable: None,
+            field_mask: None,
+            resolver: None,
+            #[cfg(feature = "serde-json")]
+            handlers: None,
         }
     }
 
@@ -165,6 +597,165 @@ impl SerializeOptions {
         self.skip_default_fields = yes;
         self
     }
+
+    /// Whether to sort the keys of all JSON objects.
+    ///
+    /// By default, message fields are serialized in order of field number, and map entries in an
+    /// unspecified order. If this option is enabled, the keys of every object, including nested
+    /// messages, maps and `google.protobuf.Struct` values, are sorted in lexicographic order. Combined
+    /// with a serializer which produces no insignificant whitespace, such as `serde_json::to_vec`,
+    /// this gives a canonical encoding in which equal messages always produce identical bytes,
+    /// suitable for hashing or signing.
+    ///
+    /// Note that enabling this option requires buffering the whole message before it is written.
+    ///
+    /// The default value is `false`.
+    pub const fn sort_keys(mut self, yes: bool) -> Self {
+        self.sort_keys = yes;
+        self
+    }
+
+    /// The encoding used for `bytes` fields, including `google.protobuf.BytesValue`.
+    ///
+    /// The canonical JSON mapping requires standard base64. Other encodings may not be understood by
+    /// other implementations.
+    ///
+    /// The default value is [`BytesEncoding::Base64`].
+    pub const fn bytes_encoding(mut self, encoding: BytesEncoding) -> Self {
+        self.bytes_encoding = encoding;
+        self
+    }
+
+    /// The encoding used for `google.protobuf.Timestamp` values.
+    ///
+    /// The canonical JSON mapping requires RFC 3339 strings. Numeric encodings are useful for
+    /// systems which store timestamps as numbers, but may not be understood by other implementations.
+    ///
+    /// The default value is [`TimestampEncoding::Rfc3339`].
+    pub const fn timestamp_encoding(mut self, encoding: TimestampEncoding) -> Self {
+        self.timestamp_encoding = encoding;
+        self
+    }
+
+    /// The encoding used for `google.protobuf.Duration` values.
+    ///
+    /// The canonical JSON mapping requires strings such as `"1.5s"`. Numeric encodings are useful for
+    /// metrics pipelines, but may not be understood by other implementations.
+    ///
+    /// The default value is [`DurationEncoding::String`].
+    pub const fn duration_encoding(mut self, encoding: DurationEncoding) -> Self {
+        self.duration_encoding = encoding;
+        self
+    }
+
+    /// The encoding used for non-finite values of `float` and `double` fields, including
+    /// `google.protobuf.FloatValue` and `google.protobuf.DoubleValue`.
+    ///
+    /// The default value is [`NonFiniteFloatEncoding::String`].
+    pub const fn non_finite_floats(mut self, encoding: NonFiniteFloatEncoding) -> Self {
+        self.non_finite_floats = encoding;
+        self
+    }
+
+    /// The number of significant digits used for `float` and `double` fields, including
+    /// `google.protobuf.FloatValue` and `google.protobuf.DoubleValue`.
+    ///
+    /// If set, values are rounded to the given number of significant digits before being serialized,
+    /// so `3.14159` with a precision of `3` is serialized as `3.14`. A precision of `0` is treated as
+    /// `1`. If `None`, values are serialized using the shortest representation which round-trips to
+    /// the same value.
+    ///
+    /// The default value is `None`.
+    pub const fn float_precision(mut self, digits: Option<u32>) -> Self {
+        self.float_precision = digits;
+        self
+    }
+
+    /// Whether to use the JSON mapping, or a more compact encoding suited to binary formats.
+    ///
+    /// If `false`, `bytes` fields are serialized as byte arrays, 64-bit integers are always
+    /// serialized as integers, and `google.protobuf.Timestamp` and `google.protobuf.Duration` are
+    /// serialized as structs with `seconds` and `nanos` fields. If `None`, the result of
+    /// [`Serializer::is_human_readable`] is used, so binary formats such as CBOR or bincode use the
+    /// compact encoding automatically.
+    ///
+    /// The default value is `None`.
+    pub const fn human_readable(mut self, yes: Option<bool>) -> Self {
+        self.human_readable = yes;
+        self
+    }
+
+    /// Sets a field mask selecting which fields are serialized.
+    ///
+    /// Only fields covered by a path of `mask` are emitted, so a path such as `"a.b"` serializes the
+    /// field `a` containing only its field `b`. A path component of `*` matches any field not otherwise
+    /// named at that level, and a path selecting a repeated or map field of messages applies the rest
+    /// of the path to each element. Paths use the proto field names, regardless of
+    /// [`use_proto_field_name`][Self::use_proto_field_name]. An empty mask selects all fields.
+    ///
+    /// Extension fields are only serialized if selected by a wildcard, and the mask is not applied
+    /// within well-known types with a special JSON representation.
+    ///
+    /// By default no field mask is set.
+    pub fn field_mask(mut self, mask: FieldMask) -> Self {
+        let tree = Tree::from_mask(&mask);
+        self.field_mask = if tree.is_leaf() {
+            None
+        } else {
+            Some(Arc::new(tree))
+        };
+        self
+    }
+
+    /// Sets a resolver used to look up the message types of `google.protobuf.Any` fields.
+    ///
+    /// Type URLs are passed to the resolver first, and looked up in the descriptor pool of the message
+    /// being serialized if the resolver does not recognize them.
+    ///
+    /// By default no resolver is set.
+    pub fn resolver(mut self, resolver: impl SchemaResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Registers a custom JSON mapping for the message type named `message`, for example
+    /// `google.type.Date`.
+    ///
+    /// The handler is used for all messages of this type. As with well-known types, the field mask is
+    /// not applied within these messages, and `google.protobuf.Any` payloads of this type are written
+    /// as `{"@type": ..., "value": ...}`. Registering a handler for a well-known type replaces its
+    /// built-in mapping.
+    ///
+    /// By default no handlers are registered.
+    #[cfg(feature = "serde-json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+    pub fn json_handler(
+        mut self,
+        message: impl Into<String>,
+        handler: impl JsonSerializeHandler + 'static,
+    ) -> Self {
+        Arc::make_mut(self.handlers.get_or_insert_with(Default::default))
+            .insert(message.into(), Arc::new(handler));
+        self
+    }
+
+    /// Returns whether messages of this type have a JSON representation other than an object of
+    /// their fields.
+    fn has_special_mapping(&self, full_name: &str) -> bool {
+        #[cfg(feature = "serde-json")]
+        if self.json_handler_for(full_name).is_some() {
+            return true;
+        }
+        is_well_known_type(full_name)
+    }
+
+    #[cfg(feature = "serde-json")]
+    fn json_handler_for(&self, full_name: &str) -> Option<&dyn JsonSerializeHandler> {
+        self.handlers
+            .as_ref()
+            .and_then(|handlers| handlers.get(full_name))
+            .map(|handler| &**handler)
+    }
 }
 
 impl Default for SerializeOptions {
@@ -179,6 +770,34 @@ const MAX_DURATION_NANOS: u32 = 999_999_999;
 const MIN_TIMESTAMP_SECONDS: i64 = -62135596800;
 const MAX_TIMESTAMP_SECONDS: i64 = 253402300799;
 
+/// Looks up the message type of a `google.protobuf.Any` payload, using the resolver if one is set
+/// and falling back to `pool`.
+fn resolve_any_type(
+    pool: &DescriptorPool,
+    type_url: &str,
+    resolver: Option<&dyn SchemaResolver>,
+) -> Result<MessageDescriptor, String> {
+    if let Some(resolver) = resolver {
+        match resolver.resolve_type_url(type_url) {
+            Ok(Some(message_desc)) => return Ok(message_desc),
+            Ok(None) => (),
+            Err(err) => {
+                return Err(format!(
+                    "failed to resolve type url '{}': {}",
+                    type_url, err
+                ))
+            }
+        }
+    }
+
+    match type_url.strip_prefix("type.googleapis.com/") {
+        Some(message_name) => pool
+            .get_message_by_name(message_name)
+            .ok_or_else(|| format!("message '{}' not found", message_name)),
+        None => Err(format!("unsupported type url '{}'", type_url)),
+    }
+}
+
 fn is_well_known_type(full_name: &str) -> bool {
     matches!(
         full_name,
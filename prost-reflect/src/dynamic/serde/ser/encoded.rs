@@ -0,0 +1,423 @@
+use std::collections::{BTreeMap, HashMap};
+
+use prost::{
+    encoding::{check_wire_type, decode_key, decode_varint, skip_field, DecodeContext, WireType},
+    DecodeError, Message,
+};
+use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::{
+    descriptor::{Kind, MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER},
+    dynamic::{
+        fields::FieldDescriptorLike, serde::SerializeOptions, DecodeOptions, DynamicMessage,
+        MapKey, Value,
+    },
+    MessageDescriptor,
+};
+
+use super::{is_human_readable, masked_options, SerializeWrapper, ValueAndKind};
+
+/// The same limit used by prost when decoding nested messages.
+const RECURSION_LIMIT: u32 = 100;
+
+pub(in crate::dynamic::serde) fn serialize_encoded<S>(
+    desc: MessageDescriptor,
+    buf: &[u8],
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let message = EncodedMessage {
+        desc,
+        chunks: vec![buf],
+        depth: 0,
+    };
+
+    if options.sort_keys {
+        // Objects are buffered as `serde_value::Value`s, whose maps are ordered by key.
+        let options = SerializeOptions {
+            sort_keys: false,
+            human_readable: Some(is_human_readable(&serializer, options)),
+            ..options.clone()
+        };
+        let value = serde_value::to_value(SerializeWrapper {
+            value: &message,
+            options: &options,
+        })
+        .map_err(Error::custom)?;
+        return value.serialize(serializer);
+    }
+
+    SerializeWrapper {
+        value: &message,
+        options,
+    }
+    .serialize(serializer)
+}
+
+/// An encoded message, split across one or more buffers whose concatenation is the full message.
+struct EncodedMessage<'a> {
+    desc: MessageDescriptor,
+    chunks: Vec<&'a [u8]>,
+    depth: u32,
+}
+
+/// The encoded records of a field, each starting after the field key.
+type Records<'a> = Vec<(WireType, &'a [u8])>;
+
+/// The value of a field, with nested messages left encoded.
+enum EncodedValue<'a> {
+    Decoded(Value),
+    Message(Vec<&'a [u8]>),
+    MessageList(Vec<&'a [u8]>),
+    MessageMap(HashMap<MapKey, Vec<&'a [u8]>>),
+}
+
+impl<'a> EncodedMessage<'a> {
+    /// Groups the records in this message by field number, or returns `None` if the message contains groups.
+    fn scan(&self) -> Result<Option<BTreeMap<u32, Records<'a>>>, DecodeError> {
+        let mut fields: BTreeMap<u32, Records<'a>> = BTreeMap::new();
+        for &chunk in &self.chunks {
+            let mut buf = chunk;
+            while !buf.is_empty() {
+                let (number, wire_type) = decode_key(&mut buf)?;
+                let start = buf;
+                skip_field(wire_type, number, &mut buf, DecodeContext::default())?;
+                let record = &start[..start.len() - buf.len()];
+
+                if let Some(field_desc) = self.desc.get_field(number) {
+                    if field_desc.is_group() || wire_type == WireType::StartGroup {
+                        return Ok(None);
+                    }
+                    // Setting a oneof field clears the other fields in the oneof.
+                    if let Some(oneof_desc) = field_desc.containing_oneof() {
+                        for oneof_field in oneof_desc.fields() {
+                            if oneof_field.number() != number {
+                                fields.remove(&oneof_field.number());
+                            }
+                        }
+                    }
+                } else if let Some(extension_desc) = self.desc.get_extension(number) {
+                    if extension_desc.is_group() || wire_type == WireType::StartGroup {
+                        return Ok(None);
+                    }
+                } else {
+                    continue;
+                }
+
+                fields.entry(number).or_default().push((wire_type, record));
+            }
+        }
+        Ok(Some(fields))
+    }
+
+    fn decode(&self) -> Result<DynamicMessage, DecodeError> {
+        let mut message = DynamicMessage::new(self.desc.clone());
+        for chunk in &self.chunks {
+            message.merge(*chunk)?;
+        }
+        Ok(message)
+    }
+
+    fn nested(&self, desc: MessageDescriptor, chunks: Vec<&'a [u8]>) -> EncodedMessage<'a> {
+        EncodedMessage {
+            desc,
+            chunks,
+            depth: self.depth + 1,
+        }
+    }
+}
+
+impl<'a, 'b> Serialize for SerializeWrapper<'a, EncodedMessage<'b>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let message = self.value;
+        if message.depth >= RECURSION_LIMIT {
+            return Err(Error::custom(DecodeError::new("recursion limit reached")));
+        }
+
+        let fields = if self.options.has_special_mapping(message.desc.full_name()) {
+            None
+        } else {
+            message.scan().map_err(Error::custom)?
+        };
+        let fields = match fields {
+            Some(fields) => fields,
+            None => {
+                let message = message.decode().map_err(Error::custom)?;
+                return SerializeWrapper {
+                    value: &message,
+                    options: self.options,
+                }
+                .serialize(serializer);
+            }
+        };
+
+        let mut map = serializer.serialize_map(None)?;
+        if self.options.skip_default_fields {
+            for (&number, records) in &fields {
+                if let Some(field_desc) = message.desc.get_field(number) {
+                    let name = if self.options.use_proto_field_name {
+                        field_desc.name()
+                    } else {
+                        field_desc.json_name()
+                    };
+                    serialize_field(
+                        &mut map,
+                        message,
+                        name,
+                        field_desc.name(),
+                        &field_desc,
+                        Some(records),
+                        self.options,
+                    )?;
+                } else if let Some(extension_desc) = message.desc.get_extension(number) {
+                    serialize_field(
+                        &mut map,
+                        message,
+                        extension_desc.json_name(),
+                        extension_desc.full_name(),
+                        &extension_desc,
+                        Some(records),
+                        self.options,
+                    )?;
+                }
+            }
+        } else {
+            for field_desc in message.desc.fields() {
+                let records = fields.get(&field_desc.number());
+                if records.is_none() && field_desc.supports_presence() {
+                    continue;
+                }
+
+                let name = if self.options.use_proto_field_name {
+                    field_desc.name()
+                } else {
+                    field_desc.json_name()
+                };
+                serialize_field(
+                    &mut map,
+                    message,
+                    name,
+                    field_desc.name(),
+                    &field_desc,
+                    records,
+                    self.options,
+                )?;
+            }
+            for extension_desc in message.desc.extensions() {
+                let records = fields.get(&extension_desc.number());
+                if records.is_none() && extension_desc.supports_presence() {
+                    continue;
+                }
+
+                serialize_field(
+                    &mut map,
+                    message,
+                    extension_desc.json_name(),
+                    extension_desc.full_name(),
+                    &extension_desc,
+                    records,
+                    self.options,
+                )?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// Serializes a field of `message` from its encoded `records`, or its default value if it has none.
+fn serialize_field<M>(
+    map: &mut M,
+    message: &EncodedMessage,
+    name: &str,
+    mask_name: &str,
+    field_desc: &impl FieldDescriptorLike,
+    records: Option<&Records>,
+    options: &SerializeOptions,
+) -> Result<(), M::Error>
+where
+    M: SerializeMap,
+{
+    let field_options = match masked_options(options, mask_name) {
+        Some(field_options) => field_options,
+        None => return Ok(()),
+    };
+
+    let value = match records {
+        Some(records) => decode_field(field_desc, records).map_err(Error::custom)?,
+        None => EncodedValue::Decoded(field_desc.default_value()),
+    };
+    if let EncodedValue::Decoded(value) = &value {
+        if options.skip_default_fields && !field_desc.has(value) {
+            return Ok(());
+        }
+    }
+
+    let kind = field_desc.kind();
+    map.serialize_entry(
+        name,
+        &SerializeWrapper {
+            value: &EncodedValueAndKind {
+                message,
+                value: &value,
+                kind: &kind,
+            },
+            options: &field_options,
+        },
+    )
+}
+
+fn decode_field<'a>(
+    field_desc: &impl FieldDescriptorLike,
+    records: &Records<'a>,
+) -> Result<EncodedValue<'a>, DecodeError> {
+    let ctx = DecodeContext::default();
+    match field_desc.kind() {
+        Kind::Message(map_entry) if field_desc.is_map() => {
+            let key_desc = map_entry.map_entry_key_field();
+            let value_desc = map_entry.map_entry_value_field();
+            if !matches!(value_desc.kind(), Kind::Message(_)) {
+                return decode_value(field_desc, records);
+            }
+
+            let mut values = HashMap::with_capacity(records.len());
+            for &(wire_type, record) in records {
+                let mut buf = length_delimited_payload(wire_type, record)?;
+                let mut key = MapKey::default_value(&key_desc.kind());
+                let mut value = Vec::new();
+                while !buf.is_empty() {
+                    let (number, wire_type) = decode_key(&mut buf)?;
+                    match number {
+                        MAP_ENTRY_KEY_NUMBER => key.merge_field(
+                            &key_desc,
+                            wire_type,
+                            &mut buf,
+                            ctx.clone(),
+                            &DecodeOptions::new(),
+                        )?,
+                        MAP_ENTRY_VALUE_NUMBER => {
+                            let start = buf;
+                            skip_field(wire_type, number, &mut buf, ctx.clone())?;
+                            let record = &start[..start.len() - buf.len()];
+                            value.push(length_delimited_payload(wire_type, record)?);
+                        }
+                        _ => skip_field(wire_type, number, &mut buf, ctx.clone())?,
+                    }
+                }
+                values.insert(key, value);
+            }
+            Ok(EncodedValue::MessageMap(values))
+        }
+        Kind::Message(_) => {
+            let payloads = records
+                .iter()
+                .map(|&(wire_type, record)| length_delimited_payload(wire_type, record))
+                .collect::<Result<_, _>>()?;
+            if field_desc.is_list() {
+                Ok(EncodedValue::MessageList(payloads))
+            } else {
+                Ok(EncodedValue::Message(payloads))
+            }
+        }
+        _ => decode_value(field_desc, records),
+    }
+}
+
+fn decode_value<'a>(
+    field_desc: &impl FieldDescriptorLike,
+    records: &Records<'a>,
+) -> Result<EncodedValue<'a>, DecodeError> {
+    let mut value = field_desc.default_value();
+    for &(wire_type, mut record) in records {
+        value.merge_field(
+            field_desc,
+            wire_type,
+            &mut record,
+            DecodeContext::default(),
+            &DecodeOptions::new(),
+        )?;
+    }
+    Ok(EncodedValue::Decoded(value))
+}
+
+/// Strips the length prefix from a length-delimited record.
+fn length_delimited_payload(wire_type: WireType, mut record: &[u8]) -> Result<&[u8], DecodeError> {
+    check_wire_type(WireType::LengthDelimited, wire_type)?;
+    decode_varint(&mut record)?;
+    Ok(record)
+}
+
+struct EncodedValueAndKind<'a, 'b> {
+    message: &'a EncodedMessage<'b>,
+    value: &'a EncodedValue<'b>,
+    kind: &'a Kind,
+}
+
+impl<'a, 'b, 'c> Serialize for SerializeWrapper<'a, EncodedValueAndKind<'b, 'c>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let EncodedValueAndKind {
+            message,
+            value,
+            kind,
+        } = self.value;
+        let message_desc = match (value, kind) {
+            (EncodedValue::Decoded(value), kind) => {
+                return SerializeWrapper {
+                    value: &ValueAndKind { value, kind },
+                    options: self.options,
+                }
+                .serialize(serializer)
+            }
+            (_, Kind::Message(message_desc)) => message_desc,
+            (_, kind) => panic!("mismatch between encoded message and type {:?}", kind),
+        };
+
+        match value {
+            EncodedValue::Decoded(_) => unreachable!(),
+            EncodedValue::Message(chunks) => SerializeWrapper {
+                value: &message.nested(message_desc.clone(), chunks.clone()),
+                options: self.options,
+            }
+            .serialize(serializer),
+            EncodedValue::MessageList(chunks) => {
+                let mut list = serializer.serialize_seq(Some(chunks.len()))?;
+                for &chunk in chunks {
+                    list.serialize_element(&SerializeWrapper {
+                        value: &message.nested(message_desc.clone(), vec![chunk]),
+                        options: self.options,
+                    })?;
+                }
+                list.end()
+            }
+            EncodedValue::MessageMap(values) => {
+                let value_desc = match message_desc.map_entry_value_field().kind() {
+                    Kind::Message(value_desc) => value_desc,
+                    kind => panic!("mismatch between encoded map value and type {:?}", kind),
+                };
+
+                let mut map = serializer.serialize_map(Some(values.len()))?;
+                for (key, chunks) in values {
+                    map.serialize_entry(
+                        &SerializeWrapper {
+                            value: key,
+                            options: self.options,
+                        },
+                        &SerializeWrapper {
+                            value: &message.nested(value_desc.clone(), chunks.clone()),
+                            options: self.options,
+                        },
+                    )?;
+                }
+                map.end()
+            }
+        }
+    }
+}
@@ -1,15 +1,24 @@
+mod encoded;
 mod wkt;
 
+use std::{borrow::Cow, fmt, sync::Arc};
+
 use base64::display::Base64Display;
 
-use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, Serializer};
 
 use crate::{
     descriptor::Kind,
-    dynamic::{fields::ValueAndDescriptor, serde::SerializeOptions, DynamicMessage, MapKey, Value},
+    dynamic::{
+        fields::ValueAndDescriptor,
+        serde::{BytesEncoding, NonFiniteFloatEncoding, SerializeOptions},
+        DynamicMessage, MapKey, Value,
+    },
     ReflectMessage,
 };
 
+pub(super) use self::encoded::serialize_encoded;
+
 struct SerializeWrapper<'a, T> {
     value: &'a T,
     options: &'a SerializeOptions,
@@ -23,6 +32,21 @@ pub(super) fn serialize_message<S>(
 where
     S: Serializer,
 {
+    if options.sort_keys {
+        // Objects are buffered as `serde_value::Value`s, whose maps are ordered by key.
+        let options = SerializeOptions {
+            sort_keys: false,
+            human_readable: Some(is_human_readable(&serializer, options)),
+            ..options.clone()
+        };
+        let value = serde_value::to_value(SerializeWrapper {
+            value: message,
+            options: &options,
+        })
+        .map_err(Error::custom)?;
+        return value.serialize(serializer);
+    }
+
     SerializeWrapper {
         value: message,
         options,
@@ -36,8 +60,29 @@ impl<'a> Serialize for SerializeWrapper<'a, DynamicMessage> {
         S: Serializer,
     {
         let message_desc = self.value.descriptor();
+
+        #[cfg(feature = "serde-json")]
+        if let Some(handler) = self.options.json_handler_for(message_desc.full_name()) {
+            let options = SerializeOptions {
+                field_mask: None,
+                ..self.options.clone()
+            };
+            return handler
+                .serialize(self.value, &options)
+                .map_err(Error::custom)?
+                .serialize(serializer);
+        }
+
         if let Some(serialize) = wkt::get_well_known_type_serializer(message_desc.full_name()) {
-            serialize(self.value, serializer, self.options)
+            if self.options.field_mask.is_some() {
+                let options = SerializeOptions {
+                    field_mask: None,
+                    ..self.options.clone()
+                };
+                serialize(self.value, serializer, &options)
+            } else {
+                serialize(self.value, serializer, self.options)
+            }
         } else {
             let mut map = serializer.serialize_map(None)?;
             serialize_dynamic_message_fields(&mut map, self.value, self.options)?;
@@ -56,31 +101,47 @@ where
 {
     if options.skip_default_fields {
         for field in value.fields.iter(&value.desc) {
-            let (name, value, ref kind) = match field {
+            let (name, value, ref kind, field_options) = match field {
                 ValueAndDescriptor::Field(value, ref field_desc) => {
                     let name = if options.use_proto_field_name {
                         field_desc.name()
                     } else {
                         field_desc.json_name()
                     };
-                    (name, value, field_desc.kind())
-                }
-                ValueAndDescriptor::Extension(value, ref extension_desc) => {
-                    (extension_desc.json_name(), value, extension_desc.kind())
+                    (
+                        name,
+                        value,
+                        field_desc.kind(),
+                        masked_options(options, field_desc.name()),
+                    )
                 }
+                ValueAndDescriptor::Extension(value, ref extension_desc) => (
+                    extension_desc.json_name(),
+                    value,
+                    extension_desc.kind(),
+                    masked_options(options, extension_desc.full_name()),
+                ),
                 ValueAndDescriptor::Unknown(_, _) => continue,
             };
+            let field_options = match field_options {
+                Some(field_options) => field_options,
+                None => continue,
+            };
 
             map.serialize_entry(
                 name,
                 &SerializeWrapper {
                     value: &ValueAndKind { value, kind },
-                    options,
+                    options: &field_options,
                 },
             )?;
         }
     } else {
         for field_desc in value.desc.fields() {
+            let field_options = match masked_options(options, field_desc.name()) {
+                Some(field_options) => field_options,
+                None => continue,
+            };
             if !field_desc.supports_presence() || value.fields.has(&field_desc) {
                 let name = if options.use_proto_field_name {
                     field_desc.name()
@@ -95,12 +156,16 @@ where
                             value: value.fields.get(&field_desc).as_ref(),
                             kind: &field_desc.kind(),
                         },
-                        options,
+                        options: &field_options,
                     },
                 )?;
             }
         }
         for extension_desc in value.desc.extensions() {
+            let field_options = match masked_options(options, extension_desc.full_name()) {
+                Some(field_options) => field_options,
+                None => continue,
+            };
             if !extension_desc.supports_presence() || value.fields.has(&extension_desc) {
                 map.serialize_entry(
                     extension_desc.json_name(),
@@ -109,16 +174,166 @@ where
                             value: value.fields.get(&extension_desc).as_ref(),
                             kind: &extension_desc.kind(),
                         },
-                        options,
+                        options: &field_options,
                     },
                 )?;
             }
         }
     }
 
+    // Unknown fields are not covered by any field mask.
+    if options.field_mask.is_none() {
+        for field in value.unknown_json_fields() {
+            map.serialize_entry(&field.name, &field.value)?;
+        }
+    }
+
     Ok(())
 }
 
+fn is_human_readable<S>(serializer: &S, options: &SerializeOptions) -> bool
+where
+    S: Serializer,
+{
+    options
+        .human_readable
+        .unwrap_or_else(|| serializer.is_human_readable())
+}
+
+fn stringify_64_bit_integers<S>(serializer: &S, options: &SerializeOptions) -> bool
+where
+    S: Serializer,
+{
+    options.stringify_64_bit_integers && is_human_readable(serializer, options)
+}
+
+fn serialize_f32<S>(
+    value: f32,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if !value.is_finite() {
+        return serialize_non_finite(value.into(), serializer, options);
+    }
+
+    match options.float_precision {
+        Some(digits) => {
+            let digits = digits.clamp(1, 9) as usize;
+            let rounded = format!("{:.*e}", digits - 1, value);
+            serializer.serialize_f32(rounded.parse().map_err(Error::custom)?)
+        }
+        None => serializer.serialize_f32(value),
+    }
+}
+
+fn serialize_f64<S>(
+    value: f64,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if !value.is_finite() {
+        return serialize_non_finite(value, serializer, options);
+    }
+
+    match options.float_precision {
+        Some(digits) => {
+            let digits = digits.clamp(1, 17) as usize;
+            let rounded = format!("{:.*e}", digits - 1, value);
+            serializer.serialize_f64(rounded.parse().map_err(Error::custom)?)
+        }
+        None => serializer.serialize_f64(value),
+    }
+}
+
+fn serialize_non_finite<S>(
+    value: f64,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match options.non_finite_floats {
+        NonFiniteFloatEncoding::String => {
+            if value == f64::INFINITY {
+                serializer.serialize_str("Infinity")
+            } else if value == f64::NEG_INFINITY {
+                serializer.serialize_str("-Infinity")
+            } else {
+                debug_assert!(value.is_nan());
+                serializer.serialize_str("NaN")
+            }
+        }
+        NonFiniteFloatEncoding::Null => serializer.serialize_none(),
+        NonFiniteFloatEncoding::Error => Err(Error::custom(format!(
+            "cannot serialize non-finite value '{}'",
+            value
+        ))),
+    }
+}
+
+fn serialize_bytes<S>(
+    value: &[u8],
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if !is_human_readable(&serializer, options) {
+        return serializer.serialize_bytes(value);
+    }
+
+    match options.bytes_encoding {
+        BytesEncoding::Base64 => {
+            serializer.collect_str(&Base64Display::with_config(value, base64::STANDARD))
+        }
+        BytesEncoding::Base64UrlSafe => {
+            serializer.collect_str(&Base64Display::with_config(value, base64::URL_SAFE))
+        }
+        BytesEncoding::Hex => serializer.collect_str(&HexDisplay(value)),
+    }
+}
+
+struct HexDisplay<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for HexDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the options used to serialize the field `name`, with the field mask narrowed to the
+/// paths below it, or `None` if the field is not selected by the field mask.
+fn masked_options<'a>(
+    options: &'a SerializeOptions,
+    name: &str,
+) -> Option<Cow<'a, SerializeOptions>> {
+    let tree = match &options.field_mask {
+        Some(tree) => tree,
+        None => return Some(Cow::Borrowed(options)),
+    };
+
+    let child = tree.get(name)?;
+    Some(Cow::Owned(SerializeOptions {
+        field_mask: if child.is_leaf() {
+            None
+        } else {
+            Some(Arc::new(child.clone()))
+        },
+        ..options.clone()
+    }))
+}
+
 struct ValueAndKind<'a> {
     value: &'a Value,
     kind: &'a Kind,
@@ -133,7 +348,7 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
             Value::Bool(value) => serializer.serialize_bool(*value),
             Value::I32(value) => serializer.serialize_i32(*value),
             Value::I64(value) => {
-                if self.options.stringify_64_bit_integers {
+                if stringify_64_bit_integers(&serializer, self.options) {
                     serializer.collect_str(value)
                 } else {
                     serializer.serialize_i64(*value)
@@ -141,40 +356,16 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
             }
             Value::U32(value) => serializer.serialize_u32(*value),
             Value::U64(value) => {
-                if self.options.stringify_64_bit_integers {
+                if stringify_64_bit_integers(&serializer, self.options) {
                     serializer.collect_str(value)
                 } else {
                     serializer.serialize_u64(*value)
                 }
             }
-            Value::F32(value) => {
-                if value.is_finite() {
-                    serializer.serialize_f32(*value)
-                } else if *value == f32::INFINITY {
-                    serializer.serialize_str("Infinity")
-                } else if *value == f32::NEG_INFINITY {
-                    serializer.serialize_str("-Infinity")
-                } else {
-                    debug_assert!(value.is_nan());
-                    serializer.serialize_str("NaN")
-                }
-            }
-            Value::F64(value) => {
-                if value.is_finite() {
-                    serializer.serialize_f64(*value)
-                } else if *value == f64::INFINITY {
-                    serializer.serialize_str("Infinity")
-                } else if *value == f64::NEG_INFINITY {
-                    serializer.serialize_str("-Infinity")
-                } else {
-                    debug_assert!(value.is_nan());
-                    serializer.serialize_str("NaN")
-                }
-            }
+            Value::F32(value) => serialize_f32(*value, serializer, self.options),
+            Value::F64(value) => serialize_f64(*value, serializer, self.options),
             Value::String(value) => serializer.serialize_str(value),
-            Value::Bytes(value) => {
-                serializer.collect_str(&Base64Display::with_config(value, base64::STANDARD))
-            }
+            Value::Bytes(value) => serialize_bytes(value, serializer, self.options),
             Value::EnumNumber(number) => {
                 let enum_ty = match self.value.kind {
                     Kind::Enum(enum_ty) => enum_ty,
@@ -1,22 +1,25 @@
 use std::cmp::Ordering;
 
-use base64::display::Base64Display;
 use prost::{DecodeError, Message};
-use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
 use time::{Duration, OffsetDateTime, UtcOffset};
 
 use crate::{
     dynamic::{
         serde::{
-            case::snake_case_to_camel_case, is_well_known_type, SerializeOptions,
-            MAX_DURATION_NANOS, MAX_DURATION_SECONDS, MAX_TIMESTAMP_SECONDS, MIN_TIMESTAMP_SECONDS,
+            case::snake_case_to_camel_case, is_well_known_type, resolve_any_type, DurationEncoding,
+            SerializeOptions, TimestampEncoding, MAX_DURATION_NANOS, MAX_DURATION_SECONDS,
+            MAX_TIMESTAMP_SECONDS, MIN_TIMESTAMP_SECONDS,
         },
         DynamicMessage,
     },
     ReflectMessage,
 };
 
-use super::{serialize_dynamic_message_fields, SerializeWrapper};
+use super::{
+    is_human_readable, serialize_dynamic_message_fields, stringify_64_bit_integers,
+    SerializeWrapper,
+};
 
 #[allow(type_alias_bounds)]
 type WellKnownTypeSerializer<S: Serializer> =
@@ -61,57 +64,78 @@ where
 {
     let raw: prost_types::Any = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if let Some(message_name) = raw.type_url.strip_prefix("type.googleapis.com/") {
-        let message_desc = msg
-            .descriptor()
-            .parent_pool()
-            .get_message_by_name(message_name)
-            .ok_or_else(|| Error::custom(format!("message '{}' not found", message_name)))?;
-
-        let mut payload_message = DynamicMessage::new(message_desc);
-        payload_message
-            .merge(raw.value.as_ref())
-            .map_err(decode_to_ser_err)?;
-
-        if is_well_known_type(message_name) {
-            let mut map = serializer.serialize_map(Some(2))?;
-            map.serialize_entry("@type", &raw.type_url)?;
-            map.serialize_entry(
-                "value",
-                &SerializeWrapper {
-                    value: &payload_message,
-                    options,
-                },
-            )?;
-            map.end()
-        } else {
-            let mut map = serializer.serialize_map(None)?;
-            map.serialize_entry("@type", &raw.type_url)?;
-            serialize_dynamic_message_fields(&mut map, &payload_message, options)?;
-            map.end()
-        }
+    let message_desc = resolve_any_type(
+        msg.descriptor().parent_pool(),
+        &raw.type_url,
+        options.resolver.as_deref(),
+    )
+    .map_err(Error::custom)?;
+
+    let mut payload_message = DynamicMessage::new(message_desc);
+    payload_message
+        .merge(raw.value.as_ref())
+        .map_err(decode_to_ser_err)?;
+
+    if options.has_special_mapping(payload_message.descriptor().full_name()) {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("@type", &raw.type_url)?;
+        map.serialize_entry(
+            "value",
+            &SerializeWrapper {
+                value: &payload_message,
+                options,
+            },
+        )?;
+        map.end()
     } else {
-        Err(Error::custom(format!(
-            "unsupported type url '{}'",
-            raw.type_url
-        )))
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("@type", &raw.type_url)?;
+        serialize_dynamic_message_fields(&mut map, &payload_message, options)?;
+        map.end()
     }
 }
 
 fn serialize_timestamp<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let raw: prost_types::Timestamp = msg.transcode_to().map_err(decode_to_ser_err)?;
+    let mut raw: prost_types::Timestamp = msg.transcode_to().map_err(decode_to_ser_err)?;
 
     if raw.seconds < MIN_TIMESTAMP_SECONDS || MAX_TIMESTAMP_SECONDS < raw.seconds {
         return Err(Error::custom("timestamp out of range"));
     }
 
+    if !is_human_readable(&serializer, options) {
+        raw.normalize();
+        return serialize_seconds_nanos(serializer, "Timestamp", raw.seconds, raw.nanos);
+    }
+
+    match options.timestamp_encoding {
+        TimestampEncoding::Rfc3339 => (),
+        TimestampEncoding::EpochSeconds => {
+            raw.normalize();
+            return if raw.nanos == 0 {
+                serializer.serialize_i64(raw.seconds)
+            } else {
+                serializer.serialize_f64(raw.seconds as f64 + raw.nanos as f64 / 1e9)
+            };
+        }
+        TimestampEncoding::EpochMillis => {
+            raw.normalize();
+            let millis = raw.seconds * 1000 + i64::from(raw.nanos / 1_000_000);
+            let sub_millis = raw.nanos % 1_000_000;
+            return if sub_millis == 0 {
+                serializer.serialize_i64(millis)
+            } else {
+                serializer.serialize_f64(millis as f64 + sub_millis as f64 / 1e6)
+            };
+        }
+    }
+
     let datetime = OffsetDateTime::from_unix_timestamp(raw.seconds)
         .map_err(|_| Error::custom("invalid timestamp"))?
         + Duration::nanoseconds(raw.nanos.into());
@@ -123,7 +147,7 @@ where
 fn serialize_duration<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -140,6 +164,30 @@ where
     }
     debug_assert!(abs_nanos <= MAX_DURATION_NANOS);
 
+    if !is_human_readable(&serializer, options) {
+        return serialize_seconds_nanos(serializer, "Duration", raw.seconds, raw.nanos);
+    }
+
+    match options.duration_encoding {
+        DurationEncoding::String => (),
+        _ if (raw.seconds < 0 && raw.nanos > 0) || (raw.seconds > 0 && raw.nanos < 0) => {
+            return Err(Error::custom("inconsistent signs for duration"));
+        }
+        DurationEncoding::Seconds => {
+            return if raw.nanos == 0 {
+                serializer.serialize_i64(raw.seconds)
+            } else {
+                serializer.serialize_f64(raw.seconds as f64 + raw.nanos as f64 / 1e9)
+            };
+        }
+        DurationEncoding::Object => {
+            let mut map = serializer.serialize_map(Some(2))?;
+            map.serialize_entry("seconds", &raw.seconds)?;
+            map.serialize_entry("nanos", &raw.nanos)?;
+            return map.end();
+        }
+    }
+
     let mut nanos_fract_digits: usize = 9;
     while nanos_fract_digits != 0 && abs_nanos % 1000 == 0 {
         abs_nanos /= 1000;
@@ -170,30 +218,45 @@ where
     }
 }
 
+fn serialize_seconds_nanos<S>(
+    serializer: S,
+    name: &'static str,
+    seconds: i64,
+    nanos: i32,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut state = serializer.serialize_struct(name, 2)?;
+    state.serialize_field("seconds", &seconds)?;
+    state.serialize_field("nanos", &nanos)?;
+    state.end()
+}
+
 fn serialize_float<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: f32 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serializer.serialize_f32(raw)
+    super::serialize_f32(raw, serializer, options)
 }
 
 fn serialize_double<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: f64 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serializer.serialize_f64(raw)
+    super::serialize_f64(raw, serializer, options)
 }
 
 fn serialize_int32<S>(
@@ -219,7 +282,7 @@ where
 {
     let raw: i64 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if options.stringify_64_bit_integers {
+    if stringify_64_bit_integers(&serializer, options) {
         serializer.collect_str(&raw)
     } else {
         serializer.serialize_i64(raw)
@@ -249,7 +312,7 @@ where
 {
     let raw: u64 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if options.stringify_64_bit_integers {
+    if stringify_64_bit_integers(&serializer, options) {
         serializer.collect_str(&raw)
     } else {
         serializer.serialize_u64(raw)
@@ -285,14 +348,14 @@ where
 fn serialize_bytes<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: Vec<u8> = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serializer.collect_str(&Base64Display::with_config(&raw, base64::STANDARD))
+    super::serialize_bytes(&raw, serializer, options)
 }
 
 fn serialize_field_mask<S>(
@@ -0,0 +1,290 @@
+use std::{error::Error, fmt};
+
+use crate::{DynamicMessage, FieldDescriptor, Kind, MapKey, Value};
+
+use super::{bind::expected_type, index::type_name};
+
+/// An error returned by [`DynamicMessage::try_set_field`] and other methods which check that a value
+/// is valid for a field before setting it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetFieldError {
+    kind: SetFieldErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SetFieldErrorKind {
+    FieldNotFound {
+        message: String,
+        name: String,
+    },
+    InvalidType {
+        field: String,
+        expected: String,
+        actual: String,
+    },
+    InvalidEnumValue {
+        field: String,
+        enum_name: String,
+        number: i32,
+    },
+    NotAList {
+        field: String,
+    },
+    NotAMap {
+        field: String,
+    },
+    IndexOutOfRange {
+        field: String,
+        index: usize,
+        len: usize,
+    },
+}
+
+impl DynamicMessage {
+    /// Sets the value of the given field, after checking that the value is valid for it.
+    ///
+    /// Unlike [`set_field`][DynamicMessage::set_field], this method returns an error if the value type
+    /// is not compatible with the field type, including the types of list elements, map keys and map
+    /// values. Message values must have the same message type as the field, and enum values must be
+    /// defined by the enum type of the field.
+    ///
+    /// If an error is returned, the message is not modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, ReflectMessage, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// let field = dynamic_message.descriptor().get_field_by_name("foo").unwrap();
+    /// assert!(dynamic_message.try_set_field(&field, Value::I32(150)).is_ok());
+    ///
+    /// let err = dynamic_message.try_set_field(&field, Value::String("150".to_owned())).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "invalid value for field 'package.MyMessage.foo': expected a 32-bit integer, but got a string",
+    /// );
+    /// ```
+    pub fn try_set_field(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        value: Value,
+    ) -> Result<(), SetFieldError> {
+        check_field(field_desc, &value).map_err(|kind| SetFieldError { kind })?;
+        self.set_field(field_desc, value);
+        Ok(())
+    }
+
+    /// Sets the value of the field with the given name, after checking that the value is valid for it.
+    ///
+    /// Returns an error if the message has no field with the given name. See
+    /// [`try_set_field`][Self::try_set_field] for more details.
+    pub fn try_set_field_by_name(&mut self, name: &str, value: Value) -> Result<(), SetFieldError> {
+        match self.desc.get_field_by_name(name) {
+            Some(field_desc) => self.try_set_field(&field_desc, value),
+            None => Err(SetFieldError {
+                kind: SetFieldErrorKind::FieldNotFound {
+                    message: self.desc.full_name().to_owned(),
+                    name: name.to_owned(),
+                },
+            }),
+        }
+    }
+}
+
+impl SetFieldError {
+    pub(super) fn not_a_list(field_desc: &FieldDescriptor) -> Self {
+        SetFieldError {
+            kind: SetFieldErrorKind::NotAList {
+                field: field_desc.full_name().to_owned(),
+            },
+        }
+    }
+
+    pub(super) fn not_a_map(field_desc: &FieldDescriptor) -> Self {
+        SetFieldError {
+            kind: SetFieldErrorKind::NotAMap {
+                field: field_desc.full_name().to_owned(),
+            },
+        }
+    }
+
+    pub(super) fn index_out_of_range(
+        field_desc: &FieldDescriptor,
+        index: usize,
+        len: usize,
+    ) -> Self {
+        SetFieldError {
+            kind: SetFieldErrorKind::IndexOutOfRange {
+                field: field_desc.full_name().to_owned(),
+                index,
+                len,
+            },
+        }
+    }
+}
+
+impl fmt::Display for SetFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            SetFieldErrorKind::FieldNotFound { message, name } => {
+                write!(f, "message '{}' has no field named '{}'", message, name)
+            }
+            SetFieldErrorKind::InvalidType {
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "invalid value for field '{}': expected {}, but got {}",
+                field, expected, actual
+            ),
+            SetFieldErrorKind::InvalidEnumValue {
+                field,
+                enum_name,
+                number,
+            } => write!(
+                f,
+                "invalid value for field '{}': enum '{}' has no value with number {}",
+                field, enum_name, number
+            ),
+            SetFieldErrorKind::NotAList { field } => {
+                write!(f, "field '{}' is not a repeated field", field)
+            }
+            SetFieldErrorKind::NotAMap { field } => {
+                write!(f, "field '{}' is not a map field", field)
+            }
+            SetFieldErrorKind::IndexOutOfRange { field, index, len } => write!(
+                f,
+                "index {} is out of range for field '{}' with {} elements",
+                index, field, len
+            ),
+        }
+    }
+}
+
+impl Error for SetFieldError {}
+
+fn check_field(field_desc: &FieldDescriptor, value: &Value) -> Result<(), SetFieldErrorKind> {
+    let kind = field_desc.kind();
+    if field_desc.is_map() {
+        let entry_desc = kind.as_message().unwrap();
+        let key_kind = entry_desc.map_entry_key_field().kind();
+        let value_kind = entry_desc.map_entry_value_field().kind();
+        match value {
+            Value::Map(map) => map.iter().try_for_each(|(key, value)| {
+                check_key(field_desc, &key_kind, key)?;
+                check_kind(field_desc, &value_kind, value)
+            }),
+            value => Err(invalid_type(field_desc, "a map".to_owned(), value)),
+        }
+    } else if field_desc.is_list() {
+        match value {
+            Value::List(list) => list
+                .iter()
+                .try_for_each(|value| check_kind(field_desc, &kind, value)),
+            value => Err(invalid_type(field_desc, "a list".to_owned(), value)),
+        }
+    } else {
+        check_kind(field_desc, &kind, value)
+    }
+}
+
+/// Checks that `value` is a valid element of the repeated field `field_desc`.
+pub(super) fn check_element(
+    field_desc: &FieldDescriptor,
+    value: &Value,
+) -> Result<(), SetFieldError> {
+    if !field_desc.is_list() {
+        return Err(SetFieldError::not_a_list(field_desc));
+    }
+    check_kind(field_desc, &field_desc.kind(), value).map_err(|kind| SetFieldError { kind })
+}
+
+/// Checks that `key` is a valid key for the map field `field_desc`, and `value`, if given, is a valid
+/// value.
+pub(super) fn check_map_entry(
+    field_desc: &FieldDescriptor,
+    key: &MapKey,
+    value: Option<&Value>,
+) -> Result<(), SetFieldError> {
+    if !field_desc.is_map() {
+        return Err(SetFieldError::not_a_map(field_desc));
+    }
+    let kind = field_desc.kind();
+    let entry_desc = kind.as_message().unwrap();
+    check_key(field_desc, &entry_desc.map_entry_key_field().kind(), key)
+        .map_err(|kind| SetFieldError { kind })?;
+    if let Some(value) = value {
+        check_kind(
+            field_desc,
+            &entry_desc.map_entry_value_field().kind(),
+            value,
+        )
+        .map_err(|kind| SetFieldError { kind })?;
+    }
+    Ok(())
+}
+
+fn check_kind(
+    field_desc: &FieldDescriptor,
+    kind: &Kind,
+    value: &Value,
+) -> Result<(), SetFieldErrorKind> {
+    match (kind, value) {
+        (Kind::Enum(enum_desc), Value::EnumNumber(number)) => {
+            if enum_desc.get_value(*number).is_some() {
+                Ok(())
+            } else {
+                Err(SetFieldErrorKind::InvalidEnumValue {
+                    field: field_desc.full_name().to_owned(),
+                    enum_name: enum_desc.full_name().to_owned(),
+                    number: *number,
+                })
+            }
+        }
+        (Kind::Message(message_desc), Value::Message(message)) => {
+            if message.desc == *message_desc {
+                Ok(())
+            } else {
+                Err(SetFieldErrorKind::InvalidType {
+                    field: field_desc.full_name().to_owned(),
+                    expected: expected_type(kind),
+                    actual: format!("a message of type '{}'", message.desc.full_name()),
+                })
+            }
+        }
+        (kind, value) if value.is_valid(kind) => Ok(()),
+        (kind, value) => Err(invalid_type(field_desc, expected_type(kind), value)),
+    }
+}
+
+fn check_key(
+    field_desc: &FieldDescriptor,
+    kind: &Kind,
+    key: &MapKey,
+) -> Result<(), SetFieldErrorKind> {
+    if key.is_valid(kind) {
+        Ok(())
+    } else {
+        Err(SetFieldErrorKind::InvalidType {
+            field: field_desc.full_name().to_owned(),
+            expected: format!("map keys of {}", expected_type(kind)),
+            actual: format!("a key of {}", type_name(&Value::from(key.clone()))),
+        })
+    }
+}
+
+fn invalid_type(
+    field_desc: &FieldDescriptor,
+    expected: String,
+    value: &Value,
+) -> SetFieldErrorKind {
+    SetFieldErrorKind::InvalidType {
+        field: field_desc.full_name().to_owned(),
+        expected,
+        actual: type_name(value).to_owned(),
+    }
+}
@@ -0,0 +1,327 @@
+use std::fmt::Write;
+
+use crate::{DynamicMessage, Kind, MapKey, Value};
+
+use super::{fields::ValueAndDescriptor, unknown::UnknownFieldValue};
+
+const INDENT: &str = "  ";
+const REDACTED: &str = "[redacted]";
+
+/// Options to control the output of [`DynamicMessage::to_snapshot`].
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotOptions {
+    redacted_paths: Vec<String>,
+}
+
+impl DynamicMessage {
+    /// Renders this message in a stable, human-readable format designed for snapshot testing.
+    ///
+    /// The output resembles the protobuf text format, with the following guarantees to keep snapshots from
+    /// changing unnecessarily:
+    ///
+    /// - Fields are listed in field number order, followed by extensions and unknown fields. Fields which are
+    ///   not set (as defined by [`has_field`][Self::has_field]) are omitted.
+    /// - Map entries are sorted by key.
+    /// - Floating point values are printed in their shortest round-trippable form, with negative zero printed
+    ///   as `0` and non-finite values printed as `inf`, `-inf` or `nan`.
+    /// - Enum values are printed by name if known, or by number otherwise.
+    /// - Fields matching a path set with [`SnapshotOptions::redact`] have their value replaced with `[redacted]`.
+    pub fn to_snapshot(&self, options: &SnapshotOptions) -> String {
+        let mut output = String::new();
+        output.push_str(self.desc.full_name());
+        output.push(' ');
+        write_message(&mut output, self, options, "", 0);
+        output.push('\n');
+        output
+    }
+
+    /// Gets the name and formatted value of each field which is set in this message, including extensions
+    /// and unknown fields, ordered by field number.
+    pub(crate) fn field_strings(&self) -> Vec<(String, String)> {
+        self.fields
+            .iter(&self.desc)
+            .map(|field| match field {
+                ValueAndDescriptor::Field(value, field_desc) => (
+                    field_desc.name().to_owned(),
+                    value_to_string(value, &field_desc.kind()),
+                ),
+                ValueAndDescriptor::Extension(value, extension_desc) => (
+                    format!("[{}]", extension_desc.full_name()),
+                    value_to_string(value, &extension_desc.kind()),
+                ),
+                ValueAndDescriptor::Unknown(number, unknowns) => {
+                    (number.to_string(), unknown_fields_to_string(unknowns))
+                }
+            })
+            .collect()
+    }
+}
+
+impl SnapshotOptions {
+    /// Creates a new instance of [`SnapshotOptions`], with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redacts the value of a field, which is useful for fields which change on every test run, such as
+    /// timestamps or randomly generated IDs.
+    ///
+    /// The path is a sequence of field names separated by `.`, relative to the root message, for
+    /// example `"metadata.created_at"`. Paths pass through repeated and map fields without an index, so
+    /// `"items.id"` redacts the `id` field of every element of `items`. Extension fields are named by
+    /// their full name in brackets, for example `"[my.package.ext]"`.
+    pub fn redact(mut self, path: impl Into<String>) -> Self {
+        self.redacted_paths.push(path.into());
+        self
+    }
+
+    fn is_redacted(&self, path: &str) -> bool {
+        self.redacted_paths.iter().any(|redacted| redacted == path)
+    }
+}
+
+fn write_message(
+    output: &mut String,
+    message: &DynamicMessage,
+    options: &SnapshotOptions,
+    path: &str,
+    depth: usize,
+) {
+    let mut fields: Vec<_> = message.fields.iter(&message.desc).collect();
+    if fields.is_empty() {
+        output.push_str("{}");
+        return;
+    }
+    fields.sort_by_key(|field| match field {
+        ValueAndDescriptor::Field(..) => 0,
+        ValueAndDescriptor::Extension(..) => 1,
+        ValueAndDescriptor::Unknown(..) => 2,
+    });
+
+    output.push_str("{\n");
+    for field in fields {
+        match field {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                let path = join_path(path, field_desc.name());
+                write_field(
+                    output,
+                    field_desc.name(),
+                    value,
+                    &field_desc.kind(),
+                    options,
+                    &path,
+                    depth + 1,
+                );
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                let name = format!("[{}]", extension_desc.full_name());
+                let path = join_path(path, &name);
+                write_field(
+                    output,
+                    &name,
+                    value,
+                    &extension_desc.kind(),
+                    options,
+                    &path,
+                    depth + 1,
+                );
+            }
+            ValueAndDescriptor::Unknown(number, unknowns) => {
+                for unknown in unknowns {
+                    write_unknown(output, number, unknown, depth + 1);
+                }
+            }
+        }
+    }
+
+    write_indent(output, depth);
+    output.push('}');
+}
+
+fn write_field(
+    output: &mut String,
+    name: &str,
+    value: &Value,
+    kind: &Kind,
+    options: &SnapshotOptions,
+    path: &str,
+    depth: usize,
+) {
+    let redacted = options.is_redacted(path);
+    match value {
+        Value::List(values) => {
+            for value in values {
+                write_indent(output, depth);
+                write!(output, "{}: ", name).unwrap();
+                write_value(output, value, kind, options, path, redacted, depth);
+                output.push('\n');
+            }
+        }
+        Value::Map(values) => {
+            let entry = kind.as_message().expect("map field must be a message");
+            let value_kind = entry.map_entry_value_field().kind();
+
+            let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            for (key, value) in entries {
+                write_indent(output, depth);
+                write!(output, "{} {{ key: ", name).unwrap();
+                write_map_key(output, key);
+                output.push_str(", value: ");
+                write_value(output, value, &value_kind, options, path, redacted, depth);
+                output.push_str(" }\n");
+            }
+        }
+        value => {
+            write_indent(output, depth);
+            write!(output, "{}: ", name).unwrap();
+            write_value(output, value, kind, options, path, redacted, depth);
+            output.push('\n');
+        }
+    }
+}
+
+fn write_value(
+    output: &mut String,
+    value: &Value,
+    kind: &Kind,
+    options: &SnapshotOptions,
+    path: &str,
+    redacted: bool,
+    depth: usize,
+) {
+    if redacted {
+        output.push_str(REDACTED);
+        return;
+    }
+
+    match value {
+        Value::Bool(value) => write!(output, "{}", value).unwrap(),
+        Value::I32(value) => write!(output, "{}", value).unwrap(),
+        Value::I64(value) => write!(output, "{}", value).unwrap(),
+        Value::U32(value) => write!(output, "{}", value).unwrap(),
+        Value::U64(value) => write!(output, "{}", value).unwrap(),
+        Value::F32(value) => write_float(output, *value as f64, value.to_string()),
+        Value::F64(value) => write_float(output, *value, value.to_string()),
+        Value::String(value) => write!(output, "{:?}", value).unwrap(),
+        Value::Bytes(value) => write_bytes(output, value),
+        Value::EnumNumber(number) => match kind.as_enum().and_then(|e| e.get_value(*number)) {
+            Some(value) => output.push_str(value.name()),
+            None => write!(output, "{}", number).unwrap(),
+        },
+        Value::Message(message) => write_message(output, message, options, path, depth),
+        Value::List(_) | Value::Map(_) => unreachable!("nested lists and maps are not supported"),
+    }
+}
+
+fn write_map_key(output: &mut String, key: &MapKey) {
+    match key {
+        MapKey::Bool(value) => write!(output, "{}", value).unwrap(),
+        MapKey::I32(value) => write!(output, "{}", value).unwrap(),
+        MapKey::I64(value) => write!(output, "{}", value).unwrap(),
+        MapKey::U32(value) => write!(output, "{}", value).unwrap(),
+        MapKey::U64(value) => write!(output, "{}", value).unwrap(),
+        MapKey::String(value) => write!(output, "{:?}", value).unwrap(),
+    }
+}
+
+fn write_unknown(output: &mut String, number: u32, unknown: &UnknownFieldValue, depth: usize) {
+    write_indent(output, depth);
+    match unknown {
+        UnknownFieldValue::Group(_) => write!(output, "{} ", number).unwrap(),
+        _ => write!(output, "{}: ", number).unwrap(),
+    }
+    write_unknown_value(output, unknown, depth);
+    output.push('\n');
+}
+
+fn write_unknown_value(output: &mut String, unknown: &UnknownFieldValue, depth: usize) {
+    match unknown {
+        UnknownFieldValue::Varint(value) => write!(output, "{}", value).unwrap(),
+        UnknownFieldValue::SixtyFourBit(value) => {
+            write!(output, "0x{:016x}", u64::from_le_bytes(*value)).unwrap()
+        }
+        UnknownFieldValue::ThirtyTwoBit(value) => {
+            write!(output, "0x{:08x}", u32::from_le_bytes(*value)).unwrap()
+        }
+        UnknownFieldValue::LengthDelimited(value) => write_bytes(output, value),
+        UnknownFieldValue::Group(group) => {
+            let fields: Vec<_> = group.iter().collect();
+            if fields.is_empty() {
+                output.push_str("{}");
+            } else {
+                output.push_str("{\n");
+                for (number, field) in fields {
+                    write_unknown(output, number, field, depth + 1);
+                }
+                write_indent(output, depth);
+                output.push('}');
+            }
+        }
+    }
+}
+
+fn write_float(output: &mut String, value: f64, display: String) {
+    if value.is_nan() {
+        output.push_str("nan");
+    } else if value.is_infinite() {
+        output.push_str(if value > 0.0 { "inf" } else { "-inf" });
+    } else if value == 0.0 {
+        output.push('0');
+    } else {
+        output.push_str(&display);
+    }
+}
+
+fn write_bytes(output: &mut String, bytes: &[u8]) {
+    output.push('"');
+    for &byte in bytes {
+        output.extend(std::ascii::escape_default(byte).map(char::from));
+    }
+    output.push('"');
+}
+
+fn write_indent(output: &mut String, depth: usize) {
+    for _ in 0..depth {
+        output.push_str(INDENT);
+    }
+}
+
+pub(super) fn join_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+pub(super) fn value_to_string(value: &Value, kind: &Kind) -> String {
+    let mut output = String::new();
+    write_value(
+        &mut output,
+        value,
+        kind,
+        &SnapshotOptions::default(),
+        "",
+        false,
+        0,
+    );
+    output
+}
+
+pub(super) fn map_key_to_string(key: &MapKey) -> String {
+    let mut output = String::new();
+    write_map_key(&mut output, key);
+    output
+}
+
+pub(super) fn unknown_fields_to_string(unknowns: &[UnknownFieldValue]) -> String {
+    let mut output = String::new();
+    for (index, unknown) in unknowns.iter().enumerate() {
+        if index != 0 {
+            output.push_str(", ");
+        }
+        write_unknown_value(&mut output, unknown, 0);
+    }
+    output
+}
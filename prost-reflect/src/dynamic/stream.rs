@@ -0,0 +1,399 @@
+use std::{
+    convert::TryFrom,
+    io::{self, BufReader, Read, Write},
+};
+
+use prost::{
+    encoding::{encode_key, encode_varint, WireType},
+    Message,
+};
+
+use crate::{
+    descriptor::{Kind, MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER},
+    DynamicMessage, MessageDescriptor, Value,
+};
+
+use super::{
+    delimited::{check_message_len, read_length_delimiter},
+    fields::{FieldDescriptorLike, ValueAndDescriptor},
+};
+
+/// The number of bytes buffered before they are written to the underlying writer.
+const CHUNK_LEN: usize = 8 * 1024;
+/// The maximum depth of nested messages, matching the limit used by `prost`.
+const RECURSION_LIMIT: u32 = 100;
+
+impl DynamicMessage {
+    /// Encodes this message and writes it to `writer`.
+    ///
+    /// The output is identical to [`encode_to_vec`][Message::encode_to_vec], but the message is written in
+    /// chunks as it is encoded, so only the largest single string, bytes value or packed list needs to be
+    /// held in memory at once, rather than the whole encoded message.
+    ///
+    /// Writes are buffered internally, so `writer` does not need to be.
+    pub fn encode_to_writer<W>(&self, writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut writer = ChunkWriter {
+            writer,
+            buf: Vec::with_capacity(CHUNK_LEN),
+        };
+        writer.write_message(self)?;
+        writer.finish()
+    }
+
+    /// Decodes a message of type `desc` from `reader`, reading until the end of the stream.
+    ///
+    /// Nested messages are decoded directly from the stream, so only the largest single string, bytes
+    /// value, packed list or map entry needs to be held in memory at once, in addition to the decoded
+    /// message itself.
+    ///
+    /// Decoding errors are returned as [`io::Error`]s of kind [`InvalidData`][io::ErrorKind::InvalidData],
+    /// and a stream which ends in the middle of a field returns an error of kind
+    /// [`UnexpectedEof`][io::ErrorKind::UnexpectedEof]. Reads are buffered internally, so `reader` does
+    /// not need to be.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor.clone());
+    /// message.set_field_by_name("foo", Value::I32(150));
+    ///
+    /// let mut file = Vec::new();
+    /// message.encode_to_writer(&mut file).unwrap();
+    ///
+    /// let decoded = DynamicMessage::decode_from_reader(message_descriptor, file.as_slice()).unwrap();
+    /// assert_eq!(decoded, message);
+    /// ```
+    pub fn decode_from_reader<R>(desc: MessageDescriptor, reader: R) -> io::Result<DynamicMessage>
+    where
+        R: Read,
+    {
+        let mut message = DynamicMessage::new(desc);
+        let mut reader = LimitedReader {
+            reader: BufReader::new(reader),
+            limit: u64::MAX,
+        };
+        decode_message(&mut message, &mut reader, None, 0)?;
+        Ok(message)
+    }
+}
+
+struct ChunkWriter<W> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+impl<W> ChunkWriter<W>
+where
+    W: Write,
+{
+    fn write_message(&mut self, message: &DynamicMessage) -> io::Result<()> {
+        for field in message.fields.iter(&message.desc) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    self.write_field(value, &field_desc)?
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    self.write_field(value, &extension_desc)?
+                }
+                ValueAndDescriptor::Unknown(number, unknowns) => {
+                    for unknown in unknowns {
+                        unknown.encode_field(number, &mut self.buf);
+                        self.flush_full_chunk()?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_field(
+        &mut self,
+        value: &Value,
+        field_desc: &impl FieldDescriptorLike,
+    ) -> io::Result<()> {
+        if !field_desc.supports_presence() && field_desc.is_default_value(value) {
+            return Ok(());
+        }
+
+        let number = field_desc.number();
+        match (value, field_desc.kind()) {
+            (Value::Message(message), Kind::Message(_)) => {
+                if field_desc.is_group() {
+                    encode_key(number, WireType::StartGroup, &mut self.buf);
+                    self.write_message(message)?;
+                    encode_key(number, WireType::EndGroup, &mut self.buf);
+                } else {
+                    // The length is cached by the message, so this does not encode it twice.
+                    encode_key(number, WireType::LengthDelimited, &mut self.buf);
+                    encode_varint(message.encoded_len() as u64, &mut self.buf);
+                    self.write_message(message)?;
+                }
+            }
+            (Value::List(values), _) if field_desc.is_list() && !field_desc.is_packed() => {
+                for value in values {
+                    self.write_field(value, field_desc)?;
+                }
+            }
+            (Value::Map(values), Kind::Message(map_entry)) if field_desc.is_map() => {
+                let key_desc = map_entry.get_field(MAP_ENTRY_KEY_NUMBER).unwrap();
+                let value_desc = map_entry.get_field(MAP_ENTRY_VALUE_NUMBER).unwrap();
+
+                for (key, value) in values {
+                    let len = key.encoded_len(&key_desc) + value.encoded_len(&value_desc);
+
+                    encode_key(number, WireType::LengthDelimited, &mut self.buf);
+                    encode_varint(len as u64, &mut self.buf);
+
+                    key.encode_field(&key_desc, &mut self.buf);
+                    self.write_field(value, &value_desc)?;
+                }
+            }
+            (value, _) => value.encode_field(field_desc, &mut self.buf),
+        }
+        self.flush_full_chunk()
+    }
+
+    fn flush_full_chunk(&mut self) -> io::Result<()> {
+        if self.buf.len() >= CHUNK_LEN {
+            self.writer.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buf)?;
+        self.writer.flush()
+    }
+}
+
+/// A reader which returns the end of the stream after `limit` bytes, used to decode nested messages.
+///
+/// Unlike nesting [`Read::take`], this avoids a separate reader type for each level of nesting.
+struct LimitedReader<R> {
+    reader: BufReader<R>,
+    limit: u64,
+}
+
+impl<R> Read for LimitedReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = buf
+            .len()
+            .min(usize::try_from(self.limit).unwrap_or(usize::MAX));
+        let len = self.reader.read(&mut buf[..max])?;
+        self.limit -= len as u64;
+        Ok(len)
+    }
+}
+
+/// Decodes fields from `reader` into `message`, until the end of the stream, or until the end group tag
+/// for the field `end_group` if the message is a group.
+fn decode_message<R>(
+    message: &mut DynamicMessage,
+    reader: &mut LimitedReader<R>,
+    end_group: Option<u32>,
+    depth: u32,
+) -> io::Result<()>
+where
+    R: Read,
+{
+    if depth >= RECURSION_LIMIT {
+        return Err(invalid_data("recursion limit reached"));
+    }
+
+    let mut buf = Vec::new();
+    loop {
+        let (number, wire_type) = match read_length_delimiter(reader)? {
+            Some(key) => decode_key(key)?,
+            None if end_group.is_some() => return Err(io::ErrorKind::UnexpectedEof.into()),
+            None => return Ok(()),
+        };
+
+        if wire_type == WireType::EndGroup {
+            return if end_group == Some(number) {
+                Ok(())
+            } else {
+                Err(invalid_data("unexpected end group tag"))
+            };
+        }
+
+        let nested = match message.desc.get_field(number) {
+            Some(field_desc) if is_nested_message(&field_desc, wire_type) => Some(nested_message(
+                message.get_field_mut(&field_desc),
+                &field_desc,
+            )),
+            Some(_) => None,
+            None => match message.desc.get_extension(number) {
+                Some(extension_desc) if is_nested_message(&extension_desc, wire_type) => Some(
+                    nested_message(message.get_extension_mut(&extension_desc), &extension_desc),
+                ),
+                _ => None,
+            },
+        };
+
+        match nested {
+            Some(NestedMessage::Message(nested)) => {
+                decode_nested_message(nested, reader, number, wire_type, depth)?
+            }
+            Some(NestedMessage::ListItem(list, desc)) => {
+                let mut nested = DynamicMessage::new(desc);
+                decode_nested_message(&mut nested, reader, number, wire_type, depth)?;
+                list.push(Value::Message(nested));
+            }
+            None => {
+                // Other fields are read into memory and decoded as usual.
+                buf.clear();
+                encode_key(number, wire_type, &mut buf);
+                copy_field(reader, number, wire_type, &mut buf, depth)?;
+                message.merge(buf.as_slice()).map_err(invalid_data)?;
+            }
+        }
+    }
+}
+
+enum NestedMessage<'a> {
+    Message(&'a mut DynamicMessage),
+    ListItem(&'a mut Vec<Value>, MessageDescriptor),
+}
+
+/// Returns `true` if the field is a message which can be decoded directly from the stream, rather than
+/// being read into memory first.
+fn is_nested_message(field_desc: &impl FieldDescriptorLike, wire_type: WireType) -> bool {
+    let expected_wire_type = if field_desc.is_group() {
+        WireType::StartGroup
+    } else {
+        WireType::LengthDelimited
+    };
+    matches!(field_desc.kind(), Kind::Message(_))
+        && !field_desc.is_map()
+        && wire_type == expected_wire_type
+}
+
+fn nested_message<'a>(
+    value: &'a mut Value,
+    field_desc: &impl FieldDescriptorLike,
+) -> NestedMessage<'a> {
+    match (value, field_desc.kind()) {
+        (Value::Message(message), _) => NestedMessage::Message(message),
+        (Value::List(list), Kind::Message(desc)) => NestedMessage::ListItem(list, desc),
+        (value, ty) => panic!(
+            "mismatch between DynamicMessage value {:?} and type {:?}",
+            value, ty
+        ),
+    }
+}
+
+fn decode_nested_message<R>(
+    message: &mut DynamicMessage,
+    reader: &mut LimitedReader<R>,
+    number: u32,
+    wire_type: WireType,
+    depth: u32,
+) -> io::Result<()>
+where
+    R: Read,
+{
+    if wire_type == WireType::StartGroup {
+        decode_message(message, reader, Some(number), depth + 1)
+    } else {
+        let len = read_required_varint(reader)?;
+        if len > reader.limit {
+            return Err(invalid_data("buffer underflow"));
+        }
+
+        let remaining = reader.limit - len;
+        reader.limit = len;
+        decode_message(message, reader, None, depth + 1)?;
+        if reader.limit != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended in the middle of a message",
+            ));
+        }
+        reader.limit = remaining;
+        Ok(())
+    }
+}
+
+/// Reads the value of a field from `reader`, and appends its encoded bytes to `buf`.
+fn copy_field<R>(
+    reader: &mut LimitedReader<R>,
+    number: u32,
+    wire_type: WireType,
+    buf: &mut Vec<u8>,
+    depth: u32,
+) -> io::Result<()>
+where
+    R: Read,
+{
+    match wire_type {
+        WireType::Varint => encode_varint(read_required_varint(reader)?, buf),
+        WireType::SixtyFourBit => copy_bytes(reader, 8, buf)?,
+        WireType::ThirtyTwoBit => copy_bytes(reader, 4, buf)?,
+        WireType::LengthDelimited => {
+            let len = read_required_varint(reader)?;
+            encode_varint(len, buf);
+            copy_bytes(reader, len, buf)?;
+        }
+        WireType::StartGroup => {
+            if depth >= RECURSION_LIMIT {
+                return Err(invalid_data("recursion limit reached"));
+            }
+
+            loop {
+                let (field_number, field_wire_type) = decode_key(read_required_varint(reader)?)?;
+                encode_key(field_number, field_wire_type, buf);
+                if field_wire_type == WireType::EndGroup {
+                    if field_number != number {
+                        return Err(invalid_data("unexpected end group tag"));
+                    }
+                    break;
+                }
+                copy_field(reader, field_number, field_wire_type, buf, depth + 1)?;
+            }
+        }
+        WireType::EndGroup => return Err(invalid_data("unexpected end group tag")),
+    }
+    Ok(())
+}
+
+fn copy_bytes<R>(reader: &mut LimitedReader<R>, len: u64, buf: &mut Vec<u8>) -> io::Result<()>
+where
+    R: Read,
+{
+    let start = buf.len();
+    reader.take(len).read_to_end(buf)?;
+    check_message_len(&buf[start..], len)
+}
+
+fn read_required_varint<R>(reader: &mut R) -> io::Result<u64>
+where
+    R: Read,
+{
+    read_length_delimiter(reader)?.ok_or_else(|| io::ErrorKind::UnexpectedEof.into())
+}
+
+fn decode_key(key: u64) -> io::Result<(u32, WireType)> {
+    if key > u64::from(u32::MAX) {
+        return Err(invalid_data(format!("invalid key value: {}", key)));
+    }
+    let wire_type = WireType::try_from(key & 0x07).map_err(invalid_data)?;
+    let number = key as u32 >> 3;
+    if number == 0 {
+        return Err(invalid_data("invalid tag value: 0"));
+    }
+    Ok((number, wire_type))
+}
+
+fn invalid_data(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
@@ -0,0 +1,348 @@
+use std::fmt::Write;
+
+use crate::{
+    dynamic::{fields::ValueAndDescriptor, unknown::UnknownFieldValue},
+    DynamicMessage, Kind, MapKey, Value,
+};
+
+use super::FormatOptions;
+
+const INDENT: &str = "  ";
+const ANY_NAME: &str = "google.protobuf.Any";
+
+pub(super) struct Writer<'a> {
+    output: &'a mut String,
+    options: &'a FormatOptions,
+    depth: usize,
+    first: bool,
+}
+
+impl<'a> Writer<'a> {
+    pub(super) fn new(output: &'a mut String, options: &'a FormatOptions) -> Self {
+        Writer {
+            output,
+            options,
+            depth: 0,
+            first: true,
+        }
+    }
+
+    pub(super) fn write_message(&mut self, message: &DynamicMessage) {
+        if self.options.expand_any && self.try_write_expanded_any(message) {
+            return;
+        }
+
+        let mut fields: Vec<_> = message.fields.iter(&message.desc).collect();
+        // Unknown fields are written after all known fields, as by the C++ implementation.
+        fields.sort_by_key(|field| matches!(field, ValueAndDescriptor::Unknown(..)));
+
+        for field in fields {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    let name = if field_desc.is_group() {
+                        field_desc
+                            .kind()
+                            .as_message()
+                            .expect("group must be a message")
+                            .name()
+                            .to_owned()
+                    } else {
+                        field_desc.name().to_owned()
+                    };
+                    self.write_field(&name, value, &field_desc.kind());
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    let name = format!("[{}]", extension_desc.full_name());
+                    self.write_field(&name, value, &extension_desc.kind());
+                }
+                ValueAndDescriptor::Unknown(number, unknowns) => {
+                    if !self.options.skip_unknown_fields {
+                        for unknown in unknowns {
+                            self.write_unknown(number, unknown);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn try_write_expanded_any(&mut self, message: &DynamicMessage) -> bool {
+        if message.desc.full_name() != ANY_NAME {
+            return false;
+        }
+
+        let type_url = match message.get_field_by_name("type_url") {
+            Some(type_url) => type_url.as_str().unwrap_or_default().to_owned(),
+            None => return false,
+        };
+        let value = match message.get_field_by_name("value") {
+            Some(value) => value.as_bytes().cloned().unwrap_or_default(),
+            None => return false,
+        };
+        let type_name = match type_url.rsplit_once('/') {
+            Some((_, type_name)) => type_name,
+            None => return false,
+        };
+        let payload_desc = match message.desc.parent_pool().get_message_by_name(type_name) {
+            Some(payload_desc) => payload_desc,
+            None => return false,
+        };
+        let payload = match DynamicMessage::decode(payload_desc, value) {
+            Ok(payload) => payload,
+            Err(_) => return false,
+        };
+
+        self.write_field_start(&format!("[{}]", type_url));
+        self.write_message_value(&payload);
+        true
+    }
+
+    fn write_field(&mut self, name: &str, value: &Value, kind: &Kind) {
+        match value {
+            Value::List(values) => {
+                for value in values {
+                    self.write_field_start(name);
+                    self.write_value(value, kind);
+                }
+            }
+            Value::Map(values) => {
+                let entry = kind.as_message().expect("map field must be a message");
+                let value_kind = entry.map_entry_value_field().kind();
+
+                let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+                for (key, value) in entries {
+                    self.write_field_start(name);
+                    self.output.push(' ');
+                    self.write_block(|writer| {
+                        writer.write_field_start("key");
+                        writer.write_map_key(key);
+                        writer.write_field_start("value");
+                        writer.write_value(value, &value_kind);
+                    });
+                }
+            }
+            value => {
+                self.write_field_start(name);
+                self.write_value(value, kind);
+            }
+        }
+    }
+
+    /// Writes the separator before a field, and its name.
+    fn write_field_start(&mut self, name: &str) {
+        if !self.first || self.depth > 0 {
+            if self.options.pretty {
+                self.output.push('\n');
+                for _ in 0..self.depth {
+                    self.output.push_str(INDENT);
+                }
+            } else {
+                self.output.push(' ');
+            }
+        }
+        self.first = false;
+        self.output.push_str(name);
+    }
+
+    fn write_value(&mut self, value: &Value, kind: &Kind) {
+        if let Value::Message(message) = value {
+            return self.write_message_value(message);
+        }
+
+        self.output.push_str(": ");
+        self.write_scalar(value, Some(kind));
+    }
+
+    /// Writes a value which is not part of a message field, so its kind is not known. Messages are written
+    /// in braces, lists in brackets, and maps as a list of entries sorted by key.
+    pub(super) fn write_standalone_value(&mut self, value: &Value) {
+        match value {
+            Value::Message(message) => self.write_block(|writer| writer.write_message(message)),
+            Value::List(values) => {
+                self.output.push('[');
+                for (index, value) in values.iter().enumerate() {
+                    if index != 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_standalone_value(value);
+                }
+                self.output.push(']');
+            }
+            Value::Map(values) => {
+                let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+
+                self.output.push('[');
+                for (index, (key, value)) in entries.into_iter().enumerate() {
+                    if index != 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_block(|writer| {
+                        writer.write_field_start("key");
+                        writer.write_map_key(key);
+                        writer.write_field_start("value");
+                        match value {
+                            Value::Message(message) => writer.write_message_value(message),
+                            value => {
+                                writer.output.push_str(": ");
+                                writer.write_scalar(value, None);
+                            }
+                        }
+                    });
+                }
+                self.output.push(']');
+            }
+            value => self.write_scalar(value, None),
+        }
+    }
+
+    /// Writes a scalar value. Enum values are written by name if `kind` is known.
+    fn write_scalar(&mut self, value: &Value, kind: Option<&Kind>) {
+        match value {
+            Value::Bool(value) => write!(self.output, "{}", value).unwrap(),
+            Value::I32(value) => write!(self.output, "{}", value).unwrap(),
+            Value::I64(value) => write!(self.output, "{}", value).unwrap(),
+            Value::U32(value) => write!(self.output, "{}", value).unwrap(),
+            Value::U64(value) => write!(self.output, "{}", value).unwrap(),
+            Value::F32(value) => self.write_float(*value as f64, value.to_string()),
+            Value::F64(value) => self.write_float(*value, value.to_string()),
+            Value::String(value) => write_escaped(self.output, value.as_bytes(), true),
+            Value::Bytes(value) => write_escaped(self.output, value, false),
+            Value::EnumNumber(number) => match kind
+                .and_then(|kind| kind.as_enum())
+                .and_then(|e| e.get_value(*number))
+            {
+                Some(value) => self.output.push_str(value.name()),
+                None => write!(self.output, "{}", number).unwrap(),
+            },
+            Value::Message(_) | Value::List(_) | Value::Map(_) => {
+                unreachable!("nested lists and maps are not supported")
+            }
+        }
+    }
+
+    fn write_message_value(&mut self, message: &DynamicMessage) {
+        self.output.push(' ');
+        self.write_block(|writer| writer.write_message(message));
+    }
+
+    fn write_block(&mut self, f: impl FnOnce(&mut Self)) {
+        self.output.push('{');
+        self.depth += 1;
+        self.first = true;
+        f(self);
+        self.depth -= 1;
+
+        if self.first && !self.options.protoc_style {
+            // The block was empty.
+            self.output.push('}');
+        } else if self.options.pretty {
+            self.output.push('\n');
+            for _ in 0..self.depth {
+                self.output.push_str(INDENT);
+            }
+            self.output.push('}');
+        } else {
+            self.output.push_str(" }");
+        }
+        self.first = false;
+    }
+
+    fn write_map_key(&mut self, key: &MapKey) {
+        self.output.push_str(": ");
+        match key {
+            MapKey::Bool(value) => write!(self.output, "{}", value).unwrap(),
+            MapKey::I32(value) => write!(self.output, "{}", value).unwrap(),
+            MapKey::I64(value) => write!(self.output, "{}", value).unwrap(),
+            MapKey::U32(value) => write!(self.output, "{}", value).unwrap(),
+            MapKey::U64(value) => write!(self.output, "{}", value).unwrap(),
+            MapKey::String(value) => write_escaped(self.output, value.as_bytes(), true),
+        }
+    }
+
+    fn write_unknown(&mut self, number: u32, unknown: &UnknownFieldValue) {
+        self.write_field_start(&number.to_string());
+        match unknown {
+            UnknownFieldValue::Varint(value) => write!(self.output, ": {}", value).unwrap(),
+            // The C++ implementation pads fixed-width values with zeros to their full width.
+            UnknownFieldValue::SixtyFourBit(value) if self.options.protoc_style => {
+                write!(self.output, ": 0x{:016x}", u64::from_le_bytes(*value)).unwrap()
+            }
+            UnknownFieldValue::ThirtyTwoBit(value) if self.options.protoc_style => {
+                write!(self.output, ": 0x{:08x}", u32::from_le_bytes(*value)).unwrap()
+            }
+            UnknownFieldValue::SixtyFourBit(value) => {
+                write!(self.output, ": 0x{:x}", u64::from_le_bytes(*value)).unwrap()
+            }
+            UnknownFieldValue::ThirtyTwoBit(value) => {
+                write!(self.output, ": 0x{:x}", u32::from_le_bytes(*value)).unwrap()
+            }
+            UnknownFieldValue::LengthDelimited(value) => {
+                self.output.push_str(": ");
+                write_escaped(self.output, value, false);
+            }
+            UnknownFieldValue::Group(group) => {
+                self.output.push(' ');
+                self.write_block(|writer| {
+                    for (number, field) in group.iter() {
+                        writer.write_unknown(number, field);
+                    }
+                })
+            }
+        }
+    }
+
+    fn write_float(&mut self, value: f64, display: String) {
+        if value.is_nan() {
+            self.output.push_str("nan");
+        } else if value.is_infinite() {
+            self.output
+                .push_str(if value > 0.0 { "inf" } else { "-inf" });
+        } else {
+            self.output.push_str(&display);
+        }
+    }
+}
+
+/// Writes a quoted string, escaping special characters as by the C++ implementation. If `utf8` is true,
+/// printable non-ASCII characters are written as-is, otherwise all bytes outside the printable ASCII range
+/// are escaped.
+fn write_escaped(output: &mut String, bytes: &[u8], utf8: bool) {
+    output.push('"');
+    match std::str::from_utf8(bytes) {
+        Ok(value) if utf8 => {
+            for ch in value.chars() {
+                if ch.is_ascii() {
+                    write_escaped_byte(output, ch as u8);
+                } else if ch.is_control() {
+                    let mut buf = [0; 4];
+                    for &byte in ch.encode_utf8(&mut buf).as_bytes() {
+                        write_escaped_byte(output, byte);
+                    }
+                } else {
+                    output.push(ch);
+                }
+            }
+        }
+        _ => {
+            for &byte in bytes {
+                write_escaped_byte(output, byte);
+            }
+        }
+    }
+    output.push('"');
+}
+
+fn write_escaped_byte(output: &mut String, byte: u8) {
+    match byte {
+        b'\n' => output.push_str("\\n"),
+        b'\r' => output.push_str("\\r"),
+        b'\t' => output.push_str("\\t"),
+        b'"' => output.push_str("\\\""),
+        b'\'' => output.push_str("\\'"),
+        b'\\' => output.push_str("\\\\"),
+        0x20..=0x7e => output.push(byte as char),
+        _ => write!(output, "\\{:03o}", byte).unwrap(),
+    }
+}
@@ -0,0 +1,263 @@
+mod format;
+mod parse;
+
+use std::{error::Error, fmt};
+
+use crate::{DynamicMessage, MessageDescriptor, Value};
+
+/// Options to control printing of the text format, used by [`DynamicMessage::to_text_format_with_options`].
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+pub struct FormatOptions {
+    pretty: bool,
+    skip_unknown_fields: bool,
+    expand_any: bool,
+    protoc_style: bool,
+}
+
+/// An error that may occur while parsing the text format.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ParseErrorKind {
+    InvalidToken { message: &'static str },
+    UnexpectedToken { expected: String, found: String },
+    FieldNotFound { message: String, name: String },
+    ExtensionNotFound { message: String, name: String },
+    TypeNotFound { type_url: String },
+    UnexpectedTypeUrl { message: String },
+    DuplicateField { field: String },
+    InvalidValue { field: String, expected: String },
+    InvalidUtf8 { field: String },
+}
+
+impl DynamicMessage {
+    /// Parses a message of the type described by `desc` from the [protobuf text format](https://developers.google.com/protocol-buffers/docs/text-format-spec).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::parse_text_format(message_descriptor, "foo: 150").unwrap();
+    /// assert_eq!(dynamic_message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn parse_text_format(desc: MessageDescriptor, input: &str) -> Result<Self, ParseError> {
+        let mut message = DynamicMessage::new(desc);
+        message.merge_text_format(input)?;
+        Ok(message)
+    }
+
+    /// Merges fields from the [protobuf text format](https://developers.google.com/protocol-buffers/docs/text-format-spec)
+    /// into this message.
+    ///
+    /// Singular fields which are already set in this message are overwritten, and repeated fields are
+    /// appended to.
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn merge_text_format(&mut self, input: &str) -> Result<(), ParseError> {
+        parse::Parser::new(input).parse_root(self)
+    }
+
+    /// Formats this message using the [protobuf text format](https://developers.google.com/protocol-buffers/docs/text-format-spec),
+    /// with the default options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// assert_eq!(dynamic_message.to_text_format(), "foo: 150");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn to_text_format(&self) -> String {
+        self.to_text_format_with_options(&FormatOptions::new())
+    }
+
+    /// Formats this message using the [protobuf text format](https://developers.google.com/protocol-buffers/docs/text-format-spec),
+    /// with the given options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let options = FormatOptions::new().pretty(true);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "foo: 150");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn to_text_format_with_options(&self, options: &FormatOptions) -> String {
+        let mut output = String::new();
+        format::Writer::new(&mut output, options).write_message(self);
+        output
+    }
+
+    /// Formats this message using the [protobuf text format](https://developers.google.com/protocol-buffers/docs/text-format-spec),
+    /// matching the output of `protoc --decode`.
+    ///
+    /// Each field is written on its own line, with nested messages indented by two spaces, and every line,
+    /// including the last, is terminated by a newline. Unknown fields are included, and
+    /// `google.protobuf.Any` messages are expanded when their type is found in the message's descriptor
+    /// pool. This makes the output suitable for diffable golden files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x10\x01".as_ref()).unwrap();
+    /// assert_eq!(dynamic_message.to_text_format_pretty(), "foo: 150\n2: 1\n");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn to_text_format_pretty(&self) -> String {
+        let options = FormatOptions {
+            pretty: true,
+            skip_unknown_fields: false,
+            expand_any: true,
+            protoc_style: true,
+        };
+
+        let mut output = self.to_text_format_with_options(&options);
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+impl fmt::Display for DynamicMessage {
+    /// Formats this message using the protobuf text format. The alternate flag (`{:#}`) enables pretty-printing.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = FormatOptions::new().pretty(f.alternate());
+        f.write_str(&self.to_text_format_with_options(&options))
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+impl fmt::Display for Value {
+    /// Formats this value using the protobuf text format. The alternate flag (`{:#}`) enables pretty-printing.
+    ///
+    /// Since the field a value belongs to is not known, enum values are printed by number. Messages are
+    /// printed in braces, lists in brackets, and maps as a list of entries sorted by key, for example
+    /// `[{ key: "a" value: 1 }, { key: "b" value: 2 }]`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = FormatOptions::new().pretty(f.alternate());
+        let mut output = String::new();
+        format::Writer::new(&mut output, &options).write_standalone_value(self);
+        f.write_str(&output)
+    }
+}
+
+impl FormatOptions {
+    /// Creates a new instance of [`FormatOptions`], with the default options chosen to match the behavior of
+    /// the C++ implementation.
+    pub const fn new() -> Self {
+        FormatOptions {
+            pretty: false,
+            skip_unknown_fields: true,
+            expand_any: true,
+            protoc_style: false,
+        }
+    }
+
+    /// Whether to prettify the format by adding whitespace and newlines.
+    ///
+    /// The default value is `false`.
+    pub const fn pretty(mut self, yes: bool) -> Self {
+        self.pretty = yes;
+        self
+    }
+
+    /// Whether to skip unknown fields, which are otherwise printed by their field number.
+    ///
+    /// The default value is `true`.
+    pub const fn skip_unknown_fields(mut self, yes: bool) -> Self {
+        self.skip_unknown_fields = yes;
+        self
+    }
+
+    /// Whether to print `google.protobuf.Any` messages in their expanded form, using the type name in
+    /// brackets, when the type is found in the message's descriptor pool.
+    ///
+    /// The default value is `true`.
+    pub const fn expand_any(mut self, yes: bool) -> Self {
+        self.expand_any = yes;
+        self
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParseError {
+    /// Gets the line of the input where the error occurred, starting from `1`.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Gets the column of the input where the error occurred, in characters starting from `1`.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::InvalidToken { message } => f.write_str(message),
+            ParseErrorKind::UnexpectedToken { expected, found } => {
+                write!(f, "expected {}, but found {}", expected, found)
+            }
+            ParseErrorKind::FieldNotFound { message, name } => {
+                write!(f, "message '{}' has no field named '{}'", message, name)
+            }
+            ParseErrorKind::ExtensionNotFound { message, name } => {
+                write!(f, "message '{}' has no extension named '{}'", message, name)
+            }
+            ParseErrorKind::TypeNotFound { type_url } => {
+                write!(f, "message type for '{}' not found", type_url)
+            }
+            ParseErrorKind::UnexpectedTypeUrl { message } => write!(
+                f,
+                "a type URL can only be used in 'google.protobuf.Any', not '{}'",
+                message
+            ),
+            ParseErrorKind::DuplicateField { field } => {
+                write!(f, "field '{}' is specified multiple times", field)
+            }
+            ParseErrorKind::InvalidValue { field, expected } => {
+                write!(
+                    f,
+                    "invalid value for field '{}': expected {}",
+                    field, expected
+                )
+            }
+            ParseErrorKind::InvalidUtf8 { field } => {
+                write!(f, "invalid UTF-8 in string field '{}'", field)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,710 @@
+use std::{collections::HashSet, convert::TryFrom};
+
+use prost::{bytes::Bytes, Message};
+
+use crate::{
+    dynamic::fields::FieldDescriptorLike, DynamicMessage, Kind, MapKey, MessageDescriptor, Value,
+};
+
+use super::{ParseError, ParseErrorKind};
+
+const RECURSION_LIMIT: u32 = 100;
+const ANY_NAME: &str = "google.protobuf.Any";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(String),
+    Float(String),
+    String(Vec<u8>),
+    Punct(char),
+    Eof,
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+pub(super) struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<(Token, usize)>,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(value) | Token::Int(value) | Token::Float(value) => {
+                format!("'{}'", value)
+            }
+            Token::String(_) => "a string".to_owned(),
+            Token::Punct(ch) => format!("'{}'", ch),
+            Token::Eof => "end of input".to_owned(),
+        }
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump_char(&mut self) -> Option<char> {
+        let ch = self.peek_char()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(ch) = self.peek_char() {
+            if ch == '#' {
+                while !matches!(self.bump_char(), Some('\n') | None) {}
+            } else if ch.is_whitespace() {
+                self.bump_char();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<(Token, usize), (ParseErrorKind, usize)> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let ch = match self.peek_char() {
+            Some(ch) => ch,
+            None => return Ok((Token::Eof, start)),
+        };
+
+        let token = if ch.is_ascii_alphabetic() || ch == '_' {
+            while matches!(self.peek_char(), Some(ch) if ch.is_ascii_alphanumeric() || ch == '_') {
+                self.bump_char();
+            }
+            Token::Ident(self.input[start..self.pos].to_owned())
+        } else if ch.is_ascii_digit()
+            || (ch == '.'
+                && matches!(self.input[start + 1..].chars().next(), Some(ch) if ch.is_ascii_digit()))
+        {
+            self.number(start)
+        } else if ch == '"' || ch == '\'' {
+            self.string(ch).map_err(|kind| (kind, start))?
+        } else if ":;,{}<>[]/-.".contains(ch) {
+            self.bump_char();
+            Token::Punct(ch)
+        } else {
+            return Err((
+                ParseErrorKind::InvalidToken {
+                    message: "unexpected character",
+                },
+                start,
+            ));
+        };
+        Ok((token, start))
+    }
+
+    fn number(&mut self, start: usize) -> Token {
+        let is_hex = self.input[start..].starts_with("0x") || self.input[start..].starts_with("0X");
+        while let Some(ch) = self.peek_char() {
+            let prev = self.input[..self.pos].chars().next_back();
+            if ch.is_ascii_alphanumeric()
+                || ch == '_'
+                || ch == '.'
+                || (!is_hex && (ch == '+' || ch == '-') && matches!(prev, Some('e' | 'E')))
+            {
+                self.bump_char();
+            } else {
+                break;
+            }
+        }
+
+        let text = &self.input[start..self.pos];
+        if !is_hex
+            && (text.contains(|ch| matches!(ch, '.' | 'e' | 'E'))
+                || text.ends_with(|ch| matches!(ch, 'f' | 'F')))
+        {
+            Token::Float(text.to_owned())
+        } else {
+            Token::Int(text.to_owned())
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<Token, ParseErrorKind> {
+        let unterminated = ParseErrorKind::InvalidToken {
+            message: "unterminated string",
+        };
+        let invalid_escape = ParseErrorKind::InvalidToken {
+            message: "invalid escape sequence",
+        };
+
+        self.bump_char();
+        let mut value = Vec::new();
+        loop {
+            match self.bump_char() {
+                None | Some('\n') => return Err(unterminated),
+                Some(ch) if ch == quote => return Ok(Token::String(value)),
+                Some('\\') => {
+                    let byte = match self.bump_char() {
+                        Some('n') => b'\n',
+                        Some('r') => b'\r',
+                        Some('t') => b'\t',
+                        Some('a') => b'\x07',
+                        Some('b') => b'\x08',
+                        Some('f') => b'\x0c',
+                        Some('v') => b'\x0b',
+                        Some('?') => b'?',
+                        Some('\\') => b'\\',
+                        Some('\'') => b'\'',
+                        Some('"') => b'"',
+                        Some(ch @ '0'..='7') => {
+                            let mut byte = ch.to_digit(8).unwrap();
+                            for _ in 0..2 {
+                                match self.peek_char().and_then(|ch| ch.to_digit(8)) {
+                                    Some(digit) => {
+                                        self.bump_char();
+                                        byte = byte * 8 + digit;
+                                    }
+                                    None => break,
+                                }
+                            }
+                            u8::try_from(byte).map_err(|_| invalid_escape.clone())?
+                        }
+                        Some('x') => {
+                            let digits = self.hex_digits(2);
+                            u8::from_str_radix(digits, 16).map_err(|_| invalid_escape.clone())?
+                        }
+                        Some(escape @ ('u' | 'U')) => {
+                            let len = if escape == 'u' { 4 } else { 8 };
+                            let digits = self.hex_digits(len);
+                            if digits.len() != len {
+                                return Err(invalid_escape);
+                            }
+                            let ch = u32::from_str_radix(digits, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| invalid_escape.clone())?;
+                            let mut buf = [0; 4];
+                            value.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                            continue;
+                        }
+                        _ => return Err(invalid_escape),
+                    };
+                    value.push(byte);
+                }
+                Some(ch) => {
+                    let mut buf = [0; 4];
+                    value.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+    }
+
+    fn hex_digits(&mut self, max_len: usize) -> &'a str {
+        let start = self.pos;
+        while self.pos - start < max_len
+            && matches!(self.peek_char(), Some(ch) if ch.is_ascii_hexdigit())
+        {
+            self.bump_char();
+        }
+        &self.input[start..self.pos]
+    }
+}
+
+impl<'a> Parser<'a> {
+    pub(super) fn new(input: &'a str) -> Self {
+        Parser {
+            lexer: Lexer { input, pos: 0 },
+            peeked: None,
+        }
+    }
+
+    pub(super) fn parse_root(&mut self, message: &mut DynamicMessage) -> Result<(), ParseError> {
+        self.parse_fields(message, None, RECURSION_LIMIT)
+    }
+
+    fn peek(&mut self) -> Result<&Token, ParseError> {
+        if self.peeked.is_none() {
+            let next = self
+                .lexer
+                .next()
+                .map_err(|(kind, pos)| self.error(pos, kind))?;
+            self.peeked = Some(next);
+        }
+        Ok(&self.peeked.as_ref().unwrap().0)
+    }
+
+    fn peek_pos(&mut self) -> Result<usize, ParseError> {
+        self.peek()?;
+        Ok(self.peeked.as_ref().unwrap().1)
+    }
+
+    fn bump(&mut self) -> Result<(Token, usize), ParseError> {
+        self.peek()?;
+        Ok(self.peeked.take().unwrap())
+    }
+
+    fn eat_punct(&mut self, ch: char) -> Result<bool, ParseError> {
+        if self.peek()? == &Token::Punct(ch) {
+            self.bump()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect_punct(&mut self, ch: char) -> Result<(), ParseError> {
+        let (token, pos) = self.bump()?;
+        if token == Token::Punct(ch) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&token, pos, &format!("'{}'", ch)))
+        }
+    }
+
+    fn error(&self, pos: usize, kind: ParseErrorKind) -> ParseError {
+        let before = &self.lexer.input[..pos];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            kind,
+        }
+    }
+
+    fn unexpected(&self, token: &Token, pos: usize, expected: &str) -> ParseError {
+        self.error(
+            pos,
+            ParseErrorKind::UnexpectedToken {
+                expected: expected.to_owned(),
+                found: token.describe(),
+            },
+        )
+    }
+
+    fn parse_fields(
+        &mut self,
+        message: &mut DynamicMessage,
+        end: Option<char>,
+        limit: u32,
+    ) -> Result<(), ParseError> {
+        let mut seen = HashSet::new();
+        loop {
+            match (self.peek()?, end) {
+                (Token::Eof, None) => return Ok(()),
+                (Token::Punct(ch), Some(end)) if *ch == end => {
+                    self.bump()?;
+                    return Ok(());
+                }
+                _ => (),
+            }
+
+            self.parse_field(message, &mut seen, limit)?;
+            if !self.eat_punct(';')? {
+                self.eat_punct(',')?;
+            }
+        }
+    }
+
+    fn parse_field(
+        &mut self,
+        message: &mut DynamicMessage,
+        seen: &mut HashSet<u32>,
+        limit: u32,
+    ) -> Result<(), ParseError> {
+        let (token, pos) = self.bump()?;
+        match token {
+            Token::Punct('[') => {
+                let name = self.parse_bracketed_name()?;
+                if name.contains('/') {
+                    if !seen.insert(0) {
+                        return Err(self.error(
+                            pos,
+                            ParseErrorKind::DuplicateField {
+                                field: format!("{}.value", ANY_NAME),
+                            },
+                        ));
+                    }
+                    return self.parse_any(message, name, pos, limit);
+                }
+
+                let extension = message
+                    .desc
+                    .get_extension_by_json_name(&format!("[{}]", name))
+                    .ok_or_else(|| {
+                        self.error(
+                            pos,
+                            ParseErrorKind::ExtensionNotFound {
+                                message: message.desc.full_name().to_owned(),
+                                name: name.clone(),
+                            },
+                        )
+                    })?;
+                let full_name = extension.full_name().to_owned();
+                self.parse_field_value(message, &extension, &full_name, pos, seen, limit)
+            }
+            Token::Ident(name) => {
+                let field = find_field(&message.desc, &name).ok_or_else(|| {
+                    self.error(
+                        pos,
+                        ParseErrorKind::FieldNotFound {
+                            message: message.desc.full_name().to_owned(),
+                            name,
+                        },
+                    )
+                })?;
+                let full_name = field.full_name().to_owned();
+                self.parse_field_value(message, &field, &full_name, pos, seen, limit)
+            }
+            token => Err(self.unexpected(&token, pos, "a field name")),
+        }
+    }
+
+    fn parse_bracketed_name(&mut self) -> Result<String, ParseError> {
+        let mut name = String::new();
+        loop {
+            let (token, pos) = self.bump()?;
+            match token {
+                Token::Ident(ident) => name.push_str(&ident),
+                Token::Punct(ch @ ('.' | '/' | '-')) => name.push(ch),
+                Token::Punct(']') if !name.is_empty() => return Ok(name),
+                token => return Err(self.unexpected(&token, pos, "an extension name or type URL")),
+            }
+        }
+    }
+
+    fn parse_any(
+        &mut self,
+        message: &mut DynamicMessage,
+        type_url: String,
+        pos: usize,
+        limit: u32,
+    ) -> Result<(), ParseError> {
+        if message.desc.full_name() != ANY_NAME {
+            return Err(self.error(
+                pos,
+                ParseErrorKind::UnexpectedTypeUrl {
+                    message: message.desc.full_name().to_owned(),
+                },
+            ));
+        }
+
+        let type_name = type_url.rsplit('/').next().unwrap_or_default();
+        let payload_desc = message
+            .desc
+            .parent_pool()
+            .get_message_by_name(type_name)
+            .ok_or_else(|| {
+                self.error(
+                    pos,
+                    ParseErrorKind::TypeNotFound {
+                        type_url: type_url.clone(),
+                    },
+                )
+            })?;
+
+        self.eat_punct(':')?;
+        let mut payload = DynamicMessage::new(payload_desc);
+        self.parse_message_value(&mut payload, limit)?;
+
+        message.set_field_by_name("type_url", Value::String(type_url));
+        message.set_field_by_name("value", Value::Bytes(Bytes::from(payload.encode_to_vec())));
+        Ok(())
+    }
+
+    fn parse_field_value(
+        &mut self,
+        message: &mut DynamicMessage,
+        field: &impl FieldDescriptorLike,
+        full_name: &str,
+        pos: usize,
+        seen: &mut HashSet<u32>,
+        limit: u32,
+    ) -> Result<(), ParseError> {
+        let kind = field.kind();
+        if kind.as_message().is_some() {
+            self.eat_punct(':')?;
+        } else {
+            self.expect_punct(':')?;
+        }
+
+        if field.is_list() || field.is_map() {
+            if self.eat_punct('[')? {
+                if self.eat_punct(']')? {
+                    return Ok(());
+                }
+                loop {
+                    let value = self.parse_value(&kind, full_name, limit)?;
+                    insert_value(message, field, value);
+
+                    let (token, pos) = self.bump()?;
+                    match token {
+                        Token::Punct(',') => continue,
+                        Token::Punct(']') => return Ok(()),
+                        token => return Err(self.unexpected(&token, pos, "',' or ']'")),
+                    }
+                }
+            } else {
+                let value = self.parse_value(&kind, full_name, limit)?;
+                insert_value(message, field, value);
+                Ok(())
+            }
+        } else {
+            if !seen.insert(field.number()) {
+                return Err(self.error(
+                    pos,
+                    ParseErrorKind::DuplicateField {
+                        field: full_name.to_owned(),
+                    },
+                ));
+            }
+            let value = self.parse_value(&kind, full_name, limit)?;
+            message.fields.set(field, value);
+            Ok(())
+        }
+    }
+
+    fn parse_value(
+        &mut self,
+        kind: &Kind,
+        full_name: &str,
+        limit: u32,
+    ) -> Result<Value, ParseError> {
+        let pos = self.peek_pos()?;
+        let invalid_value = |parser: &Self, expected: &str| {
+            parser.error(
+                pos,
+                ParseErrorKind::InvalidValue {
+                    field: full_name.to_owned(),
+                    expected: expected.to_owned(),
+                },
+            )
+        };
+
+        match kind {
+            Kind::Message(desc) => {
+                let mut message = DynamicMessage::new(desc.clone());
+                self.parse_message_value(&mut message, limit)?;
+                Ok(Value::Message(message))
+            }
+            Kind::Enum(enum_desc) => {
+                let expected = format!("a value of enum '{}'", enum_desc.full_name());
+                if let Token::Ident(name) = self.peek()? {
+                    let value = enum_desc.get_value_by_name(name);
+                    self.bump()?;
+                    return match value {
+                        Some(value) => Ok(Value::EnumNumber(value.number())),
+                        None => Err(invalid_value(self, &expected)),
+                    };
+                }
+                match self
+                    .parse_integer()?
+                    .and_then(|value| i32::try_from(value).ok())
+                {
+                    Some(value) => Ok(Value::EnumNumber(value)),
+                    None => Err(invalid_value(self, &expected)),
+                }
+            }
+            Kind::Bool => {
+                let (token, _) = self.bump()?;
+                match token {
+                    Token::Ident(ident) if matches!(ident.as_str(), "true" | "True" | "t") => {
+                        Ok(Value::Bool(true))
+                    }
+                    Token::Ident(ident) if matches!(ident.as_str(), "false" | "False" | "f") => {
+                        Ok(Value::Bool(false))
+                    }
+                    Token::Int(int) if int == "1" => Ok(Value::Bool(true)),
+                    Token::Int(int) if int == "0" => Ok(Value::Bool(false)),
+                    _ => Err(invalid_value(self, "a boolean")),
+                }
+            }
+            Kind::String | Kind::Bytes => {
+                let mut bytes = match self.bump()? {
+                    (Token::String(bytes), _) => bytes,
+                    (token, pos) => return Err(self.unexpected(&token, pos, "a string")),
+                };
+                while let Token::String(_) = self.peek()? {
+                    if let (Token::String(next), _) = self.bump()? {
+                        bytes.extend_from_slice(&next);
+                    }
+                }
+
+                if matches!(kind, Kind::Bytes) {
+                    Ok(Value::Bytes(Bytes::from(bytes)))
+                } else {
+                    String::from_utf8(bytes).map(Value::String).map_err(|_| {
+                        self.error(
+                            pos,
+                            ParseErrorKind::InvalidUtf8 {
+                                field: full_name.to_owned(),
+                            },
+                        )
+                    })
+                }
+            }
+            Kind::Double => match self.parse_float()? {
+                Some(value) => Ok(Value::F64(value)),
+                None => Err(invalid_value(self, "a number")),
+            },
+            Kind::Float => match self.parse_float()? {
+                Some(value) => Ok(Value::F32(value as f32)),
+                None => Err(invalid_value(self, "a number")),
+            },
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                match self
+                    .parse_integer()?
+                    .and_then(|value| i32::try_from(value).ok())
+                {
+                    Some(value) => Ok(Value::I32(value)),
+                    None => Err(invalid_value(self, "a 32-bit integer")),
+                }
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                match self
+                    .parse_integer()?
+                    .and_then(|value| i64::try_from(value).ok())
+                {
+                    Some(value) => Ok(Value::I64(value)),
+                    None => Err(invalid_value(self, "a 64-bit integer")),
+                }
+            }
+            Kind::Uint32 | Kind::Fixed32 => {
+                match self
+                    .parse_integer()?
+                    .and_then(|value| u32::try_from(value).ok())
+                {
+                    Some(value) => Ok(Value::U32(value)),
+                    None => Err(invalid_value(self, "an unsigned 32-bit integer")),
+                }
+            }
+            Kind::Uint64 | Kind::Fixed64 => {
+                match self
+                    .parse_integer()?
+                    .and_then(|value| u64::try_from(value).ok())
+                {
+                    Some(value) => Ok(Value::U64(value)),
+                    None => Err(invalid_value(self, "an unsigned 64-bit integer")),
+                }
+            }
+        }
+    }
+
+    fn parse_message_value(
+        &mut self,
+        message: &mut DynamicMessage,
+        limit: u32,
+    ) -> Result<(), ParseError> {
+        let (token, pos) = self.bump()?;
+        let end = match token {
+            Token::Punct('{') => '}',
+            Token::Punct('<') => '>',
+            token => return Err(self.unexpected(&token, pos, "'{'")),
+        };
+
+        if limit == 0 {
+            return Err(self.error(
+                pos,
+                ParseErrorKind::InvalidToken {
+                    message: "recursion limit reached",
+                },
+            ));
+        }
+        self.parse_fields(message, Some(end), limit - 1)
+    }
+
+    /// Parses an optionally negated integer, returning `None` if it is not a valid integer literal.
+    fn parse_integer(&mut self) -> Result<Option<i128>, ParseError> {
+        let negative = self.eat_punct('-')?;
+        match self.bump()? {
+            (Token::Int(int), _) => Ok(parse_int_literal(&int).map(|value| {
+                if negative {
+                    -i128::from(value)
+                } else {
+                    i128::from(value)
+                }
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parses an optionally negated floating point number, returning `None` if it is not a valid number.
+    fn parse_float(&mut self) -> Result<Option<f64>, ParseError> {
+        let negative = self.eat_punct('-')?;
+        let value = match self.bump()? {
+            (Token::Float(float), _) => float
+                .trim_end_matches(|ch| ch == 'f' || ch == 'F')
+                .parse::<f64>()
+                .ok(),
+            (Token::Int(int), _) => parse_int_literal(&int)
+                .map(|value| value as f64)
+                .or_else(|| int.parse().ok()),
+            (Token::Ident(ident), _) => match ident.to_ascii_lowercase().as_str() {
+                "inf" | "infinity" => Some(f64::INFINITY),
+                "nan" => Some(f64::NAN),
+                _ => None,
+            },
+            _ => None,
+        };
+        Ok(value.map(|value| if negative { -value } else { value }))
+    }
+}
+
+fn find_field(desc: &MessageDescriptor, name: &str) -> Option<crate::FieldDescriptor> {
+    desc.get_field_by_name(name).or_else(|| {
+        // Group fields are named by their message type.
+        desc.fields().find(|field| {
+            field.is_group()
+                && field
+                    .kind()
+                    .as_message()
+                    .map_or(false, |group| group.name() == name)
+        })
+    })
+}
+
+fn insert_value(message: &mut DynamicMessage, field: &impl FieldDescriptorLike, value: Value) {
+    if field.is_map() {
+        let entry = match value {
+            Value::Message(entry) => entry,
+            _ => unreachable!("map entry must be a message"),
+        };
+        let key_field = entry.desc.map_entry_key_field();
+        let value_field = entry.desc.map_entry_value_field();
+        let key = value_to_map_key(entry.get_field(&key_field).into_owned());
+        let value = entry.get_field(&value_field).into_owned();
+
+        message
+            .fields
+            .get_mut(field)
+            .as_map_mut()
+            .expect("expected map value")
+            .insert(key, value);
+    } else {
+        message
+            .fields
+            .get_mut(field)
+            .as_list_mut()
+            .expect("expected list value")
+            .push(value);
+    }
+}
+
+fn value_to_map_key(value: Value) -> MapKey {
+    match value {
+        Value::Bool(value) => MapKey::Bool(value),
+        Value::I32(value) => MapKey::I32(value),
+        Value::I64(value) => MapKey::I64(value),
+        Value::U32(value) => MapKey::U32(value),
+        Value::U64(value) => MapKey::U64(value),
+        Value::String(value) => MapKey::String(value),
+        _ => unreachable!("invalid map key type"),
+    }
+}
+
+fn parse_int_literal(int: &str) -> Option<u64> {
+    if let Some(hex) = int.strip_prefix("0x").or_else(|| int.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if int.len() > 1 && int.starts_with('0') {
+        u64::from_str_radix(&int[1..], 8).ok()
+    } else {
+        int.parse().ok()
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use prost::bytes::Bytes;
+
+use crate::{DynamicMessage, FieldDescriptor, Kind, MapKey, Value};
+
+use super::bind::expected_type;
+
+macro_rules! typed_accessors {
+    ($($name:ident, $by_name:ident -> $ty:ty, $as:ident, $wanted:literal, $accepts:expr;)*) => {
+        impl DynamicMessage {
+            $(
+                #[doc = concat!("Gets the value of the given field as ", $wanted, ", or `None` if it is unset.")]
+                ///
+                /// See [`get_field_opt`][Self::get_field_opt] for when a field is considered unset.
+                ///
+                /// # Panics
+                ///
+                #[doc = concat!("Panics if the field does not hold ", $wanted, ".")]
+                pub fn $name(&self, field_desc: &FieldDescriptor) -> Option<$ty> {
+                    let accepts: fn(&FieldDescriptor) -> bool = $accepts;
+                    if !accepts(field_desc) {
+                        kind_mismatch(field_desc, $wanted);
+                    }
+                    self.get_field_opt(field_desc).and_then(Value::$as)
+                }
+
+                #[doc = concat!("Gets the value of the field with the given name as ", $wanted, ", or `None` if it is unset.")]
+                ///
+                /// If the message has no field with the given name, `None` is returned.
+                ///
+                #[doc = concat!("See [`", stringify!($name), "`][Self::", stringify!($name), "] for more details.")]
+                pub fn $by_name(&self, name: &str) -> Option<$ty> {
+                    let field_desc = self.desc.get_field_by_name(name)?;
+                    self.$name(&field_desc)
+                }
+            )*
+        }
+    };
+}
+
+typed_accessors! {
+    get_bool, get_bool_by_name -> bool, as_bool, "a bool",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Bool));
+    get_i32, get_i32_by_name -> i32, as_i32, "an `i32`",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Int32 | Kind::Sint32 | Kind::Sfixed32));
+    get_i64, get_i64_by_name -> i64, as_i64, "an `i64`",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Int64 | Kind::Sint64 | Kind::Sfixed64));
+    get_u32, get_u32_by_name -> u32, as_u32, "a `u32`",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Uint32 | Kind::Fixed32));
+    get_u64, get_u64_by_name -> u64, as_u64, "a `u64`",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Uint64 | Kind::Fixed64));
+    get_f32, get_f32_by_name -> f32, as_f32, "an `f32`",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Float));
+    get_f64, get_f64_by_name -> f64, as_f64, "an `f64`",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Double));
+    get_str, get_str_by_name -> &str, as_str, "a string",
+        |field| is_singular(field, |kind| matches!(kind, Kind::String));
+    get_bytes, get_bytes_by_name -> &Bytes, as_bytes, "bytes",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Bytes));
+    get_enum_number, get_enum_number_by_name -> i32, as_enum_number, "an enum number",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Enum(_)));
+    get_message, get_message_by_name -> &DynamicMessage, as_message, "a message",
+        |field| is_singular(field, |kind| matches!(kind, Kind::Message(_)));
+    get_list, get_list_by_name -> &[Value], as_list, "a list",
+        |field| field.is_list();
+    get_map, get_map_by_name -> &HashMap<MapKey, Value>, as_map, "a map",
+        |field| field.is_map();
+}
+
+fn is_singular(field_desc: &FieldDescriptor, accepts: impl FnOnce(&Kind) -> bool) -> bool {
+    !field_desc.is_list() && !field_desc.is_map() && accepts(&field_desc.kind())
+}
+
+fn kind_mismatch(field_desc: &FieldDescriptor, wanted: &str) -> ! {
+    let actual = if field_desc.is_list() {
+        "a list".to_owned()
+    } else if field_desc.is_map() {
+        "a map".to_owned()
+    } else {
+        expected_type(&field_desc.kind())
+    };
+    panic!(
+        "cannot get field '{}' as {}, since it holds {}",
+        field_desc.full_name(),
+        wanted.replace('`', ""),
+        actual
+    )
+}
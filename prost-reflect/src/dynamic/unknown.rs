@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, convert::TryInto};
 
 use prost::{
     bytes::{Buf, BufMut, Bytes},
@@ -6,9 +6,25 @@ use prost::{
     DecodeError, Message,
 };
 
-/// An unknown field in a protobuf message.
+use crate::{DescriptorPool, DynamicMessage, Value};
+
+/// A field of a [`DynamicMessage`][crate::DynamicMessage] which was not recognized when decoding,
+/// consisting of its field number, wire type and raw value.
+///
+/// The raw value does not include the field key. It holds the encoded varint for [`WireType::Varint`],
+/// the little-endian bytes for fixed-width wire types, the contents without the length prefix for
+/// [`WireType::LengthDelimited`], and the encoded fields of the group without the end tag for
+/// [`WireType::StartGroup`].
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) enum UnknownField {
+pub struct UnknownField {
+    number: u32,
+    wire_type: WireType,
+    value: Bytes,
+}
+
+/// An unknown field in a protobuf message.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub(crate) enum UnknownFieldValue {
     /// An unknown field with the `Varint` wire type.
     Varint(u64),
     /// An unknown field with the `SixtyFourBit` wire type.
@@ -21,9 +37,9 @@ pub(crate) enum UnknownField {
     ThirtyTwoBit([u8; 4]),
 }
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq, Hash)]
 pub(crate) struct UnknownFieldSet {
-    fields: BTreeMap<u32, Vec<UnknownField>>,
+    fields: BTreeMap<u32, Vec<UnknownFieldValue>>,
 }
 
 impl Message for UnknownFieldSet {
@@ -50,7 +66,7 @@ impl Message for UnknownFieldSet {
         B: Buf,
         Self: Sized,
     {
-        let field = UnknownField::decode(number, wire_type, buf, ctx)?;
+        let field = UnknownFieldValue::decode(number, wire_type, buf, ctx)?;
         self.fields.entry(number).or_default().push(field);
         Ok(())
     }
@@ -70,27 +86,174 @@ impl Message for UnknownFieldSet {
     }
 }
 
+impl UnknownFieldSet {
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &UnknownFieldValue)> {
+        self.fields
+            .iter()
+            .flat_map(|(&number, fields)| fields.iter().map(move |field| (number, field)))
+    }
+}
+
+impl DynamicMessage {
+    /// Re-interprets the unknown fields of this message and any nested messages using the types
+    /// in `pool`.
+    ///
+    /// Extension fields can only be decoded if the extension is known when the message is decoded,
+    /// otherwise they are stored as unknown fields. This method can be used to decode such fields
+    /// after the extensions have been added, for example using
+    /// [`DescriptorPool::add_file_descriptor_proto`]. Since adding files to a pool does not affect
+    /// existing descriptors, the message type of this message is replaced by the type with the same
+    /// name in `pool`, and the message is decoded again from its encoded bytes, so that nested messages
+    /// also have their types replaced by those in `pool`. If the type of this message is not found in
+    /// `pool`, it is left unchanged and its nested messages are re-interpreted instead.
+    ///
+    /// Unknown fields which still do not match any field or extension are kept as unknown fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message cannot be decoded as the type in `pool`, for example because an
+    /// unknown field was encoded with a different wire type than the matching extension, or because
+    /// `pool` contains an incompatible definition of the message. In this case the message is left
+    /// unchanged, unless its type is not found in `pool`, in which case nested messages may already have
+    /// been updated.
+    pub fn reparse_unknown_fields(&mut self, pool: &DescriptorPool) -> Result<(), DecodeError> {
+        let desc = match pool.get_message_by_name(self.desc.full_name()) {
+            Some(desc) => desc,
+            None => {
+                for value in self.fields.values_mut() {
+                    reparse_value(value, pool)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let reparsed = DynamicMessage::decode(desc, self.encode_to_vec().as_slice())?;
+        #[cfg(feature = "serde")]
+        let reparsed = DynamicMessage {
+            unknown_json_fields: self.unknown_json_fields.take(),
+            ..reparsed
+        };
+        *self = reparsed;
+        Ok(())
+    }
+}
+
+fn reparse_value(value: &mut Value, pool: &DescriptorPool) -> Result<(), DecodeError> {
+    match value {
+        Value::Message(message) => message.reparse_unknown_fields(pool),
+        Value::List(list) => list
+            .iter_mut()
+            .try_for_each(|value| reparse_value(value, pool)),
+        Value::Map(map) => map
+            .values_mut()
+            .try_for_each(|value| reparse_value(value, pool)),
+        _ => Ok(()),
+    }
+}
+
 impl UnknownField {
+    /// Creates a new unknown field from its number, wire type and raw value.
+    ///
+    /// Returns an error if `value` is not a valid encoding for `wire_type`, or if `wire_type` is
+    /// [`WireType::EndGroup`].
+    pub fn new(number: u32, wire_type: WireType, value: Bytes) -> Result<Self, DecodeError> {
+        let field = UnknownField {
+            number,
+            wire_type,
+            value,
+        };
+        field.to_value()?;
+        Ok(field)
+    }
+
+    /// Gets the field number of this unknown field.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the wire type this unknown field was encoded with.
+    pub fn wire_type(&self) -> WireType {
+        self.wire_type
+    }
+
+    /// Gets the raw value of this unknown field, not including the field key.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    pub(crate) fn from_value(number: u32, value: &UnknownFieldValue) -> Self {
+        let (wire_type, value) = match value {
+            UnknownFieldValue::Varint(value) => {
+                let mut buf = Vec::with_capacity(encoding::encoded_len_varint(*value));
+                encoding::encode_varint(*value, &mut buf);
+                (WireType::Varint, Bytes::from(buf))
+            }
+            UnknownFieldValue::SixtyFourBit(value) => {
+                (WireType::SixtyFourBit, Bytes::copy_from_slice(value))
+            }
+            UnknownFieldValue::LengthDelimited(value) => (WireType::LengthDelimited, value.clone()),
+            UnknownFieldValue::Group(value) => {
+                (WireType::StartGroup, Bytes::from(value.encode_to_vec()))
+            }
+            UnknownFieldValue::ThirtyTwoBit(value) => {
+                (WireType::ThirtyTwoBit, Bytes::copy_from_slice(value))
+            }
+        };
+        UnknownField {
+            number,
+            wire_type,
+            value,
+        }
+    }
+
+    pub(crate) fn to_value(&self) -> Result<UnknownFieldValue, DecodeError> {
+        match self.wire_type {
+            WireType::Varint => {
+                let mut buf = self.value.as_ref();
+                let value = encoding::decode_varint(&mut buf)?;
+                if buf.has_remaining() {
+                    return Err(DecodeError::new("unexpected trailing bytes after varint"));
+                }
+                Ok(UnknownFieldValue::Varint(value))
+            }
+            WireType::SixtyFourBit => match self.value.as_ref().try_into() {
+                Ok(value) => Ok(UnknownFieldValue::SixtyFourBit(value)),
+                Err(_) => Err(DecodeError::new("expected 8 bytes for a 64-bit value")),
+            },
+            WireType::LengthDelimited => Ok(UnknownFieldValue::LengthDelimited(self.value.clone())),
+            WireType::StartGroup => Ok(UnknownFieldValue::Group(UnknownFieldSet::decode(
+                self.value.clone(),
+            )?)),
+            WireType::EndGroup => Err(DecodeError::new("unexpected end group tag")),
+            WireType::ThirtyTwoBit => match self.value.as_ref().try_into() {
+                Ok(value) => Ok(UnknownFieldValue::ThirtyTwoBit(value)),
+                Err(_) => Err(DecodeError::new("expected 4 bytes for a 32-bit value")),
+            },
+        }
+    }
+}
+
+impl UnknownFieldValue {
     pub fn encode_field<B>(&self, number: u32, buf: &mut B)
     where
         B: BufMut,
     {
         match self {
-            UnknownField::Varint(value) => {
+            UnknownFieldValue::Varint(value) => {
                 encoding::encode_key(number, WireType::Varint, buf);
                 encoding::encode_varint(*value, buf);
             }
-            UnknownField::SixtyFourBit(value) => {
+            UnknownFieldValue::SixtyFourBit(value) => {
                 encoding::encode_key(number, WireType::SixtyFourBit, buf);
                 buf.put_slice(value);
             }
-            UnknownField::LengthDelimited(value) => {
+            UnknownFieldValue::LengthDelimited(value) => {
                 encoding::bytes::encode(number, value, buf);
             }
-            UnknownField::Group(value) => {
+            UnknownFieldValue::Group(value) => {
                 encoding::group::encode(number, value, buf);
             }
-            UnknownField::ThirtyTwoBit(value) => {
+            UnknownFieldValue::ThirtyTwoBit(value) => {
                 encoding::encode_key(number, WireType::ThirtyTwoBit, buf);
                 buf.put_slice(value);
             }
@@ -109,7 +272,7 @@ impl UnknownField {
         match wire_type {
             WireType::Varint => {
                 let value = encoding::decode_varint(buf)?;
-                Ok(UnknownField::Varint(value))
+                Ok(UnknownFieldValue::Varint(value))
             }
             WireType::SixtyFourBit => {
                 let mut value = [0; 8];
@@ -117,17 +280,17 @@ impl UnknownField {
                     return Err(DecodeError::new("buffer underflow"));
                 }
                 buf.copy_to_slice(&mut value);
-                Ok(UnknownField::SixtyFourBit(value))
+                Ok(UnknownFieldValue::SixtyFourBit(value))
             }
             WireType::LengthDelimited => {
                 let mut value = Bytes::default();
                 encoding::bytes::merge(wire_type, &mut value, buf, ctx)?;
-                Ok(UnknownField::LengthDelimited(value))
+                Ok(UnknownFieldValue::LengthDelimited(value))
             }
             WireType::StartGroup => {
                 let mut value = UnknownFieldSet::default();
                 encoding::group::merge(number, wire_type, &mut value, buf, ctx)?;
-                Ok(UnknownField::Group(value))
+                Ok(UnknownFieldValue::Group(value))
             }
             WireType::EndGroup => Err(DecodeError::new("unexpected end group tag")),
             WireType::ThirtyTwoBit => {
@@ -136,20 +299,22 @@ impl UnknownField {
                     return Err(DecodeError::new("buffer underflow"));
                 }
                 buf.copy_to_slice(&mut value);
-                Ok(UnknownField::ThirtyTwoBit(value))
+                Ok(UnknownFieldValue::ThirtyTwoBit(value))
             }
         }
     }
 
     pub fn encoded_len(&self, number: u32) -> usize {
         match self {
-            UnknownField::Varint(value) => {
+            UnknownFieldValue::Varint(value) => {
                 encoding::key_len(number) + encoding::encoded_len_varint(*value)
             }
-            UnknownField::SixtyFourBit(value) => encoding::key_len(number) + value.len(),
-            UnknownField::LengthDelimited(value) => encoding::bytes::encoded_len(number, value),
-            UnknownField::Group(value) => encoding::group::encoded_len(number, value),
-            UnknownField::ThirtyTwoBit(value) => encoding::key_len(number) + value.len(),
+            UnknownFieldValue::SixtyFourBit(value) => encoding::key_len(number) + value.len(),
+            UnknownFieldValue::LengthDelimited(value) => {
+                encoding::bytes::encoded_len(number, value)
+            }
+            UnknownFieldValue::Group(value) => encoding::group::encoded_len(number, value),
+            UnknownFieldValue::ThirtyTwoBit(value) => encoding::key_len(number) + value.len(),
         }
     }
 }
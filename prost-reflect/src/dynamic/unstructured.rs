@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use arbitrary::{Result, Unstructured};
+use prost::bytes::Bytes;
+
+use crate::{Cardinality, DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value};
+
+use super::fields::FieldDescriptorLike;
+
+/// The maximum depth of nested messages to generate. Deeper message fields are left unset, unless they are required.
+const MAX_DEPTH: u32 = 16;
+
+impl DynamicMessage {
+    /// Generates a message of the type described by `desc` from the raw bytes in `u`, using the
+    /// [`arbitrary`](https://crates.io/crates/arbitrary) crate.
+    ///
+    /// This is intended for fuzz targets, such as those run by `cargo fuzz`, which need structured input
+    /// for a message type that is only known at runtime. Since [`DynamicMessage`] cannot be created
+    /// without a descriptor, it does not implement the [`Arbitrary`](arbitrary::Arbitrary) trait.
+    ///
+    /// Generated messages are always valid for their descriptor: at most one field of each oneof is set,
+    /// required fields are always populated, and fields without presence are only set to non-default
+    /// values. Enum fields are set to a declared value. Floating-point fields may be NaN or infinite, and
+    /// strings may contain any Unicode characters. Message fields nested more than 16 levels deep are
+    /// left unset, or empty if they are required. Extension fields and unknown fields are never generated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use arbitrary::Unstructured;
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let data = [1, 2, 3, 4, 5, 6, 7, 8];
+    /// let mut u = Unstructured::new(&data);
+    /// let message = DynamicMessage::arbitrary_from_unstructured(message_descriptor, &mut u).unwrap();
+    /// assert_eq!(message.descriptor().full_name(), "package.MyMessage");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "arbitrary")))]
+    pub fn arbitrary_from_unstructured(
+        desc: MessageDescriptor,
+        u: &mut Unstructured<'_>,
+    ) -> Result<Self> {
+        message(desc, u, 0)
+    }
+}
+
+fn message(
+    desc: MessageDescriptor,
+    u: &mut Unstructured<'_>,
+    depth: u32,
+) -> Result<DynamicMessage> {
+    let mut message = DynamicMessage::new(desc.clone());
+
+    // Choose at most one field from each oneof.
+    let mut oneof_fields = Vec::new();
+    for oneof in desc.oneofs() {
+        let fields: Vec<FieldDescriptor> = oneof.fields().collect();
+        let index = u.int_in_range(0..=fields.len())?;
+        if let Some(field) = fields.get(index) {
+            oneof_fields.push(field.number());
+        }
+    }
+
+    for field in desc.fields() {
+        let required = field.cardinality() == Cardinality::Required;
+        if field.containing_oneof().is_some() {
+            if !oneof_fields.contains(&field.number()) {
+                continue;
+            }
+        } else if !required && !u.arbitrary::<bool>()? {
+            continue;
+        }
+
+        if let Some(value) = field_value(&field, u, required, depth)? {
+            if field.supports_presence() || !field.is_default_value(&value) {
+                message.set_field(&field, value);
+            }
+        }
+    }
+
+    Ok(message)
+}
+
+fn field_value(
+    field: &FieldDescriptor,
+    u: &mut Unstructured<'_>,
+    required: bool,
+    depth: u32,
+) -> Result<Option<Value>> {
+    let kind = field.kind();
+    if field.is_map() {
+        let entry = kind.as_message().expect("map field must be a message");
+        let key_kind = entry.map_entry_key_field().kind();
+        let value_kind = entry.map_entry_value_field().kind();
+
+        let mut map = HashMap::new();
+        while u.arbitrary::<bool>()? {
+            let key = map_key(&key_kind, u)?;
+            if let Some(value) = value(&value_kind, u, true, depth)? {
+                map.insert(key, value);
+            }
+        }
+        Ok(Some(Value::Map(map)))
+    } else if field.is_list() {
+        let mut list = Vec::new();
+        while u.arbitrary::<bool>()? {
+            if let Some(value) = value(&kind, u, false, depth)? {
+                list.push(value);
+            }
+        }
+        Ok(Some(Value::List(list)))
+    } else {
+        value(&kind, u, required, depth)
+    }
+}
+
+/// Generates a value of the given type. Messages beyond the maximum depth are only generated if `required` is set.
+fn value(
+    kind: &Kind,
+    u: &mut Unstructured<'_>,
+    required: bool,
+    depth: u32,
+) -> Result<Option<Value>> {
+    Ok(Some(match kind {
+        Kind::Double => Value::F64(u.arbitrary()?),
+        Kind::Float => Value::F32(u.arbitrary()?),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(u.arbitrary()?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(u.arbitrary()?),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(u.arbitrary()?),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(u.arbitrary()?),
+        Kind::Bool => Value::Bool(u.arbitrary()?),
+        Kind::String => Value::String(u.arbitrary()?),
+        Kind::Bytes => Value::Bytes(Bytes::from(u.arbitrary::<Vec<u8>>()?)),
+        Kind::Enum(enum_desc) => {
+            let values: Vec<i32> = enum_desc.values().map(|value| value.number()).collect();
+            if values.is_empty() {
+                Value::EnumNumber(0)
+            } else {
+                Value::EnumNumber(*u.choose(&values)?)
+            }
+        }
+        Kind::Message(message_desc) => {
+            if depth >= MAX_DEPTH {
+                if !required {
+                    return Ok(None);
+                }
+                // Required message fields beyond the maximum depth are left empty.
+                Value::Message(DynamicMessage::new(message_desc.clone()))
+            } else {
+                Value::Message(message(message_desc.clone(), u, depth + 1)?)
+            }
+        }
+    }))
+}
+
+fn map_key(kind: &Kind, u: &mut Unstructured<'_>) -> Result<MapKey> {
+    Ok(match kind {
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => MapKey::I32(u.arbitrary()?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => MapKey::I64(u.arbitrary()?),
+        Kind::Uint32 | Kind::Fixed32 => MapKey::U32(u.arbitrary()?),
+        Kind::Uint64 | Kind::Fixed64 => MapKey::U64(u.arbitrary()?),
+        Kind::Bool => MapKey::Bool(u.arbitrary()?),
+        Kind::String => MapKey::String(u.arbitrary()?),
+        _ => unreachable!("invalid type for map key"),
+    })
+}
@@ -0,0 +1,1176 @@
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::HashMap,
+    error::Error,
+    fmt, mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use regex::Regex;
+
+use crate::{
+    DescriptorPool, DynamicMessage, ExtensionDescriptor, FieldDescriptor, Kind, MessageDescriptor,
+    OneofDescriptor, Value,
+};
+
+use super::snapshot::{join_path, map_key_to_string};
+
+const FIELD_EXTENSIONS: &[&str] = &["buf.validate.field", "validate.rules"];
+const MESSAGE_EXTENSIONS: &[&str] = &[
+    "buf.validate.message",
+    "validate.disabled",
+    "validate.ignored",
+];
+const ONEOF_EXTENSIONS: &[&str] = &["buf.validate.oneof", "validate.required"];
+
+const TYPE_RULES: &[&str] = &[
+    "float",
+    "double",
+    "int32",
+    "int64",
+    "uint32",
+    "uint64",
+    "sint32",
+    "sint64",
+    "fixed32",
+    "fixed64",
+    "sfixed32",
+    "sfixed64",
+    "bool",
+    "string",
+    "bytes",
+    "enum",
+    "repeated",
+    "map",
+    "any",
+    "duration",
+    "timestamp",
+];
+
+const IGNORE_IF_UNPOPULATED: i32 = 1;
+const IGNORE_IF_DEFAULT_VALUE: i32 = 2;
+const IGNORE_ALWAYS: i32 = 3;
+
+/// Validates messages against the constraints declared in their schema, as described in
+/// [`DynamicMessage::validate`].
+///
+/// A `Validator` caches the rules read from descriptor options and the compiled regular expressions
+/// for `pattern` rules, so reusing one is much faster than calling [`DynamicMessage::validate`] for
+/// every message.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, DynamicMessage, Validator};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// let mut validator = Validator::new(&pool);
+/// let message = DynamicMessage::new(pool.get_message_by_name("package.MyMessage").unwrap());
+/// assert_eq!(validator.validate(&message), Ok(()));
+/// ```
+#[derive(Debug)]
+pub struct Validator {
+    pool: DescriptorPool,
+    field_extensions: Vec<ExtensionDescriptor>,
+    message_extensions: Vec<ExtensionDescriptor>,
+    oneof_extensions: Vec<ExtensionDescriptor>,
+    field_rules: HashMap<String, Option<Arc<DynamicMessage>>>,
+    regexes: HashMap<String, Option<Regex>>,
+    violations: Vec<Violation>,
+}
+
+/// An error returned by [`DynamicMessage::validate`], listing every constraint violated by the message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    violations: Vec<Violation>,
+}
+
+/// A single constraint violated by a message, as reported by [`ValidationError::violations`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    field_path: String,
+    rule_id: String,
+    message: String,
+}
+
+impl DynamicMessage {
+    /// Validates this message against the constraints declared in its schema using the
+    /// [`protovalidate`](https://github.com/bufbuild/protovalidate) `buf.validate` custom options, or
+    /// the legacy [`protoc-gen-validate`](https://github.com/bufbuild/protoc-gen-validate) `validate`
+    /// options.
+    ///
+    /// Nested messages are validated recursively, and every violation found is returned rather than
+    /// just the first. Rules are read from the options of the descriptors in the message's pool, so the
+    /// pool must contain the `validate.proto` file defining the options, and must have been created in a way
+    /// that preserves custom options (see [`MessageDescriptor::options`]). Messages whose pool
+    /// does not define either set of options always pass validation.
+    ///
+    /// The following rules are supported:
+    ///
+    /// - `required` fields and oneofs, `ignore` and `ignore_empty` on fields, and `disabled` on messages.
+    /// - `const`, `lt`, `lte`, `gt`, `gte`, `in` and `not_in` for numeric types, plus `finite` for
+    ///   floating-point types.
+    /// - `const` for `bool`.
+    /// - `const`, `len`, `min_len`, `max_len`, `len_bytes`, `min_bytes`, `max_bytes`, `pattern`,
+    ///   `prefix`, `suffix`, `contains`, `not_contains`, `in` and `not_in` for `string`, and the
+    ///   `email`, `hostname`, `ip`, `ipv4`, `ipv6`, `address` and `uuid` formats.
+    /// - `const`, `len`, `min_len`, `max_len`, `pattern`, `prefix`, `suffix`, `contains`, `in`,
+    ///   `not_in`, `ip`, `ipv4` and `ipv6` for `bytes`.
+    /// - `const`, `defined_only`, `in` and `not_in` for enums.
+    /// - `min_items`, `max_items`, `unique` and `items` for repeated fields, and `min_pairs`, `max_pairs`,
+    ///   `keys` and `values` for maps.
+    /// - `in` and `not_in` for `google.protobuf.Any`, and `const`, `lt`, `lte`, `gt`, `gte`, `in`
+    ///   and `not_in` for `google.protobuf.Duration` and `google.protobuf.Timestamp`, plus `lt_now`,
+    ///   `gt_now` and `within` for timestamps.
+    ///
+    /// Other rules, including CEL expressions, are ignored.
+    ///
+    /// This reads the rules from the descriptor options each time it is called. To validate many messages,
+    /// use a [`Validator`] instead.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        Validator::new(self.desc.parent_pool()).validate(self)
+    }
+}
+
+impl ValidationError {
+    /// Gets the constraints which were violated, in the order they were found.
+    pub fn violations(&self) -> impl ExactSizeIterator<Item = &Violation> + '_ {
+        self.violations.iter()
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "message failed validation: ")?;
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i != 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+impl Violation {
+    /// The path of the invalid value from the root message, using the same format as
+    /// [`Visit`][crate::Visit], for example `items[0].id`. The path is empty for rules on the root
+    /// message itself.
+    pub fn field_path(&self) -> &str {
+        &self.field_path
+    }
+
+    /// The identifier of the violated rule, for example `string.min_len` or `required`.
+    pub fn rule_id(&self) -> &str {
+        &self.rule_id
+    }
+
+    /// A human-readable description of the violation.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field_path.is_empty() {
+            write!(f, "{} [{}]", self.message, self.rule_id)
+        } else {
+            write!(
+                f,
+                "{}: {} [{}]",
+                self.field_path, self.message, self.rule_id
+            )
+        }
+    }
+}
+
+impl Validator {
+    /// Creates a new [`Validator`] for messages whose descriptors belong to `pool`.
+    ///
+    /// Messages from other pools may also be validated, but doing so discards the cached rules.
+    pub fn new(pool: &DescriptorPool) -> Self {
+        let find = |names: &[&str]| {
+            pool.all_extensions()
+                .filter(|ext| names.contains(&ext.full_name()))
+                .collect::<Vec<_>>()
+        };
+
+        Validator {
+            pool: pool.clone(),
+            field_extensions: find(FIELD_EXTENSIONS),
+            message_extensions: find(MESSAGE_EXTENSIONS),
+            oneof_extensions: find(ONEOF_EXTENSIONS),
+            field_rules: HashMap::new(),
+            regexes: HashMap::new(),
+            violations: Vec::new(),
+        }
+    }
+
+    /// Validates `message`, returning every constraint it violates.
+    pub fn validate(&mut self, message: &DynamicMessage) -> Result<(), ValidationError> {
+        if message.desc.parent_pool() != &self.pool {
+            *self = Validator::new(message.desc.parent_pool());
+        }
+
+        self.validate_message("", message);
+        if self.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                violations: mem::take(&mut self.violations),
+            })
+        }
+    }
+
+    fn report(&mut self, path: &str, rule_id: impl Into<String>, message: impl Into<String>) {
+        self.violations.push(Violation {
+            field_path: path.to_owned(),
+            rule_id: rule_id.into(),
+            message: message.into(),
+        });
+    }
+
+    fn validate_message(&mut self, path: &str, message: &DynamicMessage) {
+        if self.is_message_disabled(&message.desc) {
+            return;
+        }
+
+        for oneof in message.desc.oneofs() {
+            if self.is_oneof_required(&oneof) && !oneof.fields().any(|f| message.has_field(&f)) {
+                self.report(
+                    &join_path(path, oneof.name()),
+                    "required",
+                    "exactly one field is required in oneof",
+                );
+            }
+        }
+
+        for field_desc in message.desc.fields() {
+            let path = join_path(path, field_desc.name());
+            self.validate_field(&path, message, &field_desc);
+        }
+    }
+
+    fn validate_field(
+        &mut self,
+        path: &str,
+        message: &DynamicMessage,
+        field_desc: &FieldDescriptor,
+    ) {
+        let populated = message.has_field(field_desc);
+        let rules = self.field_rules(field_desc);
+        let mut skip_nested = false;
+
+        if let Some(rules) = &rules {
+            if is_required(rules) && !populated {
+                self.report(path, "required", "value is required");
+                return;
+            }
+            if is_ignored(rules, populated) {
+                return;
+            }
+            skip_nested = get_bool(rules, "message", "skip");
+        }
+
+        if !populated && field_desc.supports_presence() {
+            return;
+        }
+
+        let value = message.get_field(field_desc);
+        let kind = field_desc.kind();
+        if let Some(rules) = &rules {
+            if let Some((name, type_rules)) = type_rules(rules) {
+                if field_desc.is_map() {
+                    if name == "map" {
+                        self.validate_map(path, &type_rules, &kind, &value);
+                    }
+                } else if field_desc.is_list() {
+                    if name == "repeated" {
+                        self.validate_list(path, &type_rules, &kind, &value);
+                    }
+                } else {
+                    self.validate_value(path, name, &type_rules, &kind, &value);
+                }
+            }
+        }
+
+        if !skip_nested {
+            self.validate_nested(path, &value);
+        }
+    }
+
+    fn validate_nested(&mut self, path: &str, value: &Value) {
+        match value {
+            Value::Message(message) => self.validate_message(path, message),
+            Value::List(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    if let Value::Message(message) = value {
+                        self.validate_message(&format!("{}[{}]", path, index), message);
+                    }
+                }
+            }
+            Value::Map(values) => {
+                for (key, value) in values {
+                    if let Value::Message(message) = value {
+                        let path = format!("{}[{}]", path, map_key_to_string(key));
+                        self.validate_message(&path, message);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Applies the constraints for an element of a list or map, such as the `items` rules of a repeated field.
+    fn validate_element(&mut self, path: &str, rules: &DynamicMessage, kind: &Kind, value: &Value) {
+        let populated = !is_default(value);
+        if is_required(rules) && !populated {
+            self.report(path, "required", "value is required");
+            return;
+        }
+        if is_ignored(rules, populated) {
+            return;
+        }
+        if let Some((name, type_rules)) = type_rules(rules) {
+            self.validate_value(path, name, &type_rules, kind, value);
+        }
+    }
+
+    fn validate_list(&mut self, path: &str, rules: &DynamicMessage, kind: &Kind, value: &Value) {
+        let values = match value.as_list() {
+            Some(values) => values,
+            None => return,
+        };
+
+        if let Some(min) = get(rules, "min_items").and_then(|v| v.as_u64()) {
+            if (values.len() as u64) < min {
+                self.report(
+                    path,
+                    "repeated.min_items",
+                    format!("value must contain at least {} item(s)", min),
+                );
+            }
+        }
+        if let Some(max) = get(rules, "max_items").and_then(|v| v.as_u64()) {
+            if (values.len() as u64) > max {
+                self.report(
+                    path,
+                    "repeated.max_items",
+                    format!("value must contain no more than {} item(s)", max),
+                );
+            }
+        }
+        if get_bool(rules, "unique", "")
+            && values
+                .iter()
+                .enumerate()
+                .any(|(i, value)| values[..i].contains(value))
+        {
+            self.report(
+                path,
+                "repeated.unique",
+                "repeated value must contain unique items",
+            );
+        }
+        if let Some(items) = get(rules, "items") {
+            if let Some(items) = items.as_message() {
+                for (index, value) in values.iter().enumerate() {
+                    self.validate_element(&format!("{}[{}]", path, index), items, kind, value);
+                }
+            }
+        }
+    }
+
+    fn validate_map(&mut self, path: &str, rules: &DynamicMessage, kind: &Kind, value: &Value) {
+        let values = match value.as_map() {
+            Some(values) => values,
+            None => return,
+        };
+        let entry_desc = match kind.as_message() {
+            Some(entry_desc) => entry_desc,
+            None => return,
+        };
+
+        if let Some(min) = get(rules, "min_pairs").and_then(|v| v.as_u64()) {
+            if (values.len() as u64) < min {
+                self.report(
+                    path,
+                    "map.min_pairs",
+                    format!("map must be at least {} entries", min),
+                );
+            }
+        }
+        if let Some(max) = get(rules, "max_pairs").and_then(|v| v.as_u64()) {
+            if (values.len() as u64) > max {
+                self.report(
+                    path,
+                    "map.max_pairs",
+                    format!("map must be at most {} entries", max),
+                );
+            }
+        }
+
+        let key_rules = get(rules, "keys");
+        let value_rules = get(rules, "values");
+        if key_rules.is_none() && value_rules.is_none() {
+            return;
+        }
+
+        let key_kind = entry_desc.map_entry_key_field().kind();
+        let value_kind = entry_desc.map_entry_value_field().kind();
+        let mut entries: Vec<_> = values.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        for (key, value) in entries {
+            let path = format!("{}[{}]", path, map_key_to_string(key));
+            if let Some(key_rules) = key_rules.as_ref().and_then(|r| r.as_message()) {
+                let key = Value::from(key.clone());
+                self.validate_element(&path, key_rules, &key_kind, &key);
+            }
+            if let Some(value_rules) = value_rules.as_ref().and_then(|r| r.as_message()) {
+                self.validate_element(&path, value_rules, &value_kind, value);
+            }
+        }
+    }
+
+    fn validate_value(
+        &mut self,
+        path: &str,
+        name: &str,
+        rules: &DynamicMessage,
+        kind: &Kind,
+        value: &Value,
+    ) {
+        match name {
+            "bool" => self.validate_const(path, name, rules, value),
+            "string" => self.validate_string(path, rules, value),
+            "bytes" => self.validate_bytes(path, rules, value),
+            "enum" => self.validate_enum(path, rules, kind, value),
+            "any" => self.validate_any(path, rules, value),
+            "duration" => self.validate_ordered(path, name, rules, value),
+            "timestamp" => self.validate_timestamp(path, rules, value),
+            "repeated" | "map" => (),
+            _ => {
+                self.validate_ordered(path, name, rules, value);
+                if get_bool(rules, "finite", "") {
+                    let finite = match value {
+                        Value::F32(value) => value.is_finite(),
+                        Value::F64(value) => value.is_finite(),
+                        _ => true,
+                    };
+                    if !finite {
+                        self.report(path, format!("{}.finite", name), "value must be finite");
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_const(&mut self, path: &str, name: &str, rules: &DynamicMessage, value: &Value) {
+        if let Some(expected) = get(rules, "const") {
+            if compare(value, &expected) != Some(Ordering::Equal) {
+                self.report(
+                    path,
+                    format!("{}.const", name),
+                    format!("value must equal {}", display(&expected)),
+                );
+            }
+        }
+    }
+
+    fn validate_in(&mut self, path: &str, name: &str, rules: &DynamicMessage, value: &Value) {
+        let contains = |list: &[Value]| {
+            list.iter()
+                .any(|item| compare(value, item) == Some(Ordering::Equal))
+        };
+
+        if let Some(list) = get(rules, "in") {
+            if let Some(list) = list.as_list() {
+                if !contains(list) {
+                    self.report(
+                        path,
+                        format!("{}.in", name),
+                        format!("value must be in list {}", display_list(list)),
+                    );
+                }
+            }
+        }
+        if let Some(list) = get(rules, "not_in") {
+            if let Some(list) = list.as_list() {
+                if contains(list) {
+                    self.report(
+                        path,
+                        format!("{}.not_in", name),
+                        format!("value must not be in list {}", display_list(list)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Checks the `const`, range, `in` and `not_in` rules shared by numeric and well-known types.
+    fn validate_ordered(&mut self, path: &str, name: &str, rules: &DynamicMessage, value: &Value) {
+        self.validate_const(path, name, rules, value);
+
+        let lower = get(rules, "gt")
+            .map(|bound| ("gt", bound))
+            .or_else(|| get(rules, "gte").map(|bound| ("gte", bound)));
+        let upper = get(rules, "lt")
+            .map(|bound| ("lt", bound))
+            .or_else(|| get(rules, "lte").map(|bound| ("lte", bound)));
+
+        let check_lower = |rule: &str, bound: &Value| match compare(value, bound) {
+            Some(Ordering::Greater) => true,
+            Some(Ordering::Equal) => rule == "gte",
+            _ => false,
+        };
+        let check_upper = |rule: &str, bound: &Value| match compare(value, bound) {
+            Some(Ordering::Less) => true,
+            Some(Ordering::Equal) => rule == "lte",
+            _ => false,
+        };
+        let describe = |rule: &str, bound: &Value| {
+            let relation = match rule {
+                "gt" => "greater than",
+                "gte" => "greater than or equal to",
+                "lt" => "less than",
+                _ => "less than or equal to",
+            };
+            format!("{} {}", relation, display(bound))
+        };
+
+        match (lower, upper) {
+            (Some((lower_rule, lower)), Some((upper_rule, upper))) => {
+                if compare(&lower, &upper) == Some(Ordering::Greater) {
+                    // An exclusive range, where the value must lie outside of (upper, lower).
+                    if !check_lower(lower_rule, &lower) && !check_upper(upper_rule, &upper) {
+                        self.report(
+                            path,
+                            format!("{}.{}_{}_exclusive", name, lower_rule, upper_rule),
+                            format!(
+                                "value must be {} or {}",
+                                describe(lower_rule, &lower),
+                                describe(upper_rule, &upper)
+                            ),
+                        );
+                    }
+                } else if !check_lower(lower_rule, &lower) || !check_upper(upper_rule, &upper) {
+                    self.report(
+                        path,
+                        format!("{}.{}_{}", name, lower_rule, upper_rule),
+                        format!(
+                            "value must be {} and {}",
+                            describe(lower_rule, &lower),
+                            describe(upper_rule, &upper)
+                        ),
+                    );
+                }
+            }
+            (Some((rule, bound)), None) => {
+                if !check_lower(rule, &bound) {
+                    self.report(
+                        path,
+                        format!("{}.{}", name, rule),
+                        format!("value must be {}", describe(rule, &bound)),
+                    );
+                }
+            }
+            (None, Some((rule, bound))) => {
+                if !check_upper(rule, &bound) {
+                    self.report(
+                        path,
+                        format!("{}.{}", name, rule),
+                        format!("value must be {}", describe(rule, &bound)),
+                    );
+                }
+            }
+            (None, None) => (),
+        }
+
+        self.validate_in(path, name, rules, value);
+    }
+
+    fn validate_string(&mut self, path: &str, rules: &DynamicMessage, value: &Value) {
+        let value_str = match value.as_str() {
+            Some(value) => value,
+            None => return,
+        };
+
+        self.validate_const(path, "string", rules, value);
+
+        let chars = value_str.chars().count() as u64;
+        let bytes = value_str.len() as u64;
+        if let Some(len) = get(rules, "len").and_then(|v| v.as_u64()) {
+            if chars != len {
+                self.report(
+                    path,
+                    "string.len",
+                    format!("value length must be {} characters", len),
+                );
+            }
+        }
+        if let Some(min) = get(rules, "min_len").and_then(|v| v.as_u64()) {
+            if chars < min {
+                self.report(
+                    path,
+                    "string.min_len",
+                    format!("value length must be at least {} characters", min),
+                );
+            }
+        }
+        if let Some(max) = get(rules, "max_len").and_then(|v| v.as_u64()) {
+            if chars > max {
+                self.report(
+                    path,
+                    "string.max_len",
+                    format!("value length must be at most {} characters", max),
+                );
+            }
+        }
+        if let Some(len) = get(rules, "len_bytes").and_then(|v| v.as_u64()) {
+            if bytes != len {
+                self.report(
+                    path,
+                    "string.len_bytes",
+                    format!("value length must be {} bytes", len),
+                );
+            }
+        }
+        if let Some(min) = get(rules, "min_bytes").and_then(|v| v.as_u64()) {
+            if bytes < min {
+                self.report(
+                    path,
+                    "string.min_bytes",
+                    format!("value length must be at least {} bytes", min),
+                );
+            }
+        }
+        if let Some(max) = get(rules, "max_bytes").and_then(|v| v.as_u64()) {
+            if bytes > max {
+                self.report(
+                    path,
+                    "string.max_bytes",
+                    format!("value length must be at most {} bytes", max),
+                );
+            }
+        }
+        if let Some(pattern) = get(rules, "pattern") {
+            if let Some(pattern) = pattern.as_str() {
+                match self.regex(pattern).map(|regex| regex.is_match(value_str)) {
+                    Some(true) => (),
+                    Some(false) => self.report(
+                        path,
+                        "string.pattern",
+                        format!("value does not match regex pattern `{}`", pattern),
+                    ),
+                    None => self.report(
+                        path,
+                        "string.pattern",
+                        format!("invalid regex pattern `{}`", pattern),
+                    ),
+                }
+            }
+        }
+        if let Some(prefix) = get(rules, "prefix") {
+            if let Some(prefix) = prefix.as_str() {
+                if !value_str.starts_with(prefix) {
+                    self.report(
+                        path,
+                        "string.prefix",
+                        format!("value does not have prefix `{}`", prefix),
+                    );
+                }
+            }
+        }
+        if let Some(suffix) = get(rules, "suffix") {
+            if let Some(suffix) = suffix.as_str() {
+                if !value_str.ends_with(suffix) {
+                    self.report(
+                        path,
+                        "string.suffix",
+                        format!("value does not have suffix `{}`", suffix),
+                    );
+                }
+            }
+        }
+        if let Some(substring) = get(rules, "contains") {
+            if let Some(substring) = substring.as_str() {
+                if !value_str.contains(substring) {
+                    self.report(
+                        path,
+                        "string.contains",
+                        format!("value does not contain substring `{}`", substring),
+                    );
+                }
+            }
+        }
+        if let Some(substring) = get(rules, "not_contains") {
+            if let Some(substring) = substring.as_str() {
+                if value_str.contains(substring) {
+                    self.report(
+                        path,
+                        "string.not_contains",
+                        format!("value contains substring `{}`", substring),
+                    );
+                }
+            }
+        }
+        self.validate_in(path, "string", rules, value);
+
+        type Check = fn(&str) -> bool;
+        let formats: [(&str, Check, &str); 7] = [
+            ("email", is_email, "value must be a valid email address"),
+            ("hostname", is_hostname, "value must be a valid hostname"),
+            (
+                "ip",
+                |s| s.parse::<IpAddr>().is_ok(),
+                "value must be a valid IP address",
+            ),
+            (
+                "ipv4",
+                |s| s.parse::<Ipv4Addr>().is_ok(),
+                "value must be a valid IPv4 address",
+            ),
+            (
+                "ipv6",
+                |s| s.parse::<Ipv6Addr>().is_ok(),
+                "value must be a valid IPv6 address",
+            ),
+            (
+                "address",
+                |s| is_hostname(s) || s.parse::<IpAddr>().is_ok(),
+                "value must be a valid hostname or IP address",
+            ),
+            ("uuid", is_uuid, "value must be a valid UUID"),
+        ];
+        for (format, check, message) in formats.iter() {
+            if get_bool(rules, format, "") && !check(value_str) {
+                self.report(path, format!("string.{}", format), *message);
+            }
+        }
+    }
+
+    fn validate_bytes(&mut self, path: &str, rules: &DynamicMessage, value: &Value) {
+        let value_bytes = match value.as_bytes() {
+            Some(value) => value,
+            None => return,
+        };
+
+        self.validate_const(path, "bytes", rules, value);
+
+        let len = value_bytes.len() as u64;
+        if let Some(expected) = get(rules, "len").and_then(|v| v.as_u64()) {
+            if len != expected {
+                self.report(
+                    path,
+                    "bytes.len",
+                    format!("value length must be {} bytes", expected),
+                );
+            }
+        }
+        if let Some(min) = get(rules, "min_len").and_then(|v| v.as_u64()) {
+            if len < min {
+                self.report(
+                    path,
+                    "bytes.min_len",
+                    format!("value length must be at least {} bytes", min),
+                );
+            }
+        }
+        if let Some(max) = get(rules, "max_len").and_then(|v| v.as_u64()) {
+            if len > max {
+                self.report(
+                    path,
+                    "bytes.max_len",
+                    format!("value length must be at most {} bytes", max),
+                );
+            }
+        }
+        if let Some(pattern) = get(rules, "pattern") {
+            if let Some(pattern) = pattern.as_str() {
+                let matches = std::str::from_utf8(value_bytes)
+                    .ok()
+                    .map(|value_str| self.regex(pattern).map(|regex| regex.is_match(value_str)));
+                match matches {
+                    Some(Some(true)) => (),
+                    Some(None) => self.report(
+                        path,
+                        "bytes.pattern",
+                        format!("invalid regex pattern `{}`", pattern),
+                    ),
+                    _ => self.report(
+                        path,
+                        "bytes.pattern",
+                        format!("value must match regex pattern `{}`", pattern),
+                    ),
+                }
+            }
+        }
+        if let Some(prefix) = get(rules, "prefix") {
+            if let Some(prefix) = prefix.as_bytes() {
+                if !value_bytes.starts_with(prefix) {
+                    self.report(
+                        path,
+                        "bytes.prefix",
+                        format!("value does not have prefix {:?}", prefix),
+                    );
+                }
+            }
+        }
+        if let Some(suffix) = get(rules, "suffix") {
+            if let Some(suffix) = suffix.as_bytes() {
+                if !value_bytes.ends_with(suffix) {
+                    self.report(
+                        path,
+                        "bytes.suffix",
+                        format!("value does not have suffix {:?}", suffix),
+                    );
+                }
+            }
+        }
+        if let Some(substring) = get(rules, "contains") {
+            if let Some(substring) = substring.as_bytes() {
+                if !substring.is_empty()
+                    && !value_bytes
+                        .windows(substring.len())
+                        .any(|window| window == substring.as_ref())
+                {
+                    self.report(
+                        path,
+                        "bytes.contains",
+                        format!("value does not contain {:?}", substring),
+                    );
+                }
+            }
+        }
+        self.validate_in(path, "bytes", rules, value);
+
+        if get_bool(rules, "ip", "") && len != 4 && len != 16 {
+            self.report(path, "bytes.ip", "value must be a valid IP address");
+        }
+        if get_bool(rules, "ipv4", "") && len != 4 {
+            self.report(path, "bytes.ipv4", "value must be a valid IPv4 address");
+        }
+        if get_bool(rules, "ipv6", "") && len != 16 {
+            self.report(path, "bytes.ipv6", "value must be a valid IPv6 address");
+        }
+    }
+
+    fn validate_enum(&mut self, path: &str, rules: &DynamicMessage, kind: &Kind, value: &Value) {
+        self.validate_const(path, "enum", rules, value);
+        if get_bool(rules, "defined_only", "") {
+            if let (Some(enum_desc), Some(number)) = (kind.as_enum(), value.as_enum_number()) {
+                if enum_desc.get_value(number).is_none() {
+                    self.report(
+                        path,
+                        "enum.defined_only",
+                        "value must be one of the defined enum values",
+                    );
+                }
+            }
+        }
+        self.validate_in(path, "enum", rules, value);
+    }
+
+    fn validate_any(&mut self, path: &str, rules: &DynamicMessage, value: &Value) {
+        let type_url = match value
+            .as_message()
+            .and_then(|message| message.get_field_by_name("type_url"))
+        {
+            Some(type_url) => type_url.into_owned(),
+            None => return,
+        };
+        self.validate_in(path, "any", rules, &type_url);
+    }
+
+    fn validate_timestamp(&mut self, path: &str, rules: &DynamicMessage, value: &Value) {
+        self.validate_ordered(path, "timestamp", rules, value);
+
+        let timestamp = match value.as_message().and_then(seconds_and_nanos) {
+            Some(timestamp) => timestamp,
+            None => return,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| (now.as_secs() as i64, now.subsec_nanos() as i32))
+            .unwrap_or((0, 0));
+
+        if get_bool(rules, "lt_now", "") && timestamp >= now {
+            self.report(path, "timestamp.lt_now", "value must be less than now");
+        }
+        if get_bool(rules, "gt_now", "") && timestamp <= now {
+            self.report(path, "timestamp.gt_now", "value must be greater than now");
+        }
+        if let Some(within) = get(rules, "within") {
+            if let Some(within) = within.as_message().and_then(seconds_and_nanos) {
+                let diff = to_nanos(timestamp) - to_nanos(now);
+                if diff.abs() > to_nanos(within) {
+                    self.report(
+                        path,
+                        "timestamp.within",
+                        format!("value must be within {} of now", display_seconds(within)),
+                    );
+                }
+            }
+        }
+    }
+
+    fn is_message_disabled(&self, desc: &MessageDescriptor) -> bool {
+        if self.message_extensions.is_empty() {
+            return false;
+        }
+        let options = match desc.options() {
+            Some(options) => options,
+            None => return false,
+        };
+
+        self.message_extensions.iter().any(|ext| {
+            if !options.has_extension(ext) {
+                return false;
+            }
+            match options.get_extension(ext).as_ref() {
+                Value::Bool(value) => *value,
+                Value::Message(constraints) => get_bool(constraints, "disabled", ""),
+                _ => false,
+            }
+        })
+    }
+
+    fn is_oneof_required(&self, oneof: &OneofDescriptor) -> bool {
+        if self.oneof_extensions.is_empty() {
+            return false;
+        }
+        let options = match oneof.options() {
+            Some(options) => options,
+            None => return false,
+        };
+
+        self.oneof_extensions.iter().any(|ext| {
+            if !options.has_extension(ext) {
+                return false;
+            }
+            match options.get_extension(ext).as_ref() {
+                Value::Bool(value) => *value,
+                Value::Message(constraints) => get_bool(constraints, "required", ""),
+                _ => false,
+            }
+        })
+    }
+
+    fn field_rules(&mut self, field_desc: &FieldDescriptor) -> Option<Arc<DynamicMessage>> {
+        if self.field_extensions.is_empty() {
+            return None;
+        }
+        if let Some(rules) = self.field_rules.get(field_desc.full_name()) {
+            return rules.clone();
+        }
+
+        let rules = field_desc.options().and_then(|options| {
+            self.field_extensions
+                .iter()
+                .find(|ext| options.has_extension(ext))
+                .and_then(|ext| options.get_extension(ext).as_message().cloned())
+                .map(Arc::new)
+        });
+        self.field_rules
+            .insert(field_desc.full_name().to_owned(), rules.clone());
+        rules
+    }
+
+    fn regex(&mut self, pattern: &str) -> Option<&Regex> {
+        self.regexes
+            .entry(pattern.to_owned())
+            .or_insert_with(|| Regex::new(pattern).ok())
+            .as_ref()
+    }
+}
+
+/// Gets the value of a field of a rules message, if it is set.
+fn get<'a>(rules: &'a DynamicMessage, name: &str) -> Option<Cow<'a, Value>> {
+    if rules.has_field_by_name(name) {
+        rules.get_field_by_name(name)
+    } else {
+        None
+    }
+}
+
+/// Gets a boolean rule, optionally nested in a message-typed field of the rules.
+fn get_bool(rules: &DynamicMessage, name: &str, nested: &str) -> bool {
+    let value = match get(rules, name) {
+        Some(value) => value,
+        None => return false,
+    };
+    if nested.is_empty() {
+        value.as_bool().unwrap_or(false)
+    } else {
+        value
+            .as_message()
+            .map(|message| get_bool(message, nested, ""))
+            .unwrap_or(false)
+    }
+}
+
+/// Gets the set field of the `type` oneof in a `buf.validate.FieldConstraints` or `validate.FieldRules` message.
+fn type_rules(rules: &DynamicMessage) -> Option<(&'static str, DynamicMessage)> {
+    TYPE_RULES.iter().find_map(|&name| {
+        get(rules, name)
+            .and_then(|value| value.as_message().cloned())
+            .map(|message| (name, message))
+    })
+}
+
+fn is_required(rules: &DynamicMessage) -> bool {
+    get_bool(rules, "required", "") || get_bool(rules, "message", "required")
+}
+
+fn is_ignored(rules: &DynamicMessage, populated: bool) -> bool {
+    if get_bool(rules, "skipped", "") {
+        return true;
+    }
+
+    let ignore = get(rules, "ignore")
+        .and_then(|v| v.as_enum_number())
+        .unwrap_or(0);
+    if ignore == IGNORE_ALWAYS {
+        return true;
+    }
+
+    if !populated {
+        if ignore == IGNORE_IF_UNPOPULATED || ignore == IGNORE_IF_DEFAULT_VALUE {
+            return true;
+        }
+        if get_bool(rules, "ignore_empty", "") {
+            return true;
+        }
+        if let Some((_, type_rules)) = type_rules(rules) {
+            if get_bool(&type_rules, "ignore_empty", "") {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+fn is_default(value: &Value) -> bool {
+    match value {
+        Value::Bool(value) => !*value,
+        Value::I32(value) | Value::EnumNumber(value) => *value == 0,
+        Value::I64(value) => *value == 0,
+        Value::U32(value) => *value == 0,
+        Value::U64(value) => *value == 0,
+        Value::F32(value) => *value == 0.0,
+        Value::F64(value) => *value == 0.0,
+        Value::String(value) => value.is_empty(),
+        Value::Bytes(value) => value.is_empty(),
+        Value::Message(_) => false,
+        Value::List(value) => value.is_empty(),
+        Value::Map(value) => value.is_empty(),
+    }
+}
+
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+        (Value::I32(l), Value::I32(r)) => Some(l.cmp(r)),
+        (Value::I64(l), Value::I64(r)) => Some(l.cmp(r)),
+        (Value::U32(l), Value::U32(r)) => Some(l.cmp(r)),
+        (Value::U64(l), Value::U64(r)) => Some(l.cmp(r)),
+        (Value::F32(l), Value::F32(r)) => l.partial_cmp(r),
+        (Value::F64(l), Value::F64(r)) => l.partial_cmp(r),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        (Value::Bytes(l), Value::Bytes(r)) => Some(l.cmp(r)),
+        (Value::EnumNumber(l), Value::EnumNumber(r)) | (Value::EnumNumber(l), Value::I32(r)) => {
+            Some(l.cmp(r))
+        }
+        (Value::Message(l), Value::Message(r)) => {
+            Some(seconds_and_nanos(l)?.cmp(&seconds_and_nanos(r)?))
+        }
+        _ => None,
+    }
+}
+
+/// Gets the fields of a `google.protobuf.Duration` or `google.protobuf.Timestamp` message.
+fn seconds_and_nanos(message: &DynamicMessage) -> Option<(i64, i32)> {
+    let seconds = message.get_field_by_name("seconds")?.as_i64()?;
+    let nanos = message.get_field_by_name("nanos")?.as_i32()?;
+    Some((seconds, nanos))
+}
+
+fn to_nanos((seconds, nanos): (i64, i32)) -> i128 {
+    i128::from(seconds) * 1_000_000_000 + i128::from(nanos)
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Bool(value) => value.to_string(),
+        Value::I32(value) | Value::EnumNumber(value) => value.to_string(),
+        Value::I64(value) => value.to_string(),
+        Value::U32(value) => value.to_string(),
+        Value::U64(value) => value.to_string(),
+        Value::F32(value) => value.to_string(),
+        Value::F64(value) => value.to_string(),
+        Value::String(value) => format!("{:?}", value),
+        Value::Bytes(value) => format!("{:?}", value),
+        Value::Message(message) => match seconds_and_nanos(message) {
+            Some(value) => display_seconds(value),
+            None => format!("{:?}", message),
+        },
+        Value::List(values) => display_list(values),
+        Value::Map(values) => format!("{:?}", values),
+    }
+}
+
+fn display_list(values: &[Value]) -> String {
+    let values: Vec<_> = values.iter().map(display).collect();
+    format!("[{}]", values.join(", "))
+}
+
+fn display_seconds((seconds, nanos): (i64, i32)) -> String {
+    if nanos == 0 {
+        format!("{}s", seconds)
+    } else {
+        format!("{}.{:09}s", seconds, nanos.abs())
+    }
+}
+
+fn is_email(value: &str) -> bool {
+    match value.rsplit_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && local.len() <= 64
+                && !local
+                    .chars()
+                    .any(|c| c.is_whitespace() || c == '<' || c == '>')
+                && is_hostname(domain)
+        }
+        None => false,
+    }
+}
+
+fn is_hostname(value: &str) -> bool {
+    let value = value.strip_suffix('.').unwrap_or(value);
+    if value.is_empty() || value.len() > 253 {
+        return false;
+    }
+
+    value.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
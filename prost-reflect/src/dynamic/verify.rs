@@ -0,0 +1,452 @@
+use std::{error::Error, fmt, str};
+
+use prost::encoding::WireType;
+
+use crate::{DecodeOptions, DynamicMessage, Kind, MessageDescriptor};
+
+use super::fields::FieldDescriptorLike;
+
+const RECURSION_LIMIT: u32 = 100;
+const MAX_FIELD_NUMBER: u64 = 536_870_911;
+
+/// An error returned by [`DynamicMessage::verify`], describing the first problem found in the encoded bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyError {
+    offset: usize,
+    kind: VerifyErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum VerifyErrorKind {
+    TruncatedVarint,
+    VarintOverflow,
+    InvalidFieldNumber {
+        number: u64,
+    },
+    InvalidWireType {
+        value: u64,
+    },
+    UnexpectedWireType {
+        field: String,
+        expected: WireType,
+        actual: WireType,
+    },
+    LengthOverrun {
+        len: u64,
+        remaining: usize,
+    },
+    Truncated {
+        expected: usize,
+        remaining: usize,
+    },
+    UnexpectedEndGroup {
+        number: u32,
+    },
+    UnterminatedGroup {
+        number: u32,
+    },
+    InvalidUtf8 {
+        field: String,
+    },
+    InvalidPackedLength {
+        field: String,
+        len: usize,
+        element_size: usize,
+    },
+    RecursionLimitReached,
+}
+
+impl DynamicMessage {
+    /// Checks that `buf` is a well-formed encoding of the message type described by `desc`, without
+    /// decoding it into a [`DynamicMessage`].
+    ///
+    /// This is cheaper than [`decode`][DynamicMessage::decode] since no values are allocated, and
+    /// on failure the returned [`VerifyError`] reports the byte offset of the problem. The following are
+    /// checked, recursively for nested messages and groups:
+    ///
+    /// - varints are not truncated and fit in 64 bits.
+    /// - field numbers and wire types are valid, and the wire type of each known field matches its type.
+    /// - length-delimited fields and fixed-width values do not extend past the end of their enclosing message.
+    /// - groups are correctly terminated.
+    /// - `string` fields contain valid UTF-8, if [`decode`][DynamicMessage::decode] requires it. By
+    ///   default, this applies to fields defined in `proto3` files but not `proto2` files.
+    /// - packed fields contain a whole number of elements.
+    ///
+    /// Unknown fields are only checked for well-formedness. Semantic constraints, such as required fields
+    /// being present, are not checked.
+    pub fn verify(desc: &MessageDescriptor, buf: &[u8]) -> Result<(), VerifyError> {
+        DynamicMessage::verify_with_options(desc, buf, &DecodeOptions::default())
+    }
+
+    /// Checks that `buf` is a well-formed encoding of the message type described by `desc`, as for
+    /// [`verify`][DynamicMessage::verify], accepting invalid UTF-8 in `string` fields if it would be
+    /// accepted by [`decode_with_options`][DynamicMessage::decode_with_options] with the same options.
+    pub fn verify_with_options(
+        desc: &MessageDescriptor,
+        buf: &[u8],
+        options: &DecodeOptions,
+    ) -> Result<(), VerifyError> {
+        let mut verifier = Verifier {
+            buf,
+            pos: 0,
+            options,
+        };
+        verifier.message(desc, buf.len(), None, RECURSION_LIMIT)
+    }
+}
+
+impl VerifyError {
+    /// The offset, in bytes from the start of the buffer, at which the error was found.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            VerifyErrorKind::TruncatedVarint => write!(f, "truncated varint"),
+            VerifyErrorKind::VarintOverflow => write!(f, "varint exceeds 64 bits"),
+            VerifyErrorKind::InvalidFieldNumber { number } => {
+                write!(f, "invalid field number {}", number)
+            }
+            VerifyErrorKind::InvalidWireType { value } => {
+                write!(f, "invalid wire type value {}", value)
+            }
+            VerifyErrorKind::UnexpectedWireType {
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "invalid wire type for field '{}': expected {:?}, found {:?}",
+                field, expected, actual
+            ),
+            VerifyErrorKind::LengthOverrun { len, remaining } => write!(
+                f,
+                "length {} exceeds the {} bytes remaining in the enclosing message",
+                len, remaining
+            ),
+            VerifyErrorKind::Truncated {
+                expected,
+                remaining,
+            } => write!(
+                f,
+                "expected {} bytes but only {} remain in the enclosing message",
+                expected, remaining
+            ),
+            VerifyErrorKind::UnexpectedEndGroup { number } => {
+                write!(f, "unexpected end group tag for field number {}", number)
+            }
+            VerifyErrorKind::UnterminatedGroup { number } => {
+                write!(f, "missing end group tag for field number {}", number)
+            }
+            VerifyErrorKind::InvalidUtf8 { field } => {
+                write!(f, "invalid UTF-8 in string field '{}'", field)
+            }
+            VerifyErrorKind::InvalidPackedLength {
+                field,
+                len,
+                element_size,
+            } => write!(
+                f,
+                "packed field '{}' has length {} which is not a multiple of {}",
+                field, len, element_size
+            ),
+            VerifyErrorKind::RecursionLimitReached => write!(f, "recursion limit reached"),
+        }?;
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+impl Error for VerifyError {}
+
+struct Verifier<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    options: &'a DecodeOptions,
+}
+
+impl<'a> Verifier<'a> {
+    /// Verifies fields up to `end`, or until an end group tag for `group` if set.
+    fn message(
+        &mut self,
+        desc: &MessageDescriptor,
+        end: usize,
+        group: Option<u32>,
+        limit: u32,
+    ) -> Result<(), VerifyError> {
+        if limit == 0 {
+            return Err(self.error(self.pos, VerifyErrorKind::RecursionLimitReached));
+        }
+
+        while self.pos < end {
+            let start = self.pos;
+            let (number, wire_type) = self.key(end)?;
+
+            if wire_type == WireType::EndGroup {
+                return match group {
+                    Some(group) if group == number => Ok(()),
+                    _ => Err(self.error(start, VerifyErrorKind::UnexpectedEndGroup { number })),
+                };
+            }
+
+            match desc.get_field(number) {
+                Some(field) => {
+                    self.field(&field, field.full_name(), wire_type, start, end, limit)?
+                }
+                None => match desc.get_extension(number) {
+                    Some(extension) => self.field(
+                        &extension,
+                        extension.full_name(),
+                        wire_type,
+                        start,
+                        end,
+                        limit,
+                    )?,
+                    None => self.skip(number, wire_type, end, limit)?,
+                },
+            }
+        }
+
+        match group {
+            Some(number) => {
+                Err(self.error(self.pos, VerifyErrorKind::UnterminatedGroup { number }))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn field(
+        &mut self,
+        field: &impl FieldDescriptorLike,
+        name: &str,
+        wire_type: WireType,
+        start: usize,
+        end: usize,
+        limit: u32,
+    ) -> Result<(), VerifyError> {
+        let kind = field.kind();
+        let expected = expected_wire_type(&kind, field.is_group());
+
+        if field.is_list() && field.is_packable() && wire_type == WireType::LengthDelimited {
+            return self.packed(name, &kind, end);
+        }
+
+        if wire_type != expected {
+            return Err(self.error(
+                start,
+                VerifyErrorKind::UnexpectedWireType {
+                    field: name.to_owned(),
+                    expected,
+                    actual: wire_type,
+                },
+            ));
+        }
+
+        match &kind {
+            Kind::Message(message_desc) if field.is_group() => {
+                self.message(message_desc, end, Some(field.number()), limit - 1)
+            }
+            Kind::Message(message_desc) => {
+                let len = self.len(end)?;
+                let message_end = self.pos + len;
+                self.message(message_desc, message_end, None, limit - 1)
+            }
+            Kind::String => {
+                let len = self.len(end)?;
+                let value_start = self.pos;
+                self.pos += len;
+                match str::from_utf8(&self.buf[value_start..self.pos]) {
+                    Ok(_) => Ok(()),
+                    Err(_) if !self.options.reject_invalid_utf8(field) => Ok(()),
+                    Err(err) => Err(self.error(
+                        value_start + err.valid_up_to(),
+                        VerifyErrorKind::InvalidUtf8 {
+                            field: name.to_owned(),
+                        },
+                    )),
+                }
+            }
+            _ => self.value(wire_type, end),
+        }
+    }
+
+    fn packed(&mut self, name: &str, kind: &Kind, end: usize) -> Result<(), VerifyError> {
+        let len = self.len(end)?;
+        let packed_start = self.pos;
+        let packed_end = self.pos + len;
+
+        match expected_wire_type(kind, false) {
+            WireType::Varint => {
+                while self.pos < packed_end {
+                    self.varint(packed_end)?;
+                }
+            }
+            wire_type => {
+                let element_size = if wire_type == WireType::SixtyFourBit {
+                    8
+                } else {
+                    4
+                };
+                if len % element_size != 0 {
+                    return Err(self.error(
+                        packed_start,
+                        VerifyErrorKind::InvalidPackedLength {
+                            field: name.to_owned(),
+                            len,
+                            element_size,
+                        },
+                    ));
+                }
+                self.pos = packed_end;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn skip(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        end: usize,
+        limit: u32,
+    ) -> Result<(), VerifyError> {
+        match wire_type {
+            WireType::StartGroup => {
+                if limit == 0 {
+                    return Err(self.error(self.pos, VerifyErrorKind::RecursionLimitReached));
+                }
+                while self.pos < end {
+                    let start = self.pos;
+                    let (inner_number, inner_wire_type) = self.key(end)?;
+                    if inner_wire_type == WireType::EndGroup {
+                        return if inner_number == number {
+                            Ok(())
+                        } else {
+                            Err(self.error(
+                                start,
+                                VerifyErrorKind::UnexpectedEndGroup {
+                                    number: inner_number,
+                                },
+                            ))
+                        };
+                    }
+                    self.skip(inner_number, inner_wire_type, end, limit - 1)?;
+                }
+                Err(self.error(self.pos, VerifyErrorKind::UnterminatedGroup { number }))
+            }
+            wire_type => self.value(wire_type, end),
+        }
+    }
+
+    fn value(&mut self, wire_type: WireType, end: usize) -> Result<(), VerifyError> {
+        match wire_type {
+            WireType::Varint => self.varint(end).map(drop),
+            WireType::SixtyFourBit => self.fixed(8, end),
+            WireType::ThirtyTwoBit => self.fixed(4, end),
+            WireType::LengthDelimited => {
+                let len = self.len(end)?;
+                self.pos += len;
+                Ok(())
+            }
+            WireType::StartGroup | WireType::EndGroup => unreachable!(),
+        }
+    }
+
+    fn key(&mut self, end: usize) -> Result<(u32, WireType), VerifyError> {
+        let start = self.pos;
+        let key = self.varint(end)?;
+
+        let wire_type = match key & 0x7 {
+            0 => WireType::Varint,
+            1 => WireType::SixtyFourBit,
+            2 => WireType::LengthDelimited,
+            3 => WireType::StartGroup,
+            4 => WireType::EndGroup,
+            5 => WireType::ThirtyTwoBit,
+            value => return Err(self.error(start, VerifyErrorKind::InvalidWireType { value })),
+        };
+
+        let number = key >> 3;
+        if number == 0 || number > MAX_FIELD_NUMBER {
+            return Err(self.error(start, VerifyErrorKind::InvalidFieldNumber { number }));
+        }
+
+        Ok((number as u32, wire_type))
+    }
+
+    /// Reads a length prefix, checking that it does not extend past `end`.
+    fn len(&mut self, end: usize) -> Result<usize, VerifyError> {
+        let start = self.pos;
+        let len = self.varint(end)?;
+        let remaining = end - self.pos;
+        if len > remaining as u64 {
+            return Err(self.error(start, VerifyErrorKind::LengthOverrun { len, remaining }));
+        }
+        Ok(len as usize)
+    }
+
+    fn fixed(&mut self, size: usize, end: usize) -> Result<(), VerifyError> {
+        let remaining = end - self.pos;
+        if remaining < size {
+            return Err(self.error(
+                self.pos,
+                VerifyErrorKind::Truncated {
+                    expected: size,
+                    remaining,
+                },
+            ));
+        }
+        self.pos += size;
+        Ok(())
+    }
+
+    fn varint(&mut self, end: usize) -> Result<u64, VerifyError> {
+        let start = self.pos;
+        let mut value = 0u64;
+        for index in 0..10 {
+            if self.pos >= end {
+                return Err(self.error(start, VerifyErrorKind::TruncatedVarint));
+            }
+
+            let byte = self.buf[self.pos];
+            self.pos += 1;
+
+            // The tenth byte may only contribute the single remaining bit.
+            if index == 9 && byte > 1 {
+                return Err(self.error(start, VerifyErrorKind::VarintOverflow));
+            }
+
+            value |= u64::from(byte & 0x7f) << (index * 7);
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+
+        Err(self.error(start, VerifyErrorKind::VarintOverflow))
+    }
+
+    fn error(&self, offset: usize, kind: VerifyErrorKind) -> VerifyError {
+        VerifyError { offset, kind }
+    }
+}
+
+fn expected_wire_type(kind: &Kind, is_group: bool) -> WireType {
+    match kind {
+        Kind::Double | Kind::Fixed64 | Kind::Sfixed64 => WireType::SixtyFourBit,
+        Kind::Float | Kind::Fixed32 | Kind::Sfixed32 => WireType::ThirtyTwoBit,
+        Kind::Int32
+        | Kind::Int64
+        | Kind::Uint32
+        | Kind::Uint64
+        | Kind::Sint32
+        | Kind::Sint64
+        | Kind::Bool
+        | Kind::Enum(_) => WireType::Varint,
+        Kind::String | Kind::Bytes => WireType::LengthDelimited,
+        Kind::Message(_) if is_group => WireType::StartGroup,
+        Kind::Message(_) => WireType::LengthDelimited,
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, Kind, MapKey, MessageDescriptor,
+    UnknownField, Value,
+};
+
+use super::{
+    fields::ValueAndDescriptor,
+    snapshot::{join_path, map_key_to_string},
+};
+
+/// A visitor for the values in a [`DynamicMessage`], used with [`DynamicMessage::visit`].
+///
+/// Each method is called with the path of the value from the root message, using the same format
+/// as [`MessageDiff`][crate::MessageDiff], for example `items[0].id` or `labels[key]`. Extension
+/// fields are named by their full name in brackets, for example `[my.package.extension]`. The path
+/// of the root message is an empty string.
+///
+/// All methods have empty default implementations, so implementations only need to override the
+/// methods for the values they are interested in. Only fields which are set are visited.
+///
+/// # Examples
+///
+/// ```
+/// # use prost::Message;
+/// # use prost_reflect::{DynamicMessage, DescriptorPool, FieldDescriptor, Value, Visit};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// struct Paths(Vec<String>);
+///
+/// impl Visit for Paths {
+///     fn visit_field(&mut self, path: &str, _: &FieldDescriptor, _: &Value) {
+///         self.0.push(path.to_owned());
+///     }
+/// }
+///
+/// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+/// let mut paths = Paths(Vec::new());
+/// dynamic_message.visit(&mut paths);
+/// assert_eq!(paths.0, ["foo"]);
+/// ```
+pub trait Visit {
+    /// Called for each message, including the root message, before any of its fields are visited.
+    fn visit_message(&mut self, path: &str, message: &DynamicMessage) {
+        let _ = (path, message);
+    }
+
+    /// Called for each field which is set. For repeated and map fields, `value` is the whole list or
+    /// map, and each element is then visited with [`visit_list_element`][Self::visit_list_element]
+    /// or [`visit_map_entry`][Self::visit_map_entry].
+    fn visit_field(&mut self, path: &str, field_desc: &FieldDescriptor, value: &Value) {
+        let _ = (path, field_desc, value);
+    }
+
+    /// Called for each extension field which is set.
+    ///
+    /// See [`visit_field`][Self::visit_field] for more details.
+    fn visit_extension(&mut self, path: &str, extension_desc: &ExtensionDescriptor, value: &Value) {
+        let _ = (path, extension_desc, value);
+    }
+
+    /// Called for each element of a repeated field, where `kind` is the type of the elements.
+    fn visit_list_element(&mut self, path: &str, kind: &Kind, index: usize, value: &Value) {
+        let _ = (path, kind, index, value);
+    }
+
+    /// Called for each entry of a map field, where `entry_desc` is the map entry message type.
+    fn visit_map_entry(
+        &mut self,
+        path: &str,
+        entry_desc: &MessageDescriptor,
+        key: &MapKey,
+        value: &Value,
+    ) {
+        let _ = (path, entry_desc, key, value);
+    }
+
+    /// Called for each unknown field. The path ends with the field number.
+    fn visit_unknown_field(&mut self, path: &str, field: &UnknownField) {
+        let _ = (path, field);
+    }
+}
+
+impl DynamicMessage {
+    /// Walks this message and all nested messages, calling the methods of `visitor` for each value.
+    ///
+    /// Fields are visited in order of field number, depth first. Map entries are visited in order
+    /// of their keys, so the traversal order is deterministic.
+    ///
+    /// See [`Visit`] for more details.
+    pub fn visit<V>(&self, visitor: &mut V)
+    where
+        V: Visit + ?Sized,
+    {
+        visit_message(visitor, "", self);
+    }
+}
+
+fn visit_message<V>(visitor: &mut V, path: &str, message: &DynamicMessage)
+where
+    V: Visit + ?Sized,
+{
+    visitor.visit_message(path, message);
+
+    for field in message.fields.iter(&message.desc) {
+        match field {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                let path = join_path(path, field_desc.name());
+                visitor.visit_field(&path, &field_desc, value);
+                visit_field_value(visitor, &path, &field_desc.kind(), value);
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                let path = join_path(path, &format!("[{}]", extension_desc.full_name()));
+                visitor.visit_extension(&path, &extension_desc, value);
+                visit_field_value(visitor, &path, &extension_desc.kind(), value);
+            }
+            ValueAndDescriptor::Unknown(number, unknowns) => {
+                let path = join_path(path, &number.to_string());
+                for unknown in unknowns {
+                    visitor.visit_unknown_field(&path, &UnknownField::from_value(number, unknown));
+                }
+            }
+        }
+    }
+}
+
+fn visit_field_value<V>(visitor: &mut V, path: &str, kind: &Kind, value: &Value)
+where
+    V: Visit + ?Sized,
+{
+    match value {
+        Value::List(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let path = format!("{}[{}]", path, index);
+                visitor.visit_list_element(&path, kind, index, value);
+                visit_value(visitor, &path, value);
+            }
+        }
+        Value::Map(values) => {
+            let entry_desc = kind.as_message().expect("map field must be a message");
+
+            let mut entries: Vec<(&MapKey, &Value)> = values.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            for (key, value) in entries {
+                let path = format!("{}[{}]", path, map_key_to_string(key));
+                visitor.visit_map_entry(&path, entry_desc, key, value);
+                visit_value(visitor, &path, value);
+            }
+        }
+        value => visit_value(visitor, path, value),
+    }
+}
+
+fn visit_value<V>(visitor: &mut V, path: &str, value: &Value)
+where
+    V: Visit + ?Sized,
+{
+    if let Value::Message(message) = value {
+        visit_message(visitor, path, message);
+    }
+}
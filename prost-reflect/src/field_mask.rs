@@ -0,0 +1,282 @@
+//! Utilities for working with [`FieldMask`]s and dynamic messages.
+//!
+//! A `google.protobuf.FieldMask` is a set of paths, each of which is a dot-separated list of field names
+//! selecting a field of a message, or a field of a nested message. Field masks are commonly used by APIs
+//! to select which fields are returned by a read request, or which fields are modified by an update request.
+//!
+//! The functions in this module treat a mask as the set of fields it covers, so a path also covers all
+//! fields nested below it. For example, the mask `["a", "a.b"]` is equivalent to `["a"]`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use prost_reflect::{DescriptorPool, DynamicMessage, Value};
+//! # let pool = DescriptorPool::decode(include_bytes!("file_descriptor_set.bin").as_ref()).unwrap();
+//! # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+//! use prost_reflect::field_mask;
+//! use prost_types::FieldMask;
+//!
+//! let mask = FieldMask { paths: vec!["foo".to_owned()] };
+//! field_mask::validate(&message_descriptor, &mask).unwrap();
+//!
+//! let mut message = DynamicMessage::new(message_descriptor);
+//! message.set_field_by_name("foo", Value::I32(150));
+//! field_mask::prune(&mut message, &mask);
+//! assert!(!message.has_field_by_name("foo"));
+//! ```
+
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use prost_types::FieldMask;
+
+use crate::{DynamicMessage, MessageDescriptor, ReflectMessage, Value};
+
+/// An error returned by [`validate`] if a path of a field mask does not refer to a field.
+#[derive(Debug)]
+pub struct FieldMaskError {
+    path: String,
+    kind: FieldMaskErrorKind,
+}
+
+#[derive(Debug)]
+enum FieldMaskErrorKind {
+    EmptyPath,
+    FieldNotFound { message: String, field: String },
+    NotASingularMessage { field: String },
+}
+
+/// A set of fields, stored as a tree of field names. A node with no children covers the whole field.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Tree {
+    children: BTreeMap<String, Tree>,
+}
+
+/// Checks that every path of `mask` refers to a field of `desc`.
+///
+/// Every component of a path except the last must be a singular message field, since paths may not
+/// select fields inside repeated fields or maps.
+pub fn validate(desc: &MessageDescriptor, mask: &FieldMask) -> Result<(), FieldMaskError> {
+    for path in &mask.paths {
+        let error = |kind| FieldMaskError {
+            path: path.clone(),
+            kind,
+        };
+
+        if path.is_empty() {
+            return Err(error(FieldMaskErrorKind::EmptyPath));
+        }
+
+        let mut desc = desc.clone();
+        let mut parts = path.split('.').peekable();
+        while let Some(part) = parts.next() {
+            let field = match desc.get_field_by_name(part) {
+                Some(field) => field,
+                None => {
+                    return Err(error(FieldMaskErrorKind::FieldNotFound {
+                        message: desc.full_name().to_owned(),
+                        field: part.to_owned(),
+                    }))
+                }
+            };
+
+            if parts.peek().is_some() {
+                desc = match field.kind().as_message() {
+                    Some(message) if !field.is_list() && !field.is_map() => message.clone(),
+                    _ => {
+                        return Err(error(FieldMaskErrorKind::NotASingularMessage {
+                            field: field.full_name().to_owned(),
+                        }))
+                    }
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Clears all fields of `message` which are not covered by `mask`, so that only the selected fields
+/// remain.
+///
+/// Paths which do not refer to a field of the message are ignored. Extension fields and unknown fields
+/// are not affected.
+pub fn project(message: &mut DynamicMessage, mask: &FieldMask) {
+    project_tree(message, &Tree::from_mask(mask));
+}
+
+/// Clears all fields of `message` which are covered by `mask`.
+///
+/// Paths which do not refer to a field of the message are ignored. Extension fields and unknown fields
+/// are not affected.
+pub fn prune(message: &mut DynamicMessage, mask: &FieldMask) {
+    prune_tree(message, &Tree::from_mask(mask));
+}
+
+/// Returns a mask covering all fields covered by either `a` or `b`.
+///
+/// The paths of the result are sorted, and paths covered by another path are removed.
+pub fn merge(a: &FieldMask, b: &FieldMask) -> FieldMask {
+    let mut tree = Tree::from_mask(a);
+    for path in &b.paths {
+        tree.insert(path);
+    }
+    tree.to_mask()
+}
+
+/// Returns a mask covering only the fields covered by both `a` and `b`.
+///
+/// The paths of the result are sorted, and paths covered by another path are removed.
+pub fn intersect(a: &FieldMask, b: &FieldMask) -> FieldMask {
+    match Tree::from_mask(a).intersect(&Tree::from_mask(b)) {
+        Some(tree) => tree.to_mask(),
+        None => FieldMask::default(),
+    }
+}
+
+/// Returns an equivalent mask with sorted paths, and with any paths covered by another path removed.
+pub fn normalize(mask: &FieldMask) -> FieldMask {
+    Tree::from_mask(mask).to_mask()
+}
+
+fn project_tree(message: &mut DynamicMessage, tree: &Tree) {
+    for field in message.descriptor().fields() {
+        if !message.has_field(&field) {
+            continue;
+        }
+        match tree.children.get(field.name()) {
+            None => message.clear_field(&field),
+            Some(child) if child.is_leaf() => (),
+            Some(child) => {
+                if let Value::Message(nested) = message.get_field_mut(&field) {
+                    project_tree(nested, child);
+                }
+            }
+        }
+    }
+}
+
+fn prune_tree(message: &mut DynamicMessage, tree: &Tree) {
+    for (name, child) in &tree.children {
+        let field = match message.descriptor().get_field_by_name(name) {
+            Some(field) => field,
+            None => continue,
+        };
+        if child.is_leaf() {
+            message.clear_field(&field);
+        } else if message.has_field(&field) {
+            if let Value::Message(nested) = message.get_field_mut(&field) {
+                prune_tree(nested, child);
+            }
+        }
+    }
+}
+
+impl Tree {
+    pub(crate) fn from_mask(mask: &FieldMask) -> Self {
+        let mut tree = Tree::default();
+        for path in &mask.paths {
+            tree.insert(path);
+        }
+        tree
+    }
+
+    pub(crate) fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Returns the subtree for the field `name`, falling back to a `*` wildcard entry.
+    #[cfg(feature = "serde")]
+    pub(crate) fn get(&self, name: &str) -> Option<&Tree> {
+        self.children.get(name).or_else(|| self.children.get("*"))
+    }
+
+    fn insert(&mut self, path: &str) {
+        if path.is_empty() {
+            return;
+        }
+
+        let mut node = self;
+        let mut parts = path.split('.').peekable();
+        while let Some(part) = parts.next() {
+            let is_new = !node.children.contains_key(part);
+            let child = node.children.entry(part.to_owned()).or_default();
+            if parts.peek().is_none() {
+                // The whole field is selected, so any more specific paths are redundant.
+                child.children.clear();
+                return;
+            }
+            if !is_new && child.is_leaf() {
+                // The field is already selected in full.
+                return;
+            }
+            node = child;
+        }
+    }
+
+    /// Returns the intersection of two trees, or `None` if it is empty.
+    fn intersect(&self, other: &Tree) -> Option<Tree> {
+        let mut children = BTreeMap::new();
+        for (name, left) in &self.children {
+            let right = match other.children.get(name) {
+                Some(right) => right,
+                None => continue,
+            };
+            let child = if left.is_leaf() {
+                Some(right.clone())
+            } else if right.is_leaf() {
+                Some(left.clone())
+            } else {
+                left.intersect(right)
+            };
+            if let Some(child) = child {
+                children.insert(name.clone(), child);
+            }
+        }
+
+        if children.is_empty() {
+            None
+        } else {
+            Some(Tree { children })
+        }
+    }
+
+    fn to_mask(&self) -> FieldMask {
+        let mut paths = Vec::new();
+        self.collect_paths(&mut String::new(), &mut paths);
+        FieldMask { paths }
+    }
+
+    fn collect_paths(&self, prefix: &mut String, paths: &mut Vec<String>) {
+        for (name, child) in &self.children {
+            let len = prefix.len();
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(name);
+            if child.is_leaf() {
+                paths.push(prefix.clone());
+            } else {
+                child.collect_paths(prefix, paths);
+            }
+            prefix.truncate(len);
+        }
+    }
+}
+
+impl fmt::Display for FieldMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid field mask path '{}': ", self.path)?;
+        match &self.kind {
+            FieldMaskErrorKind::EmptyPath => write!(f, "the path is empty"),
+            FieldMaskErrorKind::FieldNotFound { message, field } => {
+                write!(f, "field '{}' not found in message '{}'", field, message)
+            }
+            FieldMaskErrorKind::NotASingularMessage { field } => write!(
+                f,
+                "field '{}' is not a singular message, so it cannot contain subfields",
+                field
+            ),
+        }
+    }
+}
+
+impl Error for FieldMaskError {}
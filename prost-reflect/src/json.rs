@@ -0,0 +1,143 @@
+//! Conversions between the `google.protobuf.Struct` family of well-known types and [`serde_json::Value`].
+//!
+//! The `google.protobuf.Struct`, `google.protobuf.Value` and `google.protobuf.ListValue` types represent
+//! arbitrary JSON, and their JSON mapping is the JSON value they hold. The functions in this module convert
+//! between these types and [`serde_json`] values directly, without going through an intermediate string.
+//!
+//! Conversions from JSON are infallible. Conversions to JSON fail if a number is NaN or infinite, since
+//! these values cannot be represented as JSON.
+//!
+//! # Examples
+//!
+//! ```
+//! use prost_reflect::json;
+//! use serde_json::json;
+//!
+//! let value = json!({ "name": "example", "tags": ["a", "b"], "enabled": true, "nested": null });
+//! let message = json::to_struct(value.as_object().unwrap().clone());
+//! assert_eq!(json::from_struct(&message).unwrap(), *value.as_object().unwrap());
+//! ```
+
+use prost_types::{value::Kind, ListValue, NullValue, Struct};
+use serde::ser::Error;
+
+use crate::{DynamicMessage, ReflectMessage, Value};
+
+/// Converts a `google.protobuf.Struct` into a JSON object.
+pub fn from_struct(
+    value: &Struct,
+) -> Result<serde_json::Map<String, serde_json::Value>, serde_json::Error> {
+    value
+        .fields
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), from_value(value)?)))
+        .collect()
+}
+
+/// Converts a `google.protobuf.Value` into a JSON value.
+///
+/// A value with no kind set is converted to `null`.
+pub fn from_value(value: &prost_types::Value) -> Result<serde_json::Value, serde_json::Error> {
+    match &value.kind {
+        None | Some(Kind::NullValue(_)) => Ok(serde_json::Value::Null),
+        Some(Kind::BoolValue(value)) => Ok(serde_json::Value::Bool(*value)),
+        Some(Kind::NumberValue(value)) => match serde_json::Number::from_f64(*value) {
+            Some(number) => Ok(serde_json::Value::Number(number)),
+            None => Err(serde_json::Error::custom(
+                "cannot serialize non-finite double in google.protobuf.Value",
+            )),
+        },
+        Some(Kind::StringValue(value)) => Ok(serde_json::Value::String(value.clone())),
+        Some(Kind::ListValue(value)) => from_list_value(value).map(serde_json::Value::Array),
+        Some(Kind::StructValue(value)) => from_struct(value).map(serde_json::Value::Object),
+    }
+}
+
+/// Converts a `google.protobuf.ListValue` into a JSON array.
+pub fn from_list_value(value: &ListValue) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+    value.values.iter().map(from_value).collect()
+}
+
+/// Converts a JSON object into a `google.protobuf.Struct`.
+pub fn to_struct(value: serde_json::Map<String, serde_json::Value>) -> Struct {
+    Struct {
+        fields: value
+            .into_iter()
+            .map(|(key, value)| (key, to_value(value)))
+            .collect(),
+    }
+}
+
+/// Converts a JSON value into a `google.protobuf.Value`.
+pub fn to_value(value: serde_json::Value) -> prost_types::Value {
+    let kind = match value {
+        serde_json::Value::Null => Kind::NullValue(NullValue::NullValue as i32),
+        serde_json::Value::Bool(value) => Kind::BoolValue(value),
+        serde_json::Value::Number(value) => Kind::NumberValue(value.as_f64().unwrap_or(f64::NAN)),
+        serde_json::Value::String(value) => Kind::StringValue(value),
+        serde_json::Value::Array(value) => Kind::ListValue(to_list_value(value)),
+        serde_json::Value::Object(value) => Kind::StructValue(to_struct(value)),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+/// Converts a JSON array into a `google.protobuf.ListValue`.
+pub fn to_list_value(value: Vec<serde_json::Value>) -> ListValue {
+    ListValue {
+        values: value.into_iter().map(to_value).collect(),
+    }
+}
+
+/// Converts a [`Value::Message`] holding a `google.protobuf.Struct`, `google.protobuf.Value` or
+/// `google.protobuf.ListValue` into JSON.
+///
+/// Returns an error if `value` is not a message of one of these types.
+pub fn from_message(value: &Value) -> Result<serde_json::Value, serde_json::Error> {
+    let message = match value {
+        Value::Message(message) => message,
+        _ => {
+            return Err(serde_json::Error::custom(
+                "expected a google.protobuf.Struct, google.protobuf.Value or google.protobuf.ListValue message",
+            ))
+        }
+    };
+
+    match message.descriptor().full_name() {
+        "google.protobuf.Struct" => from_struct(&transcode(message)?).map(serde_json::Value::Object),
+        "google.protobuf.Value" => from_value(&transcode(message)?),
+        "google.protobuf.ListValue" => {
+            from_list_value(&transcode(message)?).map(serde_json::Value::Array)
+        }
+        name => Err(serde_json::Error::custom(format_args!(
+            "expected a google.protobuf.Struct, google.protobuf.Value or google.protobuf.ListValue message, found '{}'",
+            name
+        ))),
+    }
+}
+
+/// Converts a JSON object into a [`Value::Message`] holding a `google.protobuf.Struct`.
+///
+/// # Examples
+///
+/// ```
+/// use prost_reflect::{json, Value};
+/// use serde_json::json;
+///
+/// let value = json::to_struct_message(json!({ "foo": [1.5, "bar"] }).as_object().unwrap().clone());
+/// assert_eq!(json::from_message(&value).unwrap(), json!({ "foo": [1.5, "bar"] }));
+/// assert!(json::from_message(&Value::I32(1)).is_err());
+/// ```
+#[cfg(feature = "reflect-well-known-types")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reflect-well-known-types")))]
+pub fn to_struct_message(value: serde_json::Map<String, serde_json::Value>) -> Value {
+    Value::Message(to_struct(value).transcode_to_dynamic())
+}
+
+fn transcode<T>(message: &DynamicMessage) -> Result<T, serde_json::Error>
+where
+    T: prost::Message + Default,
+{
+    message
+        .transcode_to()
+        .map_err(|err| serde_json::Error::custom(format!("error decoding: {}", err)))
+}
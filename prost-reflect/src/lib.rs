@@ -13,22 +13,82 @@
 #[cfg(feature = "serde1")]
 extern crate serde1 as serde;
 
+#[cfg(feature = "buf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "buf")))]
+pub mod buf;
+#[cfg(feature = "tokio-util")]
+mod codec;
 mod descriptor;
 mod dynamic;
+#[cfg(feature = "serde-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+pub mod json;
+#[cfg(feature = "serde-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+pub mod ndjson;
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "protobuf")]
+mod protobuf;
 mod reflect;
+#[cfg(feature = "grpc-reflection")]
+#[cfg_attr(docsrs, doc(cfg(feature = "grpc-reflection")))]
+pub mod reflection;
+#[cfg(feature = "registry")]
+mod registry;
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+pub mod remote;
+#[cfg(feature = "tonic")]
+mod tonic;
+
+pub mod compat;
+pub mod field_mask;
+pub mod lint;
+pub mod plugin;
+pub mod resolver;
+pub mod testing;
+pub mod wire;
 
 pub use {prost, prost::bytes, prost_types};
 
 pub use self::descriptor::{
-    Cardinality, DescriptorError, DescriptorPool, EnumDescriptor, EnumValueDescriptor,
-    ExtensionDescriptor, FieldDescriptor, FileDescriptor, Kind, MessageDescriptor,
-    MethodDescriptor, OneofDescriptor, ServiceDescriptor, Syntax,
+    Cardinality, Compression, DescriptorDiagnostic, DescriptorError, DescriptorPool, EnumBuilder,
+    EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor, FieldBuilder, FieldDescriptor,
+    FileDescriptor, FileDescriptorBuilder, FileDescriptorSetDecoder, IncrementalPool, Kind,
+    Location, MergeConflict, MergeOptions, MessageBuilder, MessageDescriptor, MethodBuilder,
+    MethodDescriptor, OneofDescriptor, PoolOptions, ServiceBuilder, ServiceDescriptor, Syntax,
+};
+pub use self::dynamic::{
+    AssignError, BindError, BindOptions, CompareOptions, CorpusProfiler, DecodeOptions, Difference,
+    DynamicMessage, EncodeOptions, ExplainedField, Explanation, FieldStatistics, FillOptions,
+    GenerateOptions, LengthDelimitedReader, LogFields, LogOptions, MapKey, MessageDiff, Mutator,
+    SetFieldError, SnapshotOptions, TimeConversionError, TryFromValueError, UninitializedError,
+    UnknownField, UnpackAnyError, Utf8Validation, Value, VerifyError, Visit,
 };
-pub use self::dynamic::{DynamicMessage, MapKey, Value};
 pub use self::reflect::ReflectMessage;
 
+#[cfg(feature = "tokio-util")]
+pub use self::codec::DynamicMessageCodec;
+#[cfg(feature = "proptest")]
+pub use self::dynamic::ArbitraryOptions;
 #[cfg(feature = "serde")]
-pub use self::dynamic::{DeserializeOptions, SerializeOptions};
+pub use self::dynamic::{
+    BytesEncoding, DeserializeOptions, DurationEncoding, NonFiniteFloatEncoding, SerializeOptions,
+    TimestampEncoding, UnknownJsonField,
+};
+#[cfg(feature = "text-format")]
+pub use self::dynamic::{FormatOptions, ParseError};
+#[cfg(feature = "serde-json")]
+pub use self::dynamic::{JsonDeserializeHandler, JsonSerializeHandler};
+#[cfg(feature = "validate")]
+pub use self::dynamic::{ValidationError, Validator, Violation};
+#[cfg(feature = "postgres")]
+pub use self::postgres::{RowError, RowOptions};
+#[cfg(feature = "registry")]
+pub use self::registry::DescriptorRegistry;
+#[cfg(feature = "tonic")]
+pub use self::tonic::{DynamicCodec, DynamicDecoder, DynamicEncoder};
 
 #[cfg(feature = "derive")]
 #[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
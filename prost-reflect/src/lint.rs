@@ -0,0 +1,582 @@
+//! A pluggable lint pass for checking protobuf schemas against style rules.
+//!
+//! A [`Linter`] walks every element of the files in a [`DescriptorPool`] and passes it to each of its
+//! [`LintRule`]s, which can report [`Diagnostic`]s. Diagnostics include the location of the element in its
+//! source file when the file was compiled with source info (for example, with `protoc --include_source_info`).
+//!
+//! # Examples
+//!
+//! ```
+//! # use prost_reflect::DescriptorPool;
+//! use prost_reflect::lint::{LintContext, LintRule, Linter};
+//! use prost_reflect::MessageDescriptor;
+//!
+//! struct NoEmptyMessages;
+//!
+//! impl LintRule for NoEmptyMessages {
+//!     fn name(&self) -> &str {
+//!         "no_empty_messages"
+//!     }
+//!
+//!     fn check_message(&self, message: &MessageDescriptor, cx: &mut LintContext<'_>) {
+//!         if message.fields().len() == 0 {
+//!             cx.report(format!("message '{}' has no fields", message.name()));
+//!         }
+//!     }
+//! }
+//!
+//! # let pool = DescriptorPool::decode(include_bytes!("file_descriptor_set.bin").as_ref()).unwrap();
+//! let linter = Linter::empty().rule(NoEmptyMessages);
+//! assert!(linter.lint(&pool).is_empty());
+//! ```
+
+use std::{collections::HashMap, fmt, ops::Range};
+
+pub use crate::Location;
+
+use crate::{
+    DescriptorPool, EnumDescriptor, EnumValueDescriptor, ExtensionDescriptor, FieldDescriptor,
+    FileDescriptor, MessageDescriptor, MethodDescriptor, ServiceDescriptor, Syntax,
+};
+
+// Field numbers used to build paths into a FileDescriptorProto, as described in the documentation
+// for google.protobuf.SourceCodeInfo.
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const FILE_EXTENSION: i32 = 7;
+const MESSAGE_FIELD: i32 = 2;
+const MESSAGE_NESTED_TYPE: i32 = 3;
+const MESSAGE_ENUM_TYPE: i32 = 4;
+const MESSAGE_EXTENSION: i32 = 6;
+const ENUM_VALUE: i32 = 2;
+const SERVICE_METHOD: i32 = 2;
+
+/// A rule checked by a [`Linter`].
+///
+/// Each method is called once for every element of the corresponding type, and may report any number
+/// of problems using [`LintContext::report`]. All methods have an empty default implementation, so rules
+/// only need to implement the methods for the elements they check.
+pub trait LintRule {
+    /// The name of this rule, which is included in every [`Diagnostic`] it reports.
+    fn name(&self) -> &str;
+
+    /// Checks a file.
+    fn check_file(&self, _file: &FileDescriptor, _cx: &mut LintContext<'_>) {}
+
+    /// Checks a message type, including nested message types. Synthetic map entry messages are not checked.
+    fn check_message(&self, _message: &MessageDescriptor, _cx: &mut LintContext<'_>) {}
+
+    /// Checks a field of a message.
+    fn check_field(&self, _field: &FieldDescriptor, _cx: &mut LintContext<'_>) {}
+
+    /// Checks an extension field.
+    fn check_extension(&self, _extension: &ExtensionDescriptor, _cx: &mut LintContext<'_>) {}
+
+    /// Checks an enum type.
+    fn check_enum(&self, _enum_desc: &EnumDescriptor, _cx: &mut LintContext<'_>) {}
+
+    /// Checks a value of an enum type.
+    fn check_enum_value(&self, _value: &EnumValueDescriptor, _cx: &mut LintContext<'_>) {}
+
+    /// Checks a service.
+    fn check_service(&self, _service: &ServiceDescriptor, _cx: &mut LintContext<'_>) {}
+
+    /// Checks a method of a service.
+    fn check_method(&self, _method: &MethodDescriptor, _cx: &mut LintContext<'_>) {}
+}
+
+/// Runs a set of [`LintRule`]s over the files in a [`DescriptorPool`].
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+/// The context passed to a [`LintRule`], used to report problems with the element being checked.
+#[derive(Debug)]
+pub struct LintContext<'a> {
+    rule: &'a str,
+    file: &'a str,
+    element: &'a str,
+    location: Option<Location>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+/// A problem reported by a [`LintRule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    rule: String,
+    message: String,
+    file: String,
+    element: String,
+    location: Option<Location>,
+}
+
+/// Checks that elements follow the naming conventions of the
+/// [protobuf style guide](https://developers.google.com/protocol-buffers/docs/style):
+///
+/// - Message, enum, service and method names are `PascalCase`.
+/// - Field and extension names are `lower_snake_case`.
+/// - Enum value names are `UPPER_SNAKE_CASE`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NamingConventions;
+
+/// Checks that there are no unused field numbers between the lowest and highest field numbers of a message,
+/// unless they are reserved or declared as an extension range. Gaps usually indicate a field was deleted without
+/// reserving its number, which risks the number being reused incompatibly.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FieldNumberGaps;
+
+/// Checks that every enum has a zero value, that it is declared first, and that its name ends in
+/// `_UNSPECIFIED`, since the zero value is used when a field is not set.
+///
+/// Enums in `proto2` files are not checked, since they use the first value as the default and are not
+/// required to have a zero value.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnumZeroValue;
+
+/// Checks that every file declares a package, and that the file's directory matches its package.
+/// For example, a file in package `foo.bar.v1` should be in the directory `foo/bar/v1`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileLayout;
+
+impl Linter {
+    /// Creates a new [`Linter`] with the built-in rules: [`NamingConventions`], [`FieldNumberGaps`],
+    /// [`EnumZeroValue`] and [`FileLayout`].
+    pub fn new() -> Self {
+        Linter::empty()
+            .rule(NamingConventions)
+            .rule(FieldNumberGaps)
+            .rule(EnumZeroValue)
+            .rule(FileLayout)
+    }
+
+    /// Creates a new [`Linter`] with no rules.
+    pub fn empty() -> Self {
+        Linter { rules: Vec::new() }
+    }
+
+    /// Adds a rule to this linter.
+    pub fn rule(mut self, rule: impl LintRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Checks every file in `pool`, returning the problems found, ordered by file.
+    pub fn lint(&self, pool: &DescriptorPool) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for file in pool.files() {
+            self.lint_file_into(&file, &mut diagnostics);
+        }
+        diagnostics
+    }
+
+    /// Checks a single file, returning the problems found.
+    ///
+    /// This is useful for skipping files which are not under the user's control, such as dependencies.
+    pub fn lint_file(&self, file: &FileDescriptor) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        self.lint_file_into(file, &mut diagnostics);
+        diagnostics
+    }
+
+    fn lint_file_into(&self, file: &FileDescriptor, diagnostics: &mut Vec<Diagnostic>) {
+        let mut walker = Walker {
+            rules: &self.rules,
+            file_name: file.name(),
+            locations: locations(file),
+            path: Vec::new(),
+            diagnostics,
+        };
+
+        walker.visit(file.name(), |rule, cx| rule.check_file(file, cx));
+
+        for (index, message) in file.messages().enumerate() {
+            walker.with_path(&[FILE_MESSAGE_TYPE, index as i32], |walker| {
+                walker.message(&message)
+            });
+        }
+        for (index, enum_desc) in file.enums().enumerate() {
+            walker.with_path(&[FILE_ENUM_TYPE, index as i32], |walker| {
+                walker.enum_(&enum_desc)
+            });
+        }
+        for (index, extension) in file.extensions().enumerate() {
+            walker.with_path(&[FILE_EXTENSION, index as i32], |walker| {
+                walker.visit(extension.full_name(), |rule, cx| {
+                    rule.check_extension(&extension, cx)
+                })
+            });
+        }
+        for (index, service) in file.services().enumerate() {
+            walker.with_path(&[FILE_SERVICE, index as i32], |walker| {
+                walker.service(&service)
+            });
+        }
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Linter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Linter")
+            .field(
+                "rules",
+                &self
+                    .rules
+                    .iter()
+                    .map(|rule| rule.name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<'a> LintContext<'a> {
+    /// Reports a problem with the element being checked.
+    pub fn report(&mut self, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            rule: self.rule.to_owned(),
+            message: message.into(),
+            file: self.file.to_owned(),
+            element: self.element.to_owned(),
+            location: self.location,
+        });
+    }
+
+    /// The location of the element being checked, if the file includes source info.
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+}
+
+impl Diagnostic {
+    /// The name of the rule which reported this problem.
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    /// A description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The name of the file containing the element with the problem.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The full name of the element with the problem, or the file name for problems with a file.
+    pub fn element(&self) -> &str {
+        &self.element
+    }
+
+    /// The location of the element with the problem, if the file includes source info.
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(location) = &self.location {
+            write!(f, ":{}:{}", location.line(), location.column())?;
+        }
+        write!(f, ": {} ({})", self.message, self.rule)
+    }
+}
+
+impl LintRule for NamingConventions {
+    fn name(&self) -> &str {
+        "naming_conventions"
+    }
+
+    fn check_message(&self, message: &MessageDescriptor, cx: &mut LintContext<'_>) {
+        if !is_pascal_case(message.name()) {
+            cx.report(format!(
+                "message name '{}' should be PascalCase",
+                message.name()
+            ));
+        }
+    }
+
+    fn check_field(&self, field: &FieldDescriptor, cx: &mut LintContext<'_>) {
+        if !is_lower_snake_case(field.name()) {
+            cx.report(format!(
+                "field name '{}' should be lower_snake_case",
+                field.name()
+            ));
+        }
+    }
+
+    fn check_extension(&self, extension: &ExtensionDescriptor, cx: &mut LintContext<'_>) {
+        if !is_lower_snake_case(extension.name()) {
+            cx.report(format!(
+                "extension name '{}' should be lower_snake_case",
+                extension.name()
+            ));
+        }
+    }
+
+    fn check_enum(&self, enum_desc: &EnumDescriptor, cx: &mut LintContext<'_>) {
+        if !is_pascal_case(enum_desc.name()) {
+            cx.report(format!(
+                "enum name '{}' should be PascalCase",
+                enum_desc.name()
+            ));
+        }
+    }
+
+    fn check_enum_value(&self, value: &EnumValueDescriptor, cx: &mut LintContext<'_>) {
+        if !is_upper_snake_case(value.name()) {
+            cx.report(format!(
+                "enum value name '{}' should be UPPER_SNAKE_CASE",
+                value.name()
+            ));
+        }
+    }
+
+    fn check_service(&self, service: &ServiceDescriptor, cx: &mut LintContext<'_>) {
+        if !is_pascal_case(service.name()) {
+            cx.report(format!(
+                "service name '{}' should be PascalCase",
+                service.name()
+            ));
+        }
+    }
+
+    fn check_method(&self, method: &MethodDescriptor, cx: &mut LintContext<'_>) {
+        if !is_pascal_case(method.name()) {
+            cx.report(format!(
+                "method name '{}' should be PascalCase",
+                method.name()
+            ));
+        }
+    }
+}
+
+impl LintRule for FieldNumberGaps {
+    fn name(&self) -> &str {
+        "field_number_gaps"
+    }
+
+    fn check_message(&self, message: &MessageDescriptor, cx: &mut LintContext<'_>) {
+        let mut used: Vec<Range<u32>> = message
+            .fields()
+            .map(|field| field.number()..field.number() + 1)
+            .chain(message.reserved_ranges())
+            .chain(message.extension_ranges())
+            .collect();
+        used.sort_by_key(|range| range.start);
+
+        let mut next = match used.first() {
+            Some(range) => range.end,
+            None => return,
+        };
+        for range in &used[1..] {
+            if range.start > next {
+                if range.start - next == 1 {
+                    cx.report(format!("field number {} is unused and not reserved", next));
+                } else {
+                    cx.report(format!(
+                        "field numbers {} to {} are unused and not reserved",
+                        next,
+                        range.start - 1
+                    ));
+                }
+            }
+            next = next.max(range.end);
+        }
+    }
+}
+
+impl LintRule for EnumZeroValue {
+    fn name(&self) -> &str {
+        "enum_zero_value"
+    }
+
+    fn check_enum(&self, enum_desc: &EnumDescriptor, cx: &mut LintContext<'_>) {
+        if enum_desc.parent_file().syntax() != Syntax::Proto3 {
+            return;
+        }
+
+        let first = match enum_desc.enum_descriptor_proto().value.first() {
+            Some(first) => first,
+            None => return,
+        };
+
+        if first.number() != 0 {
+            if enum_desc.get_value(0).is_some() {
+                cx.report(format!(
+                    "the zero value of enum '{}' should be declared first",
+                    enum_desc.name()
+                ));
+            } else {
+                cx.report(format!("enum '{}' has no zero value", enum_desc.name()));
+            }
+        } else if !first.name().ends_with("_UNSPECIFIED") {
+            cx.report(format!(
+                "the zero value of enum '{}' should be named with the suffix '_UNSPECIFIED', found '{}'",
+                enum_desc.name(),
+                first.name()
+            ));
+        }
+    }
+}
+
+impl LintRule for FileLayout {
+    fn name(&self) -> &str {
+        "file_layout"
+    }
+
+    fn check_file(&self, file: &FileDescriptor, cx: &mut LintContext<'_>) {
+        let package = file.package_name();
+        if package.is_empty() {
+            cx.report("file does not declare a package");
+            return;
+        }
+
+        let directory = match file.name().rfind('/') {
+            Some(index) => &file.name()[..index],
+            None => "",
+        };
+        let expected = package.replace('.', "/");
+        if directory != expected {
+            cx.report(format!(
+                "file should be in directory '{}' to match its package '{}'",
+                expected, package
+            ));
+        }
+    }
+}
+
+struct Walker<'a> {
+    rules: &'a [Box<dyn LintRule>],
+    file_name: &'a str,
+    locations: HashMap<Vec<i32>, Location>,
+    path: Vec<i32>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl<'a> Walker<'a> {
+    fn with_path(&mut self, path: &[i32], f: impl FnOnce(&mut Self)) {
+        let len = self.path.len();
+        self.path.extend_from_slice(path);
+        f(self);
+        self.path.truncate(len);
+    }
+
+    fn visit(&mut self, element: &str, check: impl Fn(&dyn LintRule, &mut LintContext<'_>)) {
+        let location = self.locations.get(&self.path).copied();
+        for rule in self.rules {
+            let mut cx = LintContext {
+                rule: rule.name(),
+                file: self.file_name,
+                element,
+                location,
+                diagnostics: &mut *self.diagnostics,
+            };
+            check(rule.as_ref(), &mut cx);
+        }
+    }
+
+    fn message(&mut self, message: &MessageDescriptor) {
+        if message.is_map_entry() {
+            return;
+        }
+
+        self.visit(message.full_name(), |rule, cx| {
+            rule.check_message(message, cx)
+        });
+
+        for (index, raw_field) in message.descriptor_proto().field.iter().enumerate() {
+            let field = message
+                .get_field_by_name(raw_field.name())
+                .expect("field not found");
+            self.with_path(&[MESSAGE_FIELD, index as i32], |walker| {
+                walker.visit(field.full_name(), |rule, cx| rule.check_field(&field, cx))
+            });
+        }
+        for (index, nested) in message.child_messages().enumerate() {
+            self.with_path(&[MESSAGE_NESTED_TYPE, index as i32], |walker| {
+                walker.message(&nested)
+            });
+        }
+        for (index, enum_desc) in message.child_enums().enumerate() {
+            self.with_path(&[MESSAGE_ENUM_TYPE, index as i32], |walker| {
+                walker.enum_(&enum_desc)
+            });
+        }
+        for (index, extension) in message.child_extensions().enumerate() {
+            self.with_path(&[MESSAGE_EXTENSION, index as i32], |walker| {
+                walker.visit(extension.full_name(), |rule, cx| {
+                    rule.check_extension(&extension, cx)
+                })
+            });
+        }
+    }
+
+    fn enum_(&mut self, enum_desc: &EnumDescriptor) {
+        self.visit(enum_desc.full_name(), |rule, cx| {
+            rule.check_enum(enum_desc, cx)
+        });
+
+        for (index, raw_value) in enum_desc.enum_descriptor_proto().value.iter().enumerate() {
+            let value = enum_desc
+                .get_value_by_name(raw_value.name())
+                .expect("enum value not found");
+            self.with_path(&[ENUM_VALUE, index as i32], |walker| {
+                walker.visit(value.full_name(), |rule, cx| {
+                    rule.check_enum_value(&value, cx)
+                })
+            });
+        }
+    }
+
+    fn service(&mut self, service: &ServiceDescriptor) {
+        self.visit(service.full_name(), |rule, cx| {
+            rule.check_service(service, cx)
+        });
+
+        for (index, method) in service.methods().enumerate() {
+            self.with_path(&[SERVICE_METHOD, index as i32], |walker| {
+                walker.visit(method.full_name(), |rule, cx| {
+                    rule.check_method(&method, cx)
+                })
+            });
+        }
+    }
+}
+
+fn locations(file: &FileDescriptor) -> HashMap<Vec<i32>, Location> {
+    file.file_descriptor_proto()
+        .source_code_info
+        .iter()
+        .flat_map(|info| info.location.iter())
+        .filter_map(|location| Some((location.path.clone(), Location::from_span(&location.span)?)))
+        .collect()
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_uppercase())
+        && name.chars().all(|ch| ch.is_ascii_alphanumeric())
+}
+
+fn is_lower_snake_case(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+}
+
+fn is_upper_snake_case(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_uppercase() || ch.is_ascii_digit() || ch == '_')
+}
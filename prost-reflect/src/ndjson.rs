@@ -0,0 +1,298 @@
+//! Streaming conversion between length-delimited protobuf messages and [newline-delimited JSON](http://ndjson.org/).
+//!
+//! Length-delimited streams, as written by [`Message::encode_length_delimited`], are a common format for
+//! logs and data dumps. The functions in this module convert such streams to and from newline-delimited
+//! JSON one message at a time, so memory usage is bounded by the size of the largest message rather
+//! than the size of the stream.
+//!
+//! The synchronous functions read from [`Read`] and write to [`Write`] implementations. With the `tokio`
+//! feature enabled, asynchronous versions are available for [`AsyncRead`][tokio::io::AsyncRead] and
+//! [`AsyncWrite`][tokio::io::AsyncWrite] implementations. Since length delimiters are read a byte at a time,
+//! readers should be buffered.
+//!
+//! # Examples
+//!
+//! ```
+//! # use prost::Message;
+//! # use prost_reflect::{DescriptorPool, DeserializeOptions, DynamicMessage, SerializeOptions, Value};
+//! # let pool = DescriptorPool::decode(include_bytes!("file_descriptor_set.bin").as_ref()).unwrap();
+//! # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+//! use prost_reflect::ndjson;
+//!
+//! let mut message = DynamicMessage::new(message_descriptor.clone());
+//! message.set_field_by_name("foo", Value::I32(150));
+//! let mut binary = message.encode_length_delimited_to_vec();
+//! binary.extend(message.encode_length_delimited_to_vec());
+//!
+//! let mut json = Vec::new();
+//! let count = ndjson::binary_to_ndjson(
+//!     &message_descriptor,
+//!     binary.as_slice(),
+//!     &mut json,
+//!     &SerializeOptions::new(),
+//! )
+//! .unwrap();
+//! assert_eq!(count, 2);
+//! assert_eq!(json, b"{\"foo\":150}\n{\"foo\":150}\n");
+//!
+//! let mut roundtripped = Vec::new();
+//! ndjson::ndjson_to_binary(
+//!     &message_descriptor,
+//!     json.as_slice(),
+//!     &mut roundtripped,
+//!     &DeserializeOptions::new(),
+//! )
+//! .unwrap();
+//! assert_eq!(roundtripped, binary);
+//! ```
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufRead, Read, Write},
+};
+
+use prost::{DecodeError, Message};
+
+use crate::{
+    dynamic::delimited::{check_message_len, read_length_delimiter},
+    DeserializeOptions, DynamicMessage, MessageDescriptor, SerializeOptions,
+};
+
+/// An error returned when converting between length-delimited messages and newline-delimited JSON.
+#[derive(Debug)]
+pub struct TranscodeError {
+    kind: TranscodeErrorKind,
+}
+
+#[derive(Debug)]
+enum TranscodeErrorKind {
+    Io(io::Error),
+    Decode { index: u64, err: DecodeError },
+    Serialize { index: u64, err: serde_json::Error },
+    Deserialize { line: u64, err: serde_json::Error },
+}
+
+/// Reads length-delimited messages of type `desc` from `reader` until the end of the stream, and writes each
+/// to `writer` as a line of JSON.
+///
+/// Returns the number of messages written.
+pub fn binary_to_ndjson<R, W>(
+    desc: &MessageDescriptor,
+    mut reader: R,
+    mut writer: W,
+    options: &SerializeOptions,
+) -> Result<u64, TranscodeError>
+where
+    R: Read,
+    W: Write,
+{
+    let mut buf = Vec::new();
+    let mut line = Vec::new();
+    let mut count = 0;
+    while let Some(len) = read_length_delimiter(&mut reader)? {
+        buf.clear();
+        (&mut reader).take(len).read_to_end(&mut buf)?;
+        check_message_len(&buf, len)?;
+
+        line.clear();
+        message_to_json_line(desc, &buf, &mut line, options, count)?;
+        writer.write_all(&line)?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Reads lines of JSON from `reader` until the end of the stream, parsing each as a message of type `desc`,
+/// and writes them to `writer` as length-delimited messages.
+///
+/// Blank lines are skipped. Returns the number of messages written.
+pub fn ndjson_to_binary<R, W>(
+    desc: &MessageDescriptor,
+    mut reader: R,
+    mut writer: W,
+    options: &DeserializeOptions,
+) -> Result<u64, TranscodeError>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut line = Vec::new();
+    let mut buf = Vec::new();
+    let mut line_number = 0;
+    let mut count = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+
+        buf.clear();
+        if json_line_to_message(desc, &line, &mut buf, options, line_number)? {
+            writer.write_all(&buf)?;
+            count += 1;
+        }
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Reads length-delimited messages of type `desc` from `reader` until the end of the stream, and writes each
+/// to `writer` as a line of JSON.
+///
+/// This is an asynchronous version of [`binary_to_ndjson`]. Returns the number of messages written.
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub async fn binary_to_ndjson_async<R, W>(
+    desc: &MessageDescriptor,
+    mut reader: R,
+    mut writer: W,
+    options: &SerializeOptions,
+) -> Result<u64, TranscodeError>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    use crate::dynamic::delimited::read_varint_async;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut buf = Vec::new();
+    let mut line = Vec::new();
+    let mut count = 0;
+    while let Some(len) = read_varint_async(&mut reader).await? {
+        buf.clear();
+        (&mut reader).take(len).read_to_end(&mut buf).await?;
+        check_message_len(&buf, len)?;
+
+        line.clear();
+        message_to_json_line(desc, &buf, &mut line, options, count)?;
+        writer.write_all(&line).await?;
+        count += 1;
+    }
+    writer.flush().await?;
+    Ok(count)
+}
+
+/// Reads lines of JSON from `reader` until the end of the stream, parsing each as a message of type `desc`,
+/// and writes them to `writer` as length-delimited messages.
+///
+/// This is an asynchronous version of [`ndjson_to_binary`]. Returns the number of messages written.
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub async fn ndjson_to_binary_async<R, W>(
+    desc: &MessageDescriptor,
+    mut reader: R,
+    mut writer: W,
+    options: &DeserializeOptions,
+) -> Result<u64, TranscodeError>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let mut line = Vec::new();
+    let mut buf = Vec::new();
+    let mut line_number = 0;
+    let mut count = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            break;
+        }
+        line_number += 1;
+
+        buf.clear();
+        if json_line_to_message(desc, &line, &mut buf, options, line_number)? {
+            writer.write_all(&buf).await?;
+            count += 1;
+        }
+    }
+    writer.flush().await?;
+    Ok(count)
+}
+
+fn message_to_json_line(
+    desc: &MessageDescriptor,
+    buf: &[u8],
+    line: &mut Vec<u8>,
+    options: &SerializeOptions,
+    index: u64,
+) -> Result<(), TranscodeError> {
+    let message = DynamicMessage::decode(desc.clone(), buf)
+        .map_err(|err| TranscodeErrorKind::Decode { index, err })?;
+    message
+        .serialize_with_options(&mut serde_json::Serializer::new(&mut *line), options)
+        .map_err(|err| TranscodeErrorKind::Serialize { index, err })?;
+    line.push(b'\n');
+    Ok(())
+}
+
+/// Parses a line of JSON and appends it to `buf` as a length-delimited message, returning `false` if the line
+/// is blank.
+fn json_line_to_message(
+    desc: &MessageDescriptor,
+    line: &[u8],
+    buf: &mut Vec<u8>,
+    options: &DeserializeOptions,
+    line_number: u64,
+) -> Result<bool, TranscodeError> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(false);
+    }
+
+    let mut deserializer = serde_json::Deserializer::from_slice(line);
+    let message =
+        DynamicMessage::deserialize_with_options(desc.clone(), &mut deserializer, options)
+            .and_then(|message| deserializer.end().map(|()| message))
+            .map_err(|err| TranscodeErrorKind::Deserialize {
+                line: line_number,
+                err,
+            })?;
+    message
+        .encode_length_delimited(buf)
+        .expect("Vec<u8> has unbounded capacity");
+    Ok(true)
+}
+
+impl From<io::Error> for TranscodeError {
+    fn from(err: io::Error) -> Self {
+        TranscodeErrorKind::Io(err).into()
+    }
+}
+
+impl From<TranscodeErrorKind> for TranscodeError {
+    fn from(kind: TranscodeErrorKind) -> Self {
+        TranscodeError { kind }
+    }
+}
+
+impl fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TranscodeErrorKind::Io(err) => write!(f, "i/o error: {}", err),
+            TranscodeErrorKind::Decode { index, err } => {
+                write!(f, "failed to decode message {}: {}", index, err)
+            }
+            TranscodeErrorKind::Serialize { index, err } => {
+                write!(f, "failed to serialize message {}: {}", index, err)
+            }
+            TranscodeErrorKind::Deserialize { line, err } => {
+                write!(f, "failed to parse line {}: {}", line, err)
+            }
+        }
+    }
+}
+
+impl Error for TranscodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            TranscodeErrorKind::Io(err) => Some(err),
+            TranscodeErrorKind::Decode { err, .. } => Some(err),
+            TranscodeErrorKind::Serialize { err, .. } => Some(err),
+            TranscodeErrorKind::Deserialize { err, .. } => Some(err),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Helpers for writing `protoc` plugins.
+//!
+//! A `protoc` plugin is an executable which reads a `google.protobuf.compiler.CodeGeneratorRequest` from its
+//! standard input, and writes a `google.protobuf.compiler.CodeGeneratorResponse` to its standard output. A
+//! [`PluginRequest`] wraps the request with a [`DescriptorPool`] built from the files it contains, so plugins
+//! can use resolved descriptors instead of raw `FileDescriptorProto`s.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use prost_reflect::plugin::{write_response, PluginRequest};
+//! use prost_types::compiler::{code_generator_response::File, CodeGeneratorResponse};
+//!
+//! let request = PluginRequest::read().unwrap();
+//!
+//! let mut response = CodeGeneratorResponse::default();
+//! for file in request.files_to_generate() {
+//!     let content: String = file
+//!         .messages()
+//!         .map(|message| format!("{}\n", message.full_name()))
+//!         .collect();
+//!     response.file.push(File {
+//!         name: Some(format!("{}.txt", file.name())),
+//!         content: Some(content),
+//!         ..Default::default()
+//!     });
+//! }
+//!
+//! write_response(&response).unwrap();
+//! ```
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+};
+
+use prost::{bytes::Buf, DecodeError, Message};
+use prost_types::compiler::{CodeGeneratorRequest, CodeGeneratorResponse, Version};
+
+use crate::{DescriptorError, DescriptorPool, FileDescriptor};
+
+/// A code generation request sent by `protoc` to a plugin.
+#[derive(Debug, Clone)]
+pub struct PluginRequest {
+    pool: DescriptorPool,
+    files_to_generate: Vec<FileDescriptor>,
+    parameter: Option<String>,
+    compiler_version: Option<Version>,
+}
+
+/// An error that may occur while reading a [`PluginRequest`].
+#[derive(Debug)]
+pub struct PluginError {
+    kind: PluginErrorKind,
+}
+
+#[derive(Debug)]
+enum PluginErrorKind {
+    Io(io::Error),
+    Decode(DecodeError),
+    Descriptor(DescriptorError),
+    FileNotFound { name: String },
+}
+
+impl PluginRequest {
+    /// Reads a request from standard input, as sent by `protoc` when running a plugin.
+    pub fn read() -> Result<Self, PluginError> {
+        let stdin = io::stdin();
+        PluginRequest::read_from(stdin.lock())
+    }
+
+    /// Reads an encoded request from `reader` until the end of input.
+    pub fn read_from<R>(mut reader: R) -> Result<Self, PluginError>
+    where
+        R: Read,
+    {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(PluginErrorKind::Io)?;
+        PluginRequest::decode(bytes.as_slice())
+    }
+
+    /// Decodes a request from its protobuf byte representation.
+    pub fn decode<B>(bytes: B) -> Result<Self, PluginError>
+    where
+        B: Buf,
+    {
+        let request = CodeGeneratorRequest::decode(bytes).map_err(PluginErrorKind::Decode)?;
+        PluginRequest::from_request(request)
+    }
+
+    /// Creates a [`PluginRequest`] from a decoded [`CodeGeneratorRequest`].
+    ///
+    /// Returns an error if the files in the request are not valid, or if a file to generate is not included
+    /// in the request.
+    pub fn from_request(request: CodeGeneratorRequest) -> Result<Self, PluginError> {
+        let pool = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+            file: request.proto_file,
+        })
+        .map_err(PluginErrorKind::Descriptor)?;
+
+        let files_to_generate = request
+            .file_to_generate
+            .into_iter()
+            .map(|name| match pool.get_file_by_name(&name) {
+                Some(file) => Ok(file),
+                None => Err(PluginError::from(PluginErrorKind::FileNotFound { name })),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(PluginRequest {
+            pool,
+            files_to_generate,
+            parameter: request.parameter,
+            compiler_version: request.compiler_version,
+        })
+    }
+
+    /// Gets the pool containing the files to generate and all of their dependencies.
+    pub fn pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    /// Gets the files which were explicitly listed on the command line, in the order given.
+    ///
+    /// Code should only be generated for these files, and not for their dependencies.
+    pub fn files_to_generate(&self) -> impl ExactSizeIterator<Item = FileDescriptor> + '_ {
+        self.files_to_generate.iter().cloned()
+    }
+
+    /// Gets the parameter passed to the plugin on the command line, if any.
+    ///
+    /// For example, `protoc --foo_out=bar,baz:out_dir` passes the parameter `bar,baz`.
+    pub fn parameter(&self) -> Option<&str> {
+        self.parameter.as_deref()
+    }
+
+    /// Gets the version of `protoc` which sent the request, if it was provided.
+    pub fn compiler_version(&self) -> Option<&Version> {
+        self.compiler_version.as_ref()
+    }
+}
+
+/// Writes a response to standard output, as expected by `protoc` from a plugin.
+pub fn write_response(response: &CodeGeneratorResponse) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    stdout.write_all(&response.encode_to_vec())?;
+    stdout.flush()
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PluginErrorKind::Io(err) => write!(f, "failed to read request: {}", err),
+            PluginErrorKind::Decode(err) => write!(f, "failed to decode request: {}", err),
+            PluginErrorKind::Descriptor(err) => write!(f, "invalid request: {}", err),
+            PluginErrorKind::FileNotFound { name } => {
+                write!(f, "file to generate '{}' not found in request", name)
+            }
+        }
+    }
+}
+
+impl Error for PluginError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            PluginErrorKind::Io(err) => Some(err),
+            PluginErrorKind::Decode(err) => Some(err),
+            PluginErrorKind::Descriptor(err) => Some(err),
+            PluginErrorKind::FileNotFound { .. } => None,
+        }
+    }
+}
+
+impl From<PluginErrorKind> for PluginError {
+    fn from(kind: PluginErrorKind) -> Self {
+        PluginError { kind }
+    }
+}
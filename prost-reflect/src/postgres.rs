@@ -0,0 +1,534 @@
+//! Conversions between dynamic messages and Postgres rows of the [`sqlx`](https://crates.io/crates/sqlx) crate.
+
+use std::{convert::TryFrom, error::Error, fmt};
+
+use prost::bytes::Bytes;
+use sqlx::{
+    error::BoxDynError,
+    postgres::{PgArguments, PgRow},
+    types::{time::OffsetDateTime, time::PrimitiveDateTime, Json, JsonValue},
+    Arguments, Column, Row, TypeInfo, ValueRef,
+};
+
+use crate::{
+    DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, ReflectMessage, SerializeOptions,
+    Value,
+};
+
+const TIMESTAMP_NAME: &str = "google.protobuf.Timestamp";
+
+/// Options to control the conversion of messages to Postgres rows.
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+pub struct RowOptions {
+    use_enum_numbers: bool,
+}
+
+/// An error that may occur while converting between a [`DynamicMessage`] and a Postgres row.
+#[derive(Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+pub struct RowError {
+    kind: RowErrorKind,
+}
+
+#[derive(Debug)]
+enum RowErrorKind {
+    Decode {
+        column: String,
+        err: sqlx::Error,
+    },
+    Encode {
+        column: String,
+        err: BoxDynError,
+    },
+    Json {
+        column: String,
+        err: serde_json::Error,
+    },
+    UnsupportedType {
+        column: String,
+        type_name: String,
+        field: String,
+    },
+    OutOfRange {
+        column: String,
+        field: String,
+    },
+    UnknownEnumValue {
+        column: String,
+        value: String,
+        enum_name: String,
+    },
+    FieldNotFound {
+        column: String,
+    },
+}
+
+impl DynamicMessage {
+    /// Creates a message of the type described by `desc` from a Postgres row.
+    ///
+    /// Each column is matched to the field with the same name, or otherwise the same JSON name. Columns which
+    /// do not match any field are ignored, and fields whose column is `NULL` are left unset. Columns are
+    /// converted based on their type:
+    ///
+    /// - Integer, floating point, `BOOL`, text and `BYTEA` columns are converted to fields of the
+    ///   corresponding scalar type. Integers are range checked.
+    /// - Enum fields are read from text columns and Postgres enum types by value name, or from integer
+    ///   columns by number.
+    /// - `google.protobuf.Timestamp` fields are read from `TIMESTAMPTZ` columns, or from `TIMESTAMP` columns
+    ///   which are assumed to be in UTC.
+    /// - `JSON` and `JSONB` columns may be used for fields of any type, including messages, lists and maps,
+    ///   and are parsed using the [canonical JSON encoding](https://developers.google.com/protocol-buffers/docs/proto3#json).
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn from_pg_row(desc: MessageDescriptor, row: &PgRow) -> Result<Self, RowError> {
+        let mut message = DynamicMessage::new(desc.clone());
+        for (index, column) in row.columns().iter().enumerate() {
+            let field = match find_field(&desc, column.name()) {
+                Some(field) => field,
+                None => continue,
+            };
+
+            let raw = row
+                .try_get_raw(index)
+                .map_err(|err| RowError::decode(column.name(), err))?;
+            if raw.is_null() {
+                continue;
+            }
+
+            let value = decode_column(row, index, column.name(), &field)?;
+            message.set_field(&field, value);
+        }
+        Ok(message)
+    }
+
+    /// Encodes the fields of this message matching `columns` as query arguments, using the default
+    /// [`RowOptions`].
+    ///
+    /// See [`to_pg_arguments_with_options`][Self::to_pg_arguments_with_options] for details.
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn to_pg_arguments(&self, columns: &[&str]) -> Result<PgArguments, RowError> {
+        self.to_pg_arguments_with_options(columns, &RowOptions::default())
+    }
+
+    /// Encodes the fields of this message matching `columns` as query arguments, for use with
+    /// [`sqlx::query_with`]. The arguments are in the same order as `columns`, so the first column is bound
+    /// to `$1`.
+    ///
+    /// Columns are matched to fields as for [`from_pg_row`][Self::from_pg_row], and an error is returned if
+    /// a column does not match any field. Fields which support presence and are not set are encoded as
+    /// `NULL`. Scalar fields are encoded as the corresponding Postgres type, with unsigned integers encoded
+    /// as `INT8`. Enum fields are encoded as `TEXT`, or `INT4` if
+    /// [`use_enum_numbers`][RowOptions::use_enum_numbers] is set. Fields of type `google.protobuf.Timestamp`
+    /// are encoded as `TIMESTAMPTZ`, and all other message, list and map fields as `JSONB`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use prost_reflect::DynamicMessage;
+    /// # async fn insert(pool: &sqlx::PgPool, message: &DynamicMessage) -> Result<(), Box<dyn std::error::Error>> {
+    /// let arguments = message.to_pg_arguments(&["id", "name", "created_at"])?;
+    /// sqlx::query_with("INSERT INTO events (id, name, created_at) VALUES ($1, $2, $3)", arguments)
+    ///     .execute(pool)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn to_pg_arguments_with_options(
+        &self,
+        columns: &[&str],
+        options: &RowOptions,
+    ) -> Result<PgArguments, RowError> {
+        let mut arguments = PgArguments::default();
+        for &column in columns {
+            let field = find_field(&self.descriptor(), column).ok_or_else(|| {
+                RowError::from(RowErrorKind::FieldNotFound {
+                    column: column.to_owned(),
+                })
+            })?;
+            encode_field(&mut arguments, self, column, &field, options)?;
+        }
+        Ok(arguments)
+    }
+}
+
+impl RowOptions {
+    /// Creates a new instance of [`RowOptions`], with the default options chosen to match the canonical
+    /// JSON encoding.
+    pub const fn new() -> Self {
+        RowOptions {
+            use_enum_numbers: false,
+        }
+    }
+
+    /// Whether to encode enum fields as their number instead of their name.
+    ///
+    /// The default value is `false`.
+    pub const fn use_enum_numbers(mut self, yes: bool) -> Self {
+        self.use_enum_numbers = yes;
+        self
+    }
+}
+
+impl Default for RowOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RowError {
+    fn decode(column: &str, err: sqlx::Error) -> Self {
+        RowErrorKind::Decode {
+            column: column.to_owned(),
+            err,
+        }
+        .into()
+    }
+
+    fn out_of_range(column: &str, field: &FieldDescriptor) -> Self {
+        RowErrorKind::OutOfRange {
+            column: column.to_owned(),
+            field: field.full_name().to_owned(),
+        }
+        .into()
+    }
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            RowErrorKind::Decode { column, err } => {
+                write!(f, "failed to decode column '{}': {}", column, err)
+            }
+            RowErrorKind::Encode { column, err } => {
+                write!(f, "failed to encode column '{}': {}", column, err)
+            }
+            RowErrorKind::Json { column, err } => {
+                write!(f, "invalid JSON value for column '{}': {}", column, err)
+            }
+            RowErrorKind::UnsupportedType {
+                column,
+                type_name,
+                field,
+            } => write!(
+                f,
+                "column '{}' of type {} cannot be converted to field '{}'",
+                column, type_name, field
+            ),
+            RowErrorKind::OutOfRange { column, field } => write!(
+                f,
+                "value of column '{}' is out of range for field '{}'",
+                column, field
+            ),
+            RowErrorKind::UnknownEnumValue {
+                column,
+                value,
+                enum_name,
+            } => write!(
+                f,
+                "column '{}' has value '{}', which is not a value of enum '{}'",
+                column, value, enum_name
+            ),
+            RowErrorKind::FieldNotFound { column } => {
+                write!(f, "no field found for column '{}'", column)
+            }
+        }
+    }
+}
+
+impl Error for RowError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            RowErrorKind::Decode { err, .. } => Some(err),
+            RowErrorKind::Encode { err, .. } => Some(err.as_ref()),
+            RowErrorKind::Json { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<RowErrorKind> for RowError {
+    fn from(kind: RowErrorKind) -> Self {
+        RowError { kind }
+    }
+}
+
+fn find_field(desc: &MessageDescriptor, column: &str) -> Option<FieldDescriptor> {
+    desc.get_field_by_name(column)
+        .or_else(|| desc.get_field_by_json_name(column))
+}
+
+fn decode_column(
+    row: &PgRow,
+    index: usize,
+    column: &str,
+    field: &FieldDescriptor,
+) -> Result<Value, RowError> {
+    let type_name = row.columns()[index].type_info().name();
+    if type_name == "JSON" || type_name == "JSONB" {
+        let json: JsonValue = row
+            .try_get(index)
+            .map_err(|err| RowError::decode(column, err))?;
+        return decode_json(column, field, json);
+    }
+
+    let unsupported = || {
+        RowError::from(RowErrorKind::UnsupportedType {
+            column: column.to_owned(),
+            type_name: type_name.to_owned(),
+            field: field.full_name().to_owned(),
+        })
+    };
+    if field.is_list() || field.is_map() {
+        return Err(unsupported());
+    }
+
+    let value = match field.kind() {
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+            let value = decode_integer(row, index, column)?.ok_or_else(unsupported)?;
+            Value::I32(i32::try_from(value).map_err(|_| RowError::out_of_range(column, field))?)
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+            Value::I64(decode_integer(row, index, column)?.ok_or_else(unsupported)?)
+        }
+        Kind::Uint32 | Kind::Fixed32 => {
+            let value = decode_integer(row, index, column)?.ok_or_else(unsupported)?;
+            Value::U32(u32::try_from(value).map_err(|_| RowError::out_of_range(column, field))?)
+        }
+        Kind::Uint64 | Kind::Fixed64 => {
+            let value = decode_integer(row, index, column)?.ok_or_else(unsupported)?;
+            Value::U64(u64::try_from(value).map_err(|_| RowError::out_of_range(column, field))?)
+        }
+        Kind::Float => match type_name {
+            "FLOAT4" => Value::F32(get(row, index, column)?),
+            "FLOAT8" => Value::F32(get::<f64>(row, index, column)? as f32),
+            _ => return Err(unsupported()),
+        },
+        Kind::Double => match type_name {
+            "FLOAT4" => Value::F64(get::<f32>(row, index, column)?.into()),
+            "FLOAT8" => Value::F64(get(row, index, column)?),
+            _ => return Err(unsupported()),
+        },
+        Kind::Bool => match type_name {
+            "BOOL" => Value::Bool(get(row, index, column)?),
+            _ => return Err(unsupported()),
+        },
+        Kind::String => match type_name {
+            "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" => Value::String(get(row, index, column)?),
+            _ => return Err(unsupported()),
+        },
+        Kind::Bytes => match type_name {
+            "BYTEA" => Value::Bytes(Bytes::from(get::<Vec<u8>>(row, index, column)?)),
+            _ => return Err(unsupported()),
+        },
+        Kind::Enum(enum_desc) => match decode_integer(row, index, column)? {
+            Some(number) => Value::EnumNumber(
+                i32::try_from(number).map_err(|_| RowError::out_of_range(column, field))?,
+            ),
+            None => {
+                // Postgres enum types have custom names, so any other type is decoded as text.
+                let name: String = row
+                    .try_get_unchecked(index)
+                    .map_err(|err| RowError::decode(column, err))?;
+                match enum_desc.get_value_by_name(&name) {
+                    Some(value) => Value::EnumNumber(value.number()),
+                    None => {
+                        return Err(RowErrorKind::UnknownEnumValue {
+                            column: column.to_owned(),
+                            value: name,
+                            enum_name: enum_desc.full_name().to_owned(),
+                        }
+                        .into())
+                    }
+                }
+            }
+        },
+        Kind::Message(message_desc) if message_desc.full_name() == TIMESTAMP_NAME => {
+            let timestamp = match type_name {
+                "TIMESTAMPTZ" => get::<OffsetDateTime>(row, index, column)?,
+                "TIMESTAMP" => get::<PrimitiveDateTime>(row, index, column)?.assume_utc(),
+                _ => return Err(unsupported()),
+            };
+            let mut message = DynamicMessage::new(message_desc);
+            message.set_field_by_name("seconds", Value::I64(timestamp.unix_timestamp()));
+            message.set_field_by_name("nanos", Value::I32(timestamp.nanosecond() as i32));
+            Value::Message(message)
+        }
+        Kind::Message(_) => return Err(unsupported()),
+    };
+    Ok(value)
+}
+
+fn get<'r, T>(row: &'r PgRow, index: usize, column: &str) -> Result<T, RowError>
+where
+    T: sqlx::Decode<'r, sqlx::Postgres> + sqlx::Type<sqlx::Postgres>,
+{
+    row.try_get(index)
+        .map_err(|err| RowError::decode(column, err))
+}
+
+/// Decodes an integer column of any width, or returns `None` if the column is not an integer.
+fn decode_integer(row: &PgRow, index: usize, column: &str) -> Result<Option<i64>, RowError> {
+    match row.columns()[index].type_info().name() {
+        "INT2" => Ok(Some(get::<i16>(row, index, column)?.into())),
+        "INT4" => Ok(Some(get::<i32>(row, index, column)?.into())),
+        "INT8" => Ok(Some(get::<i64>(row, index, column)?)),
+        _ => Ok(None),
+    }
+}
+
+fn decode_json(column: &str, field: &FieldDescriptor, json: JsonValue) -> Result<Value, RowError> {
+    // Deserialize a message containing only this field, so the canonical JSON mapping is reused for
+    // fields of any type.
+    let mut object = serde_json::Map::new();
+    object.insert(field.json_name().to_owned(), json);
+    let message =
+        DynamicMessage::deserialize(field.parent_message().clone(), JsonValue::Object(object))
+            .map_err(|err| {
+                RowError::from(RowErrorKind::Json {
+                    column: column.to_owned(),
+                    err,
+                })
+            })?;
+    Ok(message.get_field(field).into_owned())
+}
+
+fn encode_field(
+    arguments: &mut PgArguments,
+    message: &DynamicMessage,
+    column: &str,
+    field: &FieldDescriptor,
+    options: &RowOptions,
+) -> Result<(), RowError> {
+    let is_null = field.supports_presence() && !message.has_field(field);
+    let value = message.get_field(field);
+
+    let result = match field.kind() {
+        _ if field.is_list() || field.is_map() => {
+            arguments.add(Json(encode_json(column, message, field)?))
+        }
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+            arguments.add(non_null(is_null, value.as_i32()))
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+            arguments.add(non_null(is_null, value.as_i64()))
+        }
+        Kind::Uint32 | Kind::Fixed32 => {
+            arguments.add(non_null(is_null, value.as_u32().map(i64::from)))
+        }
+        Kind::Uint64 | Kind::Fixed64 => {
+            let value = match non_null(is_null, value.as_u64()) {
+                Some(value) => {
+                    Some(i64::try_from(value).map_err(|_| RowError::out_of_range(column, field))?)
+                }
+                None => None,
+            };
+            arguments.add(value)
+        }
+        Kind::Float => arguments.add(non_null(is_null, value.as_f32())),
+        Kind::Double => arguments.add(non_null(is_null, value.as_f64())),
+        Kind::Bool => arguments.add(non_null(is_null, value.as_bool())),
+        Kind::String => arguments.add(non_null(is_null, value.as_str())),
+        Kind::Bytes => arguments.add(non_null(is_null, value.as_bytes().map(|b| b.as_ref()))),
+        Kind::Enum(enum_desc) => {
+            let number = non_null(is_null, value.as_enum_number());
+            if options.use_enum_numbers {
+                arguments.add(number)
+            } else {
+                let name = match number {
+                    Some(number) => match enum_desc.get_value(number) {
+                        Some(value) => Some(value.name().to_owned()),
+                        None => {
+                            return Err(RowErrorKind::UnknownEnumValue {
+                                column: column.to_owned(),
+                                value: number.to_string(),
+                                enum_name: enum_desc.full_name().to_owned(),
+                            }
+                            .into())
+                        }
+                    },
+                    None => None,
+                };
+                arguments.add(name)
+            }
+        }
+        Kind::Message(message_desc) if message_desc.full_name() == TIMESTAMP_NAME => {
+            let timestamp = match non_null(is_null, value.as_message()) {
+                Some(timestamp) => Some(encode_timestamp(timestamp, column, field)?),
+                None => None,
+            };
+            arguments.add(timestamp)
+        }
+        Kind::Message(_) => {
+            let json = if is_null {
+                None
+            } else {
+                Some(Json(encode_json(column, message, field)?))
+            };
+            arguments.add(json)
+        }
+    };
+
+    result.map_err(|err| {
+        RowErrorKind::Encode {
+            column: column.to_owned(),
+            err,
+        }
+        .into()
+    })
+}
+
+fn non_null<T>(is_null: bool, value: Option<T>) -> Option<T> {
+    if is_null {
+        None
+    } else {
+        value
+    }
+}
+
+fn encode_timestamp(
+    timestamp: &DynamicMessage,
+    column: &str,
+    field: &FieldDescriptor,
+) -> Result<OffsetDateTime, RowError> {
+    let seconds = timestamp
+        .get_field_by_name("seconds")
+        .and_then(|value| value.as_i64())
+        .unwrap_or_default();
+    let nanos = timestamp
+        .get_field_by_name("nanos")
+        .and_then(|value| value.as_i32())
+        .unwrap_or_default();
+    OffsetDateTime::from_unix_timestamp_nanos(
+        i128::from(seconds) * 1_000_000_000 + i128::from(nanos),
+    )
+    .map_err(|_| RowError::out_of_range(column, field))
+}
+
+fn encode_json(
+    column: &str,
+    message: &DynamicMessage,
+    field: &FieldDescriptor,
+) -> Result<JsonValue, RowError> {
+    // Serialize a message containing only this field, so the canonical JSON mapping is reused for
+    // fields of any type.
+    let mut single = DynamicMessage::new(message.descriptor());
+    single.set_field(field, message.get_field(field).into_owned());
+    let json = single
+        .serialize_with_options(
+            serde_json::value::Serializer,
+            &SerializeOptions::new().skip_default_fields(false),
+        )
+        .map_err(|err| {
+            RowError::from(RowErrorKind::Json {
+                column: column.to_owned(),
+                err,
+            })
+        })?;
+    match json {
+        JsonValue::Object(mut object) => {
+            Ok(object.remove(field.json_name()).unwrap_or(JsonValue::Null))
+        }
+        _ => unreachable!("messages are serialized as objects"),
+    }
+}
@@ -0,0 +1,188 @@
+//! Conversions to and from the descriptors and dynamic messages of the [`protobuf`](https://crates.io/crates/protobuf)
+//! crate.
+
+use std::collections::{HashMap, HashSet};
+
+use ::protobuf::{reflect as pb, Message as _, MessageDyn};
+use prost::{DecodeError, Message};
+use prost_types::FileDescriptorProto;
+
+use crate::{DescriptorError, DescriptorPool, DynamicMessage, FileDescriptor, MessageDescriptor};
+
+impl DescriptorPool {
+    /// Creates a [`DescriptorPool`] from file descriptors of the `protobuf` crate, including all of
+    /// their dependencies.
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn from_protobuf(files: &[pb::FileDescriptor]) -> Result<Self, DescriptorError> {
+        let mut pool = DescriptorPool::new();
+        for file in files {
+            pool.add_protobuf_file_descriptor(file)?;
+        }
+        Ok(pool)
+    }
+
+    /// Adds a file descriptor of the `protobuf` crate to this pool, along with all of its dependencies
+    /// which are not already in the pool.
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn add_protobuf_file_descriptor(
+        &mut self,
+        file: &pb::FileDescriptor,
+    ) -> Result<(), DescriptorError> {
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        collect_protobuf_files(file, &mut seen, &mut files);
+
+        let protos = files
+            .into_iter()
+            .map(from_protobuf_proto)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(DescriptorError::decode_file_descriptor_set)?;
+        self.add_file_descriptor_protos(protos)
+    }
+
+    /// Converts every file in this pool to a file descriptor of the `protobuf` crate.
+    ///
+    /// The files are returned in the same order as [`files`][DescriptorPool::files].
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn to_protobuf(&self) -> ::protobuf::Result<Vec<pb::FileDescriptor>> {
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        for file in self.files() {
+            collect_files(&file, &mut seen, &mut files);
+        }
+
+        let built = build_protobuf_files(&files)?;
+        Ok(self
+            .files()
+            .map(|file| built[file.name()].clone())
+            .collect())
+    }
+}
+
+impl FileDescriptor {
+    /// Converts this file, and all of its dependencies, to a file descriptor of the `protobuf` crate.
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn to_protobuf(&self) -> ::protobuf::Result<pb::FileDescriptor> {
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        collect_files(self, &mut seen, &mut files);
+
+        let mut built = build_protobuf_files(&files)?;
+        Ok(built.remove(self.name()).expect("file not built"))
+    }
+}
+
+impl MessageDescriptor {
+    /// Converts this message type to a message descriptor of the `protobuf` crate.
+    ///
+    /// This converts the whole file containing the message, so when converting many messages it is more
+    /// efficient to convert the file or pool once using [`FileDescriptor::to_protobuf`] or
+    /// [`DescriptorPool::to_protobuf`].
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn to_protobuf(&self) -> ::protobuf::Result<pb::MessageDescriptor> {
+        let file = self.parent_file().to_protobuf()?;
+        Ok(file
+            .message_by_full_name(&format!(".{}", self.full_name()))
+            .expect("message not found in converted file"))
+    }
+}
+
+impl DynamicMessage {
+    /// Converts this message to a dynamic message of the `protobuf` crate, with the type described
+    /// by `desc`.
+    ///
+    /// Returns an error if this message cannot be decoded as an instance of `desc`. Required fields are
+    /// not checked.
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn to_protobuf(
+        &self,
+        desc: &pb::MessageDescriptor,
+    ) -> ::protobuf::Result<Box<dyn MessageDyn>> {
+        let mut message = desc.new_instance();
+        message.merge_from_bytes_dyn(&self.encode_to_vec())?;
+        Ok(message)
+    }
+
+    /// Converts a message of the `protobuf` crate to a [`DynamicMessage`] of the type described by `desc`.
+    ///
+    /// The message may be a generated message or a dynamic message. Required fields are not checked.
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn from_protobuf(
+        desc: MessageDescriptor,
+        message: &dyn MessageDyn,
+    ) -> Result<Self, DecodeError> {
+        let bytes = message
+            .write_length_delimited_to_bytes_dyn()
+            .map_err(|err| DecodeError::new(err.to_string()))?;
+        let mut dynamic = DynamicMessage::new(desc);
+        dynamic.merge_length_delimited(bytes.as_slice())?;
+        Ok(dynamic)
+    }
+}
+
+/// Collects a file and its transitive dependencies, with dependencies before the files which import them.
+fn collect_files(
+    file: &FileDescriptor,
+    seen: &mut HashSet<String>,
+    files: &mut Vec<FileDescriptor>,
+) {
+    if !seen.insert(file.name().to_owned()) {
+        return;
+    }
+    for name in &file.file_descriptor_proto().dependency {
+        let dependency = file
+            .parent_pool()
+            .get_file_by_name(name)
+            .expect("dependency not found");
+        collect_files(&dependency, seen, files);
+    }
+    files.push(file.clone());
+}
+
+/// Converts files to the `protobuf` crate, given in an order where dependencies come before the files
+/// which import them.
+fn build_protobuf_files(
+    files: &[FileDescriptor],
+) -> ::protobuf::Result<HashMap<String, pb::FileDescriptor>> {
+    let mut built: HashMap<String, pb::FileDescriptor> = HashMap::with_capacity(files.len());
+    for file in files {
+        let proto = to_protobuf_proto(file.file_descriptor_proto())?;
+        let dependencies: Vec<_> = proto
+            .dependency
+            .iter()
+            .map(|name| built[name.as_str()].clone())
+            .collect();
+        let converted = pb::FileDescriptor::new_dynamic(proto, &dependencies)?;
+        built.insert(file.name().to_owned(), converted);
+    }
+    Ok(built)
+}
+
+fn collect_protobuf_files<'a>(
+    file: &'a pb::FileDescriptor,
+    seen: &mut HashSet<&'a str>,
+    files: &mut Vec<&'a ::protobuf::descriptor::FileDescriptorProto>,
+) {
+    if !seen.insert(file.name()) {
+        return;
+    }
+    for dependency in file.deps() {
+        collect_protobuf_files(dependency, seen, files);
+    }
+    files.push(file.proto());
+}
+
+fn to_protobuf_proto(
+    file: &FileDescriptorProto,
+) -> ::protobuf::Result<::protobuf::descriptor::FileDescriptorProto> {
+    ::protobuf::descriptor::FileDescriptorProto::parse_from_bytes(&file.encode_to_vec())
+}
+
+fn from_protobuf_proto(
+    file: &::protobuf::descriptor::FileDescriptorProto,
+) -> Result<FileDescriptorProto, DecodeError> {
+    let bytes = file
+        .write_to_bytes()
+        .map_err(|err| DecodeError::new(err.to_string()))?;
+    FileDescriptorProto::decode(bytes.as_slice())
+}
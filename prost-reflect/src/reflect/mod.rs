@@ -1,9 +1,12 @@
 #[cfg(feature = "reflect-well-known-types")]
 mod wkt;
 
+#[cfg(feature = "reflect-well-known-types")]
+pub(crate) use self::wkt::well_known_types;
+
 use prost::Message;
 
-use crate::{DynamicMessage, MessageDescriptor};
+use crate::{dynamic, DynamicMessage, MessageDescriptor, UnpackAnyError};
 
 /// Trait for message types that support reflection.
 pub trait ReflectMessage: Message {
@@ -24,6 +27,26 @@ pub trait ReflectMessage: Message {
             .expect("error converting to dynamic message");
         message
     }
+
+    /// Encodes this message into a `google.protobuf.Any`.
+    ///
+    /// The type URL is the full name of the message type, prefixed with `type.googleapis.com/`.
+    fn pack_any(&self) -> prost_types::Any
+    where
+        Self: Sized,
+    {
+        dynamic::pack_any(self)
+    }
+
+    /// Decodes a message of this type from a `google.protobuf.Any`.
+    ///
+    /// Returns an error if the last segment of the type URL is not the full name of this message type.
+    fn unpack_any(any: &prost_types::Any) -> Result<Self, UnpackAnyError>
+    where
+        Self: Sized + Default,
+    {
+        dynamic::unpack_any(any)
+    }
 }
 
 impl<M> ReflectMessage for Box<M>
@@ -7,6 +7,11 @@ static WELL_KNOWN_TYPES_BYTES: &[u8] =
 static WELL_KNOWN_TYPES: Lazy<DescriptorPool> =
     Lazy::new(|| DescriptorPool::decode(WELL_KNOWN_TYPES_BYTES).unwrap());
 
+/// Gets a pool containing the files defining the well-known types.
+pub(crate) fn well_known_types() -> &'static DescriptorPool {
+    &WELL_KNOWN_TYPES
+}
+
 macro_rules! impl_reflect_message {
     ($($ty:ty => $name:literal;)*) => {
         $(
@@ -0,0 +1,258 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fmt,
+};
+
+use prost::{DecodeError, Message};
+use prost_types::FileDescriptorProto;
+
+use crate::{DescriptorError, DescriptorPool};
+
+use super::{
+    ExtensionRequest, MessageRequest, MessageResponse, ReflectionTransport, ServerReflectionRequest,
+};
+
+/// A client for the gRPC server reflection protocol, which builds a [`DescriptorPool`] from the files
+/// returned by the server.
+///
+/// Files are added to the pool as they are loaded, along with all of their transitive dependencies, so
+/// the pool is always in a consistent state.
+#[derive(Debug)]
+pub struct ReflectionClient<T> {
+    transport: T,
+    host: String,
+    pool: DescriptorPool,
+}
+
+/// An error that may occur while loading files from a server using the reflection protocol.
+#[derive(Debug)]
+pub struct ReflectionError {
+    kind: ReflectionErrorKind,
+}
+
+#[derive(Debug)]
+enum ReflectionErrorKind {
+    Transport(Box<dyn Error + Send + Sync>),
+    Server { code: i32, message: String },
+    UnexpectedResponse { expected: &'static str },
+    Decode(DecodeError),
+    Descriptor(DescriptorError),
+}
+
+impl<T> ReflectionClient<T>
+where
+    T: ReflectionTransport,
+{
+    /// Creates a new [`ReflectionClient`] which sends requests using `transport`, starting with an
+    /// empty pool.
+    pub fn new(transport: T) -> Self {
+        ReflectionClient {
+            transport,
+            host: String::new(),
+            pool: DescriptorPool::new(),
+        }
+    }
+
+    /// Sets the host included in each request, for servers which host multiple services.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Sets the pool which loaded files are added to.
+    ///
+    /// Files already in the pool are not requested from the server, so this can be used to avoid
+    /// fetching common dependencies such as the well-known types.
+    pub fn initial_pool(mut self, pool: DescriptorPool) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Gets a reference to the pool containing all files loaded so far.
+    pub fn pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    /// Consumes this client, returning the pool containing all files loaded.
+    pub fn into_pool(self) -> DescriptorPool {
+        self.pool
+    }
+
+    /// Gets the full names of all services exposed by the server.
+    pub fn list_services(&mut self) -> Result<Vec<String>, ReflectionError> {
+        match self.send(MessageRequest::ListServices(String::new()))? {
+            MessageResponse::ListServicesResponse(response) => Ok(response
+                .service
+                .into_iter()
+                .map(|service| service.name)
+                .collect()),
+            _ => Err(ReflectionErrorKind::UnexpectedResponse {
+                expected: "a list services",
+            }
+            .into()),
+        }
+    }
+
+    /// Loads the file with the given name, and its dependencies.
+    pub fn load_file(&mut self, name: &str) -> Result<(), ReflectionError> {
+        if self.pool.get_file_by_name(name).is_some() {
+            return Ok(());
+        }
+
+        let files = self.fetch_files(MessageRequest::FileByFilename(name.to_owned()))?;
+        self.add_files(files)
+    }
+
+    /// Loads the file defining the symbol with the given full name, and its dependencies.
+    ///
+    /// The symbol may be a service, method, message, enum or extension.
+    pub fn load_symbol(&mut self, symbol: &str) -> Result<(), ReflectionError> {
+        let files = self.fetch_files(MessageRequest::FileContainingSymbol(symbol.to_owned()))?;
+        self.add_files(files)
+    }
+
+    /// Loads the file defining the extension of `containing_type` with the given field number, and its
+    /// dependencies.
+    pub fn load_extension(
+        &mut self,
+        containing_type: &str,
+        extension_number: i32,
+    ) -> Result<(), ReflectionError> {
+        let files =
+            self.fetch_files(MessageRequest::FileContainingExtension(ExtensionRequest {
+                containing_type: containing_type.to_owned(),
+                extension_number,
+            }))?;
+        self.add_files(files)
+    }
+
+    /// Loads the files defining all services exposed by the server, and their dependencies.
+    pub fn load_all_services(&mut self) -> Result<(), ReflectionError> {
+        for service in self.list_services()? {
+            self.load_symbol(&service)?;
+        }
+        Ok(())
+    }
+
+    /// Fetches any dependencies of `files` not yet in the pool, then adds them all to the pool.
+    fn add_files(&mut self, files: Vec<FileDescriptorProto>) -> Result<(), ReflectionError> {
+        let mut pending = BTreeMap::new();
+        let mut queue = files;
+        let mut requested = HashSet::new();
+
+        loop {
+            for file in queue.drain(..) {
+                if self.pool.get_file_by_name(file.name()).is_none() {
+                    pending.insert(file.name().to_owned(), file);
+                }
+            }
+
+            let missing: Vec<String> = pending
+                .values()
+                .flat_map(|file| file.dependency.iter())
+                .filter(|name| {
+                    !pending.contains_key(*name) && self.pool.get_file_by_name(name).is_none()
+                })
+                .cloned()
+                .collect();
+            if missing.is_empty() {
+                break;
+            }
+
+            for name in missing {
+                if requested.insert(name.clone()) {
+                    queue.extend(self.fetch_files(MessageRequest::FileByFilename(name))?);
+                }
+            }
+            if queue.is_empty() {
+                // The server did not return some dependencies, so report the error when building the pool.
+                break;
+            }
+        }
+
+        self.pool
+            .add_file_descriptor_protos(pending.into_values())
+            .map_err(|err| ReflectionErrorKind::Descriptor(err).into())
+    }
+
+    fn fetch_files(
+        &mut self,
+        request: MessageRequest,
+    ) -> Result<Vec<FileDescriptorProto>, ReflectionError> {
+        match self.send(request)? {
+            MessageResponse::FileDescriptorResponse(response) => response
+                .file_descriptor_proto
+                .iter()
+                .map(|bytes| {
+                    FileDescriptorProto::decode(bytes.as_slice())
+                        .map_err(|err| ReflectionErrorKind::Decode(err).into())
+                })
+                .collect(),
+            _ => Err(ReflectionErrorKind::UnexpectedResponse {
+                expected: "a file descriptor",
+            }
+            .into()),
+        }
+    }
+
+    fn send(&mut self, request: MessageRequest) -> Result<MessageResponse, ReflectionError> {
+        let response = self
+            .transport
+            .send(ServerReflectionRequest {
+                host: self.host.clone(),
+                message_request: Some(request),
+            })
+            .map_err(|err| ReflectionErrorKind::Transport(Box::new(err)))?;
+
+        match response.message_response {
+            Some(MessageResponse::ErrorResponse(err)) => Err(ReflectionErrorKind::Server {
+                code: err.error_code,
+                message: err.error_message,
+            }
+            .into()),
+            Some(response) => Ok(response),
+            None => Err(ReflectionErrorKind::UnexpectedResponse {
+                expected: "a non-empty",
+            }
+            .into()),
+        }
+    }
+}
+
+impl fmt::Display for ReflectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ReflectionErrorKind::Transport(err) => write!(f, "failed to send request: {}", err),
+            ReflectionErrorKind::Server { code, message } => {
+                write!(f, "server returned error {}: {}", code, message)
+            }
+            ReflectionErrorKind::UnexpectedResponse { expected } => {
+                write!(f, "expected {} response from server", expected)
+            }
+            ReflectionErrorKind::Decode(err) => {
+                write!(f, "failed to decode file descriptor: {}", err)
+            }
+            ReflectionErrorKind::Descriptor(err) => {
+                write!(f, "invalid file descriptor: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for ReflectionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ReflectionErrorKind::Transport(err) => Some(&**err),
+            ReflectionErrorKind::Decode(err) => Some(err),
+            ReflectionErrorKind::Descriptor(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ReflectionErrorKind> for ReflectionError {
+    fn from(kind: ReflectionErrorKind) -> Self {
+        ReflectionError { kind }
+    }
+}
@@ -0,0 +1,76 @@
+//! Support for the [gRPC server reflection protocol](https://github.com/grpc/grpc/blob/master/doc/server-reflection.md).
+//!
+//! A [`ReflectionClient`] sends requests of the `grpc.reflection.v1` protocol to a server, and builds a
+//! [`DescriptorPool`][crate::DescriptorPool] from the file descriptors it returns, fetching any
+//! dependencies which are not yet in the pool. This makes it possible to call methods or decode
+//! messages of a server without having access to its `.proto` files.
+//!
+//! Conversely, a [`ReflectionService`] responds to reflection requests using the files in a
+//! [`DescriptorPool`][crate::DescriptorPool], so that a server can support reflection.
+//!
+//! This module does not include an HTTP/2 implementation. Instead, client requests are sent through a
+//! [`ReflectionTransport`], which can be implemented on top of any gRPC library by forwarding each
+//! request to the [`SERVER_REFLECTION_INFO_PATH`] method and returning the next response from the stream.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use prost_reflect::reflection::{ServerReflectionRequest, ServerReflectionResponse};
+//! # fn call_server(request: ServerReflectionRequest) -> Result<ServerReflectionResponse, std::io::Error> { unimplemented!() }
+//! use prost_reflect::reflection::ReflectionClient;
+//!
+//! let mut client = ReflectionClient::new(call_server);
+//! client.load_all_services().unwrap();
+//! let pool = client.into_pool();
+//! ```
+
+mod client;
+mod proto;
+mod server;
+
+pub use self::client::{ReflectionClient, ReflectionError};
+pub use self::proto::{
+    ErrorResponse, ExtensionNumberResponse, ExtensionRequest, FileDescriptorResponse,
+    ListServiceResponse, MessageRequest, MessageResponse, ServerReflectionRequest,
+    ServerReflectionResponse, ServiceResponse,
+};
+pub use self::server::ReflectionService;
+
+use std::error::Error;
+
+/// The full name of the server reflection service.
+pub const SERVICE_NAME: &str = "grpc.reflection.v1.ServerReflection";
+
+/// The path of the bidirectional streaming method used for server reflection requests.
+pub const SERVER_REFLECTION_INFO_PATH: &str =
+    "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo";
+
+/// A connection to a server over which reflection requests can be sent.
+///
+/// This trait is implemented for closures, so a transport can be created from any function which sends
+/// a request to the server and waits for the corresponding response.
+pub trait ReflectionTransport {
+    /// The error returned if a request fails.
+    type Error: Error + Send + Sync + 'static;
+
+    /// Sends a request to the server and returns its response.
+    fn send(
+        &mut self,
+        request: ServerReflectionRequest,
+    ) -> Result<ServerReflectionResponse, Self::Error>;
+}
+
+impl<F, E> ReflectionTransport for F
+where
+    F: FnMut(ServerReflectionRequest) -> Result<ServerReflectionResponse, E>,
+    E: Error + Send + Sync + 'static,
+{
+    type Error = E;
+
+    fn send(
+        &mut self,
+        request: ServerReflectionRequest,
+    ) -> Result<ServerReflectionResponse, Self::Error> {
+        self(request)
+    }
+}
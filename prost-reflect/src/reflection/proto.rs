@@ -0,0 +1,127 @@
+//! Message types of the `grpc.reflection.v1` protocol.
+
+// The methods generated by `prost::Oneof` are undocumented.
+#![allow(missing_docs)]
+
+/// The message sent by the client when calling the `ServerReflectionInfo` method.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ServerReflectionRequest {
+    /// The host of the server, which may be used by servers hosting multiple services.
+    #[prost(string, tag = "1")]
+    pub host: String,
+    /// The type of request.
+    #[prost(oneof = "MessageRequest", tags = "3, 4, 5, 6, 7")]
+    pub message_request: Option<MessageRequest>,
+}
+
+/// The type of a [`ServerReflectionRequest`].
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum MessageRequest {
+    /// Requests a file by its name.
+    #[prost(string, tag = "3")]
+    FileByFilename(String),
+    /// Requests the file defining a symbol, such as a service, method or message.
+    #[prost(string, tag = "4")]
+    FileContainingSymbol(String),
+    /// Requests the file defining an extension.
+    #[prost(message, tag = "5")]
+    FileContainingExtension(ExtensionRequest),
+    /// Requests the field numbers of all extensions of a message.
+    #[prost(string, tag = "6")]
+    AllExtensionNumbersOfType(String),
+    /// Requests the names of all services exposed by the server. The content is ignored.
+    #[prost(string, tag = "7")]
+    ListServices(String),
+}
+
+/// Identifies an extension by the message it extends and its field number.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExtensionRequest {
+    /// The full name of the extended message.
+    #[prost(string, tag = "1")]
+    pub containing_type: String,
+    /// The field number of the extension.
+    #[prost(int32, tag = "2")]
+    pub extension_number: i32,
+}
+
+/// The message sent by the server in response to a [`ServerReflectionRequest`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ServerReflectionResponse {
+    /// The host of the server.
+    #[prost(string, tag = "1")]
+    pub valid_host: String,
+    /// The request this is a response to.
+    #[prost(message, optional, tag = "2")]
+    pub original_request: Option<ServerReflectionRequest>,
+    /// The type of response.
+    #[prost(oneof = "MessageResponse", tags = "4, 5, 6, 7")]
+    pub message_response: Option<MessageResponse>,
+}
+
+/// The type of a [`ServerReflectionResponse`].
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum MessageResponse {
+    /// The response to a [`MessageRequest::FileByFilename`], [`MessageRequest::FileContainingSymbol`] or
+    /// [`MessageRequest::FileContainingExtension`] request.
+    #[prost(message, tag = "4")]
+    FileDescriptorResponse(FileDescriptorResponse),
+    /// The response to a [`MessageRequest::AllExtensionNumbersOfType`] request.
+    #[prost(message, tag = "5")]
+    AllExtensionNumbersResponse(ExtensionNumberResponse),
+    /// The response to a [`MessageRequest::ListServices`] request.
+    #[prost(message, tag = "6")]
+    ListServicesResponse(ListServiceResponse),
+    /// Returned if the request failed.
+    #[prost(message, tag = "7")]
+    ErrorResponse(ErrorResponse),
+}
+
+/// A set of serialized `google.protobuf.FileDescriptorProto` messages.
+///
+/// The server may include some or all of the dependencies of the requested file, and may omit files
+/// it has already sent earlier on the same stream.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FileDescriptorResponse {
+    /// The serialized file descriptors.
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub file_descriptor_proto: Vec<Vec<u8>>,
+}
+
+/// The field numbers of all extensions of a message known to the server.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExtensionNumberResponse {
+    /// The full name of the extended message.
+    #[prost(string, tag = "1")]
+    pub base_type_name: String,
+    /// The field numbers of the extensions.
+    #[prost(int32, repeated, tag = "2")]
+    pub extension_number: Vec<i32>,
+}
+
+/// The services exposed by the server.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListServiceResponse {
+    /// The services exposed by the server.
+    #[prost(message, repeated, tag = "1")]
+    pub service: Vec<ServiceResponse>,
+}
+
+/// A service exposed by the server.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ServiceResponse {
+    /// The full name of the service.
+    #[prost(string, tag = "1")]
+    pub name: String,
+}
+
+/// An error returned by the server.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ErrorResponse {
+    /// The gRPC status code of the error.
+    #[prost(int32, tag = "1")]
+    pub error_code: i32,
+    /// A description of the error.
+    #[prost(string, tag = "2")]
+    pub error_message: String,
+}
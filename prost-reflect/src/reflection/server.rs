@@ -0,0 +1,214 @@
+use std::{collections::HashSet, convert::Infallible};
+
+use prost::Message;
+
+use crate::{DescriptorPool, FileDescriptor};
+
+use super::{
+    ErrorResponse, ExtensionNumberResponse, ExtensionRequest, FileDescriptorResponse,
+    ListServiceResponse, MessageRequest, MessageResponse, ReflectionTransport,
+    ServerReflectionRequest, ServerReflectionResponse, ServiceResponse,
+};
+
+const INVALID_ARGUMENT: i32 = 3;
+const NOT_FOUND: i32 = 5;
+
+/// An implementation of the gRPC server reflection service, which responds to requests using the files
+/// in a [`DescriptorPool`].
+///
+/// This type only produces responses to individual requests. To expose it from a gRPC server, call
+/// [`handle`][ReflectionService::handle] for each message received on the
+/// [`SERVER_REFLECTION_INFO_PATH`][super::SERVER_REFLECTION_INFO_PATH] stream, and send back the response.
+///
+/// # Examples
+///
+/// ```
+/// # let pool = prost_reflect::DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// use prost_reflect::reflection::{ReflectionClient, ReflectionService};
+///
+/// let service = ReflectionService::new(pool);
+/// let mut client = ReflectionClient::new(service);
+/// client.load_all_services().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReflectionService {
+    pool: DescriptorPool,
+    services: Option<Vec<String>>,
+}
+
+impl ReflectionService {
+    /// Creates a new [`ReflectionService`] which describes the files in `pool`.
+    ///
+    /// By default, all services in the pool are listed.
+    pub fn new(pool: DescriptorPool) -> Self {
+        ReflectionService {
+            pool,
+            services: None,
+        }
+    }
+
+    /// Sets the full names of the services returned by a list services request.
+    ///
+    /// This is useful if the pool contains services which are not implemented by the server. Files
+    /// defining other services can still be requested by name or symbol.
+    pub fn services<I, S>(mut self, services: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.services = Some(services.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Gets a reference to the pool used to respond to requests.
+    pub fn pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    /// Responds to a single reflection request.
+    ///
+    /// File descriptor responses include the requested file and all of its transitive dependencies. If the
+    /// request cannot be satisfied, the response contains an error with the `NOT_FOUND` or
+    /// `INVALID_ARGUMENT` status code.
+    pub fn handle(&self, request: &ServerReflectionRequest) -> ServerReflectionResponse {
+        let message_response = match &request.message_request {
+            Some(MessageRequest::FileByFilename(name)) => self
+                .file_response(self.pool.get_file_by_name(name), || {
+                    format!("file '{}' not found", name)
+                }),
+            Some(MessageRequest::FileContainingSymbol(symbol)) => self
+                .file_response(self.find_symbol(symbol), || {
+                    format!("symbol '{}' not found", symbol)
+                }),
+            Some(MessageRequest::FileContainingExtension(ExtensionRequest {
+                containing_type,
+                extension_number,
+            })) => self.file_response(
+                self.pool
+                    .all_extensions()
+                    .find(|extension| {
+                        extension.containing_message().full_name() == containing_type
+                            && extension.number() as i32 == *extension_number
+                    })
+                    .map(|extension| extension.parent_file()),
+                || {
+                    format!(
+                        "extension {} of '{}' not found",
+                        extension_number, containing_type
+                    )
+                },
+            ),
+            Some(MessageRequest::AllExtensionNumbersOfType(name)) => {
+                match self.pool.get_message_by_name(name) {
+                    Some(message) => {
+                        MessageResponse::AllExtensionNumbersResponse(ExtensionNumberResponse {
+                            base_type_name: message.full_name().to_owned(),
+                            extension_number: self
+                                .pool
+                                .all_extensions()
+                                .filter(|extension| {
+                                    extension.containing_message().full_name() == name
+                                })
+                                .map(|extension| extension.number() as i32)
+                                .collect(),
+                        })
+                    }
+                    None => error_response(NOT_FOUND, format!("message '{}' not found", name)),
+                }
+            }
+            Some(MessageRequest::ListServices(_)) => {
+                let service = match &self.services {
+                    Some(services) => services
+                        .iter()
+                        .map(|name| ServiceResponse { name: name.clone() })
+                        .collect(),
+                    None => self
+                        .pool
+                        .services()
+                        .map(|service| ServiceResponse {
+                            name: service.full_name().to_owned(),
+                        })
+                        .collect(),
+                };
+                MessageResponse::ListServicesResponse(ListServiceResponse { service })
+            }
+            None => error_response(INVALID_ARGUMENT, "empty request".to_owned()),
+        };
+
+        ServerReflectionResponse {
+            valid_host: request.host.clone(),
+            original_request: Some(request.clone()),
+            message_response: Some(message_response),
+        }
+    }
+
+    /// Finds the file defining a service, method, message, enum or extension.
+    fn find_symbol(&self, symbol: &str) -> Option<FileDescriptor> {
+        if let Some(message) = self.pool.get_message_by_name(symbol) {
+            return Some(message.parent_file());
+        }
+        if let Some(enum_) = self.pool.get_enum_by_name(symbol) {
+            return Some(enum_.parent_file());
+        }
+        if let Some(service) = self.pool.get_service_by_name(symbol) {
+            return Some(service.parent_file());
+        }
+        if let Some((service_name, method_name)) = symbol.rsplit_once('.') {
+            if let Some(service) = self.pool.get_service_by_name(service_name) {
+                if service.methods().any(|method| method.name() == method_name) {
+                    return Some(service.parent_file());
+                }
+            }
+        }
+        self.pool
+            .get_extension_by_name(symbol)
+            .map(|extension| extension.parent_file())
+    }
+
+    fn file_response(
+        &self,
+        file: Option<FileDescriptor>,
+        not_found: impl FnOnce() -> String,
+    ) -> MessageResponse {
+        let file = match file {
+            Some(file) => file,
+            None => return error_response(NOT_FOUND, not_found()),
+        };
+
+        let mut seen = HashSet::new();
+        let mut stack = vec![file.name().to_owned()];
+        let mut file_descriptor_proto = Vec::new();
+        while let Some(name) = stack.pop() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            if let Some(file) = self.pool.get_file_by_name(&name) {
+                let raw = file.file_descriptor_proto();
+                file_descriptor_proto.push(raw.encode_to_vec());
+                stack.extend(raw.dependency.iter().cloned());
+            }
+        }
+
+        MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+            file_descriptor_proto,
+        })
+    }
+}
+
+impl ReflectionTransport for ReflectionService {
+    type Error = Infallible;
+
+    fn send(
+        &mut self,
+        request: ServerReflectionRequest,
+    ) -> Result<ServerReflectionResponse, Self::Error> {
+        Ok(self.handle(&request))
+    }
+}
+
+fn error_response(error_code: i32, error_message: String) -> MessageResponse {
+    MessageResponse::ErrorResponse(ErrorResponse {
+        error_code,
+        error_message,
+    })
+}
@@ -0,0 +1,143 @@
+//! A process-wide registry of descriptors which can be shared between crates.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError, RwLock},
+};
+
+use once_cell::sync::Lazy;
+use prost_types::FileDescriptorProto;
+
+use crate::{DescriptorError, DescriptorPool, EnumDescriptor, MergeOptions, MessageDescriptor};
+
+static GLOBAL: Lazy<DescriptorRegistry> = Lazy::new(DescriptorRegistry::new);
+static NAMED: Lazy<Mutex<HashMap<String, &'static DescriptorRegistry>>> =
+    Lazy::new(Default::default);
+
+/// A [`DescriptorPool`] which can be shared between threads, and which any number of crates can add files to.
+///
+/// The [`global`][Self::global] registry is available to all crates in a process without needing to pass
+/// a pool between them. Separate registries can be created with [`new`][Self::new], or looked up by name
+/// using [`named`][Self::named], for example to keep the descriptors contributed by plugins separate from
+/// those of the host application.
+///
+/// Files added to a registry cannot be removed. A file which is identical to a file already in the
+/// registry is ignored, so several crates may register the same dependencies, such as the well-known
+/// types. Registering a different file with the same name, or a file defining a type which is already
+/// defined in another file, returns an error and leaves the registry unchanged.
+///
+/// Since [`DescriptorRegistry`] provides a [`get_message_by_name`][Self::get_message_by_name] method, it
+/// can be used as the `descriptor_pool` of the [`ReflectMessage`](crate::ReflectMessage) derive macro, for
+/// example `#[prost_reflect(descriptor_pool = "prost_reflect::DescriptorRegistry::global()", message_name = "package.MyMessage")]`.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorRegistry, FileDescriptorBuilder, MessageBuilder};
+/// let pool = FileDescriptorBuilder::new("my_crate.proto")
+///     .package("my_crate")
+///     .add_message(MessageBuilder::new("Event"))
+///     .build()
+///     .unwrap();
+/// DescriptorRegistry::global().register_pool(&pool).unwrap();
+///
+/// // Elsewhere, in another crate.
+/// let message = DescriptorRegistry::global().get_message_by_name("my_crate.Event").unwrap();
+/// assert_eq!(message.full_name(), "my_crate.Event");
+/// ```
+#[derive(Debug, Default)]
+#[cfg_attr(docsrs, doc(cfg(feature = "registry")))]
+pub struct DescriptorRegistry {
+    pool: RwLock<DescriptorPool>,
+}
+
+impl DescriptorRegistry {
+    /// Creates a new, empty [`DescriptorRegistry`].
+    pub fn new() -> Self {
+        DescriptorRegistry::default()
+    }
+
+    /// Gets the global registry, which is shared by all crates in the process.
+    pub fn global() -> &'static DescriptorRegistry {
+        &GLOBAL
+    }
+
+    /// Gets the registry with the given name, creating it if it does not exist yet.
+    ///
+    /// Every call with the same name returns the same registry. Named registries are never freed, so the
+    /// number of distinct names used should be bounded.
+    pub fn named(name: &str) -> &'static DescriptorRegistry {
+        let mut named = NAMED.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(registry) = named.get(name) {
+            return registry;
+        }
+
+        let registry: &'static DescriptorRegistry = Box::leak(Box::new(DescriptorRegistry::new()));
+        named.insert(name.to_owned(), registry);
+        registry
+    }
+
+    /// Adds all the files in `pool` to this registry.
+    ///
+    /// Files which are identical to a file already in the registry are ignored. If any other file or
+    /// definition in `pool` conflicts with one in the registry, an error is returned, with the conflicts
+    /// available from [`DescriptorError::merge_conflicts`], and the registry is not modified.
+    pub fn register_pool(&self, pool: &DescriptorPool) -> Result<(), DescriptorError> {
+        self.update(|registered| {
+            registered
+                .merge_with_options(pool, &MergeOptions::new().allow_identical_duplicates(true))
+        })
+    }
+
+    /// Decodes a [`FileDescriptorSet`](prost_types::FileDescriptorSet) from its protobuf byte
+    /// representation and adds its files to this registry.
+    ///
+    /// This is typically used with the output of the protobuf compiler included in a crate with
+    /// [`include_bytes!`]. The files are added as for [`register_pool`][Self::register_pool].
+    pub fn register_file_descriptor_set_bytes(&self, bytes: &[u8]) -> Result<(), DescriptorError> {
+        self.register_pool(&DescriptorPool::decode(bytes)?)
+    }
+
+    /// Adds a collection of file descriptors to this registry.
+    ///
+    /// As for [`DescriptorPool::add_file_descriptor_protos`], the files may be provided in any order, but
+    /// all types referenced must be defined either in one of the files provided or in a file already in
+    /// the registry. Files which are identical to a file already in the registry are ignored.
+    pub fn register_file_descriptor_protos<I>(&self, files: I) -> Result<(), DescriptorError>
+    where
+        I: IntoIterator<Item = FileDescriptorProto>,
+    {
+        self.update(|registered| registered.add_file_descriptor_protos(files))
+    }
+
+    /// Gets a snapshot of the files registered so far.
+    ///
+    /// The returned pool is not affected by files registered later. This is cheap, since pools are
+    /// reference counted.
+    pub fn pool(&self) -> DescriptorPool {
+        self.pool
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Gets a [`MessageDescriptor`] by its fully qualified name from the files registered so far.
+    pub fn get_message_by_name(&self, name: &str) -> Option<MessageDescriptor> {
+        self.pool().get_message_by_name(name)
+    }
+
+    /// Gets an [`EnumDescriptor`] by its fully qualified name from the files registered so far.
+    pub fn get_enum_by_name(&self, name: &str) -> Option<EnumDescriptor> {
+        self.pool().get_enum_by_name(name)
+    }
+
+    fn update(
+        &self,
+        f: impl FnOnce(&mut DescriptorPool) -> Result<(), DescriptorError>,
+    ) -> Result<(), DescriptorError> {
+        // Pools are left unchanged when adding files fails, so the lock may safely be used even if a
+        // previous registration panicked.
+        let mut pool = self.pool.write().unwrap_or_else(PoisonError::into_inner);
+        f(&mut pool)
+    }
+}
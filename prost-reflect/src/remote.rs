@@ -0,0 +1,307 @@
+//! Fetching file descriptor sets over HTTP, with caching.
+//!
+//! A [`RemoteDescriptorSet`] downloads a serialized `google.protobuf.FileDescriptorSet` from a URL, such
+//! as a build artifact published by a schema repository, and can be refreshed periodically to pick up
+//! new versions. Requests are conditional on the `ETag` and `Last-Modified` headers returned by the server, so
+//! refreshing an unchanged descriptor set is cheap. If a cache directory is configured, the last downloaded
+//! descriptor set is also stored on disk, so it is available on startup even if the server is unreachable.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use prost_reflect::remote::RemoteDescriptorSet;
+//!
+//! let mut remote = RemoteDescriptorSet::new("https://schemas.example.com/descriptors.bin")
+//!     .cache_dir("/var/cache/schemas")
+//!     .min_refresh_interval(Duration::from_secs(60));
+//! let pool = remote.load().unwrap();
+//!
+//! // Later, for example on a timer:
+//! if remote.refresh().unwrap() {
+//!     let new_pool = remote.pool().unwrap();
+//! }
+//! ```
+
+use std::{
+    error::Error,
+    fmt, fs, io,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{DescriptorError, DescriptorPool};
+
+/// A file descriptor set fetched from a URL, which can be refreshed to pick up changes.
+#[derive(Debug, Clone)]
+pub struct RemoteDescriptorSet {
+    url: String,
+    agent: ureq::Agent,
+    headers: Vec<(String, String)>,
+    cache_dir: Option<PathBuf>,
+    min_refresh_interval: Duration,
+    state: Option<State>,
+    last_checked: Option<Instant>,
+    cache_error: Option<Arc<io::Error>>,
+}
+
+/// An error that may occur while fetching a file descriptor set.
+#[derive(Debug)]
+pub struct FetchError {
+    kind: FetchErrorKind,
+}
+
+#[derive(Debug)]
+enum FetchErrorKind {
+    Io(io::Error),
+    Transport(Box<ureq::Transport>),
+    Status { status: u16 },
+    Descriptor(DescriptorError),
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    pool: DescriptorPool,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl RemoteDescriptorSet {
+    /// Creates a new [`RemoteDescriptorSet`] which fetches from `url`. No request is made until
+    /// [`load`][Self::load] or [`refresh`][Self::refresh] is called.
+    pub fn new(url: impl Into<String>) -> Self {
+        RemoteDescriptorSet {
+            url: url.into(),
+            agent: ureq::Agent::new(),
+            headers: Vec::new(),
+            cache_dir: None,
+            min_refresh_interval: Duration::from_secs(0),
+            state: None,
+            last_checked: None,
+            cache_error: None,
+        }
+    }
+
+    /// Adds a header to send with every request, for example to authenticate with the server.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets a directory in which to cache the downloaded descriptor set. The directory is created if it
+    /// does not exist.
+    ///
+    /// When a cache directory is set, [`load`][Self::load] uses the cached descriptor set if the server
+    /// reports it is unchanged, or if the server cannot be reached.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the minimum time between requests to the server. Calls to [`refresh`][Self::refresh] within this
+    /// interval of the last request return immediately without checking for changes.
+    ///
+    /// The default value is zero, so every call makes a request.
+    pub fn min_refresh_interval(mut self, interval: Duration) -> Self {
+        self.min_refresh_interval = interval;
+        self
+    }
+
+    /// Gets the current descriptor pool, or `None` if it has not been loaded yet.
+    pub fn pool(&self) -> Option<&DescriptorPool> {
+        self.state.as_ref().map(|state| &state.pool)
+    }
+
+    /// Gets the error from the last attempt to write the downloaded descriptor set to the
+    /// [cache directory][Self::cache_dir], or `None` if it succeeded.
+    ///
+    /// Failing to write the cache does not cause [`load`][Self::load] or [`refresh`][Self::refresh] to fail,
+    /// since the new descriptor set is still available from [`pool`][Self::pool].
+    pub fn cache_error(&self) -> Option<&io::Error> {
+        self.cache_error.as_deref()
+    }
+
+    /// Gets the descriptor pool, fetching it if it has not been loaded yet.
+    ///
+    /// On the first call, if a cache directory is set and contains a cached copy, a conditional request is made
+    /// so the descriptor set is only downloaded if it has changed. If the request fails due to a network error,
+    /// the cached copy is used instead.
+    pub fn load(&mut self) -> Result<DescriptorPool, FetchError> {
+        if let Some(state) = &self.state {
+            return Ok(state.pool.clone());
+        }
+
+        if let Some(cached) = self.read_cache() {
+            self.state = Some(cached);
+            // Fall back to the cached copy if the server is unreachable.
+            if let Err(err) = self.fetch() {
+                if !matches!(err.kind, FetchErrorKind::Transport(_)) {
+                    return Err(err);
+                }
+            }
+        } else {
+            self.fetch()?;
+        }
+
+        Ok(self.pool().expect("pool not loaded").clone())
+    }
+
+    /// Checks the server for a new version of the descriptor set, returning `true` if it changed.
+    ///
+    /// If the last request was made within the [minimum refresh interval][Self::min_refresh_interval], this
+    /// returns `false` without making a request. If the request fails, the current descriptor pool is kept.
+    pub fn refresh(&mut self) -> Result<bool, FetchError> {
+        if let Some(last_checked) = self.last_checked {
+            if last_checked.elapsed() < self.min_refresh_interval {
+                return Ok(false);
+            }
+        }
+
+        self.fetch()
+    }
+
+    /// Makes a conditional request for the descriptor set, updating the state and cache if it changed.
+    fn fetch(&mut self) -> Result<bool, FetchError> {
+        self.last_checked = Some(Instant::now());
+
+        let mut request = self.agent.get(&self.url);
+        for (name, value) in &self.headers {
+            request = request.set(name, value);
+        }
+        if let Some(state) = &self.state {
+            if let Some(etag) = &state.etag {
+                request = request.set("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &state.last_modified {
+                request = request.set("If-Modified-Since", last_modified);
+            }
+        }
+
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, _)) => {
+                return Err(FetchErrorKind::Status { status }.into())
+            }
+            Err(ureq::Error::Transport(err)) => {
+                return Err(FetchErrorKind::Transport(Box::new(err)).into())
+            }
+        };
+        if response.status() == 304 && self.state.is_some() {
+            return Ok(false);
+        }
+
+        let etag = response.header("ETag").map(ToOwned::to_owned);
+        let last_modified = response.header("Last-Modified").map(ToOwned::to_owned);
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut bytes)
+            .map_err(FetchErrorKind::Io)?;
+
+        let pool = DescriptorPool::decode(bytes.as_slice()).map_err(FetchErrorKind::Descriptor)?;
+        let state = State {
+            pool,
+            etag,
+            last_modified,
+        };
+        self.cache_error = self.write_cache(&state, &bytes).err().map(Arc::new);
+        self.state = Some(state);
+        Ok(true)
+    }
+
+    fn cache_paths(&self) -> Option<(PathBuf, PathBuf)> {
+        let dir = self.cache_dir.as_ref()?;
+        let name = format!("{:016x}", fnv1a(self.url.as_bytes()));
+        Some((
+            dir.join(format!("{}.bin", name)),
+            dir.join(format!("{}.meta", name)),
+        ))
+    }
+
+    fn read_cache(&self) -> Option<State> {
+        let (data_path, meta_path) = self.cache_paths()?;
+        let bytes = fs::read(data_path).ok()?;
+        let meta = fs::read_to_string(meta_path).ok()?;
+
+        let mut etag = None;
+        let mut last_modified = None;
+        for line in meta.lines() {
+            if let Some(value) = line.strip_prefix("etag: ") {
+                etag = Some(value.to_owned());
+            } else if let Some(value) = line.strip_prefix("last-modified: ") {
+                last_modified = Some(value.to_owned());
+            }
+        }
+
+        // Ignore a corrupt cache, it will be replaced by the next successful request.
+        let pool = DescriptorPool::decode(bytes.as_slice()).ok()?;
+        Some(State {
+            pool,
+            etag,
+            last_modified,
+        })
+    }
+
+    fn write_cache(&self, state: &State, bytes: &[u8]) -> io::Result<()> {
+        let (data_path, meta_path) = match self.cache_paths() {
+            Some(paths) => paths,
+            None => return Ok(()),
+        };
+
+        let mut meta = String::new();
+        if let Some(etag) = &state.etag {
+            meta.push_str(&format!("etag: {}\n", etag));
+        }
+        if let Some(last_modified) = &state.last_modified {
+            meta.push_str(&format!("last-modified: {}\n", last_modified));
+        }
+
+        fs::create_dir_all(data_path.parent().unwrap_or_else(|| Path::new(".")))?;
+        write_atomic(&data_path, bytes)?;
+        write_atomic(&meta_path, meta.as_bytes())
+    }
+}
+
+/// Writes a file by renaming a temporary file, so readers never observe a partially written file.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(temp_path, path)
+}
+
+/// The 64-bit FNV-1a hash, used to derive stable cache file names from URLs.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            FetchErrorKind::Io(err) => write!(f, "failed to read descriptor set: {}", err),
+            FetchErrorKind::Transport(err) => write!(f, "failed to connect to server: {}", err),
+            FetchErrorKind::Status { status } => write!(f, "server returned status {}", status),
+            FetchErrorKind::Descriptor(err) => write!(f, "invalid descriptor set: {}", err),
+        }
+    }
+}
+
+impl Error for FetchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            FetchErrorKind::Io(err) => Some(err),
+            FetchErrorKind::Transport(err) => Some(err),
+            FetchErrorKind::Status { .. } => None,
+            FetchErrorKind::Descriptor(err) => Some(err),
+        }
+    }
+}
+
+impl From<FetchErrorKind> for FetchError {
+    fn from(kind: FetchErrorKind) -> Self {
+        FetchError { kind }
+    }
+}
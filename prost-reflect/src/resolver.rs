@@ -0,0 +1,392 @@
+//! Resolving message types from schema registries.
+//!
+//! Messages are often sent with an identifier for their type instead of the schema itself, for example the
+//! type URL of a `google.protobuf.Any`, or the schema ID prefixed to records by Confluent Schema Registry.
+//! The [`SchemaResolver`] trait abstracts over where these identifiers are looked up, so the same code can
+//! work with a local [`DescriptorPool`], a descriptor set fetched over HTTP, or any other registry.
+//!
+//! # Examples
+//!
+//! Implementing a resolver for a registry which uses its own type URL prefix:
+//!
+//! ```
+//! # use prost_reflect::{DescriptorPool, MessageDescriptor};
+//! use prost_reflect::resolver::{ResolveError, SchemaResolver};
+//!
+//! #[derive(Debug)]
+//! struct MyRegistry {
+//!     pool: DescriptorPool,
+//! }
+//!
+//! impl SchemaResolver for MyRegistry {
+//!     fn resolve_type_url(&self, type_url: &str) -> Result<Option<MessageDescriptor>, ResolveError> {
+//!         match type_url.strip_prefix("registry.example.com/") {
+//!             Some(name) => Ok(self.pool.get_message_by_name(name)),
+//!             None => Ok(None),
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Restricting which types may be unpacked from `google.protobuf.Any` messages with a [`TypeUrlPolicy`]:
+//!
+//! ```
+//! # use prost_reflect::DescriptorPool;
+//! # let pool = DescriptorPool::decode(include_bytes!("file_descriptor_set.bin").as_ref()).unwrap();
+//! use prost_reflect::resolver::{SchemaResolver, TypeUrlPolicy};
+//!
+//! let policy = TypeUrlPolicy::new()
+//!     .resolver("local", pool)
+//!     .load(
+//!         "# Only allow types from our own package
+//!          map type.googleapis.com/ local
+//!          allow package.*",
+//!     )
+//!     .unwrap();
+//!
+//! assert!(policy.resolve_type_url("type.googleapis.com/package.MyMessage").is_ok());
+//! assert!(policy.resolve_type_url("type.googleapis.com/google.protobuf.Empty").is_err());
+//! assert!(policy.resolve_type_url("example.com/package.MyMessage").is_err());
+//! ```
+
+use std::{collections::HashMap, error::Error, fmt, sync::Arc};
+
+use prost::Message;
+
+use crate::{DescriptorPool, DynamicMessage, MessageDescriptor};
+
+/// A source of message types, such as a schema registry.
+///
+/// Each method returns `Ok(None)` if the type is not known to the resolver, and an error if the lookup
+/// itself failed, for example because the registry could not be reached. All methods have default
+/// implementations which return `Ok(None)`, so implementations only need to provide the lookups supported
+/// by their backend.
+pub trait SchemaResolver: fmt::Debug + Send + Sync {
+    /// Looks up a message type by the type URL used in `google.protobuf.Any`, for example
+    /// `type.googleapis.com/google.protobuf.Duration`.
+    fn resolve_type_url(&self, type_url: &str) -> Result<Option<MessageDescriptor>, ResolveError> {
+        let _ = type_url;
+        Ok(None)
+    }
+
+    /// Looks up a message type by a numeric schema ID, such as the ID assigned by Confluent Schema Registry.
+    fn resolve_id(&self, id: u32) -> Result<Option<MessageDescriptor>, ResolveError> {
+        let _ = id;
+        Ok(None)
+    }
+
+    /// Looks up a message type by subject name and version. If `version` is `None`, the latest version of
+    /// the subject is used.
+    fn resolve_subject(
+        &self,
+        subject: &str,
+        version: Option<u32>,
+    ) -> Result<Option<MessageDescriptor>, ResolveError> {
+        let _ = (subject, version);
+        Ok(None)
+    }
+}
+
+/// An error returned by a [`SchemaResolver`] when a lookup fails.
+#[derive(Debug)]
+pub struct ResolveError {
+    inner: Box<dyn Error + Send + Sync>,
+}
+
+impl ResolveError {
+    /// Creates a new [`ResolveError`] from an error or message.
+    pub fn new(err: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        ResolveError { inner: err.into() }
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl Error for ResolveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+/// Resolves type URLs against the messages in the pool.
+///
+/// Any type URL prefix is accepted, and the message name is taken from the text after the last `/`.
+impl SchemaResolver for DescriptorPool {
+    fn resolve_type_url(&self, type_url: &str) -> Result<Option<MessageDescriptor>, ResolveError> {
+        match type_url.rsplit_once('/') {
+            Some((_, name)) => Ok(self.get_message_by_name(name)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Resolves type URLs against the files registered so far.
+#[cfg(feature = "registry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "registry")))]
+impl SchemaResolver for crate::DescriptorRegistry {
+    fn resolve_type_url(&self, type_url: &str) -> Result<Option<MessageDescriptor>, ResolveError> {
+        self.pool().resolve_type_url(type_url)
+    }
+}
+
+/// Resolves type URLs against the most recently loaded descriptor pool. If the pool has not been loaded
+/// yet, no types are resolved.
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+impl SchemaResolver for crate::remote::RemoteDescriptorSet {
+    fn resolve_type_url(&self, type_url: &str) -> Result<Option<MessageDescriptor>, ResolveError> {
+        match self.pool() {
+            Some(pool) => pool.resolve_type_url(type_url),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Creates a [`SchemaResolver`] which resolves type URLs by calling `f`.
+///
+/// This is a convenient way to supply a lookup function, for example to the `resolver` option when
+/// serializing and deserializing messages as JSON, without defining a new type.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::DescriptorPool;
+/// # let pool = DescriptorPool::decode(include_bytes!("file_descriptor_set.bin").as_ref()).unwrap();
+/// use prost_reflect::resolver::{self, SchemaResolver};
+///
+/// let resolver = resolver::from_fn(move |type_url| {
+///     Ok(type_url
+///         .strip_prefix("registry.example.com/")
+///         .and_then(|name| pool.get_message_by_name(name)))
+/// });
+///
+/// assert!(resolver.resolve_type_url("registry.example.com/package.MyMessage").unwrap().is_some());
+/// assert!(resolver.resolve_type_url("type.googleapis.com/package.MyMessage").unwrap().is_none());
+/// ```
+pub fn from_fn<F>(f: F) -> FromFn<F>
+where
+    F: Fn(&str) -> Result<Option<MessageDescriptor>, ResolveError> + Send + Sync,
+{
+    FromFn(f)
+}
+
+/// A [`SchemaResolver`] which resolves type URLs with a function.
+///
+/// This type is created by [`from_fn`].
+#[derive(Clone)]
+pub struct FromFn<F>(F);
+
+impl<F> SchemaResolver for FromFn<F>
+where
+    F: Fn(&str) -> Result<Option<MessageDescriptor>, ResolveError> + Send + Sync,
+{
+    fn resolve_type_url(&self, type_url: &str) -> Result<Option<MessageDescriptor>, ResolveError> {
+        (self.0)(type_url)
+    }
+}
+
+impl<F> fmt::Debug for FromFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromFn").finish_non_exhaustive()
+    }
+}
+
+/// Decodes the payload of a `google.protobuf.Any` message, using `resolver` to look up its type.
+///
+/// Returns an error if the type URL is not known to the resolver, or the payload is not a valid
+/// message of that type.
+pub fn unpack_any(
+    any: &prost_types::Any,
+    resolver: &dyn SchemaResolver,
+) -> Result<DynamicMessage, ResolveError> {
+    let desc = resolver.resolve_type_url(&any.type_url)?.ok_or_else(|| {
+        ResolveError::new(format!("message type for '{}' not found", any.type_url))
+    })?;
+    let mut message = DynamicMessage::new(desc);
+    message
+        .merge(any.value.as_ref())
+        .map_err(ResolveError::new)?;
+    Ok(message)
+}
+
+/// A [`SchemaResolver`] which restricts the type URLs that may be resolved, and chooses a resolver for each
+/// URL based on its prefix.
+///
+/// A policy can be configured with builder methods, or loaded from a configuration file with
+/// [`load`][Self::load]. It can then be used wherever type URLs are resolved, for example with
+/// [`unpack_any`], or the `resolver` option when serializing and deserializing messages as JSON.
+///
+/// Every type URL resolved by the policy must match a [mapped prefix][Self::map], and the message name following
+/// the prefix must be allowed by the [allow][Self::allow] and [deny][Self::deny] lists. The name may not contain
+/// a `/`, and the full name of the message type returned by the resolver is checked against the lists as well.
+/// Otherwise, an error is returned, so that resolution does not fall back to another source of types.
+#[derive(Debug, Clone, Default)]
+pub struct TypeUrlPolicy {
+    resolvers: HashMap<String, Arc<dyn SchemaResolver>>,
+    prefixes: Vec<(String, String)>,
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+/// An error that may occur while loading a [`TypeUrlPolicy`] configuration.
+#[derive(Debug)]
+pub struct PolicyError {
+    line: usize,
+    message: String,
+}
+
+impl TypeUrlPolicy {
+    /// Creates a new, empty [`TypeUrlPolicy`], which rejects all type URLs.
+    pub fn new() -> Self {
+        TypeUrlPolicy::default()
+    }
+
+    /// Registers a resolver under the given name, so it can be referred to by [`map`][Self::map] or
+    /// in a configuration file.
+    pub fn resolver(
+        mut self,
+        name: impl Into<String>,
+        resolver: impl SchemaResolver + 'static,
+    ) -> Self {
+        self.resolvers.insert(name.into(), Arc::new(resolver));
+        self
+    }
+
+    /// Resolves type URLs starting with `prefix` using the resolver registered as `resolver`.
+    ///
+    /// If several prefixes match a type URL, the longest is used.
+    pub fn map(mut self, prefix: impl Into<String>, resolver: impl Into<String>) -> Self {
+        self.prefixes.push((prefix.into(), resolver.into()));
+        self
+    }
+
+    /// Allows message names matching `pattern`. If no patterns are allowed, all message names are allowed
+    /// unless denied.
+    ///
+    /// A pattern is either a full message name such as `my.package.MyMessage`, a package name followed by `.*`
+    /// such as `my.package.*` to match all messages within that package and its sub-packages, or `*` to
+    /// match every message.
+    pub fn allow(mut self, pattern: impl Into<String>) -> Self {
+        self.allow.push(pattern.into());
+        self
+    }
+
+    /// Denies message names matching `pattern`, even if they are also allowed. See [`allow`][Self::allow]
+    /// for the pattern syntax.
+    pub fn deny(mut self, pattern: impl Into<String>) -> Self {
+        self.deny.push(pattern.into());
+        self
+    }
+
+    /// Applies the rules in a configuration file to this policy.
+    ///
+    /// The configuration contains one rule per line, in one of the following forms:
+    ///
+    /// - `map <prefix> <resolver>`, equivalent to [`map`][Self::map]. The resolver must already be registered
+    ///   with [`resolver`][Self::resolver].
+    /// - `allow <pattern>`, equivalent to [`allow`][Self::allow].
+    /// - `deny <pattern>`, equivalent to [`deny`][Self::deny].
+    ///
+    /// Blank lines, and lines starting with `#`, are ignored.
+    pub fn load(mut self, config: &str) -> Result<Self, PolicyError> {
+        for (index, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| PolicyError {
+                line: index + 1,
+                message,
+            };
+            let args: Vec<&str> = line.split_whitespace().collect();
+            self = match args.as_slice() {
+                ["map", prefix, resolver] => {
+                    if !self.resolvers.contains_key(*resolver) {
+                        return Err(error(format!("resolver '{}' is not registered", resolver)));
+                    }
+                    self.map(*prefix, *resolver)
+                }
+                ["allow", pattern] => self.allow(*pattern),
+                ["deny", pattern] => self.deny(*pattern),
+                [rule, ..] if matches!(*rule, "map" | "allow" | "deny") => {
+                    return Err(error(format!("wrong number of arguments for '{}'", rule)))
+                }
+                [rule, ..] => return Err(error(format!("unknown rule '{}'", rule))),
+                [] => unreachable!(),
+            };
+        }
+        Ok(self)
+    }
+}
+
+impl SchemaResolver for TypeUrlPolicy {
+    fn resolve_type_url(&self, type_url: &str) -> Result<Option<MessageDescriptor>, ResolveError> {
+        let (prefix, resolver_name) = self
+            .prefixes
+            .iter()
+            .filter(|(prefix, _)| type_url.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .ok_or_else(|| {
+                ResolveError::new(format!("type url '{}' is not permitted", type_url))
+            })?;
+
+        // The message name must be the whole of the rest of the URL. Otherwise, a resolver which uses the
+        // text after the last `/` could return a type other than the one that was checked.
+        let name = &type_url[prefix.len()..];
+        if name.contains('/') {
+            return Err(ResolveError::new(format!(
+                "type url '{}' is not permitted",
+                type_url
+            )));
+        }
+        self.check_name(name)?;
+
+        let resolver = self.resolvers.get(resolver_name).ok_or_else(|| {
+            ResolveError::new(format!("resolver '{}' is not registered", resolver_name))
+        })?;
+        match resolver.resolve_type_url(type_url)? {
+            Some(desc) => {
+                self.check_name(desc.full_name())?;
+                Ok(Some(desc))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl TypeUrlPolicy {
+    fn check_name(&self, name: &str) -> Result<(), ResolveError> {
+        let allowed = self.allow.is_empty() || self.allow.iter().any(|p| pattern_matches(p, name));
+        if !allowed || self.deny.iter().any(|p| pattern_matches(p, name)) {
+            return Err(ResolveError::new(format!(
+                "message type '{}' is not permitted",
+                name
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn pattern_matches(pattern: &str, name: &str) -> bool {
+    if pattern == "*" {
+        true
+    } else if let Some(package) = pattern.strip_suffix(".*") {
+        name.strip_prefix(package)
+            .map_or(false, |rest| rest.starts_with('.'))
+    } else {
+        pattern == name
+    }
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for PolicyError {}
@@ -0,0 +1,284 @@
+//! Helpers for testing code which produces or consumes protobuf messages.
+
+use std::{error::Error, fmt};
+
+use prost::{DecodeError, Message};
+use prost_types::FieldMask;
+
+#[cfg(feature = "text-format")]
+use crate::FormatOptions;
+#[cfg(feature = "serde")]
+use crate::{DeserializeOptions, SerializeOptions};
+use crate::{DynamicMessage, ReflectMessage};
+
+pub use crate::dynamic::{Difference, MessageDiff};
+
+/// Asserts that two messages are equal, as defined by [`DynamicMessage::diff`].
+///
+/// # Panics
+///
+/// Panics with a description of every difference if the messages are not equal.
+#[track_caller]
+pub fn assert_messages_eq(left: &DynamicMessage, right: &DynamicMessage) {
+    assert_messages_eq_ignoring(left, right, &FieldMask::default())
+}
+
+/// Asserts that two messages are equal, ignoring any fields matching a path in `ignore`.
+///
+/// This is useful for comparing messages containing volatile fields, such as timestamps or
+/// randomly generated IDs. See [`DynamicMessage::diff`] for details of how paths are matched.
+///
+/// # Panics
+///
+/// Panics with a description of every difference if the messages are not equal.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+/// # use prost_reflect::testing::assert_messages_eq_ignoring;
+/// # use prost_types::FieldMask;
+/// # let pool = DescriptorPool::decode(include_bytes!("file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let mut left = DynamicMessage::new(message_descriptor.clone());
+/// left.set_field_by_name("foo", Value::I32(1));
+/// let mut right = DynamicMessage::new(message_descriptor);
+/// right.set_field_by_name("foo", Value::I32(2));
+///
+/// assert_messages_eq_ignoring(&left, &right, &FieldMask { paths: vec!["foo".to_owned()] });
+/// ```
+#[track_caller]
+pub fn assert_messages_eq_ignoring(
+    left: &DynamicMessage,
+    right: &DynamicMessage,
+    ignore: &FieldMask,
+) {
+    let diff = left.diff(right, ignore);
+    if !diff.is_empty() {
+        if ignore.paths.is_empty() {
+            panic!("messages are not equal:\n{}", diff)
+        } else {
+            panic!(
+                "messages are not equal (ignoring {}):\n{}",
+                ignore.paths.join(", "),
+                diff
+            )
+        }
+    }
+}
+
+/// An error returned when a message does not survive a round-trip through an encoding unchanged.
+#[derive(Debug)]
+pub struct RoundTripError {
+    kind: RoundTripErrorKind,
+}
+
+#[derive(Debug)]
+enum RoundTripErrorKind {
+    Decode(DecodeError),
+    #[cfg(feature = "serde")]
+    Serialize(String),
+    #[cfg(feature = "serde")]
+    Deserialize(String),
+    #[cfg(feature = "text-format")]
+    TextFormatParse(crate::ParseError),
+    MessageMismatch {
+        format: &'static str,
+        diff: MessageDiff,
+    },
+    #[cfg(feature = "serde")]
+    JsonMismatch {
+        first: serde_value::Value,
+        second: serde_value::Value,
+    },
+    #[cfg(feature = "text-format")]
+    TextFormatMismatch {
+        first: String,
+        second: String,
+    },
+}
+
+/// Checks that `message` is unchanged after encoding it to the protobuf binary format and decoding it again,
+/// and that decoding and re-encoding the bytes is stable.
+///
+/// Messages are compared using [`DynamicMessage::diff`], so fields without presence which are set to their
+/// default value are treated the same as unset fields.
+pub fn check_binary_roundtrip(message: &DynamicMessage) -> Result<(), RoundTripError> {
+    let desc = message.descriptor();
+
+    let bytes = message.encode_to_vec();
+    let decoded = DynamicMessage::decode(desc.clone(), bytes.as_slice())
+        .map_err(RoundTripErrorKind::Decode)?;
+    check_diff("binary", message, &decoded)?;
+
+    let reencoded = decoded.encode_to_vec();
+    let redecoded =
+        DynamicMessage::decode(desc, reencoded.as_slice()).map_err(RoundTripErrorKind::Decode)?;
+    check_diff("binary", &decoded, &redecoded)
+}
+
+/// Checks that `message` is unchanged after serializing it with the [JSON mapping](https://developers.google.com/protocol-buffers/docs/proto3#json)
+/// and deserializing it again, and that serializing the deserialized message again produces identical JSON.
+///
+/// Note that the JSON mapping does not preserve unknown fields, so messages containing them will fail this check.
+///
+/// Messages are compared using [`DynamicMessage::diff`], so fields without presence which are set to their
+/// default value are treated the same as unset fields.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn check_json_roundtrip(
+    message: &DynamicMessage,
+    serialize_options: &SerializeOptions,
+    deserialize_options: &DeserializeOptions,
+) -> Result<(), RoundTripError> {
+    let first = to_json_value(message, serialize_options)?;
+    let deserialized = DynamicMessage::deserialize_with_options(
+        message.descriptor(),
+        first.clone(),
+        deserialize_options,
+    )
+    .map_err(|err| RoundTripErrorKind::Deserialize(err.to_string()))?;
+    check_diff("JSON", message, &deserialized)?;
+
+    let second = to_json_value(&deserialized, serialize_options)?;
+    if first != second {
+        return Err(RoundTripErrorKind::JsonMismatch { first, second }.into());
+    }
+    Ok(())
+}
+
+/// Checks that `message` is unchanged after formatting it with the [text format](https://developers.google.com/protocol-buffers/docs/text-format-spec)
+/// and parsing it again, and that formatting the parsed message again produces identical text.
+///
+/// Note that the text format does not preserve unknown fields, so messages containing them will fail this check.
+///
+/// Messages are compared using [`DynamicMessage::diff`], so fields without presence which are set to their
+/// default value are treated the same as unset fields.
+#[cfg(feature = "text-format")]
+#[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+pub fn check_text_format_roundtrip(message: &DynamicMessage) -> Result<(), RoundTripError> {
+    let options = FormatOptions::new();
+    let first = message.to_text_format_with_options(&options);
+    let parsed = DynamicMessage::parse_text_format(message.descriptor(), &first)
+        .map_err(RoundTripErrorKind::TextFormatParse)?;
+    check_diff("text format", message, &parsed)?;
+
+    let second = parsed.to_text_format_with_options(&options);
+    if first != second {
+        return Err(RoundTripErrorKind::TextFormatMismatch { first, second }.into());
+    }
+    Ok(())
+}
+
+/// Asserts that [`check_binary_roundtrip`] succeeds for `message`, that [`check_json_roundtrip`] succeeds
+/// with the default options if the `serde` feature is enabled, and that [`check_text_format_roundtrip`]
+/// succeeds if the `text-format` feature is enabled.
+///
+/// # Panics
+///
+/// Panics with a description of the mismatch if any round-trip check fails.
+#[track_caller]
+pub fn assert_roundtrip(message: &DynamicMessage) {
+    if let Err(err) = check_binary_roundtrip(message) {
+        panic!("{}", err);
+    }
+    #[cfg(feature = "serde")]
+    if let Err(err) = check_json_roundtrip(
+        message,
+        &SerializeOptions::new(),
+        &DeserializeOptions::new(),
+    ) {
+        panic!("{}", err);
+    }
+    #[cfg(feature = "text-format")]
+    if let Err(err) = check_text_format_roundtrip(message) {
+        panic!("{}", err);
+    }
+}
+
+#[cfg(feature = "serde")]
+fn to_json_value(
+    message: &DynamicMessage,
+    options: &SerializeOptions,
+) -> Result<serde_value::Value, RoundTripError> {
+    struct WithOptions<'a>(&'a DynamicMessage, &'a SerializeOptions);
+
+    impl<'a> serde::Serialize for WithOptions<'a> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            self.0.serialize_with_options(serializer, self.1)
+        }
+    }
+
+    serde_value::to_value(WithOptions(message, options))
+        .map_err(|err| RoundTripErrorKind::Serialize(err.to_string()).into())
+}
+
+fn check_diff(
+    format: &'static str,
+    expected: &DynamicMessage,
+    actual: &DynamicMessage,
+) -> Result<(), RoundTripError> {
+    let diff = expected.diff(actual, &FieldMask::default());
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(RoundTripErrorKind::MessageMismatch { format, diff }.into())
+    }
+}
+
+impl From<RoundTripErrorKind> for RoundTripError {
+    fn from(kind: RoundTripErrorKind) -> Self {
+        RoundTripError { kind }
+    }
+}
+
+impl fmt::Display for RoundTripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            RoundTripErrorKind::Decode(err) => {
+                write!(f, "failed to decode encoded message: {}", err)
+            }
+            #[cfg(feature = "serde")]
+            RoundTripErrorKind::Serialize(err) => write!(f, "failed to serialize message: {}", err),
+            #[cfg(feature = "serde")]
+            RoundTripErrorKind::Deserialize(err) => {
+                write!(f, "failed to deserialize serialized message: {}", err)
+            }
+            #[cfg(feature = "text-format")]
+            RoundTripErrorKind::TextFormatParse(err) => {
+                write!(f, "failed to parse formatted message: {}", err)
+            }
+            RoundTripErrorKind::MessageMismatch { format, diff } => write!(
+                f,
+                "message changed after {} round-trip (left: original, right: round-tripped):\n{}",
+                format, diff
+            ),
+            #[cfg(feature = "serde")]
+            RoundTripErrorKind::JsonMismatch { first, second } => write!(
+                f,
+                "JSON output changed after round-trip:\n  first: {:?}\n  second: {:?}",
+                first, second
+            ),
+            #[cfg(feature = "text-format")]
+            RoundTripErrorKind::TextFormatMismatch { first, second } => write!(
+                f,
+                "text format output changed after round-trip:\n  first: {}\n  second: {}",
+                first, second
+            ),
+        }
+    }
+}
+
+impl Error for RoundTripError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            RoundTripErrorKind::Decode(err) => Some(err),
+            #[cfg(feature = "text-format")]
+            RoundTripErrorKind::TextFormatParse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! A codec for sending and receiving dynamic messages with the [`tonic`](https://crates.io/crates/tonic) crate.
+
+use ::tonic::{
+    codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+    Status,
+};
+use prost::Message;
+
+use crate::{DynamicMessage, MessageDescriptor, MethodDescriptor};
+
+/// A [`Codec`] which encodes and decodes [`DynamicMessage`]s for a method, allowing any gRPC method to be
+/// called given only its [`MethodDescriptor`].
+///
+/// A client codec, created with [`DynamicCodec::new`], decodes messages of the method's output type. A
+/// server codec, created with [`DynamicCodec::server`], decodes messages of the method's input type.
+/// Messages are encoded as-is, so it is the caller's responsibility to send messages of the correct type.
+///
+/// The codec may be used for unary and streaming methods alike. The path of the method, which is
+/// required to make a request with [`tonic::client::Grpc`](::tonic::client::Grpc), has the form
+/// `/{service full name}/{method name}`.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, DynamicCodec};
+/// # let pool = DescriptorPool::decode(include_bytes!("file_descriptor_set.bin").as_ref()).unwrap();
+/// # let service = match pool.services().next() { Some(service) => service, None => return };
+/// let method = service.methods().next().unwrap();
+/// let path = format!("/{}/{}", service.full_name(), method.name());
+/// let codec = DynamicCodec::new(method);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "tonic")))]
+pub struct DynamicCodec {
+    method: MethodDescriptor,
+    decode_desc: MessageDescriptor,
+}
+
+/// The [`Encoder`] used by [`DynamicCodec`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(docsrs, doc(cfg(feature = "tonic")))]
+pub struct DynamicEncoder {
+    _private: (),
+}
+
+/// The [`Decoder`] used by [`DynamicCodec`].
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "tonic")))]
+pub struct DynamicDecoder {
+    desc: MessageDescriptor,
+}
+
+impl DynamicCodec {
+    /// Creates a new [`DynamicCodec`] for calling `method` from a client. Responses are decoded as
+    /// messages of the method's output type.
+    pub fn new(method: MethodDescriptor) -> Self {
+        DynamicCodec {
+            decode_desc: method.output(),
+            method,
+        }
+    }
+
+    /// Creates a new [`DynamicCodec`] for implementing `method` in a server. Requests are decoded as
+    /// messages of the method's input type.
+    pub fn server(method: MethodDescriptor) -> Self {
+        DynamicCodec {
+            decode_desc: method.input(),
+            method,
+        }
+    }
+
+    /// Gets a reference to the method this codec was created for.
+    pub fn method(&self) -> &MethodDescriptor {
+        &self.method
+    }
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder::default()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder {
+            desc: self.decode_desc.clone(),
+        }
+    }
+}
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .expect("message only errors if not enough space");
+        Ok(())
+    }
+}
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.desc.clone(), src)
+            .map(Some)
+            .map_err(|err| Status::internal(err.to_string()))
+    }
+}
@@ -0,0 +1,434 @@
+//! Inspecting and rewriting encoded protobuf messages at the wire level, without a descriptor.
+//!
+//! [`RawFields`] iterates over the fields of an encoded message, yielding each as a [`RawField`] with its
+//! field number, wire type and raw value. This is the same information used by [`DynamicMessage::decode`]
+//! to find the field each value belongs to, but since it does not interpret the values, it can be used on
+//! messages of any type, for example to remove or rewrite fields without decoding the whole message.
+//!
+//! Raw fields can be written back with [`RawField::encode`], so a message can be filtered by encoding the
+//! fields to keep. Field values are slices of the encoded message, so they are not copied.
+//!
+//! For debugging payloads of an unknown type, [`inspect`] goes further and guesses the type of each
+//! field, decoding nested messages and strings, and can display the result as text.
+//!
+//! # Examples
+//!
+//! ```
+//! use prost_reflect::wire::RawFields;
+//!
+//! // Remove field 2 from an encoded message, leaving the other fields unchanged.
+//! let encoded = b"\x08\x96\x01\x12\x03foo\x18\x01";
+//! let mut stripped = Vec::new();
+//! for field in RawFields::new(encoded.as_ref()) {
+//!     let field = field.unwrap();
+//!     if field.number() != 2 {
+//!         field.encode(&mut stripped);
+//!     }
+//! }
+//! assert_eq!(stripped, b"\x08\x96\x01\x18\x01");
+//! ```
+//!
+//! [`DynamicMessage::decode`]: crate::DynamicMessage::decode
+
+use std::fmt;
+
+use prost::{
+    bytes::{Buf, BufMut, Bytes},
+    encoding::{self, DecodeContext, WireType},
+    DecodeError,
+};
+
+/// The maximum depth of nested messages guessed by [`inspect`], matching the limit used by `prost`.
+const RECURSION_LIMIT: u32 = 100;
+
+/// A field of an encoded protobuf message, consisting of its field number, wire type and raw value.
+///
+/// The raw value does not include the field key. It holds the encoded varint for [`WireType::Varint`],
+/// the little-endian bytes for fixed-width wire types, the contents without the length prefix for
+/// [`WireType::LengthDelimited`], and the encoded fields of the group without the end tag for
+/// [`WireType::StartGroup`]. This matches the representation used by
+/// [`UnknownField`](crate::UnknownField).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawField {
+    number: u32,
+    wire_type: WireType,
+    value: Bytes,
+}
+
+/// An iterator over the fields of an encoded protobuf message.
+///
+/// Each item is a [`RawField`], or an error if the message is not validly encoded. Iteration stops after
+/// the first error.
+#[derive(Debug, Clone)]
+pub struct RawFields {
+    buf: Bytes,
+    offset: usize,
+    done: bool,
+}
+
+impl RawField {
+    /// Creates a new raw field from its number, wire type and raw value.
+    ///
+    /// Returns an error if `number` is not a valid field number, if `value` is not a valid encoding for
+    /// `wire_type`, or if `wire_type` is [`WireType::EndGroup`].
+    pub fn new(number: u32, wire_type: WireType, value: Bytes) -> Result<Self, DecodeError> {
+        if !(encoding::MIN_TAG..=encoding::MAX_TAG).contains(&number) {
+            return Err(DecodeError::new(format!(
+                "invalid field number: {}",
+                number
+            )));
+        }
+
+        match wire_type {
+            WireType::Varint => {
+                let mut buf = value.as_ref();
+                encoding::decode_varint(&mut buf)?;
+                if buf.has_remaining() {
+                    return Err(DecodeError::new("unexpected trailing bytes after varint"));
+                }
+            }
+            WireType::SixtyFourBit if value.len() != 8 => {
+                return Err(DecodeError::new("expected 8 bytes for a 64-bit value"))
+            }
+            WireType::ThirtyTwoBit if value.len() != 4 => {
+                return Err(DecodeError::new("expected 4 bytes for a 32-bit value"))
+            }
+            WireType::StartGroup => {
+                for field in RawFields::new(value.clone()) {
+                    field?;
+                }
+            }
+            WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+            WireType::SixtyFourBit | WireType::ThirtyTwoBit | WireType::LengthDelimited => (),
+        }
+
+        Ok(RawField {
+            number,
+            wire_type,
+            value,
+        })
+    }
+
+    /// Gets the field number of this field.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the wire type this field was encoded with.
+    pub fn wire_type(&self) -> WireType {
+        self.wire_type
+    }
+
+    /// Gets the raw value of this field, not including the field key.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Encodes this field, including its key, to `buf`.
+    pub fn encode<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        encoding::encode_key(self.number, self.wire_type, buf);
+        match self.wire_type {
+            WireType::LengthDelimited => {
+                encoding::encode_varint(self.value.len() as u64, buf);
+                buf.put_slice(&self.value);
+            }
+            WireType::StartGroup => {
+                buf.put_slice(&self.value);
+                encoding::encode_key(self.number, WireType::EndGroup, buf);
+            }
+            _ => buf.put_slice(&self.value),
+        }
+    }
+
+    /// Returns the number of bytes written by [`encode`][Self::encode].
+    pub fn encoded_len(&self) -> usize {
+        let len = encoding::key_len(self.number) + self.value.len();
+        match self.wire_type {
+            WireType::LengthDelimited => {
+                len + encoding::encoded_len_varint(self.value.len() as u64)
+            }
+            WireType::StartGroup => len + encoding::key_len(self.number),
+            _ => len,
+        }
+    }
+}
+
+impl RawFields {
+    /// Creates an iterator over the fields of the encoded message `buf`.
+    pub fn new(buf: impl Into<Bytes>) -> Self {
+        RawFields {
+            buf: buf.into(),
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Gets the number of bytes of the message read so far.
+    ///
+    /// If the last item returned was an error, this is the offset of the start of the invalid field.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn read_field(&mut self) -> Result<RawField, DecodeError> {
+        let mut buf = self.buf.clone();
+        let (number, wire_type) = encoding::decode_key(&mut buf)?;
+        let value = match wire_type {
+            WireType::LengthDelimited => {
+                let len = encoding::decode_varint(&mut buf)?;
+                if len > buf.remaining() as u64 {
+                    return Err(DecodeError::new("buffer underflow"));
+                }
+                buf.split_to(len as usize)
+            }
+            WireType::StartGroup => {
+                let start = buf.clone();
+                loop {
+                    let value_len = start.len() - buf.len();
+                    let (inner_number, inner_wire_type) = encoding::decode_key(&mut buf)?;
+                    if inner_wire_type == WireType::EndGroup {
+                        if inner_number != number {
+                            return Err(DecodeError::new("unexpected end group tag"));
+                        }
+                        break start.slice(..value_len);
+                    }
+                    encoding::skip_field(
+                        inner_wire_type,
+                        inner_number,
+                        &mut buf,
+                        DecodeContext::default(),
+                    )?;
+                }
+            }
+            _ => {
+                let start = buf.clone();
+                encoding::skip_field(wire_type, number, &mut buf, DecodeContext::default())?;
+                start.slice(..start.len() - buf.len())
+            }
+        };
+
+        self.offset += self.buf.len() - buf.len();
+        self.buf = buf;
+        Ok(RawField {
+            number,
+            wire_type,
+            value,
+        })
+    }
+}
+
+impl Iterator for RawFields {
+    type Item = Result<RawField, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.buf.is_empty() {
+            return None;
+        }
+
+        let result = self.read_field();
+        if result.is_err() {
+            self.done = true;
+        }
+        Some(result)
+    }
+}
+
+/// Decodes an encoded protobuf message without a descriptor, guessing the type of each field.
+///
+/// Varints and fixed-width values are decoded as unsigned integers, since their signedness and whether
+/// they are floating point numbers is not encoded. Length-delimited values are decoded as strings if they
+/// are printable UTF-8 text, as nested messages if they are a valid encoding of a message, and as bytes
+/// otherwise. These guesses may be wrong, for example a short nested message may happen to also be
+/// printable text. The result can be displayed as text, which is useful for debugging unknown payloads.
+///
+/// Decoding never fails. If part of `buf` is not a valid encoding of a message, the fields before it are
+/// returned, and the remaining bytes are available from [`InspectedMessage::trailing_bytes`].
+///
+/// # Examples
+///
+/// ```
+/// use prost_reflect::wire::{self, InspectedValue};
+///
+/// let message = wire::inspect(b"\x08\x96\x01\x12\x03foo\x1a\x02\x08\x05".as_ref());
+/// assert_eq!(message.fields()[0].value(), &InspectedValue::Varint(150));
+/// assert_eq!(message.fields()[1].value(), &InspectedValue::String("foo".to_owned()));
+/// assert_eq!(message.to_string(), "1: 150\n2: \"foo\"\n3: {\n  1: 5\n}\n");
+/// ```
+pub fn inspect(buf: impl Into<Bytes>) -> InspectedMessage {
+    inspect_message(buf.into(), 0)
+}
+
+/// A message decoded without a descriptor by [`inspect`].
+///
+/// The [`Display`](fmt::Display) implementation writes each field on its own line, with nested messages
+/// indented.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedMessage {
+    fields: Vec<InspectedField>,
+    trailing_bytes: Bytes,
+}
+
+/// A field of a message decoded without a descriptor by [`inspect`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedField {
+    number: u32,
+    value: InspectedValue,
+}
+
+/// The guessed value of a field decoded without a descriptor by [`inspect`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum InspectedValue {
+    /// A value encoded with [`WireType::Varint`].
+    Varint(u64),
+    /// A value encoded with [`WireType::ThirtyTwoBit`].
+    Fixed32(u32),
+    /// A value encoded with [`WireType::SixtyFourBit`].
+    Fixed64(u64),
+    /// A length-delimited value which is printable UTF-8 text.
+    String(String),
+    /// A length-delimited value which is a valid encoding of a message.
+    Message(InspectedMessage),
+    /// A length-delimited value which is neither text nor a message.
+    Bytes(Bytes),
+    /// A group, encoded with [`WireType::StartGroup`].
+    Group(InspectedMessage),
+}
+
+impl InspectedMessage {
+    /// Gets the fields of the message, in the order they were encoded.
+    pub fn fields(&self) -> &[InspectedField] {
+        &self.fields
+    }
+
+    /// Gets the bytes at the end of the message which could not be decoded, or an empty buffer if the
+    /// whole message was decoded.
+    pub fn trailing_bytes(&self) -> &Bytes {
+        &self.trailing_bytes
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        for field in &self.fields {
+            write!(f, "{:indent$}{}: ", "", field.number, indent = indent)?;
+            match &field.value {
+                InspectedValue::Varint(value) => writeln!(f, "{}", value)?,
+                InspectedValue::Fixed32(value) => writeln!(f, "{}i32", value)?,
+                InspectedValue::Fixed64(value) => writeln!(f, "{}i64", value)?,
+                InspectedValue::String(value) => writeln!(f, "{:?}", value)?,
+                InspectedValue::Bytes(value) => writeln!(f, "{}", EscapedBytes(value))?,
+                InspectedValue::Message(message) => {
+                    writeln!(f, "{{")?;
+                    message.fmt_indented(f, indent + 2)?;
+                    writeln!(f, "{:indent$}}}", "", indent = indent)?;
+                }
+                InspectedValue::Group(message) => {
+                    writeln!(f, "!{{")?;
+                    message.fmt_indented(f, indent + 2)?;
+                    writeln!(f, "{:indent$}}}", "", indent = indent)?;
+                }
+            }
+        }
+        if !self.trailing_bytes.is_empty() {
+            writeln!(
+                f,
+                "{:indent$}# invalid: {}",
+                "",
+                EscapedBytes(&self.trailing_bytes),
+                indent = indent
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for InspectedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+impl InspectedField {
+    /// Gets the field number of this field.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the guessed value of this field.
+    pub fn value(&self) -> &InspectedValue {
+        &self.value
+    }
+}
+
+fn inspect_message(buf: Bytes, depth: u32) -> InspectedMessage {
+    let mut fields = Vec::new();
+    let mut raw_fields = RawFields::new(buf.clone());
+    for field in &mut raw_fields {
+        let field = match field {
+            Ok(field) => field,
+            Err(_) => {
+                return InspectedMessage {
+                    fields,
+                    trailing_bytes: buf.slice(raw_fields.offset()..),
+                }
+            }
+        };
+
+        let value = match field.wire_type {
+            WireType::Varint => {
+                InspectedValue::Varint(encoding::decode_varint(&mut field.value.as_ref()).unwrap())
+            }
+            WireType::ThirtyTwoBit => InspectedValue::Fixed32(field.value.as_ref().get_u32_le()),
+            WireType::SixtyFourBit => InspectedValue::Fixed64(field.value.as_ref().get_u64_le()),
+            WireType::LengthDelimited => inspect_length_delimited(field.value, depth),
+            WireType::StartGroup => InspectedValue::Group(inspect_message(field.value, depth + 1)),
+            WireType::EndGroup => unreachable!("end group tags are not returned as fields"),
+        };
+        fields.push(InspectedField {
+            number: field.number,
+            value,
+        });
+    }
+
+    InspectedMessage {
+        fields,
+        trailing_bytes: Bytes::new(),
+    }
+}
+
+fn inspect_length_delimited(value: Bytes, depth: u32) -> InspectedValue {
+    if let Ok(string) = std::str::from_utf8(&value) {
+        if is_printable(string) {
+            return InspectedValue::String(string.to_owned());
+        }
+    }
+
+    // Nested messages are limited to the same depth as when decoding messages.
+    if depth < RECURSION_LIMIT {
+        let message = inspect_message(value.clone(), depth + 1);
+        if message.trailing_bytes.is_empty() {
+            return InspectedValue::Message(message);
+        }
+    }
+
+    InspectedValue::Bytes(value)
+}
+
+fn is_printable(string: &str) -> bool {
+    string
+        .chars()
+        .all(|ch| !ch.is_control() || matches!(ch, '\n' | '\r' | '\t'))
+}
+
+struct EscapedBytes<'a>(&'a [u8]);
+
+impl fmt::Display for EscapedBytes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("b\"")?;
+        for &byte in self.0 {
+            write!(f, "{}", std::ascii::escape_default(byte))?;
+        }
+        f.write_str("\"")
+    }
+}